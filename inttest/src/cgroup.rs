@@ -0,0 +1,132 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Control groups testing.
+
+use crate::{
+	log,
+	mount::{mount, umount},
+	sched::wait,
+	test_assert, test_assert_eq,
+	util::TestResult,
+};
+use std::{
+	fs,
+	fs::OpenOptions,
+	io,
+	io::Write,
+	path::Path,
+	time::{Duration, Instant},
+};
+
+/// The path at which the cgroup2 filesystem is mounted.
+const MOUNT_PATH: &str = "/tmp/cgroup";
+/// The path of the group used for testing.
+const GROUP_PATH: &str = "/tmp/cgroup/test";
+
+/// Writes `content` to the control file `name` of the group at `group`.
+///
+/// The file is not truncated, since control files do not support it.
+fn write_control(group: &str, name: &str, content: &str) -> io::Result<()> {
+	let path = Path::new(group).join(name);
+	OpenOptions::new()
+		.write(true)
+		.open(path)?
+		.write_all(content.as_bytes())
+}
+
+/// Reads the control file `name` of the group at `group`.
+fn read_control(group: &str, name: &str) -> io::Result<String> {
+	fs::read_to_string(Path::new(group).join(name))
+}
+
+pub fn hierarchy() -> TestResult {
+	mount("cgroup2", MOUNT_PATH, "cgroup2")?;
+	log!("Create a group");
+	fs::create_dir(GROUP_PATH)?;
+	test_assert_eq!(read_control(GROUP_PATH, "cpu.max")?, "max 100000\n");
+	test_assert_eq!(read_control(GROUP_PATH, "memory.max")?, "max\n");
+	test_assert_eq!(read_control(GROUP_PATH, "cgroup.procs")?, "");
+	log!("Set limits");
+	write_control(GROUP_PATH, "cpu.max", "20000 50000")?;
+	test_assert_eq!(read_control(GROUP_PATH, "cpu.max")?, "20000 50000\n");
+	write_control(GROUP_PATH, "memory.max", "1048576")?;
+	test_assert_eq!(read_control(GROUP_PATH, "memory.max")?, "1048576\n");
+	write_control(GROUP_PATH, "memory.max", "max")?;
+	log!("Set an invalid limit");
+	let err = write_control(GROUP_PATH, "cpu.max", "10 100000").unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Move the current process into the group");
+	write_control(GROUP_PATH, "cgroup.procs", "0")?;
+	let pid = format!("{}\n", std::process::id());
+	test_assert_eq!(read_control(GROUP_PATH, "cgroup.procs")?, pid);
+	log!("Remove a non-empty group");
+	let err = fs::remove_dir(GROUP_PATH).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EBUSY));
+	log!("Remove the group");
+	write_control(MOUNT_PATH, "cgroup.procs", "0")?;
+	fs::remove_dir(GROUP_PATH)?;
+	test_assert!(!Path::new(GROUP_PATH).exists());
+	umount(MOUNT_PATH)?;
+	Ok(())
+}
+
+pub fn cpu_max() -> TestResult {
+	mount("cgroup2", MOUNT_PATH, "cgroup2")?;
+	fs::create_dir(GROUP_PATH)?;
+	log!("Limit the group to half a CPU");
+	write_control(GROUP_PATH, "cpu.max", "50000 100000")?;
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// Busy loop in the group and exit with the share of CPU time used, in percent
+		let share = (|| -> io::Result<u8> {
+			write_control(GROUP_PATH, "cgroup.procs", "0")?;
+			let cpu_time = || {
+				let mut ts: libc::timespec = unsafe { std::mem::zeroed() };
+				unsafe {
+					libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut ts);
+				}
+				Duration::new(ts.tv_sec as _, ts.tv_nsec as _)
+			};
+			let start = Instant::now();
+			let cpu_start = cpu_time();
+			while start.elapsed() < Duration::from_secs(1) {}
+			let cpu = cpu_time() - cpu_start;
+			Ok((cpu.as_millis() * 100 / start.elapsed().as_millis()) as _)
+		})();
+		unsafe {
+			libc::_exit(share.unwrap_or(u8::MAX) as _);
+		}
+	}
+	log!("Measure the CPU time used by a busy process in the group");
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	let share = libc::WEXITSTATUS(status);
+	log!("CPU share: {share}%");
+	test_assert!((35..=65).contains(&share));
+	let stat = read_control(GROUP_PATH, "cpu.stat")?;
+	test_assert!(stat
+		.lines()
+		.any(|l| l.starts_with("nr_throttled ") && l != "nr_throttled 0"));
+	fs::remove_dir(GROUP_PATH)?;
+	umount(MOUNT_PATH)?;
+	Ok(())
+}
@@ -0,0 +1,212 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! devtmpfs filesystem testing.
+
+use crate::{
+	log,
+	mount::{mount, umount},
+	test_assert, test_assert_eq, util,
+	util::TestResult,
+};
+use std::{
+	fs,
+	fs::OpenOptions,
+	io::{Read, Seek, SeekFrom, Write},
+	os::{
+		fd::AsRawFd,
+		unix::fs::{FileTypeExt, OpenOptionsExt},
+	},
+	path::Path,
+	ptr, slice,
+};
+
+pub fn nodes() -> TestResult {
+	mount("devtmpfs", "/tmp/devtmpfs", "devtmpfs")?;
+	log!("Check the status of a device file");
+	let stat = util::stat("/tmp/devtmpfs/null")?;
+	test_assert_eq!(stat.st_mode & libc::S_IFMT, libc::S_IFCHR);
+	test_assert_eq!(stat.st_rdev, libc::makedev(1, 3));
+	log!("List device files");
+	let mut found = false;
+	for ent in fs::read_dir("/tmp/devtmpfs")? {
+		let ent = ent?;
+		let file_type = ent.file_type()?;
+		test_assert!(file_type.is_char_device() || file_type.is_block_device());
+		found |= ent.file_name() == "zero";
+	}
+	test_assert!(found);
+	log!("Use a device file");
+	let content = fs::read("/tmp/devtmpfs/null")?;
+	test_assert!(content.is_empty());
+	umount("/tmp/devtmpfs")?;
+	Ok(())
+}
+
+pub fn mem() -> TestResult {
+	/// Physical address of the VGA text mode framebuffer.
+	const VGA_BUFFER: u64 = 0xb8000;
+	/// Offset of the last line of the screen in the framebuffer.
+	const LAST_LINE: usize = 80 * 24 * 2;
+
+	mount("devtmpfs", "/tmp/devtmpfs", "devtmpfs")?;
+	let mut file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.open("/tmp/devtmpfs/mem")?;
+	log!("Write to physical memory");
+	let pattern: Vec<u8> = (0..16)
+		.map(|i| if i % 2 == 0 { b'A' + i } else { 0x07 })
+		.collect();
+	let mut orig = [0; 16];
+	file.seek(SeekFrom::Start(VGA_BUFFER + LAST_LINE as u64))?;
+	file.read_exact(&mut orig)?;
+	file.seek(SeekFrom::Start(VGA_BUFFER + LAST_LINE as u64))?;
+	file.write_all(&pattern)?;
+	log!("Read back from physical memory");
+	let mut buf = [0; 16];
+	file.seek(SeekFrom::Start(VGA_BUFFER + LAST_LINE as u64))?;
+	file.read_exact(&mut buf)?;
+	test_assert_eq!(buf.as_slice(), pattern.as_slice());
+	log!("Map physical memory");
+	let len = 80 * 25 * 2;
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ,
+			libc::MAP_SHARED,
+			file.as_raw_fd(),
+			VGA_BUFFER as _,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(std::io::Error::last_os_error().into());
+	}
+	let mem = unsafe { slice::from_raw_parts(ptr as *const u8, len) };
+	test_assert_eq!(&mem[LAST_LINE..(LAST_LINE + 16)], pattern.as_slice());
+	unsafe {
+		libc::munmap(ptr, len);
+	}
+	// Restore the screen
+	file.seek(SeekFrom::Start(VGA_BUFFER + LAST_LINE as u64))?;
+	file.write_all(&orig)?;
+	drop(file);
+	umount("/tmp/devtmpfs")?;
+	Ok(())
+}
+
+pub fn console() -> TestResult {
+	const GIO_CMAP: libc::c_ulong = 0x4b70;
+	const PIO_CMAP: libc::c_ulong = 0x4b71;
+	const KDGCURSOR: libc::c_ulong = 0x4bf0;
+	const KDSCURSOR: libc::c_ulong = 0x4bf1;
+
+	let ioctl = |fd: libc::c_int, req: libc::c_ulong, arg: *mut u8| {
+		let res = unsafe { libc::ioctl(fd, req as _, arg) };
+		if res < 0 {
+			Err(std::io::Error::last_os_error())
+		} else {
+			Ok(())
+		}
+	};
+	mount("devtmpfs", "/tmp/devtmpfs", "devtmpfs")?;
+	let file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.open("/tmp/devtmpfs/tty")?;
+	let fd = file.as_raw_fd();
+	log!("Set a palette entry");
+	let mut orig = [0u8; 48];
+	ioctl(fd, GIO_CMAP, orig.as_mut_ptr())?;
+	let mut cmap = orig;
+	// The DAC has 6 bits per component, so use multiples of 4
+	cmap[3..6].copy_from_slice(&[0x40, 0x80, 0xc0]);
+	ioctl(fd, PIO_CMAP, cmap.as_mut_ptr())?;
+	log!("Read the palette back");
+	let mut buf = [0u8; 48];
+	ioctl(fd, GIO_CMAP, buf.as_mut_ptr())?;
+	test_assert_eq!(buf, cmap);
+	ioctl(fd, PIO_CMAP, orig.as_mut_ptr())?;
+	log!("Set the cursor shape");
+	let mut orig = [0u8; 3];
+	ioctl(fd, KDGCURSOR, orig.as_mut_ptr())?;
+	let mut cursor = [14u8, 15, 1];
+	ioctl(fd, KDSCURSOR, cursor.as_mut_ptr())?;
+	let mut buf = [0u8; 3];
+	ioctl(fd, KDGCURSOR, buf.as_mut_ptr())?;
+	test_assert_eq!(buf, cursor);
+	ioctl(fd, KDSCURSOR, orig.as_mut_ptr())?;
+	drop(file);
+	umount("/tmp/devtmpfs")?;
+	Ok(())
+}
+
+pub fn pty() -> TestResult {
+	const TIOCGPTN: libc::c_ulong = 0x80045430;
+	const TIOCSPTLCK: libc::c_ulong = 0x40045431;
+
+	mount("devtmpfs", "/tmp/devtmpfs", "devtmpfs")?;
+	log!("Allocate a pseudo-terminal");
+	let mut master = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.open("/tmp/devtmpfs/ptmx")?;
+	let mut n: libc::c_uint = 0;
+	let res = unsafe { libc::ioctl(master.as_raw_fd(), TIOCGPTN as _, &mut n) };
+	test_assert_eq!(res, 0);
+	let slave_path = format!("/dev/pts/{n}");
+	let stat = util::stat(&slave_path)?;
+	test_assert_eq!(stat.st_rdev, libc::makedev(136, n));
+	log!("Open the locked slave");
+	let open_slave = || {
+		OpenOptions::new()
+			.read(true)
+			.write(true)
+			.custom_flags(libc::O_NOCTTY)
+			.open(&slave_path)
+	};
+	let res = open_slave();
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EIO)));
+	log!("Unlock the slave");
+	let unlock: libc::c_int = 0;
+	let res = unsafe { libc::ioctl(master.as_raw_fd(), TIOCSPTLCK as _, &unlock) };
+	test_assert_eq!(res, 0);
+	let mut slave = open_slave()?;
+	log!("Write on the master");
+	master.write_all(b"hi\n")?;
+	let mut buf = [0; 16];
+	let len = slave.read(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"hi\n");
+	log!("Write on the slave");
+	slave.write_all(b"ok\n")?;
+	// The echo comes first, then the output of the slave, with NL translated to CR-NL
+	let mut buf = [0; 8];
+	master.read_exact(&mut buf)?;
+	test_assert_eq!(&buf, b"hi\r\nok\r\n");
+	log!("Close the master");
+	drop(master);
+	test_assert!(!Path::new(&slave_path).exists());
+	let len = slave.read(&mut buf)?;
+	test_assert_eq!(len, 0);
+	let res = slave.write(b"x");
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EIO)));
+	drop(slave);
+	umount("/tmp/devtmpfs")?;
+	Ok(())
+}
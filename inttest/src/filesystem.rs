@@ -24,12 +24,21 @@ use crate::{
 };
 use memmap2::MmapOptions;
 use std::{
+	ffi::CString,
 	fs,
-	fs::OpenOptions,
+	fs::{File, OpenOptions},
 	io,
 	io::{Read, Seek, SeekFrom, Write},
-	os::{fd::AsRawFd, unix, unix::fs::MetadataExt},
+	os::{
+		fd::{AsRawFd, FromRawFd},
+		unix,
+		unix::{
+			ffi::OsStrExt,
+			fs::{FileExt, MetadataExt},
+		},
+	},
 	path::Path,
+	ptr,
 };
 
 pub fn basic(root: &Path) -> TestResult {
@@ -298,6 +307,257 @@ pub fn symlinks(root: &Path) -> TestResult {
 	Ok(())
 }
 
+pub fn openat2(root: &Path) -> TestResult {
+	const RESOLVE_NO_SYMLINKS: u64 = 0x04;
+	const RESOLVE_BENEATH: u64 = 0x08;
+	const RESOLVE_IN_ROOT: u64 = 0x10;
+
+	let dir_path = root.join("openat2");
+	fs::create_dir(&dir_path)?;
+	fs::write(dir_path.join("file"), b"abc")?;
+	fs::write(root.join("escape"), b"def")?;
+	unix::fs::symlink("file", dir_path.join("link"))?;
+	let dir = File::open(&dir_path)?;
+	let open = |path: &str, resolve: u64| -> io::Result<File> {
+		let path = CString::new(path)?;
+		let how = util::OpenHow {
+			flags: libc::O_RDONLY as _,
+			mode: 0,
+			resolve,
+		};
+		let fd = util::openat2(dir.as_raw_fd(), &path, &how)?;
+		Ok(unsafe { File::from_raw_fd(fd) })
+	};
+
+	log!("Open without restriction");
+	open("../escape", 0)?;
+	log!("Open beneath");
+	open("file", RESOLVE_BENEATH)?;
+	let res = open("../escape", RESOLVE_BENEATH);
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EXDEV)));
+	let res = open("/dev/null", RESOLVE_BENEATH);
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EXDEV)));
+	log!("Open in root");
+	let mut content = String::new();
+	open("../../file", RESOLVE_IN_ROOT)?.read_to_string(&mut content)?;
+	test_assert_eq!(content, "abc");
+	log!("Open without symbolic links");
+	open("link", 0)?;
+	let res = open("link", RESOLVE_NO_SYMLINKS);
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::ELOOP)));
+	log!("Invalid flags");
+	let res = open("file", 1 << 40);
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EINVAL)));
+	let res = open("file", RESOLVE_BENEATH | RESOLVE_IN_ROOT);
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EINVAL)));
+	let path = CString::new("file")?;
+	let res = unsafe {
+		libc::syscall(
+			libc::SYS_faccessat2,
+			dir.as_raw_fd(),
+			path.as_ptr(),
+			libc::R_OK,
+			0x8000,
+		)
+	};
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+
+	log!("Cleanup");
+	fs::remove_file(root.join("escape"))?;
+	fs::remove_dir_all(&dir_path)?;
+
+	Ok(())
+}
+
+pub fn fadvise(root: &Path) -> TestResult {
+	let path = root.join("fadvise");
+	let data: Vec<u8> = (0..(4096 * 8)).map(|i| (i % 251) as u8).collect();
+	fs::write(&path, &data)?;
+	let file = File::open(&path)?;
+	let fd = file.as_raw_fd();
+	let advise = |off: i64, len: i64, advice: libc::c_int| unsafe {
+		libc::posix_fadvise(fd, off, len, advice)
+	};
+
+	log!("Give hints");
+	for advice in [
+		libc::POSIX_FADV_NORMAL,
+		libc::POSIX_FADV_SEQUENTIAL,
+		libc::POSIX_FADV_RANDOM,
+		libc::POSIX_FADV_NOREUSE,
+	] {
+		test_assert_eq!(advise(0, 0, advice), 0);
+	}
+	log!("Warm the cache");
+	test_assert_eq!(advise(4096, 4096 * 4, libc::POSIX_FADV_WILLNEED), 0);
+	test_assert!(fs::read(&path)? == data);
+	log!("Drop the cache");
+	test_assert_eq!(advise(0, 0, libc::POSIX_FADV_DONTNEED), 0);
+	test_assert!(fs::read(&path)? == data);
+	log!("Drop dirty pages");
+	OpenOptions::new()
+		.write(true)
+		.open(&path)?
+		.write_all(b"dirty")?;
+	test_assert_eq!(advise(0, 4096, libc::POSIX_FADV_DONTNEED), 0);
+	test_assert_eq!(&fs::read(&path)?[..5], b"dirty");
+
+	log!("Invalid arguments");
+	test_assert_eq!(advise(0, 0, 42), libc::EINVAL);
+	test_assert_eq!(advise(0, -1, libc::POSIX_FADV_NORMAL), libc::EINVAL);
+	let res = unsafe { libc::posix_fadvise(-1, 0, 0, libc::POSIX_FADV_NORMAL) };
+	test_assert_eq!(res, libc::EBADF);
+	let mut pipe = [0; 2];
+	test_assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+	let res = unsafe { libc::posix_fadvise(pipe[0], 0, 0, libc::POSIX_FADV_NORMAL) };
+	test_assert_eq!(res, libc::ESPIPE);
+	unsafe {
+		libc::close(pipe[0]);
+		libc::close(pipe[1]);
+	}
+
+	log!("Cleanup");
+	fs::remove_file(path)?;
+
+	Ok(())
+}
+
+pub fn vectored(root: &Path) -> TestResult {
+	let path = root.join("vectored");
+	let file = OpenOptions::new()
+		.read(true)
+		.write(true)
+		.create(true)
+		.truncate(true)
+		.open(&path)?;
+	let fd = file.as_raw_fd();
+	let iovec = |buf: &[u8]| libc::iovec {
+		iov_base: buf.as_ptr() as *mut _,
+		iov_len: buf.len(),
+	};
+
+	log!("Gather write");
+	let iov = [iovec(b"hello"), iovec(b""), iovec(b" world")];
+	let len = unsafe { libc::writev(fd, iov.as_ptr(), iov.len() as _) };
+	test_assert_eq!(len, 11);
+	test_assert_eq!(fs::read(&path)?, b"hello world");
+	log!("Scatter read");
+	let mut a = [0u8; 3];
+	let mut b = [0u8; 16];
+	let iov = [iovec(&a), iovec(&b)];
+	let len = unsafe { libc::preadv(fd, iov.as_ptr(), iov.len() as _, 0) };
+	test_assert_eq!(len, 11);
+	test_assert_eq!(&a, b"hel");
+	test_assert_eq!(&b[..8], b"lo world");
+	log!("Short read stops at the end of file");
+	a.fill(0);
+	b.fill(0);
+	let iov = [iovec(&b), iovec(&a)];
+	let len = unsafe { libc::preadv(fd, iov.as_ptr(), iov.len() as _, 6) };
+	test_assert_eq!(len, 5);
+	test_assert_eq!(&b[..5], b"world");
+	test_assert_eq!(a, [0; 3]);
+
+	log!("Too many chunks");
+	let iov_max = unsafe { libc::sysconf(libc::_SC_IOV_MAX) } as usize;
+	let iov = vec![iovec(b"x"); iov_max + 1];
+	let len = unsafe { libc::writev(fd, iov.as_ptr(), iov.len() as _) };
+	test_assert_eq!(len, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	log!("Invalid iovec");
+	let len = unsafe { libc::writev(fd, ptr::null(), 1) };
+	test_assert_eq!(len, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EFAULT)
+	);
+	let len = unsafe { libc::readv(fd, 0x10 as *const _, 1) };
+	test_assert_eq!(len, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EFAULT)
+	);
+	test_assert_eq!(fs::read(&path)?, b"hello world");
+	log!("Invalid buffer after a successful chunk");
+	let iov = [
+		iovec(b"!"),
+		libc::iovec {
+			iov_base: ptr::null_mut(),
+			iov_len: 4,
+		},
+	];
+	let len = unsafe { libc::writev(fd, iov.as_ptr(), iov.len() as _) };
+	test_assert_eq!(len, 1);
+	test_assert_eq!(fs::read(&path)?, b"hello world!");
+
+	log!("Cleanup");
+	drop(file);
+	fs::remove_file(path)?;
+
+	Ok(())
+}
+
+pub fn pread_pwrite(root: &Path) -> TestResult {
+	let path = root.join("pread_pwrite");
+	fs::write(&path, b"0123456789")?;
+	let mut file = OpenOptions::new().read(true).write(true).open(&path)?;
+	let mut buf = [0u8; 3];
+
+	log!("Interleave positional and sequential reads");
+	file.read_exact(&mut buf[..2])?;
+	test_assert_eq!(&buf[..2], b"01");
+	test_assert_eq!(file.read_at(&mut buf, 5)?, 3);
+	test_assert_eq!(&buf, b"567");
+	test_assert_eq!(file.read_at(&mut buf[..2], 1)?, 2);
+	test_assert_eq!(&buf[..2], b"12");
+	file.read_exact(&mut buf[..2])?;
+	test_assert_eq!(&buf[..2], b"23");
+	test_assert_eq!(file.read_at(&mut buf, 20)?, 0);
+	log!("Positional write");
+	test_assert_eq!(file.write_at(b"xy", 8)?, 2);
+	test_assert_eq!(file.stream_position()?, 4);
+	test_assert_eq!(fs::read(&path)?, b"01234567xy");
+
+	log!("Invalid arguments");
+	let res = unsafe { libc::pread(file.as_raw_fd(), buf.as_mut_ptr() as _, 1, -1) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	let mut pipe = [0; 2];
+	test_assert_eq!(unsafe { libc::pipe(pipe.as_mut_ptr()) }, 0);
+	let res = unsafe { libc::pwrite(pipe[1], b"a".as_ptr() as _, 1, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ESPIPE)
+	);
+	let res = unsafe { libc::pread(pipe[0], buf.as_mut_ptr() as _, 1, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ESPIPE)
+	);
+	unsafe {
+		libc::close(pipe[0]);
+		libc::close(pipe[1]);
+	}
+
+	log!("Cleanup");
+	drop(file);
+	fs::remove_file(path)?;
+
+	Ok(())
+}
+
 pub fn rename(root: &Path) -> TestResult {
 	let old = root.join("old");
 	let new = root.join("new");
@@ -340,6 +600,102 @@ pub fn rename(root: &Path) -> TestResult {
 	Ok(())
 }
 
+pub fn statfs(root: &Path) -> TestResult {
+	log!("Statfs");
+	let stat = util::statfs(root)?;
+	test_assert!(stat.f_bsize > 0);
+	test_assert!(stat.f_bfree <= stat.f_blocks);
+	test_assert!(stat.f_bavail <= stat.f_bfree);
+	test_assert!(stat.f_ffree <= stat.f_files);
+	test_assert!(stat.f_namelen > 0);
+
+	log!("Fstatfs");
+	let file = File::open(root)?;
+	let fstat = util::fstatfs(file.as_raw_fd())?;
+	test_assert_eq!(fstat.f_type, stat.f_type);
+	test_assert_eq!(fstat.f_blocks, stat.f_blocks);
+	test_assert_eq!(fstat.f_files, stat.f_files);
+
+	log!("Statfs on missing file");
+	let res = util::statfs(root.join("statfs_missing"));
+	test_assert!(matches!(res, Err(e) if e.kind() == io::ErrorKind::NotFound));
+
+	log!("Statfs on invalid buffer");
+	let path = CString::new(root.as_os_str().as_bytes())?;
+	let res = unsafe { libc::statfs(path.as_ptr(), ptr::null_mut()) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EFAULT)
+	);
+
+	Ok(())
+}
+
+pub fn rename_move(root: &Path) -> TestResult {
+	let a = root.join("rename_a");
+	let b = root.join("rename_b");
+
+	log!("Create directories");
+	fs::create_dir_all(a.join("dir/sub"))?;
+	fs::create_dir(&b)?;
+
+	log!("Move directory");
+	fs::rename(a.join("dir"), b.join("dir"))?;
+	test_assert!(
+		matches!(fs::metadata(a.join("dir")), Err(e) if e.kind() == io::ErrorKind::NotFound)
+	);
+	test_assert_eq!(fs::metadata(&a)?.nlink(), 2);
+	test_assert_eq!(fs::metadata(&b)?.nlink(), 3);
+	test_assert_eq!(fs::metadata(b.join("dir"))?.nlink(), 3);
+	log!("Check `..` entry");
+	test_assert_eq!(
+		fs::metadata(b.join("dir/.."))?.ino(),
+		fs::metadata(&b)?.ino()
+	);
+
+	log!("Move directory into its own descendant");
+	let res = fs::rename(b.join("dir"), b.join("dir/sub/dir"));
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EINVAL)));
+
+	log!("Overwrite file");
+	fs::write(a.join("file0"), b"abc")?;
+	fs::write(b.join("file1"), b"defgh")?;
+	fs::rename(a.join("file0"), b.join("file1"))?;
+	test_assert!(
+		matches!(fs::metadata(a.join("file0")), Err(e) if e.kind() == io::ErrorKind::NotFound)
+	);
+	let metadata = fs::metadata(b.join("file1"))?;
+	test_assert_eq!(metadata.nlink(), 1);
+	test_assert_eq!(fs::read(b.join("file1"))?, b"abc");
+
+	log!("Overwrite directory with file");
+	fs::write(a.join("file0"), b"abc")?;
+	let res = fs::rename(a.join("file0"), b.join("dir"));
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::EISDIR)));
+	fs::remove_file(a.join("file0"))?;
+
+	log!("Overwrite non-empty directory");
+	fs::create_dir(a.join("dir"))?;
+	let res = fs::rename(a.join("dir"), b.join("dir"));
+	test_assert!(matches!(res, Err(e) if e.raw_os_error() == Some(libc::ENOTEMPTY)));
+
+	log!("Overwrite empty directory");
+	fs::rename(b.join("dir/sub"), a.join("dir"))?;
+	test_assert!(
+		matches!(fs::metadata(b.join("dir/sub")), Err(e) if e.kind() == io::ErrorKind::NotFound)
+	);
+	test_assert_eq!(fs::metadata(&a)?.nlink(), 3);
+	test_assert_eq!(fs::metadata(b.join("dir"))?.nlink(), 2);
+	test_assert_eq!(fs::metadata(a.join("dir"))?.nlink(), 2);
+
+	log!("Cleanup");
+	fs::remove_dir_all(&a)?;
+	fs::remove_dir_all(&b)?;
+
+	Ok(())
+}
+
 pub fn fifo(root: &Path) -> TestResult {
 	log!("Create fifo");
 	let path = root.join("fifo");
@@ -0,0 +1,791 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V IPC testing.
+
+use crate::{
+	log, test_assert, test_assert_eq,
+	util::{sigaction, signal, TestResult},
+};
+use std::{
+	fs, io,
+	io::{Read, Write},
+	mem,
+	os::{
+		fd::AsRawFd,
+		unix::{
+			fs::FileTypeExt,
+			net::{UnixDatagram, UnixListener, UnixStream},
+		},
+	},
+	path::Path,
+	ptr, slice,
+};
+
+/// The key used for testing.
+const KEY: libc::key_t = 0x4d414553;
+
+/// Semaphore operation flag: undo the operation on exit.
+const SEM_UNDO: libc::c_int = 0x1000;
+/// Semaphore command: get the value of a semaphore.
+const GETVAL: libc::c_int = 12;
+/// Semaphore command: set the value of a semaphore.
+const SETVAL: libc::c_int = 16;
+
+/// Waits for the child process `pid` and checks it exited successfully.
+fn wait_child(pid: libc::pid_t) -> TestResult {
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	Ok(())
+}
+
+pub fn shm() -> TestResult {
+	const SIZE: usize = 8192;
+	const DATA: &[u8] = b"hello from the other side";
+	log!("Create segment");
+	let id = unsafe { libc::shmget(KEY, SIZE, libc::IPC_CREAT | libc::IPC_EXCL | 0o600) };
+	if id < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::shmget(KEY, SIZE, libc::IPC_CREAT | libc::IPC_EXCL | 0o600) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EEXIST)
+	);
+	log!("Write from child");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// Lookup the segment by key
+		let id = unsafe { libc::shmget(KEY, 0, 0) };
+		let ptr = unsafe { libc::shmat(id, ptr::null(), 0) };
+		if id < 0 || ptr as isize == -1 {
+			unsafe { libc::_exit(1) };
+		}
+		unsafe {
+			ptr::copy_nonoverlapping(DATA.as_ptr(), ptr as *mut u8, DATA.len());
+			libc::shmdt(ptr);
+			libc::_exit(0);
+		}
+	}
+	wait_child(pid)?;
+	log!("Read from parent");
+	let ptr = unsafe { libc::shmat(id, ptr::null(), libc::SHM_RDONLY) };
+	if ptr as isize == -1 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let data = unsafe { slice::from_raw_parts(ptr as *const u8, DATA.len()) };
+	test_assert_eq!(data, DATA);
+	log!("Status");
+	let mut ds: libc::shmid_ds = unsafe { mem::zeroed() };
+	let res = unsafe { libc::shmctl(id, libc::IPC_STAT, &mut ds) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert_eq!(ds.shm_segsz, SIZE);
+	test_assert_eq!(ds.shm_nattch, 1);
+	test_assert_eq!(ds.shm_perm.mode & 0o777, 0o600);
+	log!("Remove");
+	let res = unsafe { libc::shmctl(id, libc::IPC_RMID, ptr::null_mut()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	// The segment remains accessible until detached
+	test_assert_eq!(data, DATA);
+	let res = unsafe { libc::shmget(KEY, 0, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOENT)
+	);
+	let res = unsafe { libc::shmdt(ptr) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	Ok(())
+}
+
+/// Performs the operation `op` on the semaphore `num` of the set `id`.
+fn semop(id: libc::c_int, num: u16, op: i16, flg: libc::c_int) -> io::Result<()> {
+	let mut buf = libc::sembuf {
+		sem_num: num,
+		sem_op: op,
+		sem_flg: flg as _,
+	};
+	let res = unsafe { libc::semop(id, &mut buf, 1) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn sem() -> TestResult {
+	const ITEMS: u16 = 0;
+	const UNDO: u16 = 1;
+	log!("Create set");
+	let id = unsafe { libc::semget(KEY, 2, libc::IPC_CREAT | libc::IPC_EXCL | 0o600) };
+	if id < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let val = unsafe { libc::semctl(id, ITEMS as _, GETVAL) };
+	test_assert_eq!(val, 0);
+	log!("Non-blocking operation");
+	let res = semop(id, ITEMS, -1, libc::IPC_NOWAIT);
+	test_assert_eq!(res.map_err(|e| e.raw_os_error()), Err(Some(libc::EAGAIN)));
+	log!("Consume from child");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// Blocks until the parent produces items
+		let status = (0..3).try_for_each(|_| semop(id, ITEMS, -1, 0)).is_err();
+		unsafe {
+			libc::_exit(status as _);
+		}
+	}
+	log!("Produce from parent");
+	for _ in 0..3 {
+		semop(id, ITEMS, 1, 0)?;
+	}
+	wait_child(pid)?;
+	let val = unsafe { libc::semctl(id, ITEMS as _, GETVAL) };
+	test_assert_eq!(val, 0);
+	log!("Undo on exit");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let status = semop(id, UNDO, 5, SEM_UNDO).is_err();
+		unsafe {
+			libc::_exit(status as _);
+		}
+	}
+	wait_child(pid)?;
+	let val = unsafe { libc::semctl(id, UNDO as _, GETVAL) };
+	test_assert_eq!(val, 0);
+	log!("Set value");
+	let res = unsafe { libc::semctl(id, UNDO as _, SETVAL, 7) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let val = unsafe { libc::semctl(id, UNDO as _, GETVAL) };
+	test_assert_eq!(val, 7);
+	log!("Remove");
+	let res = unsafe { libc::semctl(id, 0, libc::IPC_RMID) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::semget(KEY, 0, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOENT)
+	);
+	Ok(())
+}
+
+/// A message for message queues.
+#[repr(C)]
+struct Msg {
+	/// The type of the message.
+	mtype: libc::c_long,
+	/// The content of the message.
+	mtext: [u8; 16],
+}
+
+/// Sends the message `text` with type `mtype` to the queue `id`.
+fn msgsnd(id: libc::c_int, mtype: libc::c_long, text: &[u8]) -> io::Result<()> {
+	let mut msg = Msg {
+		mtype,
+		mtext: [0; 16],
+	};
+	msg.mtext[..text.len()].copy_from_slice(text);
+	let res = unsafe { libc::msgsnd(id, &msg as *const _ as *const _, text.len(), 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+/// Receives a message with type selector `mtype` from the queue `id`.
+///
+/// On success, the function returns the type and content of the message.
+fn msgrcv(
+	id: libc::c_int,
+	mtype: libc::c_long,
+	flags: libc::c_int,
+) -> io::Result<(libc::c_long, Vec<u8>)> {
+	let mut msg = Msg {
+		mtype: 0,
+		mtext: [0; 16],
+	};
+	let len = unsafe { libc::msgrcv(id, &mut msg as *mut _ as *mut _, 16, mtype, flags) };
+	if len < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok((msg.mtype, msg.mtext[..len as usize].to_vec()))
+}
+
+pub fn msg() -> TestResult {
+	log!("Create queue");
+	let id = unsafe { libc::msgget(KEY, libc::IPC_CREAT | libc::IPC_EXCL | 0o600) };
+	if id < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	log!("Send messages");
+	msgsnd(id, 1, b"one")?;
+	msgsnd(id, 2, b"two")?;
+	msgsnd(id, 1, b"three")?;
+	log!("Receive by type");
+	test_assert_eq!(msgrcv(id, 2, 0)?, (2, b"two".to_vec()));
+	let mut ds: libc::msqid_ds = unsafe { mem::zeroed() };
+	let res = unsafe { libc::msgctl(id, libc::IPC_STAT, &mut ds) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert_eq!(ds.msg_qnum, 2);
+	test_assert_eq!(ds.msg_qbytes, 16384);
+	let res = msgrcv(id, 2, libc::IPC_NOWAIT);
+	test_assert_eq!(res.map_err(|e| e.raw_os_error()), Err(Some(libc::ENOMSG)));
+	// The other messages remain in order
+	test_assert_eq!(msgrcv(id, 0, 0)?, (1, b"one".to_vec()));
+	test_assert_eq!(msgrcv(id, 1, 0)?, (1, b"three".to_vec()));
+	log!("Receive from child");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// Blocks until the parent sends the message
+		let status = !matches!(msgrcv(id, 3, 0), Ok((3, text)) if text == b"ping");
+		unsafe {
+			libc::_exit(status as _);
+		}
+	}
+	msgsnd(id, 3, b"ping")?;
+	wait_child(pid)?;
+	log!("Remove");
+	let res = unsafe { libc::msgctl(id, libc::IPC_RMID, ptr::null_mut()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::msgget(KEY, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOENT)
+	);
+	Ok(())
+}
+
+pub fn mqueue() -> TestResult {
+	let name = c"/inttest";
+	log!("Create queue");
+	let mut attr: libc::mq_attr = unsafe { mem::zeroed() };
+	attr.mq_maxmsg = 4;
+	attr.mq_msgsize = 16;
+	let mqd = unsafe {
+		libc::mq_open(
+			name.as_ptr(),
+			libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+			0o600,
+			&attr,
+		)
+	};
+	if mqd < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let mut attr: libc::mq_attr = unsafe { mem::zeroed() };
+	let res = unsafe { libc::mq_getattr(mqd, &mut attr) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert_eq!(attr.mq_maxmsg, 4);
+	test_assert_eq!(attr.mq_msgsize, 16);
+	test_assert_eq!(attr.mq_curmsgs, 0);
+	log!("Send messages");
+	for (text, prio) in [(&b"low"[..], 1), (b"high", 10), (b"mid", 5), (b"high2", 10)] {
+		let res = unsafe { libc::mq_send(mqd, text.as_ptr() as _, text.len(), prio) };
+		if res < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+	}
+	log!("Receive by priority");
+	let mut buf = [0u8; 16];
+	for (text, prio) in [(&b"high"[..], 10), (b"high2", 10), (b"mid", 5), (b"low", 1)] {
+		let mut p = 0;
+		let len = unsafe { libc::mq_receive(mqd, buf.as_mut_ptr() as _, buf.len(), &mut p) };
+		if len < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		test_assert_eq!(&buf[..len as usize], text);
+		test_assert_eq!(p, prio);
+	}
+	log!("Buffer too small");
+	let res = unsafe { libc::mq_receive(mqd, buf.as_mut_ptr() as _, 8, ptr::null_mut()) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EMSGSIZE)
+	);
+	log!("Timeout");
+	let mut ts: libc::timespec = unsafe { mem::zeroed() };
+	unsafe {
+		libc::clock_gettime(libc::CLOCK_REALTIME, &mut ts);
+	}
+	let res = unsafe {
+		libc::mq_timedreceive(mqd, buf.as_mut_ptr() as _, buf.len(), ptr::null_mut(), &ts)
+	};
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ETIMEDOUT)
+	);
+	log!("Non-blocking");
+	let mut attr: libc::mq_attr = unsafe { mem::zeroed() };
+	attr.mq_flags = libc::O_NONBLOCK as _;
+	let res = unsafe { libc::mq_setattr(mqd, &attr, ptr::null_mut()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::mq_receive(mqd, buf.as_mut_ptr() as _, buf.len(), ptr::null_mut()) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EAGAIN)
+	);
+	log!("Unlink");
+	unsafe {
+		libc::mq_close(mqd);
+	}
+	let res = unsafe { libc::mq_unlink(name.as_ptr()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::mq_open(name.as_ptr(), libc::O_RDWR) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOENT)
+	);
+	Ok(())
+}
+
+/// Waits for at most 4 events on the epoll instance `epfd` and returns them as pairs of events
+/// mask and user data.
+fn epoll_wait(epfd: libc::c_int, timeout: libc::c_int) -> io::Result<Vec<(u32, u64)>> {
+	let mut events: [libc::epoll_event; 4] = unsafe { mem::zeroed() };
+	let res = unsafe { libc::epoll_wait(epfd, events.as_mut_ptr(), 4, timeout) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(events[..res as usize]
+		.iter()
+		.map(|ev| (ev.events, ev.u64))
+		.collect())
+}
+
+/// Performs the `op` operation on the file descriptor `fd` of the epoll instance `epfd`.
+fn epoll_ctl(epfd: libc::c_int, op: libc::c_int, fd: libc::c_int, events: u32) -> io::Result<()> {
+	let mut ev = libc::epoll_event {
+		events,
+		u64: fd as _,
+	};
+	let res = unsafe { libc::epoll_ctl(epfd, op, fd, &mut ev) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn epoll() -> TestResult {
+	log!("Create instance");
+	let epfd = unsafe { libc::epoll_create1(libc::EPOLL_CLOEXEC) };
+	if epfd < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let mut pipe = [0; 2];
+	if unsafe { libc::pipe(pipe.as_mut_ptr()) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let [rd, wr] = pipe;
+	const IN: u32 = libc::EPOLLIN as _;
+	epoll_ctl(epfd, libc::EPOLL_CTL_ADD, rd, IN)?;
+	let err = epoll_ctl(epfd, libc::EPOLL_CTL_ADD, rd, IN).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EEXIST));
+	test_assert_eq!(epoll_wait(epfd, 0)?, []);
+	log!("Level-triggered");
+	test_assert_eq!(unsafe { libc::write(wr, b"a".as_ptr() as _, 1) }, 1);
+	test_assert_eq!(epoll_wait(epfd, 0)?, [(IN, rd as u64)]);
+	test_assert_eq!(epoll_wait(epfd, 0)?, [(IN, rd as u64)]);
+	log!("Edge-triggered");
+	epoll_ctl(epfd, libc::EPOLL_CTL_MOD, rd, IN | libc::EPOLLET as u32)?;
+	test_assert_eq!(epoll_wait(epfd, 0)?, [(IN, rd as u64)]);
+	test_assert_eq!(epoll_wait(epfd, 0)?, []);
+	log!("One-shot");
+	epoll_ctl(
+		epfd,
+		libc::EPOLL_CTL_MOD,
+		rd,
+		IN | libc::EPOLLONESHOT as u32,
+	)?;
+	test_assert_eq!(epoll_wait(epfd, 0)?, [(IN, rd as u64)]);
+	test_assert_eq!(epoll_wait(epfd, 0)?, []);
+	epoll_ctl(epfd, libc::EPOLL_CTL_MOD, rd, IN)?;
+	test_assert_eq!(epoll_wait(epfd, 0)?, [(IN, rd as u64)]);
+	let mut b = 0u8;
+	test_assert_eq!(unsafe { libc::read(rd, &mut b as *mut _ as _, 1) }, 1);
+	test_assert_eq!(epoll_wait(epfd, 0)?, []);
+	log!("Wake up on write");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::usleep(50000);
+			libc::write(wr, b"b".as_ptr() as _, 1);
+			libc::_exit(0);
+		}
+	}
+	test_assert_eq!(epoll_wait(epfd, 5000)?, [(IN, rd as u64)]);
+	wait_child(pid)?;
+	log!("Hang up");
+	unsafe {
+		libc::close(wr);
+	}
+	test_assert_eq!(
+		epoll_wait(epfd, 0)?,
+		[(IN | libc::EPOLLHUP as u32, rd as u64)]
+	);
+	log!("Unregister");
+	epoll_ctl(epfd, libc::EPOLL_CTL_DEL, rd, 0)?;
+	let err = epoll_ctl(epfd, libc::EPOLL_CTL_DEL, rd, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+	unsafe {
+		libc::close(rd);
+	}
+	let err = epoll_ctl(epfd, libc::EPOLL_CTL_DEL, rd, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+	unsafe {
+		libc::close(epfd);
+	}
+	Ok(())
+}
+
+extern "C" fn noop_handler(_: libc::c_int) {}
+
+/// Polls the file descriptor `fd` for `events` and returns the resulting events.
+fn poll_fd(fd: libc::c_int, events: libc::c_short, timeout: libc::c_int) -> io::Result<i16> {
+	let mut pfd = libc::pollfd {
+		fd,
+		events,
+		revents: 0,
+	};
+	let res = unsafe { libc::poll(&mut pfd, 1, timeout) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(pfd.revents)
+}
+
+pub fn poll_select() -> TestResult {
+	let mut pipe = [0; 2];
+	if unsafe { libc::pipe(pipe.as_mut_ptr()) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let [rd, wr] = pipe;
+	log!("Poll with a zero timeout");
+	test_assert_eq!(poll_fd(rd, libc::POLLIN, 0)?, 0);
+	test_assert_eq!(poll_fd(wr, libc::POLLOUT, 0)?, libc::POLLOUT);
+	test_assert_eq!(poll_fd(1000, libc::POLLIN, 0)?, libc::POLLNVAL);
+	log!("Poll readable");
+	test_assert_eq!(unsafe { libc::write(wr, b"a".as_ptr() as _, 1) }, 1);
+	test_assert_eq!(poll_fd(rd, libc::POLLIN, 0)?, libc::POLLIN);
+	log!("Select");
+	let select = |read: bool, timeout: Option<libc::timeval>| -> io::Result<(i32, bool)> {
+		let mut set: libc::fd_set = unsafe { mem::zeroed() };
+		let (rfds, wfds) = unsafe {
+			libc::FD_SET(if read { rd } else { wr }, &mut set);
+			if read {
+				(&mut set as *mut _, ptr::null_mut())
+			} else {
+				(ptr::null_mut(), &mut set as *mut _)
+			}
+		};
+		let mut timeout = timeout;
+		let timeout = timeout
+			.as_mut()
+			.map(|t| t as *mut _)
+			.unwrap_or(ptr::null_mut());
+		let res = unsafe { libc::select(rd.max(wr) + 1, rfds, wfds, ptr::null_mut(), timeout) };
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		let fd = if read { rd } else { wr };
+		Ok((res, unsafe { libc::FD_ISSET(fd, &set) }))
+	};
+	let zero = libc::timeval {
+		tv_sec: 0,
+		tv_usec: 0,
+	};
+	test_assert_eq!(select(true, Some(zero))?, (1, true));
+	test_assert_eq!(select(false, None)?, (1, true));
+	let mut b = 0u8;
+	test_assert_eq!(unsafe { libc::read(rd, &mut b as *mut _ as _, 1) }, 1);
+	test_assert_eq!(select(true, Some(zero))?, (0, false));
+	log!("Wait for data");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::usleep(50000);
+			libc::write(wr, b"b".as_ptr() as _, 1);
+			libc::_exit(0);
+		}
+	}
+	test_assert_eq!(poll_fd(rd, libc::POLLIN, -1)?, libc::POLLIN);
+	wait_child(pid)?;
+	test_assert_eq!(unsafe { libc::read(rd, &mut b as *mut _ as _, 1) }, 1);
+	log!("Interrupt with a signal");
+	sigaction(libc::SIGUSR1, noop_handler as usize, 0)?;
+	let ppid = unsafe { libc::getpid() };
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::usleep(50000);
+			libc::kill(ppid, libc::SIGUSR1);
+			libc::_exit(0);
+		}
+	}
+	let err = poll_fd(rd, libc::POLLIN, -1).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+	wait_child(pid)?;
+	signal(libc::SIGUSR1, libc::SIG_DFL)?;
+	log!("Hang up");
+	unsafe {
+		libc::close(wr);
+	}
+	test_assert_eq!(poll_fd(rd, libc::POLLIN, 0)?, libc::POLLHUP);
+	unsafe {
+		libc::close(rd);
+	}
+	Ok(())
+}
+
+pub fn unix_stream() -> TestResult {
+	const PATH: &str = "/tmp/unix_stream.sock";
+	let _ = fs::remove_file(PATH);
+	log!("Bind");
+	let listener = UnixListener::bind(PATH)?;
+	test_assert!(fs::metadata(PATH)?.file_type().is_socket());
+	let err = UnixListener::bind(PATH).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EADDRINUSE));
+	log!("Connect");
+	let mut client = UnixStream::connect(PATH)?;
+	test_assert_eq!(
+		poll_fd(listener.as_raw_fd(), libc::POLLIN, 0)?,
+		libc::POLLIN
+	);
+	let (mut server, _) = listener.accept()?;
+	log!("Exchange data");
+	client.write_all(b"ping")?;
+	test_assert_eq!(poll_fd(server.as_raw_fd(), libc::POLLIN, 0)?, libc::POLLIN);
+	let mut buf = [0u8; 4];
+	server.read_exact(&mut buf)?;
+	test_assert_eq!(&buf, b"ping");
+	server.write_all(b"pong")?;
+	client.read_exact(&mut buf)?;
+	test_assert_eq!(&buf, b"pong");
+	log!("Close");
+	drop(server);
+	test_assert_eq!(client.read(&mut buf)?, 0);
+	drop(listener);
+	let err = UnixStream::connect(PATH).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ECONNREFUSED));
+	fs::remove_file(PATH)?;
+	Ok(())
+}
+
+pub fn unix_dgram() -> TestResult {
+	const PATH_A: &str = "/tmp/unix_dgram_a.sock";
+	const PATH_B: &str = "/tmp/unix_dgram_b.sock";
+	let _ = fs::remove_file(PATH_A);
+	let _ = fs::remove_file(PATH_B);
+	log!("Bind");
+	let a = UnixDatagram::bind(PATH_A)?;
+	let b = UnixDatagram::bind(PATH_B)?;
+	log!("Send messages");
+	a.send_to(b"hello", PATH_B)?;
+	a.send_to(b"world!", PATH_B)?;
+	log!("Receive messages");
+	let mut buf = [0u8; 16];
+	let (len, addr) = b.recv_from(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"hello");
+	test_assert_eq!(addr.as_pathname(), Some(Path::new(PATH_A)));
+	// Message boundaries are preserved
+	let (len, _) = b.recv_from(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"world!");
+	log!("Connected socket");
+	b.connect(PATH_A)?;
+	b.send(b"reply")?;
+	let len = a.recv(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"reply");
+	log!("Non-blocking receive");
+	a.set_nonblocking(true)?;
+	let err = a.recv(&mut buf).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EAGAIN));
+	fs::remove_file(PATH_A)?;
+	fs::remove_file(PATH_B)?;
+	Ok(())
+}
+
+/// Returns the value of the socket option `opt` at level `SOL_SOCKET`.
+fn getsockopt(fd: libc::c_int, opt: libc::c_int) -> io::Result<libc::c_int> {
+	let mut val: libc::c_int = 0;
+	let mut len = mem::size_of_val(&val) as libc::socklen_t;
+	let res =
+		unsafe { libc::getsockopt(fd, libc::SOL_SOCKET, opt, &mut val as *mut _ as _, &mut len) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(val)
+}
+
+/// Sets the value of the socket option `opt` at level `SOL_SOCKET`.
+fn setsockopt(fd: libc::c_int, opt: libc::c_int, val: libc::c_int) -> io::Result<()> {
+	let len = mem::size_of_val(&val) as libc::socklen_t;
+	let res = unsafe { libc::setsockopt(fd, libc::SOL_SOCKET, opt, &val as *const _ as _, len) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn unix_sockopt() -> TestResult {
+	let sock = UnixDatagram::unbound()?;
+	let fd = sock.as_raw_fd();
+	log!("Read options");
+	test_assert_eq!(getsockopt(fd, libc::SO_TYPE)?, libc::SOCK_DGRAM);
+	let stream_fd = unsafe { libc::socket(libc::AF_UNIX, libc::SOCK_STREAM, 0) };
+	if stream_fd < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert_eq!(getsockopt(stream_fd, libc::SO_TYPE)?, libc::SOCK_STREAM);
+	unsafe {
+		libc::close(stream_fd);
+	}
+	test_assert_eq!(getsockopt(fd, libc::SO_ERROR)?, 0);
+	test_assert_eq!(getsockopt(fd, libc::SO_REUSEADDR)?, 0);
+	log!("Set and read back buffer sizes");
+	setsockopt(fd, libc::SO_RCVBUF, 8192)?;
+	// The size is doubled to account for bookkeeping overhead
+	test_assert_eq!(getsockopt(fd, libc::SO_RCVBUF)?, 16384);
+	setsockopt(fd, libc::SO_SNDBUF, 16384)?;
+	test_assert_eq!(getsockopt(fd, libc::SO_SNDBUF)?, 32768);
+	setsockopt(fd, libc::SO_REUSEADDR, 1)?;
+	test_assert_eq!(getsockopt(fd, libc::SO_REUSEADDR)?, 1);
+	log!("Invalid options");
+	let val: libc::c_short = 1;
+	let res = unsafe {
+		libc::setsockopt(
+			fd,
+			libc::SOL_SOCKET,
+			libc::SO_RCVBUF,
+			&val as *const _ as _,
+			2,
+		)
+	};
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	let err = setsockopt(fd, libc::SO_TYPE, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ENOPROTOOPT));
+	Ok(())
+}
+
+pub fn unix_pair() -> TestResult {
+	log!("Stream pair");
+	let (mut a, mut b) = UnixStream::pair()?;
+	a.write_all(b"ping")?;
+	let mut buf = [0u8; 4];
+	b.read_exact(&mut buf)?;
+	test_assert_eq!(&buf, b"ping");
+	b.write_all(b"pong")?;
+	a.read_exact(&mut buf)?;
+	test_assert_eq!(&buf, b"pong");
+	drop(b);
+	test_assert_eq!(a.read(&mut buf)?, 0);
+	log!("Datagram pair");
+	let (a, b) = UnixDatagram::pair()?;
+	a.send(b"hello")?;
+	a.send(b"world!")?;
+	let mut buf = [0u8; 16];
+	let len = b.recv(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"hello");
+	let len = b.recv(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"world!");
+	b.send(b"reply")?;
+	let len = a.recv(&mut buf)?;
+	test_assert_eq!(&buf[..len], b"reply");
+	log!("Flags");
+	let mut sv = [0; 2];
+	let res = unsafe {
+		libc::socketpair(
+			libc::AF_UNIX,
+			libc::SOCK_STREAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+			0,
+			sv.as_mut_ptr(),
+		)
+	};
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	for fd in sv {
+		test_assert!(unsafe { libc::fcntl(fd, libc::F_GETFD) } & libc::FD_CLOEXEC != 0);
+		test_assert!(unsafe { libc::fcntl(fd, libc::F_GETFL) } & libc::O_NONBLOCK != 0);
+	}
+	let res = unsafe { libc::read(sv[0], buf.as_mut_ptr() as _, buf.len()) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EAGAIN)
+	);
+	unsafe {
+		libc::close(sv[0]);
+		libc::close(sv[1]);
+	}
+	Ok(())
+}
@@ -24,12 +24,20 @@ use crate::{
 	mount::{mount, umount},
 	util::TestResult,
 };
-use std::{path::Path, process::exit};
+use std::{env, path::Path, process::exit};
 
+mod cgroup;
+mod devtmpfs;
 mod filesystem;
+mod ipc;
 mod mount;
+mod namespace;
 mod procfs;
+mod sched;
 mod signal;
+mod sysfs;
+mod system;
+mod time;
 mod util;
 
 /*
@@ -92,6 +100,26 @@ macro_rules! fs_suite {
 					desc: "Test symbolic links",
 					start: || filesystem::symlinks(Path::new($root)),
 				},
+				Test {
+					name: "openat2",
+					desc: "Test restricting path resolution with openat2",
+					start: || filesystem::openat2(Path::new($root)),
+				},
+				Test {
+					name: "fadvise",
+					desc: "Give hints about file accesses with posix_fadvise",
+					start: || filesystem::fadvise(Path::new($root)),
+				},
+				Test {
+					name: "vectored",
+					desc: "Scatter/gather I/O with readv and writev",
+					start: || filesystem::vectored(Path::new($root)),
+				},
+				Test {
+					name: "pread_pwrite",
+					desc: "Read and write at an offset with pread and pwrite",
+					start: || filesystem::pread_pwrite(Path::new($root)),
+				},
 				// TODO test with a lot of files
 				// TODO test with big files
 				// TODO try to fill the filesystem
@@ -101,6 +129,16 @@ macro_rules! fs_suite {
 					desc: "Test renaming files",
 					start: || filesystem::rename(Path::new($root)),
 				},
+				Test {
+					name: "statfs",
+					desc: "Test getting filesystem statistics",
+					start: || filesystem::statfs(Path::new($root)),
+				},
+				Test {
+					name: "rename_move",
+					desc: "Test moving files across directories and replacing existing ones",
+					start: || filesystem::rename_move(Path::new($root)),
+				},
 				Test {
 					name: "fifo",
 					desc: "Test FIFO files",
@@ -130,6 +168,11 @@ const TESTS: &[TestSuite] = &[
 				desc: "Mount tmpfs",
 				start: || mount("tmpfs", "/tmp", "tmpfs"),
 			},
+			Test {
+				name: "sysfs",
+				desc: "Mount sysfs",
+				start: || mount("sysfs", "/sys", "sysfs"),
+			},
 			// TODO other filesystem types
 		],
 	},
@@ -145,15 +188,401 @@ const TESTS: &[TestSuite] = &[
 				name: "handler",
 				desc: "Register and use a signal handler",
 				start: signal::handler,
-			}, /* TODO signal masking
-			    * TODO pause */
+			},
+			Test {
+				name: "masking",
+				desc: "Block signals and deliver them once unblocked",
+				start: signal::masking,
+			},
+			Test {
+				name: "nesting",
+				desc: "Block the signal during the execution of its handler, unless `SA_NODEFER`",
+				start: signal::nesting,
+			},
+			Test {
+				name: "realtime",
+				desc: "Queue real-time signals",
+				start: signal::realtime,
+			},
+			Test {
+				name: "siginfo",
+				desc: "Receive signal information with `SA_SIGINFO`",
+				start: signal::siginfo,
+			},
+			Test {
+				name: "illegal_instruction",
+				desc: "Catch `SIGILL` on an invalid opcode",
+				start: signal::illegal_instruction,
+			},
+			Test {
+				name: "divide_error",
+				desc: "Catch `SIGFPE` on integer division errors",
+				start: signal::divide_error,
+			},
+			Test {
+				name: "alignment_check",
+				desc: "Catch `SIGBUS` on misaligned accesses with alignment checks enabled",
+				start: signal::alignment_check,
+			},
+			Test {
+				name: "sigreturn",
+				desc: "Restore the interrupted context after a signal handler",
+				start: signal::sigreturn,
+			},
+			Test {
+				name: "restart",
+				desc: "Restart interrupted system calls with SA_RESTART",
+				start: signal::restart,
+			},
+			Test {
+				name: "tty",
+				desc: "Send signals to the foreground process group from the TTY",
+				start: signal::tty,
+			}, // TODO pause
+		],
+	},
+	TestSuite {
+		name: "system",
+		desc: "Test system-wide information",
+		tests: &[
+			Test {
+				name: "sysinfo",
+				desc: "Get system statistics with sysinfo",
+				start: system::sysinfo,
+			},
+			Test {
+				name: "swap",
+				desc: "Use more memory than available with a swap file",
+				start: system::swap,
+			},
+			Test {
+				name: "overcommit",
+				desc: "Memory commit accounting policies",
+				start: system::overcommit,
+			},
+			Test {
+				name: "oom_killer",
+				desc: "Exhaust memory and check the culprit gets killed",
+				start: system::oom_killer,
+			},
+			Test {
+				name: "mprotect",
+				desc: "Change the protection of a range of memory",
+				start: system::mprotect,
+			},
+			Test {
+				name: "fork_cow",
+				desc: "Copy-on-write of private mappings on fork",
+				start: system::fork_cow,
+			},
+			Test {
+				name: "mremap",
+				desc: "Resize and move mappings of memory",
+				start: system::mremap,
+			},
+			Test {
+				name: "stack_overflow",
+				desc: "Overflow the stack and check the guard page catches it",
+				start: system::stack_overflow,
+			},
+			Test {
+				name: "stack_growsdown",
+				desc: "Grow a MAP_GROWSDOWN mapping down to its guard page",
+				start: system::stack_growsdown,
+			},
+			Test {
+				name: "execfn",
+				desc: "AT_EXECFN points to the path of the program",
+				start: system::execfn,
+			},
+			Test {
+				name: "suid",
+				desc: "Execute set-user-ID and set-group-ID programs",
+				start: system::suid,
+			},
+			Test {
+				name: "aslr",
+				desc: "Randomize the memory layout of executed programs",
+				start: system::aslr,
+			},
+			Test {
+				name: "port_io",
+				desc: "Access I/O ports with ioperm and iopl",
+				start: system::port_io,
+			},
+			Test {
+				name: "kcov",
+				desc: "Collect the kernel's code coverage of a system call",
+				start: system::kcov,
+			},
+			Test {
+				name: "fault_inject",
+				desc: "Make kernel operations fail with /proc/self/fail-nth",
+				start: system::fault_inject,
+			},
+		],
+	},
+	TestSuite {
+		name: "ipc",
+		desc: "Test System V IPC",
+		tests: &[
+			Test {
+				name: "shm",
+				desc: "Share memory between processes",
+				start: ipc::shm,
+			},
+			Test {
+				name: "sem",
+				desc: "Coordinate processes with semaphores",
+				start: ipc::sem,
+			},
+			Test {
+				name: "msg",
+				desc: "Exchange messages between processes",
+				start: ipc::msg,
+			},
+			Test {
+				name: "mqueue",
+				desc: "Exchange prioritized messages through a POSIX message queue",
+				start: ipc::mqueue,
+			},
+			Test {
+				name: "epoll",
+				desc: "Wait for events on a pipe with epoll",
+				start: ipc::epoll,
+			},
+			Test {
+				name: "poll_select",
+				desc: "Wait for events on a pipe with poll and select",
+				start: ipc::poll_select,
+			},
+			Test {
+				name: "unix_stream",
+				desc: "Exchange data over a connected UNIX socket",
+				start: ipc::unix_stream,
+			},
+			Test {
+				name: "unix_dgram",
+				desc: "Exchange datagrams between UNIX sockets",
+				start: ipc::unix_dgram,
+			},
+			Test {
+				name: "unix_sockopt",
+				desc: "Get and set socket options",
+				start: ipc::unix_sockopt,
+			},
+			Test {
+				name: "unix_pair",
+				desc: "Exchange data over a pair of connected UNIX sockets",
+				start: ipc::unix_pair,
+			},
 		],
 	},
 	// TODO ELF files (execve)
 	// TODO user/group file accesses (including SUID/SGID)
-	// TODO time ((non-)monotonic clock, sleep and timer_*)
+	TestSuite {
+		name: "sched",
+		desc: "Test the scheduler",
+		tests: &[
+			Test {
+				name: "policy",
+				desc: "Get and set scheduling policies",
+				start: sched::policy,
+			},
+			Test {
+				name: "fifo",
+				desc: "A SCHED_FIFO process monopolizes the CPU",
+				start: sched::fifo,
+			},
+			Test {
+				name: "yield",
+				desc: "Busy processes alternate on each sched_yield",
+				start: sched::yield_alternate,
+			},
+			Test {
+				name: "affinity",
+				desc: "Get and set the CPU affinity mask",
+				start: sched::affinity,
+			},
+			Test {
+				name: "nice",
+				desc: "Get and set nice values",
+				start: sched::nice,
+			},
+			Test {
+				name: "nice_share",
+				desc: "A process with a lower nice value gets more CPU time",
+				start: sched::nice_share,
+			},
+			Test {
+				name: "ioprio",
+				desc: "Get and set I/O priorities",
+				start: sched::ioprio,
+			},
+			Test {
+				name: "threads",
+				desc: "Threads share the PID, memory and file descriptors",
+				start: sched::threads,
+			},
+			Test {
+				name: "exit_group",
+				desc: "Exiting or killing a thread terminates the thread group",
+				start: sched::exit_group,
+			},
+			Test {
+				name: "fpu",
+				desc: "The FPU state of processes survives context switches",
+				start: sched::fpu,
+			},
+		],
+	},
+	TestSuite {
+		name: "namespace",
+		desc: "Test namespaces",
+		tests: &[
+			Test {
+				name: "pid",
+				desc: "Translate PIDs across PID namespaces",
+				start: namespace::pid,
+			},
+			Test {
+				name: "mount",
+				desc: "Mount a filesystem in a new mount namespace",
+				start: namespace::mount,
+			},
+			Test {
+				name: "user",
+				desc: "Gain privileges in new user and UTS namespaces",
+				start: namespace::user,
+			},
+			Test {
+				name: "setns",
+				desc: "Join a namespace through its procfs link",
+				start: namespace::setns,
+			},
+			Test {
+				name: "chroot_escape",
+				desc: "Prevent a chrooted process from escaping through namespaces",
+				start: namespace::chroot_escape,
+			},
+		],
+	},
+	TestSuite {
+		name: "cgroup",
+		desc: "Test control groups",
+		tests: &[
+			Test {
+				name: "hierarchy",
+				desc: "Create and remove groups, and move processes",
+				start: cgroup::hierarchy,
+			},
+			Test {
+				name: "cpu.max",
+				desc: "Limit the CPU time of a group",
+				start: cgroup::cpu_max,
+			},
+		],
+	},
+	TestSuite {
+		name: "time",
+		desc: "Test clocks",
+		tests: &[
+			Test {
+				name: "clock_getres",
+				desc: "Get the resolution of clocks",
+				start: time::clock_resolution,
+			},
+			Test {
+				name: "clock_gettime",
+				desc: "Read the available clocks",
+				start: time::clocks,
+			},
+			Test {
+				name: "cpu_time",
+				desc: "Measure the CPU time consumed by the process",
+				start: time::cpu_time,
+			},
+			Test {
+				name: "clock_settime",
+				desc: "Set the real time clock without affecting the monotonic clock",
+				start: time::set_realtime,
+			},
+			Test {
+				name: "adjtime",
+				desc: "Gradually adjust the real time clock",
+				start: time::adjtime,
+			},
+			Test {
+				name: "gettimeofday",
+				desc: "Read the real time clock with gettimeofday and time",
+				start: time::gettimeofday,
+			},
+			Test {
+				name: "vvar",
+				desc: "Read clocks from the vvar page shared with the vDSO",
+				start: time::vvar,
+			},
+			Test {
+				name: "sleep",
+				desc: "Sleep with nanosleep and clock_nanosleep",
+				start: time::sleep,
+			},
+			Test {
+				name: "timers",
+				desc: "Arm one-shot and periodic POSIX timers",
+				start: time::timers,
+			},
+		],
+	},
 	// TODO termcaps
 	// TODO SSE/MMX/AVX states consistency
+	TestSuite {
+		name: "sysfs",
+		desc: "Test correctness of the sysfs filesystem",
+		tests: &[
+			Test {
+				name: "/sys/kernel/version",
+				desc: "/sys/kernel/version",
+				start: sysfs::version,
+			},
+			Test {
+				name: "/sys/block",
+				desc: "/sys/block",
+				start: sysfs::block,
+			},
+			Test {
+				name: "/sys/kernel/debug",
+				desc: "Mount the debugfs and read a file registered by the kernel",
+				start: sysfs::debugfs,
+			},
+		],
+	},
+	TestSuite {
+		name: "devtmpfs",
+		desc: "Test correctness of the devtmpfs filesystem",
+		tests: &[
+			Test {
+				name: "nodes",
+				desc: "List and use device files",
+				start: devtmpfs::nodes,
+			},
+			Test {
+				name: "mem",
+				desc: "Access physical memory through /dev/mem",
+				start: devtmpfs::mem,
+			},
+			Test {
+				name: "console",
+				desc: "Change the console's palette and cursor",
+				start: devtmpfs::console,
+			},
+			Test {
+				name: "pty",
+				desc: "Allocate a pseudo-terminal and exchange data between its master and slave",
+				start: devtmpfs::pty,
+			},
+		],
+	},
 	TestSuite {
 		name: "procfs",
 		desc: "Test correctness of the procfs filesystem",
@@ -178,6 +607,51 @@ const TESTS: &[TestSuite] = &[
 				desc: "/proc/self/environ",
 				start: procfs::environ,
 			},
+			Test {
+				name: "/proc/<pid>/environ",
+				desc: "Read the environment of a process that is not dumpable",
+				start: procfs::environ_dumpable,
+			},
+			Test {
+				name: "/proc/sys/fs/dentry-cache",
+				desc: "/proc/sys/fs/dentry-cache",
+				start: procfs::dentry_cache,
+			},
+			Test {
+				name: "/proc/self/oom_score_adj",
+				desc: "/proc/self/oom_score_adj",
+				start: procfs::oom_score_adj,
+			},
+			Test {
+				name: "/proc/self/status",
+				desc: "/proc/self/status",
+				start: procfs::status,
+			},
+			Test {
+				name: "/proc/self/maps",
+				desc: "/proc/self/maps",
+				start: procfs::maps,
+			},
+			Test {
+				name: "/proc/<pid>/maps",
+				desc: "Read the memory mappings of a process that is not dumpable",
+				start: procfs::maps_dumpable,
+			},
+			Test {
+				name: "/proc/<pid>",
+				desc: "Process directories follow the set of existing processes",
+				start: procfs::pid_dirs,
+			},
+			Test {
+				name: "/proc/self/strace",
+				desc: "Trace the system calls of the current process",
+				start: procfs::strace,
+			},
+			Test {
+				name: "/proc/kernel_tests",
+				desc: "Run the kernel's integration tests",
+				start: procfs::kernel_tests,
+			},
 			// TODO /proc/self/stat
 		],
 	},
@@ -219,11 +693,26 @@ const TESTS: &[TestSuite] = &[
 				desc: "Unmount tmpfs",
 				start: || umount("/tmp"),
 			},
+			Test {
+				name: "sysfs",
+				desc: "Unmount sysfs",
+				start: || umount("/sys"),
+			},
 		],
 	},
 ];
 
 fn main() {
+	// When executed again by a test with `ids` as argument, only print the process's IDs
+	if env::args().nth(1).as_deref() == Some("ids") {
+		system::print_ids();
+		return;
+	}
+	// When executed again by a test with `layout` as argument, only print the memory layout
+	if env::args().nth(1).as_deref() == Some("layout") {
+		system::print_layout();
+		return;
+	}
 	// The total number of tests
 	let total: usize = TESTS.iter().map(|t| t.tests.len()).sum();
 	// Start marker
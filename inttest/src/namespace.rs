@@ -0,0 +1,441 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Namespaces testing.
+
+use crate::{log, mount as mnt, sched::wait, test_assert, test_assert_eq, util::TestResult};
+use std::{
+	fs, io,
+	os::{fd::AsRawFd, unix::fs::PermissionsExt},
+	path::Path,
+};
+
+/// Creates a child process with the `clone` flags `flags`.
+///
+/// Like `fork`, the function returns `0` in the child and the PID of the child in the parent.
+fn clone(flags: libc::c_int) -> io::Result<libc::pid_t> {
+	let res = unsafe { libc::syscall(libc::SYS_clone, flags | libc::SIGCHLD, 0, 0, 0, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(res as _)
+}
+
+/// Checks the PIDs seen from inside a new PID namespace, then exits.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn pid_ns_child() -> ! {
+	let status = (|| {
+		// The first process of the namespace is its init, and its parent is not visible
+		if unsafe { libc::getpid() } != 1 {
+			return 1;
+		}
+		if unsafe { libc::getppid() } != 0 {
+			return 2;
+		}
+		let pid = unsafe { libc::fork() };
+		if pid < 0 {
+			return 3;
+		}
+		if pid == 0 {
+			let ok = unsafe { libc::getpid() == 2 && libc::getppid() == 1 };
+			unsafe {
+				libc::_exit(if ok { 0 } else { 1 });
+			}
+		}
+		if pid != 2 {
+			return 4;
+		}
+		match wait(pid) {
+			Ok(status) if libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0 => {}
+			_ => return 5,
+		}
+		// PIDs outside of the namespace cannot be reached
+		if unsafe { libc::kill(1000, 0) } == 0 {
+			return 6;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+pub fn pid() -> TestResult {
+	let parent = unsafe { libc::getpid() };
+	log!("Create a PID namespace");
+	let pid = clone(libc::CLONE_NEWPID)?;
+	if pid == 0 {
+		pid_ns_child();
+	}
+	log!("Check PIDs from the parent namespace");
+	test_assert_eq!(unsafe { libc::getpid() }, parent);
+	test_assert!(pid > 1);
+	test_assert_eq!(unsafe { libc::kill(pid, 0) }, 0);
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Create a thread in a new PID namespace");
+	let err = clone(libc::CLONE_NEWPID | libc::CLONE_THREAD).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	Ok(())
+}
+
+/// The directory on which a filesystem is mounted by [`mount_ns_child`].
+const MNT_PATH: &str = "/mnt";
+/// A file created in the filesystem mounted by [`mount_ns_child`].
+const MNT_FILE: &str = "/mnt/mount_ns";
+
+/// Mounts a tmpfs in a new mount namespace, then notifies the parent through `ready` and waits
+/// for it to write on `done` before exiting.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn mount_ns_child(ready: libc::c_int, done: libc::c_int) -> ! {
+	let status = (|| {
+		if unsafe { libc::unshare(libc::CLONE_NEWNS) } < 0 {
+			return 1;
+		}
+		if mnt::mount("tmpfs", MNT_PATH, "tmpfs").is_err() {
+			return 2;
+		}
+		if fs::write(MNT_FILE, b"hello").is_err() {
+			return 3;
+		}
+		let mut buf = [0u8];
+		unsafe {
+			if libc::write(ready, buf.as_ptr() as _, 1) != 1 {
+				return 4;
+			}
+			if libc::read(done, buf.as_mut_ptr() as _, 1) != 1 {
+				return 5;
+			}
+		}
+		// The mountpoint is still visible from the namespace
+		if !Path::new(MNT_FILE).exists() {
+			return 6;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+pub fn mount() -> TestResult {
+	fs::create_dir_all(MNT_PATH)?;
+	let mut ready = [0; 2];
+	let mut done = [0; 2];
+	unsafe {
+		test_assert_eq!(libc::pipe(ready.as_mut_ptr()), 0);
+		test_assert_eq!(libc::pipe(done.as_mut_ptr()), 0);
+	}
+	log!("Mount a tmpfs in a new mount namespace");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		mount_ns_child(ready[1], done[0]);
+	}
+	let mut buf = [0u8];
+	let len = unsafe { libc::read(ready[0], buf.as_mut_ptr() as _, 1) };
+	log!("Check the mountpoint is not visible from the parent namespace");
+	let visible = Path::new(MNT_FILE).exists();
+	unsafe {
+		libc::write(done[1], buf.as_ptr() as _, 1);
+		for fd in ready.into_iter().chain(done) {
+			libc::close(fd);
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	test_assert_eq!(len, 1);
+	test_assert!(!visible);
+	Ok(())
+}
+
+/// A file owned by root, which must remain inaccessible from a user namespace.
+const USER_NS_FILE: &str = "/tmp/user_ns";
+
+/// Drops privileges, creates new user and UTS namespaces, then checks privileges inside them.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn user_ns_child() -> ! {
+	let status = (|| {
+		unsafe {
+			if libc::setgid(1000) < 0 || libc::setuid(1000) < 0 {
+				return 1;
+			}
+			if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWUTS) < 0 {
+				return 2;
+			}
+		}
+		if fs::write("/proc/self/uid_map", b"0 1000 1").is_err() {
+			return 3;
+		}
+		// The process is root inside of the namespace
+		if unsafe { libc::getuid() != 0 || libc::geteuid() != 0 } {
+			return 4;
+		}
+		let name = b"inside";
+		if unsafe { libc::sethostname(name.as_ptr() as _, name.len()) } < 0 {
+			return 5;
+		}
+		// ...but not outside of it
+		match fs::File::open(USER_NS_FILE) {
+			Err(e) if e.raw_os_error() == Some(libc::EACCES) => {}
+			_ => return 6,
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+/// Returns the hostname of the system.
+fn hostname() -> io::Result<Vec<u8>> {
+	let mut buf = [0u8; 256];
+	if unsafe { libc::gethostname(buf.as_mut_ptr() as _, buf.len()) } < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+	Ok(buf[..len].to_vec())
+}
+
+pub fn user() -> TestResult {
+	fs::write(USER_NS_FILE, b"secret")?;
+	fs::set_permissions(USER_NS_FILE, fs::Permissions::from_mode(0o600))?;
+	let name = hostname()?;
+	log!("Create user and UTS namespaces");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		user_ns_child();
+	}
+	let status = wait(pid)?;
+	fs::remove_file(USER_NS_FILE)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Check the hostname is unchanged outside of the namespace");
+	test_assert_eq!(hostname()?, name);
+	Ok(())
+}
+
+/// Creates a UTS namespace with the hostname `shared`, then notifies the parent through `ready`
+/// and waits for it to write on `done` before checking the hostname has been changed to `joined`
+/// by another process.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn setns_owner_child(ready: libc::c_int, done: libc::c_int) -> ! {
+	let status = (|| {
+		let name = b"shared";
+		unsafe {
+			if libc::unshare(libc::CLONE_NEWUTS) < 0 {
+				return 1;
+			}
+			if libc::sethostname(name.as_ptr() as _, name.len()) < 0 {
+				return 2;
+			}
+		}
+		let mut buf = [0u8];
+		unsafe {
+			if libc::write(ready, buf.as_ptr() as _, 1) != 1 {
+				return 3;
+			}
+			if libc::read(done, buf.as_mut_ptr() as _, 1) != 1 {
+				return 4;
+			}
+		}
+		if !matches!(hostname(), Ok(name) if name == b"joined") {
+			return 5;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+/// Joins the UTS namespace of the process `pid`, then changes the hostname to `joined`.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn setns_joiner_child(pid: libc::pid_t) -> ! {
+	let status = (|| {
+		let Ok(file) = fs::File::open(format!("/proc/{pid}/ns/uts")) else {
+			return 1;
+		};
+		if unsafe { libc::setns(file.as_raw_fd(), libc::CLONE_NEWUTS) } < 0 {
+			return 2;
+		}
+		if !matches!(hostname(), Ok(name) if name == b"shared") {
+			return 3;
+		}
+		let name = b"joined";
+		if unsafe { libc::sethostname(name.as_ptr() as _, name.len()) } < 0 {
+			return 4;
+		}
+		// The link of the process now points to the joined namespace
+		let Ok(own) = fs::read_link("/proc/self/ns/uts") else {
+			return 5;
+		};
+		if !matches!(fs::read_link(format!("/proc/{pid}/ns/uts")), Ok(link) if link == own) {
+			return 6;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+pub fn setns() -> TestResult {
+	let name = hostname()?;
+	let mut ready = [0; 2];
+	let mut done = [0; 2];
+	unsafe {
+		test_assert_eq!(libc::pipe(ready.as_mut_ptr()), 0);
+		test_assert_eq!(libc::pipe(done.as_mut_ptr()), 0);
+	}
+	log!("Create a UTS namespace");
+	let owner = unsafe { libc::fork() };
+	if owner < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if owner == 0 {
+		setns_owner_child(ready[1], done[0]);
+	}
+	let mut buf = [0u8];
+	let len = unsafe { libc::read(ready[0], buf.as_mut_ptr() as _, 1) };
+	log!("Join the namespace from another process");
+	let joiner = unsafe { libc::fork() };
+	if joiner < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if joiner == 0 {
+		setns_joiner_child(owner);
+	}
+	let joiner_status = wait(joiner)?;
+	log!("Check the namespaces of the parent are unchanged");
+	let own = fs::read_link("/proc/self/ns/uts")?;
+	let other = fs::read_link(format!("/proc/{owner}/ns/uts"))?;
+	unsafe {
+		libc::write(done[1], buf.as_ptr() as _, 1);
+		for fd in ready.into_iter().chain(done) {
+			libc::close(fd);
+		}
+	}
+	let owner_status = wait(owner)?;
+	test_assert_eq!(len, 1);
+	test_assert!(libc::WIFEXITED(joiner_status));
+	test_assert_eq!(libc::WEXITSTATUS(joiner_status), 0);
+	test_assert!(libc::WIFEXITED(owner_status));
+	test_assert_eq!(libc::WEXITSTATUS(owner_status), 0);
+	test_assert!(own != other);
+	test_assert_eq!(hostname()?, name);
+	log!("Join a namespace through a file that is not a namespace");
+	let file = fs::File::open("/proc/self/status")?;
+	let res = unsafe { libc::setns(file.as_raw_fd(), 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	Ok(())
+}
+
+/// Checks a chrooted process cannot create a user namespace.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn chroot_user_ns_child() -> ! {
+	let status = (|| {
+		if unsafe { libc::chroot(c"/tmp".as_ptr()) } < 0 {
+			return 1;
+		}
+		if unsafe { libc::unshare(libc::CLONE_NEWUSER) } == 0 {
+			return 2;
+		}
+		if io::Error::last_os_error().raw_os_error() != Some(libc::EPERM) {
+			return 3;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+/// Checks a process that is privileged only in a new user namespace cannot join the mount
+/// namespace referred to by `fd`, which is owned by the initial user namespace.
+///
+/// The exit status is the number of the first failed check, or `0` on success.
+fn setns_mount_child(fd: libc::c_int) -> ! {
+	let status = (|| {
+		unsafe {
+			if libc::setgid(1000) < 0 || libc::setuid(1000) < 0 {
+				return 1;
+			}
+			if libc::unshare(libc::CLONE_NEWUSER) < 0 {
+				return 2;
+			}
+		}
+		if fs::write("/proc/self/uid_map", b"0 1000 1").is_err() {
+			return 3;
+		}
+		if unsafe { libc::setns(fd, libc::CLONE_NEWNS) } == 0 {
+			return 4;
+		}
+		if io::Error::last_os_error().raw_os_error() != Some(libc::EPERM) {
+			return 5;
+		}
+		0
+	})();
+	unsafe {
+		libc::_exit(status);
+	}
+}
+
+pub fn chroot_escape() -> TestResult {
+	log!("Create a user namespace from a chrooted process");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		chroot_user_ns_child();
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Join the initial mount namespace from a new user namespace");
+	let file = fs::File::open("/proc/self/ns/mnt")?;
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		setns_mount_child(file.as_raw_fd());
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	Ok(())
+}
@@ -19,10 +19,21 @@
 //! procfs filesystem testing.
 
 use crate::{
-	test_assert_eq,
-	util::{TestError, TestResult},
+	log,
+	sched::wait,
+	test_assert, test_assert_eq,
+	util::{sigprocmask, TestError, TestResult},
+};
+use std::{
+	collections::HashMap,
+	env,
+	env::current_dir,
+	ffi::CString,
+	fs, io,
+	io::Read,
+	os::unix::{ffi::OsStrExt, fs::MetadataExt, io::AsRawFd},
+	ptr,
 };
-use std::{collections::HashMap, env, env::current_dir, fs, os::unix::ffi::OsStrExt};
 
 pub fn cwd() -> TestResult {
 	let cwd = fs::read_link("/proc/self/cwd")?;
@@ -69,3 +80,413 @@ pub fn environ() -> TestResult {
 	test_assert_eq!(args0, args1);
 	Ok(())
 }
+
+/// Checks the file `name` of a process that is not dumpable can be read by a privileged process,
+/// but not by another process of the same user.
+fn check_dumpable(name: &str) -> TestResult {
+	// The target blocks until the write end of the pipe is closed
+	let mut ready = [0; 2];
+	let mut done = [0; 2];
+	unsafe {
+		test_assert_eq!(libc::pipe(ready.as_mut_ptr()), 0);
+		test_assert_eq!(libc::pipe(done.as_mut_ptr()), 0);
+	}
+	let target = unsafe { libc::fork() };
+	if target < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if target == 0 {
+		unsafe {
+			libc::close(done[1]);
+			let mut b = 0u8;
+			let ok = libc::setuid(1000) == 0 && libc::prctl(libc::PR_SET_DUMPABLE, 0) == 0;
+			if ok {
+				libc::write(ready[1], &b as *const _ as *const _, 1);
+				libc::read(done[0], &mut b as *mut _ as *mut _, 1);
+			}
+			libc::_exit(if ok { 0 } else { 1 });
+		}
+	}
+	unsafe {
+		libc::close(ready[1]);
+		libc::close(done[0]);
+	}
+	let mut b = 0u8;
+	let len = unsafe { libc::read(ready[0], &mut b as *mut _ as *mut _, 1) };
+	let path = format!("/proc/{target}/{name}");
+	log!("Read {path} as the same user");
+	let reader = unsafe { libc::fork() };
+	if reader < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if reader == 0 {
+		let status = if unsafe { libc::setuid(1000) } < 0 {
+			1
+		} else {
+			match fs::read(&path) {
+				Err(e) if e.raw_os_error() == Some(libc::EACCES) => 0,
+				_ => 2,
+			}
+		};
+		unsafe {
+			libc::_exit(status);
+		}
+	}
+	let reader_status = wait(reader)?;
+	log!("Read {path} as a privileged user");
+	let privileged = fs::read(&path);
+	unsafe {
+		libc::close(ready[0]);
+		libc::close(done[1]);
+	}
+	let target_status = wait(target)?;
+	test_assert_eq!(len, 1);
+	test_assert!(libc::WIFEXITED(target_status));
+	test_assert_eq!(libc::WEXITSTATUS(target_status), 0);
+	test_assert!(libc::WIFEXITED(reader_status));
+	test_assert_eq!(libc::WEXITSTATUS(reader_status), 0);
+	test_assert!(privileged.is_ok());
+	Ok(())
+}
+
+pub fn environ_dumpable() -> TestResult {
+	check_dumpable("environ")
+}
+
+pub fn maps_dumpable() -> TestResult {
+	check_dumpable("maps")
+}
+
+/// Returns the number of hits and misses of the directory entries cache.
+fn dentry_cache_stats() -> Result<(u64, u64), TestError> {
+	let content = fs::read_to_string("/proc/sys/fs/dentry-cache")?;
+	let mut values = content.lines().map(|line| {
+		line.split_once(": ")
+			.and_then(|(_, val)| val.parse().ok())
+			.ok_or_else(|| TestError(format!("invalid line: {line}")))
+	});
+	let hits = values.next().transpose()?.unwrap_or_default();
+	let misses = values.next().transpose()?.unwrap_or_default();
+	Ok((hits, misses))
+}
+
+pub fn dentry_cache() -> TestResult {
+	let (hits0, misses0) = dentry_cache_stats()?;
+	// Resolving the same path twice must hit the cache at least once
+	fs::metadata("/proc/sys/fs/dentry-cache")?;
+	fs::metadata("/proc/sys/fs/dentry-cache")?;
+	let (hits1, misses1) = dentry_cache_stats()?;
+	test_assert!(hits1 > hits0);
+	test_assert!(misses1 >= misses0);
+	Ok(())
+}
+
+pub fn oom_score_adj() -> TestResult {
+	const PATH: &str = "/proc/self/oom_score_adj";
+	test_assert_eq!(fs::read_to_string(PATH)?, "0\n");
+	let score: u64 = fs::read_to_string("/proc/self/oom_score")?.trim().parse()?;
+	test_assert!(score > 0);
+	// Out of range values are rejected
+	let err = fs::write(PATH, "1001").unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	// Raising the adjustment raises the score
+	fs::write(PATH, "500")?;
+	test_assert_eq!(fs::read_to_string(PATH)?, "500\n");
+	let adjusted: u64 = fs::read_to_string("/proc/self/oom_score")?.trim().parse()?;
+	test_assert!(adjusted > score);
+	// The adjustment is inherited, and cannot be lowered without privilege
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let ok = matches!(fs::read_to_string(PATH).as_deref(), Ok("500\n"))
+			&& unsafe { libc::setuid(1000) } == 0
+			&& fs::write(PATH, "0").is_err_and(|e| e.raw_os_error() == Some(libc::EACCES))
+			&& fs::write(PATH, "1000").is_ok();
+		unsafe {
+			libc::_exit(!ok as _);
+		}
+	}
+	let status = wait(pid)?;
+	fs::write(PATH, "0")?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	Ok(())
+}
+
+pub fn status() -> TestResult {
+	let content = fs::read_to_string("/proc/self/status")?;
+	let fields: HashMap<&str, &str> = content
+		.lines()
+		.filter_map(|line| line.split_once(':'))
+		.map(|(name, val)| (name, val.trim()))
+		.collect();
+	let pid = unsafe { libc::getpid() }.to_string();
+	test_assert_eq!(fields.get("Pid").copied(), Some(pid.as_str()));
+	test_assert_eq!(fields.get("Tgid").copied(), Some(pid.as_str()));
+	test_assert!(fields.get("State").is_some_and(|s| s.starts_with('R')));
+	let uid = unsafe { libc::getuid() };
+	let uids: Vec<u32> = fields["Uid"]
+		.split_whitespace()
+		.map(str::parse)
+		.collect::<Result<_, _>>()?;
+	test_assert!(uids.len() == 4 && uids[0] == uid);
+	let rss: u64 = fields["VmRSS"]
+		.strip_suffix("kB")
+		.ok_or_else(|| TestError("no unit for VmRSS".to_owned()))?
+		.trim()
+		.parse()?;
+	test_assert!(rss > 0);
+	let threads: u64 = fields["Threads"].parse()?;
+	test_assert!(threads >= 1);
+	// A blocked signal appears in the mask
+	sigprocmask(libc::SIG_BLOCK, &[libc::SIGUSR1])?;
+	let content = fs::read_to_string("/proc/self/status")?;
+	sigprocmask(libc::SIG_UNBLOCK, &[libc::SIGUSR1])?;
+	let blocked = content
+		.lines()
+		.find_map(|line| line.strip_prefix("SigBlk:"))
+		.ok_or_else(|| TestError("no SigBlk field".to_owned()))?;
+	let blocked = u64::from_str_radix(blocked.trim(), 16)?;
+	test_assert!(blocked & (1 << (libc::SIGUSR1 - 1)) != 0);
+	Ok(())
+}
+
+/// A line of `/proc/[pid]/maps`.
+struct MapLine<'s> {
+	begin: usize,
+	end: usize,
+	perms: &'s str,
+	off: u64,
+	inode: u64,
+	path: &'s str,
+}
+
+/// Parses the content of a `maps` file.
+fn parse_maps(content: &str) -> Result<Vec<MapLine<'_>>, TestError> {
+	content
+		.lines()
+		.map(|line| {
+			let err = || TestError(format!("invalid line: {line}"));
+			let mut fields = line.splitn(6, ' ');
+			let mut field = || fields.next().ok_or_else(err);
+			let (begin, end) = field()?.split_once('-').ok_or_else(err)?;
+			let perms = field()?;
+			let off = field()?;
+			let _dev = field()?;
+			let inode = field()?;
+			let path = fields.next().unwrap_or("").trim_start();
+			Ok(MapLine {
+				begin: usize::from_str_radix(begin, 16)?,
+				end: usize::from_str_radix(end, 16)?,
+				perms,
+				off: u64::from_str_radix(off, 16)?,
+				inode: inode.parse()?,
+				path,
+			})
+		})
+		.collect()
+}
+
+pub fn maps() -> TestResult {
+	log!("Map an anonymous region and a file");
+	let anon = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			3 * 4096,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if anon == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let file = fs::File::open("/inttest")?;
+	let file_map = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			4096,
+			libc::PROT_READ,
+			libc::MAP_SHARED,
+			file.as_raw_fd(),
+			4096,
+		)
+	};
+	if file_map == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	// Allocate before reading so that the memory mappings do not change in between
+	let mut content = String::with_capacity(1 << 16);
+	let mut chunked = Vec::with_capacity(1 << 16);
+	log!("Read the whole file");
+	fs::File::open("/proc/self/maps")?.read_to_string(&mut content)?;
+	log!("Read the file in small chunks");
+	let mut maps = fs::File::open("/proc/self/maps")?;
+	let mut buf = [0u8; 7];
+	loop {
+		let len = maps.read(&mut buf)?;
+		if len == 0 {
+			break;
+		}
+		chunked.extend_from_slice(&buf[..len]);
+	}
+	test_assert_eq!(content.as_bytes(), chunked.as_slice());
+	let lines = parse_maps(&content)?;
+	unsafe {
+		libc::munmap(anon, 3 * 4096);
+		libc::munmap(file_map, 4096);
+	}
+	log!("Check mappings are sorted");
+	test_assert!(lines.windows(2).all(|w| w[0].end <= w[1].begin));
+	log!("Check the anonymous mapping");
+	let anon = lines
+		.iter()
+		.find(|l| l.begin == anon as usize)
+		.ok_or_else(|| TestError("anonymous mapping not found".to_owned()))?;
+	test_assert_eq!(anon.end - anon.begin, 3 * 4096);
+	test_assert_eq!(anon.perms, "rw-p");
+	test_assert_eq!(anon.inode, 0);
+	test_assert_eq!(anon.path, "");
+	log!("Check the file mapping");
+	let file_map = lines
+		.iter()
+		.find(|l| l.begin == file_map as usize)
+		.ok_or_else(|| TestError("file mapping not found".to_owned()))?;
+	test_assert_eq!(file_map.perms, "r--s");
+	test_assert_eq!(file_map.off, 4096);
+	test_assert_eq!(file_map.inode, file.metadata()?.ino());
+	test_assert_eq!(file_map.path, "/inttest");
+	log!("Check special mappings");
+	let local = 0u8;
+	let stack = lines
+		.iter()
+		.find(|l| l.path == "[stack]")
+		.ok_or_else(|| TestError("stack not found".to_owned()))?;
+	test_assert!((stack.begin..stack.end).contains(&(&local as *const u8 as usize)));
+	test_assert!(lines
+		.iter()
+		.any(|l| l.path == "[vdso]" && l.perms == "r-xp"));
+	test_assert!(lines
+		.iter()
+		.any(|l| l.path == "/inttest" && l.perms.contains('x')));
+	Ok(())
+}
+
+/// Returns the list of PIDs in `/proc`.
+fn list_pids() -> io::Result<Vec<libc::pid_t>> {
+	fs::read_dir("/proc")?
+		.map(|ent| Ok(ent?.file_name().to_str().and_then(|name| name.parse().ok())))
+		.filter_map(Result::transpose)
+		.collect()
+}
+
+pub fn pid_dirs() -> TestResult {
+	let self_pid = unsafe { libc::getpid() };
+	test_assert!(list_pids()?.contains(&self_pid));
+	// The child blocks until the write end of the pipe is closed
+	let mut fds = [0; 2];
+	if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::close(fds[1]);
+			let mut b = 0u8;
+			libc::read(fds[0], &mut b as *mut _ as *mut _, 1);
+			libc::_exit(0);
+		}
+	}
+	unsafe {
+		libc::close(fds[0]);
+	}
+	let path = format!("/proc/{pid}");
+	let listed = list_pids()?.contains(&pid);
+	let exists = fs::metadata(&path).is_ok_and(|m| m.is_dir());
+	unsafe {
+		libc::close(fds[1]);
+	}
+	wait(pid)?;
+	test_assert!(listed);
+	test_assert!(exists);
+	// Once reaped, the process disappears, even though its entry was cached
+	test_assert!(!list_pids()?.contains(&pid));
+	let err = fs::metadata(&path).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ENOENT));
+	Ok(())
+}
+
+/// Returns the trace of the system call `name` with the arguments starting with `args` made by the
+/// process `pid`, as logged by the kernel.
+fn find_trace<'l>(logs: &'l str, pid: libc::pid_t, name: &str, args: &str) -> Option<&'l str> {
+	let prefix = format!("[strace {pid}] {name}({args}");
+	logs.lines().find(|l| l.starts_with(&prefix))
+}
+
+pub fn strace() -> TestResult {
+	const PATH: &str = "/tmp/strace";
+	if fs::metadata("/proc/self/strace").is_err() {
+		log!("The kernel is built without the strace feature, skipping");
+		return Ok(());
+	}
+	fs::write(PATH, b"hello")?;
+	let path = CString::new(PATH).unwrap();
+	let pid = unsafe { libc::getpid() };
+	log!("Trace open, read and close");
+	fs::write("/proc/self/strace", b"1")?;
+	let (fd, len) = unsafe {
+		let fd = libc::syscall(libc::SYS_open, path.as_ptr(), libc::O_RDONLY, 0) as libc::c_int;
+		let mut buf = [0u8; 16];
+		let len = libc::read(fd, buf.as_mut_ptr() as _, buf.len());
+		libc::close(fd);
+		(fd, len)
+	};
+	fs::write("/proc/self/strace", b"0")?;
+	test_assert!(fd >= 0);
+	test_assert_eq!(len, 5);
+	let logs = fs::read("/dev/kmsg")?;
+	let logs = String::from_utf8_lossy(&logs);
+	let open = find_trace(&logs, pid, "open", "")
+		.filter(|l| l.contains(PATH))
+		.ok_or_else(|| TestError("`open` not traced".to_owned()))?;
+	test_assert!(open.ends_with(&format!(", 0, 0) -> {fd}")));
+	let read = find_trace(&logs, pid, "read", &format!("{fd}, "))
+		.ok_or_else(|| TestError("`read` not traced".to_owned()))?;
+	test_assert!(read.ends_with(", 16) -> 5"));
+	let close = find_trace(&logs, pid, "close", &format!("{fd})"))
+		.ok_or_else(|| TestError("`close` not traced".to_owned()))?;
+	test_assert!(close.ends_with(" -> 0"));
+	log!("Tracing is disabled");
+	let traced = fs::read_to_string("/proc/self/strace")?;
+	test_assert_eq!(traced, "0\n");
+	unsafe {
+		libc::close(fd);
+	}
+	let logs = fs::read("/dev/kmsg")?;
+	let logs = String::from_utf8_lossy(&logs);
+	test_assert_eq!(
+		logs.lines()
+			.filter(|l| l.starts_with(&format!("[strace {pid}] close({fd})")))
+			.count(),
+		1
+	);
+	fs::remove_file(PATH)?;
+	Ok(())
+}
+
+pub fn kernel_tests() -> TestResult {
+	let report = fs::read_to_string("/proc/kernel_tests")?;
+	log!("{report}");
+	let summary = report
+		.lines()
+		.last()
+		.ok_or_else(|| TestError("empty report".to_owned()))?;
+	test_assert!(summary.ends_with(" 0 failed"));
+	Ok(())
+}
@@ -0,0 +1,654 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Scheduler testing.
+
+use crate::{
+	log, test_assert, test_assert_eq,
+	util::{gettid, signal, tgkill, TestResult},
+};
+use std::{
+	arch::asm,
+	hint, io, ptr,
+	sync::{
+		atomic::{AtomicBool, AtomicI32, AtomicU64, AtomicU8, AtomicUsize, Ordering::Relaxed},
+		Arc,
+	},
+	thread,
+	time::{Duration, Instant},
+};
+
+/// Sets the scheduling policy and priority of the process `pid`.
+///
+/// The system call is used directly since the libc does not implement it.
+fn sched_setscheduler(pid: libc::pid_t, policy: libc::c_int, prio: libc::c_int) -> io::Result<()> {
+	// The structure only contains the priority
+	let res = unsafe { libc::syscall(libc::SYS_sched_setscheduler, pid, policy, &prio) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+/// Returns the scheduling policy of the process `pid`.
+fn sched_getscheduler(pid: libc::pid_t) -> io::Result<libc::c_int> {
+	let res = unsafe { libc::syscall(libc::SYS_sched_getscheduler, pid) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(res as _)
+}
+
+/// Sets the CPU affinity mask of the process `pid`, passing a buffer of `len` bytes.
+fn sched_setaffinity(pid: libc::pid_t, len: usize, mask: u64) -> io::Result<()> {
+	let buf = [mask, 0];
+	let res = unsafe { libc::syscall(libc::SYS_sched_setaffinity, pid, len, buf.as_ptr()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+/// Returns the CPU affinity mask of the process `pid`, passing a buffer of `len` bytes, along
+/// with the number of bytes written.
+///
+/// The system call is used directly since the libc hides the number of bytes written.
+fn sched_getaffinity(pid: libc::pid_t, len: usize) -> io::Result<(u64, usize)> {
+	let mut buf = [0u64; 2];
+	let res = unsafe { libc::syscall(libc::SYS_sched_getaffinity, pid, len, buf.as_mut_ptr()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok((buf[0], res as _))
+}
+
+/// Waits for the child process `pid` to exit and returns its wait status.
+pub fn wait(pid: libc::pid_t) -> io::Result<libc::c_int> {
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(status)
+}
+
+pub fn policy() -> TestResult {
+	log!("Check the default policy");
+	test_assert_eq!(sched_getscheduler(0)?, libc::SCHED_OTHER);
+	log!("Check priority bounds");
+	let max = unsafe { libc::sched_get_priority_max(libc::SCHED_FIFO) };
+	let min = unsafe { libc::sched_get_priority_min(libc::SCHED_FIFO) };
+	test_assert_eq!((min, max), (1, 99));
+	log!("Set an invalid priority");
+	let err = sched_setscheduler(0, libc::SCHED_FIFO, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	let err = sched_setscheduler(0, libc::SCHED_OTHER, 1).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Set a real-time policy without privilege");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let ok = unsafe { libc::setuid(1000) } == 0
+			&& sched_setscheduler(0, libc::SCHED_RR, 1)
+				.is_err_and(|e| e.raw_os_error() == Some(libc::EPERM));
+		unsafe {
+			libc::_exit(!ok as _);
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	Ok(())
+}
+
+pub fn fifo() -> TestResult {
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			4096,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let counter = unsafe { &*(ptr as *const AtomicU64) };
+	log!("Spawn a busy peer");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		loop {
+			counter.fetch_add(1, Relaxed);
+		}
+	}
+	// Let the peer run
+	while counter.load(Relaxed) == 0 {
+		unsafe {
+			libc::sched_yield();
+		}
+	}
+	log!("Monopolize the CPU with SCHED_FIFO");
+	sched_setscheduler(0, libc::SCHED_FIFO, 50)?;
+	let policy = sched_getscheduler(0)?;
+	let before = counter.load(Relaxed);
+	let start = Instant::now();
+	while start.elapsed() < Duration::from_millis(100) {}
+	let after = counter.load(Relaxed);
+	sched_setscheduler(0, libc::SCHED_OTHER, 0)?;
+	unsafe {
+		libc::kill(pid, libc::SIGKILL);
+	}
+	wait(pid)?;
+	unsafe {
+		libc::munmap(ptr, 4096);
+	}
+	test_assert_eq!(policy, libc::SCHED_FIFO);
+	test_assert_eq!(before, after);
+	Ok(())
+}
+
+pub fn affinity() -> TestResult {
+	log!("Get the default mask");
+	let (mask, len) = sched_getaffinity(0, 16)?;
+	test_assert_eq!(len, 8);
+	test_assert!(mask & 1 != 0);
+	log!("Restrict to the first CPU");
+	sched_setaffinity(0, 8, 1)?;
+	test_assert_eq!(sched_getaffinity(0, 8)?.0, 1);
+	log!("Check the mask is inherited");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let ok = matches!(sched_getaffinity(0, 8), Ok((1, _)));
+		unsafe {
+			libc::_exit(if ok { 0 } else { 1 });
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Check invalid masks");
+	let err = sched_setaffinity(0, 8, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	let err = sched_setaffinity(0, 8, 1 << 63).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Check a buffer too small to hold the mask");
+	let err = sched_getaffinity(0, 4).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Restore the mask");
+	sched_setaffinity(0, 8, mask)?;
+	test_assert_eq!(sched_getaffinity(0, 8)?.0, mask);
+	Ok(())
+}
+
+/// The number of times each process yields in [`yield_alternate`].
+const YIELD_COUNT: usize = 16;
+
+/// The order in which processes ran in [`yield_alternate`], shared between processes.
+struct YieldLog {
+	/// Tells whether the processes can start.
+	start: AtomicBool,
+	/// The number of entries.
+	len: AtomicUsize,
+	/// The ID of the process for each turn.
+	entries: [AtomicU8; YIELD_COUNT * 2],
+}
+
+pub fn yield_alternate() -> TestResult {
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			4096,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let log = unsafe { &*(ptr as *const YieldLog) };
+	// Use SCHED_FIFO so that the processes switch only when yielding. The children inherit it
+	sched_setscheduler(0, libc::SCHED_FIFO, 10)?;
+	log!("Spawn two busy processes");
+	let mut pids = [0; 2];
+	for (id, pid) in pids.iter_mut().enumerate() {
+		*pid = unsafe { libc::fork() };
+		if *pid < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		if *pid == 0 {
+			while !log.start.load(Relaxed) {
+				unsafe {
+					libc::sched_yield();
+				}
+			}
+			for _ in 0..YIELD_COUNT {
+				let i = log.len.fetch_add(1, Relaxed);
+				log.entries[i].store(id as _, Relaxed);
+				unsafe {
+					libc::sched_yield();
+				}
+			}
+			unsafe {
+				libc::_exit(0);
+			}
+		}
+	}
+	log.start.store(true, Relaxed);
+	for pid in pids {
+		wait(pid)?;
+	}
+	sched_setscheduler(0, libc::SCHED_OTHER, 0)?;
+	log!("Check the processes alternated");
+	let entries: Vec<u8> = log.entries.iter().map(|e| e.load(Relaxed)).collect();
+	let len = log.len.load(Relaxed);
+	unsafe {
+		libc::munmap(ptr, 4096);
+	}
+	test_assert_eq!(len, YIELD_COUNT * 2);
+	test_assert!(entries.windows(2).all(|w| w[0] != w[1]));
+	log!("Yield while being the only runnable process");
+	test_assert_eq!(unsafe { libc::sched_yield() }, 0);
+	Ok(())
+}
+
+/// Returns the nice value of the process `pid`.
+fn getpriority(pid: libc::pid_t) -> io::Result<libc::c_int> {
+	// The system call returns the nice value in the range `[1, 40]`
+	let res = unsafe { libc::syscall(libc::SYS_getpriority, libc::PRIO_PROCESS, pid) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(20 - res as libc::c_int)
+}
+
+/// Sets the nice value of the process `pid`.
+fn setpriority(pid: libc::pid_t, nice: libc::c_int) -> io::Result<()> {
+	let res = unsafe { libc::setpriority(libc::PRIO_PROCESS, pid as _, nice) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn nice() -> TestResult {
+	log!("Check the default nice value");
+	test_assert_eq!(getpriority(0)?, 0);
+	log!("Check nice values are clamped");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let status = (|| {
+			if setpriority(0, -100).is_err() || !matches!(getpriority(0), Ok(-20)) {
+				return 1;
+			}
+			if setpriority(0, 100).is_err() || !matches!(getpriority(0), Ok(19)) {
+				return 2;
+			}
+			// Only privileged processes can lower the nice value
+			if unsafe { libc::setuid(1000) } < 0 {
+				return 3;
+			}
+			if !setpriority(0, 10).is_err_and(|e| e.raw_os_error() == Some(libc::EACCES)) {
+				return 4;
+			}
+			0
+		})();
+		unsafe {
+			libc::_exit(status);
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Check an invalid target");
+	let err = setpriority(-1, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+	let res = unsafe { libc::syscall(libc::SYS_getpriority, libc::PRIO_PGRP, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	Ok(())
+}
+
+pub fn nice_share() -> TestResult {
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			4096,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let counters = unsafe { &*(ptr as *const [AtomicU64; 2]) };
+	log!("Spawn two busy processes with different nice values");
+	let mut pids = [0; 2];
+	for (i, pid) in pids.iter_mut().enumerate() {
+		*pid = unsafe { libc::fork() };
+		if *pid < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		if *pid == 0 {
+			let _ = setpriority(0, i as libc::c_int * 10);
+			loop {
+				counters[i].fetch_add(1, Relaxed);
+			}
+		}
+	}
+	// Let the processes run while sleeping
+	std::thread::sleep(Duration::from_secs(2));
+	for pid in pids {
+		unsafe {
+			libc::kill(pid, libc::SIGKILL);
+		}
+		wait(pid)?;
+	}
+	let [high, low] = counters.each_ref().map(|c| c.load(Relaxed));
+	unsafe {
+		libc::munmap(ptr, 4096);
+	}
+	log!("Check the process with the lowest nice value ran more");
+	test_assert!(low > 0);
+	test_assert!(high > low * 3 / 2);
+	Ok(())
+}
+
+/// `which` value for `ioprio_get` and `ioprio_set`: the target is a process.
+const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+/// Returns the I/O priority value for the class `class` and the level `level`.
+const fn ioprio_value(class: libc::c_int, level: libc::c_int) -> libc::c_int {
+	(class << 13) | level
+}
+
+/// Returns the I/O priority of the process `pid`.
+fn ioprio_get(pid: libc::pid_t) -> io::Result<libc::c_int> {
+	let res = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(res as _)
+}
+
+/// Sets the I/O priority of the process `pid`.
+fn ioprio_set(pid: libc::pid_t, ioprio: libc::c_int) -> io::Result<()> {
+	let res = unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, pid, ioprio) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn ioprio() -> TestResult {
+	log!("Check the default I/O priority");
+	test_assert_eq!(ioprio_get(0)?, 0);
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		let status = (|| {
+			let be = ioprio_value(2, 3);
+			if ioprio_set(0, be).is_err() || !matches!(ioprio_get(0), Ok(p) if p == be) {
+				return 1;
+			}
+			// Invalid class and level
+			for ioprio in [ioprio_value(4, 0), ioprio_value(2, 8)] {
+				if !ioprio_set(0, ioprio).is_err_and(|e| e.raw_os_error() == Some(libc::EINVAL)) {
+					return 2;
+				}
+			}
+			// The priority is inherited
+			let pid = unsafe { libc::fork() };
+			if pid == 0 {
+				let status = if matches!(ioprio_get(0), Ok(p) if p == be) {
+					0
+				} else {
+					1
+				};
+				unsafe {
+					libc::_exit(status);
+				}
+			}
+			if !matches!(wait(pid), Ok(s) if libc::WIFEXITED(s) && libc::WEXITSTATUS(s) == 0) {
+				return 3;
+			}
+			// Only privileged processes can use the real-time class
+			if unsafe { libc::setuid(1000) } < 0 {
+				return 4;
+			}
+			let rt = ioprio_value(1, 0);
+			if !ioprio_set(0, rt).is_err_and(|e| e.raw_os_error() == Some(libc::EPERM)) {
+				return 5;
+			}
+			if ioprio_set(0, ioprio_value(3, 0)).is_err() {
+				return 6;
+			}
+			0
+		})();
+		unsafe {
+			libc::_exit(status);
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Check an invalid target");
+	let err = ioprio_set(-1, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+	let res = unsafe { libc::syscall(libc::SYS_ioprio_get, 2, 0) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::EINVAL)
+	);
+	Ok(())
+}
+
+/// The TID of the thread which received the last `SIGUSR1`.
+static SIGNALED_TID: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn tid_handler(_: libc::c_int) {
+	SIGNALED_TID.store(gettid(), Relaxed);
+}
+
+pub fn threads() -> TestResult {
+	let pid = unsafe { libc::getpid() };
+	let tid = gettid();
+	log!("Spawn a thread");
+	let value = Arc::new(AtomicUsize::new(0));
+	let handle = thread::spawn({
+		let value = value.clone();
+		move || -> io::Result<_> {
+			value.store(1, Relaxed);
+			let mut fds = [0; 2];
+			if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+				return Err(io::Error::last_os_error());
+			}
+			Ok((unsafe { libc::getpid() }, gettid(), fds))
+		}
+	});
+	let (thread_pid, thread_tid, fds) = handle.join().unwrap()?;
+	log!("Check the thread shares the PID and memory, but not the TID");
+	test_assert_eq!(thread_pid, pid);
+	test_assert!(thread_tid != tid);
+	test_assert_eq!(value.load(Relaxed), 1);
+	log!("Check the thread shares file descriptors");
+	let mut buf = [0u8; 4];
+	unsafe {
+		test_assert_eq!(libc::write(fds[1], b"abcd".as_ptr() as _, 4), 4);
+		test_assert_eq!(libc::read(fds[0], buf.as_mut_ptr() as _, 4), 4);
+		libc::close(fds[0]);
+		libc::close(fds[1]);
+	}
+	test_assert_eq!(&buf, b"abcd");
+	log!("Send a signal to a thread with tgkill");
+	signal(libc::SIGUSR1, tid_handler as usize)?;
+	let thread_tid = Arc::new(AtomicI32::new(0));
+	let handle = thread::spawn({
+		let thread_tid = thread_tid.clone();
+		move || {
+			thread_tid.store(gettid(), Relaxed);
+			// Signals do not interrupt sleeping, hence spinning
+			while SIGNALED_TID.load(Relaxed) == 0 {
+				hint::spin_loop();
+			}
+		}
+	});
+	let target = loop {
+		let tid = thread_tid.load(Relaxed);
+		if tid != 0 {
+			break tid;
+		}
+		thread::yield_now();
+	};
+	tgkill(pid, target, libc::SIGUSR1)?;
+	handle.join().unwrap();
+	signal(libc::SIGUSR1, libc::SIG_DFL)?;
+	test_assert_eq!(SIGNALED_TID.load(Relaxed), target);
+	log!("Check tgkill fails with another thread group");
+	let ppid = unsafe { libc::getppid() };
+	let err = tgkill(ppid, tid, libc::SIGUSR1).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::ESRCH));
+	Ok(())
+}
+
+pub fn exit_group() -> TestResult {
+	log!("Exit a process from a thread while the main thread waits");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		thread::spawn(|| unsafe { libc::_exit(42) });
+		loop {
+			hint::spin_loop();
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 42);
+	log!("Kill a process with a signal sent to one of its threads");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		thread::spawn(|| unsafe {
+			libc::syscall(libc::SYS_tgkill, libc::getpid(), gettid(), libc::SIGTERM);
+		});
+		loop {
+			hint::spin_loop();
+		}
+	}
+	let status = wait(pid)?;
+	test_assert!(libc::WIFSIGNALED(status));
+	test_assert_eq!(libc::WTERMSIG(status), libc::SIGTERM);
+	Ok(())
+}
+
+/// Yields the CPU while `val` is held in the `xmm0` register, then returns the value of the
+/// register.
+fn yield_with_xmm0(val: u64) -> u64 {
+	let out: u64;
+	unsafe {
+		asm!(
+			"movq xmm0, {val}",
+			"syscall",
+			"movq {out}, xmm0",
+			val = in(reg) val,
+			out = out(reg) out,
+			inout("rax") libc::SYS_sched_yield => _,
+			out("rcx") _,
+			out("r11") _,
+			out("xmm0") _,
+		);
+	}
+	out
+}
+
+pub fn fpu() -> TestResult {
+	const ITERATIONS: usize = 1000;
+	log!("Spawn processes using the FPU and a process not using it");
+	let mut pids = [0; 3];
+	for (id, pid) in pids.iter_mut().enumerate() {
+		*pid = unsafe { libc::fork() };
+		if *pid < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		if *pid > 0 {
+			continue;
+		}
+		// The last process only performs integer operations
+		if id == 2 {
+			for _ in 0..ITERATIONS {
+				unsafe {
+					libc::sched_yield();
+				}
+			}
+			unsafe {
+				libc::_exit(0);
+			}
+		}
+		let mut acc = 0.0f64;
+		for i in 0..ITERATIONS {
+			let val = ((id as u64 + 1) << 32) | i as u64;
+			if yield_with_xmm0(val) != val {
+				unsafe {
+					libc::_exit(1);
+				}
+			}
+			acc = hint::black_box(acc + (id + 1) as f64 * 0.5);
+		}
+		let code = if acc == (id + 1) as f64 * 0.5 * ITERATIONS as f64 {
+			0
+		} else {
+			1
+		};
+		unsafe {
+			libc::_exit(code);
+		}
+	}
+	log!("Check the FPU states survived context switches");
+	for pid in pids {
+		let status = wait(pid)?;
+		test_assert!(libc::WIFEXITED(status));
+		test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	}
+	Ok(())
+}
@@ -19,14 +19,19 @@
 //! Signals testing.
 
 use crate::{
-	log,
-	util::{kill, signal, TestResult},
+	log, test_assert, test_assert_eq,
+	util::{kill, sigaction, signal, sigprocmask, TestResult},
+};
+use libc::{
+	getpid, siginfo_t, SA_NODEFER, SA_RESTART, SA_SIGINFO, SIGBUS, SIGFPE, SIGILL, SIGINT,
+	SIGUSR1, SIG_BLOCK, SIG_DFL, SIG_UNBLOCK,
 };
-use libc::{getpid, SIGINT, SIG_DFL};
 use std::{
-	ffi::c_int,
+	arch::asm,
+	ffi::{c_int, c_void},
+	io, ptr,
 	sync::atomic::{
-		AtomicBool,
+		AtomicBool, AtomicI32, AtomicUsize,
 		Ordering::{Acquire, Release},
 	},
 };
@@ -54,3 +59,434 @@ pub fn handler() -> TestResult {
 
 	Ok(())
 }
+
+/// The number of times each signal has been received.
+static COUNTS: [AtomicUsize; 65] = [const { AtomicUsize::new(0) }; 65];
+/// The last signal received.
+static LAST: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn counting_handler(sig: c_int) {
+	COUNTS[sig as usize].fetch_add(1, Release);
+	LAST.store(sig as _, Release);
+}
+
+pub fn masking() -> TestResult {
+	log!("Register signal handler");
+	signal(SIGUSR1, counting_handler as usize)?;
+
+	log!("Block signal");
+	sigprocmask(SIG_BLOCK, &[SIGUSR1])?;
+	log!("Kill self twice");
+	unsafe {
+		kill(getpid(), SIGUSR1)?;
+		kill(getpid(), SIGUSR1)?;
+	}
+	test_assert_eq!(COUNTS[SIGUSR1 as usize].load(Acquire), 0);
+	log!("Unblock signal");
+	sigprocmask(SIG_UNBLOCK, &[SIGUSR1])?;
+	// Standard signals are merged
+	test_assert_eq!(COUNTS[SIGUSR1 as usize].load(Acquire), 1);
+
+	log!("Cleanup");
+	signal(SIGUSR1, SIG_DFL)?;
+	COUNTS[SIGUSR1 as usize].store(0, Release);
+
+	Ok(())
+}
+
+/// The number of nested executions of [`nesting_handler`].
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+/// The maximum value reached by [`DEPTH`].
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn nesting_handler(sig: c_int) {
+	let depth = DEPTH.fetch_add(1, Release) + 1;
+	MAX_DEPTH.fetch_max(depth, Release);
+	COUNTS[sig as usize].fetch_add(1, Release);
+	// Raise the signal again from the first execution only
+	if depth == 1 && COUNTS[sig as usize].load(Acquire) == 1 {
+		let _ = kill(unsafe { getpid() }, sig);
+	}
+	DEPTH.fetch_sub(1, Release);
+}
+
+pub fn nesting() -> TestResult {
+	for (flags, max_depth) in [(0, 1), (SA_NODEFER, 2)] {
+		log!("Register signal handler (flags: {flags:x})");
+		sigaction(SIGUSR1, nesting_handler as usize, flags)?;
+
+		log!("Kill self");
+		kill(unsafe { getpid() }, SIGUSR1)?;
+		// Without `SA_NODEFER`, the signal is blocked until the handler returns
+		test_assert_eq!(COUNTS[SIGUSR1 as usize].load(Acquire), 2);
+		test_assert_eq!(MAX_DEPTH.load(Acquire), max_depth);
+
+		log!("Cleanup");
+		signal(SIGUSR1, SIG_DFL)?;
+		COUNTS[SIGUSR1 as usize].store(0, Release);
+		MAX_DEPTH.store(0, Release);
+	}
+	Ok(())
+}
+
+pub fn realtime() -> TestResult {
+	let rtmin = libc::SIGRTMIN();
+	let rtmax = libc::SIGRTMAX();
+	test_assert_eq!(rtmax, 64);
+
+	log!("Register signal handlers");
+	signal(rtmin, counting_handler as usize)?;
+	signal(rtmax, counting_handler as usize)?;
+
+	log!("Block signals");
+	sigprocmask(SIG_BLOCK, &[rtmin, rtmax])?;
+	log!("Queue signals");
+	unsafe {
+		kill(getpid(), rtmax)?;
+		for _ in 0..3 {
+			kill(getpid(), rtmin)?;
+		}
+	}
+	log!("Unblock signals");
+	sigprocmask(SIG_UNBLOCK, &[rtmin, rtmax])?;
+	// Real-time signals are queued, and delivered lowest number first
+	test_assert_eq!(COUNTS[rtmin as usize].load(Acquire), 3);
+	test_assert_eq!(COUNTS[rtmax as usize].load(Acquire), 1);
+	test_assert_eq!(LAST.load(Acquire), rtmax as usize);
+
+	log!("Cleanup");
+	signal(rtmin, SIG_DFL)?;
+	signal(rtmax, SIG_DFL)?;
+	COUNTS[rtmin as usize].store(0, Release);
+	COUNTS[rtmax as usize].store(0, Release);
+
+	Ok(())
+}
+
+/// `si_code` value for signals sent by `kill`.
+const SI_USER: c_int = 0;
+/// `si_code` value for illegal opcodes.
+const ILL_ILLOPC: c_int = 1;
+/// `si_code` value for integer divisions by zero.
+const FPE_INTDIV: c_int = 1;
+/// `si_code` value for integer overflows.
+const FPE_INTOVF: c_int = 2;
+/// `si_code` value for invalid address alignments.
+const BUS_ADRALN: c_int = 1;
+
+/// The signal number, code and sender PID of the last signal received with information.
+static INFO: [AtomicI32; 3] = [const { AtomicI32::new(0) }; 3];
+
+extern "C" fn info_handler(sig: c_int, info: *mut siginfo_t, _: *mut c_void) {
+	let info = unsafe { &*info };
+	assert_eq!(sig, info.si_signo);
+	INFO[0].store(info.si_signo, Release);
+	INFO[1].store(info.si_code, Release);
+	INFO[2].store(unsafe { info.si_pid() }, Release);
+}
+
+pub fn siginfo() -> TestResult {
+	log!("Register signal handler");
+	sigaction(SIGUSR1, info_handler as usize, SA_SIGINFO)?;
+
+	log!("Kill self");
+	let pid = unsafe { getpid() };
+	kill(pid, SIGUSR1)?;
+	test_assert_eq!(INFO[0].load(Acquire), SIGUSR1);
+	test_assert_eq!(INFO[1].load(Acquire), SI_USER);
+	test_assert_eq!(INFO[2].load(Acquire), pid);
+
+	log!("Cleanup");
+	signal(SIGUSR1, SIG_DFL)?;
+
+	Ok(())
+}
+
+/// The signal number, code and fault address of the last fault.
+static FAULT: [AtomicUsize; 3] = [const { AtomicUsize::new(0) }; 3];
+
+/// Records the fault, then skips the faulting instruction.
+///
+/// The faulting instructions used by tests are all 2 bytes long.
+extern "C" fn fault_handler(_: c_int, info: *mut siginfo_t, ctx: *mut c_void) {
+	let info = unsafe { &*info };
+	FAULT[0].store(info.si_signo as _, Release);
+	FAULT[1].store(info.si_code as _, Release);
+	FAULT[2].store(unsafe { info.si_addr() } as _, Release);
+	let ctx = unsafe { &mut *(ctx as *mut libc::ucontext_t) };
+	ctx.uc_mcontext.gregs[libc::REG_RIP as usize] += 2;
+}
+
+pub fn illegal_instruction() -> TestResult {
+	log!("Register signal handler");
+	sigaction(SIGILL, fault_handler as usize, SA_SIGINFO)?;
+
+	log!("Execute illegal instruction");
+	let addr: usize;
+	unsafe {
+		asm!("lea {addr}, [rip + 2f]", "2:", "ud2", addr = out(reg) addr);
+	}
+	test_assert_eq!(FAULT[0].load(Acquire), SIGILL as usize);
+	test_assert_eq!(FAULT[1].load(Acquire), ILL_ILLOPC as usize);
+	test_assert_eq!(FAULT[2].load(Acquire), addr);
+
+	log!("Cleanup");
+	signal(SIGILL, SIG_DFL)?;
+
+	Ok(())
+}
+
+pub fn divide_error() -> TestResult {
+	log!("Register signal handler");
+	sigaction(SIGFPE, fault_handler as usize, SA_SIGINFO)?;
+
+	log!("Divide by zero");
+	let addr: usize;
+	unsafe {
+		asm!(
+			"lea {addr}, [rip + 2f]",
+			"2:",
+			"div ecx",
+			addr = out(reg) addr,
+			inout("eax") 1 => _,
+			inout("edx") 0 => _,
+			in("ecx") 0,
+		);
+	}
+	test_assert_eq!(FAULT[0].load(Acquire), SIGFPE as usize);
+	test_assert_eq!(FAULT[1].load(Acquire), FPE_INTDIV as usize);
+	test_assert_eq!(FAULT[2].load(Acquire), addr);
+
+	log!("Overflow the quotient");
+	let addr: usize;
+	unsafe {
+		asm!(
+			"lea {addr}, [rip + 2f]",
+			"2:",
+			"idiv ecx",
+			addr = out(reg) addr,
+			inout("eax") i32::MIN => _,
+			inout("edx") -1 => _,
+			in("ecx") -1,
+		);
+	}
+	test_assert_eq!(FAULT[0].load(Acquire), SIGFPE as usize);
+	test_assert_eq!(FAULT[1].load(Acquire), FPE_INTOVF as usize);
+	test_assert_eq!(FAULT[2].load(Acquire), addr);
+
+	log!("Cleanup");
+	signal(SIGFPE, SIG_DFL)?;
+
+	Ok(())
+}
+
+pub fn alignment_check() -> TestResult {
+	log!("Register signal handler");
+	sigaction(SIGBUS, fault_handler as usize, SA_SIGINFO)?;
+
+	log!("Misaligned access");
+	let buf = [0u64; 2];
+	let mut addr = 0;
+	unsafe {
+		// Nothing else must be done while alignment checks are enabled
+		let res = libc::prctl(libc::PR_SET_UNALIGN, libc::PR_UNALIGN_SIGBUS);
+		if res == 0 {
+			asm!(
+				"lea {addr}, [rip + 2f]",
+				"2:",
+				"mov eax, [rcx]",
+				addr = inout(reg) addr,
+				in("rcx") (buf.as_ptr() as *const u8).add(1),
+				out("eax") _,
+			);
+		}
+		libc::prctl(libc::PR_SET_UNALIGN, libc::PR_UNALIGN_NOPRINT);
+		if res != 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+	}
+	test_assert_eq!(FAULT[0].load(Acquire), SIGBUS as usize);
+	test_assert_eq!(FAULT[1].load(Acquire), BUS_ADRALN as usize);
+	test_assert_eq!(FAULT[2].load(Acquire), addr);
+
+	log!("Cleanup");
+	signal(SIGBUS, SIG_DFL)?;
+
+	Ok(())
+}
+
+extern "C" fn clobber_handler(_: c_int, _: *mut siginfo_t, ctx: *mut c_void) {
+	// Clobber registers that are not preserved across function calls
+	unsafe {
+		asm!(
+			"mov r8, 0", "mov r9, 0", "mov r10, 0",
+			out("r8") _, out("r9") _, out("r10") _,
+		);
+	}
+	// Attempt to gain I/O privileges
+	let ctx = unsafe { &mut *(ctx as *mut libc::ucontext_t) };
+	ctx.uc_mcontext.gregs[libc::REG_EFL as usize] |= 0x3000;
+}
+
+pub fn sigreturn() -> TestResult {
+	log!("Register signal handler");
+	sigaction(SIGUSR1, clobber_handler as usize, SA_SIGINFO)?;
+
+	log!("Kill self");
+	let (r8, r9, r10): (u64, u64, u64);
+	let flags: u64;
+	unsafe {
+		asm!(
+			"mov r8, 0x1111",
+			"mov r9, 0x2222",
+			"mov r10, 0x3333",
+			"syscall",
+			"pushfq",
+			"pop {flags}",
+			flags = out(reg) flags,
+			inout("rax") libc::SYS_kill => _,
+			in("rdi") getpid(),
+			in("rsi") SIGUSR1,
+			out("r8") r8,
+			out("r9") r9,
+			out("r10") r10,
+			out("rcx") _,
+			out("r11") _,
+		);
+	}
+	test_assert_eq!((r8, r9, r10), (0x1111, 0x2222, 0x3333));
+	// IOPL must not have been restored from the context
+	test_assert_eq!(flags & 0x3000, 0);
+
+	log!("Cleanup");
+	signal(SIGUSR1, SIG_DFL)?;
+
+	Ok(())
+}
+
+pub fn restart() -> TestResult {
+	for (flags, restarted) in [(SA_RESTART, true), (0, false)] {
+		log!("Register signal handler (flags: {flags:x})");
+		sigaction(SIGUSR1, counting_handler as usize, flags)?;
+
+		let mut fds = [0; 2];
+		if unsafe { libc::pipe(fds.as_mut_ptr()) } < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		log!("Interrupt a blocking read");
+		let pid = unsafe { libc::fork() };
+		if pid < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		if pid == 0 {
+			unsafe {
+				libc::usleep(50000);
+				libc::kill(libc::getppid(), SIGUSR1);
+				libc::usleep(50000);
+				libc::write(fds[1], b"x".as_ptr() as _, 1);
+				libc::_exit(0);
+			}
+		}
+		let mut buf = [0u8; 1];
+		let res = unsafe { libc::read(fds[0], buf.as_mut_ptr() as _, buf.len()) };
+		let err = io::Error::last_os_error();
+		test_assert_eq!(COUNTS[SIGUSR1 as usize].load(Acquire), 1);
+		if restarted {
+			// The read resumed after the handler and returned the data written afterwards
+			test_assert_eq!(res, 1);
+			test_assert_eq!(buf[0], b'x');
+		} else {
+			test_assert_eq!(res, -1);
+			test_assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+		}
+
+		log!("Cleanup");
+		unsafe {
+			libc::waitpid(pid, ptr::null_mut(), 0);
+			libc::close(fds[0]);
+			libc::close(fds[1]);
+		}
+		signal(SIGUSR1, SIG_DFL)?;
+		COUNTS[SIGUSR1 as usize].store(0, Release);
+	}
+	Ok(())
+}
+
+/// Forks, runs `f` in the child and waits for it, returning its status.
+fn fork_wait(f: fn() -> c_int) -> io::Result<c_int> {
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::_exit(f());
+		}
+	}
+	let mut status = 0;
+	if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(status)
+}
+
+pub fn tty() -> TestResult {
+	const TIOCSTI: libc::c_ulong = 0x5412;
+	let fd = libc::STDIN_FILENO;
+
+	log!("Check the controlling terminal");
+	test_assert_eq!(unsafe { libc::tcgetpgrp(fd) }, unsafe { libc::getpgrp() });
+	test_assert_eq!(unsafe { libc::tcgetsid(fd) }, unsafe { libc::getsid(0) });
+
+	log!("Interrupt the foreground process group");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			libc::setpgid(0, 0);
+			loop {
+				libc::pause();
+			}
+		}
+	}
+	signal(libc::SIGTTOU, libc::SIG_IGN)?;
+	unsafe {
+		libc::setpgid(pid, pid);
+		if libc::tcsetpgrp(fd, pid) < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+		let c = 0x03u8;
+		if libc::ioctl(fd, TIOCSTI as _, &c) < 0 {
+			return Err(io::Error::last_os_error().into());
+		}
+	}
+	let mut status = 0;
+	if unsafe { libc::waitpid(pid, &mut status, 0) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	unsafe {
+		libc::tcsetpgrp(fd, libc::getpgrp());
+	}
+	signal(libc::SIGTTOU, SIG_DFL)?;
+	test_assert!(libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == SIGINT);
+
+	log!("Create a session");
+	// A process group leader cannot create a session
+	test_assert!(unsafe { libc::setsid() } < 0);
+	let status = fork_wait(|| unsafe {
+		let pid = getpid();
+		if libc::setsid() != pid || libc::getsid(0) != pid {
+			return 1;
+		}
+		// The TTY is not the controlling terminal of the new session
+		if libc::tcgetpgrp(libc::STDIN_FILENO) >= 0 {
+			return 2;
+		}
+		0
+	})?;
+	test_assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+
+	Ok(())
+}
@@ -0,0 +1,70 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! sysfs filesystem testing.
+
+use crate::{
+	test_assert, test_assert_eq,
+	util::{mount, umount, TestResult},
+};
+use std::{fs, path::Path, process, ptr};
+
+pub fn version() -> TestResult {
+	let version = fs::read_to_string("/sys/kernel/version")?;
+	test_assert!(version.ends_with('\n'));
+	test_assert!(!version.trim().is_empty());
+	Ok(())
+}
+
+pub fn block() -> TestResult {
+	for ent in fs::read_dir("/sys/block")? {
+		let ent = ent?;
+		test_assert!(ent.file_type()?.is_dir());
+		// The device must have a valid device number
+		let dev = fs::read_to_string(ent.path().join("dev"))?;
+		let (major, minor) = dev.trim().split_once(':').unwrap_or_default();
+		test_assert!(major.parse::<u32>().is_ok());
+		test_assert!(minor.parse::<u32>().is_ok());
+		let size = fs::read_to_string(ent.path().join("size"))?;
+		test_assert!(size.trim().parse::<u64>().is_ok());
+		// The same device is listed in its class
+		let class_dev = fs::read_to_string(
+			Path::new("/sys/class/block")
+				.join(ent.file_name())
+				.join("dev"),
+		)?;
+		test_assert_eq!(class_dev, dev);
+	}
+	Ok(())
+}
+
+pub fn debugfs() -> TestResult {
+	mount(c"debugfs", c"/sys/kernel/debug", c"debugfs", 0, ptr::null())?;
+	let names: Vec<_> = fs::read_dir("/sys/kernel/debug")?
+		.map(|ent| ent.map(|ent| ent.file_name()))
+		.collect::<Result<_, _>>()?;
+	test_assert!(names.iter().any(|name| name == "sched"));
+	// The current process is ready to run
+	let runqueue = fs::read_to_string("/sys/kernel/debug/sched/runqueue")?;
+	let pid = process::id().to_string();
+	test_assert!(runqueue
+		.lines()
+		.any(|line| line.split_whitespace().next() == Some(&pid)));
+	umount(c"/sys/kernel/debug")?;
+	Ok(())
+}
@@ -0,0 +1,751 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System-wide information testing.
+
+use crate::{log, test_assert, test_assert_eq, util, util::TestResult};
+use std::{
+	ffi::{CStr, CString},
+	fs,
+	fs::File,
+	hint, io,
+	io::Write,
+	mem,
+	os::{
+		fd::AsRawFd,
+		unix::{ffi::OsStrExt, fs::FileExt, process::CommandExt},
+	},
+	process::Command,
+	ptr, slice,
+};
+
+/// The size of a page of memory.
+const PAGE_SIZE: usize = 4096;
+/// The path to the swap file used for testing.
+const SWAP_FILE: &str = "/swapfile";
+/// The path to the sysctl file selecting the overcommit policy.
+const OVERCOMMIT_MEMORY: &str = "/proc/sys/vm/overcommit_memory";
+
+/// Returns system statistics.
+fn get_sysinfo() -> io::Result<libc::sysinfo> {
+	let mut info: libc::sysinfo = unsafe { mem::zeroed() };
+	let res = unsafe { libc::sysinfo(&mut info) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(info)
+}
+
+/// Creates a swap file of `pages` pages at `path`, in the same way as `mkswap` does.
+fn mkswap(path: &str, pages: usize) -> io::Result<()> {
+	let mut header = [0u8; PAGE_SIZE];
+	// Version
+	header[1024..1028].copy_from_slice(&1u32.to_ne_bytes());
+	// Last page
+	header[1028..1032].copy_from_slice(&(pages as u32 - 1).to_ne_bytes());
+	header[PAGE_SIZE - 10..].copy_from_slice(b"SWAPSPACE2");
+	let mut file = File::create(path)?;
+	file.write_all(&header)?;
+	let zero = [0u8; PAGE_SIZE];
+	for _ in 1..pages {
+		file.write_all(&zero)?;
+	}
+	file.sync_all()
+}
+
+pub fn sysinfo() -> TestResult {
+	let info = get_sysinfo()?;
+	log!(
+		"uptime: {}s, totalram: {}, freeram: {}, procs: {}",
+		info.uptime,
+		info.totalram,
+		info.freeram,
+		info.procs
+	);
+	test_assert!(info.uptime > 0);
+	test_assert!(info.mem_unit > 0);
+	test_assert!(info.totalram > 0);
+	test_assert!(info.freeram <= info.totalram);
+	test_assert!(info.procs > 0);
+	Ok(())
+}
+
+pub fn swap() -> TestResult {
+	let info = get_sysinfo()?;
+	let ram = info.totalram as usize * info.mem_unit as usize;
+	// Use more memory than available, so that pages have to be swapped out
+	let len = ram + ram / 2;
+	let pages = len / PAGE_SIZE;
+	log!("Create swap file");
+	mkswap(SWAP_FILE, pages * 2)?;
+	let path = CString::new(SWAP_FILE)?;
+	util::swapon(path.as_c_str(), 0)?;
+	let info = get_sysinfo()?;
+	test_assert!(info.totalswap > 0);
+	test_assert_eq!(info.freeswap, info.totalswap);
+	log!("Fill memory");
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u64, len / 8) };
+	// Write a value specific to each page
+	for page in 0..pages {
+		mem[page * PAGE_SIZE / 8] = page as u64 ^ 0xdeadbeef;
+	}
+	let info = get_sysinfo()?;
+	test_assert!(info.freeswap < info.totalswap);
+	log!("Read back");
+	for page in 0..pages {
+		test_assert_eq!(mem[page * PAGE_SIZE / 8], page as u64 ^ 0xdeadbeef);
+	}
+	unsafe {
+		libc::munmap(ptr, len);
+	}
+	log!("Cleanup");
+	util::swapoff(path.as_c_str())?;
+	let info = get_sysinfo()?;
+	test_assert_eq!(info.totalswap, 0);
+	fs::remove_file(SWAP_FILE)?;
+	Ok(())
+}
+
+pub fn overcommit() -> TestResult {
+	let info = get_sysinfo()?;
+	let ram = info.totalram as usize * info.mem_unit as usize;
+	let map = |len: usize| unsafe {
+		let ptr = libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		);
+		if ptr != libc::MAP_FAILED {
+			libc::munmap(ptr, len);
+			Ok(())
+		} else {
+			Err(io::Error::last_os_error())
+		}
+	};
+	log!("Heuristic overcommit");
+	test_assert_eq!(fs::read_to_string(OVERCOMMIT_MEMORY)?, "0\n");
+	map(ram)?;
+	let res = map(ram * 4);
+	test_assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::ENOMEM));
+	log!("Never overcommit");
+	fs::write(OVERCOMMIT_MEMORY, "2")?;
+	let res = map(ram);
+	fs::write(OVERCOMMIT_MEMORY, "0")?;
+	test_assert_eq!(res.unwrap_err().raw_os_error(), Some(libc::ENOMEM));
+	log!("Always overcommit");
+	fs::write(OVERCOMMIT_MEMORY, "1")?;
+	let res = map(ram * 4);
+	fs::write(OVERCOMMIT_MEMORY, "0")?;
+	res?;
+	Ok(())
+}
+
+pub fn oom_killer() -> TestResult {
+	const CHUNK: usize = 16 * 1024 * 1024;
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// Allocate memory until the process gets killed
+		loop {
+			let ptr = unsafe {
+				libc::mmap(
+					ptr::null_mut(),
+					CHUNK,
+					libc::PROT_READ | libc::PROT_WRITE,
+					libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+					-1,
+					0,
+				)
+			};
+			if ptr == libc::MAP_FAILED {
+				unsafe {
+					libc::_exit(1);
+				}
+			}
+			let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, CHUNK) };
+			for off in (0..CHUNK).step_by(PAGE_SIZE) {
+				mem[off] = 1;
+			}
+		}
+	}
+	log!("Wait for the memory hog to be killed");
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(libc::WIFSIGNALED(status));
+	test_assert_eq!(libc::WTERMSIG(status), libc::SIGKILL);
+	// The system must still be able to allocate memory
+	let info = get_sysinfo()?;
+	test_assert!(info.freeram > 0);
+	Ok(())
+}
+
+pub fn mprotect() -> TestResult {
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			PAGE_SIZE * 3,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, PAGE_SIZE * 3) };
+	mem.fill(42);
+	log!("Make the middle page read-only");
+	let middle = unsafe { ptr.add(PAGE_SIZE) };
+	let res = unsafe { libc::mprotect(middle, PAGE_SIZE, libc::PROT_READ) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	// The content must be preserved and the remaining pages must still be writable
+	test_assert!(mem.iter().all(|b| *b == 42));
+	mem[0] = 0;
+	mem[PAGE_SIZE * 2] = 0;
+	log!("Write to the read-only page");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			ptr::write_volatile(middle as *mut u8, 0);
+			libc::_exit(0);
+		}
+	}
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(libc::WIFSIGNALED(status));
+	test_assert_eq!(libc::WTERMSIG(status), libc::SIGSEGV);
+	log!("Make the page writable again");
+	let res = unsafe { libc::mprotect(middle, PAGE_SIZE, libc::PROT_READ | libc::PROT_WRITE) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	mem[PAGE_SIZE] = 0;
+	test_assert_eq!(mem[PAGE_SIZE + 1], 42);
+	log!("Protect an unmapped range");
+	let res = unsafe { libc::munmap(ptr, PAGE_SIZE * 3) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let res = unsafe { libc::mprotect(ptr, PAGE_SIZE, libc::PROT_READ) };
+	test_assert_eq!(res, -1);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOMEM)
+	);
+	Ok(())
+}
+
+pub fn fork_cow() -> TestResult {
+	let ptr = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			PAGE_SIZE * 2,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+			-1,
+			0,
+		)
+	};
+	if ptr == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, PAGE_SIZE * 2) };
+	mem.fill(42);
+	log!("Write from the child process");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		// The child must see the parent's data, then its own writes
+		let before = mem.iter().all(|b| *b == 42);
+		mem[..PAGE_SIZE].fill(1);
+		let after = mem[..PAGE_SIZE].iter().all(|b| *b == 1);
+		unsafe {
+			libc::_exit(!(before && after) as _);
+		}
+	}
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(libc::WIFEXITED(status));
+	test_assert_eq!(libc::WEXITSTATUS(status), 0);
+	log!("Check the parent's data is untouched");
+	test_assert!(mem.iter().all(|b| *b == 42));
+	log!("Write from the parent process");
+	mem.fill(2);
+	test_assert!(mem.iter().all(|b| *b == 2));
+	let res = unsafe { libc::munmap(ptr, PAGE_SIZE * 2) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	Ok(())
+}
+
+pub fn mremap() -> TestResult {
+	let map = |addr: *mut libc::c_void, len: usize, flags: libc::c_int| {
+		let ptr = unsafe {
+			libc::mmap(
+				addr,
+				len,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+				-1,
+				0,
+			)
+		};
+		if ptr == libc::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(ptr)
+	};
+	// Map two pages, with free space after them
+	let ptr = map(ptr::null_mut(), PAGE_SIZE * 4, 0)?;
+	unsafe {
+		libc::munmap(ptr.byte_add(PAGE_SIZE * 2), PAGE_SIZE * 2);
+	}
+	let mem = unsafe { slice::from_raw_parts_mut(ptr as *mut u8, PAGE_SIZE * 2) };
+	mem[..PAGE_SIZE].fill(1);
+	mem[PAGE_SIZE..].fill(2);
+	let check = |ptr: *mut libc::c_void, len: usize| {
+		let mem = unsafe { slice::from_raw_parts(ptr as *const u8, len) };
+		mem[..PAGE_SIZE].iter().all(|b| *b == 1)
+			&& mem[PAGE_SIZE..(PAGE_SIZE * 2)].iter().all(|b| *b == 2)
+			&& mem[(PAGE_SIZE * 2)..].iter().all(|b| *b == 0)
+	};
+	log!("Grow in place");
+	let res = unsafe { libc::mremap(ptr, PAGE_SIZE * 2, PAGE_SIZE * 4, 0) };
+	test_assert_eq!(res, ptr);
+	test_assert!(check(ptr, PAGE_SIZE * 4));
+	log!("Grow without room nor permission to move");
+	let block = map(
+		unsafe { ptr.byte_add(PAGE_SIZE * 4) },
+		PAGE_SIZE,
+		libc::MAP_FIXED,
+	)?;
+	let res = unsafe { libc::mremap(ptr, PAGE_SIZE * 4, PAGE_SIZE * 8, 0) };
+	test_assert_eq!(res, libc::MAP_FAILED);
+	test_assert_eq!(
+		io::Error::last_os_error().raw_os_error(),
+		Some(libc::ENOMEM)
+	);
+	test_assert!(check(ptr, PAGE_SIZE * 4));
+	log!("Grow by moving");
+	let moved = unsafe { libc::mremap(ptr, PAGE_SIZE * 4, PAGE_SIZE * 8, libc::MREMAP_MAYMOVE) };
+	test_assert!(moved != libc::MAP_FAILED);
+	test_assert!(moved != ptr);
+	test_assert!(check(moved, PAGE_SIZE * 8));
+	log!("Move to a fixed address");
+	let target = map(ptr::null_mut(), PAGE_SIZE * 2, 0)?;
+	let res = unsafe {
+		libc::mremap(
+			moved,
+			PAGE_SIZE * 8,
+			PAGE_SIZE * 3,
+			libc::MREMAP_MAYMOVE | libc::MREMAP_FIXED,
+			target,
+		)
+	};
+	test_assert_eq!(res, target);
+	test_assert!(check(target, PAGE_SIZE * 3));
+	log!("Cleanup");
+	unsafe {
+		libc::munmap(target, PAGE_SIZE * 3);
+		libc::munmap(block, PAGE_SIZE);
+	}
+	Ok(())
+}
+
+/// Reads a byte from the I/O port `port` in a child process, after calling `setup`.
+///
+/// The function returns the child's wait status.
+fn inb_child(port: u16, setup: fn() -> libc::c_long) -> Result<libc::c_int, io::Error> {
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	if pid == 0 {
+		if setup() < 0 {
+			unsafe {
+				libc::_exit(1);
+			}
+		}
+		unsafe {
+			core::arch::asm!("in al, dx", out("al") _, in("dx") port);
+			libc::_exit(0);
+		}
+	}
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(status)
+}
+
+/// Runs `f` in a child process and returns the signal that killed it, if any.
+fn run_until_signal<F: FnOnce()>(f: F) -> io::Result<Option<libc::c_int>> {
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	if pid == 0 {
+		// The standard library's handler would turn the fault into an abort
+		if util::signal(libc::SIGSEGV, libc::SIG_DFL).is_err() {
+			unsafe {
+				libc::_exit(1);
+			}
+		}
+		f();
+		unsafe {
+			libc::_exit(0);
+		}
+	}
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(libc::WIFSIGNALED(status).then(|| libc::WTERMSIG(status)))
+}
+
+pub fn stack_overflow() -> TestResult {
+	#[allow(unconditional_recursion)]
+	#[inline(never)]
+	fn recurse(depth: usize) -> usize {
+		let buf = hint::black_box([depth as u8; 1024]);
+		recurse(depth + 1) + buf[0] as usize
+	}
+	log!("Overflow the stack");
+	let sig = run_until_signal(|| {
+		hint::black_box(recurse(0));
+	})?;
+	test_assert_eq!(sig, Some(libc::SIGSEGV));
+	Ok(())
+}
+
+pub fn stack_growsdown() -> TestResult {
+	let map = |addr: *mut libc::c_void, len: usize, flags: libc::c_int| {
+		let ptr = unsafe {
+			libc::mmap(
+				addr,
+				len,
+				libc::PROT_READ | libc::PROT_WRITE,
+				libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+				-1,
+				0,
+			)
+		};
+		if ptr == libc::MAP_FAILED {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(ptr as *mut u8)
+	};
+	// Keep the first page mapped to block the growth, and the last one as the stack
+	let base = map(ptr::null_mut(), PAGE_SIZE * 8, 0)?;
+	let res = unsafe { libc::munmap(base.add(PAGE_SIZE) as _, PAGE_SIZE * 7) };
+	test_assert_eq!(res, 0);
+	let stack = map(
+		unsafe { base.add(PAGE_SIZE * 7) as _ },
+		PAGE_SIZE,
+		libc::MAP_FIXED | libc::MAP_GROWSDOWN,
+	)?;
+	log!("Grow the stack");
+	let sig = run_until_signal(|| unsafe {
+		for i in 1..=5 {
+			ptr::write_volatile(stack.sub(PAGE_SIZE * i), i as u8);
+		}
+	})?;
+	test_assert_eq!(sig, None);
+	log!("Grow the stack onto the guard page");
+	let sig = run_until_signal(|| unsafe {
+		for i in 1..=6 {
+			ptr::write_volatile(stack.sub(PAGE_SIZE * i), i as u8);
+		}
+	})?;
+	test_assert_eq!(sig, Some(libc::SIGSEGV));
+	log!("Access far below the stack");
+	let sig = run_until_signal(|| unsafe {
+		ptr::write_volatile(stack.sub(PAGE_SIZE * 3), 1);
+	})?;
+	test_assert_eq!(sig, Some(libc::SIGSEGV));
+	unsafe {
+		libc::munmap(base as _, PAGE_SIZE);
+		libc::munmap(stack as _, PAGE_SIZE);
+	}
+	Ok(())
+}
+
+pub fn execfn() -> TestResult {
+	log!("Check AT_EXECFN is the path to the program");
+	let execfn = unsafe { libc::getauxval(libc::AT_EXECFN) } as *const libc::c_char;
+	test_assert!(!execfn.is_null());
+	let execfn = unsafe { CStr::from_ptr(execfn) };
+	test_assert_eq!(execfn.to_bytes(), b"/inttest");
+	let exe = fs::read_link("/proc/self/exe")?;
+	test_assert_eq!(exe.as_os_str().as_bytes(), execfn.to_bytes());
+	Ok(())
+}
+
+/// Prints the real and effective IDs of the process, whether it runs in secure mode and whether
+/// it is dumpable.
+pub fn print_ids() {
+	unsafe {
+		println!(
+			"{} {} {} {} {} {}",
+			libc::getuid(),
+			libc::geteuid(),
+			libc::getgid(),
+			libc::getegid(),
+			libc::getauxval(libc::AT_SECURE),
+			libc::prctl(libc::PR_GET_DUMPABLE),
+		);
+	}
+}
+
+/// Executes the copy of the test program at `path` as user and group `1000`, and returns the IDs
+/// printed by [`print_ids`].
+fn exec_ids(path: &str) -> io::Result<String> {
+	let out = Command::new(path).arg("ids").uid(1000).gid(1000).output()?;
+	Ok(String::from_utf8_lossy(&out.stdout).trim().to_owned())
+}
+
+pub fn suid() -> TestResult {
+	const PATH: &str = "/suid_test";
+	fs::copy("/proc/self/exe", PATH)?;
+	log!("Execute a program without set-user-ID bit");
+	util::chmod(PATH, 0o755)?;
+	test_assert_eq!(exec_ids(PATH)?, "1000 1000 1000 1000 0 1");
+	log!("Execute a set-user-ID program");
+	util::chmod(PATH, 0o4755)?;
+	test_assert_eq!(exec_ids(PATH)?, "1000 0 1000 1000 1 0");
+	log!("Execute a set-group-ID program");
+	util::chown(PATH, 1001, 1001)?;
+	util::chmod(PATH, 0o2755)?;
+	test_assert_eq!(exec_ids(PATH)?, "1000 1000 1000 1001 1 0");
+	log!("Execute a set-user-ID and set-group-ID program");
+	util::chmod(PATH, 0o6755)?;
+	test_assert_eq!(exec_ids(PATH)?, "1000 1001 1000 1001 1 0");
+	log!("Cleanup");
+	fs::remove_file(PATH)?;
+	Ok(())
+}
+
+/// Prints the address of a variable on the stack, the address of the vDSO and the bytes pointed
+/// to by `AT_RANDOM`, in hexadecimal.
+pub fn print_layout() {
+	let stack = 0u8;
+	let random = unsafe { *(libc::getauxval(libc::AT_RANDOM) as *const [u8; 16]) };
+	let random = u128::from_be_bytes(random);
+	let vdso = unsafe { libc::getauxval(libc::AT_SYSINFO_EHDR) };
+	println!("{:p} {vdso:x} {random:032x}", &stack);
+}
+
+/// Executes the test program and returns the fields printed by [`print_layout`].
+fn exec_layout() -> io::Result<Vec<String>> {
+	let out = Command::new("/proc/self/exe").arg("layout").output()?;
+	Ok(String::from_utf8_lossy(&out.stdout)
+		.split_whitespace()
+		.map(str::to_owned)
+		.collect())
+}
+
+pub fn aslr() -> TestResult {
+	let noaslr = fs::read_to_string("/proc/cmdline")?
+		.split_whitespace()
+		.any(|arg| arg == "-noaslr");
+	let a = exec_layout()?;
+	let b = exec_layout()?;
+	test_assert_eq!(a.len(), 3);
+	test_assert_eq!(b.len(), 3);
+	log!("AT_RANDOM points to random bytes");
+	test_assert!(a[2] != "0".repeat(32));
+	test_assert!(a[2] != b[2]);
+	if noaslr {
+		log!("The layout is the same across executions without ASLR");
+		test_assert_eq!(&a[..2], &b[..2]);
+	} else {
+		log!("The stack and the vDSO are placed differently across executions");
+		test_assert!(a[0] != b[0]);
+		test_assert!(a[1] != b[1]);
+	}
+	Ok(())
+}
+
+pub fn port_io() -> TestResult {
+	/// The POST diagnostic port, which is harmless to read.
+	const PORT: u16 = 0x80;
+	let faults =
+		|status: libc::c_int| libc::WIFSIGNALED(status) && libc::WTERMSIG(status) == libc::SIGSEGV;
+	let exits = |status: libc::c_int| libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0;
+	log!("Access a port without permission");
+	let status = inb_child(PORT, || 0)?;
+	test_assert!(faults(status));
+	log!("Access a port allowed with ioperm");
+	let status = inb_child(PORT, || unsafe {
+		libc::syscall(libc::SYS_ioperm, PORT as libc::c_ulong, 1, 1)
+	})?;
+	test_assert!(exits(status));
+	log!("Access a port next to the one allowed with ioperm");
+	let status = inb_child(PORT + 1, || unsafe {
+		libc::syscall(libc::SYS_ioperm, PORT as libc::c_ulong, 1, 1)
+	})?;
+	test_assert!(faults(status));
+	log!("Access a port with iopl");
+	let status = inb_child(PORT, || unsafe { libc::syscall(libc::SYS_iopl, 3) })?;
+	test_assert!(exits(status));
+	Ok(())
+}
+
+pub fn kcov() -> TestResult {
+	/// ioctl request: allocates the trace buffer.
+	const KCOV_INIT_TRACE: libc::c_ulong = 0x80086301;
+	/// ioctl request: starts tracing the calling process.
+	const KCOV_ENABLE: libc::c_ulong = 0x6364;
+	/// ioctl request: stops tracing the calling process.
+	const KCOV_DISABLE: libc::c_ulong = 0x6365;
+	/// The number of entries in the trace buffer.
+	const COVER_SIZE: usize = 64 * 1024;
+	let file = match File::options().read(true).write(true).open("/dev/kcov") {
+		Ok(file) => file,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {
+			log!("Kernel compiled without kcov, skipping");
+			return Ok(());
+		}
+		Err(e) => return Err(e.into()),
+	};
+	let fd = file.as_raw_fd();
+	log!("Setup trace buffer");
+	if unsafe { libc::ioctl(fd, KCOV_INIT_TRACE as _, COVER_SIZE) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	let len = COVER_SIZE * size_of::<usize>();
+	let cover = unsafe {
+		libc::mmap(
+			ptr::null_mut(),
+			len,
+			libc::PROT_READ | libc::PROT_WRITE,
+			libc::MAP_SHARED,
+			fd,
+			0,
+		)
+	};
+	if cover == libc::MAP_FAILED {
+		return Err(io::Error::last_os_error().into());
+	}
+	let cover = unsafe { slice::from_raw_parts_mut(cover as *mut usize, COVER_SIZE) };
+	log!("Trace a system call");
+	if unsafe { libc::ioctl(fd, KCOV_ENABLE as _, 0) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	// Enabling twice fails
+	let busy = unsafe { libc::ioctl(fd, KCOV_ENABLE as _, 0) } < 0
+		&& io::Error::last_os_error().raw_os_error() == Some(libc::EBUSY);
+	unsafe {
+		ptr::write_volatile(&mut cover[0], 0);
+		libc::getppid();
+	}
+	let count = unsafe { ptr::read_volatile(&cover[0]) };
+	if unsafe { libc::ioctl(fd, KCOV_DISABLE as _, 0) } < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(busy);
+	test_assert!(count > 0 && count < COVER_SIZE);
+	test_assert!(cover[1..=count].iter().all(|pc| *pc != 0));
+	log!("Check tracing stopped");
+	cover[0] = 0;
+	unsafe {
+		libc::getppid();
+	}
+	test_assert_eq!(unsafe { ptr::read_volatile(&cover[0]) }, 0);
+	unsafe {
+		libc::munmap(cover.as_mut_ptr() as _, len);
+	}
+	Ok(())
+}
+
+pub fn fault_inject() -> TestResult {
+	const PATH: &str = "/fault_inject";
+	let knob = match File::options()
+		.read(true)
+		.write(true)
+		.open("/proc/self/fail-nth")
+	{
+		Ok(file) => file,
+		Err(e) if e.kind() == io::ErrorKind::NotFound => {
+			log!("Kernel compiled without fault injection, skipping");
+			return Ok(());
+		}
+		Err(e) => return Err(e.into()),
+	};
+	// Writing to the knob may fail because of the fault itself, retry until it does not
+	let disarm = || while knob.write_at(b"0", 0).is_err() {};
+	let mut failures = 0;
+	for n in 1..=64 {
+		knob.write_at(n.to_string().as_bytes(), 0)?;
+		let res = fs::create_dir(PATH);
+		disarm();
+		match res {
+			Ok(()) => fs::remove_dir(PATH)?,
+			Err(e) => {
+				failures += 1;
+				test_assert!(matches!(e.raw_os_error(), Some(libc::ENOMEM | libc::EIO)));
+				// A failed operation leaves nothing behind
+				test_assert!(fs::metadata(PATH).is_err());
+			}
+		}
+	}
+	test_assert!(failures > 0);
+	log!("Check the knob is disarmed");
+	let mut buf = [0u8; 16];
+	let len = knob.read_at(&mut buf, 0)?;
+	test_assert_eq!(&buf[..len], b"0\n");
+	Ok(())
+}
@@ -0,0 +1,487 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Clocks and time testing.
+
+use crate::{
+	log,
+	sched::wait,
+	test_assert, test_assert_eq,
+	util::{sigaction, signal, sigprocmask, TestResult},
+};
+use std::{
+	io, mem, ptr,
+	sync::atomic::{
+		AtomicI64,
+		Ordering::{Acquire, Release},
+	},
+	thread,
+	time::Duration,
+};
+
+/// Returns the value of the clock `clk`, in nanoseconds.
+fn clock_gettime(clk: libc::clockid_t) -> io::Result<u64> {
+	let mut ts: libc::timespec = unsafe { mem::zeroed() };
+	let res = unsafe { libc::clock_gettime(clk, &mut ts) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64)
+}
+
+/// Returns the resolution of the clock `clk`, in nanoseconds.
+fn clock_getres(clk: libc::clockid_t) -> io::Result<u64> {
+	let mut ts: libc::timespec = unsafe { mem::zeroed() };
+	let res = unsafe { libc::clock_getres(clk, &mut ts) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64)
+}
+
+/// Sets the value of the clock `clk`, in nanoseconds.
+fn clock_settime(clk: libc::clockid_t, ts: u64) -> io::Result<()> {
+	let ts = libc::timespec {
+		tv_sec: (ts / 1_000_000_000) as _,
+		tv_nsec: (ts % 1_000_000_000) as _,
+	};
+	let res = unsafe { libc::clock_settime(clk, &ts) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+pub fn clock_resolution() -> TestResult {
+	log!("Get the resolution of the monotonic clock");
+	let res = clock_getres(libc::CLOCK_MONOTONIC)?;
+	test_assert!(res > 0);
+	test_assert!(res <= 1_000_000);
+	log!("Get the resolution of an invalid clock");
+	let err = clock_getres(-1).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	Ok(())
+}
+
+pub fn clocks() -> TestResult {
+	for (name, clk) in [
+		("monotonic", libc::CLOCK_MONOTONIC),
+		("raw monotonic", libc::CLOCK_MONOTONIC_RAW),
+		("coarse monotonic", libc::CLOCK_MONOTONIC_COARSE),
+		("boot time", libc::CLOCK_BOOTTIME),
+	] {
+		log!("Check the {name} clock never goes backwards");
+		let mut prev = clock_gettime(clk)?;
+		test_assert!(prev > 0);
+		for _ in 0..10000 {
+			let cur = clock_gettime(clk)?;
+			test_assert!(cur >= prev);
+			prev = cur;
+		}
+	}
+	log!("Compare the coarse real time clock with the real time clock");
+	let coarse = clock_gettime(libc::CLOCK_REALTIME_COARSE)?;
+	let realtime = clock_gettime(libc::CLOCK_REALTIME)?;
+	test_assert!(coarse <= realtime);
+	test_assert!(realtime - coarse <= clock_getres(libc::CLOCK_REALTIME_COARSE)? * 2);
+	log!("Get the value of an invalid clock");
+	let err = clock_gettime(-1).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	Ok(())
+}
+
+/// Sleeps for `ts`, in nanoseconds, on the clock `clk`.
+///
+/// `flags` are the flags passed to `clock_nanosleep`.
+fn clock_nanosleep(clk: libc::clockid_t, flags: libc::c_int, ts: u64) -> io::Result<()> {
+	let req = libc::timespec {
+		tv_sec: (ts / 1_000_000_000) as _,
+		tv_nsec: (ts % 1_000_000_000) as _,
+	};
+	let res = unsafe {
+		libc::syscall(
+			libc::SYS_clock_nanosleep,
+			clk,
+			flags,
+			&req,
+			ptr::null_mut::<libc::timespec>(),
+		)
+	};
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+extern "C" fn wake_handler(_: libc::c_int) {}
+
+pub fn sleep() -> TestResult {
+	const DELAY: u64 = 50_000_000;
+	log!("Sleep with nanosleep");
+	let start = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	thread::sleep(Duration::from_nanos(DELAY));
+	let end = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	test_assert!(end - start >= DELAY);
+	test_assert!(end - start < 1_000_000_000);
+	log!("Sleep with a relative clock_nanosleep");
+	let start = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	clock_nanosleep(libc::CLOCK_MONOTONIC, 0, DELAY)?;
+	let end = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	test_assert!(end - start >= DELAY);
+	test_assert!(end - start < 1_000_000_000);
+	log!("Sleep with an absolute clock_nanosleep");
+	let deadline = clock_gettime(libc::CLOCK_MONOTONIC)? + DELAY;
+	clock_nanosleep(libc::CLOCK_MONOTONIC, libc::TIMER_ABSTIME, deadline)?;
+	test_assert!(clock_gettime(libc::CLOCK_MONOTONIC)? >= deadline);
+	log!("Sleep until a deadline in the past");
+	clock_nanosleep(libc::CLOCK_MONOTONIC, libc::TIMER_ABSTIME, 0)?;
+	log!("Sleep on invalid clocks");
+	let err = clock_nanosleep(-1, 0, DELAY).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	let err = clock_nanosleep(libc::CLOCK_PROCESS_CPUTIME_ID, 0, DELAY).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Interrupt a sleep with a signal");
+	sigaction(libc::SIGUSR1, wake_handler as usize, 0)?;
+	let parent = unsafe { libc::getpid() };
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		thread::sleep(Duration::from_nanos(DELAY));
+		unsafe {
+			libc::kill(parent, libc::SIGUSR1);
+			libc::_exit(0);
+		}
+	}
+	let req = libc::timespec {
+		tv_sec: 10,
+		tv_nsec: 0,
+	};
+	let mut rem: libc::timespec = unsafe { mem::zeroed() };
+	let res = unsafe { libc::nanosleep(&req, &mut rem) };
+	let err = io::Error::last_os_error();
+	wait(pid)?;
+	signal(libc::SIGUSR1, libc::SIG_DFL)?;
+	test_assert_eq!(res, -1);
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINTR));
+	test_assert!(rem.tv_sec > 0 && rem.tv_sec < 10);
+	Ok(())
+}
+
+/// The number of timer notifications received, then the value and overrun count of the last one.
+static TIMER_INFO: [AtomicI64; 3] = [const { AtomicI64::new(0) }; 3];
+
+extern "C" fn timer_handler(_: libc::c_int, info: *mut libc::siginfo_t, _: *mut libc::c_void) {
+	let info = unsafe { &*info };
+	// The libc does not give access to the overrun count. The timer ID and the overrun count are
+	// the first fields of the union, which is followed by the value
+	let fields = unsafe { (info as *const libc::siginfo_t as *const i32).add(4) };
+	let overrun = unsafe { *fields.add(1) };
+	let value = unsafe { info.si_value().sival_ptr } as i64;
+	TIMER_INFO[1].store(value, Release);
+	TIMER_INFO[2].store(overrun as _, Release);
+	TIMER_INFO[0].fetch_add(1, Release);
+}
+
+/// Creates a timer on `clk`, sending `SIGUSR1` with the value `value`, and returns its ID.
+fn timer_create(clk: libc::clockid_t, value: usize) -> io::Result<libc::c_int> {
+	let mut sev: libc::sigevent = unsafe { mem::zeroed() };
+	sev.sigev_notify = libc::SIGEV_SIGNAL;
+	sev.sigev_signo = libc::SIGUSR1;
+	sev.sigev_value.sival_ptr = value as _;
+	let mut id: libc::c_int = -1;
+	let res = unsafe { libc::syscall(libc::SYS_timer_create, clk, &sev, &mut id) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(id)
+}
+
+/// Arms the timer `id` with the interval `interval` and the initial value `value`, in
+/// nanoseconds.
+fn timer_settime(id: libc::c_int, interval: u64, value: u64) -> io::Result<()> {
+	let to_timespec = |ts: u64| libc::timespec {
+		tv_sec: (ts / 1_000_000_000) as _,
+		tv_nsec: (ts % 1_000_000_000) as _,
+	};
+	let spec = libc::itimerspec {
+		it_interval: to_timespec(interval),
+		it_value: to_timespec(value),
+	};
+	let res = unsafe {
+		libc::syscall(
+			libc::SYS_timer_settime,
+			id,
+			0,
+			&spec,
+			ptr::null_mut::<libc::itimerspec>(),
+		)
+	};
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	Ok(())
+}
+
+/// Returns the interval and remaining time of the timer `id`, in nanoseconds.
+fn timer_gettime(id: libc::c_int) -> io::Result<(u64, u64)> {
+	let mut spec: libc::itimerspec = unsafe { mem::zeroed() };
+	let res = unsafe { libc::syscall(libc::SYS_timer_gettime, id, &mut spec) };
+	if res < 0 {
+		return Err(io::Error::last_os_error());
+	}
+	let to_nano = |ts: libc::timespec| ts.tv_sec as u64 * 1_000_000_000 + ts.tv_nsec as u64;
+	Ok((to_nano(spec.it_interval), to_nano(spec.it_value)))
+}
+
+/// Waits until `count` timer notifications have been received, or until one second elapsed.
+fn wait_timer(count: i64) -> io::Result<()> {
+	let end = clock_gettime(libc::CLOCK_MONOTONIC)? + 1_000_000_000;
+	while TIMER_INFO[0].load(Acquire) < count && clock_gettime(libc::CLOCK_MONOTONIC)? < end {}
+	Ok(())
+}
+
+pub fn timers() -> TestResult {
+	sigaction(libc::SIGUSR1, timer_handler as usize, libc::SA_SIGINFO)?;
+	log!("Create a timer");
+	let id = timer_create(libc::CLOCK_MONOTONIC, 42)?;
+	test_assert_eq!(timer_gettime(id)?, (0, 0));
+	log!("Arm a one-shot timer");
+	timer_settime(id, 0, 20_000_000)?;
+	let (interval, value) = timer_gettime(id)?;
+	test_assert_eq!(interval, 0);
+	test_assert!(value > 0 && value <= 20_000_000);
+	wait_timer(1)?;
+	test_assert_eq!(TIMER_INFO[0].load(Acquire), 1);
+	test_assert_eq!(TIMER_INFO[1].load(Acquire), 42);
+	test_assert_eq!(timer_gettime(id)?, (0, 0));
+	log!("Arm a periodic timer");
+	timer_settime(id, 10_000_000, 10_000_000)?;
+	wait_timer(4)?;
+	test_assert!(TIMER_INFO[0].load(Acquire) >= 4);
+	test_assert_eq!(timer_gettime(id)?.0, 10_000_000);
+	log!("Count overruns while the signal is blocked");
+	sigprocmask(libc::SIG_BLOCK, &[libc::SIGUSR1])?;
+	let count = TIMER_INFO[0].load(Acquire);
+	let end = clock_gettime(libc::CLOCK_MONOTONIC)? + 100_000_000;
+	while clock_gettime(libc::CLOCK_MONOTONIC)? < end {}
+	test_assert_eq!(TIMER_INFO[0].load(Acquire), count);
+	let overrun = unsafe { libc::syscall(libc::SYS_timer_getoverrun, id) };
+	log!("Disarm the timer");
+	timer_settime(id, 0, 0)?;
+	test_assert_eq!(timer_gettime(id)?, (0, 0));
+	sigprocmask(libc::SIG_UNBLOCK, &[libc::SIGUSR1])?;
+	test_assert!(overrun > 0);
+	test_assert_eq!(TIMER_INFO[0].load(Acquire), count + 1);
+	test_assert!(TIMER_INFO[2].load(Acquire) > 0);
+	log!("Delete the timer");
+	let res = unsafe { libc::syscall(libc::SYS_timer_delete, id) };
+	test_assert_eq!(res, 0);
+	let err = timer_gettime(id).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	signal(libc::SIGUSR1, libc::SIG_DFL)?;
+	Ok(())
+}
+
+pub fn cpu_time() -> TestResult {
+	let start = clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID)?;
+	log!("Busy loop for a while");
+	let end = clock_gettime(libc::CLOCK_MONOTONIC)? + 20_000_000;
+	while clock_gettime(libc::CLOCK_MONOTONIC)? < end {}
+	let cpu_time = clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID)?;
+	test_assert!(cpu_time > start);
+	let thread_time = clock_gettime(libc::CLOCK_THREAD_CPUTIME_ID)?;
+	test_assert!(thread_time >= cpu_time);
+	Ok(())
+}
+
+pub fn set_realtime() -> TestResult {
+	const HOUR: u64 = 3600 * 1_000_000_000;
+	// Tolerance to account for the time taken by the test itself
+	const TOLERANCE: u64 = 1_000_000_000;
+	let realtime0 = clock_gettime(libc::CLOCK_REALTIME)?;
+	let monotonic0 = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	log!("Set the real time clock forward by an hour");
+	clock_settime(libc::CLOCK_REALTIME, realtime0 + HOUR)?;
+	let realtime1 = clock_gettime(libc::CLOCK_REALTIME)?;
+	let monotonic1 = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	test_assert!(realtime1 >= realtime0 + HOUR);
+	test_assert!(realtime1 < realtime0 + HOUR + TOLERANCE);
+	test_assert!(monotonic1 >= monotonic0);
+	test_assert!(monotonic1 < monotonic0 + TOLERANCE);
+	log!("Set the monotonic clock");
+	let err = clock_settime(libc::CLOCK_MONOTONIC, 0).unwrap_err();
+	test_assert_eq!(err.raw_os_error(), Some(libc::EINVAL));
+	log!("Restore the real time clock");
+	let elapsed = clock_gettime(libc::CLOCK_MONOTONIC)? - monotonic0;
+	clock_settime(libc::CLOCK_REALTIME, realtime0 + elapsed)?;
+	test_assert!(clock_gettime(libc::CLOCK_MONOTONIC)? >= monotonic1);
+	Ok(())
+}
+
+pub fn adjtime() -> TestResult {
+	log!("Slew the real time clock");
+	let delta = libc::timeval {
+		tv_sec: 0,
+		tv_usec: 100_000,
+	};
+	let res = unsafe { libc::adjtime(&delta, ptr::null_mut()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	log!("Read the remaining adjustment");
+	let mut remaining: libc::timeval = unsafe { mem::zeroed() };
+	let res = unsafe { libc::adjtime(ptr::null(), &mut remaining) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert_eq!(remaining.tv_sec, 0);
+	test_assert!(remaining.tv_usec > 0 && remaining.tv_usec <= 100_000);
+	log!("Cancel the adjustment");
+	let zero: libc::timeval = unsafe { mem::zeroed() };
+	let res = unsafe { libc::adjtime(&zero, ptr::null_mut()) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	Ok(())
+}
+
+pub fn gettimeofday() -> TestResult {
+	// Use the system calls directly since the libc may use `clock_gettime` instead
+	let gettimeofday = || -> io::Result<libc::timeval> {
+		let mut tv: libc::timeval = unsafe { mem::zeroed() };
+		let res = unsafe {
+			libc::syscall(
+				libc::SYS_gettimeofday,
+				&mut tv,
+				ptr::null_mut::<libc::c_void>(),
+			)
+		};
+		if res < 0 {
+			return Err(io::Error::last_os_error());
+		}
+		Ok(tv)
+	};
+	let to_usec = |tv: libc::timeval| tv.tv_sec as u64 * 1_000_000 + tv.tv_usec as u64;
+	log!("Measure a short sleep");
+	let start = gettimeofday()?;
+	thread::sleep(Duration::from_millis(10));
+	let end = gettimeofday()?;
+	test_assert!((0..1_000_000).contains(&end.tv_usec));
+	let (start, end) = (to_usec(start), to_usec(end));
+	test_assert!(end > start);
+	test_assert!(end - start < 1_000_000);
+	log!("Compare with `time`");
+	let mut t: i64 = 0;
+	let res = unsafe { libc::syscall(libc::SYS_time, &mut t) };
+	let now = gettimeofday()?.tv_sec as u64;
+	test_assert_eq!(res, t as _);
+	test_assert!((now - 1..=now).contains(&(t as u64)));
+	Ok(())
+}
+
+/// The layout of the vvar page, which precedes the vDSO.
+#[repr(C)]
+struct Vvar {
+	seq: u32,
+	clock_mode: u32,
+	monotonic: u64,
+	realtime_offset: u64,
+	boottime: u64,
+	tsc_base: u64,
+	tsc_mult: u32,
+	tsc_shift: u32,
+	cpu: u32,
+	node: u32,
+}
+
+/// Reads the monotonic and real time clocks from the vvar page, without a system call.
+fn vvar_clocks(vvar: *const Vvar) -> (u64, u64) {
+	#[cfg(target_arch = "x86")]
+	use std::arch::x86::_rdtsc;
+	#[cfg(target_arch = "x86_64")]
+	use std::arch::x86_64::_rdtsc;
+	use std::{
+		ptr::addr_of,
+		sync::atomic::{fence, AtomicU32, Ordering::Acquire},
+	};
+	let seq = unsafe { &*(addr_of!((*vvar).seq) as *const AtomicU32) };
+	loop {
+		let start = seq.load(Acquire);
+		if start % 2 != 0 {
+			continue;
+		}
+		let data = unsafe { ptr::read_volatile(vvar) };
+		let tsc = unsafe { _rdtsc() };
+		fence(Acquire);
+		if seq.load(Acquire) != start {
+			continue;
+		}
+		let elapsed = if data.clock_mode == 1 {
+			(tsc.wrapping_sub(data.tsc_base) * data.tsc_mult as u64) >> data.tsc_shift
+		} else {
+			0
+		};
+		let monotonic = data.monotonic + elapsed;
+		return (monotonic, monotonic.wrapping_add(data.realtime_offset));
+	}
+}
+
+pub fn vvar() -> TestResult {
+	// Tolerance to account for the time taken by the test itself
+	const TOLERANCE: u64 = 10_000_000;
+	let vdso = unsafe { libc::getauxval(libc::AT_SYSINFO_EHDR) } as usize;
+	test_assert!(vdso != 0);
+	let vvar = (vdso - 4096) as *const Vvar;
+	log!("Check CPU data");
+	let (cpu, node) = unsafe { ((*vvar).cpu, (*vvar).node) };
+	// The kernel runs on a single CPU
+	test_assert_eq!((cpu, node), (0, 0));
+	log!("Compare clocks with the system call");
+	// If the TSC is not used, the page is only updated at each tick
+	let res = clock_getres(libc::CLOCK_MONOTONIC)?.max(TOLERANCE);
+	let monotonic0 = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	let realtime0 = clock_gettime(libc::CLOCK_REALTIME)?;
+	let (monotonic, realtime) = vvar_clocks(vvar);
+	let monotonic1 = clock_gettime(libc::CLOCK_MONOTONIC)?;
+	let realtime1 = clock_gettime(libc::CLOCK_REALTIME)?;
+	test_assert!(monotonic + res >= monotonic0);
+	test_assert!(monotonic <= monotonic1 + TOLERANCE);
+	test_assert!(realtime + res >= realtime0);
+	test_assert!(realtime <= realtime1 + TOLERANCE);
+	log!("Write to the vvar page");
+	let pid = unsafe { libc::fork() };
+	if pid < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	if pid == 0 {
+		unsafe {
+			ptr::write_volatile(vvar as *mut u64, 0);
+			libc::_exit(0);
+		}
+	}
+	let mut status = 0;
+	let res = unsafe { libc::waitpid(pid, &mut status, 0) };
+	if res < 0 {
+		return Err(io::Error::last_os_error().into());
+	}
+	test_assert!(libc::WIFSIGNALED(status));
+	test_assert_eq!(libc::WTERMSIG(status), libc::SIGSEGV);
+	Ok(())
+}
@@ -26,6 +26,7 @@ use std::{
 	os::unix::ffi::OsStrExt,
 	path::Path,
 	process::{Command, Stdio},
+	ptr,
 };
 
 pub struct TestError(pub String);
@@ -130,6 +131,31 @@ pub fn fstat(fd: c_int) -> io::Result<libc::stat> {
 	}
 }
 
+pub fn statfs<P: AsRef<Path>>(path: P) -> io::Result<libc::statfs> {
+	let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
+	unsafe {
+		let mut stat: libc::statfs = mem::zeroed();
+		let res = libc::statfs(path.as_ptr(), &mut stat);
+		if res >= 0 {
+			Ok(stat)
+		} else {
+			Err(io::Error::last_os_error())
+		}
+	}
+}
+
+pub fn fstatfs(fd: c_int) -> io::Result<libc::statfs> {
+	unsafe {
+		let mut stat: libc::statfs = mem::zeroed();
+		let res = libc::fstatfs(fd, &mut stat);
+		if res >= 0 {
+			Ok(stat)
+		} else {
+			Err(io::Error::last_os_error())
+		}
+	}
+}
+
 pub fn mkfifo<P: AsRef<Path>>(path: P, mode: mode_t) -> io::Result<()> {
 	let path = CString::new(path.as_ref().as_os_str().as_bytes())?;
 	let res = unsafe { libc::mkfifo(path.as_ptr(), mode) };
@@ -140,6 +166,31 @@ pub fn mkfifo<P: AsRef<Path>>(path: P, mode: mode_t) -> io::Result<()> {
 	}
 }
 
+/// The argument of the `openat2` system call.
+#[repr(C)]
+pub struct OpenHow {
+	pub flags: u64,
+	pub mode: u64,
+	pub resolve: u64,
+}
+
+pub fn openat2(dirfd: c_int, path: &CStr, how: &OpenHow) -> io::Result<c_int> {
+	let res = unsafe {
+		libc::syscall(
+			libc::SYS_openat2,
+			dirfd,
+			path.as_ptr(),
+			how as *const OpenHow,
+			size_of::<OpenHow>(),
+		)
+	};
+	if res >= 0 {
+		Ok(res as _)
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
 pub fn mount(
 	src: &CStr,
 	target: &CStr,
@@ -164,6 +215,24 @@ pub fn umount(src: &CStr) -> io::Result<()> {
 	}
 }
 
+pub fn swapon(path: &CStr, flags: c_int) -> io::Result<()> {
+	let res = unsafe { libc::swapon(path.as_ptr(), flags) };
+	if res >= 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
+pub fn swapoff(path: &CStr) -> io::Result<()> {
+	let res = unsafe { libc::swapoff(path.as_ptr()) };
+	if res >= 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
 pub fn seteuid(uid: uid_t) -> io::Result<()> {
 	let res = unsafe { libc::seteuid(uid) };
 	if res >= 0 {
@@ -216,6 +285,36 @@ pub fn signal(signum: c_int, handler: sighandler_t) -> io::Result<()> {
 	}
 }
 
+pub fn sigaction(signum: c_int, handler: sighandler_t, flags: c_int) -> io::Result<()> {
+	let res = unsafe {
+		let mut act: libc::sigaction = mem::zeroed();
+		act.sa_sigaction = handler;
+		act.sa_flags = flags;
+		libc::sigaction(signum, &act, ptr::null_mut())
+	};
+	if res >= 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
+
+pub fn sigprocmask(how: c_int, signals: &[c_int]) -> io::Result<()> {
+	unsafe {
+		let mut set: libc::sigset_t = mem::zeroed();
+		libc::sigemptyset(&mut set);
+		for sig in signals {
+			libc::sigaddset(&mut set, *sig);
+		}
+		let res = libc::sigprocmask(how, &set, ptr::null_mut());
+		if res >= 0 {
+			Ok(())
+		} else {
+			Err(io::Error::last_os_error())
+		}
+	}
+}
+
 pub fn kill(pid: pid_t, sig: c_int) -> io::Result<()> {
 	let res = unsafe { libc::kill(pid, sig) };
 	if res >= 0 {
@@ -224,3 +323,16 @@ pub fn kill(pid: pid_t, sig: c_int) -> io::Result<()> {
 		Err(io::Error::last_os_error())
 	}
 }
+
+pub fn gettid() -> pid_t {
+	unsafe { libc::syscall(libc::SYS_gettid) as _ }
+}
+
+pub fn tgkill(tgid: pid_t, tid: pid_t, sig: c_int) -> io::Result<()> {
+	let res = unsafe { libc::syscall(libc::SYS_tgkill, tgid, tid, sig) };
+	if res >= 0 {
+		Ok(())
+	} else {
+		Err(io::Error::last_os_error())
+	}
+}
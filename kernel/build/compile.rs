@@ -84,7 +84,12 @@ pub fn compile_c(env: &Env, target: &Target) -> io::Result<()> {
 	for f in &files {
 		println!("cargo:rerun-if-changed={}", f.display());
 	}
-	cc::Build::new()
+	let mut build = cc::Build::new();
+	// The coverage hook is compiled only when a process may collect coverage
+	if std::env::var_os("CARGO_FEATURE_KCOV").is_some() {
+		build.define("CONFIG_KCOV", None);
+	}
+	build
 		.flag("-nostdlib")
 		.flag("-ffreestanding")
 		.flag("-fno-stack-protector")
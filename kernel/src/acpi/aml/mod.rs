@@ -277,9 +277,61 @@ pub struct AMLCode {
 	term_list: TermList,
 }
 
+/// Returns the values of `SLP_TYPa` and `SLP_TYPb` to enter the S5 (soft-off) sleep state, from
+/// the `\_S5` package defined in the AML code `aml`.
+///
+/// Instead of interpreting the code, the function looks for the package's definition, which is
+/// enough since the package is always made of constants.
+pub fn find_s5(aml: &[u8]) -> Option<(u8, u8)> {
+	let parse = |off: usize| {
+		// The name must be defined by a `NameOp`, possibly with a root prefix
+		match aml[..off] {
+			[.., NAME_OP] | [.., NAME_OP, ROOT_CHAR] => {}
+			_ => return None,
+		}
+		let mut b = aml[(off + 4)..].iter().copied();
+		if b.next()? != PACKAGE_OP {
+			return None;
+		}
+		// The two most significant bits of `PkgLength` give the number of following bytes
+		let pkg_lead = b.next()?;
+		for _ in 0..(pkg_lead >> 6) {
+			b.next()?;
+		}
+		let _num_elements = b.next()?;
+		let mut element = || match b.next()? {
+			ZERO_OP => Some(0),
+			ONE_OP => Some(1),
+			BYTE_PREFIX => b.next(),
+			_ => None,
+		};
+		Some((element()?, element()?))
+	};
+	aml.windows(4)
+		.enumerate()
+		.filter(|(_, name)| *name == b"_S5_")
+		.find_map(|(off, _)| parse(off))
+}
+
 /// Parses the given AML code.
 ///
 /// On parsing error, the function returns an error message.
 pub fn parse(_aml: &[u8]) -> Result<AMLCode, String> {
 	todo!();
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn aml_find_s5() {
+		// `Name (\_S5, Package (0x04) { 0x05, Zero, Zero, Zero })`, after a reference to `_S5_`
+		let aml = [
+			0x70, b'_', b'S', b'5', b'_', 0x60, 0x08, 0x5c, b'_', b'S', b'5', b'_', 0x12, 0x08,
+			0x04, 0x0a, 0x05, 0x00, 0x00, 0x00,
+		];
+		assert_eq!(find_s5(&aml), Some((5, 0)));
+		assert_eq!(find_s5(&aml[..6]), None);
+	}
+}
@@ -18,22 +18,70 @@
 
 //! This module handles ACPI's Fixed ACPI Description Table (FADT).
 
-use super::{dsdt::Dsdt, Table, TableHdr};
+use super::{dsdt::Dsdt, phys_to_ptr, Table, TableHdr};
+use crate::{arch::x86::io::outb, device::bus::pci};
 use core::{ptr, slice};
 
-/// TODO doc
+/// Address space of a [`GenericAddr`]: system memory.
+pub const ADDR_SPACE_MEMORY: u8 = 0;
+/// Address space of a [`GenericAddr`]: system I/O.
+pub const ADDR_SPACE_IO: u8 = 1;
+/// Address space of a [`GenericAddr`]: PCI configuration space.
+pub const ADDR_SPACE_PCI: u8 = 2;
+
+/// FADT flag: the reset register is supported.
+pub const RESET_REG_SUP: u32 = 1 << 10;
+
+/// Generic Address Structure (GAS), describing the location of a register.
+#[repr(C, packed)]
 pub struct GenericAddr {
-	addr_space: u8,
-	bit_width: u8,
-	bit_offset: u8,
-	access_size: u8,
-	address: u8,
+	/// The address space of the register: system memory (`0`), system I/O (`1`), PCI
+	/// configuration space (`2`), etc...
+	pub addr_space: u8,
+	/// The size of the register in bits.
+	pub bit_width: u8,
+	/// The offset of the register in bits, at the given address.
+	pub bit_offset: u8,
+	/// The size of accesses: undefined (`0`), byte (`1`), word (`2`), dword (`3`) or qword (`4`).
+	pub access_size: u8,
+	/// The address of the register in its address space.
+	pub address: u64,
+}
+
+impl GenericAddr {
+	/// Writes the byte `value` to the register.
+	///
+	/// If the address space is not supported, the function returns `false`.
+	///
+	/// # Safety
+	///
+	/// Writing to a register may have side effects on the hardware.
+	pub unsafe fn write_byte(&self, value: u8) -> bool {
+		let addr = self.address;
+		match self.addr_space {
+			ADDR_SPACE_MEMORY => {
+				let ptr = phys_to_ptr::<u8>(addr as _) as *mut u8;
+				if ptr.is_null() {
+					return false;
+				}
+				ptr::write_volatile(ptr, value);
+			}
+			ADDR_SPACE_IO => outb(addr as _, value),
+			// The address is made of the device (bits 32..48), the function (bits 16..32) and
+			// the offset (bits 0..16). The register is on bus 0
+			ADDR_SPACE_PCI => {
+				pci::write_config_byte(0, (addr >> 32) as _, (addr >> 16) as _, addr as _, value)
+			}
+			_ => return false,
+		}
+		true
+	}
 }
 
 /// The Fixed ACPI Description Table.
 ///
 /// The documentation of every field can be found in the ACPI documentation.
-#[repr(C)]
+#[repr(C, packed)]
 pub struct Fadt {
 	/// The table's header.
 	pub header: TableHdr,
@@ -107,7 +155,7 @@ impl Fadt {
 		} else {
 			self.dsdt as _
 		};
-		let dsdt: *const TableHdr = ptr::with_exposed_provenance(dsdt as _);
+		let dsdt: *const TableHdr = phys_to_ptr(dsdt as _);
 		if !dsdt.is_null() {
 			let dsdt = unsafe {
 				let len = (*dsdt).length as usize;
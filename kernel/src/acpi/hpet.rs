@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! ACPI's HPET Description Table, describing the High Precision Event Timer.
+
+use super::{fadt::GenericAddr, Table, TableHdr};
+
+/// The HPET Description Table.
+#[repr(C, packed)]
+pub struct Hpet {
+	/// The table's header.
+	pub header: TableHdr,
+
+	/// The hardware ID of the event timer block.
+	pub event_timer_block_id: u32,
+	/// The base address of the event timer block's registers.
+	pub base_address: GenericAddr,
+	/// The sequence number of the HPET.
+	pub hpet_number: u8,
+	/// The minimum number of ticks that can be set in periodic mode without losing interrupts.
+	pub minimum_tick: u16,
+	/// Page protection and OEM attributes.
+	pub page_protection: u8,
+}
+
+impl Table for Hpet {
+	const SIGNATURE: &'static [u8; 4] = b"HPET";
+}
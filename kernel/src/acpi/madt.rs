@@ -19,7 +19,7 @@
 //! ACPI's Multiple APIC Description Table (MADT) handling.
 
 use super::{Table, TableHdr};
-use core::{ffi::c_void, intrinsics::likely};
+use core::{ffi::c_void, intrinsics::likely, mem::size_of};
 
 /// The offset of the entries in the MADT.
 const ENTRIES_OFF: usize = 0x2c;
@@ -28,6 +28,13 @@ const ENTRIES_OFF: usize = 0x2c;
 /// must be disabled when enabling ACPI APIC).
 const PCAT_COMPAT: u32 = 0b1;
 
+/// The type of Processor Local APIC entries.
+const ENTRY_LOCAL_APIC: u8 = 0;
+/// Processor Local APIC flag: the processor is ready to use.
+const LOCAL_APIC_ENABLED: u32 = 0b1;
+/// Processor Local APIC flag: the processor can be enabled at runtime.
+const LOCAL_APIC_ONLINE_CAPABLE: u32 = 0b10;
+
 /// The Multiple APIC Description Table.
 #[repr(C)]
 #[derive(Debug)]
@@ -66,6 +73,35 @@ pub struct EntryHeader {
 	pub length: u8,
 }
 
+impl EntryHeader {
+	/// If the entry is a Processor Local APIC entry, returns it.
+	pub fn as_local_apic(&self) -> Option<&LocalApicEntry> {
+		(self.entry_type == ENTRY_LOCAL_APIC
+			&& self.length as usize >= size_of::<LocalApicEntry>())
+		.then(|| unsafe { &*(self as *const Self as *const LocalApicEntry) })
+	}
+}
+
+/// A Processor Local APIC entry, describing a CPU core.
+#[repr(C, packed)]
+pub struct LocalApicEntry {
+	/// The entry header.
+	pub header: EntryHeader,
+	/// The ACPI processor ID.
+	pub processor_id: u8,
+	/// The ID of the processor's local APIC.
+	pub apic_id: u8,
+	/// Flags.
+	flags: u32,
+}
+
+impl LocalApicEntry {
+	/// Tells whether the processor is enabled, or can be enabled.
+	pub fn is_usable(&self) -> bool {
+		self.flags & (LOCAL_APIC_ENABLED | LOCAL_APIC_ONLINE_CAPABLE) != 0
+	}
+}
+
 /// Iterator over MADT entries.
 pub struct EntriesIterator<'m> {
 	madt: &'m Madt,
@@ -80,7 +116,7 @@ impl<'m> Iterator for EntriesIterator<'m> {
 		let entries_len = self.madt.header.length as usize - ENTRIES_OFF;
 		if likely(self.cursor < entries_len) {
 			let entry = unsafe {
-				let ptr = (self as *const _ as *const c_void).add(ENTRIES_OFF + self.cursor)
+				let ptr = (self.madt as *const _ as *const c_void).add(ENTRIES_OFF + self.cursor)
 					as *const EntryHeader;
 				&*ptr
 			};
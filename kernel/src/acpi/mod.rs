@@ -22,22 +22,34 @@
 //! ACPI initialization is done through the following phases:
 //! - Read the `RSDP` table in order to get a pointer to the `RSDT`, referring to every other
 //!   available tables.
+//! - Read the `FADT` and the `DSDT` to retrieve the information required for power management.
+//! - Read the `HPET` table to locate the High Precision Event Timer, if present.
 //! - TODO
 
-use crate::{acpi::rsdt::Rsdt, memory};
+use crate::{
+	acpi::rsdt::Rsdt,
+	arch::x86::io::{inw, outb, outw},
+	cpu, memory,
+	memory::PhysAddr,
+	println,
+	sync::{atomic::AtomicU64, mutex::Mutex},
+	time::hw::pit,
+};
 use core::{
 	intrinsics::{likely, unlikely},
-	mem::{align_of, size_of},
+	mem::{align_of, offset_of, size_of},
 	ptr, slice,
 	sync::{atomic, atomic::AtomicBool},
 };
 use dsdt::Dsdt;
 use fadt::Fadt;
+use hpet::Hpet;
 use madt::Madt;
 
 mod aml;
 mod dsdt;
 mod fadt;
+mod hpet;
 mod madt;
 mod rsdt;
 
@@ -46,6 +58,14 @@ mod rsdt;
 /// The signature of the RSDP.
 const RSDP_SIGNATURE: &[u8] = b"RSD PTR ";
 
+/// Returns a pointer to the ACPI structure located at the physical address `addr`.
+fn phys_to_ptr<T>(addr: usize) -> *const T {
+	PhysAddr(addr)
+		.kernel_to_virtual()
+		.map(|addr| addr.as_ptr::<T>() as *const T)
+		.unwrap_or(ptr::null())
+}
+
 /// Checks the checksum for `obj`.
 ///
 /// `len` is the size of the object in bytes.
@@ -96,7 +116,7 @@ impl Rsdp {
 	///
 	/// This function is safe only if [`check`] returns `true`.
 	pub unsafe fn get_rsdt(&self) -> &Rsdt {
-		&*ptr::with_exposed_provenance(self.rsdt_address as _)
+		&*phys_to_ptr(self.rsdt_address as _)
 	}
 }
 
@@ -170,8 +190,8 @@ pub trait Table {
 
 /// Finds the [`Rsdp`] and returns a reference to it.
 unsafe fn find_rsdp() -> Option<&'static Rsdp> {
-	let begin = (memory::PROCESS_END + 0xe0000).as_ptr();
-	let end = (memory::PROCESS_END + 0xfffff).as_ptr();
+	let begin = (memory::KERNEL_BEGIN + 0xe0000).as_ptr();
+	let end = (memory::KERNEL_BEGIN + 0xfffff).as_ptr();
 	let mut ptr = begin;
 	while ptr < end {
 		let signature_slice = slice::from_raw_parts::<u8>(ptr, RSDP_SIGNATURE.len());
@@ -183,14 +203,70 @@ unsafe fn find_rsdp() -> Option<&'static Rsdp> {
 	None
 }
 
+/// `PM1_CNT` register: enables the generation of System Control Interrupts, set while ACPI is
+/// enabled.
+const PM1_SCI_EN: u16 = 1 << 0;
+/// `PM1_CNT` register: the shift of the type of sleep state to enter.
+const PM1_SLP_TYP_SHIFT: u16 = 10;
+/// `PM1_CNT` register: the mask of the type of sleep state to enter.
+const PM1_SLP_TYP_MASK: u16 = 0b111 << PM1_SLP_TYP_SHIFT;
+/// `PM1_CNT` register: enters the sleep state given by `SLP_TYP`.
+const PM1_SLP_EN: u16 = 1 << 13;
+
+/// The values of `SLP_TYPa` and `SLP_TYPb` for the S5 state, if the DSDT does not provide them.
+///
+/// These are the values of the PIIX4 chipset, emulated by QEMU and Bochs.
+const DEFAULT_S5_SLP_TYP: (u8, u8) = (0, 0);
+
+/// Resets the system through the FADT's reset register.
+///
+/// If ACPI is not available or does not support reset, the function returns `false`. Otherwise,
+/// the function returns `true` after writing the register, which should cause the reset.
+pub fn reboot() -> bool {
+	let info = POWER_INFO.lock();
+	let Some(info) = &*info else {
+		return false;
+	};
+	// The reset register is not present in ACPI 1.0's FADT
+	let len = info.fadt.hdr().length as usize;
+	if len <= offset_of!(Fadt, reset_value) || info.fadt.flags & fadt::RESET_REG_SUP == 0 {
+		return false;
+	}
+	println!("Reset through ACPI");
+	let reset_reg = &info.fadt.reset_reg;
+	unsafe { reset_reg.write_byte(info.fadt.reset_value) }
+}
+
+/// Power management information, gathered at initialization.
+struct PowerInfo {
+	/// The FADT.
+	fadt: &'static Fadt,
+	/// The values of `SLP_TYPa` and `SLP_TYPb` to enter the S5 (soft-off) sleep state.
+	s5_slp_typ: (u8, u8),
+}
+
+/// Power management information. If `None`, ACPI is not available.
+static POWER_INFO: Mutex<Option<PowerInfo>> = Mutex::new(None);
+
 /// Boolean value telling whether the century register of the CMOS exist.
 static CENTURY_REGISTER: AtomicBool = AtomicBool::new(false);
 
+/// The physical address of the HPET's registers. If zero, the HPET is not present.
+static HPET_ADDRESS: AtomicU64 = AtomicU64::new(0);
+
 /// Tells whether the century register of the CMOS is present.
 pub fn is_century_register_present() -> bool {
 	CENTURY_REGISTER.load(atomic::Ordering::Relaxed)
 }
 
+/// Returns the physical address of the HPET's registers.
+///
+/// If the HPET is not present, the function returns `None`.
+pub fn hpet_address() -> Option<PhysAddr> {
+	let addr = HPET_ADDRESS.load(atomic::Ordering::Relaxed);
+	(addr != 0).then_some(PhysAddr(addr as _))
+}
+
 /// Initializes ACPI.
 ///
 /// This function must be called only once, at boot.
@@ -208,8 +284,8 @@ pub(crate) fn init() {
 	if let Some(madt) = rsdt.get_table::<Madt>() {
 		// Register CPU cores
 		for e in madt.entries() {
-			if e.entry_type == 0 {
-				// TODO Register a new CPU
+			if let Some(e) = e.as_local_apic().filter(|e| e.is_usable()) {
+				cpu::add_core(e.apic_id);
 			}
 		}
 	}
@@ -218,14 +294,64 @@ pub(crate) fn init() {
 	if let Some(fadt) = fadt {
 		CENTURY_REGISTER.store(fadt.century != 0, atomic::Ordering::Relaxed);
 	}
+	// Read HPET table. Only memory-mapped registers are supported
+	if let Some(hpet) = rsdt.get_table::<Hpet>() {
+		let base = &hpet.base_address;
+		if base.addr_space == fadt::ADDR_SPACE_MEMORY {
+			HPET_ADDRESS.store(base.address, atomic::Ordering::Relaxed);
+		}
+	}
 	// Get the DSDT
 	let dsdt = rsdt
 		.get_table_unsized::<Dsdt>()
 		.or_else(|| fadt.and_then(Fadt::get_dsdt));
-	if let Some(dsdt) = dsdt {
-		// Parse AML code
-		let aml = dsdt.get_aml();
-		let _ast = aml::parse(aml);
-		// TODO
+	// TODO Parse AML code with `aml::parse` once it is implemented
+	let s5_slp_typ = dsdt.and_then(|dsdt| aml::find_s5(dsdt.get_aml()));
+	if let Some(fadt) = fadt {
+		*POWER_INFO.lock() = Some(PowerInfo {
+			fadt,
+			s5_slp_typ: s5_slp_typ.unwrap_or(DEFAULT_S5_SLP_TYP),
+		});
+	}
+}
+
+/// Enters the S5 (soft-off) sleep state, powering the system off.
+///
+/// If ACPI is not available, the function returns.
+pub fn shutdown() {
+	let info = POWER_INFO.lock();
+	let Some(info) = &*info else {
+		return;
+	};
+	let pm1a_cnt = info.fadt.pm1a_control_block as u16;
+	let pm1b_cnt = info.fadt.pm1b_control_block as u16;
+	if pm1a_cnt == 0 {
+		return;
+	}
+	unsafe {
+		// If ACPI is not enabled yet, enable it
+		let smi_cmd = info.fadt.smi_commandport as u16;
+		if inw(pm1a_cnt) & PM1_SCI_EN == 0 && smi_cmd != 0 {
+			outb(smi_cmd, info.fadt.acpi_enable);
+			// The transition may take a while
+			for _ in 0..1000 {
+				if inw(pm1a_cnt) & PM1_SCI_EN != 0 {
+					break;
+				}
+				pit::busy_wait(1_000_000);
+			}
+		}
+		let (slp_typa, slp_typb) = info.s5_slp_typ;
+		let enter = |port: u16, slp_typ: u8| {
+			let val = inw(port) & !PM1_SLP_TYP_MASK;
+			outw(
+				port,
+				val | ((slp_typ as u16) << PM1_SLP_TYP_SHIFT) | PM1_SLP_EN,
+			);
+		};
+		enter(pm1a_cnt, slp_typa);
+		if pm1b_cnt != 0 {
+			enter(pm1b_cnt, slp_typb);
+		}
 	}
 }
@@ -18,7 +18,7 @@
 
 //! This module handles ACPI's Root System Description Table (RSDT).
 
-use super::{Table, TableHdr};
+use super::{phys_to_ptr, Table, TableHdr};
 use core::{mem::size_of, ptr, ptr::Pointee, slice};
 
 /// The Root System Description Table.
@@ -40,7 +40,7 @@ impl Rsdt {
 			let entries_start = (self as *const Self).add(1) as *const u32;
 			slice::from_raw_parts(entries_start, entries_count)
 				.iter()
-				.map(|p| &*ptr::with_exposed_provenance(*p as usize))
+				.map(|p| &*phys_to_ptr(*p as usize))
 		}
 	}
 
@@ -0,0 +1,192 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Detection of the features supported by the CPU, using the `CPUID` instruction.
+//!
+//! Features are detected once at boot, then cached.
+
+use super::cpuid;
+use crate::{register_get, sync::once::OnceInit};
+
+/// `CR4` bit: enables the `FXSAVE` and `FXRSTOR` instructions (and thus, SSE).
+pub const CR4_OSFXSR: usize = 1 << 9;
+/// `CR4` bit: enables unmasked SSE exceptions.
+pub const CR4_OSXMMEXCPT: usize = 1 << 10;
+/// `CR4` bit: enables the `RDFSBASE`, `WRFSBASE`, `RDGSBASE` and `WRGSBASE` instructions.
+pub const CR4_FSGSBASE: usize = 1 << 16;
+/// `CR4` bit: enables the `XSAVE` instructions set and the `XCR0` register.
+pub const CR4_OSXSAVE: usize = 1 << 18;
+
+/// `AT_HWCAP2` bit: the `FSGSBASE` instructions are available to userspace.
+const HWCAP2_FSGSBASE: u32 = 1 << 1;
+
+/// The set of features supported by the CPU.
+#[derive(Debug)]
+pub struct Features {
+	/// The raw value of `CPUID.01H:EDX`, used to fill `AT_HWCAP`.
+	pub leaf1_edx: u32,
+
+	/// x87 FPU.
+	pub fpu: bool,
+	/// Time Stamp Counter.
+	pub tsc: bool,
+	/// Physical Address Extension.
+	pub pae: bool,
+	/// `FXSAVE` and `FXRSTOR` instructions.
+	pub fxsr: bool,
+	/// SSE.
+	pub sse: bool,
+	/// SSE2.
+	pub sse2: bool,
+	/// SSE3.
+	pub sse3: bool,
+	/// Supplemental SSE3.
+	pub ssse3: bool,
+	/// SSE4.1.
+	pub sse4_1: bool,
+	/// SSE4.2.
+	pub sse4_2: bool,
+	/// `POPCNT` instruction.
+	pub popcnt: bool,
+	/// `XSAVE` instructions set.
+	pub xsave: bool,
+	/// AVX.
+	pub avx: bool,
+	/// AVX2.
+	pub avx2: bool,
+	/// `RDRAND` instruction.
+	pub rdrand: bool,
+	/// `RDSEED` instruction.
+	pub rdseed: bool,
+	/// `FSGSBASE` instructions.
+	pub fsgsbase: bool,
+	/// Supervisor Mode Execution Prevention.
+	pub smep: bool,
+	/// Supervisor Mode Access Prevention.
+	pub smap: bool,
+	/// `SYSCALL` and `SYSRET` instructions.
+	pub syscall: bool,
+	/// No-Execute bit for pages.
+	pub nx: bool,
+	/// The TSC runs at a constant rate regardless of power management.
+	pub invariant_tsc: bool,
+}
+
+impl Features {
+	/// Detects the features of the current CPU.
+	fn detect() -> Self {
+		let (max_leaf, ..) = cpuid(0, 0, 0, 0);
+		let (_, _, leaf1_ecx, leaf1_edx) = cpuid(1, 0, 0, 0);
+		let (_, leaf7_ebx, ..) = if max_leaf >= 7 {
+			cpuid(7, 0, 0, 0)
+		} else {
+			Default::default()
+		};
+		let (max_ext_leaf, ..) = cpuid(0x80000000, 0, 0, 0);
+		let (_, _, _, ext1_edx) = if max_ext_leaf >= 0x80000001 {
+			cpuid(0x80000001, 0, 0, 0)
+		} else {
+			Default::default()
+		};
+		let (_, _, _, ext7_edx) = if max_ext_leaf >= 0x80000007 {
+			cpuid(0x80000007, 0, 0, 0)
+		} else {
+			Default::default()
+		};
+		let bit = |reg: u32, n: u32| reg & (1 << n) != 0;
+		Self {
+			leaf1_edx,
+
+			fpu: bit(leaf1_edx, 0),
+			tsc: bit(leaf1_edx, 4),
+			pae: bit(leaf1_edx, 6),
+			fxsr: bit(leaf1_edx, 24),
+			sse: bit(leaf1_edx, 25),
+			sse2: bit(leaf1_edx, 26),
+			sse3: bit(leaf1_ecx, 0),
+			ssse3: bit(leaf1_ecx, 9),
+			sse4_1: bit(leaf1_ecx, 19),
+			sse4_2: bit(leaf1_ecx, 20),
+			popcnt: bit(leaf1_ecx, 23),
+			xsave: bit(leaf1_ecx, 26),
+			avx: bit(leaf1_ecx, 28),
+			avx2: bit(leaf7_ebx, 5),
+			rdrand: bit(leaf1_ecx, 30),
+			rdseed: bit(leaf7_ebx, 18),
+			fsgsbase: bit(leaf7_ebx, 0),
+			smep: bit(leaf7_ebx, 7),
+			smap: bit(leaf7_ebx, 20),
+			syscall: bit(ext1_edx, 11),
+			nx: bit(ext1_edx, 20),
+			invariant_tsc: bit(ext7_edx, 8),
+		}
+	}
+
+	/// Returns the value of the `AT_HWCAP` auxiliary vector entry.
+	pub fn hwcap(&self) -> u32 {
+		self.leaf1_edx
+	}
+
+	/// Returns the value of the `AT_HWCAP2` auxiliary vector entry.
+	pub fn hwcap2(&self) -> u32 {
+		let mut hwcap2 = 0;
+		// The instructions are available only if enabled by the kernel
+		if self.fsgsbase && register_get!("cr4") & CR4_FSGSBASE != 0 {
+			hwcap2 |= HWCAP2_FSGSBASE;
+		}
+		hwcap2
+	}
+}
+
+/// The features of the CPU.
+static FEATURES: OnceInit<Features> = unsafe { OnceInit::new() };
+
+/// Detects the features of the CPU.
+///
+/// This function must be called once at boot, before [`get`].
+pub(crate) fn init() {
+	unsafe {
+		OnceInit::init(&FEATURES, Features::detect());
+	}
+}
+
+/// Returns the features of the CPU.
+#[inline]
+pub fn get() -> &'static Features {
+	&FEATURES
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn features_consistency() {
+		let features = get();
+		// Features required to run the kernel
+		assert!(features.fpu);
+		assert!(features.fxsr);
+		assert!(features.sse);
+		// Any x86_64 CPU supports SSE2
+		#[cfg(target_arch = "x86_64")]
+		assert!(features.sse2);
+		assert_eq!(features.hwcap() & (1 << 26) != 0, features.sse2);
+		// AVX2 cannot be supported without AVX
+		assert!(!features.avx2 || features.avx);
+	}
+}
@@ -43,6 +43,11 @@ pub const USER_DS: usize = 32;
 pub const USER_CS64: usize = 40;
 /// The offset of the Task State Segment (TSS).
 pub const TSS_OFFSET: usize = 48;
+/// The offset of the TSS used to handle double faults.
+///
+/// On x86, the TSS takes only one entry, so the second one is free to use.
+#[cfg(target_arch = "x86")]
+pub const DOUBLE_FAULT_TSS_OFFSET: usize = 56;
 /// The offset of Thread Local Storage (TLS) entries.
 pub const TLS_OFFSET: usize = 64;
 
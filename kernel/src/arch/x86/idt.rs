@@ -172,6 +172,41 @@ impl IntFrame {
 		self.rax = value.map(|v| v as _).unwrap_or_else(|e| (-e.as_int()) as _);
 	}
 
+	/// Returns the value of the general purpose register with the number `n`, as encoded in
+	/// instructions.
+	///
+	/// If the register does not exist, the function returns `0`.
+	pub fn get_register(&self, n: usize) -> usize {
+		let val = match n {
+			0 => self.rax,
+			1 => self.rcx,
+			2 => self.rdx,
+			3 => self.rbx,
+			4 => self.rsp,
+			5 => self.rbp,
+			6 => self.rsi,
+			7 => self.rdi,
+			#[cfg(target_arch = "x86_64")]
+			8 => self.r8,
+			#[cfg(target_arch = "x86_64")]
+			9 => self.r9,
+			#[cfg(target_arch = "x86_64")]
+			10 => self.r10,
+			#[cfg(target_arch = "x86_64")]
+			11 => self.r11,
+			#[cfg(target_arch = "x86_64")]
+			12 => self.r12,
+			#[cfg(target_arch = "x86_64")]
+			13 => self.r13,
+			#[cfg(target_arch = "x86_64")]
+			14 => self.r14,
+			#[cfg(target_arch = "x86_64")]
+			15 => self.r15,
+			_ => 0,
+		};
+		val as _
+	}
+
 	/// Returns the stack address.
 	pub fn get_stack_address(&self) -> usize {
 		self.rsp as usize
@@ -228,8 +263,9 @@ struct InterruptDescriptor {
 	offset0: u16,
 	/// The code segment selector to execute the interrupt.
 	selector: u16,
-	/// Must be set to zero.
-	zero0: u8,
+	/// On x86_64, the index of the Interrupt Stack Table (IST) entry giving the stack to switch
+	/// to. If zero, the stack is not switched. On x86, must be set to zero.
+	ist: u8,
 	/// Interrupt handler flags.
 	flags: u8,
 	/// Bits 16..32 of the address to the handler for the interrupt.
@@ -251,7 +287,7 @@ impl InterruptDescriptor {
 		Self {
 			offset0: 0,
 			selector: 0,
-			zero0: 0,
+			ist: 0,
 			flags: 0,
 			offset1: 0,
 			#[cfg(target_arch = "x86_64")]
@@ -271,7 +307,7 @@ impl InterruptDescriptor {
 		Self {
 			offset0: (address as usize & 0xffff) as u16,
 			selector,
-			zero0: 0,
+			ist: 0,
 			flags,
 			offset1: ((address as usize >> 16) & 0xffff) as u16,
 			#[cfg(target_arch = "x86_64")]
@@ -359,9 +395,7 @@ pub fn wrap_disable_interrupts<T, F: FnOnce() -> T>(f: F) -> T {
 /// Enables the syscall/sysret instruction pairs if available.
 #[cfg(target_arch = "x86_64")]
 fn enable_syscall_inst() {
-	let (_, _, _, mask) = super::cpuid(0x80000001, 0, 0, 0);
-	let available = mask & (1 << 11) != 0;
-	if !available {
+	if !super::features::get().syscall {
 		return;
 	}
 	// STAR
@@ -371,8 +405,8 @@ fn enable_syscall_inst() {
 	);
 	// LSTAR
 	super::wrmsr(0xc0000082, crate::syscall::syscall as usize as u64);
-	// SFMASK (clear direction and interrupt flag)
-	super::wrmsr(0xc0000084, 0x600);
+	// SFMASK (clear alignment check, direction and interrupt flag)
+	super::wrmsr(0xc0000084, 0x40600);
 }
 
 /// Initializes the IDT.
@@ -389,12 +423,30 @@ pub fn init() {
 		IDT_ENTRIES[0x00] = InterruptDescriptor::new(error0 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x01] = InterruptDescriptor::new(error1 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x02] = InterruptDescriptor::new(error2 as _, 0x8, 0x8e);
+		#[cfg(target_arch = "x86_64")]
+		{
+			IDT_ENTRIES[0x02].ist = x86::tss::NMI_IST;
+		}
 		IDT_ENTRIES[0x03] = InterruptDescriptor::new(error3 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x04] = InterruptDescriptor::new(error4 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x05] = InterruptDescriptor::new(error5 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x06] = InterruptDescriptor::new(error6 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x07] = InterruptDescriptor::new(error7 as _, 0x8, 0x8e);
-		IDT_ENTRIES[0x08] = InterruptDescriptor::new(error8 as _, 0x8, 0x8e);
+		// The double fault is delivered on a dedicated stack, since the kernel stack may be the
+		// cause of the fault
+		#[cfg(target_arch = "x86")]
+		{
+			IDT_ENTRIES[0x08] = InterruptDescriptor::new(
+				core::ptr::null(),
+				gdt::DOUBLE_FAULT_TSS_OFFSET as _,
+				0x85,
+			);
+		}
+		#[cfg(target_arch = "x86_64")]
+		{
+			IDT_ENTRIES[0x08] = InterruptDescriptor::new(error8 as _, 0x8, 0x8e);
+			IDT_ENTRIES[0x08].ist = x86::tss::DOUBLE_FAULT_IST;
+		}
 		IDT_ENTRIES[0x09] = InterruptDescriptor::new(error9 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x0a] = InterruptDescriptor::new(error10 as _, 0x8, 0x8e);
 		IDT_ENTRIES[0x0b] = InterruptDescriptor::new(error11 as _, 0x8, 0x8e);
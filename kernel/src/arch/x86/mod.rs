@@ -18,6 +18,7 @@
 
 //! x86-specific code.
 
+pub mod features;
 pub mod gdt;
 #[macro_use]
 pub mod idt;
@@ -37,6 +38,10 @@ pub const IA32_KERNEL_GS_BASE: u32 = 0xc0000102;
 
 /// Process default `rflags`
 pub const DEFAULT_FLAGS: usize = 0x202;
+/// The I/O Privilege Level (IOPL) field of the `EFLAGS` register.
+pub const FLAG_IOPL: usize = 0b11 << 12;
+/// The Alignment Check (AC) flag of the `EFLAGS` register.
+pub const FLAG_AC: usize = 1 << 18;
 /// Process default `FCW`
 pub const DEFAULT_FCW: u32 = 0b1100111111;
 /// Process default `MXCSR`
@@ -141,6 +146,59 @@ pub fn cpuid(mut eax: u32, mut ebx: u32, mut ecx: u32, mut edx: u32) -> (u32, u3
 	(eax, ebx, ecx, edx)
 }
 
+/// Returns the value of the Time Stamp Counter.
+#[inline]
+pub fn rdtsc() -> u64 {
+	#[cfg(target_arch = "x86")]
+	unsafe {
+		core::arch::x86::_rdtsc()
+	}
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		core::arch::x86_64::_rdtsc()
+	}
+}
+
+/// Generates a random number using the `rdrand` instruction.
+///
+/// If the hardware failed to produce a value, the function returns `None`.
+#[inline]
+pub fn rdrand() -> Option<u64> {
+	#[cfg(target_arch = "x86")]
+	unsafe {
+		let mut lo = 0;
+		let mut hi = 0;
+		let ok =
+			core::arch::x86::_rdrand32_step(&mut lo) & core::arch::x86::_rdrand32_step(&mut hi);
+		(ok != 0).then_some(((hi as u64) << 32) | lo as u64)
+	}
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		let mut val = 0;
+		(core::arch::x86_64::_rdrand64_step(&mut val) != 0).then_some(val)
+	}
+}
+
+/// Returns a random seed using the `rdseed` instruction.
+///
+/// If the hardware failed to produce a value, the function returns `None`.
+#[inline]
+pub fn rdseed() -> Option<u64> {
+	#[cfg(target_arch = "x86")]
+	unsafe {
+		let mut lo = 0;
+		let mut hi = 0;
+		let ok =
+			core::arch::x86::_rdseed32_step(&mut lo) & core::arch::x86::_rdseed32_step(&mut hi);
+		(ok != 0).then_some(((hi as u64) << 32) | lo as u64)
+	}
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		let mut val = 0;
+		(core::arch::x86_64::_rdseed64_step(&mut val) != 0).then_some(val)
+	}
+}
+
 /// Read value from a Model Specific Register.
 #[inline]
 pub fn rdmsr(msr: u32) -> u64 {
@@ -174,36 +232,68 @@ pub fn wrmsr(msr: u32, val: u64) {
 	}
 }
 
-/// Returns HWCAP bitmask for ELF.
+/// Sets the value of the extended control register `xcr`.
 #[inline]
-pub fn get_hwcap() -> u32 {
-	cpuid(1, 0, 0, 0).3
+pub fn xsetbv(xcr: u32, val: u64) {
+	let edx = (val >> 32) as u32;
+	let eax = val as u32;
+	unsafe {
+		asm!(
+			"xsetbv",
+			in("ecx") xcr,
+			in("edx") edx,
+			in("eax") eax,
+			options(nostack)
+		);
+	}
 }
 
-/// Tells whether the CPU supports SSE.
-pub fn has_sse() -> bool {
-	get_hwcap() & (1 << 25) != 0
+/// Enables alignment checking, so that userspace can enable it by setting the [`FLAG_AC`] flag.
+pub fn enable_alignment_check() {
+	let cr0 = register_get!("cr0") | (1 << 18);
+	unsafe {
+		register_set!("cr0", cr0);
+	}
 }
 
 /// Enables SSE.
+///
+/// If supported, the `XSAVE` instructions set is enabled too, but only for the x87 and SSE
+/// states since these are the only ones saved on context switch.
 pub fn enable_sse() {
 	// Enable x87 FPU
 	let cr0 = (register_get!("cr0") & !0b100) | 0b10;
 	// Enable FXSAVE and FXRSTOR (thus, enabling SSE) and SSE exceptions
-	let cr4 = register_get!("cr4") | 0b11000000000;
+	let mut cr4 = register_get!("cr4") | features::CR4_OSFXSR | features::CR4_OSXMMEXCPT;
+	let xsave = features::get().xsave;
+	if xsave {
+		cr4 |= features::CR4_OSXSAVE;
+	}
 	unsafe {
 		register_set!("cr0", cr0);
 		register_set!("cr4", cr4);
 	}
+	if xsave {
+		// Enable x87 and SSE states
+		xsetbv(0, 0b11);
+	}
 }
 
-/// Tells whether SMEP and SMAP are supported (in that order).
+/// Sets or clears the Task Switched (TS) flag of `CR0`.
+///
+/// While the flag is set, the first x87, MMX or SSE instruction triggers a *Device Not Available*
+/// exception.
 #[inline]
-pub fn supports_supervisor_prot() -> (bool, bool) {
-	let (_, flags, ..) = cpuid(7, 0, 0, 0);
-	let smep = flags & (1 << 7) != 0;
-	let smap = flags & (1 << 20) != 0;
-	(smep, smap)
+pub fn set_task_switched(ts: bool) {
+	let mut val = register_get!("cr0");
+	if ts {
+		val |= 1 << 3;
+	} else {
+		val &= !(1 << 3);
+	}
+	unsafe {
+		register_set!("cr0", val);
+	}
 }
 
 /// Sets whether the kernel can write to read-only pages.
@@ -233,9 +323,7 @@ pub unsafe fn set_write_protected(lock: bool) {
 /// caller's responsibility to ensure no invalid memory accesses are done afterward.
 #[inline]
 pub unsafe fn set_smap_enabled(enabled: bool) {
-	// TODO cache in RAM instead
-	let (_, smap) = supports_supervisor_prot();
-	if !smap {
+	if !features::get().smap {
 		return;
 	}
 	if enabled {
@@ -250,6 +338,16 @@ pub unsafe fn set_smap_enabled(enabled: bool) {
 #[repr(align(16))]
 pub struct FxState(pub [u8; 512]);
 
+impl Default for FxState {
+	/// Returns the initial state of a process, with floating-point exceptions masked.
+	fn default() -> Self {
+		let mut state = Self([0; 512]);
+		state.0[0..2].copy_from_slice(&(DEFAULT_FCW as u16).to_le_bytes());
+		state.0[24..28].copy_from_slice(&DEFAULT_MXCSR.to_le_bytes());
+		state
+	}
+}
+
 /// Performs the `fxsave` instruction on `fxstate`.
 #[inline]
 pub fn fxsave(fxstate: &mut FxState) {
@@ -19,7 +19,7 @@
 //! x86 virtual memory support.
 
 use crate::{
-	arch::x86::supports_supervisor_prot,
+	arch::x86::features,
 	memory::{buddy, buddy::BUDDY_RETRY, PhysAddr, VirtAddr},
 	register_get, register_set,
 };
@@ -133,24 +133,32 @@ impl DerefMut for Table {
 impl Table {
 	/// Expands the PSE entry at `index` into a new table.
 	///
+	/// `level` is the level of the current table in the tree.
+	///
 	/// This function allocates a new page table and fills it so that the memory mapping keeps the
 	/// same behavior.
-	pub fn expand(&mut self, index: usize) {
+	pub fn expand(&mut self, index: usize, level: usize) {
 		let entry = self[index].load(Relaxed);
-		if entry & FLAG_PRESENT == 0 || entry & FLAG_PAGE_SIZE == 0 {
+		if level == 0 || entry & FLAG_PRESENT == 0 || entry & FLAG_PAGE_SIZE == 0 {
 			return;
 		}
-		let flags = entry & (FLAGS_MASK & !FLAG_PAGE_SIZE);
+		let flags = entry & FLAGS_MASK;
+		// On the last level, the flag does not exist (or has another meaning)
+		let child_flags = if level == 1 {
+			flags & !FLAG_PAGE_SIZE
+		} else {
+			flags
+		};
+		// The size of the memory covered by an entry of the new table
+		let stride = PAGE_SIZE * ENTRIES_PER_TABLE.pow(level as u32 - 1);
 		// Create table
 		let mut new_table = alloc_table();
 		let new_table_ref = unsafe { new_table.as_mut() };
 		new_table_ref.iter_mut().enumerate().for_each(|(i, e)| {
-			// FIXME the stride can be more than PAGE_SIZE depending on whether we are on 32 or 64
-			// bit and the level of the paging object
-			let addr = VirtAddr(entry & ADDR_MASK) + i * PAGE_SIZE;
-			let addr = addr.kernel_to_physical().unwrap();
-			e.store(to_entry(addr, flags), Relaxed);
+			let addr = PhysAddr(entry & ADDR_MASK) + i * stride;
+			e.store(to_entry(addr, child_flags), Relaxed);
 		});
+		let flags = flags & !FLAG_PAGE_SIZE;
 		// Set new entry
 		let addr = VirtAddr::from(new_table).kernel_to_physical().unwrap();
 		self[index].store(to_entry(addr, flags), Relaxed);
@@ -319,7 +327,7 @@ pub unsafe fn map(mut table: &mut Table, physaddr: PhysAddr, virtaddr: VirtAddr,
 			table[index].store(to_entry(addr, flags), Relaxed);
 		} else if previous & FLAG_PAGE_SIZE != 0 {
 			// A PSE entry is present, need to expand it for the mapping
-			table.expand(index);
+			table.expand(index, level);
 		}
 		table[index].fetch_or(flags, Relaxed);
 		// Jump to next table
@@ -339,24 +347,43 @@ pub unsafe fn unmap(mut table: &mut Table, virtaddr: VirtAddr) {
 	let virtaddr = VirtAddr(virtaddr.0 & !(PAGE_SIZE - 1));
 	// Read entries
 	let mut tables: [Option<(NonNull<Table>, usize)>; DEPTH] = [None; DEPTH];
+	let mut leaf = 0;
 	for level in (0..DEPTH).rev() {
 		let index = get_addr_element_index(virtaddr, level);
+		// Only a part of a PSE entry is unmapped, so split it
+		table.expand(index, level);
 		let entry = table[index].load(Relaxed);
-		tables[level] = Some((NonNull::from(table), index));
-		// If the entry does not exist or is PSE, stop here
-		if entry & FLAG_PRESENT == 0 || entry & FLAG_PAGE_SIZE != 0 {
+		tables[level] = Some((NonNull::from(&mut *table), index));
+		leaf = level;
+		// If the entry does not exist, stop here
+		if level == 0 || entry & FLAG_PRESENT == 0 {
 			break;
 		}
 		// Jump to next table
 		table = unsafe { unwrap_entry(entry).0.as_mut() };
 	}
-	// Remove entry and go up to remove tables that are now empty
-	for t in tables {
-		let Some((mut table, index)) = t else {
-			continue;
+	// Remove entry
+	let Some((mut table, index)) = tables[leaf] else {
+		return;
+	};
+	let table = unsafe { table.as_mut() };
+	table[index].store(0, Relaxed);
+	if !table.is_empty() {
+		return;
+	}
+	// Go up to free tables that are now empty
+	for (level, t) in tables.iter().enumerate().skip(leaf + 1) {
+		let Some((mut table, index)) = *t else {
+			break;
 		};
+		if !can_remove_table(level, index) {
+			break;
+		}
 		let table = unsafe { table.as_mut() };
-		table[index].store(0, Relaxed);
+		let entry = table[index].swap(0, Relaxed);
+		unsafe {
+			free_table(unwrap_entry(entry).0);
+		}
 		if !table.is_empty() {
 			break;
 		}
@@ -370,6 +397,26 @@ pub fn poll_dirty(table: &Table, virtaddr: VirtAddr) -> Option<(PhysAddr, bool)>
 	Some((physaddr, entry & FLAG_DIRTY != 0))
 }
 
+/// Inner implementation of [`crate::memory::vmem::VMem::poll_accessed`] for x86.
+pub fn poll_accessed(mut table: &Table, virtaddr: VirtAddr) -> bool {
+	for level in (0..DEPTH).rev() {
+		let index = get_addr_element_index(virtaddr, level);
+		let entry = table[index].load(Relaxed);
+		if entry & FLAG_PRESENT == 0 {
+			break;
+		}
+		if level == 0 || entry & FLAG_PAGE_SIZE != 0 {
+			let prev = table[index].fetch_and(!FLAG_ACCESSED, Relaxed);
+			return prev & FLAG_ACCESSED != 0;
+		}
+		// Jump to next table
+		let phys_addr = PhysAddr(entry & ADDR_MASK);
+		let virt_addr = phys_addr.kernel_to_virtual().unwrap();
+		table = unsafe { &*virt_addr.as_ptr() };
+	}
+	false
+}
+
 /// Binds the given page directory to the current CPU.
 ///
 /// # Safety
@@ -447,14 +494,52 @@ pub(crate) fn prepare() {
 	// Set cr4 flags
 	// Enable GLOBAL flag
 	let mut cr4 = register_get!("cr4") | (1 << 7);
-	let (smep, smap) = supports_supervisor_prot();
-	if smep {
+	let features = features::get();
+	if features.smep {
 		cr4 |= 1 << 20;
 	}
-	if smap {
+	if features.smap {
 		cr4 |= 1 << 21;
 	}
 	unsafe {
 		register_set!("cr4", cr4);
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn paging_split_pse() {
+		let mut dir = alloc();
+		// The size of memory covered by a PSE entry on the first level
+		let size = PAGE_SIZE * ENTRIES_PER_TABLE;
+		let virtaddr = VirtAddr(size);
+		let physaddr = PhysAddr(size * 2);
+		unsafe {
+			map(dir.as_mut(), physaddr, virtaddr, FLAG_WRITE);
+			// Replace the last level table with a PSE entry
+			let mut table = dir;
+			for level in (2..DEPTH).rev() {
+				let index = get_addr_element_index(virtaddr, level);
+				table = unwrap_entry(table.as_ref()[index].load(Relaxed)).0;
+			}
+			let index = get_addr_element_index(virtaddr, 1);
+			let entry = to_entry(physaddr, FLAG_WRITE | FLAG_PAGE_SIZE);
+			let prev = table.as_mut()[index].swap(entry, Relaxed);
+			free_table(unwrap_entry(prev).0);
+			// Unmap a single page in the middle
+			unmap(dir.as_mut(), virtaddr + PAGE_SIZE);
+		}
+		let dir_ref = unsafe { dir.as_ref() };
+		assert_eq!(translate(dir_ref, virtaddr), Some(physaddr));
+		assert_eq!(translate(dir_ref, virtaddr + PAGE_SIZE), None);
+		for off in [PAGE_SIZE * 2, size - PAGE_SIZE] {
+			assert_eq!(translate(dir_ref, virtaddr + off), Some(physaddr + off));
+		}
+		unsafe {
+			free(dir);
+		}
+	}
+}
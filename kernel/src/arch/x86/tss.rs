@@ -27,7 +27,14 @@
 //! instruction `ltr`.
 
 use crate::arch::x86::gdt;
-use core::{arch::asm, mem, ptr::addr_of};
+#[cfg(target_arch = "x86")]
+use crate::register_get;
+use core::{
+	arch::asm,
+	mem,
+	mem::offset_of,
+	ptr::{addr_of, addr_of_mut},
+};
 
 /// Task State Segment.
 #[repr(C)]
@@ -85,15 +92,92 @@ pub struct Tss {
 	pub iopb: u16,
 }
 
+/// The number of I/O ports.
+const PORTS_COUNT: usize = 0x10000;
+/// The size of the I/O permission bitmap in bytes.
+pub const IO_BITMAP_SIZE: usize = PORTS_COUNT / 8;
+
+/// The Task State Segment, followed by the I/O permission bitmap.
+///
+/// In the bitmap, a cleared bit allows access to the corresponding port from userspace.
+#[repr(C)]
+struct TaskState {
+	tss: Tss,
+	/// The I/O permission bitmap. The extra byte is required to be set by the CPU.
+	io_bitmap: [u8; IO_BITMAP_SIZE + 1],
+}
+
 /// The Task State Segment.
 #[no_mangle]
-static mut TSS: Tss = unsafe { mem::zeroed() };
+static mut TSS: TaskState = unsafe { mem::zeroed() };
+
+/// Tells whether the I/O permission bitmap currently loaded in the TSS allows access to any port.
+static mut IO_BITMAP_USED: bool = false;
+
+/// The size of stacks dedicated to exceptions that cannot use the current stack, in bytes.
+const EXCEPTION_STACK_SIZE: usize = 16384;
+
+/// A stack dedicated to the handling of an exception.
+#[repr(C, align(16))]
+struct ExceptionStack([u8; EXCEPTION_STACK_SIZE]);
+
+impl ExceptionStack {
+	/// Returns the address of the top of the stack.
+	fn top(stack: *const Self) -> usize {
+		stack as usize + EXCEPTION_STACK_SIZE
+	}
+}
+
+/// The stack used to handle double faults.
+///
+/// Since double faults can be caused by a kernel stack overflow, the handler cannot run on the
+/// current stack.
+static mut DOUBLE_FAULT_STACK: ExceptionStack = ExceptionStack([0; EXCEPTION_STACK_SIZE]);
+/// The stack used to handle Non-Maskable Interrupts.
+///
+/// Since a NMI can happen at any time, even before the kernel stack is switched, the current
+/// stack cannot be trusted.
+#[cfg(target_arch = "x86_64")]
+static mut NMI_STACK: ExceptionStack = ExceptionStack([0; EXCEPTION_STACK_SIZE]);
+
+/// The Interrupt Stack Table index of the double fault stack.
+#[cfg(target_arch = "x86_64")]
+pub const DOUBLE_FAULT_IST: u8 = 1;
+/// The Interrupt Stack Table index of the NMI stack.
+#[cfg(target_arch = "x86_64")]
+pub const NMI_IST: u8 = 2;
+
+/// The task switched to on double fault.
+///
+/// On x86, there is no Interrupt Stack Table, so the only way to get a known-good stack is to
+/// switch task through a task gate.
+#[cfg(target_arch = "x86")]
+static mut DOUBLE_FAULT_TSS: Tss = unsafe { mem::zeroed() };
+
+/// The entry point of the double fault task.
+#[cfg(target_arch = "x86")]
+extern "C" fn double_fault_task() -> ! {
+	panic!("Double Fault");
+}
 
 /// Initializes the TSS.
 pub(crate) fn init() {
+	// Deny access to all ports by default
+	unsafe {
+		let tss = &mut *addr_of_mut!(TSS);
+		tss.io_bitmap.fill(!0);
+		#[cfg(target_arch = "x86")]
+		{
+			tss.tss.iomap_base = offset_of!(TaskState, io_bitmap) as _;
+		}
+		#[cfg(target_arch = "x86_64")]
+		{
+			tss.tss.iopb = offset_of!(TaskState, io_bitmap) as _;
+		}
+	}
 	let [gdt_entry_low, gdt_entry_high] = gdt::Entry::new64(
 		addr_of!(TSS) as u64,
-		size_of::<Tss>() as u32 - 1,
+		size_of::<TaskState>() as u32 - 1,
 		0b10001001,
 		0,
 	);
@@ -107,6 +191,39 @@ pub(crate) fn init() {
 			off = const gdt::TSS_OFFSET
 		);
 	}
+	// Setup stacks for exceptions
+	#[cfg(target_arch = "x86")]
+	{
+		let cr3 = register_get!("cr3");
+		let tss = unsafe { &mut *addr_of_mut!(DOUBLE_FAULT_TSS) };
+		tss.esp = ExceptionStack::top(addr_of!(DOUBLE_FAULT_STACK)) as _;
+		tss.eip = double_fault_task as usize as _;
+		tss.cr3 = cr3 as _;
+		// Only the reserved bit is set, so that interrupts remain disabled
+		tss.eflags = 0x2;
+		tss.cs = gdt::KERNEL_CS as _;
+		tss.ds = gdt::KERNEL_DS as _;
+		tss.es = gdt::KERNEL_DS as _;
+		tss.fs = gdt::KERNEL_DS as _;
+		tss.gs = gdt::KERNEL_DS as _;
+		tss.ss = gdt::KERNEL_DS as _;
+		tss.iomap_base = size_of::<Tss>() as _;
+		// Overwrites the upper half of the TSS entry, which is not used on x86
+		let entry = gdt::Entry::new(
+			addr_of!(DOUBLE_FAULT_TSS) as u32,
+			size_of::<Tss>() as u32 - 1,
+			0b10001001,
+			0,
+		);
+		unsafe {
+			entry.update_gdt(gdt::DOUBLE_FAULT_TSS_OFFSET);
+		}
+	}
+	#[cfg(target_arch = "x86_64")]
+	unsafe {
+		TSS.tss.ist1 = ExceptionStack::top(addr_of!(DOUBLE_FAULT_STACK)) as _;
+		TSS.tss.ist2 = ExceptionStack::top(addr_of!(NMI_STACK)) as _;
+	}
 }
 
 /// Sets the kernel stack pointer on the TSS.
@@ -117,12 +234,47 @@ pub(crate) fn init() {
 pub unsafe fn set_kernel_stack(kernel_stack: *mut u8) {
 	#[cfg(target_arch = "x86")]
 	{
-		TSS.esp0 = kernel_stack as _;
-		TSS.ss0 = gdt::KERNEL_DS as _;
-		TSS.ss = gdt::USER_DS as _;
+		TSS.tss.esp0 = kernel_stack as _;
+		TSS.tss.ss0 = gdt::KERNEL_DS as _;
+		TSS.tss.ss = gdt::USER_DS as _;
 	}
 	#[cfg(target_arch = "x86_64")]
 	{
-		TSS.rsp0 = kernel_stack as _;
+		TSS.tss.rsp0 = kernel_stack as _;
+	}
+}
+
+/// Loads the I/O permission bitmap `bitmap` in the TSS.
+///
+/// If `bitmap` is shorter than [`IO_BITMAP_SIZE`], the remaining ports are denied. If empty,
+/// access to all ports is denied.
+///
+/// # Safety
+///
+/// This function is **not** reentrant.
+pub unsafe fn set_io_bitmap(bitmap: &[u8]) {
+	// Avoid rewriting the whole bitmap when not necessary
+	if bitmap.is_empty() && !IO_BITMAP_USED {
+		return;
+	}
+	let tss = &mut *addr_of_mut!(TSS);
+	let len = bitmap.len().min(IO_BITMAP_SIZE);
+	tss.io_bitmap[..len].copy_from_slice(&bitmap[..len]);
+	tss.io_bitmap[len..].fill(!0);
+	IO_BITMAP_USED = !bitmap.is_empty();
+}
+
+#[cfg(all(test, target_arch = "x86_64"))]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn exception_stacks() {
+		let (ist1, ist2) = unsafe { (TSS.tss.ist1, TSS.tss.ist2) };
+		assert_eq!(
+			ist1 as usize,
+			ExceptionStack::top(addr_of!(DOUBLE_FAULT_STACK))
+		);
+		assert_eq!(ist2 as usize, ExceptionStack::top(addr_of!(NMI_STACK)));
 	}
 }
@@ -18,7 +18,7 @@
 
 //! Boot-time kernel command line arguments parsing.
 
-use crate::tty::vga;
+use crate::{multiboot::BOOT_INFO, tty::vga};
 use core::{cmp::min, fmt, str};
 use utils::DisplayableStr;
 
@@ -29,6 +29,26 @@ fn parse_nbr(slice: &[u8]) -> Option<u32> {
 	str::from_utf8(slice).ok().and_then(|s| s.parse().ok())
 }
 
+/// The root device given on the command line.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RootDev<'s> {
+	/// The major and minor numbers of the device.
+	Number(u32, u32),
+	/// The path to the device file, such as `/dev/sda1`.
+	Path(&'s [u8]),
+}
+
+/// Parses a root device given either as `<major>:<minor>` or as the path to the device file.
+fn parse_dev(slice: &[u8]) -> Option<RootDev<'_>> {
+	if slice.starts_with(b"/") {
+		return Some(RootDev::Path(slice));
+	}
+	let i = slice.iter().position(|c| *c == b':')?;
+	let major = parse_nbr(&slice[..i])?;
+	let minor = parse_nbr(&slice[(i + 1)..])?;
+	Some(RootDev::Number(major, minor))
+}
+
 /// Structure representing a command line parsing error.
 #[derive(Debug)]
 pub struct ParseError<'s> {
@@ -121,16 +141,71 @@ impl<'s> Iterator for TokenIterator<'s> {
 	}
 }
 
+/// Returns an iterator over the tokens of the given command line.
+fn tokens(cmdline: &[u8]) -> TokenIterator<'_> {
+	TokenIterator {
+		s: cmdline,
+		cursor: 0,
+	}
+}
+
+/// Splits the given `key=value` token. If the token has no `=`, the function returns `None`.
+fn split_key_value(token: &[u8]) -> Option<(&[u8], &[u8])> {
+	let i = token.iter().position(|c| *c == b'=')?;
+	Some((&token[..i], &token[(i + 1)..]))
+}
+
+/// Returns the value of the last `key=value` argument with the given key in `cmdline`.
+fn find_value<'s>(cmdline: &'s [u8], key: &[u8]) -> Option<&'s [u8]> {
+	tokens(cmdline)
+		.filter_map(|tok| split_key_value(tok.s))
+		.filter(|(k, _)| *k == key)
+		.map(|(_, v)| v)
+		.last()
+}
+
+/// Tells whether `cmdline` contains the bare flag `name`.
+fn find_flag(cmdline: &[u8], name: &[u8]) -> bool {
+	tokens(cmdline).any(|tok| tok.s == name)
+}
+
+/// Returns the kernel command line given by the bootloader.
+fn boot_cmdline() -> &'static [u8] {
+	BOOT_INFO.cmdline.unwrap_or_default()
+}
+
+/// Returns the value of the `key=value` argument with the given key on the kernel command line.
+///
+/// If the key is present several times, the last value is returned.
+pub fn get(key: &str) -> Option<&'static [u8]> {
+	find_value(boot_cmdline(), key.as_bytes())
+}
+
+/// Tells whether the bare flag `name` is present on the kernel command line.
+pub fn flag(name: &str) -> bool {
+	find_flag(boot_cmdline(), name.as_bytes())
+}
+
 /// Command line argument parser.
 ///
+/// Both the legacy `-name <args>` syntax and `key=value` arguments or bare flags are accepted.
+/// Unknown `key=value` arguments and bare flags are ignored, so that they can be retrieved with
+/// [`get`] and [`flag`].
+///
 /// Every bytes in the command line are interpreted as ASCII characters.
 pub struct ArgsParser<'s> {
-	/// The root device major and minor numbers.
-	root: Option<(u32, u32)>,
+	/// The root device.
+	root: Option<RootDev<'s>>,
 	/// The path to the init binary, if specified.
 	init: Option<&'s [u8]>,
+	/// Whether the root filesystem is mounted read-only.
+	rdonly: bool,
 	/// Whether the kernel boots silently.
 	silent: bool,
+	/// Whether system calls of the init process (and its descendants) are traced.
+	strace: bool,
+	/// Whether Address Space Layout Randomization is disabled.
+	noaslr: bool,
 }
 
 impl<'s> ArgsParser<'s> {
@@ -139,14 +214,13 @@ impl<'s> ArgsParser<'s> {
 		let mut s = Self {
 			root: None,
 			init: None,
+			rdonly: false,
 			silent: false,
+			strace: false,
+			noaslr: false,
 		};
 
-		let mut iter = TokenIterator {
-			s: cmdline,
-			cursor: 0,
-		}
-		.enumerate();
+		let mut iter = tokens(cmdline).enumerate();
 		loop {
 			let Some((i, token)) = iter.next() else {
 				break;
@@ -176,7 +250,7 @@ impl<'s> ArgsParser<'s> {
 							token: Some((i + 2, 1)),
 						});
 					};
-					s.root = Some((major, minor));
+					s.root = Some(RootDev::Number(major, minor));
 				}
 
 				b"-init" => {
@@ -192,21 +266,45 @@ impl<'s> ArgsParser<'s> {
 
 				b"-silent" => s.silent = true,
 
-				_ => {
+				b"-noaslr" => s.noaslr = true,
+
+				#[cfg(feature = "strace")]
+				b"-strace" => s.strace = true,
+
+				b"quiet" => s.silent = true,
+				b"ro" => s.rdonly = true,
+				b"rw" => s.rdonly = false,
+
+				tok if tok.starts_with(b"-") => {
 					return Err(ParseError {
 						cmdline,
 						err: "invalid argument",
 						token: Some((token.begin, token.s.len())),
 					});
 				}
+
+				tok => match split_key_value(tok) {
+					Some((b"root", dev)) => {
+						let Some(dev) = parse_dev(dev) else {
+							return Err(ParseError {
+								cmdline,
+								err: "invalid root device (expected `<major>:<minor>` or a path)",
+								token: Some((token.begin, token.s.len())),
+							});
+						};
+						s.root = Some(dev);
+					}
+					Some((b"init", init)) => s.init = Some(init),
+					_ => {}
+				},
 			}
 		}
 
 		Ok(s)
 	}
 
-	/// Returns the major and minor numbers of the root device.
-	pub fn get_root_dev(&self) -> Option<(u32, u32)> {
+	/// Returns the root device.
+	pub fn get_root_dev(&self) -> Option<RootDev<'s>> {
 		self.root
 	}
 
@@ -215,10 +313,27 @@ impl<'s> ArgsParser<'s> {
 		self.init
 	}
 
+	/// If `true`, the root filesystem is mounted read-only.
+	pub fn is_rdonly(&self) -> bool {
+		self.rdonly
+	}
+
 	/// If `true`, the kernel doesn't print logs while booting.
 	pub fn is_silent(&self) -> bool {
 		self.silent
 	}
+
+	/// If `true`, system calls of the init process and its descendants are traced.
+	///
+	/// This option is available only with the `strace` feature.
+	pub fn is_strace(&self) -> bool {
+		self.strace
+	}
+
+	/// If `true`, Address Space Layout Randomization is disabled.
+	pub fn is_noaslr(&self) -> bool {
+		self.noaslr
+	}
 }
 
 #[cfg(test)]
@@ -264,4 +379,45 @@ mod test {
 	fn cmdline7() {
 		assert!(ArgsParser::parse(b"-root 1 0 -init bleh -silent").is_ok());
 	}
+
+	#[cfg(feature = "strace")]
+	#[test_case]
+	fn cmdline8() {
+		assert!(ArgsParser::parse(b"-root 1 0 -strace").unwrap().is_strace());
+	}
+
+	#[test_case]
+	fn cmdline9() {
+		let args = ArgsParser::parse(b"-root 1 0 -noaslr").unwrap();
+		assert!(args.is_noaslr());
+		assert!(!ArgsParser::parse(b"-root 1 0").unwrap().is_noaslr());
+	}
+
+	#[test_case]
+	fn cmdline_key_value() {
+		let args = ArgsParser::parse(b"root=8:1 init=/bin/sh quiet ro foo=bar").unwrap();
+		assert_eq!(args.get_root_dev(), Some(RootDev::Number(8, 1)));
+		let args = ArgsParser::parse(b"root=/dev/sda1").unwrap();
+		assert_eq!(args.get_root_dev(), Some(RootDev::Path(b"/dev/sda1")));
+		assert_eq!(args.get_init_path(), Some(b"/bin/sh".as_slice()));
+		assert!(args.is_silent());
+		assert!(args.is_rdonly());
+		assert!(!ArgsParser::parse(b"ro rw").unwrap().is_rdonly());
+		assert!(ArgsParser::parse(b"root=8").is_err());
+		assert!(ArgsParser::parse(b"root=8:x").is_err());
+	}
+
+	#[test_case]
+	fn cmdline_get_flag() {
+		let cmdline = b"root=8:0 console=ttyS0 quiet init=/sbin/a init=/sbin/b empty=";
+		assert_eq!(find_value(cmdline, b"root"), Some(b"8:0".as_slice()));
+		assert_eq!(find_value(cmdline, b"console"), Some(b"ttyS0".as_slice()));
+		assert_eq!(find_value(cmdline, b"init"), Some(b"/sbin/b".as_slice()));
+		assert_eq!(find_value(cmdline, b"empty"), Some(b"".as_slice()));
+		assert_eq!(find_value(cmdline, b"quiet"), None);
+		assert_eq!(find_value(cmdline, b"roo"), None);
+		assert!(find_flag(cmdline, b"quiet"));
+		assert!(!find_flag(cmdline, b"root"));
+		assert!(!find_flag(cmdline, b"ro"));
+	}
 }
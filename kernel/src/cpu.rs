@@ -0,0 +1,80 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! CPU cores management.
+//!
+//! Cores are registered at boot from the ACPI tables. Each core is given an ID, starting from
+//! `0` for the boot core, in the order of registration.
+//!
+//! For now, only the boot core runs.
+
+use core::sync::atomic::{
+	AtomicU8, AtomicUsize,
+	Ordering::{Acquire, Relaxed, Release},
+};
+
+/// The maximum number of CPU cores supported by the kernel.
+pub const CPU_MAX: usize = 64;
+
+/// A set of CPU cores, where bit `n` represents the core with ID `n`.
+pub type CpuSet = u64;
+
+/// The number of registered cores.
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+/// The local APIC ID of each registered core.
+static APIC_IDS: [AtomicU8; CPU_MAX] = [const { AtomicU8::new(0) }; CPU_MAX];
+
+/// Registers a new core with the local APIC ID `apic_id`.
+///
+/// If the maximum number of cores has been reached, the core is ignored.
+pub fn add_core(apic_id: u8) {
+	let id = COUNT.load(Acquire);
+	if id >= CPU_MAX {
+		return;
+	}
+	APIC_IDS[id].store(apic_id, Relaxed);
+	COUNT.store(id + 1, Release);
+}
+
+/// Returns the number of registered cores.
+///
+/// The boot core is always accounted for, even if not registered.
+pub fn count() -> usize {
+	COUNT.load(Acquire).max(1)
+}
+
+/// Returns the local APIC ID of the core with ID `id`.
+///
+/// If the core does not exist, the function returns `None`.
+pub fn apic_id(id: usize) -> Option<u8> {
+	(id < COUNT.load(Acquire)).then(|| APIC_IDS[id].load(Relaxed))
+}
+
+/// Returns the set of online cores.
+///
+/// The boot core is always online.
+pub fn online() -> CpuSet {
+	// TODO only mark the boot core online until other cores are started
+	CpuSet::MAX >> (CPU_MAX - count())
+}
+
+/// Returns the ID of the current core.
+pub fn current() -> usize {
+	// TODO read the local APIC ID when other cores are started
+	0
+}
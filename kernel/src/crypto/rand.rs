@@ -18,7 +18,13 @@
 
 //! This module implements randomness functions.
 
-use crate::{crypto::chacha20, sync::mutex::IntMutex};
+use crate::{
+	arch::x86::{features, rdrand, rdseed, rdtsc},
+	crypto::chacha20,
+	sync::mutex::IntMutex,
+	time::clock::{current_time_ns, Clock},
+};
+use core::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use utils::{
 	collections::{ring_buffer::RingBuffer, vec::Vec},
 	errno::AllocResult,
@@ -30,6 +36,51 @@ const ENTROPY_BUFFER_SIZE: usize = 32768;
 /// The minimum number of bytes needed to read entropy.
 const ENTROPY_THRESHOLD: usize = 1024;
 
+/// The number of times to retry reading the CPU's random number generator before giving up.
+const HW_RETRIES: usize = 10;
+/// The number of values read from the CPU's random number generator to check its health.
+const HW_HEALTH_SAMPLES: usize = 16;
+/// The number of interrupts between two reseeds of the pool from the CPU's random number
+/// generator.
+const HW_RESEED_INTERVAL: u32 = 1024;
+/// The size of a chunk of data fed to the pool from the CPU's random number generator, in
+/// bytes. This is the amount of data consumed by one encoding round.
+const HW_CHUNK_SIZE: usize = 56;
+
+/// Tells whether the CPU's random number generator passed the health check and can be trusted.
+static HW_RNG: AtomicBool = AtomicBool::new(false);
+
+/// Returns a random value from the CPU's random number generator, preferring `rdseed` over
+/// `rdrand` when available.
+///
+/// If no value could be produced, the function returns `None`.
+fn hw_random() -> Option<u64> {
+	let features = features::get();
+	(0..HW_RETRIES).find_map(|_| {
+		features
+			.rdseed
+			.then(rdseed)
+			.flatten()
+			.or_else(|| features.rdrand.then(rdrand).flatten())
+	})
+}
+
+/// Checks the CPU's random number generator is available and does not produce stuck values.
+fn hw_health_check() -> bool {
+	let mut prev = None;
+	for _ in 0..HW_HEALTH_SAMPLES {
+		let Some(val) = hw_random() else {
+			return false;
+		};
+		// Some faulty CPUs return all bits cleared or set
+		if val == 0 || val == u64::MAX || prev == Some(val) {
+			return false;
+		}
+		prev = Some(val);
+	}
+	true
+}
+
 // TODO Implement entropy extraction (Fast Key Erasure?)
 
 /// An entropy pool.
@@ -45,6 +96,9 @@ pub struct EntropyPool {
 
 	/// The seed to be used for pseudo-random generation (when the pool runs out of entropy).
 	pseudo_seed: u64,
+	/// The number of interrupts remaining before the next reseed from the CPU's random number
+	/// generator.
+	hw_countdown: u32,
 }
 
 impl EntropyPool {
@@ -57,6 +111,7 @@ impl EntropyPool {
 			counter: 0,
 
 			pseudo_seed: 0,
+			hw_countdown: HW_RESEED_INTERVAL,
 		})
 	}
 
@@ -82,7 +137,8 @@ impl EntropyPool {
 			let mut seed = self.pseudo_seed;
 			for b in buff.iter_mut() {
 				seed = 6364136223846793005u64.wrapping_mul(seed).wrapping_add(1);
-				*b = (seed & 0xff) as _;
+				// Use the high bits, since the low bits of a LCG have a short period
+				*b = (seed >> 56) as _;
 			}
 			self.pseudo_seed = seed;
 			buff.len()
@@ -142,13 +198,124 @@ impl EntropyPool {
 		}
 		buff.len() - off
 	}
+
+	/// Feeds at least `len` bytes from the CPU's random number generator to the pool.
+	///
+	/// If the generator is not usable, the function does nothing.
+	fn feed_hw(&mut self, len: usize) {
+		if !HW_RNG.load(Relaxed) {
+			return;
+		}
+		for _ in 0..len.div_ceil(HW_CHUNK_SIZE) {
+			let mut chunk = [0; HW_CHUNK_SIZE];
+			for word in chunk.chunks_exact_mut(8) {
+				let Some(val) = hw_random() else {
+					return;
+				};
+				word.copy_from_slice(&val.to_ne_bytes());
+			}
+			self.write(&chunk);
+		}
+	}
+
+	/// Must be called on each interrupt, to periodically reseed the pool from the CPU's random
+	/// number generator.
+	pub fn tick(&mut self) {
+		self.hw_countdown -= 1;
+		if self.hw_countdown == 0 {
+			self.hw_countdown = HW_RESEED_INTERVAL;
+			self.feed_hw(HW_CHUNK_SIZE);
+		}
+	}
 }
 
 /// The entropy pool.
 pub static ENTROPY_POOL: IntMutex<Option<EntropyPool>> = IntMutex::new(None);
 
+/// Returns a random value for use before the entropy pool is initialized.
+///
+/// The value comes from the CPU's random number generator if available, else from the Time Stamp
+/// Counter, mixed so that consecutive values do not look alike.
+fn early_random() -> u64 {
+	hw_random().unwrap_or_else(|| {
+		// SplitMix64 finalizer
+		let mut val = rdtsc().wrapping_add(0x9e3779b97f4a7c15);
+		val = (val ^ (val >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+		val = (val ^ (val >> 27)).wrapping_mul(0x94d049bb133111eb);
+		val ^ (val >> 31)
+	})
+}
+
+/// Fills `buf` with random bytes.
+///
+/// If the entropy pool does not contain enough entropy, or is not initialized yet, pseudo-random
+/// bytes are used, so the function shall not be used where cryptographically secure randomness is
+/// required.
+pub fn getrandom(buf: &mut [u8]) {
+	let mut pool = ENTROPY_POOL.lock();
+	let Some(pool) = &mut *pool else {
+		for chunk in buf.chunks_mut(8) {
+			let val = early_random().to_ne_bytes();
+			chunk.copy_from_slice(&val[..chunk.len()]);
+		}
+		return;
+	};
+	let mut i = 0;
+	while i < buf.len() {
+		i += pool.read(&mut buf[i..], true);
+	}
+}
+
+/// Returns a value to seed the pseudo-random generator at boot.
+fn boot_seed() -> u64 {
+	let time = current_time_ns(Clock::Realtime);
+	// Use the Time Stamp Counter, since the boot time is likely to be the same across boots
+	let tsc = rdtsc();
+	time ^ tsc.rotate_left(32)
+}
+
 /// Initializes randomness sources.
 pub(super) fn init() -> AllocResult<()> {
-	*ENTROPY_POOL.lock() = Some(EntropyPool::new()?);
+	let mut pool = EntropyPool::new()?;
+	pool.pseudo_seed = boot_seed();
+	HW_RNG.store(hw_health_check(), Relaxed);
+	// Fill the pool so that entropy is available right away
+	pool.feed_hw(ENTROPY_THRESHOLD * 2);
+	*ENTROPY_POOL.lock() = Some(pool);
 	Ok(())
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn hw_rng() {
+		if !hw_health_check() {
+			// The CPU does not expose a usable random number generator
+			return;
+		}
+		let a = hw_random().unwrap();
+		let b = hw_random().unwrap();
+		assert_ne!(a, b);
+		// Check the values are mixed into the pool
+		HW_RNG.store(true, Relaxed);
+		let mut pool = EntropyPool::new().unwrap();
+		pool.feed_hw(ENTROPY_THRESHOLD);
+		assert!(pool.available_bytes() >= ENTROPY_THRESHOLD);
+		let mut buf = [0; 64];
+		assert_eq!(pool.read(&mut buf, false), buf.len());
+		assert!(buf.iter().any(|b| *b != 0));
+	}
+
+	#[test_case]
+	fn getrandom_bytes() {
+		// Unit tests run before the entropy pool is initialized
+		let mut a = [0; 20];
+		let mut b = [0; 20];
+		getrandom(&mut a);
+		getrandom(&mut b);
+		assert_ne!(a, [0; 20]);
+		assert_ne!(a, b);
+	}
+}
@@ -29,7 +29,7 @@
 //! communications through DMA (Direct Memory Access).
 
 use crate::{
-	arch::x86::io::{inl, outl},
+	arch::x86::io::{inl, outb, outl},
 	device::{
 		bar::{BARType, BAR},
 		manager,
@@ -113,6 +113,23 @@ fn read_long(bus: u8, device: u8, func: u8, reg_off: u8) -> u32 {
 	}
 }
 
+/// Writes the byte `value` at the offset `off` (in bytes) in the configuration space of the
+/// function specified by `bus`, `device` and `func`.
+pub fn write_config_byte(bus: u8, device: u8, func: u8, off: u8, value: u8) {
+	// The PCI address, aligned on the register
+	let addr = ((bus as u32) << 16)
+		| ((device as u32) << 11)
+		| ((func as u32) << 8)
+		| (off as u32 & 0xfc)
+		| 0x80000000;
+	unsafe {
+		// Set the address
+		outl(CONFIG_ADDRESS_PORT, addr);
+		// Write the byte at its position in the register
+		outb(CONFIG_DATA_PORT + (off & 0b11) as u16, value);
+	}
+}
+
 /// Writes 32 bits from `value` into the PCI register specified by `bus`,
 /// `device`, `func` and `reg_off`.
 fn write_long(bus: u8, device: u8, func: u8, reg_off: u8, value: u32) {
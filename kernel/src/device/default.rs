@@ -21,7 +21,12 @@
 use super::{id, register_char, CharDev, DeviceType};
 use crate::{
 	crypto::rand,
-	device::{tty::TTYDeviceHandle, DeviceID},
+	device::{
+		mem::{MemDeviceHandle, PortDeviceHandle},
+		pty::{PtmxDeviceHandle, PTS_MAJOR},
+		tty::TTYDeviceHandle,
+		DeviceID,
+	},
 	file::{fs::FileOps, File},
 	logger::LOGGER,
 };
@@ -139,6 +144,15 @@ impl FileOps for KMsgDeviceHandle {
 /// Creates the default devices.
 pub(super) fn create() -> EResult<()> {
 	let _first_major = ManuallyDrop::new(id::alloc_major(DeviceType::Char, Some(1))?);
+	register_char(CharDev::new(
+		DeviceID {
+			major: 1,
+			minor: 1,
+		},
+		PathBuf::try_from(b"/dev/mem")?,
+		0o600,
+		MemDeviceHandle,
+	)?)?;
 	register_char(CharDev::new(
 		DeviceID {
 			major: 1,
@@ -148,6 +162,15 @@ pub(super) fn create() -> EResult<()> {
 		0o666,
 		NullDeviceHandle,
 	)?)?;
+	register_char(CharDev::new(
+		DeviceID {
+			major: 1,
+			minor: 4,
+		},
+		PathBuf::try_from(b"/dev/port")?,
+		0o600,
+		PortDeviceHandle,
+	)?)?;
 	register_char(CharDev::new(
 		DeviceID {
 			major: 1,
@@ -195,6 +218,17 @@ pub(super) fn create() -> EResult<()> {
 		0o666,
 		TTYDeviceHandle,
 	)?)?;
+	register_char(CharDev::new(
+		DeviceID {
+			major: 5,
+			minor: 2,
+		},
+		PathBuf::try_from(b"/dev/ptmx")?,
+		0o666,
+		PtmxDeviceHandle,
+	)?)?;
+
+	let _pts_major = ManuallyDrop::new(id::alloc_major(DeviceType::Char, Some(PTS_MAJOR))?);
 
 	Ok(())
 }
@@ -0,0 +1,259 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Request merging elevator for block devices.
+//!
+//! Pending requests are kept sorted by offset. A new request is merged with a pending one when
+//! both are contiguous and go in the same direction, so that the driver performs one larger
+//! transfer instead of several small ones.
+//!
+//! Requests are dispatched in C-LOOK order: the head of the device moves towards increasing
+//! offsets, then jumps back to the lowest pending offset, reducing the number of seeks.
+
+use crate::memory::cache::RcFrame;
+use core::{fmt, fmt::Formatter};
+use utils::{
+	boxed::Box,
+	collections::vec::Vec,
+	errno::{AllocResult, EResult},
+};
+
+/// Callback called once a request has completed, with the result of the transfer.
+pub type Completion = Box<dyn FnMut(EResult<()>)>;
+
+/// Returns the key ordering a request at offset `off` when the head of the device is at `head`.
+///
+/// Requests located at or after the head come first, in increasing order, then the others.
+#[inline]
+pub fn look_key(head: u64, off: u64) -> (bool, u64) {
+	(off < head, off)
+}
+
+/// A transfer between memory and a block device.
+pub struct Request {
+	/// The offset of the first page on the device.
+	pub off: u64,
+	/// Tells whether the request writes to the device.
+	pub write: bool,
+	/// The frames to transfer, one per page, in device order.
+	pub frames: Vec<RcFrame>,
+	/// The callbacks of the requests merged into this one.
+	completions: Vec<Completion>,
+}
+
+impl Request {
+	/// Creates a request transferring `frames` at the page offset `off` on the device.
+	///
+	/// `completion` is called once the request has completed.
+	pub fn new<F: 'static + FnMut(EResult<()>)>(
+		off: u64,
+		write: bool,
+		frames: Vec<RcFrame>,
+		completion: F,
+	) -> AllocResult<Self> {
+		let mut completions = Vec::new();
+		completions.push(Box::new(completion)? as Completion)?;
+		Ok(Self {
+			off,
+			write,
+			frames,
+			completions,
+		})
+	}
+
+	/// Returns the number of pages of the request.
+	#[inline]
+	pub fn count(&self) -> u64 {
+		self.frames.len() as _
+	}
+
+	/// Returns the offset of the page following the request.
+	#[inline]
+	pub fn end(&self) -> u64 {
+		self.off + self.count()
+	}
+
+	/// Tells whether `next` can be appended to `self` without exceeding `max_pages`.
+	fn can_merge(&self, next: &Self, max_pages: usize) -> bool {
+		self.write == next.write
+			&& self.end() == next.off
+			&& self.frames.len() + next.frames.len() <= max_pages
+	}
+
+	/// Moves the content of the contiguous request `next` at the end of `self`.
+	///
+	/// On failure, both requests are left unchanged.
+	fn merge(&mut self, next: &mut Self) -> AllocResult<()> {
+		self.frames.reserve(next.frames.len())?;
+		self.completions.reserve(next.completions.len())?;
+		self.frames.append(&mut next.frames)?;
+		self.completions.append(&mut next.completions)?;
+		Ok(())
+	}
+
+	/// Completes the request with the result `res`, calling the callbacks of all the requests
+	/// merged into it.
+	pub fn complete(mut self, res: EResult<()>) {
+		for completion in self.completions.iter_mut() {
+			completion(res);
+		}
+	}
+}
+
+impl fmt::Debug for Request {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Request")
+			.field("off", &self.off)
+			.field("count", &self.count())
+			.field("write", &self.write)
+			.field("merged", &self.completions.len())
+			.finish()
+	}
+}
+
+/// A queue of pending requests, merging and ordering them.
+#[derive(Debug)]
+pub struct Elevator {
+	/// The pending requests, sorted by offset.
+	pending: Vec<Request>,
+	/// The offset following the last dispatched request.
+	head: u64,
+	/// The maximum number of pages in a merged request.
+	max_pages: usize,
+}
+
+impl Elevator {
+	/// Creates a new empty elevator, merging requests up to `max_pages` pages.
+	pub const fn new(max_pages: usize) -> Self {
+		Self {
+			pending: Vec::new(),
+			head: 0,
+			max_pages,
+		}
+	}
+
+	/// Tells whether no request is pending.
+	#[inline]
+	pub fn is_empty(&self) -> bool {
+		self.pending.is_empty()
+	}
+
+	/// Inserts `req` in the queue, merging it with the contiguous pending requests.
+	pub fn insert(&mut self, mut req: Request) -> AllocResult<()> {
+		let i = self.pending.partition_point(|r| r.off < req.off);
+		// If merging fails, the requests are simply left apart
+		if let Some(next) = self.pending.get_mut(i) {
+			if req.can_merge(next, self.max_pages) && req.merge(next).is_ok() {
+				self.pending.remove(i);
+			}
+		}
+		if let Some(prev) = i.checked_sub(1).map(|i| &mut self.pending[i]) {
+			if prev.can_merge(&req, self.max_pages) && prev.merge(&mut req).is_ok() {
+				return Ok(());
+			}
+		}
+		self.pending.insert(i, req)
+	}
+
+	/// Removes the next request to be serviced from the queue and returns it.
+	pub fn dispatch(&mut self) -> Option<Request> {
+		if self.pending.is_empty() {
+			return None;
+		}
+		// Since requests are sorted, this is the request with the lowest `look_key`
+		let mut i = self.pending.partition_point(|r| r.off < self.head);
+		if i == self.pending.len() {
+			i = 0;
+		}
+		let req = self.pending.remove(i);
+		self.head = req.end();
+		Some(req)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::memory::{buddy::ZONE_KERNEL, cache::FrameOwner};
+	use core::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+	/// The number of completed requests.
+	static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+
+	/// Returns a single page request at offset `off`.
+	fn request(off: u64, write: bool) -> Request {
+		let mut frames = Vec::new();
+		frames
+			.push(RcFrame::new(0, ZONE_KERNEL, FrameOwner::Anon, off).unwrap())
+			.unwrap();
+		Request::new(off, write, frames, |res| {
+			assert!(res.is_ok());
+			COMPLETED.fetch_add(1, Relaxed);
+		})
+		.unwrap()
+	}
+
+	/// Dispatches all the requests of `elevator`, completing them and returning their offset and
+	/// size.
+	fn dispatch_all(elevator: &mut Elevator) -> Vec<(u64, u64)> {
+		let mut ops = Vec::new();
+		while let Some(req) = elevator.dispatch() {
+			ops.push((req.off, req.count())).unwrap();
+			req.complete(Ok(()));
+		}
+		ops
+	}
+
+	#[test_case]
+	fn elevator_merge() {
+		COMPLETED.store(0, Relaxed);
+		let mut elevator = Elevator::new(4);
+		for off in [7, 2, 12, 3, 8, 1, 13, 4, 20, 5] {
+			elevator.insert(request(off, false)).unwrap();
+		}
+		// A write is not merged with reads
+		elevator.insert(request(9, true)).unwrap();
+		let ops = dispatch_all(&mut elevator);
+		assert_eq!(
+			ops.as_slice(),
+			&[(1, 4), (5, 1), (7, 2), (9, 1), (12, 2), (20, 1)]
+		);
+		// Every merged request is completed
+		assert_eq!(COMPLETED.load(Relaxed), 11);
+	}
+
+	#[test_case]
+	fn elevator_look() {
+		let mut elevator = Elevator::new(4);
+		for off in [30, 10, 50] {
+			elevator.insert(request(off, false)).unwrap();
+		}
+		let req = elevator.dispatch().unwrap();
+		assert_eq!(req.off, 10);
+		req.complete(Ok(()));
+		// Requests behind the head are serviced on the next sweep
+		for off in [5, 40, 20] {
+			elevator.insert(request(off, false)).unwrap();
+		}
+		let ops = dispatch_all(&mut elevator);
+		assert_eq!(
+			ops.as_slice(),
+			&[(20, 1), (30, 1), (40, 1), (50, 1), (5, 1)]
+		);
+	}
+}
@@ -0,0 +1,458 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Input event devices (evdev) give userspace a generic interface to input peripherals.
+//!
+//! Each device is exposed as `/dev/input/eventN`. Reading it returns [`InputEvent`] records,
+//! grouped in frames terminated by a `SYN_REPORT` event. The device's capabilities can be
+//! queried with the `EVIOCGBIT` ioctl.
+
+use crate::{
+	device::{id, register_char, CharDev, DeviceID, DeviceType},
+	file::{fs::FileOps, wait_queue, wait_queue::WaitQueue, File, O_NONBLOCK},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::IntMutex,
+	syscall::{
+		ioctl,
+		poll::{POLLIN, POLLOUT},
+		FromSyscallArg,
+	},
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::{TimeUnit, Timeval},
+	},
+};
+use core::{
+	cmp::min,
+	ffi::{c_int, c_void},
+	fmt,
+	mem::{size_of, ManuallyDrop},
+	sync::atomic::{AtomicU32, Ordering::Relaxed},
+};
+use utils::{
+	bytes, collections::ring_buffer::RingBuffer, errno, errno::EResult, format, ptr::arc::Arc,
+};
+
+/// The major number of input devices.
+pub const MAJOR: u32 = 13;
+/// The minor number of the first event device.
+const EVENT_MINOR_BASE: u32 = 64;
+/// The maximum number of event devices.
+const EVENT_DEVICES_MAX: u32 = 32;
+
+/// The version of the evdev protocol.
+const EV_VERSION: c_int = 0x010001;
+
+/// Event type: synchronization.
+pub const EV_SYN: u16 = 0x00;
+/// Event type: key or button.
+pub const EV_KEY: u16 = 0x01;
+/// Event type: relative movement.
+pub const EV_REL: u16 = 0x02;
+/// The maximum event type.
+const EV_MAX: u16 = 0x1f;
+
+/// Synchronization event: end of a frame of events.
+pub const SYN_REPORT: u16 = 0;
+/// Synchronization event: events have been dropped because the buffer was full.
+pub const SYN_DROPPED: u16 = 3;
+
+/// Button code: left mouse button.
+pub const BTN_LEFT: u16 = 0x110;
+/// Button code: right mouse button.
+pub const BTN_RIGHT: u16 = 0x111;
+/// Button code: middle mouse button.
+pub const BTN_MIDDLE: u16 = 0x112;
+/// The maximum key code.
+const KEY_MAX: u16 = 0x2ff;
+
+/// Relative axis: X.
+pub const REL_X: u16 = 0x00;
+/// Relative axis: Y.
+pub const REL_Y: u16 = 0x01;
+/// Relative axis: scroll wheel.
+pub const REL_WHEEL: u16 = 0x08;
+/// The maximum relative axis.
+const REL_MAX: u16 = 0x0f;
+
+/// Bus type: i8042 controller.
+pub const BUS_I8042: u16 = 0x11;
+
+/// ioctl type of evdev requests.
+const EVIOC_TYPE: u8 = b'E';
+/// ioctl number: returns the protocol version.
+const EVIOCGVERSION: u8 = 0x01;
+/// ioctl number: returns the device's ID.
+const EVIOCGID: u8 = 0x02;
+/// ioctl number: returns the device's name.
+const EVIOCGNAME: u8 = 0x06;
+/// ioctl number: returns the state of keys.
+const EVIOCGKEY: u8 = 0x18;
+/// ioctl number: returns the codes supported for an event type, added to the number.
+const EVIOCGBIT: u8 = 0x20;
+
+/// The number of events the buffer can hold.
+const BUFFER_SIZE: usize = 64;
+
+/// Returns the size of a bitmap able to hold bits `0` to `max` included.
+const fn bitmap_size(max: u16) -> usize {
+	max as usize / 8 + 1
+}
+
+/// Sets bit `bit` in `bitmap`.
+fn set_bit(bitmap: &mut [u8], bit: u16) {
+	bitmap[bit as usize / 8] |= 1 << (bit % 8);
+}
+
+/// Tells whether bit `bit` is set in `bitmap`.
+fn get_bit(bitmap: &[u8], bit: u16) -> bool {
+	bitmap
+		.get(bit as usize / 8)
+		.map(|b| b & (1 << (bit % 8)) != 0)
+		.unwrap_or(false)
+}
+
+/// An input event, as read from an event device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputEvent {
+	/// The time at which the event happened.
+	pub time: Timeval,
+	/// The type of event.
+	pub type_: u16,
+	/// The code of the event, depending on its type.
+	pub code: u16,
+	/// The value of the event, depending on its type.
+	pub value: i32,
+}
+
+/// An empty event, used to initialize buffers.
+const EMPTY_EVENT: InputEvent = InputEvent {
+	time: Timeval {
+		tv_sec: 0,
+		tv_usec: 0,
+	},
+	type_: 0,
+	code: 0,
+	value: 0,
+};
+
+/// The identifier of an input device.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct InputId {
+	/// The bus the device is connected to.
+	pub bustype: u16,
+	/// The vendor ID.
+	pub vendor: u16,
+	/// The product ID.
+	pub product: u16,
+	/// The version of the product.
+	pub version: u16,
+}
+
+/// The mutable state of an input device.
+struct InputState {
+	/// The buffer of events to be read.
+	events: RingBuffer<InputEvent, [InputEvent; BUFFER_SIZE]>,
+	/// The bitmap of currently pressed keys.
+	keys: [u8; bitmap_size(KEY_MAX)],
+	/// Tells whether events have been emitted since the last `SYN_REPORT`.
+	pending: bool,
+}
+
+/// An input device, feeding an event device.
+pub struct InputDevice {
+	/// The device's name.
+	name: &'static str,
+	/// The device's ID.
+	id: InputId,
+	/// The bitmap of supported keys.
+	key_bits: [u8; bitmap_size(KEY_MAX)],
+	/// The bitmap of supported relative axes.
+	rel_bits: [u8; bitmap_size(REL_MAX)],
+
+	/// The device's state.
+	state: IntMutex<InputState>,
+	/// The queue of processes waiting for events.
+	queue: WaitQueue,
+}
+
+impl InputDevice {
+	/// Creates a new device, with no capabilities.
+	pub fn new(name: &'static str, id: InputId) -> Self {
+		Self {
+			name,
+			id,
+			key_bits: [0; bitmap_size(KEY_MAX)],
+			rel_bits: [0; bitmap_size(REL_MAX)],
+
+			state: IntMutex::new(InputState {
+				events: RingBuffer::new([EMPTY_EVENT; BUFFER_SIZE]),
+				keys: [0; bitmap_size(KEY_MAX)],
+				pending: false,
+			}),
+			queue: WaitQueue::new(),
+		}
+	}
+
+	/// Declares the device can report the key or button `code`.
+	pub fn set_key_bit(&mut self, code: u16) {
+		set_bit(&mut self.key_bits, code);
+	}
+
+	/// Declares the device can report movements on the relative axis `code`.
+	pub fn set_rel_bit(&mut self, code: u16) {
+		set_bit(&mut self.rel_bits, code);
+	}
+
+	/// Returns the bitmap of supported codes for the event type `ev`.
+	fn get_bits(&self, ev: u16) -> ([u8; 4], &[u8]) {
+		let mut types = [0; 4];
+		if ev != 0 {
+			let bits: &[u8] = match ev {
+				EV_KEY => &self.key_bits,
+				EV_REL => &self.rel_bits,
+				_ => &[],
+			};
+			return (types, bits);
+		}
+		set_bit(&mut types, EV_SYN);
+		if self.key_bits.iter().any(|b| *b != 0) {
+			set_bit(&mut types, EV_KEY);
+		}
+		if self.rel_bits.iter().any(|b| *b != 0) {
+			set_bit(&mut types, EV_REL);
+		}
+		(types, &[])
+	}
+
+	/// Pushes an event in the buffer.
+	///
+	/// If the buffer is full, pending events are dropped and replaced by a `SYN_DROPPED` event.
+	fn push(state: &mut InputState, type_: u16, code: u16, value: i32) {
+		let time = Timeval::from_nano(current_time_ns(Clock::Realtime));
+		if state.events.get_available_len() == 0 {
+			state.events.clear();
+			state.events.write(&[InputEvent {
+				time,
+				type_: EV_SYN,
+				code: SYN_DROPPED,
+				value: 0,
+			}]);
+		}
+		state.events.write(&[InputEvent {
+			time,
+			type_,
+			code,
+			value,
+		}]);
+		state.pending = type_ != EV_SYN;
+	}
+
+	/// Reports the state of the key or button `code`.
+	///
+	/// Pressing a key that is already pressed reports a repetition.
+	pub fn report_key(&self, code: u16, pressed: bool) {
+		if !get_bit(&self.key_bits, code) {
+			return;
+		}
+		let mut state = self.state.lock();
+		let was_pressed = get_bit(&state.keys, code);
+		let value = match (pressed, was_pressed) {
+			(true, true) => 2,
+			(true, false) => 1,
+			(false, true) => 0,
+			(false, false) => return,
+		};
+		state.keys[code as usize / 8] ^= ((pressed != was_pressed) as u8) << (code % 8);
+		Self::push(&mut state, EV_KEY, code, value);
+	}
+
+	/// Reports a movement of `value` on the relative axis `code`.
+	pub fn report_rel(&self, code: u16, value: i32) {
+		if value == 0 || !get_bit(&self.rel_bits, code) {
+			return;
+		}
+		Self::push(&mut self.state.lock(), EV_REL, code, value);
+	}
+
+	/// Terminates the current frame of events and wakes up readers.
+	///
+	/// If no event has been reported since the last frame, the function does nothing.
+	pub fn sync(&self) {
+		{
+			let mut state = self.state.lock();
+			if !state.pending {
+				return;
+			}
+			Self::push(&mut state, EV_SYN, SYN_REPORT, 0);
+		}
+		self.queue.wake_all();
+		wait_queue::poll_notify();
+	}
+
+	/// Reads events into `buf`, returning the number of events read.
+	fn read_events(&self, buf: &mut [InputEvent]) -> usize {
+		self.state.lock().events.read(buf)
+	}
+}
+
+impl fmt::Debug for InputDevice {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("InputDevice")
+			.field("name", &self.name)
+			.field("id", &self.id)
+			.finish()
+	}
+}
+
+/// Handle for an event device.
+#[derive(Debug)]
+pub struct EventDeviceHandle(Arc<InputDevice>);
+
+impl FileOps for EventDeviceHandle {
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let mut events = [EMPTY_EVENT; 8];
+		let count = min(buf.len() / size_of::<InputEvent>(), events.len());
+		if count == 0 {
+			return Err(errno!(EINVAL));
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		let count =
+			self.0
+				.queue
+				.wait_until(|| match self.0.read_events(&mut events[..count]) {
+					0 if nonblock => Some(Err(errno!(EAGAIN))),
+					0 => None,
+					count => Some(Ok(count)),
+				})??;
+		let src = bytes::as_bytes(&events[..count]);
+		buf[..src.len()].copy_from_slice(src);
+		Ok(src.len())
+	}
+
+	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
+		Err(errno!(EINVAL))
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let input = !self.0.state.lock().events.is_empty();
+		let res = (if input { POLLIN } else { 0 } | POLLOUT) & mask;
+		Ok(res)
+	}
+
+	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
+		if request.major != EVIOC_TYPE || request.direction != ioctl::Direction::Read {
+			return Err(errno!(EINVAL));
+		}
+		// Copies `src` to the userspace buffer, truncated to the request's size
+		let copy = |src: &[u8]| {
+			let len = min(src.len(), request.size);
+			SyscallSlice::<u8>::from_ptr(argp as usize).copy_to_user(0, &src[..len])?;
+			Ok(len as u32)
+		};
+		match request.minor {
+			EVIOCGVERSION => {
+				SyscallPtr::<c_int>::from_ptr(argp as usize).copy_to_user(&EV_VERSION)?;
+				Ok(0)
+			}
+			EVIOCGID => {
+				SyscallPtr::<InputId>::from_ptr(argp as usize).copy_to_user(&self.0.id)?;
+				Ok(0)
+			}
+			EVIOCGNAME => {
+				// Include the terminating nul byte
+				let name = format!("{}\0", self.0.name)?;
+				copy(name.as_bytes())
+			}
+			EVIOCGKEY => {
+				let keys = self.0.state.lock().keys;
+				copy(&keys)
+			}
+			n if (EVIOCGBIT..=EVIOCGBIT + EV_MAX as u8).contains(&n) => {
+				let (types, bits) = self.0.get_bits((n - EVIOCGBIT) as u16);
+				if n == EVIOCGBIT {
+					copy(&types)
+				} else {
+					copy(bits)
+				}
+			}
+			_ => Err(errno!(EINVAL)),
+		}
+	}
+}
+
+/// The number of event devices registered so far.
+static EVENT_DEVICES_COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// Registers the input device `dev`, creating its event device file.
+pub fn register(dev: InputDevice) -> EResult<Arc<InputDevice>> {
+	let n = EVENT_DEVICES_COUNT.fetch_add(1, Relaxed);
+	if n >= EVENT_DEVICES_MAX {
+		return Err(errno!(ENOSPC));
+	}
+	let dev = Arc::new(dev)?;
+	register_char(CharDev::new(
+		DeviceID {
+			major: MAJOR,
+			minor: EVENT_MINOR_BASE + n,
+		},
+		format!("/dev/input/event{n}")?.try_into()?,
+		0o660,
+		EventDeviceHandle(dev.clone()),
+	)?)?;
+	Ok(dev)
+}
+
+/// Initializes input devices management.
+pub(crate) fn init() -> EResult<()> {
+	let _major = ManuallyDrop::new(id::alloc_major(DeviceType::Char, Some(MAJOR))?);
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn input_keypress() {
+		let mut dev = InputDevice::new("test", InputId::default());
+		dev.set_key_bit(30);
+		// Unsupported keys are ignored
+		dev.report_key(31, true);
+		dev.sync();
+		dev.report_key(30, true);
+		dev.sync();
+		dev.report_key(30, true);
+		dev.report_key(30, false);
+		dev.sync();
+		let expected = [
+			(EV_KEY, 30, 1),
+			(EV_SYN, SYN_REPORT, 0),
+			(EV_KEY, 30, 2),
+			(EV_KEY, 30, 0),
+			(EV_SYN, SYN_REPORT, 0),
+		];
+		let mut events = [EMPTY_EVENT; 8];
+		assert_eq!(dev.read_events(&mut events), expected.len());
+		for (e, expected) in events.iter().zip(expected) {
+			assert_eq!((e.type_, e.code, e.value), expected);
+		}
+	}
+}
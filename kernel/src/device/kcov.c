@@ -0,0 +1,45 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+/*
+ * Coverage hook for `kcov`. This file is written in C so that the hook is not itself instrumented
+ * by the compiler.
+ */
+
+#ifdef CONFIG_KCOV
+
+#include <stddef.h>
+
+/* The trace buffer of the current process, or NULL if tracing is disabled. */
+extern size_t *volatile kcov_area;
+/* The number of entries in the trace buffer, including the counter. */
+extern volatile size_t kcov_size;
+
+void __sanitizer_cov_trace_pc(void)
+{
+	size_t *area = kcov_area;
+	if (!area)
+		return;
+	size_t pos = area[0] + 1;
+	if (pos < kcov_size) {
+		area[pos] = (size_t) __builtin_return_address(0);
+		area[0] = pos;
+	}
+}
+
+#endif
@@ -0,0 +1,231 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! `kcov` collects the kernel's code coverage, to measure how much of the kernel a test exercises.
+//!
+//! When compiled with coverage instrumentation, the compiler inserts a call to
+//! `__sanitizer_cov_trace_pc` on each edge of the control flow graph. If tracing is enabled for
+//! the current process, the hook appends the address of its caller to the process's trace buffer.
+//! The hook is written in C (see `kcov.c`) so that it is not instrumented itself.
+//!
+//! A process collects coverage through `/dev/kcov`:
+//! - [`ioctl::KCOV_INIT_TRACE`] allocates a trace buffer, with the given number of entries
+//! - the buffer is mapped in memory with `mmap`
+//! - [`ioctl::KCOV_ENABLE`] starts tracing the calling process, until [`ioctl::KCOV_DISABLE`] or
+//!   until it exits
+//!
+//! The first entry of the buffer is the number of recorded program counters, which follow it. The
+//! process resets it to start a new trace. Interruptions handled while the process is running
+//! are recorded as well.
+
+use crate::{
+	device::{id, register_char, CharDev, DeviceID, DeviceType},
+	file::{fs::FileOps, File},
+	memory::{buddy, buddy::FrameOrder, PhysAddr, VirtAddr},
+	process::Process,
+	sync::mutex::Mutex,
+	syscall::ioctl,
+};
+use core::{
+	ffi::c_void,
+	mem::ManuallyDrop,
+	ptr,
+	ptr::NonNull,
+	sync::atomic::{
+		AtomicBool, AtomicPtr, AtomicUsize,
+		Ordering::{Relaxed, Release},
+	},
+};
+use utils::{collections::path::PathBuf, errno, errno::EResult, limits::PAGE_SIZE, ptr::arc::Arc};
+
+/// Trace mode: record program counters.
+const KCOV_TRACE_PC: usize = 0;
+
+/// The trace buffer of the current process, or null if tracing is disabled. Read by the hook.
+#[export_name = "kcov_area"]
+static AREA: AtomicPtr<usize> = AtomicPtr::new(ptr::null_mut());
+/// The number of entries in [`AREA`], including the counter. Read by the hook.
+#[export_name = "kcov_size"]
+static SIZE: AtomicUsize = AtomicUsize::new(0);
+
+/// A trace buffer.
+#[derive(Debug)]
+pub struct Area {
+	/// The beginning of the buffer.
+	ptr: NonNull<usize>,
+	/// The order of the buffer's allocation.
+	order: FrameOrder,
+	/// The number of entries in the buffer, including the counter.
+	size: usize,
+	/// Tells whether a process is being traced into the buffer.
+	enabled: AtomicBool,
+}
+
+impl Area {
+	/// Allocates a zeroed buffer of `size` entries.
+	fn new(size: usize) -> EResult<Self> {
+		if size < 2 {
+			return Err(errno!(EINVAL));
+		}
+		let pages = size
+			.checked_mul(size_of::<usize>())
+			.ok_or_else(|| errno!(EINVAL))?
+			.div_ceil(PAGE_SIZE);
+		let order = buddy::get_order(pages);
+		if order > buddy::MAX_ORDER {
+			return Err(errno!(EINVAL));
+		}
+		let ptr = buddy::alloc_kernel(order, 0)?;
+		unsafe {
+			ptr.write_bytes(0, PAGE_SIZE << order);
+		}
+		Ok(Self {
+			ptr: ptr.cast(),
+			order,
+			size,
+			enabled: AtomicBool::new(false),
+		})
+	}
+
+	/// Returns the number of pages of the buffer that can be mapped.
+	fn pages(&self) -> usize {
+		(self.size * size_of::<usize>()).div_ceil(PAGE_SIZE)
+	}
+}
+
+impl Drop for Area {
+	fn drop(&mut self) {
+		unsafe {
+			buddy::free_kernel(self.ptr.as_ptr().cast(), self.order);
+		}
+	}
+}
+
+/// Sets the buffer the hook records into, disabling recording if `None`.
+fn set_current(area: Option<&Area>) {
+	match area {
+		Some(area) => {
+			SIZE.store(area.size, Relaxed);
+			AREA.store(area.ptr.as_ptr(), Release);
+		}
+		None => AREA.store(ptr::null_mut(), Release),
+	}
+}
+
+/// Binds the trace buffer of `next`, which is about to run.
+pub fn switch(next: &Process) {
+	set_current(next.kcov.lock().as_deref());
+}
+
+/// Stops tracing `proc`, which is exiting.
+pub fn task_exit(proc: &Process) {
+	let Some(area) = proc.kcov.lock().take() else {
+		return;
+	};
+	area.enabled.store(false, Relaxed);
+	if ptr::eq(proc, &*Process::current()) {
+		set_current(None);
+	}
+}
+
+/// Handle for `/dev/kcov`.
+#[derive(Debug)]
+pub struct KcovDeviceHandle;
+
+impl FileOps for KcovDeviceHandle {
+	fn open(&self) -> EResult<Option<Arc<dyn FileOps>>> {
+		Ok(Some(Arc::new(KcovHandle::default())?))
+	}
+}
+
+/// Handle for an open file of `/dev/kcov`, owning a trace buffer.
+#[derive(Debug, Default)]
+pub struct KcovHandle(Mutex<Option<Arc<Area>>>);
+
+impl FileOps for KcovHandle {
+	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
+		match request.get_old_format() {
+			ioctl::KCOV_INIT_TRACE => {
+				let mut area = self.0.lock();
+				if area.is_some() {
+					return Err(errno!(EBUSY));
+				}
+				*area = Some(Arc::new(Area::new(argp as usize)?)?);
+			}
+			ioctl::KCOV_ENABLE => {
+				if argp as usize != KCOV_TRACE_PC {
+					return Err(errno!(EINVAL));
+				}
+				let area = self.0.lock().clone().ok_or_else(|| errno!(EINVAL))?;
+				let proc = Process::current();
+				let mut kcov = proc.kcov.lock();
+				// A process traces into one buffer, and a buffer receives the trace of one process
+				if kcov.is_some() || area.enabled.swap(true, Relaxed) {
+					return Err(errno!(EBUSY));
+				}
+				set_current(Some(&area));
+				*kcov = Some(area);
+			}
+			ioctl::KCOV_DISABLE => {
+				let area = self.0.lock();
+				let proc = Process::current();
+				let mut kcov = proc.kcov.lock();
+				// Only the process tracing into this file's buffer can disable it
+				let (Some(area), Some(cur)) = (&*area, &*kcov) else {
+					return Err(errno!(EINVAL));
+				};
+				if !ptr::eq::<Area>(&**area, &**cur) {
+					return Err(errno!(EINVAL));
+				}
+				set_current(None);
+				area.enabled.store(false, Relaxed);
+				*kcov = None;
+			}
+			_ => return Err(errno!(EINVAL)),
+		}
+		Ok(0)
+	}
+
+	fn phys_page(&self, _file: &File, off: u64) -> EResult<PhysAddr> {
+		let area = self.0.lock();
+		let area = area.as_ref().ok_or_else(|| errno!(EINVAL))?;
+		let off = usize::try_from(off)
+			.ok()
+			.filter(|off| *off < area.pages())
+			.ok_or_else(|| errno!(EINVAL))?;
+		let ptr = unsafe { area.ptr.cast::<u8>().add(off * PAGE_SIZE) };
+		VirtAddr::from(ptr.as_ptr())
+			.kernel_to_physical()
+			.ok_or_else(|| errno!(EINVAL))
+	}
+}
+
+/// Creates the `/dev/kcov` device.
+pub(super) fn create() -> EResult<()> {
+	let major = ManuallyDrop::new(id::alloc_major(DeviceType::Char, None)?);
+	register_char(CharDev::new(
+		DeviceID {
+			major: major.get_major(),
+			minor: 0,
+		},
+		PathBuf::try_from(b"/dev/kcov")?,
+		0o600,
+		KcovDeviceHandle,
+	)?)?;
+	Ok(())
+}
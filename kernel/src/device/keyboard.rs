@@ -19,10 +19,33 @@
 //! Implementation of the keyboard device manager.
 
 use crate::{
-	device::manager::{DeviceManager, PhysicalDevice},
+	device::{
+		input,
+		input::{InputDevice, InputId, BUS_I8042},
+		manager::{DeviceManager, PhysicalDevice},
+	},
 	tty::TTY,
 };
-use utils::errno::EResult;
+use core::ops::RangeInclusive;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+/// The input codes of keys in [`KeyboardKey`].
+const INPUT_CODES: &[RangeInclusive<u16>] = &[
+	1..=83,
+	87..=88,
+	96..=100,
+	102..=111,
+	113..=116,
+	119..=119,
+	125..=128,
+	140..=140,
+	142..=143,
+	155..=159,
+	163..=166,
+	172..=173,
+	217..=217,
+	226..=226,
+];
 
 /// Enumeration of keyboard keys.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -158,6 +181,137 @@ pub enum KeyboardKey {
 }
 
 impl KeyboardKey {
+	/// Returns the key's code for input event devices.
+	pub fn get_input_code(&self) -> u16 {
+		match self {
+			Self::KeyEsc => 1,
+			Self::Key1 => 2,
+			Self::Key2 => 3,
+			Self::Key3 => 4,
+			Self::Key4 => 5,
+			Self::Key5 => 6,
+			Self::Key6 => 7,
+			Self::Key7 => 8,
+			Self::Key8 => 9,
+			Self::Key9 => 10,
+			Self::Key0 => 11,
+			Self::KeyMinus => 12,
+			Self::KeyEqual => 13,
+			Self::KeyBackspace => 14,
+			Self::KeyTab => 15,
+			Self::KeyQ => 16,
+			Self::KeyW => 17,
+			Self::KeyE => 18,
+			Self::KeyR => 19,
+			Self::KeyT => 20,
+			Self::KeyY => 21,
+			Self::KeyU => 22,
+			Self::KeyI => 23,
+			Self::KeyO => 24,
+			Self::KeyP => 25,
+			Self::KeyOpenBrace => 26,
+			Self::KeyCloseBrace => 27,
+			Self::KeyEnter => 28,
+			Self::KeyLeftControl => 29,
+			Self::KeyA => 30,
+			Self::KeyS => 31,
+			Self::KeyD => 32,
+			Self::KeyF => 33,
+			Self::KeyG => 34,
+			Self::KeyH => 35,
+			Self::KeyJ => 36,
+			Self::KeyK => 37,
+			Self::KeyL => 38,
+			Self::KeySemiColon => 39,
+			Self::KeySingleQuote => 40,
+			Self::KeyBackTick => 41,
+			Self::KeyLeftShift => 42,
+			Self::KeyBackslash => 43,
+			Self::KeyZ => 44,
+			Self::KeyX => 45,
+			Self::KeyC => 46,
+			Self::KeyV => 47,
+			Self::KeyB => 48,
+			Self::KeyN => 49,
+			Self::KeyM => 50,
+			Self::KeyComma => 51,
+			Self::KeyDot => 52,
+			Self::KeySlash => 53,
+			Self::KeyRightShift => 54,
+			Self::KeyKeypadStar => 55,
+			Self::KeyLeftAlt => 56,
+			Self::KeySpace => 57,
+			Self::KeyCapsLock => 58,
+			Self::KeyF1 => 59,
+			Self::KeyF2 => 60,
+			Self::KeyF3 => 61,
+			Self::KeyF4 => 62,
+			Self::KeyF5 => 63,
+			Self::KeyF6 => 64,
+			Self::KeyF7 => 65,
+			Self::KeyF8 => 66,
+			Self::KeyF9 => 67,
+			Self::KeyF10 => 68,
+			Self::KeyNumberLock => 69,
+			Self::KeyScrollLock => 70,
+			Self::KeyKeypad7 => 71,
+			Self::KeyKeypad8 => 72,
+			Self::KeyKeypad9 => 73,
+			Self::KeyKeypadMinus => 74,
+			Self::KeyKeypad4 => 75,
+			Self::KeyKeypad5 => 76,
+			Self::KeyKeypad6 => 77,
+			Self::KeyKeypadPlus => 78,
+			Self::KeyKeypad1 => 79,
+			Self::KeyKeypad2 => 80,
+			Self::KeyKeypad3 => 81,
+			Self::KeyKeypad0 => 82,
+			Self::KeyKeypadDot => 83,
+			Self::KeyF11 => 87,
+			Self::KeyF12 => 88,
+			Self::KeyPreviousTrack => 165,
+			Self::KeyNextTrack => 163,
+			Self::KeyKeypadEnter => 96,
+			Self::KeyRightControl => 97,
+			Self::KeyMute => 113,
+			Self::KeyCalculator => 140,
+			Self::KeyPlay => 164,
+			Self::KeyStop => 166,
+			Self::KeyVolumeDown => 114,
+			Self::KeyVolumeUp => 115,
+			Self::KeyWWWHome => 172,
+			Self::KeyKeypadSlash => 98,
+			Self::KeyRightAlt => 100,
+			Self::KeyHome => 102,
+			Self::KeyCursorUp => 103,
+			Self::KeyPageUp => 104,
+			Self::KeyCursorLeft => 105,
+			Self::KeyCursorRight => 106,
+			Self::KeyEnd => 107,
+			Self::KeyCursorDown => 108,
+			Self::KeyPageDown => 109,
+			Self::KeyInsert => 110,
+			Self::KeyDelete => 111,
+			Self::KeyLeftGUI => 125,
+			Self::KeyRightGUI => 126,
+			Self::KeyApps => 127,
+			Self::KeyACPIPower => 116,
+			Self::KeyACPISleep => 142,
+			Self::KeyACPIWake => 143,
+			Self::KeyWWWSearch => 217,
+			Self::KeyWWWFavorites => 156,
+			Self::KeyWWWRefresh => 173,
+			Self::KeyWWWStop => 128,
+			Self::KeyWWWForward => 159,
+			Self::KeyWWWBack => 158,
+			Self::KeyMyComputer => 157,
+			Self::KeyEmail => 155,
+			Self::KeyMediaSelect => 226,
+			Self::KeyPrintScreen => 99,
+			Self::KeyPause => 119,
+		}
+	}
+
 	// TODO Implement correctly with modifiers
 	/// Returns the TTY characters for the given current.
 	///
@@ -505,13 +659,15 @@ pub struct KeyboardManager {
 	caps_lock: EnableKey,
 	/// The scroll lock state.
 	scroll_lock: EnableKey,
+
+	/// The input device reporting key events.
+	input: Option<Arc<InputDevice>>,
 }
 
 impl KeyboardManager {
 	/// Creates a new instance.
-	#[allow(clippy::new_without_default)]
-	pub fn new() -> Self {
-		let s = Self {
+	pub fn new() -> EResult<Self> {
+		let mut s = Self {
 			ctrl: false,
 			left_shift: false,
 			right_shift: false,
@@ -522,14 +678,29 @@ impl KeyboardManager {
 			number_lock: EnableKey::default(),
 			caps_lock: EnableKey::default(),
 			scroll_lock: EnableKey::default(),
+
+			input: None,
 		};
-		s.init_device_files();
-		s
+		s.init_device_files()?;
+		Ok(s)
 	}
 
 	/// Initializes devices files.
-	fn init_device_files(&self) {
-		// TODO Create /dev/input/event* files
+	fn init_device_files(&mut self) -> EResult<()> {
+		let mut dev = InputDevice::new(
+			"AT Translated Set 2 keyboard",
+			InputId {
+				bustype: BUS_I8042,
+				vendor: 0x1,
+				product: 0x1,
+				version: 0xab41,
+			},
+		);
+		for code in INPUT_CODES.iter().cloned().flatten() {
+			dev.set_key_bit(code);
+		}
+		self.input = Some(input::register(dev)?);
+		Ok(())
 	}
 
 	/// Destroys devices files.
@@ -563,6 +734,11 @@ impl KeyboardManager {
 			self.set_led(KeyboardLED::ScrollLock, self.scroll_lock.is_enabled());
 		}
 
+		if let Some(input) = &self.input {
+			input.report_key(key.get_input_code(), action == KeyboardAction::Pressed);
+			input.sync();
+		}
+
 		if action == KeyboardAction::Pressed {
 			let ctrl = self.ctrl || self.right_ctrl;
 			let alt = self.alt || self.right_alt;
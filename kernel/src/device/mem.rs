@@ -0,0 +1,152 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `/dev/mem` and `/dev/port` devices give privileged processes access to physical memory and
+//! I/O ports.
+//!
+//! On `/dev/mem`, the file offset is the physical address to access. On `/dev/port`, it is the
+//! I/O port.
+//!
+//! When lockdown is enabled (`/proc/sys/kernel/devmem_lockdown`), `/dev/mem` refuses to access
+//! RAM, leaving only reserved regions and device memory available.
+
+use crate::{
+	arch::x86::io::{inb, outb},
+	file::{fs::FileOps, File},
+	memory::{memmap, mmio::MMIO, PhysAddr},
+	process::Process,
+};
+use core::{
+	cmp::min,
+	intrinsics::unlikely,
+	sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+use utils::{errno, errno::EResult, limits::PAGE_SIZE};
+
+/// The number of I/O ports.
+const PORTS_COUNT: u64 = 0x10000;
+
+/// Tells whether access to RAM through `/dev/mem` is refused.
+static LOCKDOWN: AtomicBool = AtomicBool::new(false);
+
+/// Tells whether lockdown is enabled.
+pub fn is_lockdown() -> bool {
+	LOCKDOWN.load(Relaxed)
+}
+
+/// Enables or disables lockdown.
+pub fn set_lockdown(lockdown: bool) {
+	LOCKDOWN.store(lockdown, Relaxed);
+}
+
+/// Checks the current process is privileged.
+///
+/// If not, the function returns [`errno::EPERM`].
+fn check_privileged() -> EResult<()> {
+	if Process::current().fs.lock().access_profile.is_privileged() {
+		Ok(())
+	} else {
+		Err(errno!(EPERM))
+	}
+}
+
+/// Checks access to the physical page at `addr` is allowed.
+fn check_page(addr: PhysAddr) -> EResult<()> {
+	if unlikely(is_lockdown() && memmap::is_ram(addr)) {
+		return Err(errno!(EPERM));
+	}
+	Ok(())
+}
+
+/// Performs an access on physical memory at `off`, for `len` bytes.
+///
+/// Each page is temporarily mapped, then `f` is called with the offset in the buffer, and a slice
+/// over the accessed part of the page.
+fn access_phys<F: FnMut(usize, &mut [u8])>(off: u64, len: usize, mut f: F) -> EResult<usize> {
+	let mut i = 0;
+	while i < len {
+		let addr = usize::try_from(off + i as u64).map_err(|_| errno!(EINVAL))?;
+		let page = PhysAddr(addr).down_align_to(PAGE_SIZE);
+		check_page(page)?;
+		let page_off = addr - page.0;
+		let l = min(len - i, PAGE_SIZE - page_off);
+		let mmio = MMIO::new(page, 1, false)?;
+		let slice =
+			unsafe { core::slice::from_raw_parts_mut(mmio.as_ptr().as_ptr().add(page_off), l) };
+		f(i, slice);
+		i += l;
+	}
+	Ok(len)
+}
+
+/// Device giving access to physical memory.
+#[derive(Debug)]
+pub struct MemDeviceHandle;
+
+impl FileOps for MemDeviceHandle {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		check_privileged()?;
+		access_phys(off, buf.len(), |i, page| {
+			buf[i..(i + page.len())].copy_from_slice(page)
+		})
+	}
+
+	fn write(&self, _file: &File, off: u64, buf: &[u8]) -> EResult<usize> {
+		check_privileged()?;
+		access_phys(off, buf.len(), |i, page| {
+			page.copy_from_slice(&buf[i..(i + page.len())])
+		})
+	}
+
+	fn phys_page(&self, _file: &File, off: u64) -> EResult<PhysAddr> {
+		check_privileged()?;
+		let addr = off
+			.checked_mul(PAGE_SIZE as u64)
+			.and_then(|addr| usize::try_from(addr).ok())
+			.ok_or_else(|| errno!(EINVAL))?;
+		let addr = PhysAddr(addr);
+		check_page(addr)?;
+		Ok(addr)
+	}
+}
+
+/// Device giving access to I/O ports.
+#[derive(Debug)]
+pub struct PortDeviceHandle;
+
+impl FileOps for PortDeviceHandle {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		check_privileged()?;
+		let len = min(PORTS_COUNT.saturating_sub(off), buf.len() as u64) as usize;
+		for (i, b) in buf[..len].iter_mut().enumerate() {
+			*b = unsafe { inb(off as u16 + i as u16) };
+		}
+		Ok(len)
+	}
+
+	fn write(&self, _file: &File, off: u64, buf: &[u8]) -> EResult<usize> {
+		check_privileged()?;
+		let len = min(PORTS_COUNT.saturating_sub(off), buf.len() as u64) as usize;
+		for (i, b) in buf[..len].iter().enumerate() {
+			unsafe {
+				outb(off as u16 + i as u16, *b);
+			}
+		}
+		Ok(len)
+	}
+}
@@ -34,9 +34,17 @@
 pub mod bar;
 pub mod bus;
 pub mod default;
+pub mod elevator;
 pub mod id;
+pub mod input;
+#[cfg(feature = "kcov")]
+pub mod kcov;
 pub mod keyboard;
 pub mod manager;
+pub mod mem;
+pub mod mouse;
+pub mod pty;
+pub mod queue;
 pub mod serial;
 pub mod storage;
 pub mod tty;
@@ -59,13 +67,16 @@ use crate::{
 	syscall::ioctl,
 };
 use core::{ffi::c_void, fmt, intrinsics::likely, num::NonZeroU64};
+use elevator::{Elevator, Request};
 use keyboard::KeyboardManager;
+use queue::RequestQueue;
 use storage::StorageManager;
 use utils::{
 	boxed::Box,
 	collections::{
 		hashmap::HashMap,
 		path::{Path, PathBuf},
+		vec::Vec,
 	},
 	errno,
 	errno::{AllocResult, EResult, ENOENT},
@@ -188,6 +199,29 @@ pub trait BlockDeviceOps: fmt::Debug {
 	/// `off` is the offset of the frame on the device, in pages.
 	fn write_pages(&self, off: u64, buf: &[u8]) -> EResult<()>;
 
+	/// Performs the transfer of `req`, then completes it.
+	///
+	/// Drivers able to transfer several pages at once, or asynchronously, override this function.
+	/// The default implementation transfers the pages one by one.
+	fn submit(&self, req: Request) {
+		let res = req
+			.frames
+			.iter()
+			.zip(req.off..)
+			.try_for_each(|(frame, off)| {
+				if req.write {
+					self.write_pages(off, frame.slice())
+				} else {
+					let src = self.read_frame(off, 0, FrameOwner::Anon)?;
+					unsafe {
+						frame.slice_mut::<u8>().copy_from_slice(src.slice());
+					}
+					Ok(())
+				}
+			});
+		req.complete(res);
+	}
+
 	/// Polls the device with the given mask.
 	fn poll(&self, mask: u32) -> EResult<u32> {
 		let _ = mask;
@@ -205,6 +239,9 @@ pub trait BlockDeviceOps: fmt::Debug {
 	}
 }
 
+/// The maximum number of pages in a request merged by the elevator of a block device.
+const MAX_REQUEST_PAGES: usize = 32;
+
 /// A block device.
 #[derive(Debug)]
 pub struct BlkDev {
@@ -217,6 +254,10 @@ pub struct BlkDev {
 
 	/// The device I/O interface
 	pub ops: Box<dyn BlockDeviceOps>,
+	/// The queue of pending I/O requests
+	pub queue: RequestQueue,
+	/// Requests waiting to be merged and dispatched
+	elevator: Mutex<Elevator>,
 	/// The device as a mapped node
 	pub(crate) mapped: MappedNode,
 }
@@ -241,6 +282,8 @@ impl BlkDev {
 			mode,
 
 			ops,
+			queue: Default::default(),
+			elevator: Mutex::new(Elevator::new(MAX_REQUEST_PAGES)),
 			mapped: Default::default(),
 		})?;
 		if likely(file::is_init()) {
@@ -260,12 +303,109 @@ impl BlkDev {
 	) -> EResult<RcFrame> {
 		if let Some(mapped) = owner.inner() {
 			mapped.get_or_insert_frame(off, order, || {
-				this.ops.read_frame(off, order, owner.clone())
+				this.read_frame_direct(off, order, owner.clone())
 			})
 		} else {
-			this.ops.read_frame(off, order, owner)
+			this.read_frame_direct(off, order, owner)
 		}
 	}
+
+	/// Reads a frame from the device at the offset `off`, bypassing the cache.
+	///
+	/// The request waits in the device's queue until it gets serviced.
+	pub fn read_frame_direct(
+		&self,
+		off: u64,
+		order: FrameOrder,
+		owner: FrameOwner,
+	) -> EResult<RcFrame> {
+		self.queue.submit(off, || {
+			#[cfg(feature = "fault_inject")]
+			if crate::fault::should_fail() {
+				return Err(errno!(EIO));
+			}
+			self.ops.read_frame(off, order, owner)
+		})
+	}
+
+	/// Writes `buf` to the device at the offset `off`.
+	///
+	/// The request waits in the device's queue until it gets serviced.
+	pub fn write_pages(&self, off: u64, buf: &[u8]) -> EResult<()> {
+		self.queue.submit(off, || self.ops.write_pages(off, buf))
+	}
+
+	/// Queues `req` in the device's elevator, where it can be merged with other requests.
+	///
+	/// The request is performed on the next call to [`Self::dispatch_requests`].
+	pub fn queue_request(&self, req: Request) -> AllocResult<()> {
+		self.elevator.lock().insert(req)
+	}
+
+	/// Performs the request `req` once all the requests ahead of it in the device's queue have
+	/// been serviced.
+	pub fn submit(&self, req: Request) {
+		let off = req.off;
+		let mut req = Some(req);
+		let res = self.queue.submit(off, || {
+			self.ops.submit(req.take().unwrap());
+			Ok(())
+		});
+		// If the request could not be submitted, complete it with the error
+		if let (Err(e), Some(req)) = (res, req) {
+			req.complete(Err(e));
+		}
+	}
+
+	/// Performs the requests queued in the elevator, in dispatch order.
+	pub fn dispatch_requests(&self) {
+		loop {
+			let Some(req) = self.elevator.lock().dispatch() else {
+				break;
+			};
+			self.submit(req);
+		}
+	}
+
+	/// Writes the dirty frames of the device's page cache back to the device.
+	///
+	/// The frames are queued in the elevator before being dispatched, so that contiguous frames
+	/// are written with as few transfers as possible.
+	pub fn sync(&self) -> EResult<()> {
+		let res: Arc<Mutex<EResult<()>>> = Arc::new(Mutex::new(Ok(())))?;
+		let queued = (|| -> EResult<()> {
+			for frame in self.mapped.dirty_frames()? {
+				// Requests are made of single pages
+				if frame.order() != 0 {
+					frame.writeback(None)?;
+					continue;
+				}
+				let mut frames = Vec::new();
+				frames.push(frame.clone())?;
+				let req = {
+					let frame = frame.clone();
+					let res = res.clone();
+					Request::new(frame.dev_offset(), true, frames, move |r| {
+						if let Err(e) = r {
+							frame.mark_dirty();
+							*res.lock() = Err(e);
+						}
+					})?
+				};
+				// Pages modified during the transfer are written on the next sync
+				frame.clear_dirty();
+				if let Err(e) = self.queue_request(req) {
+					frame.mark_dirty();
+					return Err(e.into());
+				}
+			}
+			Ok(())
+		})();
+		self.dispatch_requests();
+		queued?;
+		let res = *res.lock();
+		res
+	}
 }
 
 impl Drop for BlkDev {
@@ -335,6 +475,15 @@ pub fn register_blk(dev: Arc<BlkDev>) -> AllocResult<()> {
 	Ok(())
 }
 
+/// Returns the ID of the block device whose file is at `path`, if any.
+pub fn find_blk_by_path(path: &[u8]) -> Option<DeviceID> {
+	BLK_DEVICES
+		.lock()
+		.iter()
+		.find(|(_, dev)| dev.path.as_bytes() == path)
+		.map(|(id, _)| *id)
+}
+
 /// Helper to insert a character device.
 #[inline]
 pub fn register_char(dev: Arc<CharDev>) -> AllocResult<()> {
@@ -399,13 +548,16 @@ impl FileOps for BlkDevFileOps {
 
 /// Initializes devices management.
 pub(crate) fn init() -> EResult<()> {
-	let keyboard_manager = KeyboardManager::new();
+	input::init()?;
+
+	let keyboard_manager = KeyboardManager::new()?;
 	manager::register(keyboard_manager)?;
 
 	let storage_manager = StorageManager::new()?;
 	manager::register(storage_manager)?;
 
 	bus::detect()?;
+	mouse::init()?;
 
 	// Testing disk I/O (if enabled)
 	#[cfg(config_debug_storage_test)]
@@ -426,6 +578,8 @@ pub(crate) fn init() -> EResult<()> {
 /// This function must be used only once at boot, after files management has been initialized.
 pub(crate) fn stage2() -> EResult<()> {
 	default::create().unwrap_or_else(|e| panic!("Failed to create default devices! ({e})"));
+	#[cfg(feature = "kcov")]
+	kcov::create()?;
 	// Create device files
 	let devs = BLK_DEVICES.lock();
 	for (id, dev) in devs.iter() {
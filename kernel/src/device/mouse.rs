@@ -0,0 +1,517 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! PS/2 mouse driver.
+//!
+//! The mouse is connected to the auxiliary port of the 8042 controller. It sends packets of 3
+//! bytes, or 4 bytes if it has a scroll wheel (IntelliMouse extension).
+//!
+//! Events are exposed through `/dev/input/mice`, using the PS/2 mouse protocol. Readers can
+//! enable the IntelliMouse protocol by writing the corresponding sample rates sequence, like they
+//! would on a physical mouse. They are also reported on an event device.
+
+use crate::{
+	arch::x86::{
+		idt::IntFrame,
+		io::{inb, outb},
+		pic,
+	},
+	device::{
+		input,
+		input::{
+			InputDevice, InputId, BTN_LEFT, BTN_MIDDLE, BTN_RIGHT, BUS_I8042, REL_WHEEL, REL_X,
+			REL_Y,
+		},
+		register_char, CharDev, DeviceID,
+	},
+	event,
+	event::CallbackResult,
+	file::{fs::FileOps, wait_queue, wait_queue::WaitQueue, File, O_NONBLOCK},
+	println,
+	sync::mutex::IntMutex,
+	syscall::poll::{POLLIN, POLLOUT},
+};
+use core::mem::ManuallyDrop;
+use utils::{
+	collections::{path::PathBuf, ring_buffer::RingBuffer},
+	errno,
+	errno::EResult,
+	ptr::arc::Arc,
+};
+
+/// The controller's data port.
+const DATA_PORT: u16 = 0x60;
+/// The controller's status register (read) and command register (write).
+const STATUS_PORT: u16 = 0x64;
+
+/// Status bit: the output buffer is full.
+const STATUS_OUTPUT_FULL: u8 = 0b1;
+/// Status bit: the input buffer is full.
+const STATUS_INPUT_FULL: u8 = 0b10;
+/// Status bit: the data in the output buffer comes from the auxiliary device.
+const STATUS_AUX_DATA: u8 = 0b100000;
+
+/// Controller command: reads the configuration byte.
+const CMD_READ_CONFIG: u8 = 0x20;
+/// Controller command: writes the configuration byte.
+const CMD_WRITE_CONFIG: u8 = 0x60;
+/// Controller command: enables the auxiliary port.
+const CMD_ENABLE_AUX: u8 = 0xa8;
+/// Controller command: tests the auxiliary port.
+const CMD_TEST_AUX: u8 = 0xa9;
+/// Controller command: sends the next byte to the auxiliary device.
+const CMD_WRITE_AUX: u8 = 0xd4;
+
+/// Configuration bit: enables the auxiliary device's interrupt.
+const CONFIG_AUX_INT: u8 = 0b10;
+/// Configuration bit: disables the auxiliary device's clock.
+const CONFIG_AUX_CLOCK_DISABLE: u8 = 0b100000;
+
+/// Mouse command: resets the mouse.
+const MOUSE_RESET: u8 = 0xff;
+/// Mouse command: sets the default settings.
+const MOUSE_SET_DEFAULTS: u8 = 0xf6;
+/// Mouse command: enables data reporting.
+const MOUSE_ENABLE_REPORTING: u8 = 0xf4;
+/// Mouse command: sets the sample rate, given in the next byte.
+const MOUSE_SET_SAMPLE_RATE: u8 = 0xf3;
+/// Mouse command: returns the device ID.
+const MOUSE_GET_ID: u8 = 0xf2;
+
+/// Mouse response: command acknowledged.
+const ACK: u8 = 0xfa;
+/// Mouse response: self-test passed.
+const SELF_TEST_OK: u8 = 0xaa;
+
+/// The sample rates sequence enabling the scroll wheel on IntelliMouse devices.
+const INTELLIMOUSE_SEQ: [u8; 3] = [200, 100, 80];
+/// The device ID of a standard PS/2 mouse.
+const ID_STANDARD: u8 = 0;
+/// The device ID of an IntelliMouse, having a scroll wheel.
+const ID_INTELLIMOUSE: u8 = 3;
+
+/// Packet bits: the pressed buttons (left, right, middle).
+const PACKET_BUTTONS: u8 = 0b111;
+/// Packet bit: always set on the first byte of a packet.
+const PACKET_ALWAYS_ONE: u8 = 0b1000;
+/// Packet bit: the X movement is negative.
+const PACKET_X_SIGN: u8 = 0b10000;
+/// Packet bit: the Y movement is negative.
+const PACKET_Y_SIGN: u8 = 0b100000;
+
+/// The IRQ of the auxiliary device.
+const IRQ: u8 = 12;
+/// The maximum number of polls when waiting for the controller.
+const TIMEOUT: usize = 100000;
+
+/// The size of the buffer of packets to be read.
+const BUFFER_SIZE: usize = 1024;
+
+/// Waits until the controller is ready to receive a byte, then writes `b` on `port`.
+fn write(port: u16, b: u8) -> EResult<()> {
+	for _ in 0..TIMEOUT {
+		if unsafe { inb(STATUS_PORT) } & STATUS_INPUT_FULL == 0 {
+			unsafe {
+				outb(port, b);
+			}
+			return Ok(());
+		}
+	}
+	Err(errno!(EIO))
+}
+
+/// Waits until the controller has a byte available, then returns it.
+fn read() -> EResult<u8> {
+	for _ in 0..TIMEOUT {
+		if unsafe { inb(STATUS_PORT) } & STATUS_OUTPUT_FULL != 0 {
+			return Ok(unsafe { inb(DATA_PORT) });
+		}
+	}
+	Err(errno!(EIO))
+}
+
+/// Sends the command `cmd` to the mouse and waits for the acknowledgement.
+fn mouse_command(cmd: u8) -> EResult<()> {
+	write(STATUS_PORT, CMD_WRITE_AUX)?;
+	write(DATA_PORT, cmd)?;
+	if read()? != ACK {
+		return Err(errno!(EIO));
+	}
+	Ok(())
+}
+
+/// A decoded mouse event.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MouseEvent {
+	/// The movement on the X axis, rightward.
+	pub dx: i32,
+	/// The movement on the Y axis, upward.
+	pub dy: i32,
+	/// The movement of the scroll wheel.
+	pub dz: i32,
+	/// The pressed buttons (bit `0`: left, bit `1`: right, bit `2`: middle).
+	pub buttons: u8,
+}
+
+/// Decoder for packets received from the mouse.
+struct Decoder {
+	/// The packet being received.
+	packet: [u8; 4],
+	/// The number of bytes received for the current packet.
+	len: usize,
+	/// The size of a packet, depending on the mouse's protocol.
+	packet_size: usize,
+}
+
+impl Decoder {
+	/// Creates a decoder for packets of `packet_size` bytes.
+	const fn new(packet_size: usize) -> Self {
+		Self {
+			packet: [0; 4],
+			len: 0,
+			packet_size,
+		}
+	}
+
+	/// Handles the byte `b` received from the mouse.
+	///
+	/// If the byte completes a packet, the function returns the decoded event.
+	fn input(&mut self, b: u8) -> Option<MouseEvent> {
+		// Resynchronize on the first byte of a packet
+		if self.len == 0 && b & PACKET_ALWAYS_ONE == 0 {
+			return None;
+		}
+		self.packet[self.len] = b;
+		self.len += 1;
+		if self.len < self.packet_size {
+			return None;
+		}
+		self.len = 0;
+		let [flags, x, y, z] = self.packet;
+		// Movements are 9 bits two's complement, the sign bit being in the first byte
+		let dx = x as i32 - if flags & PACKET_X_SIGN != 0 { 0x100 } else { 0 };
+		let dy = y as i32 - if flags & PACKET_Y_SIGN != 0 { 0x100 } else { 0 };
+		let dz = if self.packet_size == 4 {
+			z as i8 as i32
+		} else {
+			0
+		};
+		Some(MouseEvent {
+			dx,
+			dy,
+			dz,
+			buttons: flags & PACKET_BUTTONS,
+		})
+	}
+}
+
+/// The state of `/dev/input/mice`.
+struct Mice {
+	/// The buffer of encoded packets to be read.
+	buf: RingBuffer<u8, [u8; BUFFER_SIZE]>,
+	/// Tells whether the IntelliMouse protocol is enabled.
+	imps: bool,
+	/// The last sample rates written, used to detect the IntelliMouse sequence.
+	rates: [u8; 3],
+	/// Tells whether the next written byte is a sample rate.
+	expect_rate: bool,
+}
+
+impl Mice {
+	/// Creates a new instance.
+	const fn new() -> Self {
+		Self {
+			buf: RingBuffer::new([0; BUFFER_SIZE]),
+			imps: false,
+			rates: [0; 3],
+			expect_rate: false,
+		}
+	}
+
+	/// Encodes the event `ev` in the buffer.
+	///
+	/// Movements that do not fit in a single packet are split across several. If the buffer is
+	/// full, the event is dropped.
+	fn push(&mut self, ev: &MouseEvent) {
+		let (mut dx, mut dy, mut dz) = (ev.dx, ev.dy, ev.dz);
+		loop {
+			let x = dx.clamp(-127, 127);
+			let y = dy.clamp(-127, 127);
+			let z = dz.clamp(-7, 7);
+			let mut flags = PACKET_ALWAYS_ONE | (ev.buttons & PACKET_BUTTONS);
+			if x < 0 {
+				flags |= PACKET_X_SIGN;
+			}
+			if y < 0 {
+				flags |= PACKET_Y_SIGN;
+			}
+			let packet = [flags, x as u8, y as u8, z as u8];
+			let size = if self.imps { 4 } else { 3 };
+			if self.buf.get_available_len() < size {
+				break;
+			}
+			self.buf.write(&packet[..size]);
+			dx -= x;
+			dy -= y;
+			dz -= z;
+			if dx == 0 && dy == 0 && dz == 0 {
+				break;
+			}
+		}
+	}
+
+	/// Handles the command bytes `cmd` written by userspace, queueing the mouse's responses.
+	fn command(&mut self, cmd: &[u8]) {
+		// Pending packets are discarded so that responses can be read right away
+		self.buf.clear();
+		for b in cmd {
+			if self.expect_rate {
+				self.expect_rate = false;
+				self.rates.rotate_left(1);
+				self.rates[2] = *b;
+				if self.rates == INTELLIMOUSE_SEQ {
+					self.imps = true;
+				}
+				self.buf.write(&[ACK]);
+				continue;
+			}
+			match *b {
+				MOUSE_SET_SAMPLE_RATE => {
+					self.expect_rate = true;
+					self.buf.write(&[ACK]);
+				}
+				MOUSE_GET_ID => {
+					let id = if self.imps {
+						ID_INTELLIMOUSE
+					} else {
+						ID_STANDARD
+					};
+					self.buf.write(&[ACK, id]);
+				}
+				MOUSE_RESET => {
+					self.imps = false;
+					self.buf.write(&[ACK, SELF_TEST_OK, ID_STANDARD]);
+				}
+				_ => {
+					self.buf.write(&[ACK]);
+				}
+			}
+		}
+	}
+}
+
+/// The decoder for packets received from the mouse.
+static DECODER: IntMutex<Decoder> = IntMutex::new(Decoder::new(3));
+/// The state of `/dev/input/mice`.
+static MICE: IntMutex<Mice> = IntMutex::new(Mice::new());
+/// The queue of processes waiting for mouse events.
+static MICE_QUEUE: WaitQueue = WaitQueue::new();
+/// The mouse's input device.
+static INPUT: IntMutex<Option<Arc<InputDevice>>> = IntMutex::new(None);
+
+/// The buttons of the mouse, in the order of bits in packets.
+const BUTTONS: [u16; 3] = [BTN_LEFT, BTN_RIGHT, BTN_MIDDLE];
+
+/// Handles the byte `b` received from the mouse.
+fn handle_byte(b: u8) {
+	let Some(ev) = DECODER.lock().input(b) else {
+		return;
+	};
+	MICE.lock().push(&ev);
+	MICE_QUEUE.wake_all();
+	wait_queue::poll_notify();
+	if let Some(input) = &*INPUT.lock() {
+		report(input, &ev);
+	}
+}
+
+/// Reports the event `ev` on the input device `input`.
+fn report(input: &InputDevice, ev: &MouseEvent) {
+	// On input devices, the Y axis and the wheel are pointing downward
+	input.report_rel(REL_X, ev.dx);
+	input.report_rel(REL_Y, -ev.dy);
+	input.report_rel(REL_WHEEL, -ev.dz);
+	for (i, code) in BUTTONS.iter().enumerate() {
+		input.report_key(*code, ev.buttons & (1 << i) != 0);
+	}
+	input.sync();
+}
+
+/// Handle for `/dev/input/mice`.
+#[derive(Debug)]
+pub struct MiceDeviceHandle;
+
+impl FileOps for MiceDeviceHandle {
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		if buf.is_empty() {
+			return Ok(0);
+		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		MICE_QUEUE.wait_until(|| {
+			let len = MICE.lock().buf.read(buf);
+			match len {
+				0 if nonblock => Some(Err(errno!(EAGAIN))),
+				0 => None,
+				len => Some(Ok(len)),
+			}
+		})?
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		MICE.lock().command(buf);
+		MICE_QUEUE.wake_all();
+		wait_queue::poll_notify();
+		Ok(buf.len())
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let input = !MICE.lock().buf.is_empty();
+		let res = (if input { POLLIN } else { 0 } | POLLOUT) & mask;
+		Ok(res)
+	}
+}
+
+/// Called on interrupts from the auxiliary device.
+fn irq_callback(_: u32, _: u32, _: &mut IntFrame, _: u8) -> CallbackResult {
+	let status = unsafe { inb(STATUS_PORT) };
+	if status & STATUS_OUTPUT_FULL != 0 && status & STATUS_AUX_DATA != 0 {
+		handle_byte(unsafe { inb(DATA_PORT) });
+	}
+	CallbackResult::Continue
+}
+
+/// Initializes the mouse on the auxiliary port of the 8042 controller.
+///
+/// If no mouse is present, the function returns [`errno::ENODEV`].
+fn init_controller() -> EResult<()> {
+	// Flush the output buffer
+	while unsafe { inb(STATUS_PORT) } & STATUS_OUTPUT_FULL != 0 {
+		unsafe {
+			inb(DATA_PORT);
+		}
+	}
+	write(STATUS_PORT, CMD_ENABLE_AUX)?;
+	write(STATUS_PORT, CMD_READ_CONFIG)?;
+	let mut config = read()?;
+	config &= !(CONFIG_AUX_INT | CONFIG_AUX_CLOCK_DISABLE);
+	write(STATUS_PORT, CMD_WRITE_CONFIG)?;
+	write(DATA_PORT, config)?;
+	write(STATUS_PORT, CMD_TEST_AUX)?;
+	if read()? != 0 {
+		return Err(errno!(ENODEV));
+	}
+	mouse_command(MOUSE_SET_DEFAULTS).map_err(|_| errno!(ENODEV))?;
+	// Try to enable the scroll wheel
+	for rate in INTELLIMOUSE_SEQ {
+		mouse_command(MOUSE_SET_SAMPLE_RATE)?;
+		mouse_command(rate)?;
+	}
+	mouse_command(MOUSE_GET_ID)?;
+	if read()? == ID_INTELLIMOUSE {
+		DECODER.lock().packet_size = 4;
+	}
+	mouse_command(MOUSE_ENABLE_REPORTING)?;
+	// Enable interrupts
+	write(STATUS_PORT, CMD_WRITE_CONFIG)?;
+	write(DATA_PORT, config | CONFIG_AUX_INT)?;
+	Ok(())
+}
+
+/// Initializes the PS/2 mouse, if present, and creates `/dev/input/mice`.
+pub(crate) fn init() -> EResult<()> {
+	if let Err(e) = init_controller() {
+		println!("No PS/2 mouse found ({e})");
+		return Ok(());
+	}
+	let mut dev = InputDevice::new(
+		"PS/2 Generic Mouse",
+		InputId {
+			bustype: BUS_I8042,
+			vendor: 0x2,
+			product: 0x1,
+			version: 0,
+		},
+	);
+	dev.set_rel_bit(REL_X);
+	dev.set_rel_bit(REL_Y);
+	if DECODER.lock().packet_size == 4 {
+		dev.set_rel_bit(REL_WHEEL);
+	}
+	for code in BUTTONS {
+		dev.set_key_bit(code);
+	}
+	*INPUT.lock() = Some(input::register(dev)?);
+	let _ = ManuallyDrop::new(event::register_callback(0x20 + IRQ as u32, irq_callback)?);
+	pic::enable_irq(IRQ);
+	register_char(CharDev::new(
+		DeviceID {
+			major: input::MAJOR,
+			minor: 63,
+		},
+		PathBuf::try_from(b"/dev/input/mice")?,
+		0o660,
+		MiceDeviceHandle,
+	)?)?;
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Feeds `bytes` to a decoder, then encodes the resulting events to `mice`.
+	fn feed(decoder: &mut Decoder, mice: &mut Mice, bytes: &[u8]) {
+		for b in bytes {
+			if let Some(ev) = decoder.input(*b) {
+				mice.push(&ev);
+			}
+		}
+	}
+
+	#[test_case]
+	fn mouse_packet() {
+		let mut decoder = Decoder::new(3);
+		let mut mice = Mice::new();
+		// Left button, dx = 5, dy = -3, preceded by a byte out of sync
+		feed(&mut decoder, &mut mice, &[0x00, 0x29, 5, 0xfd]);
+		let mut buf = [0; 8];
+		assert_eq!(mice.buf.read(&mut buf), 3);
+		assert_eq!(&buf[..3], &[0x29, 5, 0xfd]);
+		// Right and middle buttons, dx = -200, dy = 0
+		feed(&mut decoder, &mut mice, &[0x1e, 0x38, 0]);
+		assert_eq!(mice.buf.read(&mut buf), 6);
+		assert_eq!(&buf[..6], &[0x1e, 0x81, 0, 0x1e, 0xb7, 0]);
+	}
+
+	#[test_case]
+	fn mouse_intellimouse() {
+		let mut decoder = Decoder::new(4);
+		let mut mice = Mice::new();
+		let mut buf = [0; 8];
+		// Enable the IntelliMouse protocol and check the device ID
+		mice.command(&[0xf3, 200, 0xf3, 100, 0xf3, 80]);
+		assert_eq!(mice.buf.read(&mut buf), 6);
+		mice.command(&[MOUSE_GET_ID]);
+		assert_eq!(mice.buf.read(&mut buf), 2);
+		assert_eq!(&buf[..2], &[ACK, ID_INTELLIMOUSE]);
+		// dx = 1, dy = 2, dz = -1
+		feed(&mut decoder, &mut mice, &[0x08, 1, 2, 0xff]);
+		assert_eq!(mice.buf.read(&mut buf), 4);
+		assert_eq!(&buf[..4], &[0x08, 1, 2, 0xff]);
+	}
+}
@@ -0,0 +1,201 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Pseudo-terminal devices.
+//!
+//! Opening `/dev/ptmx` allocates a pseudo-terminal and returns its master. The slave then appears
+//! at `/dev/pts/<n>`, where `n` is the number of the pseudo-terminal, and stays locked until
+//! unlocked by the master with `TIOCSPTLCK`.
+
+use crate::{
+	device::{
+		register_char,
+		tty::{acquire_controlling, check_sigttin, check_sigttou, terminal_ioctl},
+		CharDev, DeviceID, CHAR_DEVICES,
+	},
+	file::{fs::FileOps, File, O_NONBLOCK},
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::{
+		ioctl,
+		poll::{POLLHUP, POLLIN, POLLOUT},
+		FromSyscallArg,
+	},
+	tty::{pty, pty::Pty, Terminal},
+};
+use core::ffi::{c_int, c_uint, c_void};
+use utils::{errno, errno::EResult, format, ptr::arc::Arc};
+
+/// The major number of pseudo-terminal slaves.
+pub const PTS_MAJOR: u32 = 136;
+
+/// Returns the ID of the slave device of `pty`.
+fn slave_id(pty: &Pty) -> DeviceID {
+	DeviceID {
+		major: PTS_MAJOR,
+		minor: pty.get_index(),
+	}
+}
+
+/// Handle for `/dev/ptmx`.
+#[derive(Debug)]
+pub struct PtmxDeviceHandle;
+
+impl FileOps for PtmxDeviceHandle {
+	fn open(&self) -> EResult<Option<Arc<dyn FileOps>>> {
+		let pty = pty::alloc()?;
+		let index = pty.get_index();
+		// TODO make the slave's file owned by the current user, with mode 0o620
+		let res = CharDev::new(
+			slave_id(&pty),
+			format!("/dev/pts/{index}")?.try_into()?,
+			0o666,
+			PtsDeviceHandle(pty.clone()),
+		)
+		.and_then(|dev| Ok(register_char(dev)?));
+		if let Err(e) = res {
+			pty::free(&pty);
+			return Err(e);
+		}
+		Ok(Some(Arc::new(PtyMasterHandle(pty))?))
+	}
+}
+
+/// Handle for the master of a pseudo-terminal, one for each open of `/dev/ptmx`.
+#[derive(Debug)]
+pub struct PtyMasterHandle(Arc<Pty>);
+
+impl FileOps for PtyMasterHandle {
+	fn release(&self, _file: &File) {
+		self.0.close_master();
+		pty::free(&self.0);
+		// Dropping the device outside the lock, since it removes its file
+		let dev = CHAR_DEVICES.lock().remove(&slave_id(&self.0));
+		drop(dev);
+	}
+
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		self.0.master_read(buf, nonblock)
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		self.0.master_write(buf);
+		Ok(buf.len())
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let mut res = POLLOUT;
+		if self.0.master_readable() {
+			res |= POLLIN;
+		}
+		if self.0.is_slave_closed() {
+			res |= POLLHUP;
+		}
+		Ok(res & mask)
+	}
+
+	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
+		match request.get_old_format() {
+			ioctl::TIOCGPTN => {
+				let index = self.0.get_index() as c_uint;
+				SyscallPtr::<c_uint>::from_ptr(argp as usize).copy_to_user(&index)?;
+				Ok(0)
+			}
+			ioctl::TIOCSPTLCK => {
+				let lock = SyscallPtr::<c_int>::from_ptr(argp as usize)
+					.copy_from_user()?
+					.ok_or_else(|| errno!(EFAULT))?;
+				self.0.set_locked(lock != 0);
+				Ok(0)
+			}
+			_ => terminal_ioctl(&mut *self.0.ctl.lock(), request, argp),
+		}
+	}
+}
+
+/// Handle for the slave device of a pseudo-terminal, at `/dev/pts/<n>`.
+#[derive(Debug)]
+pub struct PtsDeviceHandle(Arc<Pty>);
+
+impl FileOps for PtsDeviceHandle {
+	fn open(&self) -> EResult<Option<Arc<dyn FileOps>>> {
+		self.0.check_slave_open()?;
+		Ok(Some(Arc::new(PtySlaveHandle(self.0.clone()))?))
+	}
+}
+
+/// Handle for an open file of the slave of a pseudo-terminal.
+#[derive(Debug)]
+pub struct PtySlaveHandle(Arc<Pty>);
+
+impl FileOps for PtySlaveHandle {
+	fn acquire(&self, file: &File) {
+		self.0.open_slave();
+		acquire_controlling(file, &mut *self.0.ctl.lock());
+	}
+
+	fn release(&self, _file: &File) {
+		self.0.close_slave();
+	}
+
+	fn read(&self, _file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		check_sigttin(&*self.0.ctl.lock())?;
+		self.0.slave_read(buf)
+	}
+
+	fn write(&self, file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		check_sigttou(&*self.0.ctl.lock())?;
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		self.0.slave_write(buf, nonblock)
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let mut res = 0;
+		if self.0.slave_readable() {
+			res |= POLLIN;
+		}
+		if self.0.slave_writable() {
+			res |= POLLOUT;
+		}
+		if self.0.is_master_closed() {
+			res |= POLLIN | POLLHUP;
+		}
+		Ok(res & mask)
+	}
+
+	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
+		let mut ctl = self.0.ctl.lock();
+		match request.get_old_format() {
+			ioctl::TIOCSTI => {
+				let proc = Process::current();
+				if proc.get_sid() != ctl.get_sid()
+					&& !proc.fs.lock().access_profile.is_privileged()
+				{
+					return Err(errno!(EPERM));
+				}
+				let b = SyscallPtr::<u8>::from_ptr(argp as usize)
+					.copy_from_user()?
+					.ok_or_else(|| errno!(EFAULT))?;
+				drop(ctl);
+				self.0.master_write(&[b]);
+				Ok(0)
+			}
+			_ => terminal_ioctl(&mut *ctl, request, argp),
+		}
+	}
+}
@@ -0,0 +1,210 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Block device request queue.
+//!
+//! Drivers perform one transfer at a time. When several processes issue requests on the same
+//! device, they wait in the device's queue, which services them by decreasing I/O priority (see
+//! `ioprio_set(2)`). Requests of equal priority are serviced in the order of the device's
+//! elevator (see [`elevator::look_key`]), then in submission order.
+
+use crate::{
+	device::elevator,
+	process,
+	process::{pid::Pid, scheduler, scheduler::Scheduler, Process},
+	sync::mutex::IntMutex,
+};
+use utils::{collections::vec::Vec, errno::EResult, ptr::arc::Arc};
+
+/// I/O priority class: none set, derived from the nice value.
+pub const IOPRIO_CLASS_NONE: u16 = 0;
+/// I/O priority class: real-time, serviced before all others.
+pub const IOPRIO_CLASS_RT: u16 = 1;
+/// I/O priority class: best-effort.
+pub const IOPRIO_CLASS_BE: u16 = 2;
+/// I/O priority class: idle, serviced only when no other request is pending.
+pub const IOPRIO_CLASS_IDLE: u16 = 3;
+
+/// The offset of the class in an I/O priority value.
+pub const IOPRIO_CLASS_SHIFT: u16 = 13;
+/// The number of levels in the real-time and best-effort classes.
+pub const IOPRIO_NR_LEVELS: u16 = 8;
+
+/// Returns the class of the I/O priority value `ioprio`.
+#[inline]
+pub fn ioprio_class(ioprio: u16) -> u16 {
+	ioprio >> IOPRIO_CLASS_SHIFT
+}
+
+/// Returns the level of the I/O priority value `ioprio`.
+#[inline]
+pub fn ioprio_level(ioprio: u16) -> u16 {
+	ioprio & ((1 << IOPRIO_CLASS_SHIFT) - 1)
+}
+
+/// Returns the rank of the requests issued by `proc` in a queue, lowest first.
+fn rank(proc: &Process) -> u16 {
+	let ioprio = proc.ioprio();
+	match ioprio_class(ioprio) {
+		IOPRIO_CLASS_RT => ioprio_level(ioprio),
+		IOPRIO_CLASS_BE => IOPRIO_NR_LEVELS + ioprio_level(ioprio),
+		IOPRIO_CLASS_IDLE => IOPRIO_NR_LEVELS * 2,
+		// Same mapping as Linux: a nice value of zero gives the level 4
+		_ => IOPRIO_NR_LEVELS + (proc.nice() as i16 + 20) as u16 / 5,
+	}
+}
+
+/// A request waiting in a [`RequestQueue`].
+#[derive(Debug)]
+struct Request {
+	/// The rank of the request (see [`rank`]).
+	rank: u16,
+	/// The submission number of the request, used to keep FIFO order at equal rank and offset.
+	seq: u64,
+	/// The offset of the request on the device, in pages.
+	off: u64,
+	/// The process waiting for the request to be serviced.
+	proc: Arc<Process>,
+}
+
+#[derive(Debug, Default)]
+struct QueueState {
+	/// The process whose request is being serviced, if any.
+	owner: Option<Pid>,
+	/// The pending requests, sorted by rank.
+	pending: Vec<Request>,
+	/// The submission number of the next request.
+	seq: u64,
+	/// The offset of the last serviced request.
+	head: u64,
+}
+
+impl QueueState {
+	/// Returns the index of the next pending request to be serviced.
+	fn next(&self) -> Option<usize> {
+		let rank = self.pending.first()?.rank;
+		self.pending
+			.iter()
+			.enumerate()
+			.take_while(|(_, r)| r.rank == rank)
+			.min_by_key(|(_, r)| (elevator::look_key(self.head, r.off), r.seq))
+			.map(|(i, _)| i)
+	}
+}
+
+/// A queue of requests on a block device.
+#[derive(Debug, Default)]
+pub struct RequestQueue(IntMutex<QueueState>);
+
+impl RequestQueue {
+	/// Creates a new empty queue.
+	pub const fn new() -> Self {
+		Self(IntMutex::new(QueueState {
+			owner: None,
+			pending: Vec::new(),
+			seq: 0,
+			head: 0,
+		}))
+	}
+
+	/// Returns the number of requests waiting to be serviced.
+	pub fn pending(&self) -> usize {
+		self.0.lock().pending.len()
+	}
+
+	/// Inserts a request for `proc` at the offset `off` in the queue and returns its submission
+	/// number.
+	///
+	/// If the device is idle and no request is pending, the request is serviced immediately and
+	/// the function returns `None`.
+	fn enqueue(&self, proc: Arc<Process>, off: u64) -> EResult<Option<u64>> {
+		let mut state = self.0.lock();
+		if state.owner.is_none() && state.pending.is_empty() {
+			state.owner = Some(proc.get_pid());
+			state.head = off;
+			return Ok(None);
+		}
+		let rank = rank(&proc);
+		let seq = state.seq;
+		state.seq += 1;
+		let i = state.pending.partition_point(|r| r.rank <= rank);
+		state.pending.insert(
+			i,
+			Request {
+				rank,
+				seq,
+				off,
+				proc,
+			},
+		)?;
+		Ok(Some(seq))
+	}
+
+	/// If the request with submission number `seq` is the next to be serviced and the device is
+	/// idle, removes it from the queue and returns `true`.
+	fn try_start(&self, seq: u64) -> bool {
+		let mut state = self.0.lock();
+		let next = state
+			.next()
+			.filter(|i| state.owner.is_none() && state.pending[*i].seq == seq);
+		if let Some(i) = next {
+			let req = state.pending.remove(i);
+			state.head = req.off;
+			state.owner = Some(req.proc.get_pid());
+		} else {
+			// Sleep while the queue is locked so that the wakeup cannot be missed
+			Process::current().set_state(process::State::Sleeping);
+		}
+		next.is_some()
+	}
+
+	/// Marks the current request as completed and wakes the process owning the next one.
+	fn complete(&self) {
+		let mut state = self.0.lock();
+		state.owner = None;
+		if let Some(i) = state.next() {
+			state.pending[i].proc.wake();
+		}
+	}
+
+	/// Runs the request `f`, at the offset `off` in pages, on the device once all the requests
+	/// ahead of it have been serviced.
+	///
+	/// Block I/O cannot be interrupted: signals are handled once the request has completed.
+	///
+	/// A request submitted while the current process already owns the device, such as a page
+	/// being swapped out during a transfer, is serviced immediately.
+	pub fn submit<F: FnOnce() -> EResult<T>, T>(&self, off: u64, f: F) -> EResult<T> {
+		// Before processes are initialized, there is no concurrent request
+		if !scheduler::is_initialized() {
+			return f();
+		}
+		let proc = Process::current();
+		if self.0.lock().owner == Some(proc.get_pid()) {
+			return f();
+		}
+		if let Some(seq) = self.enqueue(proc, off)? {
+			while !self.try_start(seq) {
+				Scheduler::tick();
+			}
+		}
+		let res = f();
+		self.complete();
+		res
+	}
+}
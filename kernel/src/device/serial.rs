@@ -126,8 +126,8 @@ impl Serial {
 
 	/// Creates a new instance for the specified port.
 	///
-	/// If the port doesn't exist, the function returns `None`.
-	const fn from_port(port: u16) -> Serial {
+	/// The port is probed on the first write.
+	pub const fn from_port(port: u16) -> Serial {
 		Self {
 			regs_off: port,
 			active: false,
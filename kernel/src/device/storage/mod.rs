@@ -26,6 +26,7 @@ use crate::{
 	device,
 	device::{
 		bus::pci,
+		elevator::Request,
 		id,
 		id::MajorBlock,
 		manager::{DeviceManager, PhysicalDevice},
@@ -112,12 +113,21 @@ impl BlockDeviceOps for PartitionOps {
 
 	fn write_pages(&self, off: u64, buf: &[u8]) -> EResult<()> {
 		if off < self.partition.size {
-			self.dev.ops.write_pages(self.partition.offset + off, buf)
+			self.dev.write_pages(self.partition.offset + off, buf)
 		} else {
 			Err(errno!(EINVAL))
 		}
 	}
 
+	fn submit(&self, mut req: Request) {
+		if req.end() <= self.partition.size {
+			req.off += self.partition.offset;
+			self.dev.submit(req);
+		} else {
+			req.complete(Err(errno!(EINVAL)));
+		}
+	}
+
 	fn ioctl(&self, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
 		match request.get_old_format() {
 			ioctl::HDIO_GETGEO => {
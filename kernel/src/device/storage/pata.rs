@@ -31,7 +31,7 @@
 
 use crate::{
 	arch::x86::io::inb,
-	device::{storage::ide, BlockDeviceOps},
+	device::{elevator::Request, storage::ide, BlockDeviceOps},
 	memory::{
 		buddy::{FrameOrder, ZONE_KERNEL},
 		cache::{FrameOwner, RcFrame},
@@ -415,6 +415,48 @@ impl PATAInterface {
 		(count, lba48)
 	}
 
+	/// Performs the transfer of `req` with as few commands as possible.
+	fn transfer(&self, req: &Request) -> EResult<()> {
+		let off = req
+			.off
+			.checked_mul(SECTOR_PER_PAGE)
+			.ok_or_else(|| errno!(EOVERFLOW))?;
+		let size = req.count() * SECTOR_PER_PAGE;
+		// If the offset and size are out of bounds of the disk, return an error
+		let end = off.checked_add(size).ok_or_else(|| errno!(EOVERFLOW))?;
+		if end > self.sectors_count {
+			return Err(errno!(EOVERFLOW));
+		}
+		// Avoid data race
+		let _guard = self.lock.lock();
+		// Select disk
+		self.select(false);
+		let mut i = 0;
+		while i < size {
+			let count = (size - i).min(u16::MAX as u64) as u16;
+			let (count, lba48) = self.prepare_io(off + i, count, req.write);
+			for j in i..(i + count as u64) {
+				self.wait_io()?;
+				// Each frame is one page long
+				let frame = &req.frames[(j / SECTOR_PER_PAGE) as usize];
+				let sector = (j % SECTOR_PER_PAGE) as usize * 256;
+				let buf = unsafe { &mut frame.slice_mut::<u16>()[sector..(sector + 256)] };
+				for word in buf {
+					if req.write {
+						self.outw(PortOffset::Ata(DATA_REGISTER_OFFSET), *word);
+					} else {
+						*word = self.inw(PortOffset::Ata(DATA_REGISTER_OFFSET));
+					}
+				}
+			}
+			if req.write {
+				self.cache_flush(lba48);
+			}
+			i += count as u64;
+		}
+		Ok(())
+	}
+
 	/// Waits for the drive to be ready for IO operation.
 	///
 	/// The device is assumed to be selected.
@@ -512,4 +554,9 @@ impl BlockDeviceOps for PATAInterface {
 		}
 		Ok(())
 	}
+
+	fn submit(&self, req: Request) {
+		let res = self.transfer(&req);
+		req.complete(res);
+	}
 }
@@ -20,7 +20,7 @@
 //! communicate with it.
 
 use crate::{
-	file::{fs::FileOps, File},
+	file::{fs::FileOps, File, O_NOCTTY},
 	process::{
 		mem_space::copy::SyscallPtr,
 		pid::Pid,
@@ -32,80 +32,126 @@ use crate::{
 		poll::{POLLIN, POLLOUT},
 		FromSyscallArg,
 	},
-	tty::{termios, termios::Termios, TTYDisplay, WinSize, TTY},
+	tty::{termios, termios::Termios, vga, Terminal, WinSize, TTY},
 };
 use core::ffi::c_void;
-use utils::{errno, errno::EResult};
+use utils::{errno, errno::EResult, ptr::arc::Arc};
 
-/// A TTY device's handle.
-#[derive(Debug)]
-pub struct TTYDeviceHandle;
+/// The console's color palette, as RGB components for each color.
+type ColorMap = [u8; vga::PALETTE_SIZE * 3];
 
-impl TTYDeviceHandle {
-	/// Checks whether the current process is allowed to read from the TTY.
-	///
-	/// If not, it is killed with a `SIGTTIN` signal.
-	///
-	/// This function must be called before performing the read operation.
-	fn check_sigttin(&self, tty: &TTYDisplay) -> EResult<()> {
-		let proc = Process::current();
-		if proc.get_pgid() == tty.get_pgrp() {
-			return Ok(());
-		}
-		if proc.is_in_orphan_process_group() {
+/// The console's cursor settings.
+#[repr(C)]
+#[derive(Clone, Debug)]
+pub struct ConsoleCursor {
+	/// The first scanline of the cursor.
+	pub start: u8,
+	/// The last scanline of the cursor.
+	pub end: u8,
+	/// If non-zero, the cursor is visible.
+	pub visible: u8,
+}
+
+/// Checks whether the current process is allowed to read from the terminal `tty`.
+///
+/// If not, it is killed with a `SIGTTIN` signal.
+///
+/// This function must be called before performing the read operation.
+pub(super) fn check_sigttin(tty: &dyn Terminal) -> EResult<()> {
+	let proc = Process::current();
+	if proc.get_sid() != tty.get_sid() || proc.get_pgid() == tty.get_pgrp() {
+		return Ok(());
+	}
+	if proc.is_in_orphan_process_group() {
+		return Err(errno!(EIO));
+	}
+	{
+		let signal_manager = proc.signal.lock();
+		if signal_manager.is_signal_blocked(Signal::SIGTTIN) {
 			return Err(errno!(EIO));
 		}
-		{
-			let signal_manager = proc.signal.lock();
-			if signal_manager.is_signal_blocked(Signal::SIGTTIN) {
-				return Err(errno!(EIO));
-			}
-			let handler = signal_manager.handlers.lock()[Signal::SIGTTIN as usize].clone();
-			if matches!(handler, SignalHandler::Ignore) {
-				return Err(errno!(EIO));
-			}
+		let handler = signal_manager.handlers.lock()[Signal::SIGTTIN as usize].clone();
+		if matches!(handler, SignalHandler::Ignore) {
+			return Err(errno!(EIO));
 		}
-		proc.kill_group(Signal::SIGTTIN);
-		Ok(())
 	}
+	proc.kill_group(Signal::SIGTTIN);
+	Ok(())
+}
 
-	/// Checks whether the current process is allowed to write to the TTY.
-	///
-	/// If not, it is killed with a `SIGTTOU` signal.
-	///
-	/// This function must be called before performing the write operation.
-	fn check_sigttou(&self, tty: &TTYDisplay) -> EResult<()> {
-		let proc = Process::current();
-		if tty.get_termios().c_lflag & termios::consts::TOSTOP == 0 {
-			return Ok(());
-		}
-		{
-			let signal_manager = proc.signal.lock();
-			if signal_manager.is_signal_blocked(Signal::SIGTTOU) {
-				return Err(errno!(EIO));
-			}
-			let handler = signal_manager.handlers.lock()[Signal::SIGTTOU as usize].clone();
-			if matches!(handler, SignalHandler::Ignore) {
-				return Err(errno!(EIO));
-			}
+/// Checks whether the current process is allowed to write to the terminal `tty`.
+///
+/// If not, it is killed with a `SIGTTOU` signal.
+///
+/// This function must be called before performing the write operation.
+pub(super) fn check_sigttou(tty: &dyn Terminal) -> EResult<()> {
+	let proc = Process::current();
+	if proc.get_sid() != tty.get_sid() || proc.get_pgid() == tty.get_pgrp() {
+		return Ok(());
+	}
+	if tty.get_termios().c_lflag & termios::consts::TOSTOP == 0 {
+		return Ok(());
+	}
+	{
+		let signal_manager = proc.signal.lock();
+		if signal_manager.is_signal_blocked(Signal::SIGTTOU) {
+			return Err(errno!(EIO));
 		}
-		if proc.is_in_orphan_process_group() {
+		let handler = signal_manager.handlers.lock()[Signal::SIGTTOU as usize].clone();
+		if matches!(handler, SignalHandler::Ignore) {
 			return Err(errno!(EIO));
 		}
-		proc.kill_group(Signal::SIGTTOU);
-		Ok(())
 	}
+	if proc.is_in_orphan_process_group() {
+		return Err(errno!(EIO));
+	}
+	proc.kill_group(Signal::SIGTTOU);
+	Ok(())
+}
+
+/// Checks whether `tty` is the controlling terminal of the current process's session.
+///
+/// If not, the function returns [`errno::ENOTTY`].
+pub(super) fn check_controlling(tty: &dyn Terminal) -> EResult<Arc<Process>> {
+	let proc = Process::current();
+	if proc.get_sid() != tty.get_sid() {
+		return Err(errno!(ENOTTY));
+	}
+	Ok(proc)
 }
 
+/// Makes `tty` the controlling terminal of the current process's session when `file` is
+/// opened by a session leader, if the terminal has no session.
+pub(super) fn acquire_controlling(file: &File, tty: &mut dyn Terminal) {
+	if file.get_flags() & O_NOCTTY != 0 {
+		return;
+	}
+	let proc = Process::current();
+	if !proc.is_session_leader() {
+		return;
+	}
+	if tty.get_sid() == 0 {
+		tty.set_session(proc.get_pid(), proc.get_pgid());
+	}
+}
+
+/// A TTY device's handle.
+#[derive(Debug)]
+pub struct TTYDeviceHandle;
+
 impl FileOps for TTYDeviceHandle {
+	fn acquire(&self, file: &File) {
+		acquire_controlling(file, &mut *TTY.display.lock());
+	}
+
 	fn read(&self, _file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
-		self.check_sigttin(&TTY.display.lock())?;
+		check_sigttin(&*TTY.display.lock())?;
 		let len = TTY.read(buf)?;
 		Ok(len)
 	}
 
 	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
-		self.check_sigttou(&TTY.display.lock())?;
+		check_sigttou(&*TTY.display.lock())?;
 		TTY.display.lock().write(buf);
 		Ok(buf.len())
 	}
@@ -119,47 +165,152 @@ impl FileOps for TTYDeviceHandle {
 	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
 		let mut tty = TTY.display.lock();
 		match request.get_old_format() {
-			ioctl::TCGETS => {
-				let termios_ptr = SyscallPtr::<Termios>::from_ptr(argp as usize);
-				termios_ptr.copy_to_user(tty.get_termios())?;
-				Ok(0)
-			}
-			// TODO Implement correct behaviours for each
-			ioctl::TCSETS | ioctl::TCSETSW | ioctl::TCSETSF => {
-				self.check_sigttou(&tty)?;
-				let termios_ptr = SyscallPtr::<Termios>::from_ptr(argp as usize);
-				let termios = termios_ptr
+			ioctl::TIOCSTI => {
+				let proc = Process::current();
+				if proc.get_sid() != tty.get_sid()
+					&& !proc.fs.lock().access_profile.is_privileged()
+				{
+					return Err(errno!(EPERM));
+				}
+				let b = SyscallPtr::<u8>::from_ptr(argp as usize)
 					.copy_from_user()?
 					.ok_or_else(|| errno!(EFAULT))?;
-				tty.set_termios(termios.clone());
+				drop(tty);
+				TTY.input(&[b]);
 				Ok(0)
 			}
-			ioctl::TIOCGPGRP => {
-				let pgid_ptr = SyscallPtr::<Pid>::from_ptr(argp as usize);
-				pgid_ptr.copy_to_user(&tty.get_pgrp())?;
+			ioctl::GIO_CMAP => {
+				let mut cmap: ColorMap = [0; vga::PALETTE_SIZE * 3];
+				for (i, rgb) in cmap.chunks_exact_mut(3).enumerate() {
+					rgb.copy_from_slice(&vga::get_palette_color(i as _));
+				}
+				SyscallPtr::<ColorMap>::from_ptr(argp as usize).copy_to_user(&cmap)?;
 				Ok(0)
 			}
-			ioctl::TIOCSPGRP => {
-				self.check_sigttou(&tty)?;
-				let pgid_ptr = SyscallPtr::<Pid>::from_ptr(argp as usize);
-				let pgid = pgid_ptr.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-				tty.set_pgrp(pgid);
+			ioctl::PIO_CMAP => {
+				let cmap = SyscallPtr::<ColorMap>::from_ptr(argp as usize)
+					.copy_from_user()?
+					.ok_or_else(|| errno!(EFAULT))?;
+				for (i, rgb) in cmap.chunks_exact(3).enumerate() {
+					vga::set_palette_color(i as _, [rgb[0], rgb[1], rgb[2]]);
+				}
 				Ok(0)
 			}
-			ioctl::TIOCGWINSZ => {
-				let winsize = SyscallPtr::<WinSize>::from_ptr(argp as usize);
-				winsize.copy_to_user(tty.get_winsize())?;
+			ioctl::KDGCURSOR => {
+				let (visible, start, end) = tty.get_cursor();
+				let cursor = ConsoleCursor {
+					start,
+					end,
+					visible: visible as _,
+				};
+				SyscallPtr::<ConsoleCursor>::from_ptr(argp as usize).copy_to_user(&cursor)?;
 				Ok(0)
 			}
-			ioctl::TIOCSWINSZ => {
-				let winsize_ptr = SyscallPtr::<WinSize>::from_ptr(argp as usize);
-				let winsize = winsize_ptr
+			ioctl::KDSCURSOR => {
+				let cursor = SyscallPtr::<ConsoleCursor>::from_ptr(argp as usize)
 					.copy_from_user()?
 					.ok_or_else(|| errno!(EFAULT))?;
-				tty.set_winsize(winsize.clone());
+				tty.set_cursor_shape(cursor.start, cursor.end);
+				tty.set_cursor_visible(cursor.visible != 0);
 				Ok(0)
 			}
-			_ => Err(errno!(EINVAL)),
+			_ => terminal_ioctl(&mut *tty, request, argp),
+		}
+	}
+}
+
+/// Performs the ioctl operation `request` common to all terminals on `tty`.
+pub(super) fn terminal_ioctl(
+	tty: &mut dyn Terminal,
+	request: ioctl::Request,
+	argp: *const c_void,
+) -> EResult<u32> {
+	match request.get_old_format() {
+		ioctl::TCGETS => {
+			let termios_ptr = SyscallPtr::<Termios>::from_ptr(argp as usize);
+			termios_ptr.copy_to_user(tty.get_termios())?;
+			Ok(0)
+		}
+		// TODO Implement correct behaviours for each
+		ioctl::TCSETS | ioctl::TCSETSW | ioctl::TCSETSF => {
+			check_sigttou(tty)?;
+			let termios_ptr = SyscallPtr::<Termios>::from_ptr(argp as usize);
+			let termios = termios_ptr
+				.copy_from_user()?
+				.ok_or_else(|| errno!(EFAULT))?;
+			tty.set_termios(termios.clone());
+			Ok(0)
+		}
+		ioctl::TIOCGPGRP => {
+			check_controlling(tty)?;
+			let pgid_ptr = SyscallPtr::<Pid>::from_ptr(argp as usize);
+			pgid_ptr.copy_to_user(&tty.get_pgrp())?;
+			Ok(0)
+		}
+		ioctl::TIOCSPGRP => {
+			let proc = check_controlling(tty)?;
+			check_sigttou(tty)?;
+			let pgid_ptr = SyscallPtr::<Pid>::from_ptr(argp as usize);
+			let pgid = pgid_ptr.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			if pgid == 0 {
+				return Err(errno!(EINVAL));
+			}
+			// The process group must exist in the same session
+			let leader = Process::get_by_pid(pgid)
+				.filter(|p| p.get_pgid() == pgid)
+				.ok_or_else(|| errno!(ESRCH))?;
+			if leader.get_sid() != proc.get_sid() {
+				return Err(errno!(EPERM));
+			}
+			tty.set_pgrp(pgid);
+			Ok(0)
+		}
+		ioctl::TIOCSCTTY => {
+			let proc = Process::current();
+			let sid = proc.get_sid();
+			if tty.get_sid() == sid {
+				return Ok(0);
+			}
+			if !proc.is_session_leader() {
+				return Err(errno!(EPERM));
+			}
+			// Stealing the TTY from another session requires privileges
+			let old_sid = tty.get_sid();
+			if old_sid != 0 {
+				let privileged = proc.fs.lock().access_profile.is_privileged();
+				if argp as usize != 1 || !privileged {
+					return Err(errno!(EPERM));
+				}
+				tty.hangup(old_sid);
+			}
+			tty.set_session(sid, proc.get_pgid());
+			Ok(0)
+		}
+		ioctl::TIOCNOTTY => {
+			let proc = check_controlling(tty)?;
+			if proc.is_session_leader() {
+				tty.hangup(proc.get_sid());
+			}
+			Ok(0)
+		}
+		ioctl::TIOCGSID => {
+			check_controlling(tty)?;
+			SyscallPtr::<Pid>::from_ptr(argp as usize).copy_to_user(&tty.get_sid())?;
+			Ok(0)
+		}
+		ioctl::TIOCGWINSZ => {
+			let winsize = SyscallPtr::<WinSize>::from_ptr(argp as usize);
+			winsize.copy_to_user(tty.get_winsize())?;
+			Ok(0)
+		}
+		ioctl::TIOCSWINSZ => {
+			let winsize_ptr = SyscallPtr::<WinSize>::from_ptr(argp as usize);
+			let winsize = winsize_ptr
+				.copy_from_user()?
+				.ok_or_else(|| errno!(EFAULT))?;
+			tty.set_winsize(winsize.clone());
+			Ok(0)
 		}
+		_ => Err(errno!(EINVAL)),
 	}
 }
@@ -83,6 +83,13 @@ pub const EM_860: u16 = 7;
 pub const EM_MIPS: u16 = 8;
 /// Required architecture: MIPS RS4000 Big-Endian.
 pub const EM_MIPS_RS4_BE: u16 = 10;
+/// Required architecture: AMD x86-64.
+pub const EM_X86_64: u16 = 62;
+
+/// ELF version: Invalid version.
+pub const EV_NONE: u32 = 0;
+/// ELF version: Current version.
+pub const EV_CURRENT: u32 = 1;
 
 /// Program header type: Ignored.
 pub const PT_NULL: u32 = 0;
@@ -100,6 +107,11 @@ pub const PT_SHLIB: u32 = 5;
 pub const PT_PHDR: u32 = 6;
 /// Program header type: Thread-Local Storage (TLS).
 pub const PT_TLS: u32 = 7;
+/// Program header type: GNU extension specifying the executability of the stack.
+pub const PT_GNU_STACK: u32 = 0x6474e551;
+/// Program header type: GNU extension specifying a region to be made read-only after
+/// relocation.
+pub const PT_GNU_RELRO: u32 = 0x6474e552;
 
 /// Segment flag: Execute.
 pub const PF_X: u32 = 0x1;
@@ -516,16 +516,19 @@ impl<'data> ELFParser<'data> {
 		// Get full header
 		let ehdr = FileHeader::parse(image, class).ok_or_else(|| errno!(EINVAL))?;
 		// Check machine type
-		let valid = match ehdr.e_machine {
-			// x86 | Intel 80860
-			0x3 | 0x7 => cfg!(target_arch = "x86") || cfg!(target_arch = "x86_64"),
-			// AMD x86_64
-			0x3e => cfg!(target_arch = "x86_64"),
+		let valid = match (class, ehdr.e_machine) {
+			(Class::Bit32, EM_386) => true,
+			#[cfg(target_pointer_width = "64")]
+			(Class::Bit64, EM_X86_64) => true,
 			_ => false,
 		};
 		if unlikely(!valid) {
 			return Err(errno!(EINVAL));
 		}
+		// Check version
+		if unlikely(image[EI_VERSION] as u32 != EV_CURRENT || ehdr.e_version != EV_CURRENT) {
+			return Err(errno!(EINVAL));
+		}
 		// Check header validity
 		let min_size = match class {
 			Class::Bit32 => size_of::<ELF32ELFHeader>(),
@@ -797,3 +800,52 @@ impl<'data> ELFParser<'data> {
 		None
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns a minimal 32 bit image with the given machine type and version.
+	fn image(e_machine: u16, e_version: u32) -> [u8; 92] {
+		let mut e_ident = [0; EI_NIDENT];
+		e_ident[..4].copy_from_slice(b"\x7fELF");
+		e_ident[EI_CLASS] = ELFCLASS32;
+		e_ident[EI_DATA] = ELFDATA2LSB;
+		e_ident[EI_VERSION] = EV_CURRENT as _;
+		let hdr = ELF32ELFHeader {
+			e_ident,
+			e_type: ET_EXEC,
+			e_machine,
+			e_version,
+			e_entry: 0,
+			e_phoff: 0,
+			e_shoff: size_of::<ELF32ELFHeader>() as _,
+			e_flags: 0,
+			e_ehsize: size_of::<ELF32ELFHeader>() as _,
+			e_phentsize: size_of::<ELF32ProgramHeader>() as _,
+			e_phnum: 0,
+			e_shentsize: size_of::<ELF32SectionHeader>() as _,
+			// A single null section
+			e_shnum: 1,
+			e_shstrndx: 0,
+		};
+		let mut image = [0; 92];
+		image[..size_of::<ELF32ELFHeader>()].copy_from_slice(bytes::as_bytes(&hdr));
+		image
+	}
+
+	#[test_case]
+	fn elf_machine() {
+		assert!(ELFParser::new(&image(EM_386, EV_CURRENT)).is_ok());
+		assert!(ELFParser::new(&image(EM_SPARC, EV_CURRENT)).is_err());
+		assert!(ELFParser::new(&image(EM_860, EV_CURRENT)).is_err());
+		// A 32 bit image cannot be for x86_64
+		assert!(ELFParser::new(&image(EM_X86_64, EV_CURRENT)).is_err());
+	}
+
+	#[test_case]
+	fn elf_version() {
+		assert!(ELFParser::new(&image(EM_386, EV_NONE)).is_err());
+		assert!(ELFParser::new(&image(EM_386, 2)).is_err());
+	}
+}
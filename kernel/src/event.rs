@@ -24,7 +24,7 @@ use crate::{
 	process,
 	sync::mutex::IntMutex,
 };
-use core::ptr;
+use core::{intrinsics::unlikely, ptr};
 use utils::{collections::vec::Vec, errno::AllocResult};
 
 /// The list of interrupt error messages ordered by index of the corresponding
@@ -65,6 +65,11 @@ static ERROR_MESSAGES: &[&str] = &[
 	"Unknown",
 ];
 
+/// The interrupt vector of Non-Maskable Interrupts.
+const NMI: u32 = 0x02;
+/// The interrupt vector of double faults.
+const DOUBLE_FAULT: u32 = 0x08;
+
 /// The action to execute after the interrupt handler has returned.
 pub enum CallbackResult {
 	/// Executes remaining callbacks for the interrupt.
@@ -148,15 +153,20 @@ pub fn register_callback(id: u32, callback: Callback) -> AllocResult<Option<Call
 /// `frame` is the stack frame of the interruption, with general purpose registers saved.
 #[no_mangle]
 extern "C" fn interrupt_handler(frame: &mut IntFrame) {
+	let id = frame.int as u32;
+	// A double fault cannot be recovered from, and locks may be held by the interrupted context
+	if unlikely(id == DOUBLE_FAULT) {
+		panic!("Double Fault, code: {:x}", frame.code);
+	}
 	// Feed entropy pool
 	{
 		let buf = utils::bytes::as_bytes(frame);
 		let mut pool = rand::ENTROPY_POOL.lock();
 		if let Some(pool) = &mut *pool {
 			pool.write(buf);
+			pool.tick();
 		}
 	}
-	let id = frame.int as u32;
 	let ring = (frame.cs & 0b11) as u8;
 	let code = frame.code as u32;
 	// Call corresponding callbacks
@@ -181,5 +191,9 @@ extern "C" fn interrupt_handler(frame: &mut IntFrame) {
 	if let Some(irq) = id.checked_sub(ERROR_MESSAGES.len() as u32) {
 		pic::end_of_interrupt(irq as _);
 	}
+	// The NMI runs on a dedicated stack, which must not be left by switching context
+	if id == NMI {
+		return;
+	}
 	process::yield_current(ring, frame);
 }
@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Fault injection makes operations fail on purpose, to exercise error paths that are rarely
+//! taken otherwise.
+//!
+//! Each process has a counter, set through `/proc/<pid>/fail-nth`. When the counter is set to
+//! `n`, the `n`th operation able to fail performed while the process is running fails. The
+//! counter is decremented by each of these operations, down to zero, at which point the fault
+//! has been injected.
+//!
+//! The following operations are able to fail:
+//! - allocations from [`malloc`], with [`AllocError`]
+//! - allocations from [`buddy`], with [`AllocError`]
+//! - reads from block devices, with [`errno::EIO`]
+//!
+//! The same allocation may count more than once, since [`malloc`] may allocate from [`buddy`].
+//!
+//! [`malloc`]: crate::memory::malloc
+//! [`buddy`]: crate::memory::buddy
+//! [`AllocError`]: core::alloc::AllocError
+//! [`errno::EIO`]: utils::errno::EIO
+
+use crate::process::Process;
+use core::{
+	ptr,
+	sync::atomic::{AtomicPtr, AtomicU32, Ordering::Relaxed},
+};
+
+/// The counter of the process currently running, or null if none.
+static CURRENT: AtomicPtr<AtomicU32> = AtomicPtr::new(ptr::null_mut());
+
+/// Binds `counter` as the counter of the process currently running.
+fn bind(counter: *const AtomicU32) {
+	CURRENT.store(counter.cast_mut(), Relaxed);
+}
+
+/// Binds the counter of `proc`, which is about to run.
+pub fn switch(proc: &Process) {
+	bind(&proc.fail_nth);
+}
+
+/// Tells whether the operation about to be performed shall fail.
+pub fn should_fail() -> bool {
+	// The pointer is valid as long as the process is running
+	let Some(counter) = (unsafe { CURRENT.load(Relaxed).as_ref() }) else {
+		return false;
+	};
+	counter
+		.fetch_update(Relaxed, Relaxed, |n| n.checked_sub(1))
+		.is_ok_and(|n| n == 1)
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::memory::{buddy, PhysAddr};
+	use utils::errno::AllocResult;
+
+	/// Allocates three frames, then frees them.
+	fn alloc_three() -> AllocResult<()> {
+		let mut frames: [Option<PhysAddr>; 3] = [None; 3];
+		let res = frames.iter_mut().try_for_each(|f| {
+			*f = Some(buddy::alloc(0, buddy::ZONE_KERNEL)?);
+			Ok(())
+		});
+		for f in frames.into_iter().flatten() {
+			unsafe {
+				buddy::free(f, 0);
+			}
+		}
+		res
+	}
+
+	#[test_case]
+	fn fail_third_alloc() {
+		static COUNTER: AtomicU32 = AtomicU32::new(0);
+		let alloc_pages = buddy::allocated_pages_count();
+		bind(&COUNTER);
+		COUNTER.store(3, Relaxed);
+		let res = alloc_three();
+		let left = COUNTER.load(Relaxed);
+		bind(ptr::null());
+		assert!(res.is_err());
+		assert_eq!(left, 0);
+		assert_eq!(buddy::allocated_pages_count(), alloc_pages);
+		// Once injected, the fault does not happen again
+		assert!(alloc_three().is_ok());
+	}
+}
@@ -0,0 +1,207 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! An epoll instance allows a process to wait for events on a set of files registered on it.
+//!
+//! Files are identified by both their file descriptor and their open file description. If the
+//! file descriptor gets closed, the file stops being monitored.
+
+use crate::{
+	file::{fd::FileDescriptorTable, fs::FileOps, wait_queue, File, Stat},
+	sync::mutex::Mutex,
+	syscall::poll::{POLLERR, POLLHUP},
+};
+use core::ffi::c_int;
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// `epoll_ctl` operation: Register a file.
+pub const EPOLL_CTL_ADD: c_int = 1;
+/// `epoll_ctl` operation: Unregister a file.
+pub const EPOLL_CTL_DEL: c_int = 2;
+/// `epoll_ctl` operation: Change the events of a registered file.
+pub const EPOLL_CTL_MOD: c_int = 3;
+
+/// Flag: Only one process is woken up on events. This is the default behaviour of the kernel, so
+/// the flag is accepted but ignored.
+pub const EPOLLEXCLUSIVE: u32 = 1 << 28;
+/// Flag: Prevent the system from suspending. Accepted but ignored.
+pub const EPOLLWAKEUP: u32 = 1 << 29;
+/// Flag: Disable the file once an event has been reported, until it is modified again.
+pub const EPOLLONESHOT: u32 = 1 << 30;
+/// Flag: Edge-triggered, report events only when they become ready.
+pub const EPOLLET: u32 = 1 << 31;
+/// Mask of flags that are not events.
+const FLAGS_MASK: u32 = EPOLLEXCLUSIVE | EPOLLWAKEUP | EPOLLONESHOT | EPOLLET;
+
+/// An event passed to and returned by epoll system calls.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EPollEvent {
+	/// The mask of events, along with flags.
+	pub events: u32,
+	/// User data, returned as is along with events.
+	pub data: u64,
+}
+
+/// A file registered on an epoll instance.
+#[derive(Debug)]
+struct Interest {
+	/// The file descriptor.
+	fd: c_int,
+	/// The address of the open file description.
+	///
+	/// This allows to tell a registered file apart from another file that got the same file
+	/// descriptor after the first one was closed.
+	file: usize,
+	/// The requested mask of events, along with flags.
+	events: u32,
+	/// User data.
+	data: u64,
+	/// For edge-triggered files, the events that were ready at the last check.
+	last: u32,
+	/// For edge-triggered files, the value of [`wait_queue::poll_seq`] at the last report.
+	seq: usize,
+}
+
+/// An epoll instance.
+#[derive(Debug, Default)]
+pub struct EPoll {
+	/// The list of registered files.
+	interests: Mutex<Vec<Interest>>,
+}
+
+impl EPoll {
+	/// Returns the index of the interest for `fd`, discarding it if it refers to another file
+	/// than `file`.
+	fn find(interests: &mut Vec<Interest>, fd: c_int, file: &Arc<File>) -> Option<usize> {
+		let addr = Arc::as_ptr(file) as usize;
+		let i = interests.iter().position(|i| i.fd == fd)?;
+		if interests[i].file != addr {
+			interests.remove(i);
+			return None;
+		}
+		Some(i)
+	}
+
+	/// Registers `file`, open on the file descriptor `fd`, with the given `event`.
+	///
+	/// If the file is already registered, the function returns [`errno::EEXIST`]. If it does not
+	/// support polling, the function returns [`errno::EPERM`].
+	pub fn add(&self, fd: c_int, file: &Arc<File>, event: EPollEvent) -> EResult<()> {
+		match file.ops.poll(file, 0) {
+			Err(e) if e.as_int() == errno::EINVAL => return Err(errno!(EPERM)),
+			res => res?,
+		};
+		let mut interests = self.interests.lock();
+		if Self::find(&mut interests, fd, file).is_some() {
+			return Err(errno!(EEXIST));
+		}
+		interests.push(Interest {
+			fd,
+			file: Arc::as_ptr(file) as usize,
+			events: event.events,
+			data: event.data,
+			last: 0,
+			seq: wait_queue::poll_seq(),
+		})?;
+		Ok(())
+	}
+
+	/// Changes the event of the registered `file`, open on the file descriptor `fd`.
+	///
+	/// If the file is not registered, the function returns [`errno::ENOENT`].
+	pub fn modify(&self, fd: c_int, file: &Arc<File>, event: EPollEvent) -> EResult<()> {
+		let mut interests = self.interests.lock();
+		let i = Self::find(&mut interests, fd, file).ok_or_else(|| errno!(ENOENT))?;
+		let interest = &mut interests[i];
+		interest.events = event.events;
+		interest.data = event.data;
+		interest.last = 0;
+		interest.seq = wait_queue::poll_seq();
+		Ok(())
+	}
+
+	/// Unregisters `file`, open on the file descriptor `fd`.
+	///
+	/// If the file is not registered, the function returns [`errno::ENOENT`].
+	pub fn remove(&self, fd: c_int, file: &Arc<File>) -> EResult<()> {
+		let mut interests = self.interests.lock();
+		let i = Self::find(&mut interests, fd, file).ok_or_else(|| errno!(ENOENT))?;
+		interests.remove(i);
+		Ok(())
+	}
+
+	/// Checks registered files for events and returns at most `max` of them.
+	///
+	/// `fds` is the file descriptor table of the process.
+	pub fn collect(&self, fds: &FileDescriptorTable, max: usize) -> EResult<Vec<EPollEvent>> {
+		let mut events = Vec::new();
+		let mut interests = self.interests.lock();
+		let seq = wait_queue::poll_seq();
+		let mut i = 0;
+		while i < interests.len() && events.len() < max {
+			let interest = &mut interests[i];
+			// Stop monitoring files whose file descriptor got closed
+			let file = fds
+				.get_fd(interest.fd)
+				.ok()
+				.map(|fd| fd.get_file())
+				.filter(|file| Arc::as_ptr(file) as usize == interest.file);
+			let Some(file) = file else {
+				interests.remove(i);
+				continue;
+			};
+			i += 1;
+			let mask = interest.events & !FLAGS_MASK;
+			// Disabled by `EPOLLONESHOT`
+			if mask == 0 {
+				continue;
+			}
+			let ready = file.ops.poll(file, mask)? & (mask | POLLHUP | POLLERR);
+			if interest.events & EPOLLET != 0 {
+				// Report if an event became ready, or if events have been signaled since the
+				// last report
+				let report = ready != 0 && (ready & !interest.last != 0 || seq != interest.seq);
+				interest.last = ready;
+				if !report {
+					continue;
+				}
+				interest.seq = seq;
+			} else if ready == 0 {
+				continue;
+			}
+			if interest.events & EPOLLONESHOT != 0 {
+				interest.events &= FLAGS_MASK;
+			}
+			events.push(EPollEvent {
+				events: ready,
+				data: interest.data,
+			})?;
+		}
+		Ok(events)
+	}
+}
+
+impl FileOps for EPoll {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: 0o600,
+			..Default::default()
+		})
+	}
+}
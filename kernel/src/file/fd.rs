@@ -187,16 +187,22 @@ impl FileDescriptorTable {
 	/// to ensure the first file descriptor is not created if the creation of the second fails.
 	///
 	/// Arguments:
+	/// - `flags` is the set of flags of both file descriptors
 	/// - `file0` is the file associated with the first file descriptor
 	/// - `file1` is the file associated with the second file descriptor
 	///
 	/// The function returns the IDs of the new file descriptors.
-	pub fn create_fd_pair(&mut self, file0: Arc<File>, file1: Arc<File>) -> EResult<(u32, u32)> {
+	pub fn create_fd_pair(
+		&mut self,
+		flags: i32,
+		file0: Arc<File>,
+		file1: Arc<File>,
+	) -> EResult<(u32, u32)> {
 		let id0 = self.get_available_fd(None)?;
 		// Add a constraint to avoid using twice the same ID
 		let id1 = self.get_available_fd(Some(id0 + 1))?;
-		let fd0 = FileDescriptor::new(0, file0)?;
-		let fd1 = FileDescriptor::new(0, file1)?;
+		let fd0 = FileDescriptor::new(flags, file0)?;
+		let fd1 = FileDescriptor::new(flags, file1)?;
 		// Insert the FDs
 		self.extend(id1)?; // `id1` is always larger than `id0`
 		self.0[id0 as usize] = Some(fd0);
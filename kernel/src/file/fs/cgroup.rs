@@ -0,0 +1,426 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `cgroup2` filesystem exposes the hierarchy of control groups.
+//!
+//! Each group is a directory containing the files used to manage it. Creating a directory creates
+//! a child group, and removing it removes the group, which must have no process and no child.
+//!
+//! Files of each group:
+//! - `cgroup.procs`: the PIDs of the processes of the group. Writing a PID moves the process into
+//!   the group, `0` designating the writing process
+//! - `cpu.max`: the CPU quota and period, in microseconds. The quota is `max` if unlimited
+//! - `cpu.stat`: statistics about the CPU usage of the group
+//! - `memory.current`: the amount of memory used by the group, in bytes
+//! - `memory.max`: the maximum amount of memory the group may use in bytes, or `max` if unlimited
+//!
+//! The root group has no `cpu.max` and `memory.max`, since it cannot be limited.
+
+use super::{
+	kernfs::{box_file, EitherOps, StaticDir, StaticEntry},
+	DummyOps, FileOps, Filesystem, FilesystemOps, FilesystemType, NodeOps, Statfs,
+};
+use crate::{
+	device::BlkDev,
+	file::{vfs, vfs::node::Node, DirContext, DirEntry, File, FileType, Stat},
+	format_content,
+	process::{cgroup, cgroup::Cgroup, Process},
+	sync::mutex::Mutex,
+	time::unit::Timestamp,
+};
+use core::{any::Any, fmt, str, sync::atomic::AtomicBool};
+use utils::{
+	boxed::Box,
+	collections::path::PathBuf,
+	errno,
+	errno::{AllocResult, EResult},
+	ptr::arc::Arc,
+};
+
+/// The magic number of the filesystem.
+const CGROUP2_SUPER_MAGIC: u32 = 0x63677270;
+
+/// The minimum CPU quota and period, in microseconds.
+const CPU_MIN: u64 = 1000;
+/// The maximum CPU period, in microseconds.
+const CPU_PERIOD_MAX: u64 = 1_000_000;
+
+/// Returns the status of a file with the given permissions.
+fn file_stat(perm: u32) -> Stat {
+	Stat {
+		mode: FileType::Regular.to_mode() | perm,
+		..Default::default()
+	}
+}
+
+/// Returns the status of a group's directory.
+fn dir_stat() -> Stat {
+	Stat {
+		mode: FileType::Directory.to_mode() | 0o755,
+		..Default::default()
+	}
+}
+
+/// Parses `buf` as a value which may be `max` to mean unlimited.
+fn parse_max<T: str::FromStr>(buf: &[u8]) -> EResult<Option<T>> {
+	let s = str::from_utf8(buf).map_err(|_| errno!(EINVAL))?;
+	match s {
+		"max" => Ok(None),
+		s => s.parse().map(Some).map_err(|_| errno!(EINVAL)),
+	}
+}
+
+/// Displays a value which may be `max` if unlimited.
+struct DisplayMax<T>(Option<T>);
+
+impl<T: fmt::Display> fmt::Display for DisplayMax<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match &self.0 {
+			Some(val) => write!(f, "{val}"),
+			None => write!(f, "max"),
+		}
+	}
+}
+
+/// The `cgroup.procs` file.
+#[derive(Debug)]
+struct Procs(Arc<Cgroup>);
+
+impl FileOps for Procs {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let pids = self.0.processes()?;
+		let pids = fmt::from_fn(|f| pids.iter().try_for_each(|pid| writeln!(f, "{pid}")));
+		format_content!(off, buf, "{pids}")
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let pid = str::from_utf8(buf.trim_ascii())
+			.ok()
+			.and_then(|s| s.parse().ok())
+			.ok_or_else(|| errno!(EINVAL))?;
+		let proc = match pid {
+			0 => Process::current(),
+			pid => Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))?,
+		};
+		proc.set_cgroup(self.0.clone());
+		Ok(buf.len())
+	}
+}
+
+/// The `cpu.max` file.
+#[derive(Debug)]
+struct CpuMax(Arc<Cgroup>);
+
+impl FileOps for CpuMax {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let (quota, period) = self.0.cpu_max();
+		let quota = DisplayMax(quota.map(|q| q / 1000));
+		format_content!(off, buf, "{quota} {}\n", period / 1000)
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let mut iter = buf.trim_ascii().split(u8::is_ascii_whitespace);
+		let quota: Option<u64> = parse_max(iter.next().unwrap_or_default())?;
+		// If the period is not specified, keep the current one
+		let period = match iter.next() {
+			Some(period) => parse_max(period)?.ok_or_else(|| errno!(EINVAL))?,
+			None => self.0.cpu_max().1 / 1000,
+		};
+		if iter.next().is_some()
+			|| quota.is_some_and(|q| q < CPU_MIN)
+			|| !(CPU_MIN..=CPU_PERIOD_MAX).contains(&period)
+		{
+			return Err(errno!(EINVAL));
+		}
+		let us_to_ns = |us: u64| -> Timestamp { us * 1000 };
+		self.0.set_cpu_max(quota.map(us_to_ns), us_to_ns(period));
+		Ok(buf.len())
+	}
+}
+
+/// The `cpu.stat` file.
+#[derive(Debug)]
+struct CpuStat(Arc<Cgroup>);
+
+impl FileOps for CpuStat {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let (usage, nr_periods, nr_throttled) = self.0.cpu_stat();
+		format_content!(
+			off,
+			buf,
+			"usage_usec {}\nnr_periods {nr_periods}\nnr_throttled {nr_throttled}\n",
+			usage / 1000
+		)
+	}
+}
+
+/// The `memory.current` file.
+#[derive(Debug)]
+struct MemoryCurrent(Arc<Cgroup>);
+
+impl FileOps for MemoryCurrent {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}\n", self.0.memory_usage())
+	}
+}
+
+/// The `memory.max` file.
+#[derive(Debug)]
+struct MemoryMax(Arc<Cgroup>);
+
+impl FileOps for MemoryMax {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}\n", DisplayMax(self.0.memory_max()))
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let max = parse_max(buf.trim_ascii())?;
+		self.0.set_memory_max(max);
+		Ok(buf.len())
+	}
+}
+
+/// The files of the root group, sorted by name.
+const ROOT_FILES: &[StaticEntry<Arc<Cgroup>>] = &[
+	StaticEntry {
+		name: b"cgroup.procs",
+		stat: |_| file_stat(0o644),
+		init: EitherOps::File(|g| box_file(Procs(g))),
+	},
+	StaticEntry {
+		name: b"cpu.stat",
+		stat: |_| file_stat(0o444),
+		init: EitherOps::File(|g| box_file(CpuStat(g))),
+	},
+	StaticEntry {
+		name: b"memory.current",
+		stat: |_| file_stat(0o444),
+		init: EitherOps::File(|g| box_file(MemoryCurrent(g))),
+	},
+];
+
+/// The files of other groups, sorted by name.
+const GROUP_FILES: &[StaticEntry<Arc<Cgroup>>] = &[
+	StaticEntry {
+		name: b"cgroup.procs",
+		stat: |_| file_stat(0o644),
+		init: EitherOps::File(|g| box_file(Procs(g))),
+	},
+	StaticEntry {
+		name: b"cpu.max",
+		stat: |_| file_stat(0o644),
+		init: EitherOps::File(|g| box_file(CpuMax(g))),
+	},
+	StaticEntry {
+		name: b"cpu.stat",
+		stat: |_| file_stat(0o444),
+		init: EitherOps::File(|g| box_file(CpuStat(g))),
+	},
+	StaticEntry {
+		name: b"memory.current",
+		stat: |_| file_stat(0o444),
+		init: EitherOps::File(|g| box_file(MemoryCurrent(g))),
+	},
+	StaticEntry {
+		name: b"memory.max",
+		stat: |_| file_stat(0o644),
+		init: EitherOps::File(|g| box_file(MemoryMax(g))),
+	},
+];
+
+/// The directory of a group.
+#[derive(Debug)]
+struct GroupDir {
+	/// The group. This is `None` for a directory that has just been created, until it is linked
+	/// into its parent.
+	group: Mutex<Option<Arc<Cgroup>>>,
+}
+
+impl GroupDir {
+	/// Creates the node of the directory of `group`.
+	fn new_node(fs: Arc<Filesystem>, group: Option<Arc<Cgroup>>) -> AllocResult<Arc<Node>> {
+		Arc::new(Node {
+			inode: 0,
+			fs,
+
+			stat: Mutex::new(dir_stat()),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(GroupDir {
+				group: Mutex::new(group),
+			})?,
+			file_ops: Box::new(DummyOps)?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		})
+	}
+
+	/// Returns the group, or [`errno::ENOENT`] if it has been removed or is not linked yet.
+	fn group(&self) -> EResult<Arc<Cgroup>> {
+		self.group.lock().clone().ok_or_else(|| errno!(ENOENT))
+	}
+
+	/// Returns the files of the directory, as a [`StaticDir`].
+	fn files(&self) -> EResult<StaticDir<Arc<Cgroup>>> {
+		let group = self.group()?;
+		let entries = if group.parent.is_some() {
+			GROUP_FILES
+		} else {
+			ROOT_FILES
+		};
+		Ok(StaticDir {
+			entries,
+			data: group,
+		})
+	}
+}
+
+impl NodeOps for GroupDir {
+	fn lookup_entry(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
+		let files = self.files()?;
+		files.lookup_entry(dir, ent)?;
+		if ent.node.is_some() {
+			return Ok(());
+		}
+		let child = files.data.children.lock().get(ent.name.as_bytes()).cloned();
+		ent.node = child
+			.map(|child| Self::new_node(dir.fs.clone(), Some(child)))
+			.transpose()?;
+		Ok(())
+	}
+
+	fn iter_entries(&self, dir: &Node, ctx: &mut DirContext) -> EResult<()> {
+		let files = self.files()?;
+		let files_count = files.entries.len() as u64;
+		if ctx.off < files_count {
+			files.iter_entries(dir, ctx)?;
+			if ctx.off < files_count {
+				return Ok(());
+			}
+		}
+		let children = files.data.children.lock();
+		let iter = children.iter().skip((ctx.off - files_count) as usize);
+		for (name, _) in iter {
+			let ent = DirEntry {
+				inode: 0,
+				entry_type: Some(FileType::Directory),
+				name,
+			};
+			if !(ctx.write)(&ent)? {
+				break;
+			}
+			ctx.off += 1;
+		}
+		Ok(())
+	}
+
+	fn link(&self, _parent: Arc<Node>, ent: &vfs::Entry) -> EResult<()> {
+		let files = self.files()?;
+		if files.entries.iter().any(|e| e.name == ent.name.as_bytes()) {
+			return Err(errno!(EEXIST));
+		}
+		let child = Cgroup::create_child(&files.data, &ent.name)?;
+		let node = ent.node();
+		let dir = (&*node.node_ops as &dyn Any)
+			.downcast_ref::<GroupDir>()
+			.ok_or_else(|| errno!(EINVAL))?;
+		*dir.group.lock() = Some(child);
+		Ok(())
+	}
+
+	fn unlink(&self, _parent: &Node, ent: &vfs::Entry) -> EResult<()> {
+		let files = self.files()?;
+		if files.entries.iter().any(|e| e.name == ent.name.as_bytes()) {
+			return Err(errno!(EPERM));
+		}
+		files.data.remove_child(&ent.name)?;
+		// Invalidate the node, in case it is still referenced
+		if let Some(node) = &ent.node {
+			if let Some(dir) = (&*node.node_ops as &dyn Any).downcast_ref::<GroupDir>() {
+				dir.group.lock().take();
+			}
+		}
+		Ok(())
+	}
+}
+
+/// A cgroup2 filesystem.
+#[derive(Debug)]
+pub struct CgroupFs;
+
+impl FilesystemOps for CgroupFs {
+	fn get_name(&self) -> &[u8] {
+		b"cgroup2"
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: CGROUP2_SUPER_MAGIC as _,
+			f_bsize: 0,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: 0,
+			f_frsize: 0,
+			f_flags: 0,
+			f_spare: [0; 4],
+		})
+	}
+
+	fn root(&self, fs: Arc<Filesystem>) -> EResult<Arc<Node>> {
+		Ok(GroupDir::new_node(fs, Some(cgroup::root().clone()))?)
+	}
+
+	fn create_node(&self, fs: Arc<Filesystem>, stat: Stat) -> EResult<Arc<Node>> {
+		// Only groups can be created
+		if stat.get_type() != Some(FileType::Directory) {
+			return Err(errno!(EPERM));
+		}
+		Ok(GroupDir::new_node(fs, None)?)
+	}
+
+	fn destroy_node(&self, _node: &Node) -> EResult<()> {
+		Ok(())
+	}
+}
+
+/// The cgroup2 filesystem type.
+pub struct CgroupFsType;
+
+impl FilesystemType for CgroupFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"cgroup2"
+	}
+
+	fn detect(&self, _dev: &Arc<BlkDev>) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_dev: Option<Arc<BlkDev>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<Filesystem>> {
+		Ok(Filesystem::new(0, Box::new(CgroupFs)?)?)
+	}
+}
@@ -0,0 +1,353 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `debugfs` is a virtual filesystem in which kernel subsystems expose their internal state
+//! for diagnostics. It is usually mounted at `/sys/kernel/debug`.
+//!
+//! Files are registered at runtime with [`create_file`], along with the functions called when
+//! they are read or written. Directories are implicit: a directory exists as long as it contains
+//! at least one file.
+
+use super::{
+	kernfs::box_file, DummyOps, FileOps, Filesystem, FilesystemOps, FilesystemType, NodeOps,
+	Statfs,
+};
+use crate::{
+	device::BlkDev,
+	file::{vfs, vfs::node::Node, DirContext, DirEntry, File, FileType, Stat},
+	sync::mutex::Mutex,
+};
+use core::sync::atomic::AtomicBool;
+use utils::{
+	boxed::Box,
+	collections::{
+		hashmap::HashMap,
+		path::{Component, Path, PathBuf},
+		string::String,
+		vec::Vec,
+	},
+	errno,
+	errno::{AllocResult, EResult},
+	ptr::arc::Arc,
+};
+
+/// The magic number of the filesystem.
+const DEBUGFS_MAGIC: u32 = 0x64626720;
+
+/// Function writing the content of a file to `buf`, starting at offset `off`.
+///
+/// The function returns the number of bytes written to `buf`. It is usually implemented with
+/// [`crate::format_content`].
+pub type ReadFn = fn(off: u64, buf: &mut [u8]) -> EResult<usize>;
+/// Function handling the write of `buf` to a file.
+///
+/// The function returns the number of bytes consumed from `buf`.
+pub type WriteFn = fn(buf: &[u8]) -> EResult<usize>;
+
+/// A registered file.
+#[derive(Clone, Copy, Debug)]
+struct DebugFile {
+	/// The function called on read. If `None`, the file is not readable.
+	read: Option<ReadFn>,
+	/// The function called on write. If `None`, the file is not writable.
+	write: Option<WriteFn>,
+}
+
+impl DebugFile {
+	/// Returns the status of the file.
+	fn stat(&self) -> Stat {
+		let mut perm = 0;
+		if self.read.is_some() {
+			perm |= 0o444;
+		}
+		if self.write.is_some() {
+			perm |= 0o200;
+		}
+		Stat {
+			mode: FileType::Regular.to_mode() | perm,
+			..Default::default()
+		}
+	}
+}
+
+/// The registered files, by path relative to the root of the filesystem.
+static FILES: Mutex<HashMap<String, DebugFile>> = Mutex::new(HashMap::new());
+
+/// Returns `path` relative to the root of the filesystem, with components separated by a single
+/// slash.
+fn normalize(path: &[u8]) -> EResult<String> {
+	let mut res = String::new();
+	for comp in Path::new(path)?.components() {
+		match comp {
+			Component::RootDir | Component::CurDir => {}
+			Component::ParentDir => return Err(errno!(EINVAL)),
+			Component::Normal(name) => {
+				if !res.is_empty() {
+					res.push(b'/')?;
+				}
+				res.push_str(name)?;
+			}
+		}
+	}
+	if res.is_empty() {
+		return Err(errno!(EINVAL));
+	}
+	Ok(res)
+}
+
+/// If `path` is located inside the directory `dir`, returns the path relative to it.
+///
+/// An empty `dir` designates the root of the filesystem.
+fn strip_dir<'p>(dir: &[u8], path: &'p [u8]) -> Option<&'p [u8]> {
+	if dir.is_empty() {
+		return Some(path);
+	}
+	path.strip_prefix(dir)?.strip_prefix(b"/")
+}
+
+/// Registers a file at `path`, relative to the root of the filesystem.
+///
+/// Arguments:
+/// - `read` is called when the file is read. If `None`, the file is not readable
+/// - `write` is called when the file is written. If `None`, the file is not writable
+///
+/// Missing parent directories are created implicitly.
+///
+/// If a file already exists at `path`, or if any parent is a file, the function returns
+/// [`errno::EEXIST`].
+pub fn create_file(path: &[u8], read: Option<ReadFn>, write: Option<WriteFn>) -> EResult<()> {
+	let path = normalize(path)?;
+	let mut files = FILES.lock();
+	let conflict = files.iter().any(|(p, _)| {
+		*p == path || strip_dir(&path, p).is_some() || strip_dir(p, &path).is_some()
+	});
+	if conflict {
+		return Err(errno!(EEXIST));
+	}
+	files.insert(
+		path,
+		DebugFile {
+			read,
+			write,
+		},
+	)?;
+	Ok(())
+}
+
+/// Removes the file registered at `path`.
+///
+/// If no file is registered at `path`, the function returns [`errno::ENOENT`].
+pub fn remove_file(path: &[u8]) -> EResult<()> {
+	let path = normalize(path)?;
+	FILES
+		.lock()
+		.remove(path.as_bytes())
+		.map(|_| ())
+		.ok_or_else(|| errno!(ENOENT))
+}
+
+/// Returns the status of a directory.
+fn dir_stat() -> Stat {
+	Stat {
+		mode: FileType::Directory.to_mode() | 0o755,
+		..Default::default()
+	}
+}
+
+/// Creates a node with the given status and operations.
+fn new_node(
+	fs: Arc<Filesystem>,
+	stat: Stat,
+	node_ops: Box<dyn NodeOps>,
+	file_ops: Box<dyn FileOps>,
+) -> AllocResult<Arc<Node>> {
+	Arc::new(Node {
+		inode: 0,
+		fs,
+
+		stat: Mutex::new(stat),
+		dirty: AtomicBool::new(false),
+
+		node_ops,
+		file_ops,
+
+		lock: Default::default(),
+		mapped: Default::default(),
+	})
+}
+
+/// A directory, with its path relative to the root of the filesystem.
+#[derive(Debug)]
+struct DebugDir(String);
+
+impl NodeOps for DebugDir {
+	fn lookup_entry(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
+		let mut path = String::try_from(self.0.as_bytes())?;
+		if !path.is_empty() {
+			path.push(b'/')?;
+		}
+		path.push_str(&ent.name)?;
+		let files = FILES.lock();
+		let (stat, node_ops, file_ops) = if let Some(file) = files.get(path.as_bytes()) {
+			(
+				file.stat(),
+				Box::new(DummyOps)? as _,
+				box_file(FileNode(path))?,
+			)
+		} else if files.iter().any(|(p, _)| strip_dir(&path, p).is_some()) {
+			(
+				dir_stat(),
+				Box::new(DebugDir(path))? as _,
+				Box::new(DummyOps)? as _,
+			)
+		} else {
+			ent.node = None;
+			return Ok(());
+		};
+		drop(files);
+		ent.node = Some(new_node(dir.fs.clone(), stat, node_ops, file_ops)?);
+		Ok(())
+	}
+
+	fn iter_entries(&self, _dir: &Node, ctx: &mut DirContext) -> EResult<()> {
+		let files = FILES.lock();
+		// Collect the direct children of the directory, sorted so that offsets are stable
+		let mut children: Vec<(&[u8], FileType)> = Vec::new();
+		for (path, _) in files.iter() {
+			let Some(rest) = strip_dir(&self.0, path) else {
+				continue;
+			};
+			let child = match rest.iter().position(|b| *b == b'/') {
+				Some(i) => (&rest[..i], FileType::Directory),
+				None => (rest, FileType::Regular),
+			};
+			if !children.iter().any(|(name, _)| *name == child.0) {
+				children.push(child)?;
+			}
+		}
+		children.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+		for (name, entry_type) in children.iter().skip(ctx.off as usize) {
+			let ent = DirEntry {
+				inode: 0,
+				entry_type: Some(*entry_type),
+				name,
+			};
+			if !(ctx.write)(&ent)? {
+				break;
+			}
+			ctx.off += 1;
+		}
+		Ok(())
+	}
+}
+
+/// A file, with its path relative to the root of the filesystem.
+///
+/// The file's functions are looked up on each operation, so that a removed file cannot be used
+/// anymore.
+#[derive(Debug)]
+struct FileNode(String);
+
+impl FileNode {
+	/// Returns the registered file.
+	fn get(&self) -> EResult<DebugFile> {
+		FILES
+			.lock()
+			.get(self.0.as_bytes())
+			.copied()
+			.ok_or_else(|| errno!(ENOENT))
+	}
+}
+
+impl FileOps for FileNode {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let read = self.get()?.read.ok_or_else(|| errno!(EINVAL))?;
+		read(off, buf)
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let write = self.get()?.write.ok_or_else(|| errno!(EINVAL))?;
+		write(buf)
+	}
+}
+
+/// A debugfs.
+#[derive(Debug)]
+pub struct DebugFs;
+
+impl FilesystemOps for DebugFs {
+	fn get_name(&self) -> &[u8] {
+		b"debugfs"
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: DEBUGFS_MAGIC as _,
+			f_bsize: 0,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: 0,
+			f_frsize: 0,
+			f_flags: 0,
+			f_spare: [0; 4],
+		})
+	}
+
+	fn root(&self, fs: Arc<Filesystem>) -> EResult<Arc<Node>> {
+		Ok(new_node(
+			fs,
+			dir_stat(),
+			Box::new(DebugDir(String::new()))?,
+			Box::new(DummyOps)?,
+		)?)
+	}
+
+	fn create_node(&self, _fs: Arc<Filesystem>, _stat: Stat) -> EResult<Arc<Node>> {
+		Err(errno!(EPERM))
+	}
+
+	fn destroy_node(&self, _node: &Node) -> EResult<()> {
+		Err(errno!(EINVAL))
+	}
+}
+
+/// The debugfs filesystem type.
+pub struct DebugFsType;
+
+impl FilesystemType for DebugFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"debugfs"
+	}
+
+	fn detect(&self, _dev: &Arc<BlkDev>) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_dev: Option<Arc<BlkDev>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<Filesystem>> {
+		Ok(Filesystem::new(0, Box::new(DebugFs)?)?)
+	}
+}
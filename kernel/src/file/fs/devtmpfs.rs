@@ -0,0 +1,199 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `devtmpfs` filesystem lists the device files of registered devices.
+//!
+//! The content of the root directory is not stored but is generated from the tables of
+//! registered devices, so device files appear and disappear along with their devices.
+//!
+//! Each device file is named after the file name of the device's path. Devices located in a
+//! subdirectory of `/dev` are not listed.
+
+use super::{DummyOps, Filesystem, FilesystemOps, FilesystemType, NodeOps, Statfs};
+use crate::{
+	device,
+	device::{BlkDev, DeviceType},
+	file::{vfs, vfs::node::Node, DirContext, DirEntry, FileType, Stat},
+	sync::mutex::Mutex,
+};
+use core::sync::atomic::AtomicBool;
+use utils::{
+	boxed::Box,
+	collections::path::{Path, PathBuf},
+	errno,
+	errno::EResult,
+	limits::{NAME_MAX, PAGE_SIZE},
+	ptr::arc::Arc,
+};
+
+/// The magic number of the filesystem, which is the same as tmpfs's.
+const TMPFS_MAGIC: u32 = 0x01021994;
+
+/// Tells whether the device file at `path` is listed at the root of the filesystem.
+fn is_listed(path: &Path) -> bool {
+	path.parent() == Some(Path::new_unbounded(b"/dev"))
+}
+
+/// The root directory of the filesystem.
+#[derive(Debug)]
+struct Root;
+
+impl NodeOps for Root {
+	fn lookup_entry(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
+		let find = |path: &Path| is_listed(path) && path.file_name() == Some(&ent.name);
+		let blk = device::BLK_DEVICES
+			.lock()
+			.iter()
+			.find(|(_, dev)| find(&dev.path))
+			.map(|(id, dev)| (DeviceType::Block, *id, dev.mode));
+		let dev = blk.or_else(|| {
+			device::CHAR_DEVICES
+				.lock()
+				.iter()
+				.find(|(_, dev)| find(&dev.path))
+				.map(|(id, dev)| (DeviceType::Char, *id, dev.mode))
+		});
+		ent.node = dev
+			.map(|(dev_type, id, mode)| {
+				Arc::new(Node {
+					inode: 0,
+					fs: dir.fs.clone(),
+
+					stat: Mutex::new(Stat {
+						mode: dev_type.to_file_type().to_mode() | mode,
+						dev_major: id.major,
+						dev_minor: id.minor,
+						..Default::default()
+					}),
+					dirty: AtomicBool::new(false),
+
+					node_ops: Box::new(DummyOps)?,
+					file_ops: Box::new(DummyOps)?,
+
+					lock: Default::default(),
+					mapped: Default::default(),
+				})
+			})
+			.transpose()?;
+		Ok(())
+	}
+
+	fn iter_entries(&self, _dir: &Node, ctx: &mut DirContext) -> EResult<()> {
+		let blk_devs = device::BLK_DEVICES.lock();
+		let char_devs = device::CHAR_DEVICES.lock();
+		let blk_iter = blk_devs
+			.iter()
+			.map(|(_, dev)| (FileType::BlockDevice, &dev.path));
+		let char_iter = char_devs
+			.iter()
+			.map(|(_, dev)| (FileType::CharDevice, &dev.path));
+		let iter = blk_iter
+			.chain(char_iter)
+			.filter(|(_, path)| is_listed(path))
+			.filter_map(|(file_type, path)| Some((file_type, path.file_name()?)))
+			.skip(ctx.off as usize);
+		for (file_type, name) in iter {
+			let ent = DirEntry {
+				inode: 0,
+				entry_type: Some(file_type),
+				name,
+			};
+			if !(ctx.write)(&ent)? {
+				break;
+			}
+			ctx.off += 1;
+		}
+		Ok(())
+	}
+}
+
+/// A devtmpfs filesystem.
+#[derive(Debug)]
+pub struct DevTmpFs;
+
+impl FilesystemOps for DevTmpFs {
+	fn get_name(&self) -> &[u8] {
+		b"devtmpfs"
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: TMPFS_MAGIC as _,
+			f_bsize: PAGE_SIZE as _,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: NAME_MAX as _,
+			f_frsize: 0,
+			f_flags: 0,
+			f_spare: [0; 4],
+		})
+	}
+
+	fn root(&self, fs: Arc<Filesystem>) -> EResult<Arc<Node>> {
+		Ok(Arc::new(Node {
+			inode: 0,
+			fs,
+
+			stat: Mutex::new(Stat {
+				mode: FileType::Directory.to_mode() | 0o755,
+				..Default::default()
+			}),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(Root)?,
+			file_ops: Box::new(DummyOps)?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		})?)
+	}
+
+	fn create_node(&self, _fs: Arc<Filesystem>, _stat: Stat) -> EResult<Arc<Node>> {
+		Err(errno!(EINVAL))
+	}
+
+	fn destroy_node(&self, _node: &Node) -> EResult<()> {
+		Err(errno!(EINVAL))
+	}
+}
+
+/// The devtmpfs filesystem type.
+pub struct DevTmpFsType;
+
+impl FilesystemType for DevTmpFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"devtmpfs"
+	}
+
+	fn detect(&self, _dev: &Arc<BlkDev>) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_dev: Option<Arc<BlkDev>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<Filesystem>> {
+		Ok(Filesystem::new(0, Box::new(DevTmpFs)?)?)
+	}
+}
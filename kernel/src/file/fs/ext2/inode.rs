@@ -442,6 +442,7 @@ impl Ext2INode {
 			if b == 0 {
 				let new = fs.alloc_block()?;
 				ent.store(new, Relaxed);
+				blk.mark_dirty();
 				b = new;
 			}
 			blk_off = b;
@@ -460,6 +461,7 @@ impl Ext2INode {
 		let free = Self::free_content_blk_impl(b.load(Relaxed), &offsets[1..], fs)?;
 		if free {
 			let b = b.swap(0, Relaxed);
+			blk.mark_dirty();
 			let empty = ents.iter().all(|b| b.load(Relaxed) == 0);
 			fs.free_block(b)?;
 			Ok(empty)
@@ -651,6 +653,7 @@ impl Ext2INode {
 			)?;
 			// Create free entries to cover remaining free space
 			fill_free_entries(&mut buf[(inner_off + rec_len as usize)..], &fs.sp)?;
+			blk.mark_dirty();
 		} else {
 			// No suitable free entry: Fill a new block
 			let blocks = self.get_blocks(&fs.sp);
@@ -663,6 +666,7 @@ impl Ext2INode {
 			Dirent::write_new(buf, &fs.sp, entry_inode, rec_len, Some(file_type), name)?;
 			// Create free entries to cover remaining free space
 			fill_free_entries(&mut buf[rec_len as usize..], &fs.sp)?;
+			blk.mark_dirty();
 			self.set_size(&fs.sp, (blocks as u64 + 1) * blk_size as u64, false);
 		}
 		Ok(())
@@ -692,6 +696,7 @@ impl Ext2INode {
 		let slice = unsafe { blk.slice_mut() };
 		let ent = Dirent::from_slice(&mut slice[inner_off..], &fs.sp)?;
 		ent.inode = inode as _;
+		blk.mark_dirty();
 		// If the block is now empty, free it
 		if inode == 0 && is_block_empty(slice, &fs.sp)? {
 			// If this is the last block, update the file's size
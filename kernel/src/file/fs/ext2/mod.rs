@@ -196,32 +196,36 @@ fn bitmap_alloc_impl(blk: &RcFrame) -> Option<u32> {
 #[derive(Debug)]
 struct Ext2NodeOps;
 
+/// Returns the node with the given `inode` on the filesystem `dir_fs`, loading it if not in
+/// cache.
+fn get_node(fs: &Ext2Fs, dir_fs: &Arc<Filesystem>, inode: u32) -> EResult<Arc<Node>> {
+	fs.node_cache.get_or_insert(inode as _, || {
+		let mut node = Node {
+			inode: inode as _,
+			fs: dir_fs.clone(),
+
+			stat: Default::default(),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(Ext2NodeOps)?,
+			file_ops: Box::new(Ext2FileOps)?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		};
+		let stat = Ext2INode::get(&node, fs)?.stat(&fs.sp);
+		node.stat = Mutex::new(stat);
+		Ok(Arc::new(node)?)
+	})
+}
+
 impl NodeOps for Ext2NodeOps {
 	fn lookup_entry<'n>(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
 		let fs = downcast_fs::<Ext2Fs>(&*dir.fs.ops);
 		let inode_ = Ext2INode::get(dir, fs)?;
 		ent.node = inode_
 			.get_dirent(&ent.name, fs)?
-			.map(|(inode, ..)| -> EResult<_> {
-				fs.node_cache.get_or_insert(inode as _, || {
-					let mut node = Node {
-						inode: inode as _,
-						fs: dir.fs.clone(),
-
-						stat: Default::default(),
-						dirty: AtomicBool::new(false),
-
-						node_ops: Box::new(Ext2NodeOps)?,
-						file_ops: Box::new(Ext2FileOps)?,
-
-						lock: Default::default(),
-						mapped: Default::default(),
-					};
-					let stat = Ext2INode::get(&node, fs)?.stat(&fs.sp);
-					node.stat = Mutex::new(stat);
-					Ok(Arc::new(node)?)
-				})
-			})
+			.map(|(inode, ..)| get_node(fs, &dir.fs, inode))
 			.transpose()?;
 		Ok(())
 	}
@@ -377,6 +381,7 @@ impl NodeOps for Ext2NodeOps {
 			// Copy
 			dst[..buf.len()].copy_from_slice(buf);
 			dst[buf.len()..].fill(0);
+			blk.mark_dirty();
 		}
 		// Update size
 		inode_.set_size(&fs.sp, buf.len() as _, inline);
@@ -390,9 +395,45 @@ impl NodeOps for Ext2NodeOps {
 		if unlikely(fs.readonly) {
 			return Err(errno!(EROFS));
 		}
+		if new_name == b"." || new_name == b".." {
+			return Err(errno!(EINVAL));
+		}
+		// If the destination exists, replace it
+		let new_parent_node = new_parent.node();
+		let target = Ext2INode::get(new_parent_node, fs)?.get_dirent(new_name, fs)?;
+		if let Some((target_inode, target_off)) = target {
+			// Both entries are links to the same file, there is nothing to do
+			if target_inode as INode == entry_node.inode {
+				return Ok(());
+			}
+			let dir = entry_node.get_type() == Some(FileType::Directory);
+			let target_node = get_node(fs, &entry_node.fs, target_inode)?;
+			let mut target = Ext2INode::get(&target_node, fs)?;
+			let target_dir = target.get_type() == FileType::Directory;
+			if dir && !target_dir {
+				return Err(errno!(ENOTDIR));
+			}
+			if !dir && target_dir {
+				return Err(errno!(EISDIR));
+			}
+			if target_dir && !target.is_directory_empty(fs)? {
+				return Err(errno!(ENOTEMPTY));
+			}
+			// Decrement the hard links count. The node is removed when released
+			target.i_links_count = target.i_links_count.saturating_sub(1);
+			target_node.stat.lock().nlink = target.i_links_count;
+			drop(target);
+			// Remove the directory entry
+			let mut new_parent_inode = Ext2INode::get(new_parent_node, fs)?;
+			if target_dir {
+				// Decrement links because of the `..` entry being removed
+				new_parent_inode.i_links_count = new_parent_inode.i_links_count.saturating_sub(1);
+				new_parent_node.stat.lock().nlink = new_parent_inode.i_links_count;
+			}
+			new_parent_inode.set_dirent_inode(target_off, 0, fs)?;
+		}
 		// Create new entry
 		let dir = {
-			let new_parent_node = new_parent.node();
 			let mut new_parent_inode = Ext2INode::get(new_parent_node, fs)?;
 			// Check the entry does not exist
 			if new_parent_inode.get_dirent(new_name, fs)?.is_some() {
@@ -441,14 +482,13 @@ impl NodeOps for Ext2NodeOps {
 				.translate_blk_off(off, fs)?
 				.ok_or_else(|| errno!(EOVERFLOW))?;
 			fs.dev
-				.ops
-				.read_frame(blk_off.get() as _, 0, FrameOwner::Node(node.clone()))
+				.read_frame_direct(blk_off.get() as _, 0, FrameOwner::Node(node.clone()))
 		})
 	}
 
 	fn write_frame(&self, node: &Node, frame: &RcFrame) -> EResult<()> {
 		let fs = downcast_fs::<Ext2Fs>(&*node.fs.ops);
-		fs.dev.ops.write_pages(frame.dev_offset(), frame.slice())
+		fs.dev.write_pages(frame.dev_offset(), frame.slice())
 	}
 
 	fn sync_stat(&self, node: &Node) -> EResult<()> {
@@ -712,6 +752,7 @@ impl Ext2Fs {
 		for blk_off in start_blk..end_blk {
 			let page = read_block(self, blk_off as _)?;
 			if let Some(off) = bitmap_alloc_impl(&page) {
+				page.mark_dirty();
 				let blk_off = blk_off - start_blk;
 				return Ok(Some(blk_off * blk_size * 8 + off));
 			}
@@ -732,6 +773,7 @@ impl Ext2Fs {
 		let byte = &page.slice::<AtomicU8>()[bitmap_byte_index as usize];
 		let bitmap_bit_index = index % 8;
 		let prev = byte.fetch_or(1 << bitmap_bit_index, Release);
+		page.mark_dirty();
 		Ok(prev & (1 << bitmap_bit_index) != 0)
 	}
 
@@ -755,6 +797,8 @@ impl Ext2Fs {
 				if directory {
 					bgd.bg_used_dirs_count.fetch_add(1, Release);
 				}
+				self.sp.mark_dirty();
+				bgd.mark_dirty();
 				return Ok(group * self.sp.s_inodes_per_group + j + 1);
 			}
 		}
@@ -787,6 +831,8 @@ impl Ext2Fs {
 			if directory {
 				bgd.bg_used_dirs_count.fetch_sub(1, Release);
 			}
+			self.sp.mark_dirty();
+			bgd.mark_dirty();
 		}
 		Ok(())
 	}
@@ -811,6 +857,8 @@ impl Ext2Fs {
 			}
 			self.sp.s_free_blocks_count.fetch_sub(1, Release);
 			bgd.bg_free_blocks_count.fetch_sub(1, Release);
+			self.sp.mark_dirty();
+			bgd.mark_dirty();
 			return Ok(blk_index);
 		}
 		Err(errno!(ENOSPC))
@@ -834,6 +882,8 @@ impl Ext2Fs {
 		if prev {
 			self.sp.s_free_blocks_count.fetch_add(1, Release);
 			bgd.bg_free_blocks_count.fetch_add(1, Release);
+			self.sp.mark_dirty();
+			bgd.mark_dirty();
 		}
 		Ok(())
 	}
@@ -847,19 +897,21 @@ impl FilesystemOps for Ext2Fs {
 	}
 
 	fn get_stat(&self) -> EResult<Statfs> {
+		let free_blocks = self.sp.s_free_blocks_count.load(Relaxed);
 		Ok(Statfs {
 			f_type: EXT2_MAGIC as _,
-			f_bsize: self.sp.get_block_size(),
+			f_bsize: self.sp.get_block_size() as _,
 			f_blocks: self.sp.s_blocks_count as _,
-			f_bfree: self.sp.s_free_blocks_count.load(Relaxed) as _,
-			// TODO Subtract blocks for superuser
-			f_bavail: self.sp.s_free_blocks_count.load(Relaxed) as _,
+			f_bfree: free_blocks as _,
+			// Blocks reserved for the superuser are not available to other users
+			f_bavail: free_blocks.saturating_sub(self.sp.s_r_blocks_count) as _,
 			f_files: self.sp.s_inodes_count as _,
 			f_ffree: self.sp.s_free_inodes_count.load(Relaxed) as _,
 			f_fsid: Default::default(),
 			f_namelen: NAME_MAX as _,
-			f_frsize: math::pow2(self.sp.s_log_frag_size + 10),
+			f_frsize: math::pow2::<u32>(self.sp.s_log_frag_size + 10) as _,
 			f_flags: 0, // TODO
+			f_spare: [0; 4],
 		})
 	}
 
@@ -960,13 +1012,24 @@ impl FilesystemOps for Ext2Fs {
 		let mut inode = Ext2INode::get(node, self)?;
 		// Remove the inode
 		inode.i_links_count = 0;
-		let ts = current_time_sec(Clock::Monotonic);
+		let ts = current_time_sec(Clock::Realtime);
 		inode.i_dtime = ts as _;
 		inode.free_content(self)?;
 		// Free inode
 		self.free_inode(node.inode, inode.get_type() == FileType::Directory)?;
 		Ok(())
 	}
+
+	fn sync(&self) -> EResult<()> {
+		if self.readonly {
+			return Ok(());
+		}
+		// Flush nodes first, since writing their status updates the inode tables
+		self.node_cache.sync()?;
+		// The superblock, block group descriptors, bitmaps and inode tables are all located in
+		// the device's cache
+		self.dev.sync()
+	}
 }
 
 /// The ext2 filesystem type.
@@ -1016,7 +1079,7 @@ impl FilesystemType for Ext2FsType {
 				return Err(errno!(EROFS));
 			}
 		}
-		let ts = current_time_sec(Clock::Monotonic);
+		let ts = current_time_sec(Clock::Realtime);
 		if unlikely(sp.s_mnt_count.load(Relaxed) >= sp.s_max_mnt_count) {
 			return Err(errno!(EINVAL));
 		}
@@ -1032,6 +1095,7 @@ impl FilesystemType for Ext2FsType {
 		// Set the last mount timestamp
 		sp.s_mtime.store(ts as _, Relaxed);
 		sp.s_mnt_count.fetch_add(1, Relaxed);
+		sp.mark_dirty();
 		Ok(Filesystem::new(
 			dev.id.get_device_number(),
 			Box::new(Ext2Fs {
@@ -23,7 +23,13 @@ use crate::{
 	device, file,
 	file::{perm::AccessProfile, vfs, vfs::ResolutionSettings, File, FileType, Stat, O_WRONLY},
 };
-use utils::{collections::path::Path, cpio::CPIOParser, errno, errno::EResult, ptr::arc::Arc};
+use utils::{
+	collections::path::{Component, Path},
+	cpio::CPIOParser,
+	errno,
+	errno::EResult,
+	ptr::arc::Arc,
+};
 
 /// Updates the current parent used for the unpacking operation.
 ///
@@ -61,12 +67,28 @@ fn update_parent<'p>(
 	}
 }
 
+/// Tells whether the initramfs image `data` contains an init program at its root.
+///
+/// If so, this program is in charge of mounting the root filesystem given on the command line.
+pub fn has_init(data: &[u8]) -> bool {
+	CPIOParser::new(data).any(|entry| {
+		let Ok(path) = Path::new(entry.get_filename()) else {
+			return false;
+		};
+		let mut names = path.components().filter_map(|c| match c {
+			Component::Normal(name) => Some(name),
+			_ => None,
+		});
+		names.next() == Some(b"init".as_slice()) && names.next().is_none()
+	})
+}
+
 /// Loads the initramsfs at the root of the VFS.
 ///
 /// `data` is the slice of data representing the initramfs image.
 pub fn load(data: &[u8]) -> EResult<()> {
 	// The stored parent directory
-	let mut cur_parent: (&Path, Arc<vfs::Entry>) = (Path::root(), vfs::ROOT.clone());
+	let mut cur_parent: (&Path, Arc<vfs::Entry>) = (Path::root(), vfs::root());
 	let cpio_parser = CPIOParser::new(data);
 	for entry in cpio_parser {
 		let hdr = entry.get_hdr();
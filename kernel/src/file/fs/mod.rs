@@ -19,10 +19,15 @@
 //! A filesystem is the representation of the file hierarchy on a storage
 //! device.
 
+pub mod cgroup;
+pub mod debugfs;
+pub mod devtmpfs;
 pub mod ext2;
 pub mod initramfs;
 pub mod kernfs;
+pub mod mqueue;
 pub mod proc;
+pub mod sys;
 pub mod tmp;
 
 use super::{
@@ -30,13 +35,18 @@ use super::{
 	vfs, DirContext, File, INode, Mode, Stat,
 };
 use crate::{
-	device::BlkDev, file::vfs::node::Node, memory::cache::RcFrame, sync::mutex::Mutex,
-	syscall::ioctl, time::unit::Timestamp,
+	device::BlkDev,
+	file::vfs::node::Node,
+	memory::{cache::RcFrame, PhysAddr},
+	println,
+	sync::mutex::Mutex,
+	syscall::ioctl,
+	time::unit::Timestamp,
 };
 use core::{
 	any::Any,
 	cmp::min,
-	ffi::{c_int, c_void},
+	ffi::{c_int, c_long, c_void},
 	fmt::Debug,
 	intrinsics::unlikely,
 };
@@ -54,8 +64,8 @@ use utils::{
 ///
 /// It is currently unused.
 #[repr(C)]
-#[derive(Debug, Default)]
-struct Fsid {
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Fsid {
 	/// Unused.
 	_val: [c_int; 2],
 }
@@ -65,27 +75,29 @@ struct Fsid {
 #[derive(Debug)]
 pub struct Statfs {
 	/// Type of filesystem.
-	f_type: u32,
+	pub f_type: c_long,
 	/// Optimal transfer block size.
-	f_bsize: u32,
+	pub f_bsize: c_long,
 	/// Total data blocks in filesystem.
-	f_blocks: i64,
+	pub f_blocks: u64,
 	/// Free blocks in filesystem.
-	f_bfree: i64,
+	pub f_bfree: u64,
 	/// Free blocks available to unprivileged user.
-	f_bavail: i64,
+	pub f_bavail: u64,
 	/// Total inodes in filesystem.
-	f_files: i64,
+	pub f_files: u64,
 	/// Free inodes in filesystem.
-	f_ffree: i64,
+	pub f_ffree: u64,
 	/// Filesystem ID.
-	f_fsid: Fsid,
+	pub f_fsid: Fsid,
 	/// Maximum length of filenames.
-	f_namelen: u32,
+	pub f_namelen: c_long,
 	/// Fragment size.
-	f_frsize: u32,
+	pub f_frsize: c_long,
 	/// Mount flags of filesystem.
-	f_flags: u32,
+	pub f_flags: c_long,
+	/// Padding.
+	pub f_spare: [c_long; 4],
 }
 
 /// A set of attributes to modify on a file's status.
@@ -130,6 +142,17 @@ pub trait NodeOps: Any + Debug {
 		Err(errno!(ENOTDIR))
 	}
 
+	/// Tells whether the entry `ent`, cached in the directory `dir`, is still valid.
+	///
+	/// This is used by filesystems whose content changes without going through the VFS. If the
+	/// function returns `false`, the entry is removed from the cache and looked up again.
+	///
+	/// The default implementation of this function returns `true`.
+	fn revalidate_entry(&self, dir: &Node, ent: &vfs::Entry) -> bool {
+		let _ = (dir, ent);
+		true
+	}
+
 	/// Adds a hard link into the directory.
 	///
 	/// Arguments:
@@ -198,6 +221,18 @@ pub trait NodeOps: Any + Debug {
 		Err(errno!(EINVAL))
 	}
 
+	/// If the node is a *magic link*, returns the node it points to.
+	///
+	/// Resolving a magic link does not go through the path in its content, which is then only
+	/// informative.
+	///
+	/// The default implementation of this function returns `None`, meaning the link is resolved
+	/// through its path.
+	fn follow_link(&self, node: &Node) -> EResult<Option<Arc<Node>>> {
+		let _ = node;
+		Ok(None)
+	}
+
 	/// Renames or moves a file on the filesystem.
 	///
 	/// If this feature is not supported by the filesystem, the function returns
@@ -257,6 +292,14 @@ pub trait FileOps: Any + Debug {
 		Ok(stat)
 	}
 
+	/// Returns the operations to use for a newly opened device file.
+	///
+	/// By default, the function returns `None`, meaning all open files share the device's
+	/// operations. Devices keeping a state for each open file return their own instead.
+	fn open(&self) -> EResult<Option<Arc<dyn FileOps>>> {
+		Ok(None)
+	}
+
 	/// Increments the reference counter.
 	fn acquire(&self, file: &File) {
 		let _ = file;
@@ -322,6 +365,17 @@ pub trait FileOps: Any + Debug {
 		Err(errno!(EINVAL))
 	}
 
+	/// Returns the physical address of the page at the page offset `off` in `file`, to be mapped
+	/// directly in memory.
+	///
+	/// This is used by devices giving access to physical memory.
+	///
+	/// The default implementation of this function returns an error.
+	fn phys_page(&self, file: &File, off: u64) -> EResult<PhysAddr> {
+		let _ = (file, off);
+		Err(errno!(ENODEV))
+	}
+
 	/// Changes the size of the file, truncating its content if necessary.
 	///
 	/// If `size` is greater than or equals to the current size of the file, the function does
@@ -390,6 +444,7 @@ pub fn generic_file_write(file: &File, mut off: u64, buf: &[u8]) -> EResult<usiz
 		let slice = unsafe { page.slice_mut() };
 		// FIXME: this is not concurrency friendly
 		let len = slice_copy(&buf[buf_off..], &mut slice[inner_off..]);
+		page.mark_dirty();
 		buf_off += len;
 		off += len as u64;
 	}
@@ -429,6 +484,13 @@ pub trait FilesystemOps: Any + Debug {
 	///
 	/// This function should be called only when no link to the node remain.
 	fn destroy_node(&self, node: &Node) -> EResult<()>;
+
+	/// Writes all cached data and metadata of the filesystem back to the storage device.
+	///
+	/// The default implementation of this function is a no-op.
+	fn sync(&self) -> EResult<()> {
+		Ok(())
+	}
 }
 
 /// Downcasts the given `fs` into `F`.
@@ -480,11 +542,18 @@ impl Filesystem {
 		buffers.insert(inode, buf.clone())?;
 		Ok(buf)
 	}
+
+	/// Synchronizes the filesystem to its storage device.
+	pub fn sync(&self) -> EResult<()> {
+		self.ops.sync()
+	}
 }
 
 impl Drop for Filesystem {
 	fn drop(&mut self) {
-		// TODO sync filesystem
+		if let Err(errno) = self.sync() {
+			println!("Filesystem synchronization failure: {errno}");
+		}
 	}
 }
 
@@ -552,6 +621,10 @@ pub fn register_defaults() -> EResult<()> {
 	register(ext2::Ext2FsType)?;
 	register(tmp::TmpFsType)?;
 	register(proc::ProcFsType)?;
-	// TODO sysfs
+	register(sys::SysFsType)?;
+	register(mqueue::MqueueFsType)?;
+	register(cgroup::CgroupFsType)?;
+	register(debugfs::DebugFsType)?;
+	register(devtmpfs::DevTmpFsType)?;
 	Ok(())
 }
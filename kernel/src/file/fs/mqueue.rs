@@ -0,0 +1,174 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mqueue` filesystem lists the POSIX message queues.
+//!
+//! Each queue is represented by a file named after the queue. Reading the file gives the status
+//! of the queue.
+
+use super::{DummyOps, FileOps, Filesystem, FilesystemOps, FilesystemType, NodeOps, Statfs};
+use crate::{
+	device::BlkDev,
+	file::{vfs, vfs::node::Node, DirContext, DirEntry, File, FileType, Stat},
+	ipc::{mqueue, mqueue::MsgQueue, IpcObject},
+	sync::mutex::Mutex,
+};
+use core::sync::atomic::AtomicBool;
+use utils::{boxed::Box, collections::path::PathBuf, errno, errno::EResult, ptr::arc::Arc};
+
+/// The magic number of the filesystem.
+const MQUEUE_MAGIC: u32 = 0x19800202;
+
+/// A file giving the status of a queue.
+#[derive(Debug)]
+struct Status(Arc<MsgQueue>);
+
+impl FileOps for Status {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		mqueue::read_status(&self.0, off, buf)
+	}
+}
+
+/// The root directory of the filesystem.
+#[derive(Debug)]
+struct Root;
+
+impl NodeOps for Root {
+	fn lookup_entry(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
+		let queue = mqueue::QUEUES.lock().get(ent.name.as_bytes()).cloned();
+		ent.node = queue
+			.map(|queue| {
+				let stat = {
+					let perm = queue.perm().lock();
+					Stat {
+						mode: FileType::Regular.to_mode() | (perm.mode & 0o777),
+						uid: perm.uid,
+						gid: perm.gid,
+						..Default::default()
+					}
+				};
+				Arc::new(Node {
+					inode: 0,
+					fs: dir.fs.clone(),
+
+					stat: Mutex::new(stat),
+					dirty: AtomicBool::new(false),
+
+					node_ops: Box::new(DummyOps)?,
+					file_ops: Box::new(Status(queue))?,
+
+					lock: Default::default(),
+					mapped: Default::default(),
+				})
+			})
+			.transpose()?;
+		Ok(())
+	}
+
+	fn iter_entries(&self, _dir: &Node, ctx: &mut DirContext) -> EResult<()> {
+		let queues = mqueue::QUEUES.lock();
+		for (name, _) in queues.iter().skip(ctx.off as usize) {
+			let ent = DirEntry {
+				inode: 0,
+				entry_type: Some(FileType::Regular),
+				name,
+			};
+			if !(ctx.write)(&ent)? {
+				break;
+			}
+			ctx.off += 1;
+		}
+		Ok(())
+	}
+}
+
+/// A mqueue filesystem.
+#[derive(Debug)]
+pub struct MqueueFs;
+
+impl FilesystemOps for MqueueFs {
+	fn get_name(&self) -> &[u8] {
+		b"mqueue"
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: MQUEUE_MAGIC as _,
+			f_bsize: 0,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: 0,
+			f_frsize: 0,
+			f_flags: 0,
+			f_spare: [0; 4],
+		})
+	}
+
+	fn root(&self, fs: Arc<Filesystem>) -> EResult<Arc<Node>> {
+		Ok(Arc::new(Node {
+			inode: 0,
+			fs,
+
+			stat: Mutex::new(Stat {
+				mode: FileType::Directory.to_mode() | 0o1777,
+				..Default::default()
+			}),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(Root)?,
+			file_ops: Box::new(DummyOps)?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		})?)
+	}
+
+	fn create_node(&self, _fs: Arc<Filesystem>, _stat: Stat) -> EResult<Arc<Node>> {
+		Err(errno!(EINVAL))
+	}
+
+	fn destroy_node(&self, _node: &Node) -> EResult<()> {
+		Err(errno!(EINVAL))
+	}
+}
+
+/// The mqueue filesystem type.
+pub struct MqueueFsType;
+
+impl FilesystemType for MqueueFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"mqueue"
+	}
+
+	fn detect(&self, _dev: &Arc<BlkDev>) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_dev: Option<Arc<BlkDev>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<Filesystem>> {
+		Ok(Filesystem::new(0, Box::new(MqueueFs)?)?)
+	}
+}
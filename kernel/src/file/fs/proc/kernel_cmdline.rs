@@ -0,0 +1,37 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `cmdline` file returns the command line the kernel was booted with.
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content,
+	multiboot::BOOT_INFO,
+};
+use utils::{errno::EResult, DisplayableStr};
+
+/// The kernel command line file.
+#[derive(Debug, Default)]
+pub struct KernelCmdline;
+
+impl FileOps for KernelCmdline {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let cmdline = BOOT_INFO.cmdline.unwrap_or_default();
+		format_content!(off, buf, "{}\n", DisplayableStr(cmdline))
+	}
+}
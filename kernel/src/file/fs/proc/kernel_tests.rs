@@ -0,0 +1,51 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `kernel_tests` file runs the kernel's integration tests when read and reports their
+//! results.
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content, selftest,
+	sync::mutex::Mutex,
+};
+use utils::{
+	collections::string::{String, StringWriter},
+	errno::EResult,
+};
+
+/// The report of the last run, kept so that reading it in several chunks does not run the tests
+/// again.
+static REPORT: Mutex<String> = Mutex::new(String::new());
+
+/// The `kernel_tests` file.
+#[derive(Debug, Default)]
+pub struct KernelTests;
+
+impl FileOps for KernelTests {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let mut report = REPORT.lock();
+		// Reading from the beginning starts a new run
+		if off == 0 {
+			let mut out = StringWriter(Ok(String::new()));
+			selftest::run_integration(selftest::INTEGRATION_TESTS, &mut out);
+			*report = out.0?;
+		}
+		format_content!(off, buf, "{}", *report)
+	}
+}
@@ -31,7 +31,9 @@ pub struct MemInfo;
 
 impl FileOps for MemInfo {
 	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let mem_total = memory::stats::MEM_INFO.lock().mem_total;
+		let commit_limit = memory::overcommit::commit_limit(mem_total);
 		let mem_info = memory::stats::MEM_INFO.lock();
-		format_content!(off, buf, "{}", *mem_info)
+		format_content!(off, buf, "{}CommitLimit: {commit_limit} kB\n", *mem_info)
 	}
 }
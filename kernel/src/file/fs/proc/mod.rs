@@ -19,6 +19,8 @@
 //! The `procfs` is a virtual filesystem which provides information about
 //! processes.
 
+mod kernel_cmdline;
+mod kernel_tests;
 mod mem_info;
 mod proc_dir;
 mod self_link;
@@ -42,16 +44,27 @@ use crate::{
 		vfs::node::Node,
 		DirContext, DirEntry, FileType, Mode, Stat,
 	},
-	process::{pid::Pid, scheduler::SCHEDULER, Process},
+	process::{ns::NsKind, pid::Pid, scheduler::SCHEDULER, Process},
 	sync::mutex::Mutex,
 };
 use core::sync::atomic::AtomicBool;
+use kernel_cmdline::KernelCmdline;
+use kernel_tests::KernelTests;
 use mem_info::MemInfo;
 use proc_dir::{
-	cmdline::Cmdline, cwd::Cwd, exe::Exe, mounts::Mounts, stat::StatNode, status::Status,
+	cmdline::Cmdline,
+	cwd::Cwd,
+	exe::Exe,
+	id_map::{GidMap, UidMap},
+	maps::Maps,
+	mounts::Mounts,
+	ns::NsLink,
+	oom::{OomScore, OomScoreAdj},
+	stat::StatNode,
+	status::Status,
 };
 use self_link::SelfNode;
-use sys_dir::OsRelease;
+use sys_dir::{DentryCache, DevmemLockdown, OsRelease, OvercommitMemory};
 use uptime::Uptime;
 use utils::{
 	boxed::Box, collections::path::PathBuf, errno, errno::EResult, format, ptr::arc::Arc,
@@ -81,17 +94,39 @@ fn proc_file_stat(pid: Pid, mode: Mode) -> Stat {
 	}
 }
 
+/// Returns the status of a link in a process's `ns` directory.
+fn ns_link_stat(pid: Pid) -> Stat {
+	proc_file_stat(pid, FileType::Link.to_mode() | 0o777)
+}
+
 /// The root directory of the proc.
 #[derive(Clone, Debug)]
 struct RootDir;
 
 impl RootDir {
-	// Entries offsets: The first `Pid::MAX` offsets are reserved for processes. Static entries are
-	// located right after
+	// Entries offsets: Static entries come first. Then, the offset of a process's entry is
+	// `STATIC.entries.len() + pid`, so that listing can resume correctly even if processes
+	// exited in between
 	/// Static entries of the root directory, as opposed to the dynamic ones that represent
 	/// processes.
 	const STATIC: StaticDir = StaticDir {
 		entries: &[
+			StaticEntry {
+				name: b"cmdline",
+				stat: |_| Stat {
+					mode: FileType::Regular.to_mode() | 0o444,
+					..Default::default()
+				},
+				init: EitherOps::File(|_| box_file(KernelCmdline)),
+			},
+			StaticEntry {
+				name: b"kernel_tests",
+				stat: |_| Stat {
+					mode: FileType::Regular.to_mode() | 0o400,
+					..Default::default()
+				},
+				init: EitherOps::File(|_| box_file(KernelTests)),
+			},
 			StaticEntry {
 				name: b"meminfo",
 				stat: |_| Stat {
@@ -121,20 +156,68 @@ impl RootDir {
 				stat: |_| static_dir_stat(),
 				init: EitherOps::Node(|_| {
 					box_node(StaticDir {
-						entries: &[(StaticEntry {
-							name: b"kernel",
-							stat: |_| static_dir_stat(),
-							init: EitherOps::Node(|_| {
-								box_node(StaticDir {
-									entries: &[StaticEntry {
-										name: b"osrelease",
-										stat: |_| static_dir_stat(),
-										init: EitherOps::File(|_| box_file(OsRelease)),
-									}],
-									data: (),
-								})
-							}),
-						})],
+						entries: &[
+							StaticEntry {
+								name: b"fs",
+								stat: |_| static_dir_stat(),
+								init: EitherOps::Node(|_| {
+									box_node(StaticDir {
+										entries: &[StaticEntry {
+											name: b"dentry-cache",
+											stat: |_| Stat {
+												mode: FileType::Regular.to_mode() | 0o444,
+												..Default::default()
+											},
+											init: EitherOps::File(|_| box_file(DentryCache)),
+										}],
+										data: (),
+									})
+								}),
+							},
+							StaticEntry {
+								name: b"kernel",
+								stat: |_| static_dir_stat(),
+								init: EitherOps::Node(|_| {
+									box_node(StaticDir {
+										entries: &[
+											StaticEntry {
+												name: b"devmem_lockdown",
+												stat: |_| Stat {
+													mode: FileType::Regular.to_mode() | 0o644,
+													..Default::default()
+												},
+												init: EitherOps::File(|_| {
+													box_file(DevmemLockdown)
+												}),
+											},
+											StaticEntry {
+												name: b"osrelease",
+												stat: |_| static_dir_stat(),
+												init: EitherOps::File(|_| box_file(OsRelease)),
+											},
+										],
+										data: (),
+									})
+								}),
+							},
+							StaticEntry {
+								name: b"vm",
+								stat: |_| static_dir_stat(),
+								init: EitherOps::Node(|_| {
+									box_node(StaticDir {
+										entries: &[StaticEntry {
+											name: b"overcommit_memory",
+											stat: |_| Stat {
+												mode: FileType::Regular.to_mode() | 0o644,
+												..Default::default()
+											},
+											init: EitherOps::File(|_| box_file(OvercommitMemory)),
+										}],
+										data: (),
+									})
+								}),
+							},
+						],
 						data: (),
 					})
 				}),
@@ -167,14 +250,17 @@ impl RootDir {
 			..Default::default()
 		}
 	}
+
+	/// Parses the name of a process's entry. If the name is not a PID, the function returns
+	/// `None`.
+	fn parse_pid(name: &[u8]) -> Option<Pid> {
+		core::str::from_utf8(name).ok()?.parse().ok()
+	}
 }
 
 impl NodeOps for RootDir {
 	fn lookup_entry<'n>(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
-		let pid = core::str::from_utf8(&ent.name)
-			.ok()
-			.and_then(|s| s.parse().ok());
-		let Some(pid) = pid else {
+		let Some(pid) = Self::parse_pid(&ent.name) else {
 			return Self::STATIC.lookup_entry(dir, ent);
 		};
 		ent.node = Process::get_by_pid(pid)
@@ -212,6 +298,30 @@ impl NodeOps for RootDir {
 								stat: |pid| proc_file_stat(pid, FileType::Link.to_mode() | 0o444),
 								init: EitherOps::Node(|pid| box_node(Exe(pid))),
 							},
+							#[cfg(feature = "fault_inject")]
+							StaticEntry {
+								name: b"fail-nth",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o644)
+								},
+								init: EitherOps::File(|pid| {
+									box_file(proc_dir::fail_nth::FailNth(pid))
+								}),
+							},
+							StaticEntry {
+								name: b"gid_map",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o644)
+								},
+								init: EitherOps::File(|pid| box_file(GidMap(pid))),
+							},
+							StaticEntry {
+								name: b"maps",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o444)
+								},
+								init: EitherOps::File(|pid| box_file(Maps(pid))),
+							},
 							StaticEntry {
 								name: b"mounts",
 								stat: |pid| {
@@ -219,6 +329,61 @@ impl NodeOps for RootDir {
 								},
 								init: EitherOps::File(|pid| box_file(Mounts(pid))),
 							},
+							StaticEntry {
+								name: b"ns",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Directory.to_mode() | 0o511)
+								},
+								init: EitherOps::Node(|pid| {
+									box_node(StaticDir {
+										entries: &[
+											StaticEntry {
+												name: b"mnt",
+												stat: ns_link_stat,
+												init: EitherOps::Node(|pid| {
+													box_node(NsLink(pid, NsKind::Mount))
+												}),
+											},
+											StaticEntry {
+												name: b"pid",
+												stat: ns_link_stat,
+												init: EitherOps::Node(|pid| {
+													box_node(NsLink(pid, NsKind::Pid))
+												}),
+											},
+											StaticEntry {
+												name: b"user",
+												stat: ns_link_stat,
+												init: EitherOps::Node(|pid| {
+													box_node(NsLink(pid, NsKind::User))
+												}),
+											},
+											StaticEntry {
+												name: b"uts",
+												stat: ns_link_stat,
+												init: EitherOps::Node(|pid| {
+													box_node(NsLink(pid, NsKind::Uts))
+												}),
+											},
+										],
+										data: pid,
+									})
+								}),
+							},
+							StaticEntry {
+								name: b"oom_score",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o444)
+								},
+								init: EitherOps::File(|pid| box_file(OomScore(pid))),
+							},
+							StaticEntry {
+								name: b"oom_score_adj",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o644)
+								},
+								init: EitherOps::File(|pid| box_file(OomScoreAdj(pid))),
+							},
 							StaticEntry {
 								name: b"stat",
 								stat: |pid| {
@@ -233,6 +398,23 @@ impl NodeOps for RootDir {
 								},
 								init: EitherOps::File(|pid| box_file(Status(pid))),
 							},
+							#[cfg(feature = "strace")]
+							StaticEntry {
+								name: b"strace",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o644)
+								},
+								init: EitherOps::File(|pid| {
+									box_file(proc_dir::strace::Strace(pid))
+								}),
+							},
+							StaticEntry {
+								name: b"uid_map",
+								stat: |pid| {
+									proc_file_stat(pid, FileType::Regular.to_mode() | 0o644)
+								},
+								init: EitherOps::File(|pid| box_file(UidMap(pid))),
+							},
 						],
 						data: pid,
 					})?,
@@ -262,10 +444,15 @@ impl NodeOps for RootDir {
 			}
 			ctx.off += 1;
 		}
-		// Iterate on processes
-		let off = ctx.off as usize - Self::STATIC.entries.len();
+		// Iterate on processes. Threads are not listed, but can still be looked up by TID
+		let start = ctx.off as usize - Self::STATIC.entries.len();
+		let Ok(start) = Pid::try_from(start) else {
+			return Ok(());
+		};
 		let sched = SCHEDULER.lock();
-		let proc_iter = sched.iter_process().skip(off);
+		let proc_iter = sched
+			.iter_process_from(start)
+			.filter(|(_, proc)| !proc.is_thread());
 		for (pid, _) in proc_iter {
 			let name = format!("{pid}")?;
 			let ent = DirEntry {
@@ -276,10 +463,19 @@ impl NodeOps for RootDir {
 			if !(ctx.write)(&ent)? {
 				return Ok(());
 			}
-			ctx.off += 1;
+			ctx.off = (Self::STATIC.entries.len() + *pid as usize + 1) as u64;
 		}
 		Ok(())
 	}
+
+	fn revalidate_entry(&self, _dir: &Node, ent: &vfs::Entry) -> bool {
+		// A process's entry is stale if the process exited, or if a process has been created
+		// after a failed lookup
+		let Some(pid) = Self::parse_pid(&ent.name) else {
+			return true;
+		};
+		Process::get_by_pid(pid).is_some() != ent.is_negative()
+	}
 }
 
 /// A proc.
@@ -304,6 +500,7 @@ impl FilesystemOps for ProcFS {
 			f_namelen: 0,
 			f_frsize: 0,
 			f_flags: 0,
+			f_spare: [0; 4],
 		})
 	}
 
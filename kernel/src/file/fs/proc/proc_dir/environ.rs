@@ -20,7 +20,10 @@
 
 use crate::{
 	file::{
-		fs::{proc::proc_dir::read_memory, FileOps},
+		fs::{
+			proc::proc_dir::{may_inspect, read_memory},
+			FileOps,
+		},
 		File,
 	},
 	format_content,
@@ -36,6 +39,9 @@ pub struct Environ(pub Pid);
 impl FileOps for Environ {
 	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
 		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		if !may_inspect(&proc) {
+			return Err(errno!(EACCES));
+		}
 		let mem_space = proc.mem_space.as_ref().unwrap().lock();
 		let disp = fmt::from_fn(|f| {
 			read_memory(
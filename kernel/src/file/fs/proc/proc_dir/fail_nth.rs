@@ -0,0 +1,54 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `fail-nth` file, which allows to inject a fault in an operation
+//! performed on behalf of the process (see [`crate::fault`]).
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content,
+	process::{pid::Pid, Process},
+};
+use core::{str, sync::atomic::Ordering::Relaxed};
+use utils::{errno, errno::EResult};
+
+/// The `fail-nth` node of the proc.
+#[derive(Debug)]
+pub struct FailNth(pub Pid);
+
+impl FileOps for FailNth {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let n = proc.fail_nth.load(Relaxed);
+		format_content!(off, buf, "{n}\n")
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let n: u32 = str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse().ok())
+			.ok_or_else(|| errno!(EINVAL))?;
+		// A process can only inject faults in itself
+		let proc = Process::current();
+		if proc.get_pid() != self.0 {
+			return Err(errno!(EPERM));
+		}
+		proc.fail_nth.store(n, Relaxed);
+		Ok(buf.len())
+	}
+}
@@ -0,0 +1,102 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `uid_map` and `gid_map` files, which allow to get and set the mappings
+//! of the user namespace of the process.
+
+use crate::{
+	file::{
+		fs::FileOps,
+		perm::{AccessProfile, IdMap, UserNamespace},
+		File,
+	},
+	format_content,
+	process::{pid::Pid, Process},
+};
+use utils::{errno, errno::EResult, ptr::arc::Arc};
+
+/// Returns the user namespace of the process with PID `pid`.
+fn get_user_ns(pid: Pid) -> EResult<Arc<UserNamespace>> {
+	let proc = Process::get_by_pid(pid).ok_or_else(|| errno!(ENOENT))?;
+	let ns = proc.fs.lock().user_ns.clone();
+	Ok(ns)
+}
+
+/// Writes the mapping `map` of the namespace `ns` to `buf`, starting at offset `off`.
+///
+/// The initial namespace maps every ID to itself.
+fn read_map(ns: &UserNamespace, map: Option<IdMap>, off: u64, buf: &mut [u8]) -> EResult<usize> {
+	match (ns.parent(), map) {
+		(None, _) => format_content!(off, buf, "{:>10} {:>10} {:>10}\n", 0, 0, u32::MAX),
+		(Some(_), Some(map)) => format_content!(off, buf, "{map}"),
+		(Some(_), None) => Ok(0),
+	}
+}
+
+/// Parses the mapping in `buf` and sets it with `set`, on behalf of the current process.
+fn write_map(
+	buf: &[u8],
+	set: impl FnOnce(IdMap, &UserNamespace, &AccessProfile) -> EResult<()>,
+) -> EResult<usize> {
+	let map = IdMap::parse(buf)?;
+	let (writer, ap) = {
+		let proc = Process::current();
+		let fs = proc.fs.lock();
+		(fs.user_ns.clone(), fs.access_profile)
+	};
+	set(map, &writer, &ap)?;
+	Ok(buf.len())
+}
+
+/// The `uid_map` node of the proc.
+#[derive(Debug)]
+pub struct UidMap(pub Pid);
+
+impl FileOps for UidMap {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let ns = get_user_ns(self.0)?;
+		read_map(&ns, ns.uid_map(), off, buf)
+	}
+
+	fn write(&self, _file: &File, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		let ns = get_user_ns(self.0)?;
+		write_map(buf, |map, writer, ap| ns.set_uid_map(map, writer, ap))
+	}
+}
+
+/// The `gid_map` node of the proc.
+#[derive(Debug)]
+pub struct GidMap(pub Pid);
+
+impl FileOps for GidMap {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let ns = get_user_ns(self.0)?;
+		read_map(&ns, ns.gid_map(), off, buf)
+	}
+
+	fn write(&self, _file: &File, off: u64, buf: &[u8]) -> EResult<usize> {
+		if off != 0 {
+			return Err(errno!(EINVAL));
+		}
+		let ns = get_user_ns(self.0)?;
+		write_map(buf, |map, writer, ap| ns.set_gid_map(map, writer, ap))
+	}
+}
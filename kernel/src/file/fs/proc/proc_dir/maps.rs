@@ -0,0 +1,187 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `maps` node, which lists the memory mappings of a process.
+
+use crate::{
+	device::id::{major, minor},
+	file::{
+		fs::{proc::proc_dir::may_inspect, FileOps},
+		vfs, File,
+	},
+	format_content,
+	memory::VirtAddr,
+	process::{
+		mem_space::{mapping::MemMapping, MemSpace, MAP_SHARED, PROT_EXEC, PROT_READ, PROT_WRITE},
+		pid::Pid,
+		Process,
+	},
+};
+use core::{
+	fmt,
+	fmt::{Formatter, Write},
+};
+use utils::{collections::vec::Vec, errno, errno::EResult, limits::PAGE_SIZE, ptr::arc::Arc};
+
+/// The column at which the pathname of a mapping begins.
+const PATHNAME_COLUMN: usize = 25 + size_of::<usize>() * 6 - 1;
+
+/// Writer counting the number of bytes written on a line.
+struct LineWriter<'f, 'a> {
+	f: &'f mut Formatter<'a>,
+	len: usize,
+}
+
+impl Write for LineWriter<'_, '_> {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		self.len += s.len();
+		self.f.write_str(s)
+	}
+}
+
+/// The name of a mapping.
+#[derive(Debug)]
+enum MapName {
+	/// Anonymous mapping.
+	None,
+	/// Special mapping, such as the stack.
+	Special(&'static str),
+	/// Mapping of the file with the given entry.
+	File(Arc<vfs::Entry>),
+}
+
+/// A line of the `maps` file, taken from a mapping.
+///
+/// Lines are collected before being formatted so that the memory space is not locked while
+/// resolving paths.
+#[derive(Debug)]
+struct MapLine {
+	/// The beginning address of the mapping.
+	begin: usize,
+	/// The end address of the mapping.
+	end: usize,
+	/// Memory protection.
+	prot: u8,
+	/// Mapping flags.
+	flags: i32,
+	/// The offset in the mapped file.
+	off: u64,
+	/// The name of the mapping.
+	name: MapName,
+}
+
+impl MapLine {
+	/// Creates the line describing `mapping` from `mem_space`.
+	fn new(mem_space: &MemSpace, mapping: &MemMapping) -> Self {
+		let begin = mapping.get_addr() as usize;
+		let end = begin + mapping.get_size().get() * PAGE_SIZE;
+		let entry = mapping.get_file().and_then(|file| file.vfs_entry.clone());
+		let vdso = mem_space.exe_info.vdso_begin;
+		let name = if let Some(entry) = entry {
+			MapName::File(entry)
+		} else if VirtAddr(end) == vdso {
+			MapName::Special("[vvar]")
+		} else if VirtAddr(begin) == vdso {
+			MapName::Special("[vdso]")
+		} else if (mem_space.get_brk_init().0..mem_space.get_brk().0).contains(&begin) {
+			MapName::Special("[heap]")
+		} else if (begin..end).contains(&mem_space.exe_info.argv_begin.0) {
+			MapName::Special("[stack]")
+		} else {
+			MapName::None
+		};
+		Self {
+			begin,
+			end,
+			prot: mapping.get_prot(),
+			flags: mapping.get_flags(),
+			off: mapping.get_offset(),
+			name,
+		}
+	}
+}
+
+impl fmt::Display for MapLine {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let flag = |mask: u8, c: char| if self.prot & mask != 0 { c } else { '-' };
+		let shared = if self.flags & MAP_SHARED != 0 {
+			's'
+		} else {
+			'p'
+		};
+		let (dev, inode, off) = match &self.name {
+			MapName::File(entry) => {
+				let node = entry.node();
+				(node.fs.dev, node.inode, self.off)
+			}
+			_ => (0, 0, 0),
+		};
+		let mut line = LineWriter {
+			f,
+			len: 0,
+		};
+		write!(
+			line,
+			"{begin:08x}-{end:08x} {r}{w}{x}{shared} {off:08x} {major:02x}:{minor:02x} {inode} ",
+			begin = self.begin,
+			end = self.end,
+			r = flag(PROT_READ, 'r'),
+			w = flag(PROT_WRITE, 'w'),
+			x = flag(PROT_EXEC, 'x'),
+			major = major(dev),
+			minor = minor(dev),
+		)?;
+		let pad = PATHNAME_COLUMN.saturating_sub(line.len) + 1;
+		match &self.name {
+			MapName::None => Ok(()),
+			MapName::Special(name) => write!(f, "{:pad$}{name}", ""),
+			MapName::File(entry) => {
+				let path = vfs::Entry::get_path(entry).map_err(|_| fmt::Error)?;
+				write!(f, "{:pad$}{path}", "")
+			}
+		}
+	}
+}
+
+/// The `maps` node.
+#[derive(Debug)]
+pub struct Maps(pub Pid);
+
+impl FileOps for Maps {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let Some(proc) = Process::get_by_pid(self.0) else {
+			return Ok(0);
+		};
+		if !may_inspect(&proc) {
+			return Err(errno!(EACCES));
+		}
+		let Some(mem_space) = proc.mem_space.as_ref() else {
+			return Ok(0);
+		};
+		let lines = {
+			let mem_space = mem_space.lock();
+			let mut lines = Vec::new();
+			for mapping in mem_space.iter_mappings() {
+				lines.push(MapLine::new(&mem_space, mapping))?;
+			}
+			lines
+		};
+		let disp = fmt::from_fn(|f| lines.iter().try_for_each(|line| writeln!(f, "{line}")));
+		format_content!(off, buf, "{disp}")
+	}
+}
@@ -20,19 +20,53 @@
 
 use crate::{
 	memory::{vmem, VirtAddr},
-	process::mem_space::{copy::SyscallSlice, MemSpace},
+	process::{
+		mem_space::{copy::SyscallSlice, MemSpace},
+		Process,
+	},
 	syscall::FromSyscallArg,
 };
-use core::{cmp::min, fmt, intrinsics::unlikely};
+use core::{cmp::min, fmt, intrinsics::unlikely, sync::atomic::Ordering::Relaxed};
 use utils::DisplayableStr;
 
 pub mod cmdline;
 pub mod cwd;
 pub mod environ;
 pub mod exe;
+#[cfg(feature = "fault_inject")]
+pub mod fail_nth;
+pub mod id_map;
+pub mod maps;
 pub mod mounts;
+pub mod ns;
+pub mod oom;
 pub mod stat;
 pub mod status;
+#[cfg(feature = "strace")]
+pub mod strace;
+
+/// Tells whether the current process may inspect the memory of `proc`, like with `ptrace`.
+///
+/// A process may always inspect the processes of its own thread group. Otherwise, it must have
+/// the same effective user ID as `proc` while `proc` is dumpable, or be privileged in the user
+/// namespace of `proc`.
+pub fn may_inspect(proc: &Process) -> bool {
+	let cur = Process::current();
+	if cur.get_tgid() == proc.get_tgid() {
+		return true;
+	}
+	// The current process's lock is released first, since `proc` may share its filesystem
+	// information
+	let (user_ns, ap) = {
+		let fs = cur.fs.lock();
+		(fs.user_ns.clone(), fs.access_profile)
+	};
+	let fs = proc.fs.lock();
+	if user_ns.is_privileged_in(&ap, &fs.user_ns) {
+		return true;
+	}
+	ap.euid == fs.access_profile.euid && proc.dumpable.load(Relaxed)
+}
 
 /// Reads a range of memory from `mem_space` and writes it to `f`.
 ///
@@ -21,7 +21,7 @@
 use crate::{
 	file::{fs::FileOps, vfs, vfs::mountpoint, File},
 	format_content,
-	process::pid::Pid,
+	process::{pid::Pid, Process},
 };
 use core::{fmt, fmt::Formatter};
 use utils::{errno::EResult, DisplayableStr};
@@ -38,8 +38,13 @@ impl FileOps for Mounts {
 
 impl fmt::Display for Mounts {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		let mps = mountpoint::MOUNT_POINTS.lock();
-		for (_, mp) in mps.iter() {
+		let Some(proc) = Process::get_by_pid(self.0) else {
+			return Ok(());
+		};
+		// Only show the mountpoints of the process's namespace
+		let ns = proc.fs.lock().mnt_ns.id();
+		let mps = mountpoint::MOUNT_POINTS.values().map_err(|_| fmt::Error)?;
+		for mp in mps.iter().filter(|mp| mp.ns == ns) {
 			let Ok(target) = vfs::Entry::get_path(&mp.root_entry) else {
 				continue;
 			};
@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `ns` directory's magic links, which allow to reference the namespaces of
+//! the process.
+
+use crate::{
+	file::{
+		fs::{DummyOps, NodeOps},
+		vfs::node::Node,
+		FileType, Stat,
+	},
+	format_content,
+	process::{
+		ns::{Namespace, NsFile, NsKind},
+		pid::Pid,
+		Process,
+	},
+	sync::mutex::Mutex,
+};
+use core::sync::atomic::AtomicBool;
+use utils::{boxed::Box, errno, errno::EResult, ptr::arc::Arc};
+
+/// A link to a namespace of a process.
+#[derive(Debug)]
+pub struct NsLink(pub Pid, pub NsKind);
+
+impl NsLink {
+	/// Returns the namespace the link points to.
+	fn get(&self) -> EResult<Namespace> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		Ok(Namespace::of(&proc, self.1))
+	}
+}
+
+impl NodeOps for NsLink {
+	fn readlink(&self, _node: &Node, buf: &mut [u8]) -> EResult<usize> {
+		let ns = self.get()?;
+		format_content!(0, buf, "{ns}")
+	}
+
+	fn follow_link(&self, node: &Node) -> EResult<Option<Arc<Node>>> {
+		let ns = self.get()?;
+		let stat = node.stat();
+		Ok(Some(Arc::new(Node {
+			inode: ns.id() as _,
+			fs: node.fs.clone(),
+
+			stat: Mutex::new(Stat {
+				mode: FileType::Regular.to_mode() | 0o400,
+				uid: stat.uid,
+				gid: stat.gid,
+				..Default::default()
+			}),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(DummyOps)?,
+			file_ops: Box::new(NsFile(ns))?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		})?))
+	}
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `oom_score` and `oom_score_adj` files, which allow to get the OOM score
+//! of the process and to tune it.
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content,
+	memory::{
+		oom,
+		oom::{OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN},
+	},
+	process::{pid::Pid, Process},
+};
+use core::{str, sync::atomic::Ordering::Relaxed};
+use utils::{errno, errno::EResult};
+
+/// The `oom_score` node of the proc.
+#[derive(Debug)]
+pub struct OomScore(pub Pid);
+
+impl FileOps for OomScore {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let pages = proc
+			.mem_space
+			.as_ref()
+			.map(|m| {
+				let mem_space = m.lock();
+				mem_space.get_rss() + mem_space.get_swap_usage()
+			})
+			.unwrap_or(0);
+		let adj = proc.oom_score_adj.load(Relaxed);
+		let score = oom::badness(pages, adj, oom::total_pages()).unwrap_or(0);
+		format_content!(off, buf, "{score}\n")
+	}
+}
+
+/// The `oom_score_adj` node of the proc.
+#[derive(Debug)]
+pub struct OomScoreAdj(pub Pid);
+
+impl FileOps for OomScoreAdj {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let adj = proc.oom_score_adj.load(Relaxed);
+		format_content!(off, buf, "{adj}\n")
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let adj: i16 = str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse().ok())
+			.filter(|adj| (OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(adj))
+			.ok_or_else(|| errno!(EINVAL))?;
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		// Only a privileged process can make another one less likely to be killed
+		let privileged = Process::current().fs.lock().access_profile.is_privileged();
+		if !privileged && adj < proc.oom_score_adj.load(Relaxed) {
+			return Err(errno!(EACCES));
+		}
+		proc.oom_score_adj.store(adj, Relaxed);
+		Ok(buf.len())
+	}
+}
@@ -53,9 +53,12 @@ TODO TODO TODO TODO TODO TODO TODO TODO TODO",
 				sid = 0,            // TODO
 				user_jiffies = 0,   // TODO
 				kernel_jiffies = 0, // TODO
-				priority = 0, // TODO
-				nice = 0, // TODO
-				num_threads = 1, // TODO
+				priority = match proc.rt_priority() {
+					0 => 20 + proc.nice() as i32,
+					prio => -1 - prio as i32,
+				},
+				nice = proc.nice(),
+				num_threads = proc.threads.lock().len(),
 				vmem_usage = mem_space.get_vmem_usage(),
 				sp = VirtAddr(user_regs.get_stack_address() as _),
 				pc = VirtAddr(user_regs.get_program_counter() as _),
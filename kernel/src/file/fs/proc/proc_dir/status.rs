@@ -17,15 +17,20 @@
  */
 
 //! Implementation of the `status` file, which allows to retrieve the current
-//! status of the process.
+//! status of the process in a human-readable form.
 
 use crate::{
+	cpu,
 	file::{fs::FileOps, File},
 	format_content,
-	process::{pid::Pid, Process},
+	process::{
+		pid::Pid,
+		signal::{SigSet, SignalHandler},
+		Process,
+	},
 };
 use core::fmt;
-use utils::{errno, errno::EResult};
+use utils::{errno, errno::EResult, limits::PAGE_SIZE};
 
 /// The `status` node of the proc.
 #[derive(Debug)]
@@ -35,83 +40,75 @@ impl FileOps for Status {
 	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
 		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
 		let mem_space = proc.mem_space.as_ref().unwrap().lock();
+		let (sig_pnd, sig_q, sig_blk, sig_ign, sig_cgt) = {
+			let signal = proc.signal.lock();
+			let mut ign = SigSet::default();
+			let mut cgt = SigSet::default();
+			for (n, handler) in signal.handlers.lock().iter().enumerate().skip(1) {
+				match handler {
+					SignalHandler::Ignore => ign.set(n),
+					SignalHandler::Handler(_) => cgt.set(n),
+					SignalHandler::Default => {}
+				}
+			}
+			(
+				signal.pending_set(),
+				signal.pending_count(),
+				signal.sigmask,
+				ign,
+				cgt,
+			)
+		};
+		let threads = proc.threads.lock().len();
 		let disp = fmt::from_fn(|f| {
 			let state = proc.get_state();
 			let fs = proc.fs.lock();
-			// TODO Fill every fields with process's data
+			let ap = &fs.access_profile;
+			// The filesystem IDs are the effective IDs
 			writeln!(
 				f,
-				"Name: {name}
-Umask: {umask:4o}
-State: {state_char} ({state_name})
-Tgid: 0
-Ngid: 0
-Pid: {pid}
-PPid: {ppid}
-TracerPid: 0
-Uid: {uid} {euid} {suid} {ruid}
-Gid: {gid} {egid} {sgid} {rgid}
-FDSize: TODO
-Groups: TODO
-NStgid: TODO
-NSpid: TODO
-NSpgid: TODO
-NSsid: TODO
-VmPeak: TODO kB
-VmSize: TODO kB
-VmLck: TODO kB
-VmPin: TODO kB
-VmHWM: TODO kB
-VmRSS: TODO kB
-RssAnon: TODO kB
-RssFile: TODO kB
-RssShmem: TODO kB
-VmData: TODO kB
-VmStk: TODO kB
-VmExe: TODO kB
-VmLib: TODO kB
-VmPTE: TODO kB
-VmSwap: TODO kB
-HugetlbPages: TODO kB
-CoreDumping: TODO
-THP_enabled: TODO
-Threads: TODO
-SigQ: TODO/TODO
-SigPnd: 0000000000000000
-ShdPnd: 0000000000000000
-SigBlk: 0000000000000000
-SigIgn: 0000000000000000
-SigCgt: 0000000000000000
-CapInh: 0000000000000000
-CapPrm: 0000000000000000
-CapEff: 0000000000000000
-CapBnd: 000001ffffffffff
-CapAmb: 0000000000000000
-NoNewPrivs: 0
-Seccomp: 0
-Seccomp_filters: 0
-Speculation_Store_Bypass: thread vulnerable
-SpeculationIndirectBranch: conditional enabled
-Cpus_allowed: ff
-Cpus_allowed_list: 0-7
-Mems_allowed: 00000001
-Mems_allowed_list: 0
-voluntary_ctxt_switches: 0
-nonvoluntary_ctxt_switches: 0",
+				"Name:\t{name}
+Umask:\t{umask:04o}
+State:\t{state_char} ({state_name})
+Tgid:\t{tgid}
+Pid:\t{pid}
+PPid:\t{ppid}
+TracerPid:\t0
+Uid:\t{uid}\t{euid}\t{suid}\t{euid}
+Gid:\t{gid}\t{egid}\t{sgid}\t{egid}
+VmSize:\t{vm_size:8} kB
+VmRSS:\t{vm_rss:8} kB
+VmSwap:\t{vm_swap:8} kB
+Threads:\t{threads}
+SigQ:\t{sig_q}/unlimited
+SigPnd:\t{sig_pnd:016x}
+ShdPnd:\t0000000000000000
+SigBlk:\t{sig_blk:016x}
+SigIgn:\t{sig_ign:016x}
+SigCgt:\t{sig_cgt:016x}
+NoNewPrivs:\t0
+Cpus_allowed:\t{cpus:x}",
 				name = mem_space.exe_info.exe.name,
 				umask = fs.umask(),
 				state_char = state.as_char(),
 				state_name = state.as_str(),
+				tgid = proc.get_tgid(),
 				pid = self.0,
 				ppid = proc.get_parent_pid(),
-				uid = fs.access_profile.uid,
-				euid = fs.access_profile.euid,
-				suid = fs.access_profile.suid,
-				ruid = fs.access_profile.uid,
-				gid = fs.access_profile.gid,
-				egid = fs.access_profile.egid,
-				sgid = fs.access_profile.sgid,
-				rgid = fs.access_profile.gid,
+				uid = ap.uid,
+				euid = ap.euid,
+				suid = ap.suid,
+				gid = ap.gid,
+				egid = ap.egid,
+				sgid = ap.sgid,
+				vm_size = mem_space.get_vmem_usage() * PAGE_SIZE / 1024,
+				vm_rss = mem_space.get_rss() * PAGE_SIZE / 1024,
+				vm_swap = mem_space.get_swap_usage() * PAGE_SIZE / 1024,
+				sig_pnd = sig_pnd.0,
+				sig_blk = sig_blk.0,
+				sig_ign = sig_ign.0,
+				sig_cgt = sig_cgt.0,
+				cpus = proc.get_affinity() & cpu::online(),
 			)
 		});
 		format_content!(off, buf, "{disp}")
@@ -0,0 +1,56 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Implementation of the `strace` file, which allows to enable or disable tracing of the system
+//! calls of the process.
+
+use crate::{
+	file::{fs::FileOps, File},
+	format_content,
+	process::{pid::Pid, Process},
+};
+use core::str;
+use utils::{errno, errno::EResult};
+
+/// The `strace` node of the proc.
+#[derive(Debug)]
+pub struct Strace(pub Pid);
+
+impl FileOps for Strace {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		let traced = proc.is_traced() as u8;
+		format_content!(off, buf, "{traced}\n")
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let traced = match str::from_utf8(buf).map(str::trim) {
+			Ok("0") => false,
+			Ok("1") => true,
+			_ => return Err(errno!(EINVAL)),
+		};
+		let proc = Process::get_by_pid(self.0).ok_or_else(|| errno!(ENOENT))?;
+		// Only a privileged process can trace another one
+		let cur = Process::current();
+		if cur.get_pid() != self.0 && !cur.fs.lock().access_profile.is_privileged() {
+			return Err(errno!(EPERM));
+		}
+		proc.set_traced(traced);
+		Ok(buf.len())
+	}
+}
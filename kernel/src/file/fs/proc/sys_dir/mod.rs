@@ -19,10 +19,58 @@
 //! TODO doc
 
 use crate::{
-	file::{fs::FileOps, File, FileType, Stat},
+	device::mem,
+	file::{fs::FileOps, vfs, File, FileType, Stat},
 	format_content,
+	memory::overcommit,
 };
-use utils::errno::EResult;
+use core::str;
+use utils::{errno, errno::EResult};
+
+/// The `dentry-cache` file, giving the number of hits and misses of the directory entries cache.
+#[derive(Debug, Default)]
+pub struct DentryCache;
+
+impl FileOps for DentryCache {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o444,
+			..Default::default()
+		})
+	}
+
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let (hits, misses) = vfs::cache_stats();
+		format_content!(off, buf, "hits: {hits}\nmisses: {misses}\n")
+	}
+}
+
+/// The `devmem_lockdown` file, telling whether access to RAM through `/dev/mem` is refused.
+#[derive(Debug, Default)]
+pub struct DevmemLockdown;
+
+impl FileOps for DevmemLockdown {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			..Default::default()
+		})
+	}
+
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}\n", mem::is_lockdown() as u8)
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let lockdown = match buf.trim_ascii() {
+			b"0" => false,
+			b"1" => true,
+			_ => return Err(errno!(EINVAL)),
+		};
+		mem::set_lockdown(lockdown);
+		Ok(buf.len())
+	}
+}
 
 /// The `osrelease` file.
 #[derive(Debug, Default)]
@@ -40,3 +88,29 @@ impl FileOps for OsRelease {
 		format_content!(off, buf, "{}\n", crate::VERSION)
 	}
 }
+
+/// The `overcommit_memory` file.
+#[derive(Debug, Default)]
+pub struct OvercommitMemory;
+
+impl FileOps for OvercommitMemory {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | 0o644,
+			..Default::default()
+		})
+	}
+
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}\n", overcommit::get_policy())
+	}
+
+	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let policy = str::from_utf8(buf)
+			.ok()
+			.and_then(|s| s.trim().parse().ok())
+			.ok_or_else(|| errno!(EINVAL))?;
+		overcommit::set_policy(policy)?;
+		Ok(buf.len())
+	}
+}
@@ -0,0 +1,142 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `block` directory lists the registered block devices.
+//!
+//! Each device has a directory named after its device file, containing the following
+//! attributes:
+//! - `dev`: the major and minor numbers of the device
+//! - `size`: the size of the device, in 512-byte sectors
+
+use crate::{
+	device,
+	device::DeviceID,
+	file::{
+		fs::{
+			kernfs::{box_file, static_dir_stat, EitherOps, StaticDir, StaticEntry},
+			DummyOps, FileOps, NodeOps,
+		},
+		vfs,
+		vfs::node::Node,
+		DirContext, DirEntry, File, FileType, Stat,
+	},
+	format_content,
+	sync::mutex::Mutex,
+};
+use core::sync::atomic::AtomicBool;
+use utils::{boxed::Box, errno, errno::EResult, ptr::arc::Arc};
+
+/// Returns the status of an attribute file.
+fn attr_stat(_: DeviceID) -> Stat {
+	Stat {
+		mode: FileType::Regular.to_mode() | 0o444,
+		..Default::default()
+	}
+}
+
+/// The `dev` attribute of a block device.
+#[derive(Debug)]
+struct Dev(DeviceID);
+
+impl FileOps for Dev {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		format_content!(off, buf, "{}:{}\n", self.0.major, self.0.minor)
+	}
+}
+
+/// The `size` attribute of a block device.
+#[derive(Debug)]
+struct Size(DeviceID);
+
+impl FileOps for Size {
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let dev = device::BLK_DEVICES
+			.lock()
+			.get(&self.0)
+			.cloned()
+			.ok_or_else(|| errno!(ENODEV))?;
+		let size = dev.ops.blocks_count() * dev.ops.block_size().get() / 512;
+		format_content!(off, buf, "{size}\n")
+	}
+}
+
+/// The directory listing block devices.
+#[derive(Debug)]
+pub struct BlockDir;
+
+impl NodeOps for BlockDir {
+	fn lookup_entry(&self, dir: &Node, ent: &mut vfs::Entry) -> EResult<()> {
+		let id = device::BLK_DEVICES
+			.lock()
+			.iter()
+			.find(|(_, dev)| dev.path.file_name() == Some(&ent.name))
+			.map(|(id, _)| *id);
+		ent.node = id
+			.map(|id| {
+				Arc::new(Node {
+					inode: 0,
+					fs: dir.fs.clone(),
+
+					stat: Mutex::new(static_dir_stat()),
+					dirty: AtomicBool::new(false),
+
+					node_ops: Box::new(StaticDir {
+						entries: &[
+							StaticEntry {
+								name: b"dev",
+								stat: attr_stat,
+								init: EitherOps::File(|id| box_file(Dev(id))),
+							},
+							StaticEntry {
+								name: b"size",
+								stat: attr_stat,
+								init: EitherOps::File(|id| box_file(Size(id))),
+							},
+						],
+						data: id,
+					})?,
+					file_ops: Box::new(DummyOps)?,
+
+					lock: Default::default(),
+					mapped: Default::default(),
+				})
+			})
+			.transpose()?;
+		Ok(())
+	}
+
+	fn iter_entries(&self, _dir: &Node, ctx: &mut DirContext) -> EResult<()> {
+		let devs = device::BLK_DEVICES.lock();
+		let iter = devs
+			.iter()
+			.filter_map(|(_, dev)| dev.path.file_name())
+			.skip(ctx.off as usize);
+		for name in iter {
+			let ent = DirEntry {
+				inode: 0,
+				entry_type: Some(FileType::Directory),
+				name,
+			};
+			if !(ctx.write)(&ent)? {
+				break;
+			}
+			ctx.off += 1;
+		}
+		Ok(())
+	}
+}
@@ -0,0 +1,172 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sysfs` is a virtual filesystem which provides information about the kernel and devices.
+
+mod block;
+mod version;
+
+use super::{DummyOps, Filesystem, FilesystemOps, FilesystemType};
+use crate::{
+	device::BlkDev,
+	file::{
+		fs::{
+			kernfs::{box_file, box_node, static_dir_stat, EitherOps, StaticDir, StaticEntry},
+			Statfs,
+		},
+		vfs::node::Node,
+		FileType, Stat,
+	},
+	sync::mutex::Mutex,
+};
+use block::BlockDir;
+use core::sync::atomic::AtomicBool;
+use utils::{boxed::Box, collections::path::PathBuf, errno, errno::EResult, ptr::arc::Arc};
+use version::Version;
+
+/// The root directory of the sysfs.
+const ROOT: StaticDir = StaticDir {
+	entries: &[
+		StaticEntry {
+			name: b"block",
+			stat: |_| static_dir_stat(),
+			init: EitherOps::Node(|_| box_node(BlockDir)),
+		},
+		StaticEntry {
+			name: b"class",
+			stat: |_| static_dir_stat(),
+			init: EitherOps::Node(|_| {
+				box_node(StaticDir {
+					entries: &[StaticEntry {
+						name: b"block",
+						stat: |_| static_dir_stat(),
+						init: EitherOps::Node(|_| box_node(BlockDir)),
+					}],
+					data: (),
+				})
+			}),
+		},
+		StaticEntry {
+			name: b"kernel",
+			stat: |_| static_dir_stat(),
+			init: EitherOps::Node(|_| {
+				box_node(StaticDir {
+					entries: &[
+						// Mountpoint for the debugfs
+						StaticEntry {
+							name: b"debug",
+							stat: |_| Stat {
+								mode: FileType::Directory.to_mode() | 0o700,
+								..Default::default()
+							},
+							init: EitherOps::Node(|_| {
+								box_node(StaticDir {
+									entries: &[],
+									data: (),
+								})
+							}),
+						},
+						StaticEntry {
+							name: b"version",
+							stat: |_| Stat {
+								mode: FileType::Regular.to_mode() | 0o444,
+								..Default::default()
+							},
+							init: EitherOps::File(|_| box_file(Version)),
+						},
+					],
+					data: (),
+				})
+			}),
+		},
+	],
+	data: (),
+};
+
+/// A sysfs.
+#[derive(Debug)]
+pub struct SysFS;
+
+impl FilesystemOps for SysFS {
+	fn get_name(&self) -> &[u8] {
+		b"sysfs"
+	}
+
+	fn get_stat(&self) -> EResult<Statfs> {
+		Ok(Statfs {
+			f_type: 0,
+			f_bsize: 0,
+			f_blocks: 0,
+			f_bfree: 0,
+			f_bavail: 0,
+			f_files: 0,
+			f_ffree: 0,
+			f_fsid: Default::default(),
+			f_namelen: 0,
+			f_frsize: 0,
+			f_flags: 0,
+			f_spare: [0; 4],
+		})
+	}
+
+	fn root(&self, fs: Arc<Filesystem>) -> EResult<Arc<Node>> {
+		Ok(Arc::new(Node {
+			inode: 0,
+			fs,
+
+			stat: Mutex::new(static_dir_stat()),
+			dirty: AtomicBool::new(false),
+
+			node_ops: Box::new(ROOT)?,
+			file_ops: Box::new(DummyOps)?,
+
+			lock: Default::default(),
+			mapped: Default::default(),
+		})?)
+	}
+
+	fn create_node(&self, _fs: Arc<Filesystem>, _stat: Stat) -> EResult<Arc<Node>> {
+		Err(errno!(EINVAL))
+	}
+
+	fn destroy_node(&self, _node: &Node) -> EResult<()> {
+		Err(errno!(EINVAL))
+	}
+}
+
+/// The sysfs filesystem type.
+pub struct SysFsType;
+
+impl FilesystemType for SysFsType {
+	fn get_name(&self) -> &'static [u8] {
+		b"sysfs"
+	}
+
+	fn detect(&self, _dev: &Arc<BlkDev>) -> EResult<bool> {
+		Ok(false)
+	}
+
+	fn load_filesystem(
+		&self,
+		_dev: Option<Arc<BlkDev>>,
+		_mountpath: PathBuf,
+		_readonly: bool,
+	) -> EResult<Arc<Filesystem>> {
+		Ok(Filesystem::new(0, Box::new(SysFS)?)?)
+	}
+}
@@ -143,6 +143,42 @@ impl NodeContent {
 	fn from_ops(ops: &dyn NodeOps) -> &Self {
 		(ops as &dyn Any).downcast_ref().unwrap()
 	}
+
+	/// Removes the entry with the given `name` from the directory.
+	///
+	/// `parent` is the directory's node.
+	fn remove_entry(&self, parent: &Node, name: &[u8]) -> EResult<()> {
+		// Find entry
+		let NodeContent::Directory(parent_inner) = self else {
+			return Err(errno!(ENOTDIR));
+		};
+		let mut parent_inner = parent_inner.lock();
+		let node = parent_inner.find(name).ok_or_else(|| errno!(ENOENT))?;
+		// Handle directory-specifics
+		let content = NodeContent::from_ops(&*node.node_ops);
+		if let NodeContent::Directory(inner) = content {
+			// If not empty, error
+			let mut inner = inner.lock();
+			let not_empty = inner.used_slots > 2
+				|| inner
+					.entries
+					.iter()
+					.filter_map(|e| e.as_ref())
+					.any(|e| !matches!(e.name.as_ref(), b"." | b".."));
+			if not_empty {
+				return Err(errno!(ENOTEMPTY));
+			}
+			// Remove `.` and `..` to break cycles
+			inner.entries.clear();
+			// Decrement references count
+			node.stat.lock().nlink -= 1;
+			parent.stat.lock().nlink -= 1;
+		}
+		// Remove
+		node.stat.lock().nlink -= 1;
+		parent_inner.remove(name);
+		Ok(())
+	}
 }
 
 impl NodeOps for NodeContent {
@@ -218,38 +254,7 @@ impl NodeOps for NodeContent {
 		if unlikely(fs.readonly) {
 			return Err(errno!(EROFS));
 		}
-		// Find entry
-		let NodeContent::Directory(parent_inner) = self else {
-			return Err(errno!(ENOTDIR));
-		};
-		let mut parent_inner = parent_inner.lock();
-		let node = parent_inner
-			.find(ent.name.as_ref())
-			.ok_or_else(|| errno!(ENOENT))?;
-		// Handle directory-specifics
-		let content = NodeContent::from_ops(&*node.node_ops);
-		if let NodeContent::Directory(inner) = content {
-			// If not empty, error
-			let mut inner = inner.lock();
-			let not_empty = inner.used_slots > 2
-				|| inner
-					.entries
-					.iter()
-					.filter_map(|e| e.as_ref())
-					.any(|e| !matches!(e.name.as_ref(), b"." | b".."));
-			if not_empty {
-				return Err(errno!(ENOTEMPTY));
-			}
-			// Remove `.` and `..` to break cycles
-			inner.entries.clear();
-			// Decrement references count
-			node.stat.lock().nlink -= 1;
-			parent.stat.lock().nlink -= 1;
-		}
-		// Remove
-		node.stat.lock().nlink -= 1;
-		parent_inner.remove(ent.name.as_ref());
-		Ok(())
+		self.remove_entry(parent, &ent.name)
 	}
 
 	fn readlink(&self, _node: &Node, buf: &mut [u8]) -> EResult<usize> {
@@ -286,8 +291,31 @@ impl NodeOps for NodeContent {
 		let NodeContent::Directory(new_parent_inner) = new_parent_ops else {
 			return Err(errno!(ENOTDIR));
 		};
-		// Create new entry
 		let entry_node = entry.node();
+		let dir = matches!(
+			NodeContent::from_ops(&*entry_node.node_ops),
+			NodeContent::Directory(_)
+		);
+		// If the destination exists, replace it
+		let target = new_parent_inner.lock().find(new_name).cloned();
+		if let Some(target) = target {
+			// Both entries are links to the same file, there is nothing to do
+			if Arc::as_ptr(&target) == Arc::as_ptr(entry_node) {
+				return Ok(());
+			}
+			let target_dir = matches!(
+				NodeContent::from_ops(&*target.node_ops),
+				NodeContent::Directory(_)
+			);
+			if dir && !target_dir {
+				return Err(errno!(ENOTDIR));
+			}
+			if !dir && target_dir {
+				return Err(errno!(EISDIR));
+			}
+			new_parent_ops.remove_entry(new_parent_node, new_name)?;
+		}
+		// Create new entry
 		new_parent_inner.lock().insert(TmpfsDirEntry {
 			name: Cow::Owned(new_name.try_to_owned()?),
 			node: entry_node.clone(),
@@ -408,6 +436,7 @@ impl FilesystemOps for TmpFS {
 			f_namelen: NAME_MAX as _,
 			f_frsize: 0,
 			f_flags: 0,
+			f_spare: [0; 4],
 		})
 	}
 
@@ -24,6 +24,7 @@
 //! The root filesystem is passed to the kernel as an argument on boot.
 //! Other filesystems are mounted into subdirectories.
 
+pub mod epoll;
 pub mod fd;
 pub mod fs;
 pub mod perm;
@@ -39,11 +40,9 @@ use crate::{
 		fs::FileOps,
 		perm::{Gid, Uid},
 		pipe::PipeBuffer,
-		socket::Socket,
 		vfs::node::Node,
 	},
-	net::{SocketDesc, SocketDomain, SocketType},
-	sync::{atomic::AtomicU64, mutex::Mutex, once::OnceInit},
+	sync::{atomic::AtomicU64, mutex::Mutex},
 	time::{
 		clock::{current_time_sec, Clock},
 		unit::Timestamp,
@@ -300,14 +299,14 @@ impl Stat {
 	/// Sets the owner user ID, updating `ctime` with the current timestamp.
 	pub fn set_uid(&mut self, uid: Uid) {
 		self.uid = uid;
-		let timestamp = current_time_sec(Clock::Monotonic);
+		let timestamp = current_time_sec(Clock::Realtime);
 		self.ctime = timestamp;
 	}
 
 	/// Sets the owner group ID, updating `ctime` with the current timestamp.
 	pub fn set_gid(&mut self, gid: Gid) {
 		self.gid = gid;
-		let timestamp = current_time_sec(Clock::Monotonic);
+		let timestamp = current_time_sec(Clock::Realtime);
 		self.ctime = timestamp;
 	}
 }
@@ -366,25 +365,23 @@ impl File {
 			Some(FileType::Fifo) => {
 				FileOpsWrapper::Owned(node.fs.buffer_get_or_insert(node.inode, PipeBuffer::new)?)
 			}
-			Some(FileType::Socket) => {
-				FileOpsWrapper::Owned(node.fs.buffer_get_or_insert(node.inode, || {
-					Socket::new(SocketDesc {
-						domain: SocketDomain::AfUnix,
-						type_: SocketType::SockStream,
-						protocol: 0,
-					})
-				})?)
-			}
+			// Sockets are reached with `connect` instead
+			Some(FileType::Socket) => return Err(errno!(ENXIO)),
 			Some(FileType::BlockDevice) => FileOpsWrapper::Owned(Arc::new(BlkDevFileOps)?),
 			Some(FileType::CharDevice) => {
-				let devices = CHAR_DEVICES.lock();
-				let dev = devices
+				let dev = CHAR_DEVICES
+					.lock()
 					.get(&DeviceID {
 						major: stat.dev_major,
 						minor: stat.dev_minor,
 					})
+					.cloned()
 					.ok_or_else(|| errno!(ENODEV))?;
-				FileOpsWrapper::Borrowed(NonNull::from(dev.ops.as_ref()))
+				// The devices list is unlocked since opening may register devices
+				match dev.ops.open()? {
+					Some(ops) => FileOpsWrapper::Owned(ops),
+					None => FileOpsWrapper::Borrowed(NonNull::from(dev.ops.as_ref())),
+				}
 			}
 			_ => FileOpsWrapper::Borrowed(NonNull::from(node.file_ops.as_ref())),
 		};
@@ -644,6 +641,33 @@ impl AccessProfile {
 		self.check_execute_access(stat, true)
 	}
 
+	/// Updates the IDs of the agent for the execution of a file with the given status, in the way
+	/// `execve` does.
+	///
+	/// If the file has the set-user-ID (resp. set-group-ID) bit, the effective user (resp. group)
+	/// ID becomes the file's owner (resp. group). These bits are ignored if `nosuid` is `true`.
+	///
+	/// In any case, the saved IDs are set to the effective IDs.
+	pub fn exec_file(&mut self, stat: &Stat, nosuid: bool) {
+		if !nosuid {
+			if stat.mode & perm::S_ISUID != 0 {
+				self.euid = stat.uid;
+			}
+			// Without group execute permission, the set-group-ID bit means mandatory locking
+			if stat.mode & (perm::S_ISGID | perm::S_IXGRP) == perm::S_ISGID | perm::S_IXGRP {
+				self.egid = stat.gid;
+			}
+		}
+		self.suid = self.euid;
+		self.sgid = self.egid;
+	}
+
+	/// Tells whether the effective IDs differ from the real IDs, in which case a program must be
+	/// executed in secure mode.
+	pub fn is_secure(&self) -> bool {
+		self.euid != self.uid || self.egid != self.gid
+	}
+
 	/// Tells whether the agent can access files of a directory with the given status, *if the name
 	/// of the file is known*.
 	#[inline]
@@ -659,8 +683,12 @@ impl AccessProfile {
 
 /// Initializes files management.
 ///
-/// `root` is the set of major and minor numbers of the root device. If `None`, a tmpfs is used.
-pub(crate) fn init(root: Option<(u32, u32)>) -> EResult<()> {
+/// Arguments:
+/// - `root` is the set of major and minor numbers of the root device. If `None`, a tmpfs is used.
+/// - `flags` is the set of mount flags of the root filesystem.
+pub(crate) fn init(root: Option<(u32, u32)>, flags: u32) -> EResult<()> {
+	// The initial mount namespace is owned by the initial user namespace
+	perm::init()?;
 	fs::register_defaults()?;
 	// Create the root mountpoint
 	let source = match root {
@@ -670,15 +698,14 @@ pub(crate) fn init(root: Option<(u32, u32)>) -> EResult<()> {
 		}),
 		None => MountSource::NoDev(String::try_from(b"tmpfs")?),
 	};
-	let root = mountpoint::create(source, None, 0, None)?;
+	let root = mountpoint::create(source, None, flags, None)?;
+	mountpoint::init(root.clone())?;
 	// Init the VFS's root entry.
-	unsafe {
-		OnceInit::init(&vfs::ROOT, root);
-	}
+	vfs::set_root(root);
 	Ok(())
 }
 
 /// Tells whether files management has been initialized.
 pub(crate) fn is_init() -> bool {
-	!mountpoint::MOUNT_POINTS.lock().is_empty()
+	!mountpoint::MOUNT_POINTS.is_empty()
 }
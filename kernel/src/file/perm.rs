@@ -19,9 +19,23 @@
 //! UNIX permissions are detailed in the POSIX specification.
 //!
 //! This module implements management of such permissions.
+//!
+//! User namespaces allow a process to have different user and group IDs inside of the namespace
+//! than outside. The IDs stored in an [`AccessProfile`] are always the ones of the initial
+//! namespace, so that permission checks on global resources are unaffected. They are translated
+//! when passed to or returned from userspace.
 
 use super::Mode;
-use utils::{errno, errno::EResult};
+use crate::sync::{mutex::Mutex, once::OnceInit};
+use core::{
+	fmt, ptr, str,
+	sync::atomic::{AtomicU32, Ordering::Relaxed},
+};
+use utils::{
+	errno,
+	errno::{AllocResult, EResult},
+	ptr::arc::Arc,
+};
 
 /// Type representing a user ID.
 pub type Uid = u16;
@@ -33,6 +47,11 @@ pub const ROOT_UID: Uid = 0;
 /// The root group ID.
 pub const ROOT_GID: Gid = 0;
 
+/// The user ID returned for users that are not mapped in the current user namespace.
+pub const OVERFLOW_UID: Uid = 65534;
+/// The group ID returned for groups that are not mapped in the current user namespace.
+pub const OVERFLOW_GID: Gid = 65534;
+
 /// User: Read, Write and Execute.
 pub const S_IRWXU: Mode = 0o0700;
 /// User: Read.
@@ -134,9 +153,11 @@ impl AccessProfile {
 
 	/// Sets the user ID in the same way the `setgid` system call does.
 	///
+	/// `privileged` tells whether the agent is privileged in its user namespace.
+	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
-	pub fn set_uid(&mut self, uid: Uid) -> EResult<()> {
-		if self.euid == ROOT_UID {
+	pub fn set_uid(&mut self, uid: Uid, privileged: bool) -> EResult<()> {
+		if privileged {
 			// privileged
 			self.uid = uid;
 			self.euid = uid;
@@ -164,9 +185,11 @@ impl AccessProfile {
 
 	/// Sets the group ID in the way the `setgid` system call does.
 	///
+	/// `privileged` tells whether the agent is privileged in its user namespace.
+	///
 	/// If the agent is not privileged enough to make the change, the function returns an error.
-	pub fn set_gid(&mut self, gid: Gid) -> EResult<()> {
-		if self.egid == ROOT_GID {
+	pub fn set_gid(&mut self, gid: Gid, privileged: bool) -> EResult<()> {
+		if privileged {
 			// privileged
 			self.gid = gid;
 			self.egid = gid;
@@ -192,3 +215,337 @@ impl AccessProfile {
 		}
 	}
 }
+
+/// The maximum number of extents in an ID mapping.
+pub const ID_MAP_MAX: usize = 5;
+/// The maximum nesting depth of user namespaces.
+const USER_NS_MAX_DEPTH: usize = 32;
+
+/// A range of IDs of a user namespace, mapped to a range of IDs of its parent.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdMapExtent {
+	/// The first ID of the range in the namespace.
+	pub inside: u32,
+	/// The first ID of the range in the parent namespace.
+	pub outside: u32,
+	/// The number of IDs in the range.
+	pub count: u32,
+}
+
+/// A mapping of the user or group IDs of a user namespace to the ones of its parent.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct IdMap {
+	/// The extents of the mapping.
+	extents: [IdMapExtent; ID_MAP_MAX],
+	/// The number of used extents.
+	len: usize,
+}
+
+impl IdMap {
+	/// Parses a mapping from the content written to a `uid_map` or `gid_map` file.
+	///
+	/// Each line is an extent, made of the first ID inside, the first ID outside and the number of
+	/// IDs, separated by spaces.
+	///
+	/// If the mapping is invalid or if extents overlap, the function returns [`errno::EINVAL`].
+	pub fn parse(buf: &[u8]) -> EResult<Self> {
+		let mut map = Self::default();
+		let lines = buf
+			.split(|b| *b == b'\n')
+			.filter(|line| !line.trim_ascii().is_empty());
+		for line in lines {
+			let mut nums = line
+				.split(u8::is_ascii_whitespace)
+				.filter(|n| !n.is_empty())
+				.map(|n| str::from_utf8(n).ok().and_then(|n| n.parse::<u32>().ok()));
+			let (Some(Some(inside)), Some(Some(outside)), Some(Some(count)), None) =
+				(nums.next(), nums.next(), nums.next(), nums.next())
+			else {
+				return Err(errno!(EINVAL));
+			};
+			if count == 0
+				|| inside.checked_add(count).is_none()
+				|| outside.checked_add(count).is_none()
+			{
+				return Err(errno!(EINVAL));
+			}
+			let overlap = |a: u32, b: u32, count_a: u32| a < b + count && b < a + count_a;
+			let overlapping = map.extents().iter().any(|e| {
+				overlap(e.inside, inside, e.count) || overlap(e.outside, outside, e.count)
+			});
+			if overlapping || map.len >= ID_MAP_MAX {
+				return Err(errno!(EINVAL));
+			}
+			map.extents[map.len] = IdMapExtent {
+				inside,
+				outside,
+				count,
+			};
+			map.len += 1;
+		}
+		if map.len == 0 {
+			return Err(errno!(EINVAL));
+		}
+		Ok(map)
+	}
+
+	/// Returns the extents of the mapping.
+	pub fn extents(&self) -> &[IdMapExtent] {
+		&self.extents[..self.len]
+	}
+
+	/// Translates the ID `id` of the namespace to the one of its parent.
+	fn outside_of(&self, id: u32) -> Option<u32> {
+		self.extents()
+			.iter()
+			.find(|e| (e.inside..e.inside + e.count).contains(&id))
+			.map(|e| e.outside + (id - e.inside))
+	}
+
+	/// Translates the ID `id` of the parent namespace to the one of the namespace.
+	fn inside_of(&self, id: u32) -> Option<u32> {
+		self.extents()
+			.iter()
+			.find(|e| (e.outside..e.outside + e.count).contains(&id))
+			.map(|e| e.inside + (id - e.outside))
+	}
+}
+
+impl fmt::Display for IdMap {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for e in self.extents() {
+			writeln!(f, "{:>10} {:>10} {:>10}", e.inside, e.outside, e.count)?;
+		}
+		Ok(())
+	}
+}
+
+/// Selects the user or group mapping of a user namespace.
+type MapSelector = fn(&UserNamespace) -> &Mutex<Option<IdMap>>;
+
+/// A user namespace.
+///
+/// The creator of a namespace, and the processes whose effective user ID is mapped to root in
+/// it, are privileged in the namespace and its descendants.
+#[derive(Debug)]
+pub struct UserNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The parent namespace. If `None`, this is the initial namespace.
+	parent: Option<Arc<UserNamespace>>,
+	/// The nesting depth of the namespace.
+	depth: usize,
+	/// The effective user ID of the creator of the namespace.
+	owner: Uid,
+
+	/// The mapping of user IDs to the parent namespace. `None` until it is written.
+	uid_map: Mutex<Option<IdMap>>,
+	/// The mapping of group IDs to the parent namespace. `None` until it is written.
+	gid_map: Mutex<Option<IdMap>>,
+}
+
+/// The ID of the next user namespace to be created. The initial namespace has the ID `1`.
+static NEXT_USER_NS_ID: AtomicU32 = AtomicU32::new(2);
+
+/// The initial user namespace.
+static ROOT_USER_NS: OnceInit<Arc<UserNamespace>> = unsafe { OnceInit::new() };
+
+impl UserNamespace {
+	/// Returns the initial user namespace.
+	pub fn root() -> &'static Arc<Self> {
+		&ROOT_USER_NS
+	}
+
+	/// Creates a new namespace, child of `parent`, created by a process with the effective user
+	/// ID `owner`.
+	///
+	/// The mappings of the new namespace are empty until written.
+	///
+	/// If the maximum nesting depth is reached, the function returns [`errno::EUSERS`].
+	pub fn new(parent: &Arc<Self>, owner: Uid) -> EResult<Arc<Self>> {
+		if parent.depth >= USER_NS_MAX_DEPTH {
+			return Err(errno!(EUSERS));
+		}
+		Ok(Arc::new(Self {
+			id: NEXT_USER_NS_ID.fetch_add(1, Relaxed),
+			parent: Some(parent.clone()),
+			depth: parent.depth + 1,
+			owner,
+
+			uid_map: Mutex::new(None),
+			gid_map: Mutex::new(None),
+		})?)
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// Returns the parent namespace, if any.
+	pub fn parent(&self) -> Option<&Arc<Self>> {
+		self.parent.as_ref()
+	}
+
+	/// Returns the mapping of user IDs, if written.
+	pub fn uid_map(&self) -> Option<IdMap> {
+		*self.uid_map.lock()
+	}
+
+	/// Returns the mapping of group IDs, if written.
+	pub fn gid_map(&self) -> Option<IdMap> {
+		*self.gid_map.lock()
+	}
+
+	/// Translates the ID `id` of the namespace to the one of the initial namespace.
+	fn global_of(&self, id: u32, map: MapSelector) -> Option<u32> {
+		let Some(parent) = &self.parent else {
+			return Some(id);
+		};
+		let outside = map(self).lock().as_ref()?.outside_of(id)?;
+		parent.global_of(outside, map)
+	}
+
+	/// Translates the ID `id` of the initial namespace to the one of the namespace.
+	fn local_of(&self, id: u32, map: MapSelector) -> Option<u32> {
+		let Some(parent) = &self.parent else {
+			return Some(id);
+		};
+		let outside = parent.local_of(id, map)?;
+		map(self).lock().as_ref()?.inside_of(outside)
+	}
+
+	/// Translates the user ID `uid` of the namespace to the one of the initial namespace.
+	///
+	/// If the ID is not mapped, the function returns `None`.
+	pub fn uid_to_global(&self, uid: Uid) -> Option<Uid> {
+		self.global_of(uid as _, |ns| &ns.uid_map)
+			.and_then(|id| id.try_into().ok())
+	}
+
+	/// Translates the user ID `uid` of the initial namespace to the one of the namespace.
+	///
+	/// If the ID is not mapped, the function returns `None`.
+	pub fn uid_from_global(&self, uid: Uid) -> Option<Uid> {
+		self.local_of(uid as _, |ns| &ns.uid_map)
+			.and_then(|id| id.try_into().ok())
+	}
+
+	/// Translates the group ID `gid` of the namespace to the one of the initial namespace.
+	///
+	/// If the ID is not mapped, the function returns `None`.
+	pub fn gid_to_global(&self, gid: Gid) -> Option<Gid> {
+		self.global_of(gid as _, |ns| &ns.gid_map)
+			.and_then(|id| id.try_into().ok())
+	}
+
+	/// Translates the group ID `gid` of the initial namespace to the one of the namespace.
+	///
+	/// If the ID is not mapped, the function returns `None`.
+	pub fn gid_from_global(&self, gid: Gid) -> Option<Gid> {
+		self.local_of(gid as _, |ns| &ns.gid_map)
+			.and_then(|id| id.try_into().ok())
+	}
+
+	/// Tells whether an agent of the namespace `self`, with the access profile `ap`, is
+	/// privileged in the namespace `target`.
+	pub fn is_privileged_in(&self, ap: &AccessProfile, target: &Self) -> bool {
+		let mut cur = Some(target);
+		while let Some(ns) = cur {
+			if ptr::eq(ns, self) {
+				return match &self.parent {
+					None => ap.is_privileged(),
+					Some(_) => ap.euid == self.owner || self.uid_from_global(ap.euid) == Some(0),
+				};
+			}
+			// The creator of a namespace is privileged in it from the parent namespace
+			let parent = ns.parent.as_deref();
+			if parent.is_some_and(|p| ptr::eq(p, self)) && ns.owner == ap.euid {
+				return true;
+			}
+			cur = parent;
+		}
+		false
+	}
+
+	/// Sets a mapping of the namespace.
+	///
+	/// Arguments:
+	/// - `map` is the new mapping
+	/// - `select` selects the mapping to set
+	/// - `id` is the user or group ID of the writer, in the initial namespace
+	/// - `writer` is the user namespace of the writer
+	/// - `ap` is the access profile of the writer
+	fn set_map(
+		&self,
+		map: IdMap,
+		select: MapSelector,
+		id: u32,
+		writer: &Self,
+		ap: &AccessProfile,
+	) -> EResult<()> {
+		let Some(parent) = &self.parent else {
+			return Err(errno!(EPERM));
+		};
+		// Only processes of the namespace or its parent can write the mapping
+		if !ptr::eq(writer, self) && !ptr::eq(writer, parent.as_ref()) {
+			return Err(errno!(EPERM));
+		}
+		// Outside IDs must be mapped in the parent namespace
+		let mapped = map.extents().iter().all(|e| {
+			parent.global_of(e.outside, select).is_some()
+				&& parent.global_of(e.outside + e.count - 1, select).is_some()
+		});
+		if !mapped {
+			return Err(errno!(EPERM));
+		}
+		// An unprivileged writer can only map its own ID
+		if !writer.is_privileged_in(ap, parent) {
+			let own = parent.local_of(id, select);
+			if !matches!(map.extents(), [e] if e.count == 1 && Some(e.outside) == own) {
+				return Err(errno!(EPERM));
+			}
+		}
+		// The mapping can be written only once
+		let mut cur = select(self).lock();
+		if cur.is_some() {
+			return Err(errno!(EPERM));
+		}
+		*cur = Some(map);
+		Ok(())
+	}
+
+	/// Sets the mapping of user IDs of the namespace.
+	///
+	/// `writer` and `ap` are the user namespace and access profile of the writer.
+	///
+	/// If the writer is not allowed to set the mapping, or if the mapping has already been set,
+	/// the function returns [`errno::EPERM`].
+	pub fn set_uid_map(&self, map: IdMap, writer: &Self, ap: &AccessProfile) -> EResult<()> {
+		self.set_map(map, |ns| &ns.uid_map, ap.euid as _, writer, ap)
+	}
+
+	/// Sets the mapping of group IDs of the namespace.
+	///
+	/// Arguments and errors are the same as [`Self::set_uid_map`].
+	pub fn set_gid_map(&self, map: IdMap, writer: &Self, ap: &AccessProfile) -> EResult<()> {
+		self.set_map(map, |ns| &ns.gid_map, ap.egid as _, writer, ap)
+	}
+}
+
+/// Initializes the initial user namespace.
+pub(crate) fn init() -> AllocResult<()> {
+	let ns = Arc::new(UserNamespace {
+		id: 1,
+		parent: None,
+		depth: 0,
+		owner: ROOT_UID,
+
+		uid_map: Mutex::new(None),
+		gid_map: Mutex::new(None),
+	})?;
+	unsafe {
+		OnceInit::init(&ROOT_USER_NS, ns);
+	}
+	Ok(())
+}
@@ -20,10 +20,14 @@
 //! and another writing, with a buffer in between.
 
 use crate::{
-	file::{fs::FileOps, wait_queue::WaitQueue, File, FileType, Stat},
+	file::{fs::FileOps, wait_queue, wait_queue::WaitQueue, File, FileType, Stat, O_NONBLOCK},
 	process::{mem_space::copy::SyscallPtr, signal::Signal, Process},
 	sync::mutex::Mutex,
-	syscall::{ioctl, FromSyscallArg},
+	syscall::{
+		ioctl,
+		poll::{POLLERR, POLLHUP, POLLIN, POLLOUT},
+		FromSyscallArg,
+	},
 };
 use core::{
 	ffi::{c_int, c_void},
@@ -107,11 +111,31 @@ impl FileOps for PipeBuffer {
 		if (inner.readers == 0) != (inner.writers == 0) {
 			self.rd_queue.wake_all();
 			self.wr_queue.wake_all();
+			wait_queue::poll_notify();
 		}
 	}
 
-	fn poll(&self, _file: &File, _mask: u32) -> EResult<u32> {
-		todo!()
+	fn poll(&self, file: &File, mask: u32) -> EResult<u32> {
+		let inner = self.inner.lock();
+		let mut events = 0;
+		if file.can_read() {
+			if inner.buffer.get_data_len() > 0 {
+				events |= POLLIN;
+			}
+			if inner.writers == 0 {
+				events |= POLLHUP;
+			}
+		}
+		if file.can_write() {
+			if inner.buffer.get_available_len() > 0 {
+				events |= POLLOUT;
+			}
+			if inner.readers == 0 {
+				events |= POLLERR;
+			}
+		}
+		// Errors are reported even if not requested
+		Ok(events & (mask | POLLHUP | POLLERR))
 	}
 
 	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
@@ -126,31 +150,34 @@ impl FileOps for PipeBuffer {
 		Ok(0)
 	}
 
-	fn read(&self, _file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
 		if unlikely(buf.is_empty()) {
 			return Ok(0);
 		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
 		let len = self.rd_queue.wait_until(|| {
 			let mut inner = self.inner.lock();
 			let len = inner.buffer.read(buf);
 			if len > 0 {
 				self.wr_queue.wake_next();
-				Some(len)
+				wait_queue::poll_notify();
+				Some(Ok(len))
+			} else if inner.writers == 0 {
+				Some(Ok(0))
+			} else if nonblock {
+				Some(Err(errno!(EAGAIN)))
 			} else {
-				if inner.writers == 0 {
-					return Some(0);
-				}
-				// TODO if O_NONBLOCK, return `EAGAIN`
 				None
 			}
-		})?;
+		})??;
 		Ok(len)
 	}
 
-	fn write(&self, _file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+	fn write(&self, file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
 		if unlikely(buf.is_empty()) {
 			return Ok(0);
 		}
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
 		let len = self.wr_queue.wait_until(|| {
 			let mut inner = self.inner.lock();
 			if inner.readers == 0 {
@@ -160,9 +187,11 @@ impl FileOps for PipeBuffer {
 			let len = inner.buffer.write(buf);
 			if len > 0 {
 				self.rd_queue.wake_next();
+				wait_queue::poll_notify();
 				Some(Ok(len))
+			} else if nonblock {
+				Some(Err(errno!(EAGAIN)))
 			} else {
-				// TODO if O_NONBLOCK, return `EAGAIN`
 				None
 			}
 		})??;
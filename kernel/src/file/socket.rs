@@ -19,28 +19,79 @@
 //! This file implements sockets.
 
 use crate::{
-	file::{fs::FileOps, wait_queue::WaitQueue, File, FileType, Stat},
-	net::{osi, SocketDesc},
+	file::{fs::FileOps, vfs::ResolutionSettings, File, FileType, Mode, Stat, O_NONBLOCK},
+	net::{
+		osi,
+		unix::{self, Bound, Channel, Key, Listener, UnixAddr},
+		SocketDesc, SocketDomain, SocketType,
+	},
+	process::mem_space::copy::SyscallPtr,
 	sync::mutex::Mutex,
-	syscall::ioctl::Request,
+	syscall::{
+		ioctl,
+		poll::{POLLERR, POLLHUP, POLLIN, POLLOUT},
+		FromSyscallArg,
+	},
 };
 use core::{
-	ffi::{c_int, c_void},
-	sync::{atomic, atomic::AtomicUsize},
+	ffi::{c_int, c_short, c_void},
+	sync::{
+		atomic,
+		atomic::{AtomicBool, AtomicI32, AtomicUsize, Ordering::Relaxed},
+	},
 };
 use utils::{
-	collections::{ring_buffer::RingBuffer, vec::Vec},
+	collections::vec::Vec,
 	errno,
 	errno::{AllocResult, EResult},
-	vec,
+	ptr::arc::Arc,
 };
 
-/// The maximum size of a socket's buffers.
-const BUFFER_SIZE: usize = 65536;
-
 /// Socket option level: Socket
 const SOL_SOCKET: c_int = 1;
 
+/// Socket option: allow reuse of local addresses.
+const SO_REUSEADDR: c_int = 2;
+/// Socket option: the type of the socket.
+const SO_TYPE: c_int = 3;
+/// Socket option: the pending error, cleared on read.
+const SO_ERROR: c_int = 4;
+/// Socket option: the size of the send buffer.
+const SO_SNDBUF: c_int = 7;
+/// Socket option: the size of the receive buffer.
+const SO_RCVBUF: c_int = 8;
+
+/// The minimum size of a socket buffer, in bytes.
+const SOCK_BUF_MIN: usize = 4096;
+/// The maximum size of a socket buffer, in bytes.
+const SOCK_BUF_MAX: usize = 4 * 1024 * 1024;
+
+/// The maximum value for the backlog of a listening socket.
+const SOMAXCONN: usize = 4096;
+
+/// Message flag: return data without removing it from the receive queue.
+pub const MSG_PEEK: c_int = 0x2;
+/// Message flag: the operation does not block, as if the socket were non-blocking.
+pub const MSG_DONTWAIT: c_int = 0x40;
+/// Message flag: do not send `SIGPIPE` if the peer has closed the connection.
+pub const MSG_NOSIGNAL: c_int = 0x4000;
+
+/// The connection state of a socket.
+#[derive(Debug)]
+enum State {
+	/// The socket is not connected.
+	Unconnected,
+	/// The socket is accepting connections.
+	Listening,
+	/// The socket is connected to a peer.
+	Connected {
+		/// The name of the peer.
+		peername: Vec<u8>,
+		/// For datagram sockets, the peer, which is the default destination.
+		peer: Option<Bound>,
+	},
+}
+
 /// A UNIX socket.
 #[derive(Debug)]
 pub struct Socket {
@@ -54,33 +105,96 @@ pub struct Socket {
 
 	/// The address the socket is bound to.
 	sockname: Mutex<Vec<u8>>,
+	/// The key of the name the socket is bound to, to release it on close.
+	key: Mutex<Option<Key>>,
+	/// The connection state.
+	state: Mutex<State>,
+	/// The pending error, or zero if none.
+	error: AtomicI32,
+	/// Tells whether `SO_REUSEADDR` is set.
+	reuse_addr: AtomicBool,
 
-	/// The buffer containing received data. If `None`, reception has been shutdown.
-	rx_buff: Mutex<Option<RingBuffer<u8, Vec<u8>>>>,
-	/// The buffer containing data to be transmitted. If `None`, transmission has been shutdown.
-	tx_buff: Mutex<Option<RingBuffer<u8, Vec<u8>>>>,
-
-	/// Receive wait queue.
-	rx_queue: WaitQueue,
-	/// Transmit wait queue.
-	tx_queue: WaitQueue,
+	/// The side of the socket accepting connections.
+	listener: Arc<Listener>,
+	/// The channel containing received data.
+	rx: Arc<Channel>,
+	/// The channel containing data to be transmitted. For connection-oriented sockets, the peer
+	/// receives from this channel.
+	tx: Arc<Channel>,
 }
 
 impl Socket {
 	/// Creates a new instance.
 	pub fn new(desc: SocketDesc) -> AllocResult<Self> {
+		let messages = desc.type_ != SocketType::SockStream;
+		let rx = Channel::new(messages)?;
+		let tx = Channel::new(messages)?;
+		Self::with_channels(desc, rx, tx, State::Unconnected)
+	}
+
+	/// Creates a pair of unnamed sockets connected to each other.
+	///
+	/// The sockets are cross-wired: data written on one is received on the other.
+	///
+	/// If the domain does not support pairs, the function returns [`errno::EOPNOTSUPP`].
+	pub fn pair(desc: SocketDesc) -> EResult<(Self, Self)> {
+		if desc.domain != SocketDomain::AfUnix || desc.type_ == SocketType::SockRaw {
+			return Err(errno!(EOPNOTSUPP));
+		}
+		let messages = desc.type_ != SocketType::SockStream;
+		let ch0 = Channel::new(messages)?;
+		let ch1 = Channel::new(messages)?;
+		let unnamed = (desc.domain.get_id() as c_short).to_ne_bytes();
+		let desc1 = SocketDesc {
+			domain: desc.domain,
+			type_: desc.type_,
+			protocol: desc.protocol,
+		};
+		let sock0 = Self::with_channels(desc, ch0.clone(), ch1.clone(), State::Unconnected)?;
+		let sock1 = Self::with_channels(desc1, ch1, ch0, State::Unconnected)?;
+		// Datagram sockets send to the receive channel of their peer
+		let peer = |sock: &Self| -> AllocResult<_> {
+			if sock.desc.type_.is_stream() {
+				return Ok(None);
+			}
+			Ok(Some(Bound {
+				type_: sock.desc.type_,
+				name: Arc::new(Vec::try_from(unnamed.as_slice())?)?,
+				listener: sock.listener.clone(),
+				rx: sock.rx.clone(),
+			}))
+		};
+		for (sock, peer) in [(&sock0, peer(&sock1)?), (&sock1, peer(&sock0)?)] {
+			*sock.sockname.lock() = Vec::try_from(unnamed.as_slice())?;
+			*sock.state.lock() = State::Connected {
+				peername: Vec::try_from(unnamed.as_slice())?,
+				peer,
+			};
+		}
+		Ok((sock0, sock1))
+	}
+
+	/// Creates a new instance, receiving from `rx` and transmitting to `tx`.
+	fn with_channels(
+		desc: SocketDesc,
+		rx: Arc<Channel>,
+		tx: Arc<Channel>,
+		state: State,
+	) -> AllocResult<Self> {
 		Ok(Self {
 			desc,
 			stack: None,
 			open_count: AtomicUsize::new(0),
 
 			sockname: Default::default(),
+			key: Default::default(),
+			state: Mutex::new(state),
+			error: AtomicI32::new(0),
+			reuse_addr: AtomicBool::new(false),
 
-			rx_buff: Mutex::new(Some(RingBuffer::new(vec![0; BUFFER_SIZE]?))),
-			tx_buff: Mutex::new(Some(RingBuffer::new(vec![0; BUFFER_SIZE]?))),
-
-			rx_queue: WaitQueue::new(),
-			tx_queue: WaitQueue::new(),
+			listener: Listener::new()?,
+			rx,
+			tx,
 		})
 	}
 
@@ -96,14 +210,35 @@ impl Socket {
 		self.stack.as_ref()
 	}
 
+	/// Returns an error if the socket's domain is not supported for communication.
+	fn check_domain(&self) -> EResult<()> {
+		match self.desc.domain {
+			SocketDomain::AfUnix => Ok(()),
+			// TODO support other domains
+			_ => Err(errno!(EOPNOTSUPP)),
+		}
+	}
+
 	/// Reads the given socket option.
 	///
 	/// Arguments:
 	/// - `level` is the level (protocol) at which the option is located.
 	/// - `optname` is the name of the option.
-	pub fn get_opt(&self, _level: c_int, _optname: c_int) -> EResult<&[u8]> {
-		// TODO
-		todo!()
+	///
+	/// If the option does not exist, the function returns [`errno::ENOPROTOOPT`].
+	pub fn get_opt(&self, level: c_int, optname: c_int) -> EResult<c_int> {
+		if level != SOL_SOCKET {
+			return Err(errno!(ENOPROTOOPT));
+		}
+		let val = match optname {
+			SO_REUSEADDR => self.reuse_addr.load(Relaxed) as _,
+			SO_TYPE => self.desc.type_.get_id() as _,
+			SO_ERROR => self.error.swap(0, Relaxed),
+			SO_SNDBUF => self.tx.size() as _,
+			SO_RCVBUF => self.rx.size() as _,
+			_ => return Err(errno!(ENOPROTOOPT)),
+		};
+		Ok(val)
 	}
 
 	/// Writes the given socket option.
@@ -113,9 +248,30 @@ impl Socket {
 	/// - `optname` is the name of the option.
 	/// - `optval` is the value of the option.
 	///
+	/// If `optval` is too small for the option, the function returns [`errno::EINVAL`]. If the
+	/// option does not exist or is read-only, the function returns [`errno::ENOPROTOOPT`].
+	///
 	/// The function returns a value to be returned by the syscall on success.
-	pub fn set_opt(&self, _level: c_int, _optname: c_int, _optval: &[u8]) -> EResult<c_int> {
-		// TODO
+	pub fn set_opt(&self, level: c_int, optname: c_int, optval: &[u8]) -> EResult<c_int> {
+		if level != SOL_SOCKET {
+			return Err(errno!(ENOPROTOOPT));
+		}
+		let val = optval
+			.first_chunk()
+			.map(|b| c_int::from_ne_bytes(*b))
+			.ok_or_else(|| errno!(EINVAL))?;
+		// Like Linux, the size of buffers is doubled to account for bookkeeping overhead
+		let buf_size = || {
+			(val.max(0) as usize)
+				.saturating_mul(2)
+				.clamp(SOCK_BUF_MIN, SOCK_BUF_MAX)
+		};
+		match optname {
+			SO_REUSEADDR => self.reuse_addr.store(val != 0, Relaxed),
+			SO_SNDBUF => self.tx.resize(buf_size())?,
+			SO_RCVBUF => self.rx.resize(buf_size())?,
+			_ => return Err(errno!(ENOPROTOOPT)),
+		}
 		Ok(0)
 	}
 
@@ -124,33 +280,222 @@ impl Socket {
 		&self.sockname
 	}
 
+	/// Returns the name of the peer the socket is connected to.
+	///
+	/// If the socket is not connected, the function returns [`errno::ENOTCONN`].
+	pub fn get_peername(&self) -> EResult<Vec<u8>> {
+		match &*self.state.lock() {
+			State::Connected {
+				peername, ..
+			} => Ok(Vec::try_from(peername.as_slice())?),
+			_ => Err(errno!(ENOTCONN)),
+		}
+	}
+
 	/// Binds the socket to the given address.
 	///
-	/// `sockaddr` is the new socket name.
+	/// Arguments:
+	/// - `sockaddr` is the new socket name.
+	/// - `rs` is the resolution settings, to create the socket file for `AF_UNIX` sockets
+	/// - `umask` is the umask to apply to the mode of the socket file
 	///
 	/// If the socket is already bound, or if the address is invalid, or if the address is already
 	/// in used, the function returns an error.
-	pub fn bind(&self, sockaddr: &[u8]) -> EResult<()> {
+	pub fn bind(&self, sockaddr: &[u8], rs: &ResolutionSettings, umask: Mode) -> EResult<()> {
 		let mut sockname = self.sockname.lock();
 		if !sockname.is_empty() {
 			return Err(errno!(EINVAL));
 		}
-		// TODO check if address is already in used (EADDRINUSE)
-		// TODO check the requested network interface exists (EADDRNOTAVAIL)
-		// TODO check address against stack's domain
+		if self.desc.domain != SocketDomain::AfUnix {
+			// TODO check if address is already in used (EADDRINUSE)
+			// TODO check the requested network interface exists (EADDRNOTAVAIL)
+			// TODO check address against stack's domain
+			*sockname = Vec::try_from(sockaddr)?;
+			return Ok(());
+		}
+		let (addr, name) = UnixAddr::parse(sockaddr)?;
+		let bound = Bound {
+			type_: self.desc.type_,
+			name: Arc::new(Vec::try_from(name.as_slice())?)?,
+			listener: self.listener.clone(),
+			rx: self.rx.clone(),
+		};
+		let key = unix::bind(&addr, bound, rs, 0o777 & !umask)?;
+		*self.key.lock() = Some(key);
+		*sockname = name;
+		Ok(())
+	}
 
-		*sockname = Vec::try_from(sockaddr)?;
+	/// Returns the socket bound to `sockaddr`, to communicate with it.
+	///
+	/// If the socket has a different type, the function returns [`errno::EPROTOTYPE`].
+	pub fn resolve(&self, sockaddr: &[u8], rs: &ResolutionSettings) -> EResult<Bound> {
+		self.check_domain()?;
+		let (addr, _) = UnixAddr::parse(sockaddr)?;
+		let bound = unix::lookup(&addr, rs)?;
+		if bound.type_ != self.desc.type_ {
+			return Err(errno!(EPROTOTYPE));
+		}
+		Ok(bound)
+	}
+
+	/// Makes the socket accept connections, with at most `backlog` connections waiting to be
+	/// accepted.
+	pub fn listen(&self, backlog: c_int) -> EResult<()> {
+		self.check_domain()?;
+		if !self.desc.type_.is_stream() {
+			return Err(errno!(EOPNOTSUPP));
+		}
+		// TODO bind to an autogenerated name instead
+		if self.key.lock().is_none() {
+			return Err(errno!(EINVAL));
+		}
+		let mut state = self.state.lock();
+		if matches!(*state, State::Connected { .. }) {
+			return Err(errno!(EINVAL));
+		}
+		*state = State::Listening;
+		// A negative backlog is interpreted as the maximum
+		let backlog = usize::try_from(backlog).unwrap_or(SOMAXCONN);
+		self.listener.listen(backlog.min(SOMAXCONN));
+		Ok(())
+	}
+
+	/// Accepts a connection, waiting for one if none is pending.
+	///
+	/// If `nonblock` is set and no connection is pending, the function returns
+	/// [`errno::EAGAIN`].
+	///
+	/// The function returns the socket connected to the peer.
+	pub fn accept(&self, nonblock: bool) -> EResult<Arc<Socket>> {
+		if !matches!(*self.state.lock(), State::Listening) {
+			return Err(errno!(EINVAL));
+		}
+		self.listener.accept(nonblock)
+	}
+
+	/// Connects the socket to the socket `peer`.
+	///
+	/// For connection-oriented sockets, if `nonblock` is set and the peer cannot accept more
+	/// connections, the function returns [`errno::EAGAIN`].
+	///
+	/// For datagram sockets, the peer becomes the default destination.
+	pub fn connect(&self, peer: Bound, nonblock: bool) -> EResult<()> {
+		if !self.desc.type_.is_stream() {
+			*self.state.lock() = State::Connected {
+				peername: Vec::try_from(peer.name.as_slice())?,
+				peer: Some(peer),
+			};
+			return Ok(());
+		}
+		match *self.state.lock() {
+			State::Unconnected => {}
+			State::Listening => return Err(errno!(EINVAL)),
+			State::Connected {
+				..
+			} => return Err(errno!(EISCONN)),
+		}
+		// The accepted socket shares the channels of the current socket, cross-wired
+		let accepted = Socket::with_channels(
+			SocketDesc {
+				domain: self.desc.domain,
+				type_: self.desc.type_,
+				protocol: self.desc.protocol,
+			},
+			self.tx.clone(),
+			self.rx.clone(),
+			State::Connected {
+				peername: Vec::try_from(self.sockname.lock().as_slice())?,
+				peer: None,
+			},
+		)?;
+		*accepted.sockname.lock() = Vec::try_from(peer.name.as_slice())?;
+		peer.listener.connect(Arc::new(accepted)?, nonblock)?;
+		*self.state.lock() = State::Connected {
+			peername: Vec::try_from(peer.name.as_slice())?,
+			peer: None,
+		};
 		Ok(())
 	}
 
+	/// Sends the data in `buf`.
+	///
+	/// Arguments:
+	/// - `dest` is the destination. If `None`, the data is sent to the peer the socket is
+	///   connected to
+	/// - `flags` is the set of `MSG_*` flags
+	/// - `nonblock` tells whether the socket is non-blocking
+	///
+	/// The function returns the number of bytes sent.
+	pub fn send(
+		&self,
+		buf: &[u8],
+		dest: Option<Bound>,
+		flags: c_int,
+		nonblock: bool,
+	) -> EResult<usize> {
+		self.check_domain()?;
+		let nonblock = nonblock || flags & MSG_DONTWAIT != 0;
+		let state = self.state.lock();
+		if self.desc.type_.is_stream() {
+			let connected = matches!(*state, State::Connected { .. });
+			drop(state);
+			return match (connected, dest) {
+				(true, None) => self.tx.write(buf, &[], nonblock, flags & MSG_NOSIGNAL != 0),
+				(true, Some(_)) => Err(errno!(EISCONN)),
+				(false, _) => Err(errno!(ENOTCONN)),
+			};
+		}
+		let dest = match (dest, &*state) {
+			(Some(dest), _) => dest,
+			(
+				None,
+				State::Connected {
+					peer: Some(peer), ..
+				},
+			) => peer.clone(),
+			(None, _) => return Err(errno!(EDESTADDRREQ)),
+		};
+		drop(state);
+		let sockname = Vec::try_from(self.sockname.lock().as_slice())?;
+		dest.rx.write(buf, &sockname, nonblock, true)
+	}
+
+	/// Receives data into `buf`.
+	///
+	/// Arguments:
+	/// - `flags` is the set of `MSG_*` flags
+	/// - `nonblock` tells whether the socket is non-blocking
+	///
+	/// The function returns the number of bytes received and, for datagram sockets, the name of
+	/// the sender.
+	pub fn recv(&self, buf: &mut [u8], flags: c_int, nonblock: bool) -> EResult<(usize, Vec<u8>)> {
+		self.check_domain()?;
+		let nonblock = nonblock || flags & MSG_DONTWAIT != 0;
+		if self.desc.type_.is_stream() && !matches!(*self.state.lock(), State::Connected { .. }) {
+			return Err(errno!(EINVAL));
+		}
+		self.rx.read(buf, flags & MSG_PEEK != 0, nonblock)
+	}
+
 	/// Shuts down the reception side of the socket.
 	pub fn shutdown_reception(&self) {
-		*self.rx_buff.lock() = None;
+		self.rx.shutdown_read();
 	}
 
 	/// Shuts down the transmit side of the socket.
 	pub fn shutdown_transmit(&self) {
-		*self.tx_buff.lock() = None;
+		self.tx.shutdown_write();
+	}
+
+	/// Closes the socket, releasing its name and shutting down its connections.
+	fn close(&self) {
+		if let Some(key) = self.key.lock().take() {
+			unix::unbind(&key, &self.listener);
+		}
+		self.listener.close();
+		self.shutdown_reception();
+		self.shutdown_transmit();
 	}
 }
 
@@ -168,31 +513,77 @@ impl FileOps for Socket {
 
 	fn release(&self, _file: &File) {
 		let cnt = self.open_count.fetch_sub(1, atomic::Ordering::Release);
-		if cnt == 0 {
-			// TODO close the socket
+		if cnt == 1 {
+			self.close();
 		}
 	}
 
-	fn poll(&self, _file: &File, _mask: u32) -> EResult<u32> {
-		todo!()
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let state = self.state.lock();
+		let mut events = 0;
+		match &*state {
+			State::Listening => {
+				if self.listener.has_pending() {
+					events |= POLLIN;
+				}
+			}
+			_ if !self.desc.type_.is_stream() => {
+				if self.rx.is_readable() {
+					events |= POLLIN;
+				}
+				let writable = match &*state {
+					State::Connected {
+						peer: Some(peer), ..
+					} => peer.rx.is_writable(),
+					_ => true,
+				};
+				if writable {
+					events |= POLLOUT;
+				}
+			}
+			State::Connected {
+				..
+			} => {
+				if self.rx.is_readable() {
+					events |= POLLIN;
+				}
+				if self.tx.is_writable() {
+					events |= POLLOUT;
+				}
+				if self.tx.is_rd_shutdown() {
+					events |= POLLERR;
+					if self.rx.is_wr_shutdown() {
+						events |= POLLHUP;
+					}
+				}
+			}
+			// A connection-oriented socket is closed until connected
+			State::Unconnected => events |= POLLHUP,
+		}
+		// Errors are reported even if not requested
+		Ok(events & (mask | POLLHUP | POLLERR))
 	}
 
-	fn ioctl(&self, _file: &File, _request: Request, _argp: *const c_void) -> EResult<u32> {
-		todo!()
+	fn ioctl(&self, _file: &File, request: ioctl::Request, argp: *const c_void) -> EResult<u32> {
+		match request.get_old_format() {
+			ioctl::FIONREAD => {
+				let len = self.rx.data_len() as c_int;
+				let count_ptr = SyscallPtr::from_ptr(argp as usize);
+				count_ptr.copy_to_user(&len)?;
+			}
+			_ => return Err(errno!(ENOTTY)),
+		}
+		Ok(0)
 	}
 
-	fn read(&self, _file: &File, _off: u64, _buf: &mut [u8]) -> EResult<usize> {
-		if !self.desc.type_.is_stream() {
-			// TODO error
-		}
-		todo!()
+	fn read(&self, file: &File, _off: u64, buf: &mut [u8]) -> EResult<usize> {
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		let (len, _) = self.recv(buf, 0, nonblock)?;
+		Ok(len)
 	}
 
-	fn write(&self, _file: &File, _off: u64, _buf: &[u8]) -> EResult<usize> {
-		// A destination address is required
-		let Some(_stack) = self.stack.as_ref() else {
-			return Err(errno!(EDESTADDRREQ));
-		};
-		todo!()
+	fn write(&self, file: &File, _off: u64, buf: &[u8]) -> EResult<usize> {
+		let nonblock = file.get_flags() & O_NONBLOCK != 0;
+		self.send(buf, None, 0, nonblock)
 	}
 }
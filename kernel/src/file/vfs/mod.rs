@@ -38,16 +38,16 @@ use super::{
 	perm::{AccessProfile, S_ISVTX},
 	FileType, Stat,
 };
-use crate::{
-	file::fs::StatSet,
-	process::Process,
-	sync::{mutex::Mutex, once::OnceInit},
-};
+use crate::{file::fs::StatSet, process::Process, sync::mutex::Mutex};
 use core::{
 	borrow::Borrow,
 	hash::{Hash, Hasher},
 	intrinsics::unlikely,
-	sync::atomic::Ordering::Release,
+	ptr,
+	sync::atomic::{
+		AtomicUsize,
+		Ordering::{Relaxed, Release},
+	},
 };
 use node::Node;
 use utils::{
@@ -62,7 +62,7 @@ use utils::{
 	limits::{LINK_MAX, PATH_MAX, SYMLOOP_MAX},
 	list, list_type,
 	ptr::arc::Arc,
-	vec,
+	vec, TryClone,
 };
 
 /// A child of a VFS entry.
@@ -91,6 +91,13 @@ impl Hash for EntryChild {
 	}
 }
 
+/// Tells whether `ent` is the entry cached in `children` for its name.
+fn is_cached(children: &HashSet<EntryChild>, ent: &Arc<Entry>) -> bool {
+	children
+		.get(ent.name.as_bytes())
+		.is_some_and(|child| ptr::eq(Arc::as_ptr(&child.0), Arc::as_ptr(ent)))
+}
+
 /// A VFS entry, representing a directory entry cached in memory.
 ///
 /// An entry can be negative. That is, represent a non-existent file.
@@ -158,15 +165,20 @@ impl Entry {
 		FileType::from_mode(self.stat().mode).ok_or_else(|| errno!(EUCLEAN))
 	}
 
-	/// Returns the absolute path to reach the entry.
+	/// Returns the absolute path to reach the entry, from the root of the VFS.
 	pub fn get_path(this: &Arc<Self>) -> EResult<PathBuf> {
-		if this.parent.is_none() {
+		let root = ROOT.lock().clone();
+		let is_root = |ent: &Arc<Self>| {
+			root.as_ref()
+				.is_some_and(|root| ptr::eq(Arc::as_ptr(root), Arc::as_ptr(ent)))
+		};
+		if this.parent.is_none() || is_root(this) {
 			return Ok(PathBuf::root()?);
 		}
 		let mut buf = vec![0u8; PATH_MAX]?;
 		let mut off = PATH_MAX;
 		let mut cur = this;
-		while let Some(parent) = &cur.parent {
+		while let Some(parent) = cur.parent.as_ref().filter(|_| !is_root(cur)) {
 			let len = cur.name.len();
 			off = off
 				.checked_sub(len + 1)
@@ -201,22 +213,25 @@ impl Entry {
 		};
 		// Lock now to avoid a race condition with `strong_count`
 		let mut parent_children = parent.children.lock();
-		/*
-		 * If this is **not** the last reference to the entry, we cannot remove it.
-		 *
-		 * The reference held by its own parent + the one held by the LRU + the one that we
-		 * hold here = 3
-		 *
-		 * We cannot release an entry with at least one cached child. Fortunately, a child
-		 * entry refers to its parent, so the condition below is sufficient.
-		 */
-		if Arc::strong_count(&this) > 3 {
-			return Ok(());
-		}
-		// Remove other references
-		parent_children.remove(&*this.name);
-		unsafe {
-			LRU.lock().remove(&this);
+		// The entry might have been invalidated from the cache already
+		if is_cached(&parent_children, &this) {
+			/*
+			 * If this is **not** the last reference to the entry, we cannot remove it.
+			 *
+			 * The reference held by its own parent + the one held by the LRU + the one that we
+			 * hold here = 3
+			 *
+			 * We cannot release an entry with at least one cached child. Fortunately, a child
+			 * entry refers to its parent, so the condition below is sufficient.
+			 */
+			if Arc::strong_count(&this) > 3 {
+				return Ok(());
+			}
+			// Remove other references
+			parent_children.remove(&*this.name);
+			unsafe {
+				LRU.lock().remove(&this);
+			}
 		}
 		// If other references remain, we cannot go further
 		let Some(entry) = Arc::into_inner(this) else {
@@ -234,6 +249,35 @@ impl Entry {
 /// Directory entries LRU.
 static LRU: Mutex<list_type!(Entry, lru)> = Mutex::new(list!(Entry, lru));
 
+/// The number of entries lookups that have been served from the cache.
+static CACHE_HITS: AtomicUsize = AtomicUsize::new(0);
+/// The number of entries lookups that required to query the filesystem.
+static CACHE_MISSES: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of hits and misses of the directory entries cache, in this order.
+pub fn cache_stats() -> (usize, usize) {
+	(CACHE_HITS.load(Relaxed), CACHE_MISSES.load(Relaxed))
+}
+
+/// Removes the entry with the given `name` from the children of `parent` and from the LRU, if
+/// present.
+///
+/// If this was the last reference to the entry, the underlying node is released, removing it
+/// from the filesystem if no link remain.
+fn invalidate(parent: &Entry, name: &[u8]) -> EResult<()> {
+	let Some(EntryChild(ent)) = parent.children.lock().remove(name) else {
+		return Ok(());
+	};
+	// An entry is in the LRU as long as it is a child of its parent
+	unsafe {
+		LRU.lock().remove(&ent);
+	}
+	if let Some(node) = Arc::into_inner(ent).and_then(|ent| ent.node) {
+		Node::release(node)?;
+	}
+	Ok(())
+}
+
 /// Attempts to shrink the directory entries cache.
 ///
 /// If the cache cannot shrink, the function returns `false`.
@@ -247,10 +291,14 @@ pub fn shrink_entries() -> bool {
 			continue;
 		};
 		let mut parent_children = parent.children.lock();
-		if Arc::strong_count(&entry) > 3 {
+		// If the entry has been replaced in its parent, only the LRU holds it
+		let cached = is_cached(&parent_children, &entry);
+		if Arc::strong_count(&entry) > if cached { 3 } else { 2 } {
 			continue;
 		}
-		parent_children.remove(&*entry.name);
+		if cached {
+			parent_children.remove(&*entry.name);
+		}
 		cursor.remove();
 		let Some(entry) = Arc::into_inner(entry) else {
 			continue;
@@ -266,7 +314,21 @@ pub fn shrink_entries() -> bool {
 }
 
 /// The root entry of the VFS.
-pub static ROOT: OnceInit<Arc<Entry>> = unsafe { OnceInit::new() };
+///
+/// It is set when files management is initialized, and can be changed by `pivot_root`.
+static ROOT: Mutex<Option<Arc<Entry>>> = Mutex::new(None);
+
+/// Returns the root entry of the VFS.
+///
+/// If files management is not initialized, the function panics.
+pub fn root() -> Arc<Entry> {
+	ROOT.lock().clone().expect("VFS root is not initialized")
+}
+
+/// Sets the root entry of the VFS.
+pub fn set_root(root: Arc<Entry>) {
+	*ROOT.lock() = Some(root);
+}
 
 /// Settings for a path resolution operation.
 #[derive(Clone, Debug)]
@@ -289,19 +351,38 @@ pub struct ResolutionSettings {
 	/// If `true` and if the last component of the path is a symbolic link, path resolution
 	/// follows it.
 	pub follow_link: bool,
+
+	/// If `true`, the resolution fails with [`errno::EXDEV`] instead of leaving `root`, be it
+	/// through an absolute path, an absolute symbolic link, or a `..` component.
+	pub beneath: bool,
+	/// If `true`, the resolution fails with [`errno::ELOOP`] on any symbolic link it would
+	/// follow.
+	pub no_symlinks: bool,
+	/// If `true`, the resolution fails with [`errno::ELOOP`] on any magic link it would follow
+	/// (see [`NodeOps::follow_link`]).
+	///
+	/// [`NodeOps::follow_link`]: crate::file::fs::NodeOps::follow_link
+	pub no_magiclinks: bool,
+	/// If `true`, the resolution fails with [`errno::EXDEV`] if it would cross a mountpoint.
+	pub no_xdev: bool,
 }
 
 impl ResolutionSettings {
 	/// Kernel access, following symbolic links.
 	pub fn kernel_follow() -> Self {
 		Self {
-			root: ROOT.clone(),
+			root: root(),
 			cwd: None,
 
 			access_profile: AccessProfile::KERNEL,
 
 			create: false,
 			follow_link: true,
+
+			beneath: false,
+			no_symlinks: false,
+			no_magiclinks: false,
+			no_xdev: false,
 		}
 	}
 
@@ -326,6 +407,11 @@ impl ResolutionSettings {
 
 			create: false,
 			follow_link,
+
+			beneath: false,
+			no_symlinks: false,
+			no_magiclinks: false,
+			no_xdev: false,
 		}
 	}
 }
@@ -356,14 +442,25 @@ fn resolve_entry(lookup_dir: &Arc<Entry>, name: &[u8]) -> EResult<Arc<Entry>> {
 	// Try to get from cache first
 	if let Some(ent) = children.get(name) {
 		let ent = ent.0.clone();
-		drop(children);
-		// Promote the entry in the LRU
+		let node = lookup_dir.node();
+		if node.node_ops.revalidate_entry(node, &ent) {
+			drop(children);
+			CACHE_HITS.fetch_add(1, Relaxed);
+			// Promote the entry in the LRU
+			unsafe {
+				LRU.lock().lru_promote(&ent);
+			}
+			return Ok(ent);
+		}
+		// The entry is stale: the filesystem already dropped the file, so only the cache is
+		// released
+		children.remove(name);
 		unsafe {
-			LRU.lock().lru_promote(&ent);
+			LRU.lock().remove(&ent);
 		}
-		return Ok(ent);
 	}
 	// Not in cache. Try to get from the filesystem
+	CACHE_MISSES.fetch_add(1, Relaxed);
 	let mut entry = Entry::new(String::try_from(name)?, Some(lookup_dir.clone()), None);
 	lookup_dir
 		.node()
@@ -380,31 +477,37 @@ fn resolve_entry(lookup_dir: &Arc<Entry>, name: &[u8]) -> EResult<Arc<Entry>> {
 /// Resolves the symbolic link `link` and returns the target.
 ///
 /// Arguments:
-/// - `root` is the root directory
+/// - `settings` is the settings of the resolution in which the link is encountered
 /// - `lookup_dir` is the directory from which the resolution of the target starts
-/// - `access_profile` is the access profile used for resolution
 /// - `symlink_rec` is the number of recursions so far
 ///
 /// Symbolic links are followed recursively, including the last element of the target path.
+/// Magic links directly resolve to their target node.
 fn resolve_link(
 	link: Arc<Entry>,
-	root: Arc<Entry>,
+	settings: &ResolutionSettings,
 	lookup_dir: Arc<Entry>,
-	access_profile: AccessProfile,
 	symlink_rec: usize,
 ) -> EResult<Arc<Entry>> {
 	// If too many recursions occur, error
-	if unlikely(symlink_rec + 1 > SYMLOOP_MAX) {
+	if unlikely(symlink_rec + 1 > SYMLOOP_MAX || settings.no_symlinks) {
 		return Err(errno!(ELOOP));
 	}
-	let target = link.node().readlink()?;
+	let node = link.node();
+	if let Some(target) = node.node_ops.follow_link(node)? {
+		if unlikely(settings.no_magiclinks) {
+			return Err(errno!(ELOOP));
+		}
+		let ent = Entry::new(link.name.try_clone()?, link.parent.clone(), Some(target));
+		return Ok(Arc::new(ent)?);
+	}
+	let target = node.readlink()?;
 	// Resolve link
 	let rs = ResolutionSettings {
-		root,
 		cwd: Some(lookup_dir),
-		access_profile,
 		create: false,
 		follow_link: true,
+		..settings.clone()
 	};
 	let resolved = resolve_path_impl(&target, &rs, symlink_rec + 1)?;
 	let Resolved::Found(target) = resolved else {
@@ -414,6 +517,36 @@ fn resolve_link(
 	Ok(target)
 }
 
+/// Returns the parent of `dir` for a `..` component, in the resolution with `settings`.
+///
+/// The parent of the root of the resolution is itself, unless [`ResolutionSettings::beneath`] is
+/// set, in which case the function returns [`errno::EXDEV`].
+fn parent_dir(settings: &ResolutionSettings, dir: Arc<Entry>) -> EResult<Arc<Entry>> {
+	if ptr::eq(Arc::as_ptr(&dir), Arc::as_ptr(&settings.root)) {
+		if unlikely(settings.beneath) {
+			return Err(errno!(EXDEV));
+		}
+		return Ok(dir);
+	}
+	let parent = dir.parent.clone().unwrap_or_else(|| dir.clone());
+	check_xdev(settings, &dir, &parent)?;
+	Ok(parent)
+}
+
+/// If [`ResolutionSettings::no_xdev`] is set, checks that moving from the entry `from` to `to`
+/// does not cross a mountpoint.
+///
+/// If it does, the function returns [`errno::EXDEV`].
+fn check_xdev(settings: &ResolutionSettings, from: &Entry, to: &Entry) -> EResult<()> {
+	let (Some(from), Some(to)) = (&from.node, &to.node) else {
+		return Ok(());
+	};
+	if unlikely(settings.no_xdev && !ptr::eq(Arc::as_ptr(&from.fs), Arc::as_ptr(&to.fs))) {
+		return Err(errno!(EXDEV));
+	}
+	Ok(())
+}
+
 /// Implementation of [`resolve_path`].
 ///
 /// `symlink_rec` is the number of recursions due to symbolic links resolution.
@@ -425,8 +558,12 @@ fn resolve_path_impl<'p>(
 	// Get start lookup directory
 	let mut lookup_dir = match (path.is_absolute(), &settings.cwd) {
 		(false, Some(start)) => start.clone(),
+		(true, _) if settings.beneath => return Err(errno!(EXDEV)),
 		_ => settings.root.clone(),
 	};
+	if let Some(cwd) = &settings.cwd {
+		check_xdev(settings, cwd, &lookup_dir)?;
+	}
 	let mut components = path.components();
 	let Some(final_component) = components.next_back() else {
 		return Ok(Resolved::Found(lookup_dir));
@@ -444,9 +581,7 @@ fn resolve_path_impl<'p>(
 		// Get the name of the next entry
 		let name = match comp {
 			Component::ParentDir => {
-				if let Some(parent) = &lookup_dir.parent {
-					lookup_dir = parent.clone();
-				}
+				lookup_dir = parent_dir(settings, lookup_dir)?;
 				continue;
 			}
 			Component::Normal(name) => name,
@@ -458,16 +593,13 @@ fn resolve_path_impl<'p>(
 		if entry.is_negative() {
 			return Err(errno!(ENOENT));
 		}
+		check_xdev(settings, &lookup_dir, &entry)?;
 		match entry.get_type()? {
 			FileType::Directory => lookup_dir = entry,
 			FileType::Link => {
-				lookup_dir = resolve_link(
-					entry,
-					settings.root.clone(),
-					lookup_dir,
-					settings.access_profile,
-					symlink_rec,
-				)?;
+				let target = resolve_link(entry, settings, lookup_dir.clone(), symlink_rec)?;
+				check_xdev(settings, &lookup_dir, &target)?;
+				lookup_dir = target;
 			}
 			_ => return Err(errno!(ENOTDIR)),
 		}
@@ -479,12 +611,7 @@ fn resolve_path_impl<'p>(
 			// be the root. If the component is `CurDir`, the `lookup_dir` is the target
 			return Ok(Resolved::Found(lookup_dir));
 		}
-		Component::ParentDir => {
-			if let Some(parent) = &lookup_dir.parent {
-				lookup_dir = parent.clone();
-			}
-			return Ok(Resolved::Found(lookup_dir));
-		}
+		Component::ParentDir => return Ok(Resolved::Found(parent_dir(settings, lookup_dir)?)),
 		Component::Normal(name) => name,
 	};
 	// Check lookup permission
@@ -508,15 +635,12 @@ fn resolve_path_impl<'p>(
 			Err(errno!(ENOENT))
 		};
 	}
+	check_xdev(settings, &lookup_dir, &entry)?;
 	// Resolve symbolic link if necessary
 	if settings.follow_link && entry.get_type()? == FileType::Link {
-		Ok(Resolved::Found(resolve_link(
-			entry,
-			settings.root.clone(),
-			lookup_dir,
-			settings.access_profile,
-			symlink_rec,
-		)?))
+		let target = resolve_link(entry, settings, lookup_dir.clone(), symlink_rec)?;
+		check_xdev(settings, &lookup_dir, &target)?;
+		Ok(Resolved::Found(target))
 	} else {
 		Ok(Resolved::Found(entry))
 	}
@@ -732,6 +856,9 @@ pub fn unlink(entry: &Entry, ap: &AccessProfile) -> EResult<()> {
 	dir_node.node_ops.unlink(dir_node, entry)?;
 	// Remove link from cache
 	let EntryChild(ent) = children.remove(entry.name.as_bytes()).unwrap();
+	unsafe {
+		LRU.lock().remove(&ent);
+	}
 	// Drop to avoid deadlock
 	drop(children);
 	Entry::release(ent)?;
@@ -816,6 +943,18 @@ pub fn rename(
 	if mountpoint::from_entry(&old).is_some() {
 		return Err(errno!(EBUSY));
 	}
+	// Cannot move a directory into one of its own descendants
+	let mut cur = Some(&new_parent);
+	while let Some(ent) = cur {
+		if ent
+			.node
+			.as_ref()
+			.is_some_and(|n| Arc::as_ptr(n) == Arc::as_ptr(old.node()))
+		{
+			return Err(errno!(EINVAL));
+		}
+		cur = ent.parent.as_ref();
+	}
 	// Check permissions on `old`
 	let old_parent_stat = old_parent.stat();
 	if !ap.can_write_directory(&old_parent_stat) {
@@ -846,8 +985,9 @@ pub fn rename(
 	}
 	// Perform rename
 	old.node().node_ops.rename(&old, &new_parent, new_name)?;
-	// Invalidate cache
-	old_parent.children.lock().remove(&*old.name);
-	new_parent.children.lock().remove(new_name);
+	// Invalidate cache. If the destination has been replaced, this releases it
+	drop(new);
+	invalidate(old_parent, &old.name)?;
+	invalidate(&new_parent, new_name)?;
 	Ok(())
 }
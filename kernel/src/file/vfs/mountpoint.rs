@@ -23,18 +23,23 @@ use crate::{
 	file::{
 		fs,
 		fs::{Filesystem, FilesystemType},
+		perm::UserNamespace,
 		vfs,
-		vfs::{EntryChild, ResolutionSettings},
+		vfs::ResolutionSettings,
 		FileType,
 	},
-	sync::mutex::Mutex,
+	sync::{mutex::Mutex, once::OnceInit, rcu::RcuHashMap},
+};
+use core::{
+	fmt, ptr,
+	sync::atomic::{AtomicU32, Ordering::Relaxed},
 };
-use core::fmt;
 use utils::{
 	collections::{
 		hashmap::HashMap,
 		path::{Path, PathBuf},
 		string::String,
+		vec::Vec,
 	},
 	errno,
 	errno::{AllocResult, EResult, ENOENT},
@@ -173,6 +178,21 @@ fn get_fs(
 	}
 }
 
+/// The propagation type of a mountpoint, telling how mount and unmount events are shared with
+/// the copies of the mountpoint in other mount namespaces.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Propagation {
+	/// Events are neither sent nor received.
+	Private,
+	/// Events are sent to and received from the other members of the peer group.
+	Shared,
+	/// Events are received from the master peer group, but not sent.
+	Slave,
+}
+
+/// The ID of the next peer group to be allocated.
+static NEXT_PEER_GROUP: AtomicU32 = AtomicU32::new(1);
+
 /// A mount point, allowing to attach a filesystem to a directory on the VFS.
 #[derive(Debug)]
 pub struct MountPoint {
@@ -184,6 +204,58 @@ pub struct MountPoint {
 	pub fs: Arc<Filesystem>,
 	/// The root entry of the mountpoint.
 	pub root_entry: Arc<vfs::Entry>,
+	/// The ID of the mount namespace the mountpoint belongs to.
+	pub ns: u32,
+
+	/// The peer group of the mountpoint. If zero, the mountpoint is not shared.
+	peer_group: AtomicU32,
+	/// The peer group the mountpoint receives events from. If zero, the mountpoint is not a
+	/// slave.
+	master: AtomicU32,
+}
+
+impl MountPoint {
+	/// Returns the propagation type of the mountpoint.
+	pub fn propagation(&self) -> Propagation {
+		if self.peer_group.load(Relaxed) != 0 {
+			Propagation::Shared
+		} else if self.master.load(Relaxed) != 0 {
+			Propagation::Slave
+		} else {
+			Propagation::Private
+		}
+	}
+
+	/// Sets the propagation type of the mountpoint.
+	fn set_propagation(&self, propagation: Propagation) {
+		match propagation {
+			Propagation::Private => {
+				self.peer_group.store(0, Relaxed);
+				self.master.store(0, Relaxed);
+			}
+			Propagation::Shared => {
+				if self.peer_group.load(Relaxed) == 0 {
+					let group = NEXT_PEER_GROUP.fetch_add(1, Relaxed);
+					self.peer_group.store(group, Relaxed);
+				}
+			}
+			Propagation::Slave => {
+				// A mountpoint that is not shared has no peers to receive events from
+				let group = self.peer_group.swap(0, Relaxed);
+				if group != 0 {
+					self.master.store(group, Relaxed);
+				}
+			}
+		}
+	}
+
+	/// Tells whether the mountpoint receives the events sent by `other`.
+	fn receives_from(&self, other: &Self) -> bool {
+		let group = other.peer_group.load(Relaxed);
+		group != 0
+			&& !ptr::eq(self, other)
+			&& (self.peer_group.load(Relaxed) == group || self.master.load(Relaxed) == group)
+	}
 }
 
 impl Drop for MountPoint {
@@ -208,8 +280,232 @@ impl Drop for MountPoint {
 }
 
 /// The list of mountpoints with their respective ID.
-pub static MOUNT_POINTS: Mutex<HashMap<*const vfs::Entry, Arc<MountPoint>>> =
-	Mutex::new(HashMap::new());
+///
+/// Since each mount namespace has its own tree of entries, the root entry of a mountpoint is
+/// enough to tell which namespace it belongs to.
+///
+/// Lookups do not take any lock.
+pub static MOUNT_POINTS: RcuHashMap<*const vfs::Entry, Arc<MountPoint>> = RcuHashMap::new();
+
+/// The ID of the initial mount namespace.
+const INIT_NS_ID: u32 = 1;
+/// The ID of the next mount namespace to be created.
+static NEXT_NS_ID: AtomicU32 = AtomicU32::new(INIT_NS_ID + 1);
+
+/// A mount namespace.
+///
+/// Each namespace has its own copy of the mountpoints, attached to its own tree of entries.
+/// Mounting or unmounting a filesystem in a namespace thus does not affect the others, except
+/// through propagation.
+#[derive(Debug)]
+pub struct MountNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The user namespace owning the namespace.
+	pub user_ns: Arc<UserNamespace>,
+	/// The root entry of the namespace.
+	root: Mutex<Arc<vfs::Entry>>,
+}
+
+/// The initial mount namespace.
+static INIT_NS: OnceInit<Arc<MountNamespace>> = unsafe { OnceInit::new() };
+
+impl MountNamespace {
+	/// Returns the initial mount namespace.
+	pub fn init_ns() -> &'static Arc<Self> {
+		&INIT_NS
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// Returns the root entry of the namespace.
+	pub fn root(&self) -> Arc<vfs::Entry> {
+		self.root.lock().clone()
+	}
+
+	/// Sets the root entry of the namespace.
+	pub fn set_root(&self, root: Arc<vfs::Entry>) {
+		*self.root.lock() = root;
+	}
+
+	/// Creates a new namespace with a copy of the mountpoints of `self`, owned by `user_ns`.
+	///
+	/// Copies of shared mountpoints join the peer group of the original, and copies of slave
+	/// mountpoints receive events from the same master.
+	pub fn copy(&self, user_ns: Arc<UserNamespace>) -> EResult<Arc<Self>> {
+		let old_root = self.root();
+		// Collect the visible mountpoints, along with the path from the namespace's root
+		let mut mps = Vec::new();
+		for mp in MOUNT_POINTS.values()? {
+			if mp.ns != self.id || !is_visible(&old_root, &mp.root_entry) {
+				continue;
+			}
+			let Some(chain) = chain_from(&old_root, &mp.root_entry)? else {
+				continue;
+			};
+			mps.push((chain, mp.clone()))?;
+		}
+		// Mountpoints have to be copied after the ones they are attached into
+		mps.sort_unstable_by_key(|(chain, _)| chain.len());
+		let root_node = old_root.node.clone();
+		let new_root = Arc::new(vfs::Entry::new(String::new(), None, root_node))?;
+		let ns = Arc::new(Self {
+			id: NEXT_NS_ID.fetch_add(1, Relaxed),
+			user_ns,
+			root: Mutex::new(new_root.clone()),
+		})?;
+		for (chain, mp) in mps {
+			let copy = match chain.split_last() {
+				None => add(mp.try_copy(ns.id, new_root.clone())?)?,
+				Some((last, path)) => {
+					let Some(parent) = walk(&new_root, path)? else {
+						continue;
+					};
+					let root_entry = Arc::new(vfs::Entry::new(
+						last.name.try_clone()?,
+						Some(parent),
+						mp.root_entry.node.clone(),
+					))?;
+					add(mp.try_copy(ns.id, root_entry)?)?
+				}
+			};
+			copy.master.store(mp.master.load(Relaxed), Relaxed);
+			copy.peer_group.store(mp.peer_group.load(Relaxed), Relaxed);
+		}
+		Ok(ns)
+	}
+
+	/// Returns the entry of the namespace at the same location as `ent` in the namespace `from`.
+	///
+	/// If the location does not exist in the namespace, the function returns its root.
+	pub fn translate(&self, from: &Self, ent: &Arc<vfs::Entry>) -> EResult<Arc<vfs::Entry>> {
+		let root = self.root();
+		let Some(chain) = chain_from(&from.root(), ent)? else {
+			return Ok(root);
+		};
+		Ok(walk(&root, &chain)?.unwrap_or(root))
+	}
+}
+
+impl Drop for MountNamespace {
+	fn drop(&mut self) {
+		MOUNT_POINTS.retain(|_, mp| mp.ns != self.id);
+	}
+}
+
+impl MountPoint {
+	/// Returns a copy of the mountpoint, in the namespace `ns`, with the root entry `root_entry`.
+	///
+	/// The copy is private.
+	fn try_copy(&self, ns: u32, root_entry: Arc<vfs::Entry>) -> AllocResult<Self> {
+		Ok(Self {
+			flags: self.flags,
+			source: self.source.try_clone()?,
+			fs: self.fs.clone(),
+			root_entry,
+			ns,
+
+			peer_group: AtomicU32::new(0),
+			master: AtomicU32::new(0),
+		})
+	}
+}
+
+/// A chain of entries, each being the child of the previous one.
+type Chain = Vec<Arc<vfs::Entry>>;
+
+/// Tells whether the mountpoint with the root entry `ent` is visible in the tree of `root`. That
+/// is, if it has not been covered by another mountpoint.
+fn is_visible(root: &Arc<vfs::Entry>, ent: &Arc<vfs::Entry>) -> bool {
+	match &ent.parent {
+		_ if ptr::eq(Arc::as_ptr(root), Arc::as_ptr(ent)) => true,
+		Some(parent) => vfs::is_cached(&parent.children.lock(), ent),
+		None => false,
+	}
+}
+
+/// Returns the chain of entries leading from `root` (excluded) to `ent` (included).
+///
+/// If `ent` is not under `root`, the function returns `None`.
+fn chain_from(root: &Arc<vfs::Entry>, ent: &Arc<vfs::Entry>) -> AllocResult<Option<Chain>> {
+	let mut chain = Vec::new();
+	let mut cur = ent;
+	while !ptr::eq(Arc::as_ptr(root), Arc::as_ptr(cur)) {
+		let Some(parent) = &cur.parent else {
+			return Ok(None);
+		};
+		chain.push(cur.clone())?;
+		cur = parent;
+	}
+	chain.reverse();
+	Ok(Some(chain))
+}
+
+/// Walks from `root` through the directories having the names of the entries in `chain`, and
+/// returns the last one.
+///
+/// If a directory does not exist, the function returns `None`.
+fn walk(root: &Arc<vfs::Entry>, chain: &[Arc<vfs::Entry>]) -> EResult<Option<Arc<vfs::Entry>>> {
+	let mut cur = root.clone();
+	for ent in chain {
+		let next = vfs::resolve_entry(&cur, &ent.name)?;
+		if next.is_negative() || next.get_type()? != FileType::Directory {
+			return Ok(None);
+		}
+		cur = next;
+	}
+	Ok(Some(cur))
+}
+
+/// Registers the mountpoint `mp` and attaches its root entry to its parent, if any.
+///
+/// The function returns the mountpoint.
+fn add(mp: MountPoint) -> EResult<Arc<MountPoint>> {
+	let root_entry = mp.root_entry.clone();
+	let mp = Arc::new(mp)?;
+	// If the next insertion fails, this will be undone by the implementation of `Drop`
+	MOUNT_POINTS.insert(Arc::as_ptr(&root_entry), mp.clone())?;
+	// Replace the target with the mountpoint's root in the tree
+	if let Some(parent) = &root_entry.parent {
+		parent
+			.children
+			.lock()
+			.insert(vfs::EntryChild(root_entry.clone()))?;
+	}
+	Ok(mp)
+}
+
+/// Returns the mountpoint in which `ent` is located, along with the chain of entries leading
+/// from its root (excluded) to `ent` (included).
+fn containing(ent: &Arc<vfs::Entry>) -> AllocResult<Option<(Arc<MountPoint>, Chain)>> {
+	let mut cur = ent;
+	loop {
+		if let Some(mp) = from_entry(cur) {
+			let Some(chain) = chain_from(cur, ent)? else {
+				return Ok(None);
+			};
+			return Ok(Some((mp, chain)));
+		}
+		let Some(parent) = &cur.parent else {
+			return Ok(None);
+		};
+		cur = parent;
+	}
+}
+
+/// Returns the mountpoints receiving the events sent by `mp`.
+fn receivers(mp: &MountPoint) -> AllocResult<Vec<Arc<MountPoint>>> {
+	let mut res = Vec::new();
+	for other in MOUNT_POINTS.values()? {
+		if other.receives_from(mp) {
+			res.push(other)?;
+		}
+	}
+	Ok(res)
+}
 
 /// Creates a new mountpoint.
 ///
@@ -229,36 +525,71 @@ pub fn create(
 	target: Option<Arc<vfs::Entry>>,
 ) -> EResult<Arc<vfs::Entry>> {
 	// Get filesystem
-	let (target_path, name, parent) = match target {
-		Some(target) => (
-			vfs::Entry::get_path(&target)?,
-			target.name.try_clone()?,
-			target.parent.clone(),
-		),
-		None => (PathBuf::root()?, String::new(), None),
+	let target_path = match &target {
+		Some(target) => vfs::Entry::get_path(target)?,
+		None => PathBuf::root()?,
 	};
 	let fs = get_fs(&source, fs_type, target_path, flags & FLAG_RDONLY != 0)?;
-	let mut mps = MOUNT_POINTS.lock();
+	attach(source, fs, flags, target)
+}
+
+/// Creates a new mountpoint for the already loaded filesystem `fs`.
+///
+/// This allows to make the same filesystem accessible from several places.
+///
+/// If the mountpoint containing `target` is shared, the new mountpoint is propagated to the
+/// mountpoints receiving its events.
+///
+/// Arguments are the same as [`create`].
+pub fn attach(
+	source: MountSource,
+	fs: Arc<Filesystem>,
+	flags: u32,
+	target: Option<Arc<vfs::Entry>>,
+) -> EResult<Arc<vfs::Entry>> {
+	let container = match &target {
+		Some(target) => containing(target)?,
+		None => None,
+	};
+	let ns = container
+		.as_ref()
+		.map(|(mp, _)| mp.ns)
+		.unwrap_or(INIT_NS_ID);
+	let (name, parent) = match target {
+		Some(target) => (target.name.try_clone()?, target.parent.clone()),
+		None => (String::new(), None),
+	};
 	// TODO get root node from cache if present instead
 	// Get filesystem root node
 	let root = fs.ops.root(fs.clone())?;
 	// Create an entry for the root of the mountpoint
-	let root_entry = Arc::new(vfs::Entry::new(name, parent.clone(), Some(root)))?;
-	// Create mountpoint
-	let mountpoint = Arc::new(MountPoint {
+	let root_entry = Arc::new(vfs::Entry::new(name, parent, Some(root)))?;
+	let mp = add(MountPoint {
 		flags,
 		source,
 		fs,
 		root_entry: root_entry.clone(),
+		ns,
+
+		peer_group: AtomicU32::new(0),
+		master: AtomicU32::new(0),
 	})?;
-	// If the next insertion fails, this will be undone by the implementation of `Drop`
-	mps.insert(Arc::as_ptr(&root_entry), mountpoint)?;
-	// Replace `target` with the mountpoint's root in the tree
-	if let Some(target_parent) = &parent {
-		target_parent
-			.children
-			.lock()
-			.insert(EntryChild(root_entry.clone()))?;
+	// Propagate
+	if let Some((container, chain)) = container {
+		let Some((last, path)) = chain.split_last() else {
+			return Ok(root_entry);
+		};
+		for receiver in receivers(&container)? {
+			let Some(parent) = walk(&receiver.root_entry, path)? else {
+				continue;
+			};
+			let root_entry = Arc::new(vfs::Entry::new(
+				last.name.try_clone()?,
+				Some(parent),
+				mp.root_entry.node.clone(),
+			))?;
+			add(mp.try_copy(receiver.ns, root_entry)?)?;
+		}
 	}
 	Ok(root_entry)
 }
@@ -267,26 +598,99 @@ pub fn create(
 ///
 /// Data is synchronized to the associated storage device, if any, before removing the mountpoint.
 ///
+/// If the mountpoint containing `target` is shared, the removal is propagated to the
+/// mountpoints receiving its events.
+///
 /// If `target` is not a mountpoint, the function returns [`errno::EINVAL`].
 ///
 /// If the mountpoint is busy, the function returns [`errno::EBUSY`].
 pub fn remove(target: Arc<vfs::Entry>) -> EResult<()> {
 	// TODO Check if another mount point is present in a subdirectory (EBUSY)
 	// TODO Check if busy (EBUSY)
-	// Detach entry from parent
 	let Some(parent) = &target.parent else {
 		// Cannot unmount root filesystem
 		return Err(errno!(EINVAL));
 	};
-	parent.children.lock().remove(target.name.as_bytes());
+	let mp = from_entry(&target).ok_or_else(|| errno!(EINVAL))?;
+	mp.fs.sync()?;
+	detach(parent, &target);
+	// Propagate
+	let Some((container, chain)) = containing(parent)? else {
+		return Ok(());
+	};
+	for receiver in receivers(&container)? {
+		let Some(parent) = walk(&receiver.root_entry, &chain)? else {
+			continue;
+		};
+		let Ok(ent) = vfs::resolve_entry(&parent, &target.name) else {
+			continue;
+		};
+		if from_entry(&ent)
+			.is_some_and(|other| ptr::eq(Arc::as_ptr(&other.fs), Arc::as_ptr(&mp.fs)))
+		{
+			detach(&parent, &ent);
+		}
+	}
+	Ok(())
+}
+
+/// Detaches the mountpoint with the root entry `ent` from its parent `parent` and unregisters it.
+fn detach(parent: &vfs::Entry, ent: &Arc<vfs::Entry>) {
+	parent.children.lock().remove(ent.name.as_bytes());
 	// TODO release node and children
-	MOUNT_POINTS.lock().remove(&Arc::as_ptr(&target));
+	MOUNT_POINTS.remove(&Arc::as_ptr(ent));
+}
+
+/// Sets the propagation type of the mountpoint at `target`.
+///
+/// If `recursive` is set, the propagation type of every mountpoint under `target` in the same
+/// namespace is also set.
+///
+/// If `target` is not a mountpoint, the function returns [`errno::EINVAL`].
+pub fn set_propagation(
+	target: &Arc<vfs::Entry>,
+	propagation: Propagation,
+	recursive: bool,
+) -> EResult<()> {
+	let mp = from_entry(target).ok_or_else(|| errno!(EINVAL))?;
+	mp.set_propagation(propagation);
+	if recursive {
+		for other in MOUNT_POINTS.values()? {
+			if other.ns == mp.ns && chain_from(target, &other.root_entry)?.is_some() {
+				other.set_propagation(propagation);
+			}
+		}
+	}
 	Ok(())
 }
 
+/// Returns the mountpoint in which `ent` is located.
+pub fn of_entry(ent: &vfs::Entry) -> Option<Arc<MountPoint>> {
+	let mut cur = ent;
+	loop {
+		if let Some(mp) = from_entry(cur) {
+			return Some(mp);
+		}
+		cur = cur.parent.as_ref()?;
+	}
+}
+
 /// Returns the mountpoint for the root entry `ent`.
 ///
 /// If `ent` is not associated to a mountpoint, the function returns `None`.
 pub fn from_entry(ent: &vfs::Entry) -> Option<Arc<MountPoint>> {
-	MOUNT_POINTS.lock().get(&(ent as _)).cloned()
+	MOUNT_POINTS.get(&(ent as _))
+}
+
+/// Initializes the initial mount namespace, with the root entry `root`.
+pub(in crate::file) fn init(root: Arc<vfs::Entry>) -> AllocResult<()> {
+	let ns = Arc::new(MountNamespace {
+		id: INIT_NS_ID,
+		user_ns: UserNamespace::root().clone(),
+		root: Mutex::new(root),
+	})?;
+	unsafe {
+		OnceInit::init(&INIT_NS, ns);
+	}
+	Ok(())
 }
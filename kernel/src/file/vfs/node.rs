@@ -24,19 +24,15 @@ use crate::{
 		FileType, INode, Stat,
 	},
 	memory::cache::MappedNode,
-	sync::mutex::Mutex,
+	sync::{mutex::Mutex, rcu::RcuHashMap},
 };
 use core::{
-	borrow::Borrow,
-	fmt,
-	fmt::Formatter,
-	hash::{Hash, Hasher},
 	ptr,
 	sync::atomic::{AtomicBool, Ordering::Acquire},
 };
 use utils::{
 	boxed::Box,
-	collections::{hashset::HashSet, path::PathBuf, string::String},
+	collections::{path::PathBuf, string::String},
 	errno::EResult,
 	limits::SYMLINK_MAX,
 	ptr::arc::Arc,
@@ -135,68 +131,40 @@ impl Node {
 	}
 }
 
-struct NodeWrapper(Arc<Node>);
-
-impl Borrow<INode> for NodeWrapper {
-	fn borrow(&self) -> &INode {
-		&self.0.inode
-	}
-}
-
-impl Eq for NodeWrapper {}
-
-impl PartialEq for NodeWrapper {
-	fn eq(&self, other: &Self) -> bool {
-		self.0.inode == other.0.inode
-	}
-}
-
-impl Hash for NodeWrapper {
-	fn hash<H: Hasher>(&self, state: &mut H) {
-		self.0.inode.hash(state)
-	}
-}
-
-impl fmt::Debug for NodeWrapper {
-	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		fmt::Debug::fmt(&self.0, f)
-	}
-}
-
 /// Cache for nodes for use inside filesystem implementations, to avoid duplications of [`Node`]
 /// instances when several entries point to the same node.
 #[derive(Debug, Default)]
-pub struct NodeCache(Mutex<HashSet<NodeWrapper>>);
+pub struct NodeCache(RcuHashMap<INode, Arc<Node>>);
 
 impl NodeCache {
 	/// Inserts a node in cache. If already present, the previous entry is dropped.
 	pub fn insert(&self, node: Arc<Node>) -> EResult<()> {
-		self.0.lock().insert(NodeWrapper(node))?;
+		self.0.insert(node.inode, node)?;
 		Ok(())
 	}
 
 	/// Returns the node with ID `inode` from the cache, or if not in cache, initializes it with
 	/// `init` and inserts it.
+	///
+	/// Cache hits do not take any lock.
 	pub fn get_or_insert<F: FnOnce() -> EResult<Arc<Node>>>(
 		&self,
 		inode: INode,
 		init: F,
 	) -> EResult<Arc<Node>> {
-		let mut cache = self.0.lock();
-		match cache.get(&inode) {
-			// Cache hit
-			Some(node) => Ok(node.0.clone()),
-			// Cache miss, create instance and insert
-			None => {
-				let node = init()?;
-				cache.insert(NodeWrapper(node.clone()))?;
-				Ok(node)
-			}
-		}
+		self.0.get_or_insert(inode, init)
 	}
 
 	/// Removes the node with ID `inode` from the cache.
 	pub fn remove(&self, inode: INode) {
-		self.0.lock().remove(&inode);
+		self.0.remove(&inode);
+	}
+
+	/// Synchronizes all the nodes in the cache to the storage device, including their metadata.
+	pub fn sync(&self) -> EResult<()> {
+		for node in self.0.values()? {
+			node.sync(true)?;
+		}
+		Ok(())
 	}
 }
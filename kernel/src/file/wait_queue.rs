@@ -23,8 +23,17 @@ use crate::{
 	process,
 	process::{pid::Pid, scheduler::Scheduler, Process},
 	sync::mutex::{IntMutex, Mutex},
+	time::{
+		clock::{current_time_ns, Clock},
+		timer::Timer,
+		unit::Timestamp,
+	},
+};
+use core::{
+	cmp::min,
+	mem,
+	sync::atomic::{AtomicUsize, Ordering::Relaxed},
 };
-use core::mem;
 use utils::{collections::vec::Vec, errno, errno::EResult};
 
 /// A queue of processes waiting on a resource.
@@ -43,7 +52,8 @@ impl WaitQueue {
 
 	/// Makes the current process wait until the given closure returns `Some`.
 	///
-	/// If waiting is interrupted by a signal handler, the function returns [`errno::EINTR`].
+	/// If waiting is interrupted by a signal, the function returns [`errno::ERESTARTSYS`], so that
+	/// the system call is restarted if the signal handler has been registered with `SA_RESTART`.
 	pub fn wait_until<F: FnMut() -> Option<T>, T>(&self, mut f: F) -> EResult<T> {
 		loop {
 			if let Some(val) = f() {
@@ -62,12 +72,32 @@ impl WaitQueue {
 			{
 				// If the current process had received a signal, return
 				if Process::current().has_pending_signal() {
-					return Err(errno!(EINTR));
+					return Err(errno!(ERESTARTSYS));
 				}
 			}
 		}
 	}
 
+	/// Same as [`Self::wait_until`], except the function returns [`errno::ETIMEDOUT`] if the
+	/// timestamp `ts`, in nanoseconds, is reached on `clock` before `f` returns `Some`.
+	pub fn wait_until_deadline<F: FnMut() -> Option<T>, T>(
+		&self,
+		clock: Clock,
+		ts: Timestamp,
+		mut f: F,
+	) -> EResult<T> {
+		let _timer = Timer::wake_at(clock, Process::current().get_pid(), ts)?;
+		self.wait_until(|| {
+			if let Some(val) = f() {
+				return Some(Ok(val));
+			}
+			if current_time_ns(clock) >= ts {
+				return Some(Err(errno!(ETIMEDOUT)));
+			}
+			None
+		})?
+	}
+
 	/// Wakes the next process in queue.
 	pub fn wake_next(&self) {
 		let proc = loop {
@@ -101,3 +131,72 @@ impl WaitQueue {
 		}
 	}
 }
+
+/// Queue of processes waiting for events on any file, with `poll`, `select` or `epoll_wait`.
+static POLL_QUEUE: WaitQueue = WaitQueue::new();
+/// The number of calls to [`poll_notify`] since boot.
+static POLL_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum interval, in nanoseconds, between two checks of polled files.
+///
+/// This allows to notice events on files that do not call [`poll_notify`].
+const POLL_INTERVAL: Timestamp = 10_000_000;
+
+/// Wakes processes polling files, so that they check them again.
+///
+/// Files shall call this function each time events may have occurred on them.
+pub fn poll_notify() {
+	POLL_SEQ.fetch_add(1, Relaxed);
+	POLL_QUEUE.wake_all();
+}
+
+/// Returns the number of calls to [`poll_notify`] since boot.
+///
+/// If the value did not change between two checks of a file, no event has been signaled on it.
+///
+/// The value wraps around on overflow.
+pub fn poll_seq() -> usize {
+	POLL_SEQ.load(Relaxed)
+}
+
+/// Converts [`errno::ERESTARTSYS`] into [`errno::EINTR`], for operations that must not be
+/// restarted after a signal handler, even with `SA_RESTART`.
+pub fn no_restart<T>(res: EResult<T>) -> EResult<T> {
+	res.map_err(|e| {
+		if e.as_int() == errno::ERESTARTSYS {
+			errno!(EINTR)
+		} else {
+			e
+		}
+	})
+}
+
+/// Makes the current process wait until `f` returns `Some`, checking each time [`poll_notify`] is
+/// called.
+///
+/// `deadline` is the timestamp in nanoseconds on the monotonic clock at which the function stops
+/// waiting and returns `None`. If `None`, the function waits indefinitely.
+///
+/// If waiting is interrupted by a signal handler, the function returns [`errno::EINTR`]. Like on
+/// Linux, polling is never restarted.
+pub fn poll_wait<F: FnMut() -> EResult<Option<T>>, T>(
+	deadline: Option<Timestamp>,
+	mut f: F,
+) -> EResult<Option<T>> {
+	loop {
+		let now = current_time_ns(Clock::Monotonic);
+		let ts = now.saturating_add(POLL_INTERVAL);
+		let ts = deadline.map(|d| min(d, ts)).unwrap_or(ts);
+		let res =
+			no_restart(POLL_QUEUE.wait_until_deadline(Clock::Monotonic, ts, || f().transpose()));
+		match res {
+			Ok(res) => break res.map(Some),
+			Err(e) if e.as_int() == errno::ETIMEDOUT => {
+				if deadline.is_some_and(|d| current_time_ns(Clock::Monotonic) >= d) {
+					break Ok(None);
+				}
+			}
+			Err(e) => break Err(e),
+		}
+	}
+}
@@ -0,0 +1,369 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V Inter-Process Communication (IPC) allows processes to exchange data through objects
+//! identified by a key:
+//! - shared memory segments
+//! - semaphore sets
+//! - message queues
+//!
+//! Each kind of object has its own registry, in which objects are identified by an ID returned to
+//! userspace.
+//!
+//! Removing an object from its registry (`IPC_RMID`) does not necessarily free it: it remains
+//! usable by processes which still refer to it.
+//!
+//! POSIX message queues, which are identified by a name instead of a key, are implemented in
+//! [`mqueue`].
+
+pub mod mqueue;
+pub mod msg;
+pub mod sem;
+pub mod shm;
+
+use crate::{
+	file::{
+		perm::{AccessProfile, Gid, Uid},
+		Mode,
+	},
+	sync::mutex::Mutex,
+};
+use core::{
+	ffi::{c_int, c_ulong},
+	intrinsics::unlikely,
+};
+use utils::{collections::btreemap::BTreeMap, errno, errno::EResult, ptr::arc::Arc};
+
+/// The type of an IPC key.
+pub type Key = c_int;
+
+/// Key requesting the creation of a new object which cannot be looked up by other processes.
+pub const IPC_PRIVATE: Key = 0;
+
+/// Flag: create the object if it does not exist.
+pub const IPC_CREAT: c_int = 0o1000;
+/// Flag: fail if the object already exists.
+pub const IPC_EXCL: c_int = 0o2000;
+/// Flag: return an error instead of blocking.
+pub const IPC_NOWAIT: c_int = 0o4000;
+
+/// Command: remove the object.
+pub const IPC_RMID: c_int = 0;
+/// Command: set the object's ownership and permissions.
+pub const IPC_SET: c_int = 1;
+/// Command: get the object's status.
+pub const IPC_STAT: c_int = 2;
+/// Command flag: use the 64-bit version of structures. This is the only supported version.
+pub const IPC_64: c_int = 0x100;
+
+/// Ownership and permissions of an IPC object.
+#[derive(Clone, Debug)]
+pub struct IpcPerm {
+	/// The key of the object.
+	pub key: Key,
+	/// The user ID of the owner.
+	pub uid: Uid,
+	/// The group ID of the owner.
+	pub gid: Gid,
+	/// The user ID of the creator.
+	pub cuid: Uid,
+	/// The group ID of the creator.
+	pub cgid: Gid,
+	/// The permissions of the object. Only the lower 9 bits are relevant, other bits may be
+	/// flags specific to the kind of object.
+	pub mode: Mode,
+	/// The sequence number of the object.
+	pub seq: u16,
+}
+
+impl IpcPerm {
+	/// Creates a new instance for an object created by `ap`.
+	///
+	/// Arguments:
+	/// - `key` is the key of the object
+	/// - `flags` is the set of flags given at creation, containing permissions
+	fn new(key: Key, flags: c_int, ap: &AccessProfile) -> Self {
+		Self {
+			key,
+			uid: ap.euid,
+			gid: ap.egid,
+			cuid: ap.euid,
+			cgid: ap.egid,
+			mode: flags as Mode & 0o777,
+			seq: 0,
+		}
+	}
+
+	/// Updates ownership and permissions from the userspace structure `perm`, for `IPC_SET`.
+	pub fn set(&mut self, perm: &IpcPermUser) {
+		self.uid = perm.uid as _;
+		self.gid = perm.gid as _;
+		self.mode = (self.mode & !0o777) | (perm.mode as Mode & 0o777);
+	}
+}
+
+/// Userspace structure representing ownership and permissions of an IPC object.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct IpcPermUser {
+	/// The key of the object.
+	pub key: Key,
+	/// The user ID of the owner.
+	pub uid: u32,
+	/// The group ID of the owner.
+	pub gid: u32,
+	/// The user ID of the creator.
+	pub cuid: u32,
+	/// The group ID of the creator.
+	pub cgid: u32,
+	/// The permissions of the object.
+	///
+	/// On 32-bit, this field is 16 bits long, followed by padding.
+	pub mode: u32,
+	/// The sequence number of the object.
+	pub seq: u16,
+	/// Padding.
+	pub __pad2: u16,
+	/// Reserved.
+	pub __unused1: c_ulong,
+	/// Reserved.
+	pub __unused2: c_ulong,
+}
+
+impl From<&IpcPerm> for IpcPermUser {
+	fn from(perm: &IpcPerm) -> Self {
+		Self {
+			key: perm.key,
+			uid: perm.uid as _,
+			gid: perm.gid as _,
+			cuid: perm.cuid as _,
+			cgid: perm.cgid as _,
+			mode: perm.mode as _,
+			seq: perm.seq,
+			__pad2: 0,
+			__unused1: 0,
+			__unused2: 0,
+		}
+	}
+}
+
+/// Compatibility version of [`IpcPermUser`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct CompatIpcPermUser {
+	pub key: Key,
+	pub uid: u32,
+	pub gid: u32,
+	pub cuid: u32,
+	pub cgid: u32,
+	pub mode: u32,
+	pub seq: u16,
+	pub __pad2: u16,
+	pub __unused1: u32,
+	pub __unused2: u32,
+}
+
+impl From<IpcPermUser> for CompatIpcPermUser {
+	fn from(perm: IpcPermUser) -> Self {
+		Self {
+			key: perm.key,
+			uid: perm.uid,
+			gid: perm.gid,
+			cuid: perm.cuid,
+			cgid: perm.cgid,
+			mode: perm.mode,
+			seq: perm.seq,
+			__pad2: 0,
+			__unused1: 0,
+			__unused2: 0,
+		}
+	}
+}
+
+impl From<CompatIpcPermUser> for IpcPermUser {
+	fn from(perm: CompatIpcPermUser) -> Self {
+		Self {
+			key: perm.key,
+			uid: perm.uid,
+			gid: perm.gid,
+			cuid: perm.cuid,
+			cgid: perm.cgid,
+			mode: perm.mode,
+			seq: perm.seq,
+			__pad2: 0,
+			__unused1: 0,
+			__unused2: 0,
+		}
+	}
+}
+
+impl AccessProfile {
+	/// Tells whether the agent can access the IPC object with permissions `perm`.
+	///
+	/// `mode` is the set of requested permissions, in the `0o7` form.
+	pub fn can_access_ipc(&self, perm: &IpcPerm, mode: Mode) -> bool {
+		if self.is_privileged() {
+			return true;
+		}
+		let granted = if self.euid == perm.uid || self.euid == perm.cuid {
+			perm.mode >> 6
+		} else if self.egid == perm.gid || self.egid == perm.cgid {
+			perm.mode >> 3
+		} else {
+			perm.mode
+		};
+		granted & mode & 0o7 == mode & 0o7
+	}
+
+	/// Tells whether the agent can modify or remove the IPC object with permissions `perm`.
+	pub fn is_ipc_owner(&self, perm: &IpcPerm) -> bool {
+		self.is_privileged() || self.euid == perm.uid || self.euid == perm.cuid
+	}
+}
+
+/// Trait implemented by IPC objects.
+pub trait IpcObject {
+	/// Returns the ownership and permissions of the object.
+	fn perm(&self) -> &Mutex<IpcPerm>;
+}
+
+/// A registry of IPC objects of the same kind.
+pub struct Registry<T: IpcObject> {
+	/// The objects, by ID.
+	objects: BTreeMap<c_int, Arc<T>>,
+	/// The ID to try for the next object.
+	next_id: c_int,
+}
+
+impl<T: IpcObject> Registry<T> {
+	/// Creates a new, empty registry.
+	pub const fn new() -> Self {
+		Self {
+			objects: BTreeMap::new(),
+			next_id: 0,
+		}
+	}
+
+	/// Returns the object with the given ID.
+	///
+	/// If the object does not exist, the function returns [`errno::EINVAL`].
+	pub fn get(&self, id: c_int) -> EResult<Arc<T>> {
+		self.objects.get(&id).cloned().ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Returns the ID of the object with the key `key`, creating it if required.
+	///
+	/// Arguments:
+	/// - `flags` is the set of `IPC_*` flags and permissions of the `*get` system calls
+	/// - `ap` is the access profile of the agent
+	/// - `check` is called on an existing object to check whether it is suitable
+	/// - `create` is called with the permissions of the new object to create it
+	pub fn get_or_create<C: FnOnce(&T) -> EResult<()>, F: FnOnce(IpcPerm) -> EResult<T>>(
+		&mut self,
+		key: Key,
+		flags: c_int,
+		ap: &AccessProfile,
+		check: C,
+		create: F,
+	) -> EResult<c_int> {
+		if key != IPC_PRIVATE {
+			let existing = self
+				.objects
+				.iter()
+				.find(|(_, obj)| obj.perm().lock().key == key);
+			if let Some((id, obj)) = existing {
+				if unlikely(flags & IPC_CREAT != 0 && flags & IPC_EXCL != 0) {
+					return Err(errno!(EEXIST));
+				}
+				// Check the requested permissions are granted
+				let mode = flags as Mode & 0o777;
+				let mode = (mode >> 6) | (mode >> 3) | mode;
+				if unlikely(!ap.can_access_ipc(&obj.perm().lock(), mode)) {
+					return Err(errno!(EACCES));
+				}
+				check(obj)?;
+				return Ok(*id);
+			}
+			if unlikely(flags & IPC_CREAT == 0) {
+				return Err(errno!(ENOENT));
+			}
+		}
+		let id = self.alloc_id().ok_or_else(|| errno!(ENOSPC))?;
+		let obj = Arc::new(create(IpcPerm::new(key, flags, ap))?)?;
+		self.objects.insert(id, obj)?;
+		Ok(id)
+	}
+
+	/// Allocates an ID for a new object.
+	fn alloc_id(&mut self) -> Option<c_int> {
+		// IDs are not reused right away, to avoid confusing an old object with a new one
+		let start = self.next_id;
+		loop {
+			let id = self.next_id;
+			self.next_id = self.next_id.checked_add(1).unwrap_or(0);
+			if !self.objects.contains_key(&id) {
+				return Some(id);
+			}
+			if self.next_id == start {
+				return None;
+			}
+		}
+	}
+
+	/// Removes the object with the given ID from the registry.
+	///
+	/// The object is not accessible through the registry anymore, but remains usable by its
+	/// current users.
+	pub fn remove(&mut self, id: c_int) -> Option<Arc<T>> {
+		let obj = self.objects.remove(&id)?;
+		obj.perm().lock().key = IPC_PRIVATE;
+		Some(obj)
+	}
+
+	/// Returns an iterator over the objects of the registry.
+	pub fn iter(&self) -> impl Iterator<Item = &Arc<T>> {
+		self.objects.iter().map(|(_, obj)| obj)
+	}
+}
+
+impl<T: IpcObject> Default for Registry<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn ipc_access() {
+		let owner = AccessProfile::new(1000, 1000);
+		let group = AccessProfile::new(1001, 1000);
+		let other = AccessProfile::new(1002, 1002);
+		let perm = IpcPerm::new(42, IPC_CREAT | 0o640, &owner);
+		assert!(owner.can_access_ipc(&perm, 0o6));
+		assert!(group.can_access_ipc(&perm, 0o4));
+		assert!(!group.can_access_ipc(&perm, 0o6));
+		assert!(!other.can_access_ipc(&perm, 0o4));
+		assert!(AccessProfile::KERNEL.can_access_ipc(&perm, 0o6));
+		assert!(owner.is_ipc_owner(&perm));
+		assert!(!group.is_ipc_owner(&perm));
+	}
+}
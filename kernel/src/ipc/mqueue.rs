@@ -0,0 +1,534 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! POSIX message queues allow processes to exchange messages associated with a priority.
+//!
+//! Unlike System V message queues, a queue is identified by a name and accessed through a file
+//! descriptor. Messages are received by decreasing priority, and in the order they have been
+//! sent for messages of the same priority.
+
+use super::{IpcObject, IpcPerm};
+use crate::{
+	file::{
+		fs::FileOps, perm::AccessProfile, wait_queue, wait_queue::WaitQueue, File, FileType, Mode,
+		Stat,
+	},
+	format_content,
+	process::{
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_MESGQ},
+		Process,
+	},
+	sync::mutex::Mutex,
+	syscall::poll::{POLLIN, POLLOUT},
+	time::{clock::Clock, unit::Timestamp},
+};
+use core::{
+	ffi::{c_int, c_long},
+	intrinsics::unlikely,
+};
+use utils::{
+	collections::{hashmap::HashMap, string::String, vec::Vec},
+	errno,
+	errno::EResult,
+	limits::NAME_MAX,
+	ptr::arc::Arc,
+};
+
+/// The upper bound (exclusive) of message priorities.
+pub const MQ_PRIO_MAX: u32 = 32768;
+
+/// The default number of messages in a queue, which is also the maximum for unprivileged
+/// processes.
+pub const MSG_MAX: c_long = 10;
+/// The default size of a message in bytes, which is also the maximum for unprivileged processes.
+pub const MSGSIZE_MAX: c_long = 8192;
+/// The maximum number of messages in a queue.
+pub const HARD_MSG_MAX: c_long = 65536;
+/// The maximum size of a message in bytes.
+pub const HARD_MSGSIZE_MAX: c_long = 16 * 1024 * 1024;
+
+/// The namespace of message queues. The key is the name of the queue.
+pub static QUEUES: Mutex<HashMap<String, Arc<MsgQueue>>> = Mutex::new(HashMap::new());
+
+/// Userspace structure representing the attributes of a message queue.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MqAttr {
+	/// Flags of the open file description. Only `O_NONBLOCK` is relevant.
+	pub mq_flags: c_long,
+	/// The maximum number of messages in the queue.
+	pub mq_maxmsg: c_long,
+	/// The maximum size of a message in bytes.
+	pub mq_msgsize: c_long,
+	/// The number of messages currently in the queue.
+	pub mq_curmsgs: c_long,
+	/// Reserved.
+	pub __reserved: [c_long; 4],
+}
+
+/// Compatibility version of [`MqAttr`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CompatMqAttr {
+	pub mq_flags: i32,
+	pub mq_maxmsg: i32,
+	pub mq_msgsize: i32,
+	pub mq_curmsgs: i32,
+	pub __reserved: [i32; 4],
+}
+
+impl From<MqAttr> for CompatMqAttr {
+	fn from(attr: MqAttr) -> Self {
+		Self {
+			mq_flags: attr.mq_flags as _,
+			mq_maxmsg: attr.mq_maxmsg as _,
+			mq_msgsize: attr.mq_msgsize as _,
+			mq_curmsgs: attr.mq_curmsgs as _,
+			__reserved: [0; 4],
+		}
+	}
+}
+
+impl From<CompatMqAttr> for MqAttr {
+	fn from(attr: CompatMqAttr) -> Self {
+		Self {
+			mq_flags: attr.mq_flags as _,
+			mq_maxmsg: attr.mq_maxmsg as _,
+			mq_msgsize: attr.mq_msgsize as _,
+			mq_curmsgs: attr.mq_curmsgs as _,
+			__reserved: [0; 4],
+		}
+	}
+}
+
+/// A message in a queue.
+#[derive(Debug)]
+pub struct Message {
+	/// The priority of the message.
+	pub prio: u32,
+	/// The content of the message.
+	pub data: Vec<u8>,
+}
+
+/// A notification registered with `mq_notify`.
+#[derive(Clone, Copy, Debug)]
+pub struct Notification {
+	/// The PID of the process to notify.
+	pub pid: Pid,
+	/// The notification method. Either `SIGEV_NONE` or `SIGEV_SIGNAL`.
+	pub notify: c_int,
+	/// The signal to send, if the method is `SIGEV_SIGNAL`.
+	pub signal: Option<Signal>,
+}
+
+/// The state of a message queue.
+#[derive(Debug)]
+struct MqState {
+	/// The messages, sorted by increasing priority. The next message to be received is the last
+	/// one.
+	messages: Vec<Message>,
+	/// The total size of the messages in bytes.
+	size: usize,
+	/// The number of processes waiting for a message.
+	receivers: usize,
+	/// The registered notification, if any.
+	notification: Option<Notification>,
+}
+
+/// A message queue.
+#[derive(Debug)]
+pub struct MsgQueue {
+	/// Ownership and permissions.
+	perm: Mutex<IpcPerm>,
+	/// The maximum number of messages in the queue.
+	maxmsg: usize,
+	/// The maximum size of a message in bytes.
+	msgsize: usize,
+	/// The state of the queue.
+	state: Mutex<MqState>,
+	/// The queue of processes waiting for a message.
+	rd_queue: WaitQueue,
+	/// The queue of processes waiting for space in the queue.
+	wr_queue: WaitQueue,
+}
+
+impl IpcObject for MsgQueue {
+	fn perm(&self) -> &Mutex<IpcPerm> {
+		&self.perm
+	}
+}
+
+/// Inserts `msg` in `messages`, keeping them sorted by increasing priority.
+///
+/// The message is placed before the messages of the same priority, so that those are received
+/// first.
+fn insert(messages: &mut Vec<Message>, msg: Message) -> EResult<()> {
+	let i = messages.partition_point(|m| m.prio < msg.prio);
+	messages.insert(i, msg)?;
+	Ok(())
+}
+
+/// Sleeps on `queue` until `f` returns `Some`.
+///
+/// If `deadline` is set, the function returns [`errno::ETIMEDOUT`] when the given timestamp, in
+/// nanoseconds on the realtime clock, is reached.
+fn wait<F: FnMut() -> Option<T>, T>(
+	queue: &WaitQueue,
+	deadline: Option<Timestamp>,
+	f: F,
+) -> EResult<T> {
+	match deadline {
+		Some(ts) => queue.wait_until_deadline(Clock::Realtime, ts, f),
+		None => queue.wait_until(f),
+	}
+}
+
+impl MsgQueue {
+	/// Creates a new empty queue.
+	///
+	/// Arguments:
+	/// - `perm` is the ownership and permissions of the queue
+	/// - `maxmsg` is the maximum number of messages in the queue
+	/// - `msgsize` is the maximum size of a message in bytes
+	pub fn new(perm: IpcPerm, maxmsg: usize, msgsize: usize) -> Self {
+		Self {
+			perm: Mutex::new(perm),
+			maxmsg,
+			msgsize,
+			state: Mutex::new(MqState {
+				messages: Vec::new(),
+				size: 0,
+				receivers: 0,
+				notification: None,
+			}),
+			rd_queue: WaitQueue::new(),
+			wr_queue: WaitQueue::new(),
+		}
+	}
+
+	/// Returns the attributes of the queue.
+	///
+	/// `flags` is the set of flags of the open file description.
+	pub fn attr(&self, flags: c_int) -> MqAttr {
+		MqAttr {
+			mq_flags: flags as _,
+			mq_maxmsg: self.maxmsg as _,
+			mq_msgsize: self.msgsize as _,
+			mq_curmsgs: self.state.lock().messages.len() as _,
+			__reserved: [0; 4],
+		}
+	}
+
+	/// Appends the message `msg` to the queue.
+	///
+	/// If the queue is full, the current process sleeps until space is available, unless
+	/// `nonblock` is set, in which case the function returns [`errno::EAGAIN`].
+	///
+	/// If `deadline` is set, sleeping stops with [`errno::ETIMEDOUT`] when the given timestamp, in
+	/// nanoseconds on the realtime clock, is reached.
+	///
+	/// If the message is larger than the limit of the queue, the function returns
+	/// [`errno::EMSGSIZE`].
+	pub fn send(&self, msg: Message, nonblock: bool, deadline: Option<Timestamp>) -> EResult<()> {
+		if unlikely(msg.data.len() > self.msgsize) {
+			return Err(errno!(EMSGSIZE));
+		}
+		let mut msg = Some(msg);
+		let notification = wait(&self.wr_queue, deadline, || {
+			let mut state = self.state.lock();
+			if state.messages.len() >= self.maxmsg {
+				if nonblock {
+					return Some(Err(errno!(EAGAIN)));
+				}
+				return None;
+			}
+			let msg = msg.take().unwrap();
+			let len = msg.data.len();
+			if let Err(e) = insert(&mut state.messages, msg) {
+				return Some(Err(e));
+			}
+			state.size += len;
+			// Notify only if the queue was empty and no process is waiting for the message
+			let notification = if state.messages.len() == 1 && state.receivers == 0 {
+				state.notification.take()
+			} else {
+				None
+			};
+			Some(Ok(notification))
+		})??;
+		self.rd_queue.wake_next();
+		wait_queue::poll_notify();
+		if let Some(Notification {
+			pid,
+			signal: Some(signal),
+			..
+		}) = notification
+		{
+			if let Some(proc) = Process::get_by_pid(pid) {
+				let sender = Process::current();
+				proc.kill_info(SigInfo {
+					si_pid: sender.get_pid(),
+					si_uid: sender.fs.lock().access_profile.uid,
+					..SigInfo::new(signal, SI_MESGQ)
+				});
+			}
+		}
+		Ok(())
+	}
+
+	/// Removes the message with the highest priority from the queue and returns it.
+	///
+	/// `size` is the size of the buffer receiving the message. If lower than the size limit of
+	/// messages on the queue, the function returns [`errno::EMSGSIZE`].
+	///
+	/// If the queue is empty, the current process sleeps until a message is available, unless
+	/// `nonblock` is set, in which case the function returns [`errno::EAGAIN`].
+	///
+	/// If `deadline` is set, sleeping stops with [`errno::ETIMEDOUT`] when the given timestamp, in
+	/// nanoseconds on the realtime clock, is reached.
+	pub fn receive(
+		&self,
+		size: usize,
+		nonblock: bool,
+		deadline: Option<Timestamp>,
+	) -> EResult<Message> {
+		if unlikely(size < self.msgsize) {
+			return Err(errno!(EMSGSIZE));
+		}
+		let mut waiting = false;
+		let res = wait(&self.rd_queue, deadline, || {
+			let mut state = self.state.lock();
+			let Some(msg) = state.messages.pop() else {
+				if nonblock {
+					return Some(Err(errno!(EAGAIN)));
+				}
+				if !waiting {
+					state.receivers += 1;
+					waiting = true;
+				}
+				return None;
+			};
+			state.size -= msg.data.len();
+			Some(Ok(msg))
+		});
+		if waiting {
+			self.state.lock().receivers -= 1;
+		}
+		let msg = res??;
+		self.wr_queue.wake_next();
+		wait_queue::poll_notify();
+		Ok(msg)
+	}
+
+	/// Registers or, if `None`, unregisters a notification for the arrival of a message in the
+	/// empty queue, on behalf of the process `pid`.
+	///
+	/// If another process already registered a notification, the function returns
+	/// [`errno::EBUSY`].
+	pub fn notify(&self, pid: Pid, notification: Option<Notification>) -> EResult<()> {
+		let mut state = self.state.lock();
+		match (&state.notification, notification) {
+			(Some(n), _) if n.pid != pid => {
+				// Unregistering a notification belonging to another process does nothing
+				if notification.is_some() {
+					return Err(errno!(EBUSY));
+				}
+			}
+			(Some(_), Some(_)) => return Err(errno!(EBUSY)),
+			(_, n) => state.notification = n,
+		}
+		Ok(())
+	}
+}
+
+impl FileOps for MsgQueue {
+	fn get_stat(&self, _file: &File) -> EResult<Stat> {
+		let perm = self.perm.lock();
+		Ok(Stat {
+			mode: FileType::Regular.to_mode() | (perm.mode & 0o777),
+			uid: perm.uid,
+			gid: perm.gid,
+			size: self.state.lock().size as _,
+			..Default::default()
+		})
+	}
+
+	fn release(&self, _file: &File) {
+		// Closing a descriptor removes the notification registered by the process
+		let pid = Process::current().get_pid();
+		let mut state = self.state.lock();
+		if state.notification.is_some_and(|n| n.pid == pid) {
+			state.notification = None;
+		}
+	}
+
+	fn poll(&self, _file: &File, mask: u32) -> EResult<u32> {
+		let len = self.state.lock().messages.len();
+		let mut events = 0;
+		if len > 0 {
+			events |= POLLIN;
+		}
+		if len < self.maxmsg {
+			events |= POLLOUT;
+		}
+		Ok(events & mask)
+	}
+
+	fn read(&self, _file: &File, off: u64, buf: &mut [u8]) -> EResult<usize> {
+		read_status(self, off, buf)
+	}
+}
+
+/// Writes the textual status of `queue` to `buf`, starting at the offset `off`.
+pub fn read_status(queue: &MsgQueue, off: u64, buf: &mut [u8]) -> EResult<usize> {
+	let state = queue.state.lock();
+	let (notify, signo, pid) = state
+		.notification
+		.map(|n| (n.notify, n.signal.map(|s| s as c_int).unwrap_or(0), n.pid))
+		.unwrap_or((0, 0, 0));
+	format_content!(
+		off,
+		buf,
+		"QSIZE:{size:<10} NOTIFY:{notify:<5} SIGNO:{signo:<5} NOTIFY_PID:{pid:<6}\n",
+		size = state.size
+	)
+}
+
+/// Checks the name of a queue is valid.
+fn check_name(name: &[u8]) -> EResult<()> {
+	if unlikely(name.is_empty()) {
+		return Err(errno!(ENOENT));
+	}
+	if unlikely(name.len() > NAME_MAX) {
+		return Err(errno!(ENAMETOOLONG));
+	}
+	if unlikely(name.contains(&b'/')) {
+		return Err(errno!(EACCES));
+	}
+	if unlikely(name == b"." || name == b"..") {
+		return Err(errno!(EINVAL));
+	}
+	Ok(())
+}
+
+/// Returns the queue with the given `name`, creating it if it does not exist and `create` is
+/// set.
+///
+/// Arguments:
+/// - `name` is the name of the queue
+/// - `create` tells whether the queue is created if it does not exist
+/// - `exclusive` tells whether the function must fail with [`errno::EEXIST`] if the queue exists
+/// - `access` is the set of requested permissions on an existing queue, in the `0o7` form
+/// - `mode` is the set of permissions of a created queue
+/// - `attr` is the set of attributes of a created queue. If `None`, default values are used
+/// - `ap` is the access profile of the agent
+#[allow(clippy::too_many_arguments)]
+pub fn open(
+	name: String,
+	create: bool,
+	exclusive: bool,
+	access: Mode,
+	mode: Mode,
+	attr: Option<MqAttr>,
+	ap: &AccessProfile,
+) -> EResult<Arc<MsgQueue>> {
+	check_name(&name)?;
+	let mut queues = QUEUES.lock();
+	if let Some(queue) = queues.get(name.as_bytes()) {
+		if unlikely(create && exclusive) {
+			return Err(errno!(EEXIST));
+		}
+		if unlikely(!ap.can_access_ipc(&queue.perm.lock(), access)) {
+			return Err(errno!(EACCES));
+		}
+		return Ok(queue.clone());
+	}
+	if unlikely(!create) {
+		return Err(errno!(ENOENT));
+	}
+	let (maxmsg, msgsize) = match attr {
+		Some(attr) => {
+			if unlikely(attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0) {
+				return Err(errno!(EINVAL));
+			}
+			let (msg_max, msgsize_max) = if ap.is_privileged() {
+				(HARD_MSG_MAX, HARD_MSGSIZE_MAX)
+			} else {
+				(MSG_MAX, MSGSIZE_MAX)
+			};
+			if unlikely(attr.mq_maxmsg > msg_max || attr.mq_msgsize > msgsize_max) {
+				return Err(errno!(EINVAL));
+			}
+			(attr.mq_maxmsg, attr.mq_msgsize)
+		}
+		None => (MSG_MAX, MSGSIZE_MAX),
+	};
+	let perm = IpcPerm::new(0, mode as _, ap);
+	let queue = Arc::new(MsgQueue::new(perm, maxmsg as _, msgsize as _))?;
+	queues.insert(name, queue.clone())?;
+	Ok(queue)
+}
+
+/// Removes the queue with the given `name` from the namespace.
+///
+/// The queue remains usable by processes which have it open.
+///
+/// Only the owner of the queue or a privileged agent may remove it. Otherwise, the function
+/// returns [`errno::EACCES`].
+pub fn unlink(name: &[u8], ap: &AccessProfile) -> EResult<()> {
+	check_name(name)?;
+	let mut queues = QUEUES.lock();
+	let queue = queues.get(name).ok_or_else(|| errno!(ENOENT))?;
+	if unlikely(!ap.is_ipc_owner(&queue.perm.lock())) {
+		return Err(errno!(EACCES));
+	}
+	queues.remove(name);
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use utils::errno::CollectResult;
+
+	#[test_case]
+	fn mqueue_priority() {
+		let mut messages = Vec::new();
+		for (prio, data) in [(1, b'a'), (5, b'b'), (1, b'c'), (5, b'd'), (3, b'e')] {
+			let data = Vec::try_from(&[data][..]).unwrap();
+			insert(
+				&mut messages,
+				Message {
+					prio,
+					data,
+				},
+			)
+			.unwrap();
+		}
+		let order = messages
+			.iter()
+			.rev()
+			.map(|m| m.data[0])
+			.collect::<CollectResult<Vec<_>>>()
+			.0
+			.unwrap();
+		assert_eq!(order.as_slice(), b"bdeac");
+	}
+}
@@ -0,0 +1,373 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V message queues allow processes to exchange typed messages.
+//!
+//! Messages are copied into kernel buffers. The total size of the messages in a queue is bounded
+//! by a limit, which can be changed with `IPC_SET`.
+
+use super::{CompatIpcPermUser, IpcObject, IpcPerm, IpcPermUser, Registry, IPC_NOWAIT};
+use crate::{
+	file::{wait_queue, wait_queue::WaitQueue},
+	process::pid::Pid,
+	sync::mutex::Mutex,
+	time::{
+		clock::{current_time_sec, Clock},
+		unit::Timestamp,
+	},
+};
+use core::ffi::{c_int, c_long, c_ulong};
+use utils::{collections::vec::Vec, errno, errno::EResult};
+
+/// Receive flag: truncate the message if it is too long.
+pub const MSG_NOERROR: c_int = 0o10000;
+/// Receive flag: receive the first message whose type is **not** the requested one.
+pub const MSG_EXCEPT: c_int = 0o20000;
+
+/// The maximum size of a message in bytes.
+pub const MSGMAX: usize = 8192;
+/// The default maximum size of the messages in a queue, in bytes.
+pub const MSGMNB: usize = 16384;
+
+/// The registry of message queues.
+pub static QUEUES: Mutex<Registry<MsgQueue>> = Mutex::new(Registry::new());
+
+/// A message in a queue.
+#[derive(Debug)]
+pub struct Message {
+	/// The type of the message. Always positive.
+	pub mtype: c_long,
+	/// The content of the message.
+	pub data: Vec<u8>,
+}
+
+/// The state of a message queue.
+#[derive(Debug)]
+struct MsgState {
+	/// The messages, in the order they have been sent.
+	messages: Vec<Message>, // TODO use a VecDeque
+	/// The total size of the messages in bytes.
+	cbytes: usize,
+	/// The maximum total size of the messages in bytes.
+	qbytes: usize,
+	/// The timestamp of the last send, in seconds.
+	stime: Timestamp,
+	/// The timestamp of the last receive, in seconds.
+	rtime: Timestamp,
+	/// The timestamp of the last change, in seconds.
+	ctime: Timestamp,
+	/// The PID of the last process which sent a message.
+	lspid: Pid,
+	/// The PID of the last process which received a message.
+	lrpid: Pid,
+	/// Tells whether the queue has been removed.
+	removed: bool,
+}
+
+/// A message queue.
+#[derive(Debug)]
+pub struct MsgQueue {
+	/// Ownership and permissions.
+	perm: Mutex<IpcPerm>,
+	/// The state of the queue.
+	state: Mutex<MsgState>,
+	/// The queue of processes waiting for a message.
+	rd_queue: WaitQueue,
+	/// The queue of processes waiting for space in the queue.
+	wr_queue: WaitQueue,
+}
+
+impl MsgQueue {
+	/// Creates a new empty queue.
+	pub fn new(perm: IpcPerm) -> Self {
+		Self {
+			perm: Mutex::new(perm),
+			state: Mutex::new(MsgState {
+				messages: Vec::new(),
+				cbytes: 0,
+				qbytes: MSGMNB,
+				stime: 0,
+				rtime: 0,
+				ctime: current_time_sec(Clock::Realtime),
+				lspid: 0,
+				lrpid: 0,
+				removed: false,
+			}),
+			rd_queue: WaitQueue::new(),
+			wr_queue: WaitQueue::new(),
+		}
+	}
+
+	/// Appends the message `msg` to the queue, on behalf of the process `pid`.
+	///
+	/// If the queue is full, the current process sleeps until enough space is available, unless
+	/// `flags` contains [`IPC_NOWAIT`], in which case the function returns [`errno::EAGAIN`].
+	///
+	/// If the queue is removed while waiting, the function returns [`errno::EIDRM`].
+	pub fn send(&self, pid: Pid, msg: Message, flags: c_int) -> EResult<()> {
+		let mut msg = Some(msg);
+		wait_queue::no_restart(self.wr_queue.wait_until(|| {
+			let mut state = self.state.lock();
+			if state.removed {
+				return Some(Err(errno!(EIDRM)));
+			}
+			let len = msg.as_ref().unwrap().data.len();
+			// The number of messages is bounded too, to limit the overhead of empty messages
+			let full = state.cbytes + len > state.qbytes || state.messages.len() >= state.qbytes;
+			if full {
+				if flags & IPC_NOWAIT != 0 {
+					return Some(Err(errno!(EAGAIN)));
+				}
+				return None;
+			}
+			if let Err(e) = state.messages.push(msg.take().unwrap()) {
+				return Some(Err(e.into()));
+			}
+			state.cbytes += len;
+			state.stime = current_time_sec(Clock::Realtime);
+			state.lspid = pid;
+			Some(Ok(()))
+		}))??;
+		self.rd_queue.wake_all();
+		Ok(())
+	}
+
+	/// Removes and returns a message from the queue, on behalf of the process `pid`.
+	///
+	/// Arguments:
+	/// - `msgtyp` selects the message to receive:
+	///   - if zero, the first message
+	///   - if positive, the first message of type `msgtyp` (or of another type if `flags` contains
+	///     [`MSG_EXCEPT`])
+	///   - if negative, the first message of the lowest type that is lower than or equal to the
+	///     absolute value of `msgtyp`
+	/// - `size` is the maximum size of the message. If the message is larger and `flags` does not
+	///   contain [`MSG_NOERROR`], the function returns [`errno::E2BIG`]
+	///
+	/// If no message matches, the current process sleeps until one is sent, unless `flags`
+	/// contains [`IPC_NOWAIT`], in which case the function returns [`errno::ENOMSG`].
+	///
+	/// If the queue is removed while waiting, the function returns [`errno::EIDRM`].
+	pub fn receive(
+		&self,
+		pid: Pid,
+		msgtyp: c_long,
+		size: usize,
+		flags: c_int,
+	) -> EResult<Message> {
+		let msg = wait_queue::no_restart(self.rd_queue.wait_until(|| {
+			let mut state = self.state.lock();
+			if state.removed {
+				return Some(Err(errno!(EIDRM)));
+			}
+			let Some(i) = select(&state.messages, msgtyp, flags) else {
+				if flags & IPC_NOWAIT != 0 {
+					return Some(Err(errno!(ENOMSG)));
+				}
+				return None;
+			};
+			if state.messages[i].data.len() > size && flags & MSG_NOERROR == 0 {
+				return Some(Err(errno!(E2BIG)));
+			}
+			let msg = state.messages.remove(i);
+			state.cbytes -= msg.data.len();
+			state.rtime = current_time_sec(Clock::Realtime);
+			state.lrpid = pid;
+			Some(Ok(msg))
+		}))??;
+		self.wr_queue.wake_all();
+		Ok(msg)
+	}
+
+	/// Updates ownership, permissions and the size limit of the queue from `ds`, for `IPC_SET`.
+	pub fn set(&self, ds: &MsqidDs) {
+		self.perm.lock().set(&ds.msg_perm);
+		{
+			let mut state = self.state.lock();
+			state.qbytes = ds.msg_qbytes as _;
+			state.ctime = current_time_sec(Clock::Realtime);
+		}
+		// The limit may have been increased
+		self.wr_queue.wake_all();
+	}
+
+	/// Marks the queue as removed, making waiting processes fail.
+	pub fn remove(&self) {
+		{
+			let mut state = self.state.lock();
+			state.removed = true;
+			state.ctime = current_time_sec(Clock::Realtime);
+		}
+		self.rd_queue.wake_all();
+		self.wr_queue.wake_all();
+	}
+
+	/// Returns the status of the queue.
+	pub fn stat(&self) -> MsqidDs {
+		let msg_perm = IpcPermUser::from(&*self.perm.lock());
+		let state = self.state.lock();
+		MsqidDs {
+			msg_perm,
+			msg_stime: state.stime as _,
+			msg_rtime: state.rtime as _,
+			msg_ctime: state.ctime as _,
+			msg_cbytes: state.cbytes as _,
+			msg_qnum: state.messages.len() as _,
+			msg_qbytes: state.qbytes as _,
+			msg_lspid: state.lspid as _,
+			msg_lrpid: state.lrpid as _,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
+
+impl IpcObject for MsgQueue {
+	fn perm(&self) -> &Mutex<IpcPerm> {
+		&self.perm
+	}
+}
+
+/// Returns the index of the message to receive from `messages`.
+///
+/// `msgtyp` and `flags` are the selection arguments of [`MsgQueue::receive`].
+fn select(messages: &[Message], msgtyp: c_long, flags: c_int) -> Option<usize> {
+	let mut iter = messages.iter().enumerate();
+	match msgtyp {
+		0 => iter.next().map(|(i, _)| i),
+		1.. if flags & MSG_EXCEPT != 0 => iter.find(|(_, m)| m.mtype != msgtyp).map(|(i, _)| i),
+		1.. => iter.find(|(_, m)| m.mtype == msgtyp).map(|(i, _)| i),
+		_ => iter
+			.filter(|(_, m)| m.mtype <= -msgtyp)
+			.min_by_key(|(_, m)| m.mtype)
+			.map(|(i, _)| i),
+	}
+}
+
+/// Userspace structure representing the status of a message queue.
+#[repr(C)]
+#[derive(Debug)]
+pub struct MsqidDs {
+	/// Ownership and permissions.
+	pub msg_perm: IpcPermUser,
+	/// The timestamp of the last send.
+	pub msg_stime: c_long,
+	/// The timestamp of the last receive.
+	pub msg_rtime: c_long,
+	/// The timestamp of the last change.
+	pub msg_ctime: c_long,
+	/// The total size of the messages in the queue, in bytes.
+	pub msg_cbytes: c_ulong,
+	/// The number of messages in the queue.
+	pub msg_qnum: c_ulong,
+	/// The maximum total size of the messages in the queue, in bytes.
+	pub msg_qbytes: c_ulong,
+	/// The PID of the last process which sent a message.
+	pub msg_lspid: c_int,
+	/// The PID of the last process which received a message.
+	pub msg_lrpid: c_int,
+	/// Reserved.
+	pub __unused4: c_ulong,
+	/// Reserved.
+	pub __unused5: c_ulong,
+}
+
+/// Compatibility version of [`MsqidDs`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct CompatMsqidDs {
+	pub msg_perm: CompatIpcPermUser,
+	pub msg_stime: u32,
+	pub msg_stime_high: u32,
+	pub msg_rtime: u32,
+	pub msg_rtime_high: u32,
+	pub msg_ctime: u32,
+	pub msg_ctime_high: u32,
+	pub msg_cbytes: u32,
+	pub msg_qnum: u32,
+	pub msg_qbytes: u32,
+	pub msg_lspid: c_int,
+	pub msg_lrpid: c_int,
+	pub __unused4: u32,
+	pub __unused5: u32,
+}
+
+impl From<MsqidDs> for CompatMsqidDs {
+	fn from(ds: MsqidDs) -> Self {
+		Self {
+			msg_perm: ds.msg_perm.into(),
+			msg_stime: ds.msg_stime as _,
+			msg_stime_high: (ds.msg_stime as u64 >> 32) as _,
+			msg_rtime: ds.msg_rtime as _,
+			msg_rtime_high: (ds.msg_rtime as u64 >> 32) as _,
+			msg_ctime: ds.msg_ctime as _,
+			msg_ctime_high: (ds.msg_ctime as u64 >> 32) as _,
+			msg_cbytes: ds.msg_cbytes as _,
+			msg_qnum: ds.msg_qnum as _,
+			msg_qbytes: ds.msg_qbytes as _,
+			msg_lspid: ds.msg_lspid,
+			msg_lrpid: ds.msg_lrpid,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
+
+impl From<CompatMsqidDs> for MsqidDs {
+	fn from(ds: CompatMsqidDs) -> Self {
+		Self {
+			msg_perm: ds.msg_perm.into(),
+			msg_stime: ds.msg_stime as _,
+			msg_rtime: ds.msg_rtime as _,
+			msg_ctime: ds.msg_ctime as _,
+			msg_cbytes: ds.msg_cbytes as _,
+			msg_qnum: ds.msg_qnum as _,
+			msg_qbytes: ds.msg_qbytes as _,
+			msg_lspid: ds.msg_lspid,
+			msg_lrpid: ds.msg_lrpid,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn msg_select() {
+		let messages = [3, 1, 2, 1]
+			.into_iter()
+			.map(|mtype| Message {
+				mtype,
+				data: Vec::new(),
+			})
+			.collect::<utils::errno::CollectResult<Vec<_>>>()
+			.0
+			.unwrap();
+		assert_eq!(select(&messages, 0, 0), Some(0));
+		assert_eq!(select(&messages, 2, 0), Some(2));
+		assert_eq!(select(&messages, 4, 0), None);
+		assert_eq!(select(&messages, 3, MSG_EXCEPT), Some(1));
+		assert_eq!(select(&messages, -2, 0), Some(1));
+		assert_eq!(select(&messages, -5, 0), Some(1));
+		assert_eq!(select(&[], 0, 0), None);
+	}
+}
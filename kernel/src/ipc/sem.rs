@@ -0,0 +1,283 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V semaphores are counters grouped in sets, on which processes can atomically perform
+//! operations, blocking until all of them can proceed.
+//!
+//! When an operation is performed with [`SEM_UNDO`], the inverse adjustment is recorded for the
+//! process, and applied when it exits.
+
+use super::{IpcObject, IpcPerm, Registry};
+use crate::{
+	file::{wait_queue, wait_queue::WaitQueue},
+	process::pid::Pid,
+	sync::mutex::{IntMutex, Mutex},
+};
+use core::{ffi::c_short, intrinsics::unlikely};
+use utils::{
+	collections::{btreemap::BTreeMap, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+};
+
+/// Operation flag: undo the operation when the process exits.
+pub const SEM_UNDO: c_short = 0x1000;
+
+/// Command: get the value of a semaphore.
+pub const GETVAL: i32 = 12;
+/// Command: set the value of a semaphore.
+pub const SETVAL: i32 = 16;
+
+/// The maximum number of semaphores in a set.
+pub const SEMMSL: usize = 32000;
+/// The maximum number of operations in a single `semop` call.
+pub const SEMOPM: usize = 500;
+/// The maximum value of a semaphore.
+pub const SEMVMX: i32 = 32767;
+
+/// The registry of semaphore sets.
+///
+/// Interrupts are disabled while locked since the registry is accessed when a process exits.
+pub static SETS: IntMutex<Registry<SemSet>> = IntMutex::new(Registry::new());
+
+/// An operation on a semaphore, as passed to `semop`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Sembuf {
+	/// The index of the semaphore in the set.
+	pub sem_num: u16,
+	/// The value to add to the semaphore. If zero, the operation waits for the semaphore to be
+	/// zero.
+	pub sem_op: c_short,
+	/// Operation flags.
+	pub sem_flg: c_short,
+}
+
+/// The state of a semaphore set.
+#[derive(Debug)]
+struct SemState {
+	/// The values of the semaphores.
+	values: Vec<i32>,
+	/// For each process having performed operations with [`SEM_UNDO`], the adjustments to apply
+	/// to each semaphore when it exits.
+	undo: BTreeMap<Pid, Vec<i32>>,
+	/// Tells whether the set has been removed.
+	removed: bool,
+}
+
+/// A set of semaphores.
+#[derive(Debug)]
+pub struct SemSet {
+	/// Ownership and permissions.
+	perm: Mutex<IpcPerm>,
+	/// The state of the set.
+	state: IntMutex<SemState>,
+	/// The queue of processes waiting for an operation to proceed.
+	queue: WaitQueue,
+}
+
+impl SemSet {
+	/// Creates a new set of `nsems` semaphores, all initialized to zero.
+	pub fn new(perm: IpcPerm, nsems: usize) -> AllocResult<Self> {
+		let mut values = Vec::new();
+		values.resize(nsems, 0)?;
+		Ok(Self {
+			perm: Mutex::new(perm),
+			state: IntMutex::new(SemState {
+				values,
+				undo: BTreeMap::new(),
+				removed: false,
+			}),
+			queue: WaitQueue::new(),
+		})
+	}
+
+	/// Returns the number of semaphores in the set.
+	pub fn count(&self) -> usize {
+		self.state.lock().values.len()
+	}
+
+	/// Returns the value of the semaphore at index `num`.
+	///
+	/// If the index is out of bounds, the function returns [`errno::EINVAL`].
+	pub fn get(&self, num: usize) -> EResult<i32> {
+		self.state
+			.lock()
+			.values
+			.get(num)
+			.cloned()
+			.ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Sets the value of the semaphore at index `num`.
+	///
+	/// The pending adjustments of all processes on this semaphore are cleared.
+	///
+	/// If the index is out of bounds or the value is invalid, the function returns
+	/// [`errno::EINVAL`] or [`errno::ERANGE`].
+	pub fn set(&self, num: usize, val: i32) -> EResult<()> {
+		if unlikely(!(0..=SEMVMX).contains(&val)) {
+			return Err(errno!(ERANGE));
+		}
+		{
+			let mut state = self.state.lock();
+			let sem = state.values.get_mut(num).ok_or_else(|| errno!(EINVAL))?;
+			*sem = val;
+			for (_, adj) in state.undo.iter_mut() {
+				adj[num] = 0;
+			}
+		}
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Attempts to perform all the operations `ops` atomically, on behalf of the process `pid`.
+	///
+	/// If the operations cannot proceed yet, the function returns `None`.
+	fn try_apply(&self, pid: Pid, ops: &[Sembuf]) -> Option<EResult<()>> {
+		let mut state = self.state.lock();
+		if state.removed {
+			return Some(Err(errno!(EIDRM)));
+		}
+		// Check all operations can proceed
+		for op in ops {
+			let val = state.values[op.sem_num as usize];
+			let new = val + op.sem_op as i32;
+			let blocking = (op.sem_op == 0 && val != 0) || new < 0;
+			if blocking {
+				if op.sem_flg & super::IPC_NOWAIT as c_short != 0 {
+					return Some(Err(errno!(EAGAIN)));
+				}
+				return None;
+			}
+			if new > SEMVMX {
+				return Some(Err(errno!(ERANGE)));
+			}
+		}
+		// Record adjustments first, since it may fail
+		if ops.iter().any(|op| op.sem_flg & SEM_UNDO != 0) && !state.undo.contains_key(&pid) {
+			let mut adj = Vec::new();
+			let res = adj
+				.resize(state.values.len(), 0)
+				.and_then(|_| state.undo.insert(pid, adj));
+			if let Err(e) = res {
+				return Some(Err(e.into()));
+			}
+		}
+		let SemState {
+			values,
+			undo,
+			..
+		} = &mut *state;
+		for op in ops {
+			values[op.sem_num as usize] += op.sem_op as i32;
+			if op.sem_flg & SEM_UNDO != 0 {
+				// Cannot fail since the entry has been inserted above
+				undo.get_mut(&pid).unwrap()[op.sem_num as usize] -= op.sem_op as i32;
+			}
+		}
+		Some(Ok(()))
+	}
+
+	/// Performs all the operations `ops` atomically, on behalf of the process `pid`.
+	///
+	/// If the operations cannot proceed, the current process sleeps until they can, unless an
+	/// operation has the [`super::IPC_NOWAIT`] flag, in which case the function returns
+	/// [`errno::EAGAIN`].
+	///
+	/// If the set is removed while waiting, the function returns [`errno::EIDRM`].
+	pub fn apply(&self, pid: Pid, ops: &[Sembuf]) -> EResult<()> {
+		let len = self.count();
+		if unlikely(ops.iter().any(|op| op.sem_num as usize >= len)) {
+			return Err(errno!(EFBIG));
+		}
+		// Like on Linux, semaphore operations are never restarted after a signal handler
+		wait_queue::no_restart(self.queue.wait_until(|| self.try_apply(pid, ops)))??;
+		// Values have changed: other processes may proceed
+		self.queue.wake_all();
+		Ok(())
+	}
+
+	/// Applies the adjustments recorded for the process `pid`, which is exiting.
+	fn undo(&self, pid: Pid) {
+		{
+			let mut state = self.state.lock();
+			let Some(adj) = state.undo.remove(&pid) else {
+				return;
+			};
+			for (val, adj) in state.values.iter_mut().zip(adj.iter()) {
+				*val = (*val + adj).clamp(0, SEMVMX);
+			}
+		}
+		self.queue.wake_all();
+	}
+
+	/// Marks the set as removed, making waiting processes fail.
+	pub fn remove(&self) {
+		self.state.lock().removed = true;
+		self.queue.wake_all();
+	}
+}
+
+impl IpcObject for SemSet {
+	fn perm(&self) -> &Mutex<IpcPerm> {
+		&self.perm
+	}
+}
+
+/// Applies the adjustments recorded for the process `pid` on all semaphore sets.
+///
+/// This function must be called when the process exits.
+pub fn exit(pid: Pid) {
+	let sets = SETS.lock();
+	for set in sets.iter() {
+		set.undo(pid);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{file::perm::AccessProfile, ipc::IPC_PRIVATE};
+
+	#[test_case]
+	fn sem_try_apply() {
+		let perm = IpcPerm::new(IPC_PRIVATE, 0o600, &AccessProfile::KERNEL);
+		let set = SemSet::new(perm, 2).unwrap();
+		let op = |sem_num, sem_op, sem_flg| Sembuf {
+			sem_num,
+			sem_op,
+			sem_flg,
+		};
+		// Cannot decrement below zero
+		assert!(set.try_apply(1, &[op(0, -1, 0)]).is_none());
+		// Operations are applied atomically
+		assert!(set.try_apply(1, &[op(0, 1, 0), op(1, -1, 0)]).is_none());
+		assert_eq!(set.get(0).unwrap(), 0);
+		assert!(matches!(
+			set.try_apply(1, &[op(0, 2, SEM_UNDO), op(1, 1, 0)]),
+			Some(Ok(()))
+		));
+		assert_eq!(set.get(0).unwrap(), 2);
+		assert_eq!(set.get(1).unwrap(), 1);
+		// Undo on exit
+		set.undo(1);
+		assert_eq!(set.get(0).unwrap(), 0);
+		assert_eq!(set.get(1).unwrap(), 1);
+	}
+}
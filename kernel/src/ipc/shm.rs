@@ -0,0 +1,286 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! System V shared memory segments are chunks of memory which can be attached to the memory
+//! space of several processes at once.
+//!
+//! The pages of a segment are allocated at creation. A segment is freed when it has been
+//! removed and the last attachment is detached.
+
+use super::{CompatIpcPermUser, IpcObject, IpcPerm, IpcPermUser, Registry};
+use crate::{
+	file::Mode,
+	memory::cache::{FrameOwner, RcFrame},
+	process::pid::Pid,
+	sync::mutex::Mutex,
+	time::{
+		clock::{current_time_sec, Clock},
+		unit::Timestamp,
+	},
+};
+use core::ffi::{c_int, c_long, c_ulong};
+use utils::{
+	collections::vec::Vec,
+	errno::{AllocResult, CollectResult, EResult},
+	limits::PAGE_SIZE,
+	ptr::arc::Arc,
+};
+
+/// Attach flag: attach the segment read-only.
+pub const SHM_RDONLY: c_int = 0o10000;
+/// Attach flag: round the attach address down to [`SHMLBA`].
+pub const SHM_RND: c_int = 0o20000;
+/// Attach flag: allow the segment to be executed.
+pub const SHM_EXEC: c_int = 0o100000;
+
+/// Mode flag: the segment has been removed and is destroyed on last detach.
+pub const SHM_DEST: Mode = 0o1000;
+
+/// The minimum size of a segment in bytes.
+pub const SHMMIN: usize = 1;
+/// The maximum size of a segment in bytes.
+pub const SHMMAX: usize = usize::MAX - (1 << 24);
+/// The alignment of attach addresses.
+pub const SHMLBA: usize = PAGE_SIZE;
+
+/// The registry of shared memory segments.
+pub static SEGMENTS: Mutex<Registry<ShmSegment>> = Mutex::new(Registry::new());
+
+/// Usage information of a segment.
+#[derive(Debug)]
+struct ShmState {
+	/// The timestamp of the last attach, in seconds.
+	atime: Timestamp,
+	/// The timestamp of the last detach, in seconds.
+	dtime: Timestamp,
+	/// The timestamp of the last change, in seconds.
+	ctime: Timestamp,
+	/// The PID of the creator.
+	cpid: Pid,
+	/// The PID of the last process which attached or detached the segment.
+	lpid: Pid,
+	/// The number of attachments.
+	nattch: usize,
+}
+
+/// A shared memory segment.
+#[derive(Debug)]
+pub struct ShmSegment {
+	/// Ownership and permissions.
+	perm: Mutex<IpcPerm>,
+	/// The size of the segment in bytes, as requested at creation.
+	size: usize,
+	/// The pages of the segment.
+	pages: Vec<RcFrame>,
+	/// Usage information.
+	state: Mutex<ShmState>,
+}
+
+impl ShmSegment {
+	/// Creates a new segment of `size` bytes, initialized with zeros.
+	///
+	/// Arguments:
+	/// - `perm` is the ownership and permissions of the segment
+	/// - `cpid` is the PID of the creator
+	pub fn new(perm: IpcPerm, size: usize, cpid: Pid) -> EResult<Self> {
+		let pages = (0..size.div_ceil(PAGE_SIZE))
+			.map(|_| RcFrame::new_zeroed(0, FrameOwner::Anon, 0))
+			.collect::<AllocResult<CollectResult<Vec<_>>>>()?
+			.0?;
+		Ok(Self {
+			perm: Mutex::new(perm),
+			size,
+			pages,
+			state: Mutex::new(ShmState {
+				atime: 0,
+				dtime: 0,
+				ctime: current_time_sec(Clock::Realtime),
+				cpid,
+				lpid: 0,
+				nattch: 0,
+			}),
+		})
+	}
+
+	/// Returns the size of the segment in bytes.
+	pub fn size(&self) -> usize {
+		self.size
+	}
+
+	/// Returns the pages of the segment.
+	pub fn pages(&self) -> &[RcFrame] {
+		&self.pages
+	}
+
+	/// Updates usage information after the process `pid` detached the segment.
+	pub fn detached(&self, pid: Pid) {
+		let mut state = self.state.lock();
+		state.dtime = current_time_sec(Clock::Realtime);
+		state.lpid = pid;
+	}
+
+	/// Updates the time of the last change of the segment.
+	pub fn changed(&self) {
+		self.state.lock().ctime = current_time_sec(Clock::Realtime);
+	}
+
+	/// Returns the status of the segment.
+	pub fn stat(&self) -> ShmidDs {
+		let shm_perm = IpcPermUser::from(&*self.perm.lock());
+		let state = self.state.lock();
+		ShmidDs {
+			shm_perm,
+			shm_segsz: self.size,
+			shm_atime: state.atime as _,
+			shm_dtime: state.dtime as _,
+			shm_ctime: state.ctime as _,
+			shm_cpid: state.cpid as _,
+			shm_lpid: state.lpid as _,
+			shm_nattch: state.nattch as _,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
+
+impl IpcObject for ShmSegment {
+	fn perm(&self) -> &Mutex<IpcPerm> {
+		&self.perm
+	}
+}
+
+/// An attachment of a shared memory segment to a memory space.
+///
+/// The number of attachments of the segment is incremented when the structure is created or
+/// cloned, and decremented when it is dropped.
+#[derive(Debug)]
+pub struct ShmAttach(Arc<ShmSegment>);
+
+impl ShmAttach {
+	/// Attaches the segment `seg` for the process `pid`.
+	pub fn new(seg: Arc<ShmSegment>, pid: Pid) -> Self {
+		{
+			let mut state = seg.state.lock();
+			state.atime = current_time_sec(Clock::Realtime);
+			state.lpid = pid;
+			state.nattch += 1;
+		}
+		Self(seg)
+	}
+
+	/// Returns the attached segment.
+	pub fn segment(&self) -> &Arc<ShmSegment> {
+		&self.0
+	}
+}
+
+impl Clone for ShmAttach {
+	fn clone(&self) -> Self {
+		self.0.state.lock().nattch += 1;
+		Self(self.0.clone())
+	}
+}
+
+impl Drop for ShmAttach {
+	fn drop(&mut self) {
+		self.0.state.lock().nattch -= 1;
+	}
+}
+
+/// Userspace structure representing the status of a shared memory segment.
+#[repr(C)]
+#[derive(Debug)]
+pub struct ShmidDs {
+	/// Ownership and permissions.
+	pub shm_perm: IpcPermUser,
+	/// The size of the segment in bytes.
+	pub shm_segsz: usize,
+	/// The timestamp of the last attach.
+	pub shm_atime: c_long,
+	/// The timestamp of the last detach.
+	pub shm_dtime: c_long,
+	/// The timestamp of the last change.
+	pub shm_ctime: c_long,
+	/// The PID of the creator.
+	pub shm_cpid: c_int,
+	/// The PID of the last process which attached or detached the segment.
+	pub shm_lpid: c_int,
+	/// The number of attachments.
+	pub shm_nattch: c_ulong,
+	/// Reserved.
+	pub __unused4: c_ulong,
+	/// Reserved.
+	pub __unused5: c_ulong,
+}
+
+/// Compatibility version of [`ShmidDs`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct CompatShmidDs {
+	pub shm_perm: CompatIpcPermUser,
+	pub shm_segsz: u32,
+	pub shm_atime: u32,
+	pub shm_atime_high: u32,
+	pub shm_dtime: u32,
+	pub shm_dtime_high: u32,
+	pub shm_ctime: u32,
+	pub shm_ctime_high: u32,
+	pub shm_cpid: c_int,
+	pub shm_lpid: c_int,
+	pub shm_nattch: u32,
+	pub __unused4: u32,
+	pub __unused5: u32,
+}
+
+impl From<ShmidDs> for CompatShmidDs {
+	fn from(ds: ShmidDs) -> Self {
+		Self {
+			shm_perm: ds.shm_perm.into(),
+			shm_segsz: ds.shm_segsz as _,
+			shm_atime: ds.shm_atime as _,
+			shm_atime_high: (ds.shm_atime as u64 >> 32) as _,
+			shm_dtime: ds.shm_dtime as _,
+			shm_dtime_high: (ds.shm_dtime as u64 >> 32) as _,
+			shm_ctime: ds.shm_ctime as _,
+			shm_ctime_high: (ds.shm_ctime as u64 >> 32) as _,
+			shm_cpid: ds.shm_cpid,
+			shm_lpid: ds.shm_lpid,
+			shm_nattch: ds.shm_nattch as _,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
+
+impl From<CompatShmidDs> for ShmidDs {
+	fn from(ds: CompatShmidDs) -> Self {
+		Self {
+			shm_perm: ds.shm_perm.into(),
+			shm_segsz: ds.shm_segsz as _,
+			shm_atime: ds.shm_atime as _,
+			shm_dtime: ds.shm_dtime as _,
+			shm_ctime: ds.shm_ctime as _,
+			shm_cpid: ds.shm_cpid,
+			shm_lpid: ds.shm_lpid,
+			shm_nattch: ds.shm_nattch as _,
+			__unused4: 0,
+			__unused5: 0,
+		}
+	}
+}
@@ -22,7 +22,9 @@
 //! # Features
 //!
 //! The crate has the following features:
-//! - `strace`: if enabled, the kernel traces system calls. This is a debug feature.
+//! - `strace`: if enabled, the kernel traces system calls of processes for which tracing is
+//!   enabled (see the `-strace` command line argument and `/proc/[pid]/strace`). This is a debug
+//!   feature.
 
 #![no_std]
 #![no_main]
@@ -56,12 +58,16 @@ pub mod acpi;
 pub mod arch;
 mod boot;
 pub mod cmdline;
+pub mod cpu;
 pub mod crypto;
 pub mod debug;
 pub mod device;
 pub mod elf;
 pub mod event;
+#[cfg(feature = "fault_inject")]
+pub mod fault;
 pub mod file;
+pub mod ipc;
 pub mod logger;
 pub mod memory;
 pub mod module;
@@ -80,8 +86,13 @@ pub mod time;
 pub mod tty;
 
 use crate::{
-	arch::x86::{enable_sse, has_sse, idt, idt::IntFrame},
-	file::{fs::initramfs, vfs, vfs::ResolutionSettings},
+	arch::x86::{enable_alignment_check, enable_sse, features, idt, idt::IntFrame},
+	cmdline::RootDev,
+	file::{
+		fs::initramfs,
+		vfs,
+		vfs::{mountpoint, ResolutionSettings},
+	},
 	logger::LOGGER,
 	memory::{cache, vmem},
 	process::{
@@ -90,15 +101,14 @@ use crate::{
 		scheduler::{switch, switch::idle_task, SCHEDULER},
 		Process,
 	},
-	sync::mutex::Mutex,
 	tty::TTY,
 };
 use core::{ffi::c_void, intrinsics::unlikely};
 pub use utils;
 use utils::{
-	collections::{path::Path, string::String, vec::Vec},
+	collections::{path::Path, string::String},
 	errno::EResult,
-	vec,
+	vec, DisplayableStr, TryClone,
 };
 
 /// The kernel's name.
@@ -108,9 +118,8 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 /// The path to the init process binary.
 const INIT_PATH: &[u8] = b"/sbin/init";
-
-/// The current hostname of the system.
-pub static HOSTNAME: Mutex<Vec<u8>> = Mutex::new(Vec::new());
+/// The path to the init program of the initramfs.
+const RDINIT_PATH: &[u8] = b"/init";
 
 /// Launches the init process.
 ///
@@ -123,11 +132,14 @@ fn init(init_path: String) -> EResult<IntFrame> {
 		let path = Path::new(&init_path)?;
 		let rs = ResolutionSettings::kernel_follow();
 		let ent = vfs::get_file_from_path(path, &rs)?;
+		let execfn = vfs::Entry::get_path(&ent)?;
 		let program_image = exec::build_image(
 			ent,
 			ExecInfo {
 				path_resolution: &rs,
-				argv: vec![init_path]?,
+				path,
+				execfn: &execfn,
+				argv: vec![init_path.try_clone()?]?,
 				envp: vec![
 					b"PATH=/bin:/sbin:/usr/bin:/usr/sbin:/usr/local/bin:/usr/local/sbin"
 						.try_into()?,
@@ -144,15 +156,18 @@ fn init(init_path: String) -> EResult<IntFrame> {
 
 /// An inner function is required to ensure everything in scope is dropped before idle.
 fn kernel_main_inner(magic: u32, multiboot_ptr: *const c_void) {
-	// Initialize TTY
+	// Initialize TTY, then hand logs over to it
 	TTY.display.lock().show();
+	LOGGER.lock().init_tty();
 	#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 	{
+		features::init();
 		// Ensure the CPU has SSE
-		if !has_sse() {
+		if !features::get().sse {
 			panic!("SSE support is required to run this kernel :(");
 		}
 		enable_sse();
+		enable_alignment_check();
 		// Initialize IDT
 		idt::init();
 	}
@@ -177,6 +192,9 @@ fn kernel_main_inner(magic: u32, multiboot_ptr: *const c_void) {
 	elf::kernel::init()
 		.unwrap_or_else(|_| panic!("Cannot initialize kernel symbols map! (out of memory)"));
 
+	// Build the system call tables
+	syscall::init();
+
 	// Perform kernel self-tests
 	#[cfg(test)]
 	kernel_selftest();
@@ -191,25 +209,41 @@ fn kernel_main_inner(magic: u32, multiboot_ptr: *const c_void) {
 		}
 	};
 	LOGGER.lock().silent = args_parser.is_silent();
+	exec::aslr::set_enabled(!args_parser.is_noaslr());
 
 	println!("Booting Maestro kernel version {VERSION}");
 
-	// FIXME
-	//println!("Initializing ACPI...");
-	//acpi::init();
+	println!("Initializing ACPI...");
+	acpi::init();
 
 	println!("Initializing time management...");
 	time::init().unwrap_or_else(|e| panic!("Failed to initialize time management! ({e})"));
 
 	println!("Initializing devices management...");
 	device::init().unwrap_or_else(|e| panic!("Failed to initialize devices management! ({e})"));
-	net::osi::init().unwrap_or_else(|e| panic!("Failed to initialize network! ({e})"));
+	net::init().unwrap_or_else(|e| panic!("Failed to initialize network! ({e})"));
 	crypto::init()
 		.unwrap_or_else(|_| panic!("Failed to initialize cryptography! (out of memory)"));
 
-	let root = args_parser.get_root_dev();
+	// If the initramfs has an init program, it is in charge of mounting the root filesystem
+	let rdinit = boot_info.initramfs.is_some_and(initramfs::has_init);
+	let root = args_parser
+		.get_root_dev()
+		.filter(|_| !rdinit)
+		.map(|dev| match dev {
+			RootDev::Number(major, minor) => (major, minor),
+			RootDev::Path(path) => device::find_blk_by_path(path)
+				.map(|id| (id.major, id.minor))
+				.unwrap_or_else(|| panic!("Root device `{}` not found!", DisplayableStr(path))),
+		});
+	let root_flags = if args_parser.is_rdonly() && root.is_some() {
+		mountpoint::FLAG_RDONLY
+	} else {
+		0
+	};
 	println!("Initializing files management...");
-	file::init(root).unwrap_or_else(|e| panic!("Failed to initialize files management! ({e})"));
+	file::init(root, root_flags)
+		.unwrap_or_else(|e| panic!("Failed to initialize files management! ({e})"));
 	if let Some(initramfs) = boot_info.initramfs {
 		println!("Initializing initramfs...");
 		initramfs::load(initramfs)
@@ -221,10 +255,23 @@ fn kernel_main_inner(magic: u32, multiboot_ptr: *const c_void) {
 	process::init().unwrap_or_else(|e| panic!("Failed to init processes! ({e})"));
 	exec::vdso::init().unwrap_or_else(|e| panic!("Failed to load vDSO! ({e})"));
 
-	let init_path = args_parser.get_init_path().unwrap_or(INIT_PATH);
+	// Run integration tests instead of init if requested
+	if cmdline::flag("selftest") {
+		selftest::integration_runner();
+	}
+
+	let init_path = if rdinit {
+		RDINIT_PATH
+	} else {
+		args_parser.get_init_path().unwrap_or(INIT_PATH)
+	};
 	let init_path = String::try_from(init_path).unwrap();
 	let init_frame =
 		init(init_path).unwrap_or_else(|e| panic!("Cannot execute init process: {e}"));
+	#[cfg(feature = "strace")]
+	if args_parser.is_strace() {
+		Process::current().set_traced(true);
+	}
 
 	Process::new_kthread(None, cache::flush_task, true)
 		.unwrap_or_else(|e| panic!("Cannot launch the cache flush task: {e}"));
@@ -20,8 +20,14 @@
 //!
 //! If the logger is set as silent, logs will not show up on screen, but will be kept in memory
 //! anyway.
+//!
+//! Until the TTY is initialized, logs are written to the early console. Once it is, logs emitted
+//! so far are replayed on screen.
 
-use crate::{sync::mutex::IntMutex, tty::TTY};
+use crate::{
+	sync::mutex::IntMutex,
+	tty::{early, TTY},
+};
 use core::{
 	cmp::{min, Ordering},
 	fmt,
@@ -40,6 +46,8 @@ pub static LOGGER: IntMutex<Logger> = IntMutex::new(Logger::new());
 pub struct Logger {
 	/// Tells whether the logger is silent.
 	pub silent: bool,
+	/// Tells whether logs are displayed on the TTY. If not, the early console is used.
+	tty: bool,
 
 	/// The buffer storing the kernel logs.
 	buff: [u8; LOGS_SIZE],
@@ -55,6 +63,7 @@ impl Logger {
 	pub const fn new() -> Self {
 		Logger {
 			silent: false,
+			tty: false,
 
 			buff: [0; LOGS_SIZE],
 			read_head: 0,
@@ -82,6 +91,37 @@ impl Logger {
 		&self.buff
 	}
 
+	/// Returns the logs stored in the buffer, in order, as two slices.
+	fn content_slices(&self) -> (&[u8], &[u8]) {
+		if self.write_head >= self.read_head {
+			(&self.buff[self.read_head..self.write_head], &[])
+		} else {
+			(&self.buff[self.read_head..], &self.buff[..self.write_head])
+		}
+	}
+
+	/// Hands the display of logs over to the TTY, replaying the logs emitted so far on screen.
+	///
+	/// Those logs have already been written to the early console.
+	pub fn init_tty(&mut self) {
+		self.tty = true;
+		if self.silent {
+			return;
+		}
+		let (a, b) = self.content_slices();
+		let mut display = TTY.display.lock();
+		display.write_screen(a);
+		display.write_screen(b);
+	}
+
+	/// Falls back to the early console to display logs.
+	///
+	/// This is useful when the TTY cannot be used anymore, such as on a panic that occurred while
+	/// writing to it.
+	pub fn use_early_console(&mut self) {
+		self.tty = false;
+	}
+
 	/// Pushes the given string onto the kernel logs buffer.
 	pub fn push(&mut self, s: &[u8]) {
 		if self.available_space() < s.len() {
@@ -126,7 +166,11 @@ impl Write for Logger {
 	fn write_str(&mut self, s: &str) -> fmt::Result {
 		self.push(s.as_bytes());
 		if !self.silent {
-			TTY.display.lock().write(s.as_bytes());
+			if self.tty {
+				TTY.display.lock().write(s.as_bytes());
+			} else {
+				early::write(s.as_bytes());
+			}
 		}
 		Ok(())
 	}
@@ -415,6 +415,10 @@ pub fn alloc(order: FrameOrder, flags: Flags) -> AllocResult<PhysAddr> {
 	if unlikely(order > MAX_ORDER) {
 		return Err(AllocError);
 	}
+	#[cfg(feature = "fault_inject")]
+	if crate::fault::should_fail() {
+		return Err(AllocError);
+	}
 	// Select a zone and frame to allocate on
 	let begin_zone = (flags & ZONE_TYPE_MASK) as usize;
 	let mut guard = None;
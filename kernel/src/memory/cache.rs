@@ -48,13 +48,13 @@ use core::{
 	fmt::Formatter,
 	intrinsics::unlikely,
 	marker::PhantomData,
-	ops::Deref,
+	ops::{Deref, Range},
 	slice,
 	sync::atomic::Ordering::{Acquire, Release},
 };
 use utils::{
 	bytes::AnyRepr,
-	collections::{btreemap::BTreeMap, list::ListNode},
+	collections::{btreemap::BTreeMap, list::ListNode, vec::Vec},
 	errno::{AllocResult, EResult},
 	limits::PAGE_SIZE,
 	list, list_type,
@@ -209,6 +209,12 @@ impl RcFrame {
 		}
 	}
 
+	/// Tells whether the frame is anonymous, meaning it is not backed by a file or device.
+	#[inline]
+	pub fn is_anonymous(&self) -> bool {
+		self.0.owner.inner().is_none()
+	}
+
 	/// Returns the order of the frame.
 	#[inline]
 	pub fn order(&self) -> FrameOrder {
@@ -244,6 +250,25 @@ impl RcFrame {
 		}
 	}
 
+	/// Marks all the pages of the frame as dirty, so that they get written back to disk.
+	pub fn mark_dirty(&self) {
+		for n in 0..self.pages_count() {
+			self.get_page(n).dirty.store(true, Release);
+		}
+	}
+
+	/// Marks all the pages of the frame as clean, before they get written back to disk.
+	pub fn clear_dirty(&self) {
+		for n in 0..self.pages_count() {
+			self.get_page(n).dirty.store(false, Release);
+		}
+	}
+
+	/// Tells whether a page of the frame has been modified without being written back to disk.
+	pub fn is_dirty(&self) -> bool {
+		(0..self.pages_count()).any(|n| self.get_page(n).dirty.load(Acquire))
+	}
+
 	/// Writes dirty pages back to disk, if their timestamp has expired.
 	///
 	/// `ts` is the timestamp at which the frame is written. If `None`, the timestamp is ignored.
@@ -265,7 +290,7 @@ impl RcFrame {
 			// Write page
 			match &self.0.owner {
 				FrameOwner::Anon => {}
-				FrameOwner::BlkDev(blk) => blk.ops.write_pages(self.dev_offset(), self.slice())?,
+				FrameOwner::BlkDev(blk) => blk.write_pages(self.dev_offset(), self.slice())?,
 				FrameOwner::Node(node) => node.node_ops.write_frame(node, self)?,
 			}
 			// Update page metadata
@@ -302,14 +327,23 @@ impl<T: AnyRepr> RcFrameVal<T> {
 
 	/// Returns a mutable reference to the value.
 	///
+	/// The frame is marked as dirty.
+	///
 	/// # Safety
 	///
 	/// The caller must ensure no other reference to the value is living at the same time.
 	#[inline]
 	#[allow(clippy::mut_from_ref)]
 	pub unsafe fn as_mut(&self) -> &mut T {
+		self.frame.mark_dirty();
 		&mut self.frame.slice_mut()[self.off]
 	}
+
+	/// Marks the frame containing the value as dirty.
+	#[inline]
+	pub fn mark_dirty(&self) {
+		self.frame.mark_dirty();
+	}
 }
 
 impl<T: AnyRepr> Deref for RcFrameVal<T> {
@@ -378,16 +412,28 @@ impl MappedNode {
 	}
 
 	/// Synchronizes all frames in the cache back to disk.
+	///
+	/// Dirty pages are written regardless of their age.
 	pub fn sync(&self) -> EResult<()> {
-		let ts = current_time_ms(Clock::Boottime);
-		// Sync all frames
 		let frames = self.cache.lock();
 		for (_, frame) in frames.iter() {
-			frame.writeback(Some(ts))?;
+			frame.writeback(None)?;
 		}
 		Ok(())
 	}
 
+	/// Returns the frames of the cache having dirty pages, sorted by offset.
+	pub fn dirty_frames(&self) -> AllocResult<Vec<RcFrame>> {
+		let frames = self.cache.lock();
+		let mut dirty = Vec::new();
+		for (_, frame) in frames.iter() {
+			if frame.is_dirty() {
+				dirty.push(frame.clone())?;
+			}
+		}
+		Ok(dirty)
+	}
+
 	/// Removes, without flushing, all the pages after the offset `off` (included).
 	pub fn truncate(&self, off: u64) {
 		let mut lru = LRU.lock();
@@ -401,6 +447,24 @@ impl MappedNode {
 			retain
 		});
 	}
+
+	/// Removes, without flushing, the frames whose offset is in `range`.
+	///
+	/// Frames that are dirty or referenced outside the cache, such as by a memory mapping, are
+	/// kept.
+	pub fn invalidate(&self, range: Range<u64>) {
+		let mut lru = LRU.lock();
+		self.cache.lock().retain(|o, frame| {
+			// The references in `LRU` + `self` = 2
+			let retain = !range.contains(o) || Arc::strong_count(&frame.0) > 2 || frame.is_dirty();
+			if !retain {
+				unsafe {
+					lru.remove(&frame.0);
+				}
+			}
+			retain
+		});
+	}
 }
 
 /// Global cache for all frames
@@ -245,6 +245,43 @@ impl Chunk {
 		Some(next)
 	}
 
+	/// Splits the used chunk so that a new chunk begins with its data at `ptr`, and returns it.
+	///
+	/// The current chunk keeps the memory located before `ptr`. Both chunks are marked as used.
+	///
+	/// `ptr` must be aligned to [`ALIGNMENT`] and leave enough room for both chunks.
+	pub fn split_at(&mut self, ptr: *mut u8) -> &'static mut Chunk {
+		debug_assert!(self.used);
+		debug_assert!(ptr.is_aligned_to(ALIGNMENT));
+		let new_size = (ptr as usize) - size_of::<Chunk>() - (self.get_ptr() as usize);
+		debug_assert!(new_size >= get_min_chunk_size());
+		let next_size = self.size - new_size - size_of::<Chunk>();
+		debug_assert!(next_size > 0);
+		let next = unsafe { &mut *(ptr.sub(size_of::<Chunk>()) as *mut Chunk) };
+		unsafe {
+			ptr::write_volatile(
+				next,
+				Chunk {
+					#[cfg(config_debug_malloc_magic)]
+					magic: CHUNK_MAGIC,
+
+					prev: None,
+					next: None,
+
+					used: true,
+					size: next_size,
+				},
+			);
+		}
+		next.insert_after(self);
+		self.size = new_size;
+		#[cfg(config_debug_malloc_check)]
+		self.check();
+		#[cfg(config_debug_malloc_check)]
+		next.check();
+		next
+	}
+
 	/// Tries to coalesce the chunk it with adjacent chunks if they are free.
 	///
 	/// The current chunk is removed from the free list if present.
@@ -439,7 +476,7 @@ impl FreeChunk {
 static mut FREE_LISTS: [Option<NonNull<FreeChunk>>; FREE_LIST_BINS] = [None; FREE_LIST_BINS];
 
 /// Returns the minimum data size for a chunk.
-const fn get_min_chunk_size() -> usize {
+pub const fn get_min_chunk_size() -> usize {
 	let len = size_of::<FreeChunk>() - size_of::<Chunk>();
 	// Required because `max` is not `const`
 	if len > ALIGNMENT {
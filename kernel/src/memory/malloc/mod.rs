@@ -27,17 +27,26 @@ use chunk::Chunk;
 use core::{
 	alloc::{AllocError, Layout},
 	cmp::Ordering,
+	ffi::c_void,
 	intrinsics::unlikely,
+	mem::size_of,
 	num::NonZeroUsize,
 	ptr,
 	ptr::drop_in_place,
 };
-use utils::errno::AllocResult;
+use utils::{
+	errno,
+	errno::{AllocResult, Errno},
+};
 
 /// The allocator's mutex.
 static MUTEX: IntMutex<()> = IntMutex::new(());
 
 unsafe fn alloc(n: NonZeroUsize) -> AllocResult<NonNull<u8>> {
+	#[cfg(feature = "fault_inject")]
+	if crate::fault::should_fail() {
+		return Err(AllocError);
+	}
 	let _ = MUTEX.lock();
 	// Get free chunk
 	let free_chunk = chunk::get_available_chunk(n)?;
@@ -120,6 +129,58 @@ unsafe fn free(mut ptr: NonNull<u8>) {
 	super::trace::sample("malloc", super::trace::SampleOp::Free, ptr.as_ptr() as _, 0);
 }
 
+/// Allocates `n` bytes of memory, aligned to `align`.
+///
+/// `align` must be a power of two. Else, or if `n` is zero, the function returns
+/// [`errno::EINVAL`].
+///
+/// The returned pointer must be freed with [`free_aligned`].
+///
+/// # Safety
+///
+/// Allocated memory is not initialized.
+pub unsafe fn alloc_aligned(n: usize, align: usize) -> Result<*mut c_void, Errno> {
+	if unlikely(n == 0 || !align.is_power_of_two()) {
+		return Err(errno!(EINVAL));
+	}
+	if align <= chunk::ALIGNMENT {
+		return Ok(alloc(NonZeroUsize::new(n).unwrap())?.as_ptr() as _);
+	}
+	// Over-allocate to leave room for a leading chunk before the aligned pointer
+	let size = n
+		.checked_add(align + size_of::<Chunk>() + chunk::get_min_chunk_size())
+		.ok_or(AllocError)?;
+	let ptr = alloc(NonZeroUsize::new(size).unwrap())?;
+	if ptr.as_ptr().is_aligned_to(align) {
+		let chunk = Chunk::from_ptr(ptr.as_ptr());
+		chunk.shrink(chunk.get_size() - n);
+		return Ok(ptr.as_ptr() as _);
+	}
+	// The aligned pointer, leaving room for the leading chunk
+	let min_off = size_of::<Chunk>() + chunk::get_min_chunk_size();
+	let aligned = utils::align(ptr.as_ptr().add(min_off), align) as *mut u8;
+	let chunk = Chunk::from_ptr(ptr.as_ptr()).split_at(aligned);
+	// Release the leading chunk and the tail of the aligned chunk
+	free(ptr);
+	let excess = chunk.get_size() - n;
+	if excess > 0 {
+		chunk.shrink(excess);
+	}
+	Ok(aligned as _)
+}
+
+/// Frees memory allocated with [`alloc_aligned`].
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`alloc_aligned`] and must not be used after this function
+/// returns.
+pub unsafe fn free_aligned(ptr: *mut c_void) {
+	if let Some(ptr) = NonNull::new(ptr as *mut u8) {
+		free(ptr);
+	}
+}
+
 #[no_mangle]
 unsafe fn __alloc(layout: Layout) -> AllocResult<NonNull<[u8]>> {
 	let Some(size) = NonZeroUsize::new(layout.size()) else {
@@ -243,6 +304,42 @@ mod test {
 		assert_eq!(usage, buddy::allocated_pages_count());
 	}
 
+	fn aligned_test(align: usize) {
+		let usage = buddy::allocated_pages_count();
+		unsafe {
+			for size in [1, 100, PAGE_SIZE] {
+				let ptr = alloc_aligned(size, align).unwrap();
+				assert!(ptr.is_aligned_to(align));
+				slice::from_raw_parts_mut(ptr as *mut u8, size).fill(!0);
+				free_aligned(ptr);
+			}
+		}
+		assert_eq!(usage, buddy::allocated_pages_count());
+	}
+
+	#[test_case]
+	fn alloc_aligned64() {
+		aligned_test(64);
+	}
+
+	#[test_case]
+	fn alloc_aligned512() {
+		aligned_test(512);
+	}
+
+	#[test_case]
+	fn alloc_aligned4096() {
+		aligned_test(4096);
+	}
+
+	#[test_case]
+	fn alloc_aligned_invalid() {
+		unsafe {
+			assert!(alloc_aligned(16, 0).is_err());
+			assert!(alloc_aligned(16, 48).is_err());
+		}
+	}
+
 	// TODO Check the integrity of the data after reallocation
 	#[test_case]
 	fn realloc0() {
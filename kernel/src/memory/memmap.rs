@@ -67,6 +67,17 @@ pub(crate) fn print_entries() {
 	}
 }
 
+/// Tells whether the physical address `addr` is located in a region of RAM available to the
+/// system, according to the memory map.
+pub fn is_ram(addr: PhysAddr) -> bool {
+	(0..PHYS_MAP.memory_maps_size)
+		.step_by(PHYS_MAP.memory_maps_entry_size)
+		// Safe because in range
+		.map(|off| unsafe { &*PHYS_MAP.memory_maps.byte_add(off) })
+		.filter(|entry| entry.is_valid() && entry.type_ == multiboot::MEMORY_AVAILABLE)
+		.any(|entry| (entry.addr..(entry.addr + entry.len)).contains(&(addr.0 as u64)))
+}
+
 /// Computes and returns the physical address to the end of the kernel's ELF sections' content.
 fn sections_end(boot_info: &BootInfo) -> PhysAddr {
 	// The end of ELF sections list
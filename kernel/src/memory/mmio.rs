@@ -87,9 +87,10 @@ impl MMIO {
 	///
 	/// The previously allocated chunk is freed by this function.
 	pub fn unmap(&self) -> AllocResult<()> {
+		// Restore the mapping of the allocated virtual pages to their own physical memory
 		KERNEL_VMEM.lock().map_range(
-			self.phys_addr,
-			self.phys_addr.kernel_to_virtual().unwrap(),
+			self.virt_addr.kernel_to_physical().unwrap(),
+			self.virt_addr,
 			self.pages,
 			DEFAULT_FLAGS,
 		);
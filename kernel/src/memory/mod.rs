@@ -42,7 +42,10 @@ pub mod malloc;
 pub mod memmap;
 pub mod mmio;
 pub mod oom;
+pub mod overcommit;
+pub mod slab;
 pub mod stats;
+pub mod swap;
 #[cfg(feature = "memtrace")]
 mod trace;
 pub mod vmem;
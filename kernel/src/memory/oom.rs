@@ -24,25 +24,125 @@
 //!
 //! This is an emergency procedure which is not supposed to be used under normal conditions.
 
-use crate::{file::vfs, memory::cache};
-use utils::errno::AllocResult;
+use crate::{
+	file::vfs,
+	memory::{cache, stats::MEM_INFO, swap},
+	println,
+	process::{scheduler, scheduler::SCHEDULER, signal::Signal, Process, State},
+};
+use core::sync::atomic::Ordering::Relaxed;
+use utils::{errno::AllocResult, limits::PAGE_SIZE, ptr::arc::Arc};
 
-/// Attempts to reclaim memory from different places, or panics on failure.
-pub fn reclaim() {
+/// The minimum value of `oom_score_adj`, which exempts a process from the OOM killer.
+pub const OOM_SCORE_ADJ_MIN: i16 = -1000;
+/// The maximum value of `oom_score_adj`.
+pub const OOM_SCORE_ADJ_MAX: i16 = 1000;
+
+/// Attempts to reclaim memory from different places.
+///
+/// If no memory could be reclaimed, the function returns `false`.
+pub fn try_reclaim() -> bool {
 	// Attempt to shrink the page cache
 	if cache::shrink() {
-		return;
+		return true;
 	}
 	// Attempt to shrink the directory entries cache
 	if vfs::shrink_entries() {
+		return true;
+	}
+	// Attempt to swap memory to disk
+	if swap::swap_out() {
+		return true;
+	}
+	// Last resort: kill a process
+	kill()
+}
+
+/// Returns the total number of pages the processes can use, in memory or on swap.
+pub fn total_pages() -> usize {
+	let mem_total = MEM_INFO.lock().mem_total * 1024 / PAGE_SIZE;
+	mem_total + swap::stats().0
+}
+
+/// Returns the OOM score of a process using `pages` pages, in memory or on swap, with the
+/// adjustment `adj`.
+///
+/// `total` is the total number of pages (see [`total_pages`]). The adjustment shifts the score by
+/// `adj` thousandths of it, so that [`OOM_SCORE_ADJ_MAX`] makes the process preferred over any
+/// other, and [`OOM_SCORE_ADJ_MIN`] exempts it.
+///
+/// If the process must not be selected, the function returns `None`.
+pub fn badness(pages: usize, adj: i16, total: usize) -> Option<usize> {
+	if pages == 0 || adj <= OOM_SCORE_ADJ_MIN {
+		return None;
+	}
+	let points = pages as isize + adj as isize * (total / 1000) as isize;
+	// Processes using memory remain candidates, even with a negative adjustment
+	Some(points.max(1) as usize)
+}
+
+/// Kills the process with the highest OOM score and releases its memory.
+///
+/// If no process could be killed, the function returns `false`.
+fn kill() -> bool {
+	kill_among(|_| true)
+}
+
+/// Same as [`kill`], except only the processes for which `filter` returns `true` may be selected.
+///
+/// The score of a process is computed by [`badness`]. The init process is never selected.
+///
+/// Processes whose memory space is currently locked are skipped, since the function may be called
+/// by an allocation performed while holding the lock.
+///
+/// If no process could be killed, the function returns `false`.
+pub fn kill_among<F: Fn(&Process) -> bool>(filter: F) -> bool {
+	if !scheduler::is_initialized() {
+		return false;
+	}
+	let total = total_pages();
+	let victim = {
+		let Some(sched) = SCHEDULER.try_lock() else {
+			return false;
+		};
+		sched
+			.iter_process()
+			.filter(|(_, proc)| !proc.is_init() && proc.get_state() != State::Zombie)
+			.filter(|(_, proc)| filter(proc))
+			.filter_map(|(_, proc)| {
+				let mem_space = proc.mem_space.as_ref()?.try_lock()?;
+				let pages = mem_space.get_rss() + mem_space.get_swap_usage();
+				let score = badness(pages, proc.oom_score_adj.load(Relaxed), total)?;
+				Some((score, proc.clone()))
+			})
+			.max_by_key(|(score, _)| *score)
+	};
+	let Some((score, proc)) = victim else {
+		return false;
+	};
+	println!(
+		"Out of memory: killed process {pid} (score {score})",
+		pid = proc.get_pid()
+	);
+	proc.kill(Signal::SIGKILL);
+	// Release memory right away instead of waiting for the process to exit. This is not possible
+	// if the memory space is shared with another process, such as the parent of a `vfork`
+	let mem_space = proc
+		.mem_space
+		.as_ref()
+		.filter(|m| Arc::strong_count(m) == 1)
+		.and_then(|m| m.try_lock());
+	if let Some(mut mem_space) = mem_space {
+		mem_space.reap();
+	}
+	true
+}
+
+/// Attempts to reclaim memory from different places, or panics on failure.
+pub fn reclaim() {
+	if try_reclaim() {
 		return;
 	}
-	// TODO Attempt to:
-	// - swap memory to disk
-	// - if the kernel is configured for it, prompt the user to select processes to kill
-	// - if the kernel is configured for it, kill the process with the highest OOM score (ignore
-	//   init process)
-	// - else, panic:
 	panic!("Out of memory");
 }
 
@@ -59,3 +159,22 @@ pub fn wrap<T, F: FnMut() -> AllocResult<T>>(mut f: F) -> T {
 		// TODO Check if current process has been killed
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn oom_badness() {
+		let total = 100_000;
+		// Processes using no memory and exempt processes are never selected
+		assert_eq!(badness(0, 0, total), None);
+		assert_eq!(badness(1000, OOM_SCORE_ADJ_MIN, total), None);
+		assert_eq!(badness(1000, 0, total), Some(1000));
+		// The maximum adjustment prefers a small process over a process using all the memory
+		assert!(badness(1, OOM_SCORE_ADJ_MAX, total) > badness(total, 0, total));
+		// A negative adjustment lowers the score without exempting the process
+		assert_eq!(badness(1000, -500, total), Some(1));
+		assert_eq!(badness(1000, -5, total), Some(500));
+	}
+}
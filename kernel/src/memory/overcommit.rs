@@ -0,0 +1,123 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Overcommit accounting keeps track of the amount of memory processes may use, to refuse
+//! allocations that could not be satisfied.
+//!
+//! Since memory is allocated lazily, processes can reserve more memory than the system has.
+//! Memory is *committed* when a private writable mapping is created, since its pages may all be
+//! written to, requiring physical memory (or swap) to hold them.
+//!
+//! The policy is selected with the `vm.overcommit_memory` sysctl:
+//! - [`OVERCOMMIT_GUESS`]: refuses only allocations that obviously cannot be satisfied, that is
+//!   allocations larger than the total amount of memory and swap
+//! - [`OVERCOMMIT_ALWAYS`]: never refuses allocations
+//! - [`OVERCOMMIT_NEVER`]: refuses allocations making the total committed memory exceed the commit
+//!   limit
+//!
+//! When memory is overcommitted and runs out, the OOM killer is invoked to free memory.
+
+use crate::memory::{stats::MEM_INFO, swap};
+use core::{
+	alloc::AllocError,
+	intrinsics::unlikely,
+	sync::atomic::{AtomicU8, Ordering::Relaxed},
+};
+use utils::{
+	errno,
+	errno::{AllocResult, EResult},
+	limits::PAGE_SIZE,
+};
+
+/// Overcommit policy: heuristic overcommit.
+pub const OVERCOMMIT_GUESS: u8 = 0;
+/// Overcommit policy: always overcommit.
+pub const OVERCOMMIT_ALWAYS: u8 = 1;
+/// Overcommit policy: never overcommit.
+pub const OVERCOMMIT_NEVER: u8 = 2;
+
+/// The percentage of physical memory included in the commit limit, with [`OVERCOMMIT_NEVER`].
+const OVERCOMMIT_RATIO: usize = 50;
+
+/// The current overcommit policy.
+static POLICY: AtomicU8 = AtomicU8::new(OVERCOMMIT_GUESS);
+
+/// Returns the current overcommit policy.
+pub fn get_policy() -> u8 {
+	POLICY.load(Relaxed)
+}
+
+/// Sets the overcommit policy.
+///
+/// If the policy is invalid, the function returns [`errno::EINVAL`].
+pub fn set_policy(policy: u8) -> EResult<()> {
+	if unlikely(policy > OVERCOMMIT_NEVER) {
+		return Err(errno!(EINVAL));
+	}
+	POLICY.store(policy, Relaxed);
+	Ok(())
+}
+
+/// Returns the total size of swap areas in KiB.
+fn swap_total() -> usize {
+	let (swap_total, _) = swap::stats();
+	swap_total * PAGE_SIZE / 1024
+}
+
+/// Returns the commit limit in KiB, which is the amount of memory that can be committed with
+/// the [`OVERCOMMIT_NEVER`] policy.
+///
+/// `mem_total` is the total amount of physical memory in KiB.
+pub fn commit_limit(mem_total: usize) -> usize {
+	swap_total() + mem_total * OVERCOMMIT_RATIO / 100
+}
+
+/// Commits `pages` pages of memory.
+///
+/// If the commit is refused by the current policy, the function returns an error.
+pub fn charge(pages: usize) -> AllocResult<()> {
+	let size = pages * PAGE_SIZE / 1024;
+	// Read before locking statistics, to avoid holding both locks at once
+	let swap_total = swap_total();
+	let mut mem_info = MEM_INFO.lock();
+	let accept = match get_policy() {
+		OVERCOMMIT_ALWAYS => true,
+		OVERCOMMIT_NEVER => {
+			let limit = swap_total + mem_info.mem_total * OVERCOMMIT_RATIO / 100;
+			mem_info.committed_as + size <= limit
+		}
+		_ => size <= mem_info.mem_total + swap_total,
+	};
+	if unlikely(!accept) {
+		return Err(AllocError);
+	}
+	mem_info.committed_as += size;
+	Ok(())
+}
+
+/// Commits `pages` pages of memory, regardless of the current policy.
+///
+/// This is used when already committed memory is split in several parts.
+pub fn force_charge(pages: usize) {
+	MEM_INFO.lock().committed_as += pages * PAGE_SIZE / 1024;
+}
+
+/// Releases `pages` pages of committed memory.
+pub fn uncharge(pages: usize) {
+	MEM_INFO.lock().committed_as -= pages * PAGE_SIZE / 1024;
+}
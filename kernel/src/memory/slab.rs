@@ -0,0 +1,414 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The slab allocator provides caches of fixed-size objects.
+//!
+//! The kernel allocates many objects of the same size (nodes, processes, etc...). Using the
+//! general purpose allocator for those is wasteful since it has to split and merge chunks.
+//!
+//! Instead, a [`Cache`] takes pages from the buddy allocator (called *slabs*) and carves them
+//! into slots of the size of the object. A slab begins with a header, followed by its slots.
+//! Free slots are linked together to form a free list.
+//!
+//! Slabs are tracked in three lists, depending on their state:
+//! - **Partial**: some slots are used, some are free. Allocations are served from here first
+//! - **Full**: every slot is used
+//! - **Empty**: no slot is used. Only a few of them are kept, the rest is returned to the buddy
+//!   allocator
+
+use crate::{
+	memory::{buddy, VirtAddr},
+	sync::mutex::IntMutex,
+};
+use core::{
+	marker::PhantomData,
+	mem::{align_of, size_of},
+	ptr,
+	ptr::NonNull,
+};
+use utils::{collections::list::ListNode, errno::AllocResult, limits::PAGE_SIZE, list, list_type};
+
+/// The maximum number of empty slabs kept by a cache before returning them to the buddy
+/// allocator.
+const MAX_EMPTY_SLABS: usize = 2;
+
+/// A free slot, linked to the next free slot of the same slab.
+struct FreeSlot {
+	next: Option<NonNull<FreeSlot>>,
+}
+
+/// The header at the beginning of each slab.
+struct Slab {
+	/// The node in the cache's list.
+	node: ListNode,
+	/// The first free slot.
+	free: Option<NonNull<FreeSlot>>,
+	/// The number of used slots.
+	used: usize,
+}
+
+/// The inner state of a [`Cache`].
+struct CacheInner<T> {
+	/// Slabs with both used and free slots.
+	partial: list_type!(Slab, node),
+	/// Slabs with no free slot.
+	full: list_type!(Slab, node),
+	/// Slabs with no used slot.
+	empty: list_type!(Slab, node),
+	/// The number of slabs in `empty`.
+	empty_count: usize,
+
+	_object: PhantomData<T>,
+}
+
+/// The state of a slab, determining the list it belongs to.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum SlabState {
+	Empty,
+	Partial,
+	Full,
+}
+
+impl<T> CacheInner<T> {
+	/// Returns the state of a slab with `used` used slots.
+	fn state(used: usize) -> SlabState {
+		match used {
+			0 => SlabState::Empty,
+			n if n == Cache::<T>::CAPACITY => SlabState::Full,
+			_ => SlabState::Partial,
+		}
+	}
+
+	/// Returns the list for slabs in the given state.
+	fn list(&mut self, state: SlabState) -> &mut list_type!(Slab, node) {
+		match state {
+			SlabState::Empty => &mut self.empty,
+			SlabState::Partial => &mut self.partial,
+			SlabState::Full => &mut self.full,
+		}
+	}
+
+	/// Moves `slab` to the list matching its state, given its previous number of used slots
+	/// `prev_used`.
+	///
+	/// # Safety
+	///
+	/// `slab` must be a valid slab, inserted in the list matching `prev_used`.
+	unsafe fn relink(&mut self, slab: NonNull<Slab>, prev_used: usize) {
+		let prev = Self::state(prev_used);
+		let new = Self::state(slab.as_ref().used);
+		if prev == new {
+			return;
+		}
+		self.list(prev).remove_raw(slab);
+		self.list(new).insert_front_raw(slab);
+		if prev == SlabState::Empty {
+			self.empty_count -= 1;
+		} else if new == SlabState::Empty {
+			self.empty_count += 1;
+		}
+	}
+
+	/// Allocates a new empty slab from the buddy allocator and inserts it in the empty list.
+	fn grow(&mut self) -> AllocResult<NonNull<Slab>> {
+		let page = buddy::alloc_kernel(0, buddy::ZONE_KERNEL)?;
+		let slab = page.cast::<Slab>();
+		// Link all slots together
+		let mut free = None;
+		for i in (0..Cache::<T>::CAPACITY).rev() {
+			let slot = unsafe {
+				page.byte_add(Cache::<T>::HEADER_SIZE + i * Cache::<T>::SLOT_SIZE)
+					.cast::<FreeSlot>()
+			};
+			unsafe {
+				slot.write(FreeSlot {
+					next: free,
+				});
+			}
+			free = Some(slot);
+		}
+		unsafe {
+			slab.write(Slab {
+				node: ListNode::default(),
+				free,
+				used: 0,
+			});
+			self.empty.insert_front_raw(slab);
+		}
+		self.empty_count += 1;
+		Ok(slab)
+	}
+
+	/// Returns empty slabs to the buddy allocator until at most `keep` of them remain.
+	fn release_empty(&mut self, keep: usize) {
+		while self.empty_count > keep {
+			// Cannot fail since the count is non-zero
+			let slab = self.empty.front_raw().unwrap();
+			unsafe {
+				self.empty.remove_raw(slab);
+				buddy::free_kernel(slab.as_ptr() as _, 0);
+			}
+			self.empty_count -= 1;
+		}
+	}
+}
+
+impl<T> Drop for CacheInner<T> {
+	fn drop(&mut self) {
+		// Every object should have been freed at this point
+		debug_assert!(self.partial.front_raw().is_none());
+		debug_assert!(self.full.front_raw().is_none());
+		self.release_empty(0);
+	}
+}
+
+/// A cache of objects of type `T`.
+///
+/// Since slabs are a single page, `T` must fit in a page along with the slab's header.
+pub struct Cache<T> {
+	inner: IntMutex<CacheInner<T>>,
+}
+
+impl<T> Cache<T> {
+	/// The alignment of slots.
+	const ALIGN: usize = {
+		if align_of::<T>() > align_of::<FreeSlot>() {
+			align_of::<T>()
+		} else {
+			align_of::<FreeSlot>()
+		}
+	};
+	/// The number of slots in a slab.
+	pub const CAPACITY: usize = {
+		assert!(
+			Self::HEADER_SIZE + Self::SLOT_SIZE <= PAGE_SIZE,
+			"object too large for a slab"
+		);
+		(PAGE_SIZE - Self::HEADER_SIZE) / Self::SLOT_SIZE
+	};
+	/// The offset of the first slot in a slab.
+	const HEADER_SIZE: usize = size_of::<Slab>().next_multiple_of(Self::ALIGN);
+	/// The size of a slot in bytes. A slot must be able to hold a [`FreeSlot`] when unused.
+	const SLOT_SIZE: usize = {
+		let size = if size_of::<T>() > size_of::<FreeSlot>() {
+			size_of::<T>()
+		} else {
+			size_of::<FreeSlot>()
+		};
+		size.next_multiple_of(Self::ALIGN)
+	};
+
+	/// Creates a new, empty cache.
+	pub const fn new() -> Self {
+		Self {
+			inner: IntMutex::new(CacheInner {
+				partial: list!(Slab, node),
+				full: list!(Slab, node),
+				empty: list!(Slab, node),
+				empty_count: 0,
+
+				_object: PhantomData,
+			}),
+		}
+	}
+
+	/// Allocates a slot and moves `val` into it, returning a pointer to it.
+	pub fn alloc(&self, val: T) -> AllocResult<NonNull<T>> {
+		let mut inner = self.inner.lock();
+		// Prefer partial slabs to keep empty ones free for release
+		let slab = match inner
+			.partial
+			.front_raw()
+			.or_else(|| inner.empty.front_raw())
+		{
+			Some(slab) => slab,
+			None => inner.grow()?,
+		};
+		unsafe {
+			let (slot, prev_used) = {
+				let s = &mut *slab.as_ptr();
+				// Cannot fail since the slab is not full
+				let slot = s.free.unwrap();
+				s.free = slot.as_ref().next;
+				s.used += 1;
+				(slot, s.used - 1)
+			};
+			inner.relink(slab, prev_used);
+			let ptr = slot.cast::<T>();
+			ptr.write(val);
+			Ok(ptr)
+		}
+	}
+
+	/// Drops the object at `ptr` and frees its slot.
+	///
+	/// # Safety
+	///
+	/// `ptr` must have been returned by [`Self::alloc`] on the same cache, and must not be used
+	/// after this function returns.
+	pub unsafe fn free(&self, ptr: NonNull<T>) {
+		ptr::drop_in_place(ptr.as_ptr());
+		let slab = VirtAddr::from(ptr.as_ptr())
+			.down_align_to(PAGE_SIZE)
+			.as_ptr::<Slab>();
+		let slab = NonNull::new_unchecked(slab);
+		let mut inner = self.inner.lock();
+		let prev_used = {
+			let s = &mut *slab.as_ptr();
+			let slot = ptr.cast::<FreeSlot>();
+			slot.write(FreeSlot {
+				next: s.free,
+			});
+			s.free = Some(slot);
+			s.used -= 1;
+			s.used + 1
+		};
+		inner.relink(slab, prev_used);
+		inner.release_empty(MAX_EMPTY_SLABS);
+	}
+
+	/// Returns all empty slabs to the buddy allocator.
+	pub fn shrink(&self) {
+		self.inner.lock().release_empty(0);
+	}
+}
+
+impl<T> Default for Cache<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{arch::x86::rdtsc, println};
+	use utils::{boxed::Box, collections::vec::Vec};
+
+	struct Obj {
+		val: [usize; 8],
+	}
+
+	#[test_case]
+	fn slab_alloc_free() {
+		let cache = Cache::<Obj>::new();
+		let usage = buddy::allocated_pages_count();
+		let a = cache
+			.alloc(Obj {
+				val: [1; 8],
+			})
+			.unwrap();
+		let b = cache
+			.alloc(Obj {
+				val: [2; 8],
+			})
+			.unwrap();
+		assert_ne!(a, b);
+		unsafe {
+			assert_eq!(a.as_ref().val, [1; 8]);
+			assert_eq!(b.as_ref().val, [2; 8]);
+			cache.free(a);
+			// The freed slot is reused first
+			let c = cache
+				.alloc(Obj {
+					val: [3; 8],
+				})
+				.unwrap();
+			assert_eq!(a, c);
+			cache.free(b);
+			cache.free(c);
+		}
+		cache.shrink();
+		assert_eq!(usage, buddy::allocated_pages_count());
+	}
+
+	#[test_case]
+	fn slab_multiple_slabs() {
+		let cache = Cache::<Obj>::new();
+		let usage = buddy::allocated_pages_count();
+		let count = Cache::<Obj>::CAPACITY * 3 + 1;
+		let mut ptrs = Vec::with_capacity(count).unwrap();
+		for i in 0..count {
+			ptrs.push(
+				cache
+					.alloc(Obj {
+						val: [i; 8],
+					})
+					.unwrap(),
+			)
+			.unwrap();
+		}
+		for (i, p) in ptrs.iter().enumerate() {
+			unsafe {
+				assert_eq!(p.as_ref().val, [i; 8]);
+			}
+		}
+		for p in ptrs {
+			unsafe {
+				cache.free(p);
+			}
+		}
+		cache.shrink();
+		assert_eq!(usage, buddy::allocated_pages_count());
+	}
+
+	#[test_case]
+	fn slab_bench() {
+		const COUNT: usize = 1000;
+		let cache = Cache::<Obj>::new();
+		let mut ptrs = Vec::with_capacity(COUNT).unwrap();
+		let mut boxes = Vec::with_capacity(COUNT).unwrap();
+		// Slab cache
+		let start = rdtsc();
+		for i in 0..COUNT {
+			ptrs.push(
+				cache
+					.alloc(Obj {
+						val: [i; 8],
+					})
+					.unwrap(),
+			)
+			.unwrap();
+		}
+		for p in ptrs.iter() {
+			unsafe {
+				cache.free(*p);
+			}
+		}
+		let slab_cycles = rdtsc() - start;
+		// Generic allocator
+		let start = rdtsc();
+		for i in 0..COUNT {
+			boxes
+				.push(
+					Box::new(Obj {
+						val: [i; 8],
+					})
+					.unwrap(),
+				)
+				.unwrap();
+		}
+		boxes.clear();
+		let malloc_cycles = rdtsc() - start;
+		cache.shrink();
+		println!(
+			"slab: {} cycles, malloc: {} cycles ({COUNT} objects)",
+			slab_cycles, malloc_cycles
+		);
+	}
+}
@@ -36,6 +36,8 @@ pub struct MemInfo {
 	pub active: usize,
 	/// The total amount of inactive (not mapped but cached) memory.
 	pub inactive: usize,
+	/// The total amount of committed memory.
+	pub committed_as: usize,
 }
 
 impl Display for MemInfo {
@@ -46,8 +48,14 @@ impl Display for MemInfo {
 MemFree: {} kB
 MemAvailable: {} kB
 Active: {} kB
-Inactive: {} kB",
-			self.mem_total, self.mem_free, self.mem_available, self.active, self.inactive
+Inactive: {} kB
+Committed_AS: {} kB",
+			self.mem_total,
+			self.mem_free,
+			self.mem_available,
+			self.active,
+			self.inactive,
+			self.committed_as
 		)
 	}
 }
@@ -59,4 +67,5 @@ pub static MEM_INFO: Mutex<MemInfo> = Mutex::new(MemInfo {
 	mem_available: 0,
 	active: 0,
 	inactive: 0,
+	committed_as: 0,
 });
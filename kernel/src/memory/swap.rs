@@ -0,0 +1,410 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Swap allows to move anonymous pages of memory to disk under memory pressure, to make room for
+//! other allocations.
+//!
+//! A swap area is a block device or a regular file, initialized beforehand with the `mkswap`
+//! command. Its first page is a header, and each following page is a slot which can hold a page
+//! of memory.
+//!
+//! Pages to swap out are selected with the *clock* approximation of LRU: the first time a page is
+//! encountered, its accessed flag is cleared. If the flag is still clear the next time, the page
+//! has not been used in between and is written to swap.
+//!
+//! The page is then removed from its mapping and replaced by a [`SwapEntry`], which is used by
+//! the page fault handler to read the page back.
+
+use crate::{
+	device::BlkDev,
+	file::{File, FileType},
+	memory::{
+		buddy::ZONE_KERNEL,
+		cache::{FrameOwner, RcFrame},
+	},
+	process::scheduler::SCHEDULER,
+	sync::mutex::IntMutex,
+};
+use core::{
+	intrinsics::unlikely,
+	sync::atomic::{
+		AtomicI32, AtomicU32, AtomicUsize,
+		Ordering::{Relaxed, Release},
+	},
+};
+use macros::AnyRepr;
+use utils::{
+	bytes::from_bytes,
+	collections::vec::Vec,
+	errno,
+	errno::{CollectResult, EResult},
+	limits::PAGE_SIZE,
+	ptr::arc::Arc,
+	vec,
+};
+
+/// The magic string identifying a swap area, located at the end of its header.
+const SWAP_MAGIC: &[u8] = b"SWAPSPACE2";
+/// The offset of the magic string in the header.
+const SWAP_MAGIC_OFF: usize = PAGE_SIZE - SWAP_MAGIC.len();
+/// The offset of the header's information, after the space reserved for boot loaders.
+const HEADER_INFO_OFF: usize = 1024;
+/// The maximum number of swap areas that can be active at the same time.
+const MAX_SWAPFILES: usize = 32;
+/// The number of pages the reclaim procedure attempts to swap out at once.
+const SWAP_CLUSTER: usize = 32;
+/// Reference count value marking a slot that cannot be used.
+const SLOT_BAD: u32 = u32::MAX;
+
+/// Swap flag: the priority of the area is specified in the flags.
+pub const SWAP_FLAG_PREFER: i32 = 0x8000;
+/// Swap flag: mask of the priority of the area.
+pub const SWAP_FLAG_PRIO_MASK: i32 = 0x7fff;
+/// The set of valid swap flags.
+///
+/// Discard flags are accepted but ignored.
+pub const SWAP_FLAGS_VALID: i32 = 0x7ffff;
+
+/// The information in the header of a swap area, following the boot loader's reserved space.
+#[repr(C)]
+#[derive(AnyRepr, Clone, Copy)]
+struct SwapHeaderInfo {
+	/// The version of the swap area.
+	version: u32,
+	/// The index of the last usable page.
+	last_page: u32,
+	/// The number of bad pages.
+	nr_badpages: u32,
+	/// The UUID of the area.
+	sws_uuid: [u8; 16],
+	/// The label of the area.
+	sws_volume: [u8; 16],
+	/// Padding.
+	padding: [u32; 117],
+}
+
+/// An active swap area.
+#[derive(Debug)]
+pub struct SwapArea {
+	/// The open file of the area.
+	file: Arc<File>,
+	/// If the area is a block device, the device, which is used to bypass the page cache.
+	dev: Option<Arc<BlkDev>>,
+	/// The priority of the area. Areas with a higher priority are used first.
+	prio: i32,
+	/// The reference count of each slot. A count of zero means the slot is free.
+	slots: IntMutex<Vec<u32>>,
+	/// The number of usable slots.
+	total: usize,
+	/// The number of free slots.
+	free: AtomicUsize,
+}
+
+impl SwapArea {
+	/// Allocates a free slot in the area.
+	fn alloc_slot(this: &Arc<Self>) -> Option<SwapEntry> {
+		let mut slots = this.slots.lock();
+		let slot = slots.iter().position(|count| *count == 0)?;
+		slots[slot] = 1;
+		this.free.fetch_sub(1, Relaxed);
+		Some(SwapEntry {
+			area: this.clone(),
+			slot,
+		})
+	}
+
+	/// Writes the page `buf` in the slot `slot`.
+	fn write(&self, slot: usize, buf: &[u8]) -> EResult<()> {
+		match &self.dev {
+			Some(dev) => dev.write_pages(slot as _, buf),
+			None => {
+				let off = slot as u64 * PAGE_SIZE as u64;
+				let len = self.file.ops.write(&self.file, off, buf)?;
+				if unlikely(len < buf.len()) {
+					return Err(errno!(EIO));
+				}
+				Ok(())
+			}
+		}
+	}
+
+	/// Reads the page in the slot `slot` into a newly allocated frame.
+	fn read(&self, slot: usize) -> EResult<RcFrame> {
+		match &self.dev {
+			// Anonymous frames are not inserted in the device's cache
+			Some(dev) => dev.read_frame_direct(slot as _, 0, FrameOwner::Anon),
+			None => {
+				let frame = RcFrame::new(0, ZONE_KERNEL, FrameOwner::Anon, 0)?;
+				let off = slot as u64 * PAGE_SIZE as u64;
+				let buf = unsafe { frame.slice_mut() };
+				let len = self.file.ops.read(&self.file, off, buf)?;
+				if unlikely(len < buf.len()) {
+					return Err(errno!(EIO));
+				}
+				Ok(frame)
+			}
+		}
+	}
+
+	/// Tells whether the area is located on `file`'s node.
+	fn is_file(&self, file: &File) -> bool {
+		match (self.file.node(), file.node()) {
+			(Some(a), Some(b)) => Arc::as_ptr(a) == Arc::as_ptr(b),
+			_ => false,
+		}
+	}
+}
+
+/// A reference to a slot of a swap area containing a page of memory.
+///
+/// Cloning the entry increments the reference count of the slot, and dropping it decrements it.
+/// The slot is freed when the count reaches zero.
+#[derive(Debug)]
+pub struct SwapEntry {
+	/// The swap area.
+	area: Arc<SwapArea>,
+	/// The index of the slot in the area.
+	slot: usize,
+}
+
+impl SwapEntry {
+	/// Reads the page back from swap, into a newly allocated frame.
+	pub fn read(&self) -> EResult<RcFrame> {
+		self.area.read(self.slot)
+	}
+
+	/// Tells whether the entry is located on the swap area `area`.
+	pub fn is_on(&self, area: &Arc<SwapArea>) -> bool {
+		Arc::as_ptr(&self.area) == Arc::as_ptr(area)
+	}
+}
+
+impl Clone for SwapEntry {
+	fn clone(&self) -> Self {
+		self.area.slots.lock()[self.slot] += 1;
+		Self {
+			area: self.area.clone(),
+			slot: self.slot,
+		}
+	}
+}
+
+impl Drop for SwapEntry {
+	fn drop(&mut self) {
+		let mut slots = self.area.slots.lock();
+		slots[self.slot] -= 1;
+		if slots[self.slot] == 0 {
+			self.area.free.fetch_add(1, Release);
+		}
+	}
+}
+
+/// The list of active swap areas, sorted by decreasing priority.
+static AREAS: IntMutex<Vec<Arc<SwapArea>>> = IntMutex::new(Vec::new());
+/// The priority of the last area activated without an explicit priority.
+static LEAST_PRIO: AtomicI32 = AtomicI32::new(0);
+/// The PID of the process at which the next swap out scan begins.
+static CLOCK_HAND: AtomicU32 = AtomicU32::new(0);
+
+/// Activates the swap area on `file`.
+///
+/// `flags` is the set of swap flags.
+///
+/// If the file does not contain a valid swap area, the function returns [`errno::EINVAL`].
+pub fn swapon(file: Arc<File>, flags: i32) -> EResult<()> {
+	if unlikely(flags & !SWAP_FLAGS_VALID != 0) {
+		return Err(errno!(EINVAL));
+	}
+	let stat = file.stat()?;
+	let (dev, size) = match stat.get_type() {
+		Some(FileType::Regular) => (None, stat.size / PAGE_SIZE as u64),
+		Some(FileType::BlockDevice) => {
+			let dev = file.as_block_device().ok_or_else(|| errno!(ENODEV))?;
+			let size = dev.ops.blocks_count() * dev.ops.block_size().get() / PAGE_SIZE as u64;
+			(Some(dev), size)
+		}
+		_ => return Err(errno!(EINVAL)),
+	};
+	if AREAS.lock().iter().any(|a| a.is_file(&file)) {
+		return Err(errno!(EBUSY));
+	}
+	// Read and check header
+	let mut header = vec![0u8; PAGE_SIZE]?;
+	let len = file.ops.read(&file, 0, &mut header)?;
+	if unlikely(len < PAGE_SIZE || &header[SWAP_MAGIC_OFF..] != SWAP_MAGIC) {
+		return Err(errno!(EINVAL));
+	}
+	let info: &SwapHeaderInfo = from_bytes(&header[HEADER_INFO_OFF..]).unwrap();
+	if unlikely(info.version != 1) {
+		return Err(errno!(EINVAL));
+	}
+	let pages = size.min(info.last_page as u64 + 1) as usize;
+	if unlikely(pages < 2) {
+		return Err(errno!(EINVAL));
+	}
+	let mut slots = vec![0u32; pages]?;
+	// The first page is the header
+	slots[0] = SLOT_BAD;
+	let bad_off = HEADER_INFO_OFF + size_of::<SwapHeaderInfo>();
+	let bad_pages = header[bad_off..SWAP_MAGIC_OFF]
+		.chunks_exact(4)
+		.take(info.nr_badpages as usize)
+		.map(|b| u32::from_ne_bytes(b.try_into().unwrap()) as usize);
+	for page in bad_pages {
+		if let Some(slot) = slots.get_mut(page) {
+			*slot = SLOT_BAD;
+		}
+	}
+	let total = slots.iter().filter(|count| **count == 0).count();
+	let prio = if flags & SWAP_FLAG_PREFER != 0 {
+		flags & SWAP_FLAG_PRIO_MASK
+	} else {
+		LEAST_PRIO.fetch_sub(1, Relaxed) - 1
+	};
+	let area = Arc::new(SwapArea {
+		file,
+		dev,
+		prio,
+		slots: IntMutex::new(slots),
+		total,
+		free: AtomicUsize::new(total),
+	})?;
+	// Insert
+	let mut areas = AREAS.lock();
+	if unlikely(areas.len() >= MAX_SWAPFILES) {
+		return Err(errno!(EPERM));
+	}
+	let index = areas
+		.iter()
+		.position(|a| a.prio < prio)
+		.unwrap_or(areas.len());
+	areas.insert(index, area)?;
+	Ok(())
+}
+
+/// Deactivates the swap area on `file`, reading all the pages it contains back to memory.
+///
+/// If no swap area is active on `file`, the function returns [`errno::EINVAL`].
+pub fn swapoff(file: &File) -> EResult<()> {
+	let area = {
+		let mut areas = AREAS.lock();
+		let index = areas
+			.iter()
+			.position(|a| a.is_file(file))
+			.ok_or_else(|| errno!(EINVAL))?;
+		// Removing the area first prevents new pages from being written on it
+		areas.remove(index)
+	};
+	let res = (|| {
+		// Collect processes to avoid holding the scheduler's lock during I/O
+		let procs = SCHEDULER
+			.lock()
+			.iter_process()
+			.map(|(_, proc)| proc.clone())
+			.collect::<CollectResult<Vec<_>>>()
+			.0?;
+		for proc in procs {
+			let Some(mem_space) = proc.mem_space.as_ref() else {
+				continue;
+			};
+			mem_space.lock().swap_in_area(&area)?;
+		}
+		Ok(())
+	})();
+	if let Err(e) = res {
+		// Pages could not be read back. Reactivate the area
+		let mut areas = AREAS.lock();
+		let index = areas
+			.iter()
+			.position(|a| a.prio < area.prio)
+			.unwrap_or(areas.len());
+		areas.insert(index, area)?;
+		return Err(e);
+	}
+	Ok(())
+}
+
+/// Writes the page `buf` to the first active swap area with a free slot.
+///
+/// If no slot is available, the function returns [`errno::ENOSPC`].
+pub fn write_page(buf: &[u8]) -> EResult<SwapEntry> {
+	let entry = {
+		let areas = AREAS.try_lock().ok_or_else(|| errno!(EAGAIN))?;
+		areas
+			.iter()
+			.find_map(SwapArea::alloc_slot)
+			.ok_or_else(|| errno!(ENOSPC))?
+	};
+	// On failure, the entry is dropped, freeing the slot
+	entry.area.write(entry.slot, buf)?;
+	Ok(entry)
+}
+
+/// Returns the total number of slots on active swap areas, and the number of free slots.
+pub fn stats() -> (usize, usize) {
+	AREAS.lock().iter().fold((0, 0), |(total, free), a| {
+		(total + a.total, free + a.free.load(Relaxed))
+	})
+}
+
+/// Attempts to swap out pages that have not been used recently, to free memory.
+///
+/// Memory spaces that are currently locked are skipped, since the function may be called by an
+/// allocation performed while holding the lock.
+///
+/// If no page could be swapped out, the function returns `false`.
+pub fn swap_out() -> bool {
+	// Avoid scanning memory spaces if there is no free slot
+	let Some(areas) = AREAS.try_lock() else {
+		return false;
+	};
+	if areas.iter().all(|a| a.free.load(Relaxed) == 0) {
+		return false;
+	}
+	drop(areas);
+	let Some(sched) = SCHEDULER.try_lock() else {
+		return false;
+	};
+	let hand = CLOCK_HAND.load(Relaxed) as _;
+	let mut count = 0;
+	// The first pass may only clear accessed flags, then the second pass evicts
+	for _ in 0..2 {
+		let procs = sched
+			.iter_process()
+			.filter(|(pid, _)| **pid >= hand)
+			.chain(sched.iter_process().filter(|(pid, _)| **pid < hand));
+		for (pid, proc) in procs {
+			let Some(mem_space) = proc.mem_space.as_ref() else {
+				continue;
+			};
+			let Some(mut mem_space) = mem_space.try_lock() else {
+				continue;
+			};
+			count += mem_space.swap_out(SWAP_CLUSTER - count);
+			if count >= SWAP_CLUSTER {
+				// Resume from the next process next time
+				CLOCK_HAND.store(*pid as u32 + 1, Relaxed);
+				return true;
+			}
+		}
+		if count > 0 {
+			break;
+		}
+	}
+	count > 0
+}
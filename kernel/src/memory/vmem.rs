@@ -145,6 +145,18 @@ impl VMem {
 		x86::paging::poll_dirty(self.inner(), addr)
 	}
 
+	/// Polls the value of the accessed flag on the page at `addr`, clearing it atomically.
+	///
+	/// If the page is not mapped, the function returns `false`.
+	pub fn poll_accessed(&self, addr: VirtAddr) -> bool {
+		let accessed = x86::paging::poll_accessed(self.inner(), addr);
+		// Make sure the CPU sets the flag again on the next access
+		if accessed && self.is_bound() {
+			invalidate_page_current(addr);
+		}
+		accessed
+	}
+
 	/// Binds the virtual memory context to the current CPU.
 	pub fn bind(&self) {
 		let phys_addr = VirtAddr::from(self.table.as_ptr())
@@ -362,6 +374,33 @@ mod test {
 		}
 	}
 
+	#[test_case]
+	fn vmem_map_range() {
+		let mut vmem = unsafe { VMem::new() };
+		// Cross the boundary between two tables
+		let virtaddr = VirtAddr(0x400000 - PAGE_SIZE * 2);
+		let physaddr = PhysAddr(0x100000);
+		vmem.map_range(physaddr, virtaddr, 4, 0);
+		for i in 0..4 {
+			let res = vmem.translate(virtaddr + i * PAGE_SIZE);
+			assert_eq!(res, Some(physaddr + i * PAGE_SIZE));
+		}
+		// Unmap the middle of the range
+		vmem.unmap_range(virtaddr + PAGE_SIZE, 2);
+		assert_eq!(vmem.translate(virtaddr), Some(physaddr));
+		assert_eq!(vmem.translate(virtaddr + PAGE_SIZE), None);
+		assert_eq!(vmem.translate(virtaddr + PAGE_SIZE * 2), None);
+		assert_eq!(
+			vmem.translate(virtaddr + PAGE_SIZE * 3),
+			Some(physaddr + PAGE_SIZE * 3)
+		);
+		// Unmap everything
+		vmem.unmap_range(virtaddr, 4);
+		for i in (0..0xc0000000).step_by(PAGE_SIZE) {
+			assert_eq!(vmem.translate(VirtAddr(i)), None);
+		}
+	}
+
 	#[test_case]
 	fn vmem_unmap0() {
 		let mut vmem = unsafe { VMem::new() };
@@ -19,6 +19,7 @@
 //! TODO doc
 
 use core::ptr::NonNull;
+use utils::{collections::vec::Vec, errno::AllocResult};
 
 /// A linked-list of buffers representing a packet being built.
 ///
@@ -63,4 +64,16 @@ impl<'b> BuffList<'b> {
 
 		front
 	}
+
+	/// Copies the content of the buffer and following buffers into a contiguous buffer.
+	pub fn collect(&self) -> AllocResult<Vec<u8>> {
+		let mut buf = Vec::with_capacity(self.len())?;
+		let mut cur = Some(self);
+		while let Some(b) = cur {
+			buf.extend_from_slice(b.b)?;
+			// Safe since the list's lifetime binds the following buffers
+			cur = b.next.map(|next| unsafe { next.as_ref() });
+		}
+		Ok(buf)
+	}
 }
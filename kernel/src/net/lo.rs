@@ -18,15 +18,47 @@
 
 //! This module implements the local loopback.
 
-use super::{buff::BuffList, Address, BindAddress, Interface, MAC};
-use utils::errno::EResult;
+use super::{buff::BuffList, Address, BindAddress, Interface, Route, MAC};
+use utils::{
+	collections::{string::String, vec::Vec},
+	errno,
+	errno::EResult,
+};
+
+/// The name of the loopback interface.
+pub const NAME: &[u8] = b"lo";
+/// The Maximum Transmission Unit of the loopback interface.
+pub const MTU: usize = 65536;
+/// The maximum number of frames waiting to be received on the interface.
+const QUEUE_LEN: usize = 1000;
+
+/// The addresses bound to the loopback interface.
+static ADDRESSES: [BindAddress; 2] = [
+	BindAddress {
+		addr: Address::IPv4([127, 0, 0, 1]),
+		subnet_mask: 8,
+	},
+	BindAddress {
+		addr: Address::IPv6([
+			0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+			0x00, 0x01,
+		]),
+		subnet_mask: 128,
+	},
+];
 
 /// Local loopback interfaces allows the system to write data to itself.
-pub struct LocalLoopback {}
+///
+/// Every frame transmitted on the interface is queued to be received on the same interface.
+#[derive(Default)]
+pub struct LocalLoopback {
+	/// The frames waiting to be received, from the oldest to the newest.
+	queue: Vec<Vec<u8>>,
+}
 
 impl Interface for LocalLoopback {
 	fn get_name(&self) -> &[u8] {
-		b"lo"
+		NAME
 	}
 
 	fn is_up(&self) -> bool {
@@ -38,28 +70,46 @@ impl Interface for LocalLoopback {
 	}
 
 	fn get_addresses(&self) -> &[BindAddress] {
-		&[
-			BindAddress {
-				addr: Address::IPv4([127, 0, 0, 1]),
-				subnet_mask: 8,
-			},
-			BindAddress {
-				addr: Address::IPv6([
-					0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
-					0x00, 0x00, 0x01,
-				]),
-				subnet_mask: 128,
-			},
-		]
+		&ADDRESSES
 	}
 
-	fn read(&mut self, _buff: &mut [u8]) -> EResult<u64> {
-		// TODO Write to ring buffer
-		todo!();
+	fn read(&mut self, buff: &mut [u8]) -> EResult<u64> {
+		if self.queue.is_empty() {
+			return Err(errno!(EAGAIN));
+		}
+		// The end of a frame that does not fit in the buffer is discarded
+		let frame = self.queue.remove(0);
+		let len = frame.len().min(buff.len());
+		buff[..len].copy_from_slice(&frame[..len]);
+		Ok(len as _)
 	}
 
-	fn write(&mut self, _buff: &BuffList<'_>) -> EResult<u64> {
-		// TODO Read from ring buffer
-		todo!();
+	fn write(&mut self, buff: &BuffList<'_>) -> EResult<u64> {
+		if buff.len() > MTU {
+			return Err(errno!(EMSGSIZE));
+		}
+		if self.queue.len() >= QUEUE_LEN {
+			return Err(errno!(ENOBUFS));
+		}
+		let frame = buff.collect()?;
+		let len = frame.len();
+		self.queue.push(frame)?;
+		Ok(len as _)
 	}
 }
+
+/// Registers the loopback interface, along with the route to the loopback network
+/// (`127.0.0.0/8`).
+pub(super) fn init() -> EResult<()> {
+	super::register_iface(String::try_from(NAME)?, LocalLoopback::default())?;
+	super::ROUTING_TABLE.lock().push(Route {
+		dst: Some(BindAddress {
+			addr: Address::IPv4([127, 0, 0, 0]),
+			subnet_mask: 8,
+		}),
+		iface: String::try_from(NAME)?,
+		gateway: None,
+		metric: 0,
+	})?;
+	Ok(())
+}
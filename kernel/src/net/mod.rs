@@ -26,6 +26,7 @@ pub mod netlink;
 pub mod osi;
 pub mod sockaddr;
 pub mod tcp;
+pub mod unix;
 
 use crate::{
 	file::perm::AccessProfile,
@@ -37,7 +38,7 @@ use core::{cmp::Ordering, mem::size_of};
 use utils::{
 	collections::{hashmap::HashMap, string::String, vec::Vec},
 	errno,
-	errno::{EResult, Errno},
+	errno::{AllocResult, CollectResult, EResult, Errno},
 	ptr::arc::Arc,
 };
 
@@ -47,7 +48,7 @@ pub type MAC = [u8; 6];
 // TODO allow implementation of custom protocols
 
 /// An enumeration of network address types.
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Address {
 	/// Internet Protocol version 4.
 	IPv4([u8; 4]),
@@ -56,7 +57,7 @@ pub enum Address {
 }
 
 /// An address/subnet mask pair to be bound to an interface.
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct BindAddress {
 	/// The bound address.
 	pub addr: Address,
@@ -73,11 +74,12 @@ impl BindAddress {
 				.zip(b.array_chunks::<4>())
 				.enumerate()
 				.all(|(i, (a, b))| {
-					let a = u32::from_ne_bytes(*a);
-					let b = u32::from_ne_bytes(*b);
+					// Addresses are in network byte order
+					let a = u32::from_be_bytes(*a);
+					let b = u32::from_be_bytes(*b);
 
-					let order = 32 - mask.saturating_sub(i * 32);
-					let mask = !((1 << order) - 1);
+					let bits = mask.saturating_sub(i * 32).min(32);
+					let mask = u32::MAX.checked_shl(32 - bits as u32).unwrap_or(0);
 
 					(a & mask) == (b & mask)
 				})
@@ -105,9 +107,10 @@ pub trait Interface {
 	/// Returns the list of addresses bound to the interface.
 	fn get_addresses(&self) -> &[BindAddress];
 
-	/// Reads data from the network interface and writes it into `buff`.
+	/// Reads a frame received by the network interface and writes it into `buff`.
 	///
-	/// The function returns the number of bytes read.
+	/// The function returns the number of bytes read. If no frame is available, the function
+	/// returns [`errno::EAGAIN`].
 	fn read(&mut self, buff: &mut [u8]) -> EResult<u64>;
 
 	/// Reads data from `buff` and writes it into the network interface.
@@ -119,22 +122,23 @@ pub trait Interface {
 /// An entry in the routing table.
 pub struct Route {
 	/// The destination address. If `None`, this is the default destination.
-	dst: Option<BindAddress>,
+	pub dst: Option<BindAddress>,
 
 	/// The name of the network interface.
-	iface: String,
-	/// The gateway's address.
-	gateway: Address,
+	pub iface: String,
+	/// The gateway's address. If `None`, the destination is directly reachable through the
+	/// interface.
+	pub gateway: Option<Address>,
 
 	/// The route's metric. The route with the lowest metric has priority.
-	metric: u32,
+	pub metric: u32,
 }
 
 impl Route {
 	/// Tells whether the route matches the given address.
 	pub fn is_matching(&self, addr: &Address) -> bool {
 		// Check gateway
-		if &self.gateway == addr {
+		if self.gateway.as_ref() == Some(addr) {
 			return true;
 		}
 
@@ -152,8 +156,8 @@ impl Route {
 	/// Ordering is done so that the best route is the greatest.
 	pub fn cmp_for(&self, other: &Self, addr: &Address) -> Ordering {
 		// Check gateway
-		let self_match = addr == &self.gateway;
-		let other_match = addr == &other.gateway;
+		let self_match = self.gateway.as_ref() == Some(addr);
+		let other_match = other.gateway.as_ref() == Some(addr);
 
 		self_match
 			.cmp(&other_match)
@@ -176,9 +180,15 @@ impl Route {
 
 				self_match.cmp(&other_match)
 			})
+			.then_with(|| {
+				// The most specific network prefix has priority
+				let self_len = self.dst.map(|dst| dst.subnet_mask).unwrap_or(0);
+				let other_len = other.dst.map(|dst| dst.subnet_mask).unwrap_or(0);
+				self_len.cmp(&other_len)
+			})
 			.then_with(|| {
 				// Check metric
-				self.metric.cmp(&other.metric)
+				other.metric.cmp(&self.metric)
 			})
 	}
 }
@@ -194,19 +204,57 @@ pub static ROUTING_TABLE: Mutex<Vec<Route>> = Mutex::new(Vec::new());
 /// Arguments:
 /// - `name` is the name of the interface.
 /// - `iface` is the interface to register.
+///
+/// Packets sent to the addresses bound to the interface are routed to the loopback interface,
+/// since they are destined to the system itself.
 pub fn register_iface<I: 'static + Interface>(name: String, iface: I) -> EResult<()> {
-	let mut interfaces = INTERFACES.lock();
-
+	let mut local_routes = Vec::new();
+	for bind in iface.get_addresses() {
+		let subnet_mask = match bind.addr {
+			Address::IPv4(_) => 32,
+			Address::IPv6(_) => 128,
+		};
+		local_routes.push(Route {
+			dst: Some(BindAddress {
+				addr: bind.addr,
+				subnet_mask,
+			}),
+			iface: String::try_from(lo::NAME)?,
+			gateway: None,
+			metric: 0,
+		})?;
+	}
 	let i = Arc::new(Mutex::new(iface))?;
-	interfaces.insert(name, i)?;
-
+	INTERFACES.lock().insert(name, i)?;
+	ROUTING_TABLE.lock().append(&mut local_routes)?;
 	Ok(())
 }
 
-/// Unregisters the network interface with the given name.
+/// Unregisters the network interface with the given name, along with the routes using it.
 pub fn unregister_iface(name: &[u8]) {
-	let mut interfaces = INTERFACES.lock();
-	interfaces.remove(name);
+	let Some(iface) = INTERFACES.lock().remove(name) else {
+		return;
+	};
+	let iface = iface.lock();
+	let addresses = iface.get_addresses();
+	ROUTING_TABLE.lock().retain(|route| {
+		let local = route.gateway.is_none()
+			&& route.iface.as_bytes() == lo::NAME
+			&& addresses
+				.iter()
+				.any(|bind| route.dst.map(|dst| dst.addr) == Some(bind.addr));
+		route.iface.as_bytes() != name && !local
+	});
+}
+
+/// Returns the list of registered network interfaces.
+pub fn get_ifaces() -> AllocResult<Vec<Arc<Mutex<dyn Interface>>>> {
+	INTERFACES
+		.lock()
+		.iter()
+		.map(|(_, iface)| iface.clone())
+		.collect::<CollectResult<_>>()
+		.0
 }
 
 /// Returns the network interface with the given name.
@@ -226,6 +274,13 @@ pub fn get_iface_for(addr: Address) -> Option<Arc<Mutex<dyn Interface>>> {
 	get_iface(&route.iface)
 }
 
+/// Initializes the network stack.
+pub(crate) fn init() -> EResult<()> {
+	osi::init()?;
+	lo::init()?;
+	Ok(())
+}
+
 /// Enumeration of socket domains.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum SocketDomain {
@@ -290,6 +345,11 @@ impl AccessProfile {
 	}
 }
 
+/// Socket type flag: the socket is non-blocking.
+pub const SOCK_NONBLOCK: i32 = 0o4000;
+/// Socket type flag: the file descriptor is closed on `execve`.
+pub const SOCK_CLOEXEC: i32 = 0o2000000;
+
 /// Enumeration of socket types.
 #[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
 pub enum SocketType {
@@ -0,0 +1,604 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! UNIX domain sockets (`AF_UNIX`) allow local communication between processes.
+//!
+//! A socket can be bound to a name, either a path on the filesystem or a name in the abstract
+//! namespace (starting with a NUL byte), so that other sockets can connect or send datagrams to
+//! it.
+//!
+//! Data travels through [`Channel`]s: each socket receives from one and transmits to another. On
+//! connection, both sockets of the pair share the same two channels, cross-wired.
+
+use super::SocketType;
+use crate::{
+	file::{
+		socket::Socket,
+		vfs,
+		vfs::{ResolutionSettings, Resolved},
+		wait_queue, FileType, INode, Mode, Stat,
+	},
+	process::{signal::Signal, Process},
+	sync::mutex::Mutex,
+	time::clock::{current_time_sec, Clock},
+};
+use core::{ffi::c_short, intrinsics::unlikely, mem::size_of};
+use utils::{
+	collections::{hashmap::HashMap, path::PathBuf, ring_buffer::RingBuffer, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+	ptr::arc::Arc,
+	vec, TryClone,
+};
+
+/// The maximum length of a path in a `sockaddr_un` structure, including the terminating NUL
+/// byte.
+pub const UNIX_PATH_MAX: usize = 108;
+/// The default size of the buffer of a channel, in bytes.
+pub const DEFAULT_BUFFER_SIZE: usize = 65536;
+
+/// The address of a UNIX domain socket (the `sun_path` field of `sockaddr_un`).
+#[derive(Debug)]
+pub enum UnixAddr {
+	/// The socket has no name.
+	Unnamed,
+	/// A path on the filesystem.
+	Path(PathBuf),
+	/// A name in the abstract namespace, without the leading NUL byte.
+	Abstract(Vec<u8>),
+}
+
+impl UnixAddr {
+	/// Parses the given `sockaddr_un` structure.
+	///
+	/// On success, the function also returns the structure, stripped of the bytes following the
+	/// path's terminating NUL byte, to be returned as the name of the socket.
+	pub fn parse(sockaddr: &[u8]) -> EResult<(Self, Vec<u8>)> {
+		let family_len = size_of::<c_short>();
+		if unlikely(sockaddr.len() < family_len || sockaddr.len() > family_len + UNIX_PATH_MAX) {
+			return Err(errno!(EINVAL));
+		}
+		let path = &sockaddr[family_len..];
+		match path {
+			[] => Ok((Self::Unnamed, Vec::try_from(sockaddr)?)),
+			[0, name @ ..] => Ok((
+				Self::Abstract(Vec::try_from(name)?),
+				Vec::try_from(sockaddr)?,
+			)),
+			_ => {
+				// The path ends at the first NUL byte, if any
+				let len = path.iter().position(|b| *b == 0).unwrap_or(path.len());
+				let path_buf = PathBuf::try_from(&path[..len])?;
+				// The name always includes the terminating NUL byte
+				let mut name = Vec::try_from(&sockaddr[..family_len + len])?;
+				name.push(0)?;
+				Ok((Self::Path(path_buf), name))
+			}
+		}
+	}
+}
+
+/// A message queued on a message-oriented channel.
+#[derive(Debug)]
+struct Message {
+	/// The length of the message's data, stored in the channel's buffer.
+	len: usize,
+	/// The name of the sending socket.
+	addr: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct ChannelInner {
+	/// The buffered data.
+	buff: RingBuffer<u8, Vec<u8>>,
+	/// For message-oriented channels, the queued messages, from the oldest to the newest.
+	msgs: Option<Vec<Message>>,
+	/// If `true`, the receiving end is shut down: no more data can be written.
+	rd_shutdown: bool,
+	/// If `true`, the transmitting end is shut down: reading returns end-of-file once the buffer
+	/// is empty.
+	wr_shutdown: bool,
+}
+
+/// A unidirectional channel carrying data between sockets.
+#[derive(Debug)]
+pub struct Channel {
+	/// Inner with locking.
+	inner: Mutex<ChannelInner>,
+	/// The queue of processes waiting for data to be available.
+	rd_queue: wait_queue::WaitQueue,
+	/// The queue of processes waiting for space to be available.
+	wr_queue: wait_queue::WaitQueue,
+}
+
+impl Channel {
+	/// Creates a new channel.
+	///
+	/// If `messages` is `true`, message boundaries are preserved.
+	pub fn new(messages: bool) -> AllocResult<Arc<Self>> {
+		Arc::new(Self {
+			inner: Mutex::new(ChannelInner {
+				buff: RingBuffer::new(vec![0; DEFAULT_BUFFER_SIZE]?),
+				msgs: messages.then(Vec::new),
+				rd_shutdown: false,
+				wr_shutdown: false,
+			}),
+			rd_queue: Default::default(),
+			wr_queue: Default::default(),
+		})
+	}
+
+	/// Wakes processes waiting on the channel, since its state has changed.
+	fn notify(&self) {
+		self.rd_queue.wake_all();
+		self.wr_queue.wake_all();
+		wait_queue::poll_notify();
+	}
+
+	/// Returns the size of the channel's buffer, in bytes.
+	pub fn size(&self) -> usize {
+		self.inner.lock().buff.get_size()
+	}
+
+	/// Resizes the channel's buffer to `size` bytes.
+	///
+	/// Buffered data is kept, so the buffer is never shrunk below the size it requires.
+	pub fn resize(&self, size: usize) -> AllocResult<()> {
+		{
+			let mut inner = self.inner.lock();
+			let len = inner.buff.get_data_len();
+			let mut data = vec![0; len]?;
+			let mut buff = RingBuffer::new(vec![0; size.max(len + 1)]?);
+			inner.buff.read(&mut data);
+			buff.write(&data);
+			inner.buff = buff;
+		}
+		// Writers may have more space
+		self.notify();
+		Ok(())
+	}
+
+	/// Returns the number of bytes available for reading.
+	pub fn data_len(&self) -> usize {
+		let inner = self.inner.lock();
+		match &inner.msgs {
+			// Only the first message can be read at once
+			Some(msgs) => msgs.first().map(|msg| msg.len).unwrap_or(0),
+			None => inner.buff.get_data_len(),
+		}
+	}
+
+	/// Tells whether reading from the channel would not block.
+	pub fn is_readable(&self) -> bool {
+		let inner = self.inner.lock();
+		inner.buff.get_data_len() > 0
+			|| inner.msgs.as_ref().is_some_and(|msgs| !msgs.is_empty())
+			|| inner.wr_shutdown
+	}
+
+	/// Tells whether writing to the channel would not block.
+	pub fn is_writable(&self) -> bool {
+		let inner = self.inner.lock();
+		!inner.rd_shutdown && inner.buff.get_available_len() > 0
+	}
+
+	/// Tells whether the receiving end is shut down.
+	pub fn is_rd_shutdown(&self) -> bool {
+		self.inner.lock().rd_shutdown
+	}
+
+	/// Tells whether the transmitting end is shut down.
+	pub fn is_wr_shutdown(&self) -> bool {
+		self.inner.lock().wr_shutdown
+	}
+
+	/// Shuts down the receiving end of the channel.
+	pub fn shutdown_read(&self) {
+		self.inner.lock().rd_shutdown = true;
+		self.notify();
+	}
+
+	/// Shuts down the transmitting end of the channel.
+	pub fn shutdown_write(&self) {
+		self.inner.lock().wr_shutdown = true;
+		self.notify();
+	}
+
+	/// Reads data from the channel into `buf`.
+	///
+	/// Arguments:
+	/// - `peek` tells whether the data is left in the channel
+	/// - `nonblock` tells whether the function returns [`errno::EAGAIN`] instead of waiting for
+	///   data
+	///
+	/// On success, the function returns the number of bytes read along with the name of the
+	/// sending socket, for message-oriented channels. The end of a message that does not fit in
+	/// `buf` is discarded.
+	///
+	/// Once the transmitting end is shut down and all data has been read, the function returns
+	/// zero.
+	pub fn read(&self, buf: &mut [u8], peek: bool, nonblock: bool) -> EResult<(usize, Vec<u8>)> {
+		self.rd_queue.wait_until(|| {
+			let mut inner = self.inner.lock();
+			let inner = &mut *inner;
+			let res = match &mut inner.msgs {
+				Some(msgs) if !msgs.is_empty() => {
+					let len = msgs[0].len.min(buf.len());
+					if peek {
+						inner.buff.peek(&mut buf[..len]);
+						Vec::try_from(msgs[0].addr.as_slice())
+							.map(|addr| (len, addr))
+							.map_err(Into::into)
+					} else {
+						let msg = msgs.remove(0);
+						inner.buff.read(&mut buf[..len]);
+						// Discard the rest of the message
+						let mut rest = msg.len - len;
+						let mut discard = [0u8; 64];
+						while rest > 0 {
+							let n = rest.min(discard.len());
+							inner.buff.read(&mut discard[..n]);
+							rest -= n;
+						}
+						Ok((len, msg.addr))
+					}
+				}
+				None if inner.buff.get_data_len() > 0 || buf.is_empty() => {
+					let len = if peek {
+						inner.buff.peek(buf)
+					} else {
+						inner.buff.read(buf)
+					};
+					Ok((len, Vec::new()))
+				}
+				_ if inner.wr_shutdown || inner.rd_shutdown => Ok((0, Vec::new())),
+				_ if nonblock => Err(errno!(EAGAIN)),
+				_ => return None,
+			};
+			if !peek {
+				self.wr_queue.wake_all();
+				wait_queue::poll_notify();
+			}
+			Some(res)
+		})?
+	}
+
+	/// Writes data from `buf` to the channel.
+	///
+	/// Arguments:
+	/// - `addr` is the name of the sending socket, for message-oriented channels
+	/// - `nonblock` tells whether the function returns [`errno::EAGAIN`] instead of waiting for
+	///   space
+	/// - `nosignal` tells whether `SIGPIPE` is not sent to the current process if the receiving
+	///   end is shut down
+	///
+	/// On stream channels, data is written entirely unless the function is interrupted or
+	/// `nonblock` is set, in which case the function returns the number of bytes written so far.
+	///
+	/// On message-oriented channels, messages are written atomically, and messages larger than the
+	/// buffer are rejected with [`errno::EMSGSIZE`].
+	///
+	/// If the receiving end is shut down, on stream channels the function returns
+	/// [`errno::EPIPE`], and on message-oriented channels [`errno::ECONNREFUSED`].
+	pub fn write(
+		&self,
+		buf: &[u8],
+		addr: &[u8],
+		nonblock: bool,
+		nosignal: bool,
+	) -> EResult<usize> {
+		let mut off = 0;
+		loop {
+			let res = self.wr_queue.wait_until(|| {
+				let mut inner = self.inner.lock();
+				let inner = &mut *inner;
+				if inner.rd_shutdown {
+					if inner.msgs.is_some() {
+						return Some(Err(errno!(ECONNREFUSED)));
+					}
+					if !nosignal {
+						Process::current().kill(Signal::SIGPIPE);
+					}
+					return Some(Err(errno!(EPIPE)));
+				}
+				let available = inner.buff.get_available_len();
+				let Some(msgs) = &mut inner.msgs else {
+					let len = inner.buff.write(&buf[off..]);
+					if len == 0 {
+						return nonblock.then_some(Err(errno!(EAGAIN)));
+					}
+					self.rd_queue.wake_all();
+					wait_queue::poll_notify();
+					return Some(Ok(len));
+				};
+				if unlikely(buf.len() >= inner.buff.get_size()) {
+					return Some(Err(errno!(EMSGSIZE)));
+				}
+				if available < buf.len() {
+					return nonblock.then_some(Err(errno!(EAGAIN)));
+				}
+				let res = Vec::try_from(addr).and_then(|addr| {
+					msgs.push(Message {
+						len: buf.len(),
+						addr,
+					})
+				});
+				if let Err(e) = res {
+					return Some(Err(e.into()));
+				}
+				inner.buff.write(buf);
+				self.rd_queue.wake_all();
+				wait_queue::poll_notify();
+				Some(Ok(buf.len()))
+			});
+			match res {
+				Ok(Ok(len)) => off += len,
+				// Report the data written so far
+				Ok(Err(_)) | Err(_) if off > 0 => break,
+				Ok(Err(e)) | Err(e) => return Err(e),
+			}
+			if off >= buf.len() || nonblock {
+				break;
+			}
+		}
+		Ok(off)
+	}
+}
+
+#[derive(Debug)]
+struct ListenerInner {
+	/// If `true`, the socket is accepting connections.
+	listening: bool,
+	/// The maximum number of pending connections.
+	backlog: usize,
+	/// Connections waiting to be accepted. Each entry is the socket to be returned by `accept`,
+	/// already connected to its peer.
+	pending: Vec<Arc<Socket>>,
+}
+
+/// The side of a connection-oriented socket accepting connections from other sockets.
+#[derive(Debug)]
+pub struct Listener {
+	/// Inner with locking.
+	inner: Mutex<ListenerInner>,
+	/// The queue of processes waiting for a connection to be pending, or for space in the
+	/// backlog.
+	queue: wait_queue::WaitQueue,
+}
+
+impl Listener {
+	/// Creates a new instance, not listening.
+	pub fn new() -> AllocResult<Arc<Self>> {
+		Arc::new(Self {
+			inner: Mutex::new(ListenerInner {
+				listening: false,
+				backlog: 0,
+				pending: Vec::new(),
+			}),
+			queue: Default::default(),
+		})
+	}
+
+	/// Starts accepting connections.
+	///
+	/// Connecting processes wait while more than `backlog` connections are pending.
+	pub fn listen(&self, backlog: usize) {
+		let mut inner = self.inner.lock();
+		inner.listening = true;
+		inner.backlog = backlog;
+		drop(inner);
+		// Connecting processes may now have space in the backlog
+		self.queue.wake_all();
+	}
+
+	/// Tells whether the listener is accepting connections.
+	pub fn is_listening(&self) -> bool {
+		self.inner.lock().listening
+	}
+
+	/// Tells whether a connection is pending.
+	pub fn has_pending(&self) -> bool {
+		!self.inner.lock().pending.is_empty()
+	}
+
+	/// Stops accepting connections, closing pending ones.
+	pub fn close(&self) {
+		let pending = {
+			let mut inner = self.inner.lock();
+			inner.listening = false;
+			core::mem::take(&mut inner.pending)
+		};
+		for sock in pending {
+			sock.shutdown_reception();
+			sock.shutdown_transmit();
+		}
+		self.queue.wake_all();
+		wait_queue::poll_notify();
+	}
+
+	/// Queues the connection `sock`, waiting for space in the backlog.
+	///
+	/// If `nonblock` is set and the backlog is full, the function returns [`errno::EAGAIN`].
+	///
+	/// If the listener is not accepting connections, the function returns
+	/// [`errno::ECONNREFUSED`].
+	pub fn connect(&self, sock: Arc<Socket>, nonblock: bool) -> EResult<()> {
+		let mut sock = Some(sock);
+		self.queue.wait_until(|| {
+			let mut inner = self.inner.lock();
+			if !inner.listening {
+				return Some(Err(errno!(ECONNREFUSED)));
+			}
+			if inner.pending.len() > inner.backlog {
+				return nonblock.then_some(Err(errno!(EAGAIN)));
+			}
+			if let Err(e) = inner.pending.push(sock.take().unwrap()) {
+				return Some(Err(e.into()));
+			}
+			Some(Ok(()))
+		})??;
+		self.queue.wake_all();
+		wait_queue::poll_notify();
+		Ok(())
+	}
+
+	/// Returns the next pending connection, waiting for one if necessary.
+	///
+	/// If `nonblock` is set and no connection is pending, the function returns
+	/// [`errno::EAGAIN`].
+	pub fn accept(&self, nonblock: bool) -> EResult<Arc<Socket>> {
+		let sock = self.queue.wait_until(|| {
+			let mut inner = self.inner.lock();
+			if !inner.listening {
+				return Some(Err(errno!(EINVAL)));
+			}
+			if inner.pending.is_empty() {
+				return nonblock.then_some(Err(errno!(EAGAIN)));
+			}
+			Some(Ok(inner.pending.remove(0)))
+		})??;
+		// Connecting processes may now have space in the backlog
+		self.queue.wake_all();
+		Ok(sock)
+	}
+}
+
+/// The key identifying a bound socket.
+#[derive(Debug, Eq, Hash, PartialEq)]
+pub enum Key {
+	/// A socket file, identified by its device number and inode.
+	Node(u64, INode),
+	/// A name in the abstract namespace.
+	Abstract(Vec<u8>),
+}
+
+impl TryClone for Key {
+	fn try_clone(&self) -> AllocResult<Self> {
+		Ok(match self {
+			Self::Node(dev, inode) => Self::Node(*dev, *inode),
+			Self::Abstract(name) => Self::Abstract(name.try_clone()?),
+		})
+	}
+}
+
+/// A socket bound to a name, that can be reached by other sockets.
+#[derive(Clone, Debug)]
+pub struct Bound {
+	/// The type of the socket.
+	pub type_: SocketType,
+	/// The name of the socket.
+	pub name: Arc<Vec<u8>>,
+	/// The side of the socket accepting connections.
+	pub listener: Arc<Listener>,
+	/// The channel receiving data for the socket.
+	pub rx: Arc<Channel>,
+}
+
+/// The list of bound sockets.
+static BOUND: Mutex<HashMap<Key, Bound>> = Mutex::new(HashMap::new());
+
+/// Binds a socket to the name `addr`.
+///
+/// Arguments:
+/// - `bound` is the socket to bind
+/// - `rs` is the resolution settings for paths
+/// - `mode` is the mode of the socket file to create (before applying the umask)
+///
+/// If the name is already in use, the function returns [`errno::EADDRINUSE`].
+///
+/// On success, the function returns the key to release the name with [`unbind`].
+pub fn bind(addr: &UnixAddr, bound: Bound, rs: &ResolutionSettings, mode: Mode) -> EResult<Key> {
+	let key = match addr {
+		UnixAddr::Unnamed => return Err(errno!(EINVAL)),
+		UnixAddr::Path(path) => {
+			let rs = ResolutionSettings {
+				create: true,
+				follow_link: false,
+				..rs.clone()
+			};
+			let Resolved::Creatable {
+				parent,
+				name,
+			} = vfs::resolve_path(path, &rs)?
+			else {
+				return Err(errno!(EADDRINUSE));
+			};
+			let ts = current_time_sec(Clock::Realtime);
+			let ent = vfs::create_file(
+				parent,
+				name,
+				&rs.access_profile,
+				Stat {
+					mode: FileType::Socket.to_mode() | mode,
+					ctime: ts,
+					mtime: ts,
+					atime: ts,
+					..Default::default()
+				},
+			)?;
+			let node = ent.node();
+			Key::Node(node.fs.dev, node.inode)
+		}
+		UnixAddr::Abstract(name) => Key::Abstract(Vec::try_from(name.as_slice())?),
+	};
+	let mut sockets = BOUND.lock();
+	if sockets.get(&key).is_some() {
+		return Err(errno!(EADDRINUSE));
+	}
+	sockets.insert(key.try_clone()?, bound)?;
+	Ok(key)
+}
+
+/// Releases the name `key`, if bound to a socket whose listener is `listener`.
+pub fn unbind(key: &Key, listener: &Arc<Listener>) {
+	let mut sockets = BOUND.lock();
+	let matching = sockets
+		.get(key)
+		.is_some_and(|b| Arc::as_ptr(&b.listener) == Arc::as_ptr(listener));
+	if matching {
+		sockets.remove(key);
+	}
+}
+
+/// Returns the socket bound to the name `addr`.
+///
+/// If no socket is bound to the name, the function returns [`errno::ECONNREFUSED`]. If the path
+/// does not exist, the function returns [`errno::ENOENT`].
+pub fn lookup(addr: &UnixAddr, rs: &ResolutionSettings) -> EResult<Bound> {
+	let key = match addr {
+		UnixAddr::Unnamed => return Err(errno!(EINVAL)),
+		UnixAddr::Path(path) => {
+			let ent = vfs::get_file_from_path(path, rs)?;
+			let stat = ent.stat();
+			if stat.get_type() != Some(FileType::Socket) {
+				return Err(errno!(ECONNREFUSED));
+			}
+			if !rs.access_profile.can_write_file(&stat) {
+				return Err(errno!(EACCES));
+			}
+			let node = ent.node();
+			Key::Node(node.fs.dev, node.inode)
+		}
+		UnixAddr::Abstract(name) => Key::Abstract(Vec::try_from(name.as_slice())?),
+	};
+	BOUND
+		.lock()
+		.get(&key)
+		.cloned()
+		.ok_or_else(|| errno!(ECONNREFUSED))
+}
@@ -24,14 +24,27 @@
 
 #[cfg(config_debug_qemu)]
 use crate::debug::qemu;
-use crate::{arch::x86::cli, logger, memory::VirtAddr, power, register_get};
+use crate::{arch::x86::cli, logger::LOGGER, memory::VirtAddr, power, register_get, tty::TTY};
 use core::panic::PanicInfo;
 
 /// Called on Rust panic.
 #[panic_handler]
 fn panic(panic_info: &PanicInfo) -> ! {
 	cli();
-	logger::LOGGER.lock().silent = false;
+	// If the panic occurred while logging, the lock is held by a context that will never resume
+	if LOGGER.try_lock().is_none() {
+		unsafe {
+			LOGGER.unlock(false);
+		}
+	}
+	{
+		let mut logger = LOGGER.lock();
+		logger.silent = false;
+		// If the TTY is locked, the panic occurred while writing to it
+		if TTY.display.try_lock().is_none() {
+			logger.use_early_console();
+		}
+	}
 
 	#[cfg(test)]
 	{
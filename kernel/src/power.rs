@@ -18,9 +18,14 @@
 
 //! This module handles system power.
 
-use crate::arch::x86::{
-	cli, hlt,
-	io::{inb, outb},
+use crate::{
+	acpi,
+	arch::x86::{
+		cli, hlt,
+		io::{inb, outb},
+	},
+	println,
+	time::hw::pit,
 };
 use core::arch::asm;
 
@@ -34,17 +39,30 @@ pub fn halt() -> ! {
 }
 
 /// Powers the system down.
+///
+/// If ACPI is not available, the system is halted instead.
 pub fn shutdown() -> ! {
-	// TODO Use ACPI to power off the system
-	todo!()
+	cli();
+	acpi::shutdown();
+	halt();
 }
 
+/// The delay to wait for a reset method to take effect before trying the next one, in
+/// nanoseconds.
+const RESET_DELAY: u64 = 100_000_000;
+
 /// Reboots the system.
+///
+/// The function tries ACPI's reset register, then the PS/2 controller, then a triple fault.
 pub fn reboot() -> ! {
 	cli();
 	// First try: ACPI
-	// TODO Use ACPI reset to ensure everything reboots
+	if acpi::reboot() {
+		pit::busy_wait(RESET_DELAY);
+		println!("ACPI reset failed");
+	}
 	// Second try: PS/2
+	println!("Reset through the PS/2 controller");
 	loop {
 		let tmp = unsafe { inb(0x64) };
 		// Empty keyboard buffer
@@ -62,7 +80,9 @@ pub fn reboot() -> ! {
 	unsafe {
 		outb(0x64, 0xfe);
 	}
+	pit::busy_wait(RESET_DELAY);
 	// Third try: triple fault
+	println!("Reset through a triple fault");
 	unsafe {
 		asm!("push 0xffff", "push 0", "retf");
 	}
@@ -0,0 +1,341 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Control groups allow to limit the resources used by sets of processes.
+//!
+//! Groups are organized in a hierarchy, whose root contains every process by default. A process
+//! belongs to exactly one group, inherited from its parent on fork. The limits of a group apply to
+//! all the processes of its subtree.
+//!
+//! The following controllers are available:
+//! - CPU: the processes of a group may use at most `quota` nanoseconds of CPU time per `period`.
+//!   Once the quota is exhausted, they are not scheduled until the next period begins
+//! - Memory: the memory used by the processes of a group is limited. When a page fault happens
+//!   while the limit is exceeded, memory is reclaimed from the group, killing one of its processes
+//!   as a last resort
+//!
+//! Groups are managed from userspace through the `cgroup2` filesystem.
+
+use crate::{
+	memory::{oom, swap},
+	process::{pid::Pid, scheduler::SCHEDULER, Process, State},
+	sync::{atomic::AtomicU64, mutex::Mutex, once::OnceInit},
+	time::unit::Timestamp,
+};
+use core::{
+	ptr,
+	sync::atomic::{AtomicUsize, Ordering::Relaxed},
+};
+use utils::{
+	collections::{hashmap::HashMap, string::String, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+	limits::PAGE_SIZE,
+	ptr::arc::Arc,
+};
+
+/// The default period of the CPU controller, in nanoseconds.
+pub const DEFAULT_CPU_PERIOD: Timestamp = 100_000_000;
+/// The maximum number of pages to swap out from a group at once.
+const SWAP_CLUSTER: usize = 32;
+/// The maximum number of attempts to bring the memory usage of a group back under its limit
+/// before letting an allocation through.
+const MAX_RECLAIM_ROUNDS: usize = 8;
+
+/// The root group.
+static ROOT: OnceInit<Arc<Cgroup>> = unsafe { OnceInit::new() };
+
+/// A control group.
+#[derive(Debug)]
+pub struct Cgroup {
+	/// The name of the group.
+	pub name: String,
+	/// The parent group. If `None`, the group is the root.
+	pub parent: Option<Arc<Cgroup>>,
+	/// The child groups, by name.
+	pub children: Mutex<HashMap<String, Arc<Cgroup>>>,
+
+	/// The CPU time the group may use per period, in nanoseconds. [`u64::MAX`] if unlimited.
+	cpu_quota: AtomicU64,
+	/// The period of the CPU controller, in nanoseconds.
+	cpu_period: AtomicU64,
+	/// The timestamp at which the current period began, on the monotonic clock.
+	period_start: AtomicU64,
+	/// The CPU time used during the current period, in nanoseconds.
+	period_usage: AtomicU64,
+	/// The total CPU time used by the group, in nanoseconds.
+	cpu_usage: AtomicU64,
+	/// The number of periods that elapsed while the group had a quota.
+	nr_periods: AtomicU64,
+	/// The number of periods in which the group exhausted its quota.
+	nr_throttled: AtomicU64,
+
+	/// The maximum amount of memory the group may use, in bytes. [`usize::MAX`] if unlimited.
+	memory_max: AtomicUsize,
+}
+
+impl Cgroup {
+	/// Creates a group without limits.
+	fn new(name: String, parent: Option<Arc<Cgroup>>) -> AllocResult<Arc<Self>> {
+		Arc::new(Self {
+			name,
+			parent,
+			children: Mutex::new(HashMap::new()),
+
+			cpu_quota: AtomicU64::new(u64::MAX),
+			cpu_period: AtomicU64::new(DEFAULT_CPU_PERIOD),
+			period_start: AtomicU64::new(0),
+			period_usage: AtomicU64::new(0),
+			cpu_usage: AtomicU64::new(0),
+			nr_periods: AtomicU64::new(0),
+			nr_throttled: AtomicU64::new(0),
+
+			memory_max: AtomicUsize::new(usize::MAX),
+		})
+	}
+
+	/// Creates a child group of `parent` named `name`.
+	///
+	/// If a child with the same name already exists, the function returns [`errno::EEXIST`].
+	pub fn create_child(parent: &Arc<Self>, name: &[u8]) -> EResult<Arc<Self>> {
+		let mut children = parent.children.lock();
+		if children.get(name).is_some() {
+			return Err(errno!(EEXIST));
+		}
+		let child = Self::new(String::try_from(name)?, Some(parent.clone()))?;
+		children.insert(String::try_from(name)?, child.clone())?;
+		Ok(child)
+	}
+
+	/// Removes the child group named `name`.
+	///
+	/// If the child has children of its own or processes, the function returns
+	/// [`errno::EBUSY`].
+	pub fn remove_child(&self, name: &[u8]) -> EResult<()> {
+		let mut children = self.children.lock();
+		let child = children.get(name).ok_or_else(|| errno!(ENOENT))?;
+		if !child.children.lock().is_empty() || child.has_processes() {
+			return Err(errno!(EBUSY));
+		}
+		children.remove(name);
+		Ok(())
+	}
+
+	/// Tells whether `proc` belongs to the group or one of its descendants.
+	pub fn contains(&self, proc: &Process) -> bool {
+		let group = proc.cgroup();
+		let mut cur = Some(&group);
+		while let Some(g) = cur {
+			if ptr::eq(&**g, self) {
+				return true;
+			}
+			cur = g.parent.as_ref();
+		}
+		false
+	}
+
+	/// Tells whether `proc` belongs directly to the group.
+	fn is_member(&self, proc: &Process) -> bool {
+		proc.get_state() != State::Zombie && ptr::eq(&*proc.cgroup(), self)
+	}
+
+	/// Tells whether processes belong directly to the group.
+	pub fn has_processes(&self) -> bool {
+		SCHEDULER
+			.lock()
+			.iter_process()
+			.any(|(_, proc)| self.is_member(proc))
+	}
+
+	/// Returns the PIDs of the processes belonging directly to the group.
+	pub fn processes(&self) -> AllocResult<Vec<Pid>> {
+		let sched = SCHEDULER.lock();
+		let mut pids = Vec::new();
+		for (pid, proc) in sched.iter_process() {
+			if self.is_member(proc) {
+				pids.push(*pid)?;
+			}
+		}
+		Ok(pids)
+	}
+
+	/// Returns the CPU quota in nanoseconds, or `None` if unlimited, along with the period in
+	/// nanoseconds.
+	pub fn cpu_max(&self) -> (Option<Timestamp>, Timestamp) {
+		let quota = self.cpu_quota.load(Relaxed);
+		let period = self.cpu_period.load(Relaxed);
+		((quota != u64::MAX).then_some(quota), period)
+	}
+
+	/// Sets the CPU quota `quota` in nanoseconds, or `None` if unlimited, along with the period
+	/// `period` in nanoseconds.
+	pub fn set_cpu_max(&self, quota: Option<Timestamp>, period: Timestamp) {
+		self.cpu_quota.store(quota.unwrap_or(u64::MAX), Relaxed);
+		self.cpu_period.store(period, Relaxed);
+	}
+
+	/// Returns the total CPU time used by the group in nanoseconds, the number of elapsed periods
+	/// and the number of periods in which the group has been throttled.
+	pub fn cpu_stat(&self) -> (Timestamp, u64, u64) {
+		(
+			self.cpu_usage.load(Relaxed),
+			self.nr_periods.load(Relaxed),
+			self.nr_throttled.load(Relaxed),
+		)
+	}
+
+	/// Begins a new period if the current one is over at the timestamp `now`.
+	fn refresh_period(&self, now: Timestamp) {
+		let start = self.period_start.load(Relaxed);
+		if now.saturating_sub(start) < self.cpu_period.load(Relaxed) {
+			return;
+		}
+		let quota = self.cpu_quota.load(Relaxed);
+		if quota != u64::MAX {
+			self.nr_periods.fetch_add(1, Relaxed);
+			if self.period_usage.load(Relaxed) >= quota {
+				self.nr_throttled.fetch_add(1, Relaxed);
+			}
+		}
+		self.period_start.store(now, Relaxed);
+		self.period_usage.store(0, Relaxed);
+	}
+
+	/// Charges `delta` nanoseconds of CPU time to the group and its ancestors, at the timestamp
+	/// `now`.
+	pub fn charge_cpu(&self, delta: Timestamp, now: Timestamp) {
+		let mut cur = Some(self);
+		while let Some(g) = cur {
+			g.refresh_period(now);
+			g.period_usage.fetch_add(delta, Relaxed);
+			g.cpu_usage.fetch_add(delta, Relaxed);
+			cur = g.parent.as_deref();
+		}
+	}
+
+	/// Tells whether the group or one of its ancestors exhausted its CPU quota for the period
+	/// containing the timestamp `now`.
+	pub fn is_throttled(&self, now: Timestamp) -> bool {
+		let mut cur = Some(self);
+		while let Some(g) = cur {
+			g.refresh_period(now);
+			if g.period_usage.load(Relaxed) >= g.cpu_quota.load(Relaxed) {
+				return true;
+			}
+			cur = g.parent.as_deref();
+		}
+		false
+	}
+
+	/// Returns the maximum amount of memory the group may use in bytes, or `None` if unlimited.
+	pub fn memory_max(&self) -> Option<usize> {
+		let max = self.memory_max.load(Relaxed);
+		(max != usize::MAX).then_some(max)
+	}
+
+	/// Sets the maximum amount of memory the group may use in bytes, or `None` if unlimited.
+	pub fn set_memory_max(&self, max: Option<usize>) {
+		self.memory_max.store(max.unwrap_or(usize::MAX), Relaxed);
+	}
+
+	/// Returns the amount of memory used by the processes of the group's subtree, in bytes.
+	///
+	/// Memory spaces that are currently locked are not accounted for.
+	pub fn memory_usage(&self) -> usize {
+		let Some(sched) = SCHEDULER.try_lock() else {
+			return 0;
+		};
+		let pages: usize = sched
+			.iter_process()
+			.filter(|(_, proc)| self.contains(proc))
+			.filter_map(|(_, proc)| Some(proc.mem_space.as_ref()?.try_lock()?.get_rss()))
+			.sum();
+		pages * PAGE_SIZE
+	}
+
+	/// Writes pages of the processes of the group's subtree to swap.
+	///
+	/// If no page could be swapped out, the function returns `false`.
+	fn swap_out(&self) -> bool {
+		if swap::stats().1 == 0 {
+			return false;
+		}
+		let Some(sched) = SCHEDULER.try_lock() else {
+			return false;
+		};
+		let mut count = 0;
+		// The first pass may only clear accessed flags, then the second pass evicts
+		for _ in 0..2 {
+			for (_, proc) in sched.iter_process().filter(|(_, proc)| self.contains(proc)) {
+				let Some(mut mem_space) = proc.mem_space.as_ref().and_then(|m| m.try_lock())
+				else {
+					continue;
+				};
+				count += mem_space.swap_out(SWAP_CLUSTER - count);
+				if count >= SWAP_CLUSTER {
+					return true;
+				}
+			}
+			if count > 0 {
+				break;
+			}
+		}
+		count > 0
+	}
+}
+
+/// Initializes the root group.
+pub(super) fn init() -> AllocResult<()> {
+	let root = Cgroup::new(String::new(), None)?;
+	unsafe {
+		OnceInit::init(&ROOT, root);
+	}
+	Ok(())
+}
+
+/// Returns the root group.
+pub fn root() -> &'static Arc<Cgroup> {
+	&ROOT
+}
+
+/// Brings the memory usage of the groups `proc` belongs to back under their limits, before a
+/// page is allocated for it.
+///
+/// Memory is first swapped out from the processes of the group exceeding its limit. As a last
+/// resort, the process of the group using the most memory is killed, which may be `proc` itself.
+pub fn enforce_memory_limit(proc: &Process) {
+	let group = proc.cgroup();
+	for _ in 0..MAX_RECLAIM_ROUNDS {
+		let mut cur = Some(&group);
+		let exceeded = loop {
+			let Some(g) = cur else {
+				return;
+			};
+			if g.memory_max().is_some_and(|max| g.memory_usage() >= max) {
+				break g;
+			}
+			cur = g.parent.as_ref();
+		};
+		if exceeded.swap_out() {
+			continue;
+		}
+		if !oom::kill_among(|p| exceeded.contains(p)) {
+			return;
+		}
+	}
+}
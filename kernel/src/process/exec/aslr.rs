@@ -0,0 +1,150 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Address Space Layout Randomization (ASLR).
+//!
+//! When a program is executed, the load base of position-independent executables, the user stack
+//! and the vDSO are placed at random page-aligned offsets, which makes addresses harder to
+//! predict for an attacker.
+//!
+//! ASLR can be disabled at boot with the `-noaslr` command line argument for debugging, in which
+//! case the layout is deterministic.
+
+use crate::{
+	crypto::rand,
+	memory::{VirtAddr, ALLOC_BEGIN, PROCESS_END},
+	process::mem_space::MapConstraint,
+};
+use core::{
+	mem::size_of,
+	sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+use utils::limits::PAGE_SIZE;
+
+/// The number of bits of randomness, in pages, for the load base of executables.
+#[cfg(target_arch = "x86")]
+const PIE_BITS: u32 = 16;
+/// The number of bits of randomness, in pages, for the load base of executables.
+#[cfg(target_arch = "x86_64")]
+const PIE_BITS: u32 = 28;
+/// The number of bits of randomness, in pages, for the top of the user stack.
+#[cfg(target_arch = "x86")]
+const STACK_BITS: u32 = 11;
+/// The number of bits of randomness, in pages, for the top of the user stack.
+#[cfg(target_arch = "x86_64")]
+const STACK_BITS: u32 = 22;
+/// The number of bits of randomness, in pages, for the vDSO.
+#[cfg(target_arch = "x86")]
+const MMAP_BITS: u32 = 16;
+/// The number of bits of randomness, in pages, for the vDSO.
+#[cfg(target_arch = "x86_64")]
+const MMAP_BITS: u32 = 28;
+
+/// The lowest load base for position-independent executables when ASLR is enabled.
+///
+/// On x86, executables are placed below [`ALLOC_BEGIN`] so that the program break has room to
+/// grow.
+#[cfg(target_arch = "x86")]
+const PIE_BASE: usize = PAGE_SIZE;
+/// The lowest load base for position-independent executables when ASLR is enabled.
+#[cfg(target_arch = "x86_64")]
+const PIE_BASE: usize = 0x555555554000;
+/// The highest possible address for the top of the user stack, right below the copy buffer.
+const STACK_TOP: usize = PROCESS_END.0 - PAGE_SIZE;
+
+/// Tells whether ASLR is enabled.
+static ENABLED: AtomicBool = AtomicBool::new(true);
+
+/// Enables or disables ASLR for the next program executions.
+pub fn set_enabled(enabled: bool) {
+	ENABLED.store(enabled, Relaxed);
+}
+
+/// Tells whether ASLR is enabled.
+pub fn is_enabled() -> bool {
+	ENABLED.load(Relaxed)
+}
+
+/// Returns a random offset in bytes, made of `bits` bits of pages.
+///
+/// If ASLR is disabled, the function returns `0`.
+fn random_offset(bits: u32) -> usize {
+	if !is_enabled() {
+		return 0;
+	}
+	let mut buf = [0; size_of::<usize>()];
+	rand::getrandom(&mut buf);
+	let pages = usize::from_ne_bytes(buf) & ((1 << bits) - 1);
+	pages * PAGE_SIZE
+}
+
+/// Returns the load base for a position-independent executable.
+pub fn pie_base() -> VirtAddr {
+	if is_enabled() {
+		VirtAddr(PIE_BASE + random_offset(PIE_BITS))
+	} else {
+		VirtAddr(PAGE_SIZE)
+	}
+}
+
+/// Returns the constraint to map a user stack of `size` pages.
+pub fn stack_constraint(size: usize) -> MapConstraint {
+	if is_enabled() {
+		let top = STACK_TOP - random_offset(STACK_BITS);
+		MapConstraint::Hint(VirtAddr(top - size * PAGE_SIZE))
+	} else {
+		MapConstraint::None
+	}
+}
+
+/// Returns the constraint to map the vDSO.
+pub fn vdso_constraint() -> MapConstraint {
+	if is_enabled() {
+		MapConstraint::Hint(ALLOC_BEGIN + random_offset(MMAP_BITS))
+	} else {
+		MapConstraint::None
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn aslr_layout() {
+		let enabled = is_enabled();
+		set_enabled(true);
+		let bases = [(); 4].map(|_| pie_base());
+		for base in bases {
+			assert_eq!(base.0 % PAGE_SIZE, 0);
+			assert!((PIE_BASE..PROCESS_END.0).contains(&base.0));
+		}
+		// Different executions get different layouts
+		assert!(bases.iter().any(|b| *b != bases[0]));
+		let stacks = [(); 4].map(|_| stack_constraint(1));
+		assert!(stacks.iter().any(|s| *s != stacks[0]));
+		let vdsos = [(); 4].map(|_| vdso_constraint());
+		assert!(vdsos.iter().any(|v| *v != vdsos[0]));
+		// Without ASLR, the layout is deterministic
+		set_enabled(false);
+		assert_eq!(pie_base(), VirtAddr(PAGE_SIZE));
+		assert_eq!(stack_constraint(1), MapConstraint::None);
+		assert_eq!(vdso_constraint(), MapConstraint::None);
+		set_enabled(enabled);
+	}
+}
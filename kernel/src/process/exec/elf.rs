@@ -18,21 +18,27 @@
 
 //! Implementation of ELF programs execution with respect to the **System V ABI**.
 
-use super::vdso;
+use super::{aslr, vdso};
 use crate::{
 	arch::x86,
+	crypto::rand,
 	elf,
 	elf::{
 		parser::{Class, ELFParser, ProgramHeader},
 		ET_DYN,
 	},
-	file::{vfs, File, FileType, O_RDONLY},
+	file::{
+		perm::AccessProfile,
+		vfs,
+		vfs::mountpoint::{self, FLAG_NOSUID},
+		File, O_RDONLY,
+	},
 	memory::{vmem, VirtAddr},
 	process,
 	process::{
 		exec::{vdso::MappedVDSO, ExecInfo, Executor, ProgramImage},
 		mem_space,
-		mem_space::{MapConstraint, MemSpace, MAP_ANONYMOUS, MAP_PRIVATE, PROT_READ, PROT_WRITE},
+		mem_space::{MapConstraint, MemSpace, MAP_PRIVATE, PROT_EXEC, PROT_READ, PROT_WRITE},
 	},
 };
 use core::{cmp::max, intrinsics::unlikely, num::NonZeroUsize, ptr, slice};
@@ -115,37 +121,44 @@ struct ELFLoadInfo {
 
 	/// The pointer to the entry point
 	entry_point: VirtAddr,
+
+	/// Tells whether the stack must be executable.
+	stack_exec: bool,
 }
 
 /// Enumeration of possible values for an auxiliary vector entry.
-enum AuxEntryDescValue {
+enum AuxEntryDescValue<'a> {
 	/// A single number.
 	Number(usize),
 	/// A string of bytes.
-	String(&'static [u8]),
+	String(&'a [u8]),
 }
 
 /// An auxiliary vector entry.
-struct AuxEntryDesc {
+struct AuxEntryDesc<'a> {
 	/// The entry's type.
 	pub a_type: i32,
 	/// The entry's value.
-	pub a_val: AuxEntryDescValue,
+	pub a_val: AuxEntryDescValue<'a>,
 }
 
 /// Builds an auxiliary vector.
 ///
 /// Arguments:
 /// - `exec_info` is the set of execution information.
+/// - `access_profile` is the access profile the program is executed with.
 /// - `load_base` is the base address at which the ELF is loaded.
 /// - `load_info` is the set of ELF load information.
 /// - `vdso` is the set of vDSO information.
-fn build_auxiliary(
-	exec_info: &ExecInfo,
+/// - `random` is the set of random bytes to be passed to the program.
+fn build_auxiliary<'a>(
+	exec_info: &ExecInfo<'a>,
+	access_profile: &AccessProfile,
 	load_base: *mut u8,
 	load_info: &ELFLoadInfo,
 	vdso: &MappedVDSO,
-) -> AllocResult<Vec<AuxEntryDesc>> {
+	random: &'a [u8; 16],
+) -> AllocResult<Vec<AuxEntryDesc<'a>>> {
 	let mut vec = vec![
 		AuxEntryDesc {
 			a_type: AT_PHDR,
@@ -173,19 +186,19 @@ fn build_auxiliary(
 		},
 		AuxEntryDesc {
 			a_type: AT_UID,
-			a_val: AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.uid as _),
+			a_val: AuxEntryDescValue::Number(access_profile.uid as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_EUID,
-			a_val: AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.euid as _),
+			a_val: AuxEntryDescValue::Number(access_profile.euid as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_GID,
-			a_val: AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.gid as _),
+			a_val: AuxEntryDescValue::Number(access_profile.gid as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_EGID,
-			a_val: AuxEntryDescValue::Number(exec_info.path_resolution.access_profile.egid as _),
+			a_val: AuxEntryDescValue::Number(access_profile.egid as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_PLATFORM,
@@ -193,11 +206,15 @@ fn build_auxiliary(
 		},
 		AuxEntryDesc {
 			a_type: AT_HWCAP,
-			a_val: AuxEntryDescValue::Number(x86::get_hwcap() as _),
+			a_val: AuxEntryDescValue::Number(x86::features::get().hwcap() as _),
+		},
+		AuxEntryDesc {
+			a_type: AT_HWCAP2,
+			a_val: AuxEntryDescValue::Number(x86::features::get().hwcap2() as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_SECURE,
-			a_val: AuxEntryDescValue::Number(0), // TODO
+			a_val: AuxEntryDescValue::Number(access_profile.is_secure() as _),
 		},
 		AuxEntryDesc {
 			a_type: AT_BASE_PLATFORM,
@@ -205,11 +222,11 @@ fn build_auxiliary(
 		},
 		AuxEntryDesc {
 			a_type: AT_RANDOM,
-			a_val: AuxEntryDescValue::String(&[0; 16]), // TODO
+			a_val: AuxEntryDescValue::String(random),
 		},
 		AuxEntryDesc {
 			a_type: AT_EXECFN,
-			a_val: AuxEntryDescValue::String(b"TODO\0"), // TODO
+			a_val: AuxEntryDescValue::String(exec_info.execfn.as_bytes()),
 		},
 		AuxEntryDesc {
 			a_type: AT_SYSINFO_EHDR,
@@ -292,7 +309,16 @@ fn load_elf(
 	let ehdr = elf.hdr();
 	let mut load_end = load_base;
 	let mut phdr_addr = 0;
+	// If no `PT_GNU_STACK` segment is present, the stack is not executable
+	let mut stack_exec = false;
 	for seg in elf.iter_segments() {
+		if seg.p_type == elf::PT_GNU_STACK {
+			stack_exec = seg.p_flags & elf::PF_X != 0;
+			continue;
+		}
+		// The kernel does not perform relocations: the region described by `PT_GNU_RELRO` is
+		// made read-only with `mprotect` by the code applying them (the program itself or its
+		// interpreter), so there is nothing to do here
 		if seg.p_type != elf::PT_LOAD {
 			continue;
 		}
@@ -332,6 +358,8 @@ fn load_elf(
 		phnum: ehdr.e_phnum as _,
 
 		entry_point: VirtAddr::from(load_base) + elf.hdr().e_entry as usize,
+
+		stack_exec,
 	})
 }
 
@@ -480,22 +508,13 @@ unsafe fn init_stack(
 pub struct ELFExecutor<'s>(pub ExecInfo<'s>);
 
 impl Executor for ELFExecutor<'_> {
-	// TODO Handle suid and sgid
 	fn build_image(&self, ent: Arc<vfs::Entry>) -> EResult<ProgramImage> {
-		// Check that the file can be executed by the user
+		// Honor the set-user-ID and set-group-ID bits, unless the filesystem is mounted with
+		// `nosuid`
 		let stat = ent.stat();
-		if unlikely(stat.get_type() != Some(FileType::Regular)) {
-			return Err(errno!(EACCES));
-		}
-		if unlikely(
-			!self
-				.0
-				.path_resolution
-				.access_profile
-				.can_execute_file(&stat),
-		) {
-			return Err(errno!(EACCES));
-		}
+		let nosuid = mountpoint::of_entry(&ent).is_some_and(|mp| mp.flags & FLAG_NOSUID != 0);
+		let mut access_profile = self.0.path_resolution.access_profile;
+		access_profile.exec_file(&stat, nosuid);
 		// Open file
 		let file = File::open_entry(ent.clone(), O_RDONLY)?;
 		// Read and parse file
@@ -505,26 +524,36 @@ impl Executor for ELFExecutor<'_> {
 		// Initialize memory space
 		let mut mem_space = MemSpace::new(ent)?;
 		let load_base = if parser.hdr().e_type == ET_DYN {
-			// TODO ASLR
-			PAGE_SIZE
+			aslr::pie_base()
 		} else {
-			0
+			VirtAddr(0)
 		};
-		let load_base = VirtAddr(load_base).as_ptr();
+		let load_base = load_base.as_ptr();
 		let load_info = load_elf(&file, &parser, &mut mem_space, load_base)?;
+		let mut stack_prot = PROT_READ | PROT_WRITE;
+		if load_info.stack_exec {
+			stack_prot |= PROT_EXEC;
+		}
 		let user_stack = mem_space
-			.map(
-				MapConstraint::None,
+			.map_stack(
+				aslr::stack_constraint(process::USER_STACK_SIZE + 1),
 				process::USER_STACK_SIZE.try_into().unwrap(),
-				PROT_READ | PROT_WRITE,
-				MAP_PRIVATE | MAP_ANONYMOUS,
-				None,
-				0,
+				stack_prot,
 			)?
 			.wrapping_add(process::USER_STACK_SIZE * PAGE_SIZE);
 		let vdso = vdso::map(&mut mem_space, compat)?;
+		mem_space.exe_info.vdso_begin = vdso.begin;
 		// Initialize the userspace stack
-		let aux = build_auxiliary(&self.0, load_base, &load_info, &vdso)?;
+		let mut random = [0; 16];
+		rand::getrandom(&mut random);
+		let aux = build_auxiliary(
+			&self.0,
+			&access_profile,
+			load_base,
+			&load_info,
+			&vdso,
+			&random,
+		)?;
 		let (_, init_stack_size) = get_init_stack_size(&self.0.argv, &self.0.envp, &aux, compat);
 		stack_prealloc(&mut mem_space, user_stack, init_stack_size)?;
 		unsafe {
@@ -545,6 +574,7 @@ impl Executor for ELFExecutor<'_> {
 		Ok(ProgramImage {
 			mem_space,
 			compat,
+			access_profile,
 
 			entry_point: load_info.entry_point,
 			user_stack: VirtAddr::from(user_stack) - init_stack_size,
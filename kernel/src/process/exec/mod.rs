@@ -24,18 +24,22 @@
 //! - Build the memory image according to the program
 //! - Replace the process's memory with the newly created image to run it
 
+pub mod aslr;
 pub mod elf;
+pub mod script;
 pub mod vdso;
 
 use crate::{
 	arch::x86::{idt::IntFrame, tss},
-	file::{vfs, vfs::ResolutionSettings},
+	file::{perm::AccessProfile, vfs, vfs::ResolutionSettings, File, FileType, O_RDONLY},
 	memory::VirtAddr,
-	process::{mem_space::MemSpace, Process},
+	process::{mem_space::MemSpace, signal, Process},
 	sync::mutex::{IntMutex, Mutex},
 };
+use core::{intrinsics::unlikely, sync::atomic::Ordering::Relaxed};
 use utils::{
-	collections::{string::String, vec::Vec},
+	collections::{path::Path, string::String, vec::Vec},
+	errno,
 	errno::EResult,
 	ptr::arc::Arc,
 };
@@ -44,6 +48,12 @@ use utils::{
 pub struct ExecInfo<'s> {
 	/// Path resolution settings.
 	pub path_resolution: &'s ResolutionSettings,
+	/// The path to the program, as requested by the caller.
+	pub path: &'s Path,
+	/// The absolute path to the executed file, passed to the program with `AT_EXECFN`.
+	///
+	/// When executing a script, this is the path to the script rather than to its interpreter.
+	pub execfn: &'s Path,
 	/// The list of arguments.
 	pub argv: Vec<String>,
 	/// The list of environment variables.
@@ -56,6 +66,9 @@ pub struct ProgramImage {
 	mem_space: MemSpace,
 	/// Tells whether the program runs in compatibility mode.
 	compat: bool,
+	/// The access profile the program runs with, after applying the set-user-ID and
+	/// set-group-ID bits of the executable.
+	access_profile: AccessProfile,
 
 	/// A pointer to the entry point of the program.
 	entry_point: VirtAddr,
@@ -67,21 +80,55 @@ pub struct ProgramImage {
 pub trait Executor {
 	/// Builds a program image.
 	///
-	/// `file` is the program's VFS entry.
+	/// `file` is the program's VFS entry. The caller has already checked that it is a regular file
+	/// the user is allowed to execute.
 	fn build_image(&self, file: Arc<vfs::Entry>) -> EResult<ProgramImage>;
 }
 
+/// Builds a program image from the given executable file, `depth` being the number of
+/// interpreters already traversed.
+fn build_image_impl(file: Arc<vfs::Entry>, info: ExecInfo, depth: usize) -> EResult<ProgramImage> {
+	// Check that the file can be executed by the user before reading it
+	let stat = file.stat();
+	if unlikely(stat.get_type() != Some(FileType::Regular)) {
+		return Err(errno!(EACCES));
+	}
+	if unlikely(!info.path_resolution.access_profile.can_execute_file(&stat)) {
+		return Err(errno!(EACCES));
+	}
+	// Read the beginning of the file to determine its format
+	let mut buf = [0; script::SHEBANG_MAX];
+	let len = {
+		let file = File::open_entry(file.clone(), O_RDONLY)?;
+		file.ops.read(&file, 0, &mut buf)?
+	};
+	let buf = &buf[..len];
+	if buf.starts_with(b"#!") {
+		script::ScriptExecutor {
+			info,
+			shebang: buf,
+			depth,
+		}
+		.build_image(file)
+	} else {
+		// TODO Support other formats than ELF (wasm?)
+		elf::ELFExecutor(info).build_image(file)
+	}
+}
+
 /// Builds a program image from the given executable file.
 ///
 /// Arguments:
 /// - `file` is the program's file
 /// - `info` is the set execution information for the program
 ///
+/// If the file is a script beginning with a shebang, the image of its interpreter is built
+/// instead.
+///
 /// The function returns a memory space containing the program image and the
 /// pointer to the entry point.
 pub fn build_image(file: Arc<vfs::Entry>, info: ExecInfo) -> EResult<ProgramImage> {
-	// TODO Support other formats than ELF (wasm?)
-	elf::ELFExecutor(info).build_image(file)
+	build_image_impl(file, info, 0)
 }
 
 /// Executes the program image `image` on the process `proc`.
@@ -100,7 +147,7 @@ pub fn exec(proc: &Process, frame: &mut IntFrame, image: ProgramImage) -> EResul
 			Ok(Arc::new(Mutex::new(new_fds))?)
 		})
 		.transpose()?;
-	let signal_handlers = Arc::new(Default::default())?;
+	let signal_handlers = Arc::new(Mutex::new(signal::default_handlers()))?;
 	// All fallible operations succeeded, flush to process
 	mem_space.lock().bind();
 	// Safe because no other thread can execute this function at the same time for the same process
@@ -114,6 +161,10 @@ pub fn exec(proc: &Process, frame: &mut IntFrame, image: ProgramImage) -> EResul
 		signal_manager.handlers = signal_handlers;
 		signal_manager.sigpending = Default::default();
 	}
+	proc.fs.lock().access_profile = image.access_profile;
+	// A program running with more privileges than its caller must not be inspected
+	proc.dumpable
+		.store(!image.access_profile.is_secure(), Relaxed);
 	proc.vfork_wake();
 	*proc.tls.lock() = Default::default();
 	// Set TSS here for the first process to be executed
@@ -125,7 +176,7 @@ pub fn exec(proc: &Process, frame: &mut IntFrame, image: ProgramImage) -> EResul
 	#[cfg(target_arch = "x86_64")]
 	{
 		use crate::{arch::x86, process::scheduler::SCHEDULER};
-		use core::{arch::asm, sync::atomic::Ordering::Relaxed};
+		use core::arch::asm;
 		// Preserve GS base
 		let gs_base = x86::rdmsr(x86::IA32_GS_BASE);
 		// Reset segment selector
@@ -0,0 +1,189 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Execution of scripts starting with a shebang (`#!`).
+//!
+//! The first line of a script has the form `#!interpreter [arg]`. The interpreter is executed
+//! instead of the script, with the following arguments:
+//! - the interpreter's path
+//! - the optional argument, if present
+//! - the path to the script, as requested by the caller
+//! - the original arguments, except the first one
+//!
+//! This is the order used by Linux, on purpose: the original `argv[0]` is dropped and the
+//! interpreter receives the path to the script as an argument, which is what interpreters expect
+//! to find the script to run.
+
+use super::{build_image_impl, ExecInfo, Executor, ProgramImage};
+use crate::file::vfs;
+use core::intrinsics::unlikely;
+use utils::{
+	collections::{path::Path, string::String, vec::Vec},
+	errno,
+	errno::{AllocResult, EResult},
+	ptr::arc::Arc,
+	TryClone,
+};
+
+/// The maximum length of the shebang line, including the `#!` prefix.
+pub const SHEBANG_MAX: usize = 256;
+/// The maximum number of interpreters that can be used recursively for an execution.
+pub const INTERP_MAX: usize = 4;
+
+/// Tells whether the given byte is a whitespace separating fields in a shebang.
+fn is_blank(b: &u8) -> bool {
+	matches!(b, b' ' | b'\t')
+}
+
+/// Trims blank characters at the beginning and at the end of `s`.
+fn trim(s: &[u8]) -> &[u8] {
+	let start = s.iter().position(|b| !is_blank(b)).unwrap_or(s.len());
+	let end = s
+		.iter()
+		.rposition(|b| !is_blank(b))
+		.map_or(start, |i| i + 1);
+	&s[start..end]
+}
+
+/// Parses the shebang at the beginning of `buf`.
+///
+/// `buf` contains the beginning of the file. If the line is not terminated by a newline, the
+/// whole buffer is used.
+///
+/// On success, the function returns the interpreter's path and its optional argument.
+///
+/// If `buf` does not begin with a shebang, or if the interpreter is missing, the function returns
+/// [`None`].
+fn parse(buf: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+	let line = buf.strip_prefix(b"#!")?;
+	let end = line.iter().position(|b| *b == b'\n').unwrap_or(line.len());
+	// Like on Linux, carriage returns are not stripped
+	let line = trim(&line[..end]);
+	let (interp, arg) = match line.iter().position(is_blank) {
+		Some(i) => (&line[..i], Some(trim(&line[i..]))),
+		None => (line, None),
+	};
+	if interp.is_empty() {
+		return None;
+	}
+	Some((interp, arg))
+}
+
+/// Returns the arguments of the interpreter `interp`, with its optional argument `arg`, running
+/// the script at `path` that was executed with the arguments `argv`.
+fn interp_argv(
+	interp: &[u8],
+	arg: Option<&[u8]>,
+	path: &Path,
+	argv: &[String],
+) -> AllocResult<Vec<String>> {
+	let mut res = Vec::with_capacity(argv.len() + 2)?;
+	res.push(String::try_from(interp)?)?;
+	if let Some(arg) = arg {
+		res.push(String::try_from(arg)?)?;
+	}
+	// The original `argv[0]` is replaced by the path to the script
+	res.push(String::try_from(path.as_bytes())?)?;
+	for a in argv.iter().skip(1) {
+		res.push(a.try_clone()?)?;
+	}
+	Ok(res)
+}
+
+/// The program executor for scripts beginning with a shebang.
+pub struct ScriptExecutor<'s> {
+	/// The execution information of the script.
+	pub info: ExecInfo<'s>,
+	/// The beginning of the script's content, containing the shebang.
+	pub shebang: &'s [u8],
+	/// The number of interpreters already traversed to reach this script.
+	pub depth: usize,
+}
+
+impl Executor for ScriptExecutor<'_> {
+	fn build_image(&self, _ent: Arc<vfs::Entry>) -> EResult<ProgramImage> {
+		// If there is still an interpreter but the limit has been reached
+		if unlikely(self.depth >= INTERP_MAX) {
+			return Err(errno!(ELOOP));
+		}
+		let (interp, arg) = parse(self.shebang).ok_or_else(|| errno!(ENOEXEC))?;
+		let rs = self.info.path_resolution;
+		let interp_path = Path::new(interp)?;
+		let interp_ent = vfs::get_file_from_path(interp_path, rs)?;
+		let argv = interp_argv(interp, arg, self.info.path, &self.info.argv)?;
+		build_image_impl(
+			interp_ent,
+			ExecInfo {
+				path_resolution: rs,
+				path: interp_path,
+				execfn: self.info.execfn,
+				argv,
+				envp: self.info.envp.try_clone()?,
+			},
+			self.depth + 1,
+		)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn shebang_parse() {
+		assert_eq!(parse(b"#!/bin/sh\n"), Some((b"/bin/sh".as_slice(), None)));
+		assert_eq!(parse(b"#!/bin/sh"), Some((b"/bin/sh".as_slice(), None)));
+		assert_eq!(
+			parse(b"#! \t/bin/sh  \necho hello\n"),
+			Some((b"/bin/sh".as_slice(), None))
+		);
+		assert_eq!(
+			parse(b"#!/usr/bin/env  python3 -u \n"),
+			Some((b"/usr/bin/env".as_slice(), Some(b"python3 -u".as_slice())))
+		);
+		assert_eq!(parse(b"#!\n/bin/sh\n"), None);
+		assert_eq!(parse(b"#!   "), None);
+		assert_eq!(parse(b"\x7fELF"), None);
+	}
+
+	#[test_case]
+	fn shebang_argv() {
+		let argv = |args: &[&[u8]]| -> Vec<String> {
+			let mut v = Vec::new();
+			for a in args {
+				v.push(String::try_from(*a).unwrap()).unwrap();
+			}
+			v
+		};
+		let path = Path::new(b"./script").unwrap();
+		let args = argv(&[b"script", b"a", b"b"]);
+		assert_eq!(
+			interp_argv(b"/bin/sh", None, path, &args).unwrap(),
+			argv(&[b"/bin/sh", b"./script", b"a", b"b"])
+		);
+		assert_eq!(
+			interp_argv(b"/usr/bin/env", Some(b"python3 -u"), path, &args).unwrap(),
+			argv(&[b"/usr/bin/env", b"python3 -u", b"./script", b"a", b"b"])
+		);
+		// The script is passed even if the caller gave no argument
+		assert_eq!(
+			interp_argv(b"/bin/sh", None, path, &[]).unwrap(),
+			argv(&[b"/bin/sh", b"./script"])
+		);
+	}
+}
@@ -18,18 +18,34 @@
 
 //! The vDSO (virtual dynamic shared object) is a small shared library that the kernel
 //! automatically maps into the memory space of all userspace programs.
+//!
+//! The vDSO is preceded by the vvar page, a read-only page shared by all processes in which the
+//! kernel publishes data that can be read without issuing a system call (clocks, CPU number).
 
 use crate::{
 	elf::parser::ELFParser,
+	file::perm::AccessProfile,
 	memory::{
 		buddy::ZONE_KERNEL,
 		cache::{FrameOwner, RcFrame},
 		VirtAddr,
 	},
-	process::mem_space::{MemSpace, Page, MAP_ANONYMOUS, MAP_PRIVATE, PROT_EXEC, PROT_READ},
-	sync::once::OnceInit,
+	process::{
+		exec::aslr,
+		mem_space::{MemSpace, Page, MAP_ANONYMOUS, MAP_PRIVATE, PROT_EXEC, PROT_READ},
+	},
+	sync::{mutex::IntMutex, once::OnceInit},
+	time::clock,
+};
+use core::{
+	cmp::min,
+	num::NonZeroUsize,
+	ptr::NonNull,
+	sync::atomic::{
+		fence, AtomicU32,
+		Ordering::{Relaxed, Release},
+	},
 };
-use core::{cmp::min, num::NonZeroUsize, ptr::NonNull};
 use utils::{
 	collections::vec::Vec,
 	errno::{AllocResult, CollectResult, EResult},
@@ -37,9 +53,39 @@ use utils::{
 	limits::PAGE_SIZE,
 };
 
+/// The layout of the vvar page.
+///
+/// Fields are protected by a seqlock: `seq` is odd while the kernel is updating them. A reader
+/// must read `seq`, then the fields, then `seq` again, and retry if it was odd or has changed.
+#[repr(C)]
+pub struct VvarData {
+	/// The sequence counter.
+	pub seq: AtomicU32,
+	/// The clock source. If `1`, clocks are extrapolated from the TSC. If `0`, they only advance
+	/// at each tick.
+	pub clock_mode: u32,
+	/// The value of the monotonic clock at the last update, in nanoseconds.
+	pub monotonic: u64,
+	/// The offset to add to the monotonic clock to get the real time clock, in nanoseconds. The
+	/// addition wraps around.
+	pub realtime_offset: u64,
+	/// The value of the boot time clock at the last update, in nanoseconds.
+	pub boottime: u64,
+	/// The value of the TSC at the last update.
+	pub tsc_base: u64,
+	/// The multiplier to convert TSC cycles to nanoseconds.
+	pub tsc_mult: u32,
+	/// The shift to convert TSC cycles to nanoseconds: `ns = (cycles * tsc_mult) >> tsc_shift`.
+	pub tsc_shift: u32,
+	/// The number of the CPU.
+	pub cpu: u32,
+	/// The NUMA node of the CPU.
+	pub node: u32,
+}
+
 /// Information on the vDSO ELF image.
 struct Vdso {
-	/// The list of pages on which the image is loaded.
+	/// The list of pages on which the image is loaded, preceded by the vvar page.
 	pages: Vec<RcFrame>,
 	/// The offset of the vDSO's entry.
 	entry_off: Option<NonZeroUsize>,
@@ -58,13 +104,33 @@ static VDSO: OnceInit<Vdso> = unsafe { OnceInit::new() };
 /// Same as [`VDSO`], except for the compat image.
 #[cfg(target_arch = "x86_64")]
 static VDSO_COMPAT: OnceInit<Vdso> = unsafe { OnceInit::new() };
+/// The vvar page. If `None`, the page is not allocated yet.
+static VVAR: IntMutex<Option<RcFrame>> = IntMutex::new(None);
+
+/// Updates the content of the vvar page with the closure `f`.
+///
+/// If the page is not allocated yet, the function does nothing.
+pub(crate) fn update_vvar<F: FnOnce(&mut VvarData)>(f: F) {
+	let vvar = VVAR.lock();
+	let Some(frame) = &*vvar else {
+		return;
+	};
+	let data = unsafe { &mut *frame.virt_addr().as_ptr::<VvarData>() };
+	let seq = data.seq.load(Relaxed);
+	data.seq.store(seq.wrapping_add(1), Relaxed);
+	fence(Release);
+	f(data);
+	data.seq.store(seq.wrapping_add(2), Release);
+}
 
 /// Loads the vDSO in memory and returns the image.
-fn load_image(elf: &[u8]) -> EResult<Vdso> {
+///
+/// `vvar` is the vvar page, to be mapped before the image.
+fn load_image(elf: &[u8], vvar: &RcFrame) -> EResult<Vdso> {
 	let parser = ELFParser::new(elf)?;
 	// Load image into pages
 	let pages_count = elf.len().div_ceil(PAGE_SIZE);
-	let pages = (0..pages_count)
+	let image = (0..pages_count)
 		.map(|i| {
 			let off = i * PAGE_SIZE;
 			let len = min(PAGE_SIZE, elf.len() - off);
@@ -77,8 +143,11 @@ fn load_image(elf: &[u8]) -> EResult<Vdso> {
 			virtaddr[src.len()..].fill(0);
 			Ok(page)
 		})
-		.collect::<AllocResult<CollectResult<_>>>()?
+		.collect::<AllocResult<CollectResult<Vec<_>>>>()?
 		.0?;
+	let mut pages = Vec::with_capacity(image.len() + 1)?;
+	pages.push(vvar.clone())?;
+	pages.extend_from_slice(&image)?;
 	Ok(Vdso {
 		pages,
 		entry_off: NonZeroUsize::new(parser.hdr().e_entry as usize),
@@ -102,11 +171,15 @@ pub fn map(mem_space: &mut MemSpace, compat: bool) -> EResult<MappedVDSO> {
 			&*VDSO_COMPAT
 		}
 	};
-	let begin = mem_space.map_special(
+	let vvar = mem_space.map_special(
+		aslr::vdso_constraint(),
 		PROT_READ | PROT_EXEC,
 		MAP_PRIVATE | MAP_ANONYMOUS,
 		&vdso.pages,
 	)?;
+	// The vvar page must not be executable
+	mem_space.set_prot(vvar as _, PAGE_SIZE, PROT_READ, &AccessProfile::KERNEL)?;
+	let begin = vvar.wrapping_add(PAGE_SIZE);
 	Ok(MappedVDSO {
 		begin: begin.into(),
 		entry: vdso
@@ -117,16 +190,20 @@ pub fn map(mem_space: &mut MemSpace, compat: bool) -> EResult<MappedVDSO> {
 
 /// Loads the vDSO.
 pub(crate) fn init() -> EResult<()> {
+	// The page is zeroed, so the CPU number and node are zero
+	let vvar = RcFrame::new_zeroed(0, FrameOwner::Anon, 0)?;
 	// Main image
 	unsafe {
 		static ELF: &[u8] = include_bytes_aligned!(usize, env!("VDSO_PATH"));
-		OnceInit::init(&VDSO, load_image(ELF)?);
+		OnceInit::init(&VDSO, load_image(ELF, &vvar)?);
 	}
 	// 32 bit image for backward compat
 	#[cfg(target_arch = "x86_64")]
 	unsafe {
 		static ELF: &[u8] = include_bytes_aligned!(usize, env!("VDSO_COMPAT_PATH"));
-		OnceInit::init(&VDSO_COMPAT, load_image(ELF)?);
+		OnceInit::init(&VDSO_COMPAT, load_image(ELF, &vvar)?);
 	}
+	*VVAR.lock() = Some(vvar);
+	clock::publish_vvar();
 	Ok(())
 }
@@ -0,0 +1,136 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A futex (fast userspace mutex) is a 32-bit value in userspace memory on which threads can
+//! sleep until another thread wakes them up.
+//!
+//! A futex is identified by the memory space it lives in and by its address, so that threads
+//! sharing a memory space refer to the same futex.
+
+use crate::{
+	memory::VirtAddr,
+	process::{mem_space::copy::SyscallPtr, pid::Pid, scheduler::Scheduler, Process, State},
+	sync::mutex::IntMutex,
+	time::{
+		clock::{current_time_ns, Clock},
+		timer::Timer,
+		unit::Timestamp,
+	},
+};
+use utils::{collections::vec::Vec, errno, errno::EResult, ptr::arc::Arc};
+
+/// The identifier of a futex: the address of its memory space and its address in it.
+type Key = (usize, VirtAddr);
+
+/// A thread waiting on a futex.
+struct Waiter {
+	/// The futex the thread is waiting on.
+	key: Key,
+	/// The PID of the thread.
+	pid: Pid,
+	/// Tells whether the thread has been woken up.
+	woken: bool,
+}
+
+/// The list of threads waiting on a futex.
+static WAITERS: IntMutex<Vec<Waiter>> = IntMutex::new(Vec::new());
+
+/// Returns the key of the futex at `addr` in the memory space of `proc`.
+fn get_key(proc: &Process, addr: VirtAddr) -> Key {
+	let mem_space = proc
+		.mem_space
+		.as_ref()
+		.map(|m| Arc::as_ptr(m) as usize)
+		.unwrap_or(0);
+	(mem_space, addr)
+}
+
+/// Makes the current process wait on the futex at `addr` as long as it contains `val`.
+///
+/// `deadline` is the timestamp on the monotonic clock at which the function returns
+/// [`errno::ETIMEDOUT`]. If `None`, waiting never times out.
+///
+/// Errors:
+/// - [`errno::EAGAIN`]: the futex does not contain `val`
+/// - [`errno::EINTR`]: a signal is pending
+pub fn wait(addr: SyscallPtr<u32>, val: u32, deadline: Option<Timestamp>) -> EResult<()> {
+	let proc = Process::current();
+	let pid = proc.get_pid();
+	// Register before reading the value, so that a wake up in between is not missed
+	WAITERS.lock().push(Waiter {
+		key: get_key(&proc, VirtAddr(addr.as_ptr() as _)),
+		pid,
+		woken: false,
+	})?;
+	let res = (|| {
+		let cur = addr.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+		if cur != val {
+			return Err(errno!(EAGAIN));
+		}
+		let _timer = deadline
+			.map(|ts| Timer::wake_at(Clock::Monotonic, pid, ts))
+			.transpose()?;
+		loop {
+			// Sleep before checking, so that a wake up after the check is not lost
+			proc.set_state(State::Sleeping);
+			let woken = WAITERS.lock().iter().any(|w| w.pid == pid && w.woken);
+			let res = if woken {
+				Some(Ok(()))
+			} else if proc.has_pending_signal() {
+				Some(Err(errno!(EINTR)))
+			} else if deadline.is_some_and(|ts| current_time_ns(Clock::Monotonic) >= ts) {
+				Some(Err(errno!(ETIMEDOUT)))
+			} else {
+				None
+			};
+			if let Some(res) = res {
+				proc.wake();
+				break res;
+			}
+			Scheduler::tick();
+		}
+	})();
+	let mut waiters = WAITERS.lock();
+	if let Some(i) = waiters.iter().position(|w| w.pid == pid) {
+		waiters.remove(i);
+	}
+	res
+}
+
+/// Wakes up at most `count` threads waiting on the futex at `addr` in the memory space of `proc`.
+///
+/// The function returns the number of woken up threads.
+pub fn wake(proc: &Process, addr: VirtAddr, count: usize) -> usize {
+	let key = get_key(proc, addr);
+	let mut waiters = WAITERS.lock();
+	let mut n = 0;
+	for w in waiters.iter_mut() {
+		if n >= count {
+			break;
+		}
+		if w.key != key || w.woken {
+			continue;
+		}
+		w.woken = true;
+		if let Some(proc) = Process::get_by_pid(w.pid) {
+			proc.wake();
+		}
+		n += 1;
+	}
+	n
+}
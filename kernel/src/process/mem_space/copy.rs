@@ -31,7 +31,7 @@ use core::{
 use utils::{
 	collections::{string::String, vec::Vec},
 	errno,
-	errno::EResult,
+	errno::{CollectResult, EResult},
 	limits::PAGE_SIZE,
 };
 
@@ -437,6 +437,16 @@ impl SyscallIOVec {
 			count,
 		}
 	}
+
+	/// Copies the `count` entries of the iovec from userspace.
+	///
+	/// If the iovec is not accessible, the function returns [`errno::EFAULT`].
+	pub fn copy_from_user(&self, count: usize) -> EResult<Vec<IOVec>> {
+		if unlikely(count > 0 && self.ptr.is_none()) {
+			return Err(errno!(EFAULT));
+		}
+		Ok(self.iter(count).collect::<EResult<CollectResult<_>>>()?.0?)
+	}
 }
 
 /// Iterator over [`IOVec`]s.
@@ -24,11 +24,14 @@
 use super::gap::MemGap;
 use crate::{
 	arch::x86::paging,
-	file::File,
+	file::{File, FileType},
+	ipc::shm::ShmAttach,
 	memory::{
 		buddy,
 		buddy::ZONE_USER,
 		cache::{FrameOwner, RcFrame},
+		overcommit, swap,
+		swap::{SwapArea, SwapEntry},
 		vmem,
 		vmem::{write_ro, VMem},
 		PhysAddr, VirtAddr,
@@ -87,6 +90,13 @@ fn vmem_flags(prot: u8, cow: bool) -> usize {
 	flags
 }
 
+/// Tells whether a mapping with the protection `prot` and the flags `flags` commits memory.
+///
+/// Private writable mappings commit memory since each of their pages may require its own copy.
+fn is_committed(prot: u8, flags: i32) -> bool {
+	prot & PROT_WRITE != 0 && flags & MAP_SHARED == 0
+}
+
 /// Initializes a new page and maps it at `dst`.
 ///
 /// Arguments:
@@ -128,6 +138,18 @@ fn init_page(
 	Ok(new_page)
 }
 
+/// The state of a page of a mapping.
+#[derive(Clone, Debug, Default)]
+pub enum AnonPage {
+	/// The page has not been allocated yet.
+	#[default]
+	None,
+	/// The page is in memory.
+	Frame(RcFrame),
+	/// The page has been written to swap.
+	Swapped(SwapEntry),
+}
+
 /// A mapping in a memory space.
 #[derive(Debug)]
 pub struct MemMapping {
@@ -138,7 +160,7 @@ pub struct MemMapping {
 	/// Memory protection
 	prot: u8,
 	/// Mapping flags
-	flags: u8,
+	flags: i32,
 
 	/// The mapped file, if any
 	file: Option<Arc<File>>,
@@ -147,7 +169,9 @@ pub struct MemMapping {
 
 	// TODO use a sparse array?
 	/// The list of allocated physical pages
-	pub(super) anon_pages: Vec<Option<RcFrame>>,
+	pub(super) anon_pages: Vec<AnonPage>,
+	/// The attached shared memory segment, if any
+	pub(super) shm: Option<ShmAttach>,
 }
 
 impl MemMapping {
@@ -165,13 +189,16 @@ impl MemMapping {
 		addr: *mut u8,
 		size: NonZeroUsize,
 		prot: u8,
-		flags: u8,
+		flags: i32,
 		file: Option<Arc<File>>,
 		off: u64,
 	) -> AllocResult<Self> {
 		debug_assert!(addr.is_aligned_to(PAGE_SIZE));
 		let mut anon_pages = Vec::new();
-		anon_pages.resize(size.get(), None)?;
+		anon_pages.resize(size.get(), AnonPage::None)?;
+		if is_committed(prot, flags) {
+			overcommit::charge(size.get())?;
+		}
 		Ok(Self {
 			addr,
 			size,
@@ -182,6 +209,7 @@ impl MemMapping {
 			off,
 
 			anon_pages,
+			shm: None,
 		})
 	}
 
@@ -201,10 +229,26 @@ impl MemMapping {
 	}
 
 	/// Returns the mapping's flags.
-	pub fn get_flags(&self) -> u8 {
+	pub fn get_flags(&self) -> i32 {
 		self.flags
 	}
 
+	/// Returns the mapped file, if any.
+	pub fn get_file(&self) -> Option<&Arc<File>> {
+		self.file.as_ref()
+	}
+
+	/// Returns the offset of the mapping in the mapped file. If no file is mapped, the value is
+	/// not relevant.
+	pub fn get_offset(&self) -> u64 {
+		self.off
+	}
+
+	/// Returns the attached shared memory segment, if any.
+	pub fn get_shm(&self) -> Option<&ShmAttach> {
+		self.shm.as_ref()
+	}
+
 	/// Maps the page at the offset `offset` of the mapping, onto `vmem`.
 	///
 	/// If no underlying physical memory exist for this offset, the function might allocate it.
@@ -218,12 +262,17 @@ impl MemMapping {
 	/// error.
 	pub fn map(&mut self, offset: usize, vmem: &mut VMem) -> EResult<()> {
 		let virtaddr = VirtAddr::from(self.addr) + offset * PAGE_SIZE;
-		let page = if let Some(page) = &self.anon_pages[offset] {
+		// If the page has been swapped out, read it back
+		if let AnonPage::Swapped(entry) = &self.anon_pages[offset] {
+			let page = entry.read()?;
+			self.anon_pages[offset] = AnonPage::Frame(page);
+		}
+		let page = if let AnonPage::Frame(page) = &self.anon_pages[offset] {
 			// An anonymous page is already present, use it
 			if self.flags & MAP_PRIVATE != 0 && page.is_shared() {
 				// The page cannot be shared: we need our own copy
 				let page = init_page(vmem, self.prot, Some(page), virtaddr)?;
-				self.anon_pages[offset] = Some(page);
+				self.anon_pages[offset] = AnonPage::Frame(page);
 				return Ok(());
 			} else {
 				// The page is already there, just map it
@@ -235,7 +284,14 @@ impl MemMapping {
 				// Anonymous mapping
 				None => {
 					let page = init_page(vmem, self.prot, None, virtaddr)?;
-					self.anon_pages[offset] = Some(page);
+					self.anon_pages[offset] = AnonPage::Frame(page);
+					return Ok(());
+				}
+				// Mapped device, exposing physical memory directly
+				Some(file) if file.get_type()? == FileType::CharDevice => {
+					let file_off = self.off / PAGE_SIZE as u64 + offset as u64;
+					let addr = file.ops.phys_page(file, file_off)?;
+					vmem.map(addr, virtaddr, vmem_flags(self.prot, false));
 					return Ok(());
 				}
 				// Mapped file
@@ -244,7 +300,11 @@ impl MemMapping {
 					let node = file.node().unwrap();
 					let file_off = self.off / PAGE_SIZE as u64 + offset as u64;
 					let page = node.node_ops.read_page(node, file_off)?;
-					self.anon_pages[offset].insert(page)
+					self.anon_pages[offset] = AnonPage::Frame(page);
+					let AnonPage::Frame(page) = &self.anon_pages[offset] else {
+						unreachable!();
+					};
+					page
 				}
 			}
 		};
@@ -255,6 +315,30 @@ impl MemMapping {
 		Ok(())
 	}
 
+	/// Returns a new mapping covering `size` pages of the current mapping, starting at the page
+	/// `begin`.
+	///
+	/// The pages of the new mapping are shared with the current one.
+	fn sub(&self, begin: usize, size: NonZeroUsize) -> AllocResult<Self> {
+		let anon_pages = Vec::try_from(&self.anon_pages[begin..(begin + size.get())])?;
+		// The pages are already committed by `self`
+		if is_committed(self.prot, self.flags) {
+			overcommit::force_charge(size.get());
+		}
+		Ok(Self {
+			addr: self.addr.wrapping_add(begin * PAGE_SIZE),
+			size,
+			prot: self.prot,
+			flags: self.flags,
+
+			file: self.file.clone(),
+			off: self.off + (begin * PAGE_SIZE) as u64,
+
+			anon_pages,
+			shm: self.shm.clone(),
+		})
+	}
+
 	/// Splits the current mapping, creating up to two new mappings and one gap.
 	///
 	/// Arguments:
@@ -274,19 +358,7 @@ impl MemMapping {
 		size: usize,
 	) -> AllocResult<(Option<Self>, Option<MemGap>, Option<Self>)> {
 		let prev = NonZeroUsize::new(begin)
-			.map(|size| {
-				Ok(MemMapping {
-					addr: self.addr,
-					size,
-					prot: self.prot,
-					flags: self.flags,
-
-					file: self.file.clone(),
-					off: self.off,
-
-					anon_pages: Vec::try_from(&self.anon_pages[..size.get()])?,
-				})
-			})
+			.map(|size| self.sub(0, size))
 			.transpose()?;
 		let gap = NonZeroUsize::new(size).map(|size| {
 			let addr = VirtAddr::from(self.addr) + begin * PAGE_SIZE;
@@ -299,23 +371,193 @@ impl MemMapping {
 			.get()
 			.checked_sub(end)
 			.and_then(NonZeroUsize::new)
-			.map(|size| {
-				Ok(Self {
-					addr: self.addr.wrapping_add(end * PAGE_SIZE),
-					size,
-					prot: self.prot,
-					flags: self.flags,
-
-					file: self.file.clone(),
-					off: self.off + end as u64,
-
-					anon_pages: Vec::try_from(&self.anon_pages[end..])?,
-				})
-			})
+			.map(|size| self.sub(end, size))
 			.transpose()?;
 		Ok((prev, gap, next))
 	}
 
+	/// Splits the current mapping, returning the `size` pages starting at the page `begin` as a
+	/// separate mapping, along with the mappings for the remaining pages before and after it.
+	///
+	/// `begin + size` must not exceed the size of the mapping.
+	pub fn split_range(
+		&self,
+		begin: usize,
+		size: NonZeroUsize,
+	) -> AllocResult<(Option<Self>, Self, Option<Self>)> {
+		let (prev, _, next) = self.split(begin, size.get())?;
+		let middle = self.sub(begin, size)?;
+		Ok((prev, middle, next))
+	}
+
+	/// Returns a new mapping of `new_size` pages at `addr`, taking over the `size` pages of the
+	/// current mapping starting at the page `begin`.
+	///
+	/// The pages of the new mapping are shared with the current one. If the new mapping is larger,
+	/// the pages past the end of the range are not populated.
+	///
+	/// `begin + size` must not exceed the size of the mapping.
+	pub fn remap(
+		&self,
+		begin: usize,
+		size: NonZeroUsize,
+		addr: *mut u8,
+		new_size: NonZeroUsize,
+	) -> AllocResult<Self> {
+		let mut mapping = self.sub(begin, size)?;
+		mapping.addr = addr;
+		mapping.anon_pages.resize(new_size.get(), AnonPage::None)?;
+		if is_committed(self.prot, self.flags) {
+			if let Some(extra) = new_size.get().checked_sub(size.get()) {
+				overcommit::charge(extra)?;
+			} else {
+				overcommit::uncharge(size.get() - new_size.get());
+			}
+		}
+		mapping.size = new_size;
+		Ok(mapping)
+	}
+
+	/// Returns a new mapping extending the current one by `count` pages below its beginning.
+	///
+	/// The pages of the new mapping are shared with the current one. The new pages are not
+	/// populated.
+	///
+	/// The current mapping must not map a file.
+	pub fn grow_down(&self, count: usize) -> AllocResult<Self> {
+		debug_assert!(self.file.is_none());
+		let mut anon_pages = Vec::new();
+		anon_pages.resize(count, AnonPage::None)?;
+		anon_pages.extend_from_slice(&self.anon_pages)?;
+		if is_committed(self.prot, self.flags) {
+			overcommit::charge(count)?;
+		}
+		// The pages of `self` are already committed by it
+		let mut mapping = self.sub(0, self.size)?;
+		mapping.addr = self.addr.wrapping_sub(count * PAGE_SIZE);
+		mapping.size = self.size.saturating_add(count);
+		mapping.anon_pages = anon_pages;
+		Ok(mapping)
+	}
+
+	/// Sets the memory protection of the mapping.
+	///
+	/// **Note**: the virtual memory context is not updated. The pages have to be unmapped from it
+	/// so that they are mapped again with the new protection on the next access.
+	///
+	/// If the mapping has to commit more memory and there is not enough left, the function
+	/// returns an error.
+	pub fn set_prot(&mut self, prot: u8) -> AllocResult<()> {
+		let size = self.size.get();
+		match (
+			is_committed(self.prot, self.flags),
+			is_committed(prot, self.flags),
+		) {
+			(false, true) => overcommit::charge(size)?,
+			(true, false) => overcommit::uncharge(size),
+			_ => {}
+		}
+		self.prot = prot;
+		Ok(())
+	}
+
+	/// Writes up to `max` pages of the mapping that have not been accessed recently to swap,
+	/// removing them from `vmem`.
+	///
+	/// Only anonymous pages that are not shared with another mapping are swapped out. The
+	/// accessed flag of the other pages is cleared, giving them a second chance before the next
+	/// call.
+	///
+	/// The function returns the number of pages that have been swapped out.
+	pub fn swap_out(&mut self, vmem: &mut VMem, max: usize) -> usize {
+		if self.flags & MAP_SHARED != 0 {
+			return 0;
+		}
+		let mut count = 0;
+		for (offset, page) in self.anon_pages.iter_mut().enumerate() {
+			if count >= max {
+				break;
+			}
+			let AnonPage::Frame(frame) = page else {
+				continue;
+			};
+			if !frame.is_anonymous() || frame.is_shared() {
+				continue;
+			}
+			let virtaddr = VirtAddr::from(self.addr) + offset * PAGE_SIZE;
+			if vmem.poll_accessed(virtaddr) {
+				continue;
+			}
+			// Copy the page through the copy buffer since the frame may not be mapped in kernel
+			// space
+			let mut buf: Page = [0; PAGE_SIZE];
+			vmem.map(frame.phys_addr(), COPY_BUFFER, 0);
+			unsafe {
+				vmem::switch(vmem, || {
+					buf.copy_from_slice(&*COPY_BUFFER.as_ptr::<Page>());
+				});
+			}
+			let Ok(entry) = swap::write_page(&buf) else {
+				// No space left or I/O error, stop here
+				break;
+			};
+			vmem.unmap(virtaddr);
+			// Dropping the frame frees the memory
+			*page = AnonPage::Swapped(entry);
+			count += 1;
+		}
+		count
+	}
+
+	/// Returns the number of pages of the mapping that are resident in memory.
+	pub fn get_rss(&self) -> usize {
+		self.anon_pages
+			.iter()
+			.filter(|page| matches!(page, AnonPage::Frame(_)))
+			.count()
+	}
+
+	/// Returns the number of pages of the mapping that are located on swap.
+	pub fn get_swap_usage(&self) -> usize {
+		self.anon_pages
+			.iter()
+			.filter(|page| matches!(page, AnonPage::Swapped(_)))
+			.count()
+	}
+
+	/// Releases all the pages of the mapping, unmapping them from `vmem`.
+	///
+	/// Shared mappings are left untouched since their content may have to be written back to the
+	/// mapped file.
+	pub fn reap(&mut self, vmem: &mut VMem) {
+		if self.flags & MAP_SHARED != 0 {
+			return;
+		}
+		for (offset, page) in self.anon_pages.iter_mut().enumerate() {
+			if matches!(page, AnonPage::None) {
+				continue;
+			}
+			vmem.unmap(VirtAddr::from(self.addr) + offset * PAGE_SIZE);
+			*page = AnonPage::None;
+		}
+	}
+
+	/// Reads all the pages of the mapping that are located on the swap area `area` back to
+	/// memory.
+	///
+	/// Pages are not mapped on the virtual memory context and will be upon the next access.
+	pub fn swap_in_area(&mut self, area: &Arc<SwapArea>) -> EResult<()> {
+		for page in self.anon_pages.iter_mut() {
+			let AnonPage::Swapped(entry) = page else {
+				continue;
+			};
+			if entry.is_on(area) {
+				*page = AnonPage::Frame(entry.read()?);
+			}
+		}
+		Ok(())
+	}
+
 	/// Synchronizes the data on the memory mapping back to the filesystem.
 	///
 	/// Arguments:
@@ -363,6 +605,10 @@ impl MemMapping {
 
 impl TryClone for MemMapping {
 	fn try_clone(&self) -> AllocResult<Self> {
+		let anon_pages = self.anon_pages.try_clone()?;
+		if is_committed(self.prot, self.flags) {
+			overcommit::charge(self.size.get())?;
+		}
 		Ok(Self {
 			addr: self.addr,
 			size: self.size,
@@ -372,7 +618,16 @@ impl TryClone for MemMapping {
 			file: self.file.clone(),
 			off: self.off,
 
-			anon_pages: self.anon_pages.try_clone()?,
+			anon_pages,
+			shm: self.shm.clone(),
 		})
 	}
 }
+
+impl Drop for MemMapping {
+	fn drop(&mut self) {
+		if is_committed(self.prot, self.flags) {
+			overcommit::uncharge(self.size.get());
+		}
+	}
+}
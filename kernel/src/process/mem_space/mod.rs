@@ -25,20 +25,22 @@
 
 pub mod copy;
 mod gap;
-mod mapping;
+pub mod mapping;
 mod transaction;
 
 use crate::{
 	arch::x86::paging::{PAGE_FAULT_INSTRUCTION, PAGE_FAULT_WRITE},
 	file::{perm::AccessProfile, vfs, File},
+	ipc::shm::{ShmAttach, ShmSegment},
 	memory,
-	memory::{cache::RcFrame, vmem::VMem, VirtAddr, PROCESS_END},
+	memory::{cache::RcFrame, swap::SwapArea, vmem::VMem, VirtAddr, PROCESS_END},
+	process::USER_STACK_SIZE,
 };
 use core::{
 	alloc::AllocError, cmp::min, ffi::c_void, fmt, intrinsics::unlikely, mem, num::NonZeroUsize,
 };
 use gap::MemGap;
-use mapping::MemMapping;
+use mapping::{AnonPage, MemMapping};
 use transaction::MemSpaceTransaction;
 use utils::{
 	collections::{btreemap::BTreeMap, vec::Vec},
@@ -57,13 +59,18 @@ pub const PROT_WRITE: u8 = 0x2;
 pub const PROT_EXEC: u8 = 0x4;
 
 /// Changes are shared across mappings on the same region
-pub const MAP_SHARED: u8 = 0x1;
+pub const MAP_SHARED: i32 = 0x1;
 /// Changes are *not* shared across mappings on the same region
-pub const MAP_PRIVATE: u8 = 0x2;
+pub const MAP_PRIVATE: i32 = 0x2;
 /// Interpret `addr` exactly
-pub const MAP_FIXED: u8 = 0x10;
+pub const MAP_FIXED: i32 = 0x10;
 /// The mapping is not backed by any file
-pub const MAP_ANONYMOUS: u8 = 0x20;
+pub const MAP_ANONYMOUS: i32 = 0x20;
+/// The mapping is a stack which grows downwards when accessed right below its beginning
+pub const MAP_GROWSDOWN: i32 = 0x100;
+
+/// The number of pages left unmapped below a stack to catch overflows.
+const STACK_GUARD_SIZE: usize = 1;
 
 /// The virtual address of the buffer used to map pages for copy.
 const COPY_BUFFER: VirtAddr = VirtAddr(PROCESS_END.0 - PAGE_SIZE);
@@ -76,7 +83,6 @@ pub fn bound_check(addr: usize, n: usize) -> bool {
 	addr >= PAGE_SIZE && addr.saturating_add(n) <= COPY_BUFFER.0
 }
 
-// TODO Add a variant for ASLR
 /// Enumeration of constraints for the selection of the virtual address for a memory mapping.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MapConstraint {
@@ -234,6 +240,8 @@ pub struct ExeInfo {
 	pub envp_begin: VirtAddr,
 	/// Address to the end of program environment.
 	pub envp_end: VirtAddr,
+	/// Address to the beginning of the vDSO. The `vvar` page is located right before.
+	pub vdso_begin: VirtAddr,
 }
 
 /// A virtual memory space.
@@ -274,6 +282,7 @@ impl MemSpace {
 				argv_end: Default::default(),
 				envp_begin: Default::default(),
 				envp_end: Default::default(),
+				vdso_begin: Default::default(),
 			},
 		};
 		// Create the default gap of memory which is present at the beginning
@@ -292,6 +301,11 @@ impl MemSpace {
 		self.state.vmem_usage
 	}
 
+	/// Returns an iterator over the memory mappings, sorted by address.
+	pub fn iter_mappings(&self) -> impl Iterator<Item = &MemMapping> {
+		self.state.mappings.iter().map(|(_, m)| m)
+	}
+
 	/// Returns an immutable reference to the memory mapping containing the given virtual
 	/// address.
 	///
@@ -306,7 +320,7 @@ impl MemSpace {
 		map_constraint: MapConstraint,
 		size: NonZeroUsize,
 		prot: u8,
-		flags: u8,
+		flags: i32,
 		file: Option<Arc<File>>,
 		off: u64,
 	) -> EResult<MemMapping> {
@@ -390,7 +404,7 @@ impl MemSpace {
 		map_constraint: MapConstraint,
 		size: NonZeroUsize,
 		prot: u8,
-		flags: u8,
+		flags: i32,
 		file: Option<Arc<File>>,
 		off: u64,
 	) -> EResult<*mut u8> {
@@ -410,27 +424,61 @@ impl MemSpace {
 		Ok(addr)
 	}
 
-	/// Maps a chunk of memory population with the given static pages.
-	pub fn map_special(&mut self, prot: u8, flags: u8, pages: &[RcFrame]) -> AllocResult<*mut u8> {
-		let Some(len) = NonZeroUsize::new(pages.len()) else {
-			return Err(AllocError);
-		};
+	/// Maps a stack of `size` pages, with a guard page right below it.
+	///
+	/// The guard page is not handed out to other mappings, so that a stack overflow triggers a
+	/// page fault instead of silently overwriting the memory of another mapping.
+	///
+	/// `map_constraint` applies to the range covering both the guard page and the stack.
+	///
+	/// On success, the function returns a pointer to the beginning of the stack.
+	pub fn map_stack(
+		&mut self,
+		map_constraint: MapConstraint,
+		size: NonZeroUsize,
+		prot: u8,
+	) -> EResult<*mut u8> {
+		let total = size.saturating_add(STACK_GUARD_SIZE);
 		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
-		let mut map = Self::map_impl(
+		let map = Self::map_impl(
 			&mut transaction,
-			MapConstraint::None,
-			len,
+			map_constraint,
+			total,
 			prot,
-			flags,
+			MAP_PRIVATE | MAP_ANONYMOUS,
 			None,
 			0,
-		)
-		.map_err(|_| AllocError)?;
+		)?;
+		// Leave the guard page out of the mapping. It is not a gap either, so it remains unmapped
+		let (_, _, Some(stack)) = map.split(0, STACK_GUARD_SIZE)? else {
+			unreachable!();
+		};
+		let addr = stack.get_addr();
+		transaction.insert_mapping(stack)?;
+		transaction.commit();
+		Ok(addr)
+	}
+
+	/// Implementation of [`Self::map_special`] and [`Self::map_shm`].
+	fn map_pages(
+		&mut self,
+		map_constraint: MapConstraint,
+		prot: u8,
+		flags: i32,
+		pages: &[RcFrame],
+		shm: Option<ShmAttach>,
+	) -> EResult<*mut u8> {
+		let Some(len) = NonZeroUsize::new(pages.len()) else {
+			return Err(errno!(EINVAL));
+		};
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		let mut map = Self::map_impl(&mut transaction, map_constraint, len, prot, flags, None, 0)?;
 		// Populate
 		map.anon_pages
 			.iter_mut()
 			.zip(pages.iter().cloned())
-			.for_each(|(dst, src)| *dst = Some(src));
+			.for_each(|(dst, src)| *dst = AnonPage::Frame(src));
+		map.shm = shm;
 		// Commit
 		let addr = map.get_addr();
 		transaction.insert_mapping(map)?;
@@ -438,6 +486,86 @@ impl MemSpace {
 		Ok(addr)
 	}
 
+	/// Maps a chunk of memory population with the given static pages.
+	///
+	/// `map_constraint` is the constraint for the selection of the address of the mapping.
+	pub fn map_special(
+		&mut self,
+		map_constraint: MapConstraint,
+		prot: u8,
+		flags: i32,
+		pages: &[RcFrame],
+	) -> AllocResult<*mut u8> {
+		self.map_pages(map_constraint, prot, flags, pages, None)
+			.map_err(|_| AllocError)
+	}
+
+	/// Maps the shared memory segment of the attachment `shm`.
+	///
+	/// Arguments:
+	/// - `map_constraint` is the constraint for the selection of the address of the mapping
+	/// - `prot` is the memory protection
+	pub fn map_shm(
+		&mut self,
+		map_constraint: MapConstraint,
+		prot: u8,
+		shm: ShmAttach,
+	) -> EResult<*mut u8> {
+		let seg = shm.segment().clone();
+		self.map_pages(
+			map_constraint,
+			prot,
+			MAP_SHARED | MAP_ANONYMOUS,
+			seg.pages(),
+			Some(shm),
+		)
+	}
+
+	/// Unmaps the shared memory segment attached at `addr`.
+	///
+	/// On success, the function returns the detached segment.
+	///
+	/// If no segment is attached at this address, the function returns [`errno::EINVAL`].
+	pub fn unmap_shm(&mut self, addr: VirtAddr) -> EResult<Arc<ShmSegment>> {
+		let (seg, size) = self
+			.state
+			.get_mapping_for_addr(addr)
+			.filter(|m| VirtAddr::from(m.get_addr()) == addr)
+			.and_then(|m| Some((m.get_shm()?.segment().clone(), m.get_size())))
+			.ok_or_else(|| errno!(EINVAL))?;
+		self.unmap(addr, size, false)?;
+		Ok(seg)
+	}
+
+	/// Inserts `gap`, merging it with the gaps right before and after it, if any.
+	fn insert_gap_merged(
+		transaction: &mut MemSpaceTransaction,
+		mut gap: MemGap,
+	) -> AllocResult<()> {
+		// Merge previous gap
+		let prev_gap = (!gap.get_begin().is_null())
+			.then(|| {
+				let prev_gap_ptr = gap.get_begin() - 1;
+				transaction.mem_space_state.get_gap_for_addr(prev_gap_ptr)
+			})
+			.flatten()
+			.cloned();
+		if let Some(p) = prev_gap {
+			transaction.remove_gap(p.get_begin())?;
+			gap.merge(&p);
+		}
+		// Merge next gap
+		let next_gap = transaction
+			.mem_space_state
+			.get_gap_for_addr(gap.get_end())
+			.cloned();
+		if let Some(n) = next_gap {
+			transaction.remove_gap(n.get_begin())?;
+			gap.merge(&n);
+		}
+		transaction.insert_gap(gap)
+	}
+
 	/// Implementation for `unmap`.
 	///
 	/// If `nogap` is `true`, the function does not create any gap.
@@ -481,29 +609,8 @@ impl MemSpace {
 				continue;
 			}
 			// Insert gap
-			if let Some(mut gap) = gap {
-				// Merge previous gap
-				let prev_gap = (!gap.get_begin().is_null())
-					.then(|| {
-						let prev_gap_ptr = gap.get_begin() - 1;
-						transaction.mem_space_state.get_gap_for_addr(prev_gap_ptr)
-					})
-					.flatten()
-					.cloned();
-				if let Some(p) = prev_gap {
-					transaction.remove_gap(p.get_begin())?;
-					gap.merge(&p);
-				}
-				// Merge next gap
-				let next_gap = transaction
-					.mem_space_state
-					.get_gap_for_addr(gap.get_end())
-					.cloned();
-				if let Some(n) = next_gap {
-					transaction.remove_gap(n.get_begin())?;
-					gap.merge(&n);
-				}
-				transaction.insert_gap(gap)?;
+			if let Some(gap) = gap {
+				Self::insert_gap_merged(transaction, gap)?;
 			}
 		}
 		Ok(())
@@ -536,6 +643,105 @@ impl MemSpace {
 		Ok(())
 	}
 
+	/// Resizes and/or moves a mapping of memory.
+	///
+	/// Arguments:
+	/// - `old_addr` is the aligned address of the beginning of the range to remap
+	/// - `old_size` is the size of the range in number of memory pages. The range must be
+	///   contained in a single mapping
+	/// - `new_size` is the new size of the range in number of memory pages
+	/// - `may_move` tells whether the range may be moved if it cannot be grown in place
+	/// - `new_addr` is the address at which the range is moved, if any. Mappings already present
+	///   there are unmapped
+	///
+	/// The protection, flags and mapped file of the mapping are preserved, and so is its content.
+	///
+	/// On success, the function returns the address of the remapped range.
+	pub fn remap(
+		&mut self,
+		old_addr: VirtAddr,
+		old_size: NonZeroUsize,
+		new_size: NonZeroUsize,
+		may_move: bool,
+		new_addr: Option<VirtAddr>,
+	) -> EResult<*mut u8> {
+		if unlikely(!old_addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let old_end = old_addr + old_size.get() * PAGE_SIZE;
+		// Shrinking in place
+		if new_size <= old_size && new_addr.is_none() {
+			if let Some(tail) = NonZeroUsize::new(old_size.get() - new_size.get()) {
+				self.unmap(old_addr + new_size.get() * PAGE_SIZE, tail, false)?;
+			}
+			return Ok(old_addr.as_ptr());
+		}
+		if let Some(addr) = new_addr {
+			// The destination must not overlap the range
+			let end = addr.0.checked_add(new_size.get() * PAGE_SIZE);
+			if unlikely(!MapConstraint::Fixed(addr).is_valid() || end > Some(COPY_BUFFER.0)) {
+				return Err(errno!(EINVAL));
+			}
+			if unlikely(addr < old_end && end > Some(old_addr.0)) {
+				return Err(errno!(EINVAL));
+			}
+			// Like `mmap`, the destination is cleared even if the function fails afterward
+			self.unmap(addr, new_size, false)?;
+		}
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		let mapping = transaction
+			.mem_space_state
+			.get_mapping_for_addr(old_addr)
+			.ok_or_else(|| errno!(EFAULT))?;
+		// The offset of the range in the mapping
+		let inner_off = (old_addr.0 - mapping.get_addr() as usize) / PAGE_SIZE;
+		let tail = inner_off + old_size.get();
+		if unlikely(tail > mapping.get_size().get()) {
+			return Err(errno!(EFAULT));
+		}
+		// Growing in place requires the range to end the mapping, and to be followed by a large
+		// enough gap
+		let new_end = old_addr.0.checked_add(new_size.get() * PAGE_SIZE);
+		let in_place = new_addr.is_none()
+			&& tail == mapping.get_size().get()
+			&& transaction
+				.mem_space_state
+				.get_gap_for_addr(old_end)
+				.is_some_and(|gap| Some(gap.get_end().0) >= new_end);
+		let dest = match new_addr {
+			Some(addr) => addr,
+			None if in_place => old_addr,
+			None if may_move => transaction
+				.mem_space_state
+				.get_gap(new_size)
+				.ok_or_else(|| errno!(ENOMEM))?
+				.get_begin(),
+			None => return Err(errno!(ENOMEM)),
+		};
+		let new_mapping = mapping.remap(inner_off, old_size, dest.as_ptr(), new_size)?;
+		// Reserve the destination
+		if in_place {
+			remove_gaps_in_range(&mut transaction, old_end, new_size.get() - old_size.get())?;
+		} else {
+			remove_gaps_in_range(&mut transaction, dest, new_size.get())?;
+		}
+		// Replace the range by the new mapping
+		Self::unmap_impl(&mut transaction, old_addr, old_size, true)?;
+		transaction.insert_mapping(new_mapping)?;
+		transaction.commit();
+		if !in_place {
+			// Free the old range. This is done in a separate transaction because gaps the
+			// previous one removed are still present in the state until it is committed. On
+			// failure, the range is simply not reused by later mappings
+			let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+			let gap = MemGap::new(old_addr, old_size);
+			if Self::insert_gap_merged(&mut transaction, gap).is_ok() {
+				transaction.commit();
+			}
+		}
+		Ok(dest.as_ptr())
+	}
+
 	/// Binds the memory space to the current kernel.
 	pub fn bind(&self) {
 		self.vmem.bind();
@@ -606,20 +812,68 @@ impl MemSpace {
 	/// matching permissions, the function returns an error.
 	pub fn set_prot(
 		&mut self,
-		_addr: *mut c_void,
-		_len: usize,
-		_prot: u8,
-		_access_profile: &AccessProfile,
+		addr: *mut c_void,
+		len: usize,
+		prot: u8,
+		access_profile: &AccessProfile,
 	) -> EResult<()> {
-		// TODO Iterate on mappings in the range:
-		//		If the mapping is shared and associated to a file, check file permissions match
-		// `prot` (only write)
-		//		Split the mapping if needed
-		//		Set permissions
-		//		Update vmem
+		let addr = VirtAddr::from(addr);
+		if unlikely(!addr.is_aligned_to(PAGE_SIZE)) {
+			return Err(errno!(EINVAL));
+		}
+		let size = len.div_ceil(PAGE_SIZE);
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		let mut i = 0;
+		while i < size {
+			// The current page's beginning
+			let page_addr = addr + i * PAGE_SIZE;
+			// The mapping containing the page. If the range is not fully mapped, fail
+			let mapping = transaction
+				.mem_space_state
+				.get_mapping_for_addr(page_addr)
+				.ok_or_else(|| errno!(ENOMEM))?;
+			// The pointer to the beginning of the mapping
+			let mapping_begin = mapping.get_addr();
+			// The offset in the mapping to the beginning of pages to modify
+			let inner_off = (page_addr.0 - mapping_begin as usize) / PAGE_SIZE;
+			// The number of pages to modify in the mapping
+			let pages = min(size - i, mapping.get_size().get() - inner_off);
+			i += pages;
+			if mapping.get_prot() == prot {
+				continue;
+			}
+			// Writing to a shared file requires the permission to write to it
+			if let Some(file) = mapping.get_file() {
+				if mapping.get_flags() & MAP_SHARED != 0
+					&& prot & PROT_WRITE != 0
+					&& !access_profile.can_write_file(&file.stat()?)
+				{
+					return Err(errno!(EACCES));
+				}
+			}
+			let (prev, mut middle, next) =
+				mapping.split_range(inner_off, NonZeroUsize::new(pages).unwrap())?;
+			middle.set_prot(prot)?;
+			// Replace the old mapping. Removing it unmaps its pages from the virtual memory
+			// context, so they are mapped again with the new protection on the next access
+			transaction.remove_mapping(mapping_begin)?;
+			if let Some(m) = prev {
+				transaction.insert_mapping(m)?;
+			}
+			transaction.insert_mapping(middle)?;
+			if let Some(m) = next {
+				transaction.insert_mapping(m)?;
+			}
+		}
+		transaction.commit();
 		Ok(())
 	}
 
+	/// Returns the initial address for the `brk` syscall.
+	pub fn get_brk_init(&self) -> VirtAddr {
+		self.brk_init
+	}
+
 	/// Returns the address for the `brk` syscall.
 	pub fn get_brk(&self) -> VirtAddr {
 		self.brk
@@ -678,6 +932,86 @@ impl MemSpace {
 		Ok(())
 	}
 
+	/// Returns the number of pages of the memory space that are resident in memory.
+	pub fn get_rss(&self) -> usize {
+		self.state.mappings.iter().map(|(_, m)| m.get_rss()).sum()
+	}
+
+	/// Returns the number of pages of the memory space that are located on swap.
+	pub fn get_swap_usage(&self) -> usize {
+		self.state
+			.mappings
+			.iter()
+			.map(|(_, m)| m.get_swap_usage())
+			.sum()
+	}
+
+	/// Releases the private pages of the memory space, which is about to be destroyed.
+	///
+	/// This allows to free memory without waiting for the owning process to exit. Accessing a
+	/// released page afterward gives a zeroed page, or the content of the mapped file.
+	pub fn reap(&mut self) {
+		for (_, m) in self.state.mappings.iter_mut() {
+			m.reap(&mut self.vmem);
+		}
+	}
+
+	/// Writes up to `max` pages that have not been accessed recently to swap.
+	///
+	/// The function returns the number of pages that have been swapped out.
+	pub fn swap_out(&mut self, max: usize) -> usize {
+		let mut count = 0;
+		for (_, m) in self.state.mappings.iter_mut() {
+			if count >= max {
+				break;
+			}
+			count += m.swap_out(&mut self.vmem, max - count);
+		}
+		count
+	}
+
+	/// Reads all the pages located on the swap area `area` back to memory.
+	pub fn swap_in_area(&mut self, area: &Arc<SwapArea>) -> EResult<()> {
+		for (_, m) in self.state.mappings.iter_mut() {
+			m.swap_in_area(area)?;
+		}
+		Ok(())
+	}
+
+	/// If `addr` is located right below a [`MAP_GROWSDOWN`] mapping, grows the mapping down to
+	/// the page containing `addr`.
+	///
+	/// The mapping does not grow if this would leave less than [`STACK_GUARD_SIZE`] free pages
+	/// below it, or make it larger than [`USER_STACK_SIZE`].
+	fn grow_stack(&mut self, addr: VirtAddr) -> EResult<()> {
+		let page = addr.down_align_to(PAGE_SIZE);
+		let Some(mapping) = self.state.get_mapping_for_addr(page + PAGE_SIZE) else {
+			return Ok(());
+		};
+		let begin = mapping.get_addr();
+		if mapping.get_flags() & MAP_GROWSDOWN == 0
+			|| mapping.get_file().is_some()
+			|| begin != (page + PAGE_SIZE).as_ptr()
+			|| mapping.get_size().get() >= USER_STACK_SIZE
+		{
+			return Ok(());
+		}
+		// Keep the guard pages free
+		let Some(gap) = self.state.get_gap_for_addr(page) else {
+			return Ok(());
+		};
+		if gap.get_begin() + STACK_GUARD_SIZE * PAGE_SIZE > page {
+			return Ok(());
+		}
+		let new = mapping.grow_down(1)?;
+		let mut transaction = MemSpaceTransaction::new(&mut self.state, &mut self.vmem);
+		remove_gaps_in_range(&mut transaction, page, 1)?;
+		transaction.remove_mapping(begin)?;
+		transaction.insert_mapping(new)?;
+		transaction.commit();
+		Ok(())
+	}
+
 	/// Function called whenever the CPU triggered a page fault for the context.
 	///
 	/// This function determines whether the process should continue or not.
@@ -691,11 +1025,18 @@ impl MemSpace {
 	///
 	/// If the process should continue, the function returns `true`, else `false`.
 	pub fn handle_page_fault(&mut self, addr: VirtAddr, code: u32) -> EResult<bool> {
+		if self.state.get_mapping_for_addr(addr).is_none() {
+			self.grow_stack(addr)?;
+		}
 		let Some(mapping) = self.state.get_mut_mapping_for_addr(addr) else {
 			return Ok(false);
 		};
 		// Check permissions
 		let prot = mapping.get_prot();
+		// `PROT_NONE` mappings, such as guard pages placed by userspace, cannot be accessed at all
+		if unlikely(prot == 0) {
+			return Ok(false);
+		}
 		if unlikely(code & PAGE_FAULT_WRITE != 0 && prot & PROT_WRITE == 0) {
 			return Ok(false);
 		}
@@ -22,53 +22,78 @@
 //! several processes to run at the same time by sharing the CPU resources using
 //! a scheduler.
 
+pub mod cgroup;
 pub mod exec;
+pub mod futex;
 pub mod mem_space;
+pub mod ns;
 pub mod pid;
 pub mod rusage;
 pub mod scheduler;
 pub mod signal;
 pub mod user_desc;
+pub mod uts;
 
+#[cfg(feature = "kcov")]
+use crate::device::kcov;
 use crate::{
-	arch::x86::{gdt, idt, idt::IntFrame, tss, FxState},
+	arch::x86::{
+		gdt, idt,
+		idt::IntFrame,
+		paging::{PAGE_FAULT_PRESENT, PAGE_FAULT_WRITE},
+		tss, FxState,
+	},
+	cpu::CpuSet,
 	event,
 	event::CallbackResult,
 	file,
 	file::{
 		fd::{FileDescriptorTable, NewFDConstraint},
-		perm::AccessProfile,
+		perm,
+		perm::{AccessProfile, Gid, Uid, UserNamespace},
 		vfs,
-		vfs::ResolutionSettings,
+		vfs::{mountpoint::MountNamespace, ResolutionSettings},
 		File, O_RDWR,
 	},
+	ipc,
 	memory::{buddy, buddy::FrameOrder, oom, VirtAddr},
 	process::{
+		cgroup::Cgroup,
 		mem_space::{copy, copy::SyscallPtr},
-		pid::{PidHandle, IDLE_PID, INIT_PID},
+		pid::{PidHandle, PidNamespace, IDLE_PID, INIT_PID},
 		rusage::Rusage,
 		scheduler::{
-			switch,
+			fpu, switch,
 			switch::{idle_task, KThreadEntry},
 			Scheduler, SCHEDULER,
 		},
-		signal::SigSet,
+		signal::{
+			PendingSignals, SigInfo, SigSet, BUS_ADRALN, BUS_ADRERR, CLD_CONTINUED, CLD_EXITED,
+			CLD_KILLED, CLD_STOPPED, FPE_INTDIV, FPE_INTOVF, ILL_ILLOPC, SEGV_ACCERR, SEGV_MAPERR,
+			SI_KERNEL,
+		},
 	},
 	register_get,
-	sync::mutex::{IntMutex, Mutex},
+	sync::{
+		atomic::AtomicU64,
+		mutex::{IntMutex, Mutex},
+		rcu,
+		rcu::RcuArc,
+	},
 	syscall::FromSyscallArg,
 	time::timer::TimerManager,
+	tty::{pty, Terminal, TTY},
 };
 use core::{
-	ffi::c_int,
 	fmt,
 	fmt::Formatter,
 	intrinsics::unlikely,
 	mem,
 	mem::ManuallyDrop,
+	ptr,
 	ptr::NonNull,
 	sync::atomic::{
-		AtomicBool, AtomicPtr, AtomicU32, AtomicU8,
+		AtomicBool, AtomicI16, AtomicI8, AtomicPtr, AtomicU16, AtomicU32, AtomicU8, AtomicUsize,
 		Ordering::{Acquire, Relaxed, Release, SeqCst},
 	},
 };
@@ -84,7 +109,9 @@ use utils::{
 	errno::{AllocResult, EResult},
 	ptr::arc::Arc,
 	unsafe_mut::UnsafeMut,
+	vec, TryClone,
 };
+use uts::UtsNamespace;
 
 /// The opcode of the `hlt` instruction.
 const HLT_INSTRUCTION: u8 = 0xf4;
@@ -96,7 +123,7 @@ const TTY_DEVICE_PATH: &str = "/dev/tty";
 const DEFAULT_UMASK: file::Mode = 0o022;
 
 /// The size of the userspace stack of a process in number of pages.
-const USER_STACK_SIZE: usize = 2048;
+pub(crate) const USER_STACK_SIZE: usize = 2048;
 /// The size of the kernelspace stack of a process in number of pages.
 const KERNEL_STACK_ORDER: FrameOrder = 4;
 
@@ -181,6 +208,21 @@ pub struct ForkOptions {
 	/// If `true`, the parent and child processes both share the same signal
 	/// handlers table.
 	pub share_sighand: bool,
+	/// If `true`, the parent and child processes both share the same filesystem information
+	/// (root, working directory and umask).
+	pub share_fs: bool,
+	/// If `true`, the child process is a thread in the same thread group as the parent.
+	pub thread: bool,
+	/// If `true`, the child process is created in a new PID namespace, nested in the parent's.
+	pub new_pid_ns: bool,
+	/// If `true`, the child process is created in a new mount namespace, with a copy of the
+	/// parent's mountpoints.
+	pub new_mnt_ns: bool,
+	/// If `true`, the child process is created in a new UTS namespace, with a copy of the
+	/// parent's hostname and domain name.
+	pub new_uts_ns: bool,
+	/// If `true`, the child process is created in a new user namespace, nested in the parent's.
+	pub new_user_ns: bool,
 }
 
 /// Wrapper for the kernel stack, allowing to free it on drop.
@@ -220,6 +262,10 @@ pub struct ProcessLinks {
 	group_leader: Option<Arc<Process>>,
 	/// The list of processes in the process group.
 	pub process_group: Vec<Pid>,
+	/// The ID of the process's session.
+	///
+	/// If zero, the process is the leader of its own session.
+	sid: Pid,
 }
 
 /// A process's filesystem access information.
@@ -234,6 +280,10 @@ pub struct ProcessFs {
 	pub cwd: Arc<vfs::Entry>,
 	/// Current root path used by the process
 	pub chroot: Arc<vfs::Entry>,
+	/// The mount namespace of the process.
+	pub mnt_ns: Arc<MountNamespace>,
+	/// The user namespace of the process.
+	pub user_ns: Arc<UserNamespace>,
 }
 
 impl ProcessFs {
@@ -241,6 +291,80 @@ impl ProcessFs {
 	pub fn umask(&self) -> file::Mode {
 		self.umask.load(Acquire)
 	}
+
+	/// Tells whether the process is privileged in its user namespace.
+	pub fn is_privileged(&self) -> bool {
+		self.user_ns
+			.is_privileged_in(&self.access_profile, &self.user_ns)
+	}
+
+	/// Translates the user ID `uid` to the one seen from the process's user namespace.
+	///
+	/// If the ID is not mapped, the function returns [`perm::OVERFLOW_UID`].
+	pub fn local_uid(&self, uid: Uid) -> Uid {
+		self.user_ns
+			.uid_from_global(uid)
+			.unwrap_or(perm::OVERFLOW_UID)
+	}
+
+	/// Translates the group ID `gid` to the one seen from the process's user namespace.
+	///
+	/// If the ID is not mapped, the function returns [`perm::OVERFLOW_GID`].
+	pub fn local_gid(&self, gid: Gid) -> Gid {
+		self.user_ns
+			.gid_from_global(gid)
+			.unwrap_or(perm::OVERFLOW_GID)
+	}
+
+	/// Translates the user ID `uid` of the process's user namespace to the global one.
+	///
+	/// If the ID is not mapped, the function returns [`errno::EINVAL`].
+	pub fn global_uid(&self, uid: Uid) -> EResult<Uid> {
+		self.user_ns
+			.uid_to_global(uid)
+			.ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Translates the group ID `gid` of the process's user namespace to the global one.
+	///
+	/// If the ID is not mapped, the function returns [`errno::EINVAL`].
+	pub fn global_gid(&self, gid: Gid) -> EResult<Gid> {
+		self.user_ns
+			.gid_to_global(gid)
+			.ok_or_else(|| errno!(EINVAL))
+	}
+
+	/// Tells whether the root directory of the process is not the root of its mount namespace.
+	pub fn is_chrooted(&self) -> bool {
+		!ptr::eq(self.chroot.as_ref(), self.mnt_ns.root().as_ref())
+	}
+
+	/// Moves the process to a new user namespace, nested in the current one.
+	///
+	/// The process is privileged in the new namespace.
+	///
+	/// If the process is chrooted, the function returns [`errno::EPERM`], since being privileged
+	/// in the new namespace would allow it to escape from its root directory.
+	pub fn unshare_user_ns(&mut self) -> EResult<()> {
+		if unlikely(self.is_chrooted()) {
+			return Err(errno!(EPERM));
+		}
+		self.user_ns = UserNamespace::new(&self.user_ns, self.access_profile.euid)?;
+		Ok(())
+	}
+
+	/// Moves the process to a new mount namespace, owned by the process's user namespace, with a
+	/// copy of the mountpoints of the current one.
+	///
+	/// The working directory and root directory are moved to the same locations in the new
+	/// namespace.
+	pub fn unshare_mount_ns(&mut self) -> EResult<()> {
+		let mnt_ns = self.mnt_ns.copy(self.user_ns.clone())?;
+		self.cwd = mnt_ns.translate(&self.mnt_ns, &self.cwd)?;
+		self.chroot = mnt_ns.translate(&self.mnt_ns, &self.chroot)?;
+		self.mnt_ns = mnt_ns;
+		Ok(())
+	}
 }
 
 impl Clone for ProcessFs {
@@ -250,6 +374,8 @@ impl Clone for ProcessFs {
 			umask: AtomicU32::new(self.umask.load(Acquire)),
 			cwd: self.cwd.clone(),
 			chroot: self.chroot.clone(),
+			mnt_ns: self.mnt_ns.clone(),
+			user_ns: self.user_ns.clone(),
 		}
 	}
 }
@@ -260,25 +386,37 @@ pub struct ProcessSignal {
 	pub handlers: Arc<Mutex<[SignalHandler; signal::SIGNALS_COUNT]>>,
 	/// A bitfield storing the set of blocked signals.
 	pub sigmask: SigSet,
-	/// A bitfield storing the set of pending signals.
-	sigpending: SigSet,
+	/// The set of pending signals.
+	sigpending: PendingSignals,
 
 	/// The exit status of the process after exiting.
 	pub exit_status: ExitStatus,
 	/// The terminating signal.
 	pub termsig: u8,
+	/// If `true`, the process is exiting along with its thread group, `exit_status` and
+	/// `termsig` being already set.
+	pub group_exit: bool,
+
+	/// The ID of the system call interrupted by a signal, to be restarted.
+	///
+	/// The value is consumed by the signal handler if one is executed. Else, the system call is
+	/// restarted when returning to userspace.
+	pub restart: Option<usize>,
 }
 
 impl ProcessSignal {
 	/// Creates a new instance.
 	pub fn new() -> AllocResult<Self> {
 		Ok(ProcessSignal {
-			handlers: Arc::new(Default::default())?,
+			handlers: Arc::new(Mutex::new(signal::default_handlers()))?,
 			sigmask: Default::default(),
 			sigpending: Default::default(),
 
 			exit_status: 0,
 			termsig: 0,
+			group_exit: false,
+
+			restart: None,
 		})
 	}
 
@@ -287,28 +425,34 @@ impl ProcessSignal {
 		self.sigmask.is_set(sig as _)
 	}
 
-	/// Returns the ID of the next signal to be handled.
-	///
-	/// If `peek` is `false`, the signal is cleared from the bitfield.
+	/// Tells whether a signal is pending and not blocked.
+	pub fn has_pending(&self) -> bool {
+		self.sigpending.peek(&self.sigmask).is_some()
+	}
+
+	/// Removes the next signal to be handled from the pending signals, then returns it along
+	/// with its information.
 	///
 	/// If no signal is pending, the function returns `None`.
-	pub fn next_signal(&mut self, peek: bool) -> Option<Signal> {
-		let sig = self
-			.sigpending
-			.iter()
-			.enumerate()
-			.filter(|(_, b)| *b)
-			.filter_map(|(i, _)| {
-				let s = Signal::try_from(i as c_int).ok()?;
-				(!s.can_catch() || !self.sigmask.is_set(i)).then_some(s)
-			})
-			.next();
-		if !peek {
-			if let Some(id) = sig {
-				self.sigpending.clear(id as _);
-			}
-		}
-		sig
+	pub fn next_signal(&mut self) -> Option<(Signal, SigInfo)> {
+		let sig = self.sigpending.peek(&self.sigmask)?;
+		let info = self.sigpending.pop(sig)?;
+		Some((sig, info))
+	}
+
+	/// Returns the set of pending signals.
+	pub fn pending_set(&self) -> SigSet {
+		self.sigpending.set()
+	}
+
+	/// Returns the number of pending occurrences of signals.
+	pub fn pending_count(&self) -> usize {
+		self.sigpending.count()
+	}
+
+	/// Returns the information of a pending signal for which `f` returns `true`, blocked or not.
+	pub fn find_pending<F: FnMut(&SigInfo) -> bool>(&mut self, f: F) -> Option<&mut SigInfo> {
+		self.sigpending.find_mut(f)
 	}
 }
 
@@ -319,6 +463,13 @@ pub struct Process {
 	pid: PidHandle,
 	/// The thread ID of the process.
 	pub tid: Pid,
+	/// The ID of the thread group, which is the PID of its first thread.
+	tgid: Pid,
+	/// The TIDs of the threads in the thread group, shared between all of them.
+	pub threads: Arc<Mutex<Vec<Pid>>>,
+	/// The address at which zero is written when the thread exits, before waking up a thread
+	/// waiting on it as a futex. If zero, nothing is done.
+	pub clear_child_tid: AtomicUsize,
 
 	/// The current state of the process.
 	state: AtomicU8,
@@ -335,11 +486,13 @@ pub struct Process {
 	fpu: Mutex<FxState>,
 	/// TLS entries.
 	pub tls: Mutex<[gdt::Entry; TLS_ENTRIES_COUNT]>, // TODO rwlock
+	/// The I/O permission bitmap, set with `ioperm`. If empty, no port is accessible.
+	pub io_bitmap: Mutex<Vec<u8>>,
 
 	/// The virtual memory of the process.
 	pub mem_space: UnsafeMut<Option<Arc<IntMutex<MemSpace>>>>,
 	/// Filesystem access information.
-	pub fs: Mutex<ProcessFs>, // TODO rwlock
+	pub fs: Arc<Mutex<ProcessFs>>, // TODO rwlock
 	/// The list of open file descriptors with their respective ID.
 	pub file_descriptors: UnsafeMut<Option<Arc<Mutex<FileDescriptorTable>>>>,
 	/// Process's timers, shared between all threads of the same process.
@@ -349,12 +502,119 @@ pub struct Process {
 
 	/// The process's resources usage.
 	pub rusage: Mutex<Rusage>,
+	/// The CPU time consumed by the process in nanoseconds, not including the time elapsed since
+	/// it was last scheduled.
+	///
+	/// To get the total CPU time, use [`Scheduler::get_cpu_time`].
+	pub cpu_time: AtomicU64,
+	/// The scheduling policy of the process.
+	sched_policy: AtomicU8,
+	/// The real-time priority of the process. Zero if the policy is not real-time.
+	sched_priority: AtomicU8,
+	/// The priority inherited from a process waiting for a mutex held by this process. Zero if
+	/// none.
+	inherited_priority: AtomicU8,
+	/// The nice value of the process, which weights its share of CPU time among the processes
+	/// with the [`scheduler::SCHED_OTHER`] policy.
+	nice: AtomicI8,
+	/// The I/O priority of the process, ordering its block device requests (see
+	/// [`crate::device::queue`]).
+	ioprio: AtomicU16,
+	/// The adjustment added to the OOM score of the process (see [`oom::badness`]).
+	pub oom_score_adj: AtomicI16,
+	/// The set of CPU cores the process is allowed to run on.
+	affinity: AtomicU64,
+	/// The control group the process belongs to.
+	cgroup: RcuArc<Cgroup>,
+	/// The UTS namespace of the process.
+	uts_ns: RcuArc<UtsNamespace>,
+
+	/// If `false`, only privileged processes can read the environment and memory mappings of the
+	/// process through `/proc/[pid]/environ` and `/proc/[pid]/maps`. This is set when executing a
+	/// program with more privileges than its caller.
+	pub dumpable: AtomicBool,
+
+	/// If `true`, the process's system calls are traced.
+	#[cfg(feature = "strace")]
+	strace: AtomicBool,
+	/// The number of operations left before one is made to fail, or zero if none (see
+	/// [`crate::fault`]).
+	#[cfg(feature = "fault_inject")]
+	pub fail_nth: AtomicU32,
+	/// The buffer in which the kernel's coverage is traced while the process runs, if enabled.
+	#[cfg(feature = "kcov")]
+	pub kcov: Mutex<Option<Arc<kcov::Area>>>,
+}
+
+/// Returns the `si_code` of the divide error caused by the userspace instruction at the program
+/// counter of `frame`.
+///
+/// The error is an overflow if the divisor is not zero. If the divisor cannot be retrieved, it is
+/// assumed to be zero.
+fn divide_error_code(frame: &IntFrame) -> i32 {
+	let pc = frame.get_program_counter();
+	let read = |i: usize| {
+		SyscallPtr::<u8>::from_ptr(pc + i)
+			.copy_from_user()
+			.ok()
+			.flatten()
+	};
+	// Skip prefixes
+	let mut rex = 0;
+	let mut operand16 = false;
+	let mut i = 0;
+	let opcode = loop {
+		// An instruction cannot exceed 15 bytes
+		if i >= 15 {
+			return FPE_INTDIV;
+		}
+		let Some(b) = read(i) else {
+			return FPE_INTDIV;
+		};
+		i += 1;
+		match b {
+			0x66 => operand16 = true,
+			0x26 | 0x2e | 0x36 | 0x3e | 0x64 | 0x65 | 0x67 | 0xf0 | 0xf2 | 0xf3 => {}
+			// The REX prefix exists only in 64-bit mode
+			0x40..=0x4f if !frame.is_compat() => {
+				rex = b;
+				continue;
+			}
+			_ => break b,
+		}
+		// The REX prefix is ignored if not right before the opcode
+		rex = 0;
+	};
+	// Only `div` and `idiv` with a register operand are handled
+	let Some(modrm) = read(i) else {
+		return FPE_INTDIV;
+	};
+	if !matches!(opcode, 0xf6 | 0xf7) || modrm >> 6 != 0b11 {
+		return FPE_INTDIV;
+	}
+	let rm = (modrm & 0b111) as usize | ((rex as usize & 0b1) << 3);
+	let divisor = match opcode {
+		// Without REX prefix, registers 4 to 7 are the high bytes of the first 4 registers
+		0xf6 if rex == 0 && (4..8).contains(&rm) => (frame.get_register(rm - 4) >> 8) & 0xff,
+		0xf6 => frame.get_register(rm) & 0xff,
+		_ if rex & 0b1000 != 0 => frame.get_register(rm),
+		_ if operand16 => frame.get_register(rm) & 0xffff,
+		_ => frame.get_register(rm) as u32 as usize,
+	};
+	if divisor == 0 {
+		FPE_INTDIV
+	} else {
+		FPE_INTOVF
+	}
 }
 
 /// Initializes processes system. This function must be called only once, at
 /// kernel initialization.
 pub(crate) fn init() -> EResult<()> {
 	tss::init();
+	uts::init()?;
+	pid::init()?;
+	cgroup::init()?;
 	scheduler::init()?;
 	// Register interruption callbacks
 	let callback = |id: u32, _code: u32, frame: &mut IntFrame, ring: u8| {
@@ -366,15 +626,21 @@ pub(crate) fn init() -> EResult<()> {
 		if unlikely(proc.is_idle_task()) {
 			return CallbackResult::Panic;
 		}
+		// Signal caused by the faulting instruction
+		let fault = |sig: Signal, code: i32| SigInfo {
+			si_addr: frame.get_program_counter(),
+			..SigInfo::new(sig, code)
+		};
 		match id {
 			// Divide-by-zero
+			0x00 => proc.kill_info(fault(Signal::SIGFPE, divide_error_code(frame))),
 			// x87 Floating-Point Exception
 			// SIMD Floating-Point Exception
-			0x00 | 0x10 | 0x13 => proc.kill(Signal::SIGFPE),
+			0x10 | 0x13 => proc.kill(Signal::SIGFPE),
 			// Breakpoint
 			0x03 => proc.kill(Signal::SIGTRAP),
 			// Invalid Opcode
-			0x06 => proc.kill(Signal::SIGILL),
+			0x06 => proc.kill_info(fault(Signal::SIGILL, ILL_ILLOPC)),
 			// General Protection Fault
 			0x0d => {
 				// Get the instruction opcode
@@ -388,7 +654,7 @@ pub(crate) fn init() -> EResult<()> {
 				}
 			}
 			// Alignment Check
-			0x11 => proc.kill(Signal::SIGBUS),
+			0x11 => proc.kill_info(fault(Signal::SIGBUS, BUS_ADRALN)),
 			_ => {}
 		}
 		CallbackResult::Continue
@@ -401,8 +667,20 @@ pub(crate) fn init() -> EResult<()> {
 		let Some(mem_space) = proc.mem_space.as_ref() else {
 			return CallbackResult::Panic;
 		};
+		// A page may be allocated, enforce the memory limit of the process's control group
+		if code & PAGE_FAULT_PRESENT == 0 || code & PAGE_FAULT_WRITE != 0 {
+			cgroup::enforce_memory_limit(&proc);
+		}
 		// Check access
-		let sig = mem_space.lock().handle_page_fault(accessed_addr, code);
+		let sig = loop {
+			let res = mem_space.lock().handle_page_fault(accessed_addr, code);
+			// On allocation failure, reclaim memory without holding the lock so that pages of the
+			// current process can be swapped out, then retry
+			match res {
+				Err(e) if e.as_int() == errno::ENOMEM && oom::try_reclaim() => continue,
+				res => break res,
+			}
+		};
 		match sig {
 			Ok(true) => {}
 			Ok(false) => {
@@ -415,10 +693,22 @@ pub(crate) fn init() -> EResult<()> {
 						return CallbackResult::Panic;
 					}
 				} else {
-					proc.kill(Signal::SIGSEGV);
+					// If the page is present, the fault is caused by the permissions
+					let code = if code & PAGE_FAULT_PRESENT != 0 {
+						SEGV_ACCERR
+					} else {
+						SEGV_MAPERR
+					};
+					proc.kill_info(SigInfo {
+						si_addr: accessed_addr.0,
+						..SigInfo::new(Signal::SIGSEGV, code)
+					});
 				}
 			}
-			Err(_) => proc.kill(Signal::SIGBUS),
+			Err(_) => proc.kill_info(SigInfo {
+				si_addr: accessed_addr.0,
+				..SigInfo::new(Signal::SIGBUS, BUS_ADRERR)
+			}),
 		}
 		CallbackResult::Continue
 	};
@@ -466,7 +756,7 @@ impl Process {
 	) -> AllocResult<Arc<Self>> {
 		let pid = match pid {
 			Some(pid) => PidHandle::mark_used(pid)?,
-			None => PidHandle::unique()?,
+			None => PidHandle::unique(PidNamespace::root())?,
 		};
 		let tid = *pid;
 		let kernel_stack = KernelStack::new()?;
@@ -474,6 +764,9 @@ impl Process {
 		let thread = Arc::new(Self {
 			pid,
 			tid,
+			tgid: tid,
+			threads: Arc::new(Mutex::new(vec![tid]?))?,
+			clear_child_tid: AtomicUsize::new(0),
 
 			state: AtomicU8::new(State::Running as _),
 			vfork_done: AtomicBool::new(false),
@@ -481,22 +774,43 @@ impl Process {
 
 			kernel_stack,
 			kernel_sp: AtomicPtr::new(kernel_sp),
-			fpu: Mutex::new(FxState([0; 512])),
+			fpu: Default::default(),
 			tls: Default::default(),
+			io_bitmap: Default::default(),
 
 			// TODO this is not needed. find a way to avoid init
 			mem_space: Default::default(),
-			fs: Mutex::new(ProcessFs {
+			fs: Arc::new(Mutex::new(ProcessFs {
 				access_profile: AccessProfile::KERNEL,
 				umask: Default::default(),
-				cwd: vfs::ROOT.clone(),
-				chroot: vfs::ROOT.clone(),
-			}),
+				cwd: vfs::root(),
+				chroot: vfs::root(),
+				mnt_ns: MountNamespace::init_ns().clone(),
+				user_ns: UserNamespace::root().clone(),
+			}))?,
 			file_descriptors: Default::default(),
 			timer_manager: Arc::new(Mutex::new(TimerManager::new(0)?))?,
 			signal: Mutex::new(ProcessSignal::new()?),
 
 			rusage: Default::default(),
+			cpu_time: AtomicU64::new(0),
+			sched_policy: AtomicU8::new(scheduler::SCHED_OTHER),
+			sched_priority: AtomicU8::new(0),
+			inherited_priority: AtomicU8::new(0),
+			nice: AtomicI8::new(0),
+			ioprio: AtomicU16::new(0),
+			oom_score_adj: AtomicI16::new(0),
+			affinity: AtomicU64::new(CpuSet::MAX),
+			cgroup: RcuArc::new(cgroup::root().clone()),
+			uts_ns: RcuArc::new(UtsNamespace::root().clone()),
+			dumpable: AtomicBool::new(true),
+
+			#[cfg(feature = "strace")]
+			strace: AtomicBool::new(false),
+			#[cfg(feature = "fault_inject")]
+			fail_nth: AtomicU32::new(0),
+			#[cfg(feature = "kcov")]
+			kcov: Default::default(),
 		})?;
 		if queue {
 			SCHEDULER.lock().add_process(thread.clone())?;
@@ -526,6 +840,8 @@ impl Process {
 			let tty_file = File::open_entry(tty_ent, O_RDWR)?;
 			let (stdin_fd_id, _) = fds_table.create_fd(0, tty_file)?;
 			assert_eq!(stdin_fd_id, STDIN_FILENO);
+			// The TTY is the controlling terminal of the init process's session
+			TTY.display.lock().set_session(INIT_PID, INIT_PID);
 			fds_table.duplicate_fd(
 				STDIN_FILENO as _,
 				NewFDConstraint::Fixed(STDOUT_FILENO as _),
@@ -542,6 +858,9 @@ impl Process {
 		let proc = Arc::new(Self {
 			pid: PidHandle::mark_used(INIT_PID)?,
 			tid: INIT_PID,
+			tgid: INIT_PID,
+			threads: Arc::new(Mutex::new(vec![INIT_PID]?))?,
+			clear_child_tid: AtomicUsize::new(0),
 
 			state: AtomicU8::new(State::Running as _),
 			vfork_done: AtomicBool::new(false),
@@ -549,28 +868,52 @@ impl Process {
 
 			kernel_stack: KernelStack::new()?,
 			kernel_sp: AtomicPtr::default(),
-			fpu: Mutex::new(FxState([0; 512])),
+			fpu: Default::default(),
 			tls: Default::default(),
+			io_bitmap: Default::default(),
 
 			mem_space: UnsafeMut::new(None),
-			fs: Mutex::new(ProcessFs {
+			fs: Arc::new(Mutex::new(ProcessFs {
 				access_profile: rs.access_profile,
 				umask: AtomicU32::new(DEFAULT_UMASK),
 				cwd: root_dir.clone(),
 				chroot: root_dir,
-			}),
+				mnt_ns: MountNamespace::init_ns().clone(),
+				user_ns: UserNamespace::root().clone(),
+			}))?,
 			file_descriptors: UnsafeMut::new(Some(Arc::new(Mutex::new(file_descriptors))?)),
 			timer_manager: Arc::new(Mutex::new(TimerManager::new(INIT_PID)?))?,
 			signal: Mutex::new(ProcessSignal {
-				handlers: Arc::new(Default::default())?,
+				handlers: Arc::new(Mutex::new(signal::default_handlers()))?,
 				sigmask: Default::default(),
 				sigpending: Default::default(),
 
 				exit_status: 0,
 				termsig: 0,
+				group_exit: false,
+
+				restart: None,
 			}),
 
 			rusage: Default::default(),
+			cpu_time: AtomicU64::new(0),
+			sched_policy: AtomicU8::new(scheduler::SCHED_OTHER),
+			sched_priority: AtomicU8::new(0),
+			inherited_priority: AtomicU8::new(0),
+			nice: AtomicI8::new(0),
+			ioprio: AtomicU16::new(0),
+			oom_score_adj: AtomicI16::new(0),
+			affinity: AtomicU64::new(CpuSet::MAX),
+			cgroup: RcuArc::new(cgroup::root().clone()),
+			uts_ns: RcuArc::new(UtsNamespace::root().clone()),
+			dumpable: AtomicBool::new(true),
+
+			#[cfg(feature = "strace")]
+			strace: AtomicBool::new(false),
+			#[cfg(feature = "fault_inject")]
+			fail_nth: AtomicU32::new(0),
+			#[cfg(feature = "kcov")]
+			kcov: Default::default(),
 		})?;
 		SCHEDULER.lock().add_process(proc.clone())?;
 		Ok(proc)
@@ -582,6 +925,30 @@ impl Process {
 		*self.pid
 	}
 
+	/// Returns the ID of the process's thread group.
+	#[inline]
+	pub fn get_tgid(&self) -> Pid {
+		self.tgid
+	}
+
+	/// Tells whether the process is a thread other than the first of its thread group.
+	#[inline]
+	pub fn is_thread(&self) -> bool {
+		self.tgid != *self.pid
+	}
+
+	/// Returns the PID namespace of the process.
+	#[inline]
+	pub fn pid_ns(&self) -> &Arc<PidNamespace> {
+		self.pid.ns()
+	}
+
+	/// Returns the process's ID, as seen from its own PID namespace.
+	#[inline]
+	pub fn get_local_pid(&self) -> Pid {
+		self.pid.local()
+	}
+
 	/// Tells whether the process is an idle task.
 	pub fn is_idle_task(&self) -> bool {
 		*self.pid == IDLE_PID
@@ -593,6 +960,129 @@ impl Process {
 		*self.pid == INIT_PID
 	}
 
+	/// Tells whether the process is a kernel thread.
+	///
+	/// Apart from init before it executes its program, kernel threads are the only processes
+	/// without a memory space.
+	#[inline]
+	pub fn is_kthread(&self) -> bool {
+		self.mem_space.is_none() && !self.is_init()
+	}
+
+	/// Tells whether the process's system calls are traced.
+	#[cfg(feature = "strace")]
+	#[inline]
+	pub fn is_traced(&self) -> bool {
+		self.strace.load(Relaxed)
+	}
+
+	/// Enables or disables tracing of the process's system calls.
+	///
+	/// Children processes created afterward inherit this setting.
+	#[cfg(feature = "strace")]
+	pub fn set_traced(&self, traced: bool) {
+		self.strace.store(traced, Relaxed);
+	}
+
+	/// Returns the scheduling policy of the process.
+	#[inline]
+	pub fn get_sched_policy(&self) -> u8 {
+		self.sched_policy.load(Relaxed)
+	}
+
+	/// Returns the real-time priority of the process. If the policy is not real-time, the
+	/// function returns zero.
+	#[inline]
+	pub fn rt_priority(&self) -> u8 {
+		self.sched_priority.load(Relaxed)
+	}
+
+	/// Returns the priority used to schedule the process, which is the highest of its real-time
+	/// priority and the priority it inherited (see [`Scheduler::update_priority`]).
+	#[inline]
+	pub fn effective_priority(&self) -> u8 {
+		self.rt_priority()
+			.max(self.inherited_priority.load(Relaxed))
+	}
+
+	/// Sets the scheduling policy and the real-time priority of the process.
+	///
+	/// `priority` must be zero for non real-time policies.
+	pub fn set_sched(&self, policy: u8, priority: u8) {
+		// Prevent the scheduler from seeing an inconsistent policy and priority
+		let _sched = SCHEDULER.lock();
+		self.sched_policy.store(policy, Relaxed);
+		self.sched_priority.store(priority, Relaxed);
+	}
+
+	/// Returns the nice value of the process.
+	#[inline]
+	pub fn nice(&self) -> i8 {
+		self.nice.load(Relaxed)
+	}
+
+	/// Sets the nice value of the process, clamped to the range from [`scheduler::NICE_MIN`] to
+	/// [`scheduler::NICE_MAX`].
+	///
+	/// The new value takes effect on the next time slice of the process.
+	pub fn set_nice(&self, nice: i32) {
+		let nice = nice.clamp(scheduler::NICE_MIN as _, scheduler::NICE_MAX as _);
+		self.nice.store(nice as _, Relaxed);
+	}
+
+	/// Returns the I/O priority of the process.
+	#[inline]
+	pub fn ioprio(&self) -> u16 {
+		self.ioprio.load(Relaxed)
+	}
+
+	/// Sets the I/O priority of the process.
+	///
+	/// The new value applies to the requests submitted after the call.
+	pub fn set_ioprio(&self, ioprio: u16) {
+		self.ioprio.store(ioprio, Relaxed);
+	}
+
+	/// Returns the set of CPU cores the process is allowed to run on.
+	#[inline]
+	pub fn get_affinity(&self) -> CpuSet {
+		self.affinity.load(Relaxed)
+	}
+
+	/// Sets the set of CPU cores the process is allowed to run on.
+	#[inline]
+	pub fn set_affinity(&self, set: CpuSet) {
+		self.affinity.store(set, Relaxed);
+	}
+
+	/// Tells whether the process is allowed to run on the CPU core with ID `core`.
+	#[inline]
+	pub fn can_run_on(&self, core: usize) -> bool {
+		self.get_affinity() & (1 << core) != 0
+	}
+
+	/// Returns the control group the process belongs to.
+	#[inline]
+	pub fn cgroup(&self) -> Arc<Cgroup> {
+		self.cgroup.get()
+	}
+
+	/// Returns the UTS namespace of the process.
+	#[inline]
+	pub fn uts_ns(&self) -> Arc<UtsNamespace> {
+		self.uts_ns.get()
+	}
+
+	/// Moves the process to the UTS namespace `ns`.
+	pub fn set_uts_ns(&self, ns: Arc<UtsNamespace>) {
+		self.uts_ns.swap(ns);
+	}
+
+	/// Moves the process to the control group `group`.
+	pub fn set_cgroup(&self, group: Arc<Cgroup>) {
+		self.cgroup.swap(group);
+	}
+
 	/// Returns the process group ID.
 	pub fn get_pgid(&self) -> Pid {
 		self.links
@@ -628,6 +1118,32 @@ impl Process {
 		Ok(())
 	}
 
+	/// Returns the session ID.
+	pub fn get_sid(&self) -> Pid {
+		match self.links.lock().sid {
+			0 => self.get_pid(),
+			sid => sid,
+		}
+	}
+
+	/// Tells whether the process is the leader of its session.
+	pub fn is_session_leader(&self) -> bool {
+		self.links.lock().sid == 0
+	}
+
+	/// Makes the process the leader of a new session and of a new process group.
+	///
+	/// If the process is already a process group leader, the function returns
+	/// [`errno::EPERM`].
+	pub fn set_sid(&self) -> EResult<()> {
+		if self.get_pgid() == self.get_pid() {
+			return Err(errno!(EPERM));
+		}
+		self.set_pgid(0)?;
+		self.links.lock().sid = 0;
+		Ok(())
+	}
+
 	/// The function tells whether the process is in an orphaned process group.
 	pub fn is_in_orphan_process_group(&self) -> bool {
 		self.links
@@ -697,10 +1213,12 @@ impl Process {
 				return;
 			}
 			#[cfg(feature = "strace")]
-			println!(
-				"[strace {pid}] changed state: {old_state:?} -> {new_state:?}",
-				pid = self.get_pid()
-			);
+			if self.is_traced() {
+				println!(
+					"[strace {pid}] changed state: {old_state:?} -> {new_state:?}",
+					pid = self.get_pid()
+				);
+			}
 			// Update the number of running processes
 			if new_state == State::Running {
 				SCHEDULER.lock().increment_running();
@@ -717,27 +1235,72 @@ impl Process {
 					// bound
 					*self.file_descriptors.get_mut() = None;
 				}
-				// Attach every child to the init process
-				let init_proc = Process::get_by_pid(INIT_PID).unwrap();
+				// Undo semaphore operations
+				ipc::sem::exit(*self.pid);
+				// Disassociate the controlling terminal
+				if self.is_session_leader() {
+					TTY.display.lock().hangup(*self.pid);
+					pty::hangup(*self.pid);
+				}
+				// If the process is the init process of its namespace, the namespace dies with it
+				let mut ns = self.pid_ns();
+				if ns.is_reaper(*self.pid) {
+					oom::wrap(|| ns.processes())
+						.into_iter()
+						.filter(|pid| *pid != *self.pid)
+						.filter_map(Process::get_by_pid)
+						.for_each(|proc| proc.kill(Signal::SIGKILL));
+					ns = ns.parent().unwrap();
+				}
+				// Attach every child to the reaper of the namespace. The reaper is looked up only
+				// if there are children, since a kernel thread may exit before init is started
 				let children = mem::take(&mut self.links.lock().children);
-				for child_pid in children {
-					// Check just in case
-					if child_pid == *self.pid {
-						continue;
-					}
-					if let Some(child) = Process::get_by_pid(child_pid) {
-						child.links.lock().parent = Some(init_proc.clone());
-						oom::wrap(|| init_proc.add_child(child_pid));
+				if !children.is_empty() {
+					let init_proc = Process::get_by_pid(ns.reaper())
+						.unwrap_or_else(|| Process::get_by_pid(INIT_PID).unwrap());
+					for child_pid in children {
+						// Check just in case
+						if child_pid == *self.pid {
+							continue;
+						}
+						if let Some(child) = Process::get_by_pid(child_pid) {
+							child.links.lock().parent = Some(init_proc.clone());
+							oom::wrap(|| init_proc.add_child(child_pid));
+						}
 					}
 				}
-				// Set vfork as done just in case
-				self.vfork_wake();
+				if self.is_thread() {
+					// The thread is not waited for by the parent, it is reaped by the scheduler
+					self.threads.lock().retain(|tid| *tid != *self.pid);
+					oom::wrap(|| SCHEDULER.lock().defer_reap(*self.pid));
+				} else if self.is_kthread() {
+					// Nobody can wait for a kernel thread
+					oom::wrap(|| SCHEDULER.lock().defer_reap(*self.pid));
+				} else {
+					// Set vfork as done just in case
+					self.vfork_wake();
+				}
 			}
-			// Send SIGCHLD
-			if matches!(new_state, State::Running | State::Stopped | State::Zombie) {
+			// Send SIGCHLD. Only the first thread of a thread group notifies the parent
+			if matches!(new_state, State::Running | State::Stopped | State::Zombie)
+				&& !self.is_thread()
+			{
 				let links = self.links.lock();
 				if let Some(parent) = &links.parent {
-					parent.kill(Signal::SIGCHLD);
+					let signal = self.signal.lock();
+					let (code, status) = match new_state {
+						State::Running => (CLD_CONTINUED, Signal::SIGCONT as i32),
+						State::Stopped => (CLD_STOPPED, signal.termsig as i32),
+						_ if signal.termsig != 0 => (CLD_KILLED, signal.termsig as i32),
+						_ => (CLD_EXITED, signal.exit_status as i32),
+					};
+					drop(signal);
+					parent.kill_info(SigInfo {
+						si_pid: parent.pid_ns().local_pid(self.get_pid()).unwrap_or(0),
+						si_uid: self.fs.lock().access_profile.uid,
+						si_status: status,
+						..SigInfo::new(Signal::SIGCHLD, code)
+					});
 				}
 			}
 		});
@@ -745,7 +1308,7 @@ impl Process {
 
 	/// Tells whether there is a pending signal on the process.
 	pub fn has_pending_signal(&self) -> bool {
-		Process::current().signal.lock().next_signal(true).is_some()
+		Process::current().signal.lock().has_pending()
 	}
 
 	/// Wakes up the process if in [`State::Sleeping`] state.
@@ -755,12 +1318,14 @@ impl Process {
 			(old_state == State::Sleeping as _).then_some(State::Running as _)
 		});
 		#[cfg(feature = "strace")]
-		println!(
-			"[strace {pid}] changed state: {old_state:?} -> {new_state:?}",
-			old_state = State::Sleeping,
-			new_state = State::Running,
-			pid = self.get_pid()
-		);
+		if res.is_ok() && self.is_traced() {
+			println!(
+				"[strace {pid}] changed state: {old_state:?} -> {new_state:?}",
+				old_state = State::Sleeping,
+				new_state = State::Running,
+				pid = self.get_pid()
+			);
+		}
 		// Update the number of running processes
 		if res.is_ok() {
 			SCHEDULER.lock().increment_running();
@@ -809,7 +1374,11 @@ impl Process {
 	/// If the `this` is not running, the behaviour is undefined.
 	pub fn fork(this: Arc<Self>, fork_options: ForkOptions) -> EResult<Arc<Self>> {
 		debug_assert!(matches!(this.get_state(), State::Running));
-		let pid = PidHandle::unique()?;
+		let pid = if fork_options.new_pid_ns {
+			PidHandle::unique(&PidNamespace::new(this.pid_ns())?)?
+		} else {
+			PidHandle::unique(this.pid_ns())?
+		};
 		let pid_int = *pid;
 		// Clone memory space
 		let mem_space = {
@@ -843,28 +1412,71 @@ impl Process {
 				Arc::new(Mutex::new(handlers))?
 			}
 		};
+		// Clone filesystem information
+		let fs = if fork_options.share_fs {
+			this.fs.clone()
+		} else {
+			let mut fs = this.fs.lock().clone();
+			if fork_options.new_user_ns {
+				fs.unshare_user_ns()?;
+			}
+			if fork_options.new_mnt_ns {
+				fs.unshare_mount_ns()?;
+			}
+			Arc::new(Mutex::new(fs))?
+		};
+		let uts_ns = if fork_options.new_uts_ns {
+			this.uts_ns().copy(fs.lock().user_ns.clone())?
+		} else {
+			this.uts_ns()
+		};
+		// A thread joins the parent's thread group, and has the same parent
+		let (tgid, threads, parent, timer_manager) = if fork_options.thread {
+			(
+				this.tgid,
+				this.threads.clone(),
+				this.links.lock().parent.clone(),
+				this.timer_manager.clone(),
+			)
+		} else {
+			(
+				pid_int,
+				Arc::new(Mutex::new(vec![pid_int]?))?,
+				Some(this.clone()),
+				Arc::new(Mutex::new(TimerManager::new(pid_int)?))?,
+			)
+		};
+		let sid = this.get_sid();
 		let proc = Arc::new(Self {
 			pid,
 			tid: pid_int,
+			tgid,
+			threads,
+			clear_child_tid: AtomicUsize::new(0),
 
 			state: AtomicU8::new(State::Running as _),
 			vfork_done: AtomicBool::new(false),
 			links: Mutex::new(ProcessLinks {
-				parent: Some(this.clone()),
+				parent,
 				group_leader: this.links.lock().group_leader.clone(),
+				sid,
 				..Default::default()
 			}),
 
 			kernel_stack: KernelStack::new()?,
 			kernel_sp: AtomicPtr::default(),
-			fpu: Mutex::new(this.fpu.lock().clone()),
+			fpu: Mutex::new({
+				// The state of the parent may still be held by the FPU
+				fpu::save(&this);
+				this.fpu.lock().clone()
+			}),
 			tls: Mutex::new(*this.tls.lock()),
+			io_bitmap: Mutex::new(this.io_bitmap.lock().try_clone()?),
 
 			mem_space: UnsafeMut::new(Some(mem_space)),
-			fs: Mutex::new(this.fs.lock().clone()),
+			fs,
 			file_descriptors: UnsafeMut::new(file_descriptors),
-			// TODO if creating a thread: timer_manager: this.timer_manager.clone(),
-			timer_manager: Arc::new(Mutex::new(TimerManager::new(pid_int)?))?,
+			timer_manager,
 			signal: Mutex::new(ProcessSignal {
 				handlers: signal_handlers,
 				sigmask: this.signal.lock().sigmask,
@@ -872,11 +1484,36 @@ impl Process {
 
 				exit_status: 0,
 				termsig: 0,
+				group_exit: false,
+
+				restart: None,
 			}),
 
 			rusage: Mutex::new(Rusage::default()),
+			cpu_time: AtomicU64::new(0),
+			sched_policy: AtomicU8::new(this.get_sched_policy()),
+			sched_priority: AtomicU8::new(this.rt_priority()),
+			inherited_priority: AtomicU8::new(0),
+			nice: AtomicI8::new(this.nice()),
+			ioprio: AtomicU16::new(this.ioprio()),
+			oom_score_adj: AtomicI16::new(this.oom_score_adj.load(Relaxed)),
+			affinity: AtomicU64::new(this.get_affinity()),
+			cgroup: RcuArc::new(this.cgroup()),
+			uts_ns: RcuArc::new(uts_ns),
+			dumpable: AtomicBool::new(this.dumpable.load(Relaxed)),
+
+			#[cfg(feature = "strace")]
+			strace: AtomicBool::new(this.is_traced()),
+			#[cfg(feature = "fault_inject")]
+			fail_nth: AtomicU32::new(0),
+			#[cfg(feature = "kcov")]
+			kcov: Default::default(),
 		})?;
-		this.add_child(pid_int)?;
+		if fork_options.thread {
+			this.threads.lock().push(pid_int)?;
+		} else {
+			this.add_child(pid_int)?;
+		}
 		SCHEDULER.lock().add_process(proc.clone())?;
 		Ok(proc)
 	}
@@ -885,21 +1522,29 @@ impl Process {
 	///
 	/// If the process doesn't have a signal handler, the default action for the signal is
 	/// executed.
+	///
+	/// If the signal is blocked, it remains pending until it gets unblocked.
+	///
+	/// The signal is reported as sent by the kernel. To give more information about its origin,
+	/// use [`Self::kill_info`].
 	pub fn kill(&self, sig: Signal) {
+		self.kill_info(SigInfo::new(sig, SI_KERNEL));
+	}
+
+	/// Same as [`Self::kill`], with the signal described by `info`.
+	pub fn kill_info(&self, info: SigInfo) {
 		let mut signal_manager = self.signal.lock();
-		// Ignore blocked signals
-		if sig.can_catch() && signal_manager.sigmask.is_set(sig as _) {
-			return;
-		}
 		// Statistics
 		self.rusage.lock().ru_nsignals += 1;
 		#[cfg(feature = "strace")]
-		println!(
-			"[strace {pid}] received signal `{sig}`",
-			pid = self.get_pid(),
-			sig = sig as c_int
-		);
-		signal_manager.sigpending.set(sig as _);
+		if self.is_traced() {
+			println!(
+				"[strace {pid}] received signal `{sig}`",
+				pid = self.get_pid(),
+				sig = info.si_signo
+			);
+		}
+		signal_manager.sigpending.push(info);
 	}
 
 	/// Kills every process in the process group.
@@ -916,14 +1561,80 @@ impl Process {
 
 	/// Exits the process with the given `status`.
 	///
-	/// This function changes the process's status to `Zombie`.
+	/// This function changes the process's status to `Zombie`. Other threads in the thread group
+	/// are not affected.
+	///
+	/// The process must be the current process.
 	pub fn exit(&self, status: u32) {
 		#[cfg(feature = "strace")]
-		println!(
-			"[strace {pid}] exited with status `{status}`",
-			pid = *self.pid
-		);
-		self.signal.lock().exit_status = status as ExitStatus;
+		if self.is_traced() {
+			println!(
+				"[strace {pid}] exited with status `{status}`",
+				pid = *self.pid
+			);
+		}
+		{
+			// When exiting along with the thread group, the status has already been set
+			let mut signal = self.signal.lock();
+			if !signal.group_exit {
+				signal.exit_status = status as ExitStatus;
+			}
+		}
+		self.exit_thread();
+	}
+
+	/// Exits every thread in the thread group of the process, with the given `status`, or
+	/// terminated by the signal `termsig` if nonzero.
+	///
+	/// Other threads are killed with [`Signal::SIGKILL`] and report the same status when they
+	/// exit.
+	///
+	/// The process must be the current process.
+	pub fn exit_group(&self, status: u32, termsig: u8) {
+		// If the group is already exiting, the status has been set by the thread exiting it
+		let group_exit = {
+			let mut signal = self.signal.lock();
+			let group_exit = mem::replace(&mut signal.group_exit, true);
+			if !group_exit {
+				signal.exit_status = status as ExitStatus;
+				signal.termsig = termsig;
+			}
+			group_exit
+		};
+		if !group_exit {
+			let threads = oom::wrap(|| self.threads.lock().try_clone());
+			threads
+				.into_iter()
+				.filter(|tid| *tid != *self.pid)
+				.filter_map(Process::get_by_pid)
+				.for_each(|thread| {
+					{
+						let mut signal = thread.signal.lock();
+						signal.exit_status = status as ExitStatus;
+						signal.termsig = termsig;
+						signal.group_exit = true;
+					}
+					thread.kill(Signal::SIGKILL);
+					// Interrupt sleeping
+					thread.wake();
+				});
+		}
+		self.exit_thread();
+	}
+
+	/// Clears the thread ID at [`Self::clear_child_tid`] and wakes up a thread waiting on it,
+	/// then changes the process's status to `Zombie`.
+	fn exit_thread(&self) {
+		let clear_child_tid = self.clear_child_tid.swap(0, Relaxed);
+		if clear_child_tid != 0 {
+			let ptr = SyscallPtr::<u32>::from_ptr(clear_child_tid);
+			// On failure, nobody can be waiting on the address anyway
+			if ptr.copy_to_user(&0).is_ok() {
+				futex::wake(self, VirtAddr(clear_child_tid), 1);
+			}
+		}
+		#[cfg(feature = "kcov")]
+		kcov::task_exit(self);
 		self.set_state(State::Zombie);
 	}
 }
@@ -966,16 +1677,16 @@ fn yield_current_impl(frame: &mut IntFrame) -> bool {
 		return false;
 	}
 	// Get signal handler to execute, if any
-	let (sig, handler) = {
+	let (sig, info, handler) = {
 		let mut signal_manager = proc.signal.lock();
-		let Some(sig) = signal_manager.next_signal(false) else {
+		let Some((sig, info)) = signal_manager.next_signal() else {
 			return true;
 		};
 		let handler = signal_manager.handlers.lock()[sig as usize].clone();
-		(sig, handler)
+		(sig, info, handler)
 	};
 	// Prepare for execution of signal handler
-	handler.exec(sig, &proc, frame);
+	handler.exec(sig, &info, &proc, frame);
 	// If the process is still running, continue execution
 	proc.get_state() == State::Running
 }
@@ -997,6 +1708,8 @@ pub fn yield_current(ring: u8, frame: &mut IntFrame) {
 	if ring < 3 {
 		return;
 	}
+	// No lock is held at this point, run deferred RCU callbacks
+	rcu::reclaim();
 	// Use a separate function to drop everything, since `Scheduler::tick` may never return
 	let cont = yield_current_impl(frame);
 	if !cont {
@@ -0,0 +1,119 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Namespaces isolate a global resource of the system for a group of processes.
+//!
+//! A namespace can be referenced by opening one of the `/proc/<pid>/ns/` links. The resulting
+//! file descriptor keeps the namespace alive, and allows other processes to join it with `setns`.
+
+use crate::{
+	file::{fs::FileOps, perm::UserNamespace, vfs::mountpoint::MountNamespace},
+	process::{pid::PidNamespace, uts::UtsNamespace, Process},
+};
+use core::fmt;
+use utils::ptr::arc::Arc;
+
+/// A type of namespace.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum NsKind {
+	/// Mount namespace.
+	Mount,
+	/// PID namespace.
+	Pid,
+	/// User namespace.
+	User,
+	/// UTS namespace.
+	Uts,
+}
+
+impl NsKind {
+	/// Returns the name of the namespace type, as it appears in `/proc/<pid>/ns/`.
+	pub fn name(self) -> &'static str {
+		match self {
+			Self::Mount => "mnt",
+			Self::Pid => "pid",
+			Self::User => "user",
+			Self::Uts => "uts",
+		}
+	}
+}
+
+/// A reference to a namespace of any type.
+#[derive(Clone)]
+pub enum Namespace {
+	/// Mount namespace.
+	Mount(Arc<MountNamespace>),
+	/// PID namespace.
+	Pid(Arc<PidNamespace>),
+	/// User namespace.
+	User(Arc<UserNamespace>),
+	/// UTS namespace.
+	Uts(Arc<UtsNamespace>),
+}
+
+impl Namespace {
+	/// Returns the namespace of type `kind` the process `proc` belongs to.
+	pub fn of(proc: &Process, kind: NsKind) -> Self {
+		match kind {
+			NsKind::Mount => Self::Mount(proc.fs.lock().mnt_ns.clone()),
+			NsKind::Pid => Self::Pid(proc.pid_ns().clone()),
+			NsKind::User => Self::User(proc.fs.lock().user_ns.clone()),
+			NsKind::Uts => Self::Uts(proc.uts_ns()),
+		}
+	}
+
+	/// Returns the type of the namespace.
+	pub fn kind(&self) -> NsKind {
+		match self {
+			Self::Mount(_) => NsKind::Mount,
+			Self::Pid(_) => NsKind::Pid,
+			Self::User(_) => NsKind::User,
+			Self::Uts(_) => NsKind::Uts,
+		}
+	}
+
+	/// Returns the ID of the namespace, which is unique among the namespaces of the same type.
+	pub fn id(&self) -> u32 {
+		match self {
+			Self::Mount(ns) => ns.id(),
+			Self::Pid(ns) => ns.id(),
+			Self::User(ns) => ns.id(),
+			Self::Uts(ns) => ns.id(),
+		}
+	}
+}
+
+impl fmt::Display for Namespace {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:[{}]", self.kind().name(), self.id())
+	}
+}
+
+impl fmt::Debug for Namespace {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		fmt::Display::fmt(self, f)
+	}
+}
+
+/// File operations of an open namespace.
+///
+/// The file cannot be read nor written. It only holds a reference to the namespace.
+#[derive(Debug)]
+pub struct NsFile(pub Namespace);
+
+impl FileOps for NsFile {}
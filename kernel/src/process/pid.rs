@@ -20,10 +20,28 @@
 //!
 //! Each process must have a unique PID, thus they have to be allocated.
 //! A bitfield is used to store the used PIDs.
+//!
+//! Processes are also grouped into PID namespaces. A process sees only the processes of its own
+//! namespace and of the namespaces nested in it, through PIDs that are local to its namespace.
+//! The global PID, which is unique on the whole system, is still used everywhere inside the
+//! kernel: PIDs are translated at the system call boundary.
 
-use crate::sync::mutex::Mutex;
-use core::{alloc::AllocError, ops::Deref};
-use utils::{collections::id_allocator::IDAllocator, errno::AllocResult};
+use crate::{
+	process::scheduler::SCHEDULER,
+	sync::{mutex::Mutex, once::OnceInit},
+};
+use core::{
+	alloc::AllocError,
+	fmt,
+	fmt::Formatter,
+	ops::Deref,
+	sync::atomic::{AtomicU16, AtomicU32, Ordering::Relaxed},
+};
+use utils::{
+	collections::{hashmap::HashMap, id_allocator::IDAllocator, vec::Vec},
+	errno::AllocResult,
+	ptr::arc::Arc,
+};
 
 /// Type representing a Process ID. This ID is unique for every running
 /// processes.
@@ -49,32 +67,249 @@ fn allocator_do<F: Fn(&mut IDAllocator) -> AllocResult<T>, T>(f: F) -> AllocResu
 	f(allocator)
 }
 
+/// The PIDs local to a namespace.
+struct NsPids {
+	/// The allocator for local PIDs.
+	allocator: IDAllocator,
+	/// Global PIDs to local PIDs.
+	local: HashMap<Pid, Pid>,
+	/// Local PIDs to global PIDs.
+	global: HashMap<Pid, Pid>,
+}
+
+/// A PID namespace.
+///
+/// The first process created in a namespace gets the local PID [`INIT_PID`], and becomes the
+/// reaper of the processes orphaned in it.
+pub struct PidNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The parent namespace. If `None`, this is the root namespace.
+	parent: Option<Arc<PidNamespace>>,
+	/// The local PIDs. If `None`, this is the root namespace, in which local PIDs are the global
+	/// PIDs.
+	pids: Option<Mutex<NsPids>>,
+	/// The global PID of the namespace's init process. Zero if it has not been created yet.
+	reaper: AtomicU16,
+}
+
+/// The ID of the next PID namespace to be created. The root namespace has the ID `1`.
+static NEXT_NS_ID: AtomicU32 = AtomicU32::new(2);
+
+/// The root PID namespace.
+static ROOT: OnceInit<Arc<PidNamespace>> = unsafe { OnceInit::new() };
+
+impl PidNamespace {
+	/// Creates a namespace nested in `parent`.
+	pub fn new(parent: &Arc<Self>) -> AllocResult<Arc<Self>> {
+		Arc::new(Self {
+			id: NEXT_NS_ID.fetch_add(1, Relaxed),
+			parent: Some(parent.clone()),
+			pids: Some(Mutex::new(NsPids {
+				allocator: IDAllocator::new(MAX_PID as _)?,
+				local: HashMap::new(),
+				global: HashMap::new(),
+			})),
+			reaper: AtomicU16::new(0),
+		})
+	}
+
+	/// Returns the root namespace.
+	pub fn root() -> &'static Arc<Self> {
+		&ROOT
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// Returns the parent namespace. If `None`, this is the root namespace.
+	pub fn parent(&self) -> Option<&Arc<Self>> {
+		self.parent.as_ref()
+	}
+
+	/// Tells whether this is the root namespace.
+	pub fn is_root(&self) -> bool {
+		self.parent.is_none()
+	}
+
+	/// Returns the PID local to the namespace of the process with the global PID `pid`.
+	///
+	/// If the process is not visible from the namespace, the function returns `None`.
+	pub fn local_pid(&self, pid: Pid) -> Option<Pid> {
+		match &self.pids {
+			Some(pids) => pids.lock().local.get(&pid).cloned(),
+			None => Some(pid),
+		}
+	}
+
+	/// Returns the global PID of the process with the PID `pid`, local to the namespace.
+	///
+	/// If no process has this PID in the namespace, the function returns `None`.
+	pub fn global_pid(&self, pid: Pid) -> Option<Pid> {
+		match &self.pids {
+			Some(pids) => pids.lock().global.get(&pid).cloned(),
+			None => Some(pid),
+		}
+	}
+
+	/// Returns the global PID of the process adopting the orphans of the namespace.
+	pub fn reaper(&self) -> Pid {
+		match self.reaper.load(Relaxed) {
+			0 => INIT_PID,
+			pid => pid,
+		}
+	}
+
+	/// Tells whether the process with the global PID `pid` is the init process of a namespace
+	/// other than the root.
+	pub fn is_reaper(&self, pid: Pid) -> bool {
+		!self.is_root() && self.reaper.load(Relaxed) == pid
+	}
+
+	/// Returns the global PIDs of the processes visible from the namespace.
+	pub fn processes(&self) -> AllocResult<Vec<Pid>> {
+		let mut res = Vec::new();
+		match &self.pids {
+			Some(pids) => {
+				for (pid, _) in pids.lock().local.iter() {
+					res.push(*pid)?;
+				}
+			}
+			None => {
+				for (pid, _) in SCHEDULER.lock().iter_process() {
+					res.push(*pid)?;
+				}
+			}
+		}
+		Ok(res)
+	}
+
+	/// Gives local PIDs to the process with the global PID `pid`, in the namespace and all its
+	/// ancestors.
+	///
+	/// The function returns the PID local to the namespace.
+	fn register(&self, pid: Pid) -> AllocResult<Pid> {
+		let Some(pids) = &self.pids else {
+			return Ok(pid);
+		};
+		let local = {
+			let mut pids = pids.lock();
+			let local = (pids.allocator.alloc(None)? + 1) as Pid;
+			if let Err(e) = pids.local.insert(pid, local) {
+				pids.allocator.free((local - 1) as _);
+				return Err(e);
+			}
+			if let Err(e) = pids.global.insert(local, pid) {
+				pids.local.remove(&pid);
+				pids.allocator.free((local - 1) as _);
+				return Err(e);
+			}
+			local
+		};
+		if local == INIT_PID {
+			self.reaper.store(pid, Relaxed);
+		}
+		if let Some(parent) = &self.parent {
+			parent.register(pid)?;
+		}
+		Ok(local)
+	}
+
+	/// Frees the local PIDs of the process with the global PID `pid`, in the namespace and all its
+	/// ancestors.
+	fn unregister(&self, pid: Pid) {
+		let Some(pids) = &self.pids else {
+			return;
+		};
+		{
+			let mut pids = pids.lock();
+			if let Some(local) = pids.local.remove(&pid) {
+				pids.global.remove(&local);
+				pids.allocator.free((local - 1) as _);
+			}
+		}
+		if let Some(parent) = &self.parent {
+			parent.unregister(pid);
+		}
+	}
+}
+
+/// Initializes the root PID namespace.
+pub(super) fn init() -> AllocResult<()> {
+	let root = Arc::new(PidNamespace {
+		id: 1,
+		parent: None,
+		pids: None,
+		reaper: AtomicU16::new(INIT_PID),
+	})?;
+	unsafe {
+		OnceInit::init(&ROOT, root);
+	}
+	Ok(())
+}
+
 /// Wrapper for a PID, freeing it on drop.
-#[derive(Debug)]
-pub struct PidHandle(Pid);
+///
+/// The handle also holds the PIDs of the process in its namespace and the namespace's ancestors.
+pub struct PidHandle {
+	/// The global PID.
+	pid: Pid,
+	/// The namespace of the process.
+	ns: Arc<PidNamespace>,
+	/// The PID local to `ns`.
+	local: Pid,
+}
 
 impl PidHandle {
-	/// Allocates the given `pid`.
+	/// Allocates the given `pid`, in the root namespace.
 	///
 	/// If already allocated, the function returns an error.
 	pub(super) fn mark_used(pid: Pid) -> AllocResult<Self> {
+		let handle = Self {
+			pid,
+			ns: PidNamespace::root().clone(),
+			local: pid,
+		};
 		let Some(id) = pid.checked_sub(1) else {
 			// Pid `0` is not allocated, just return a handle
-			return Ok(Self(pid));
+			return Ok(handle);
 		};
 		allocator_do(|a| {
 			if !a.is_used(id as _) {
 				a.set_used(id as _);
-				Ok(Self(pid))
+				Ok(())
 			} else {
 				Err(AllocError)
 			}
-		})
+		})?;
+		Ok(handle)
+	}
+
+	/// Returns an unused PID, marks it as used and registers it in the namespace `ns`.
+	pub fn unique(ns: &Arc<PidNamespace>) -> AllocResult<PidHandle> {
+		let pid = allocator_do(|allocator| allocator.alloc(None)).map(|i| (i + 1) as Pid)?;
+		let mut handle = Self {
+			pid,
+			ns: ns.clone(),
+			local: pid,
+		};
+		// On failure, the PIDs already allocated are freed when the handle is dropped
+		handle.local = ns.register(pid)?;
+		Ok(handle)
+	}
+
+	/// Returns the namespace of the process.
+	#[inline]
+	pub fn ns(&self) -> &Arc<PidNamespace> {
+		&self.ns
 	}
 
-	/// Returns an unused PID and marks it as used.
-	pub fn unique() -> AllocResult<PidHandle> {
-		allocator_do(|allocator| allocator.alloc(None)).map(|i| PidHandle((i + 1) as _))
+	/// Returns the PID local to the namespace of the process.
+	#[inline]
+	pub fn local(&self) -> Pid {
+		self.local
 	}
 }
 
@@ -82,14 +317,21 @@ impl Deref for PidHandle {
 	type Target = Pid;
 
 	fn deref(&self) -> &Self::Target {
-		&self.0
+		&self.pid
+	}
+}
+
+impl fmt::Debug for PidHandle {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		fmt::Debug::fmt(&self.pid, f)
 	}
 }
 
 impl Drop for PidHandle {
 	fn drop(&mut self) {
+		self.ns.unregister(self.pid);
 		// Cannot free PID `0`
-		let Some(i) = self.0.checked_sub(1) else {
+		let Some(i) = self.pid.checked_sub(1) else {
 			return;
 		};
 		// Cannot fail
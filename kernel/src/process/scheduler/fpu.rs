@@ -0,0 +1,112 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Lazy switching of the FPU state.
+//!
+//! Saving and restoring the state of the FPU on each context switch is wasteful, since many tasks
+//! never use it. Instead, the FPU is disabled by setting the TS flag of `CR0` when switching to a
+//! task which does not own it. The first x87, MMX or SSE instruction of that task then triggers a
+//! *Device Not Available* exception (`#NM`), whose handler saves the state of the previous owner
+//! and restores the state of the current task, which becomes the new owner.
+//!
+//! The kernel itself does not use the FPU, so the exception is only triggered from userspace.
+
+use crate::{
+	arch::x86::{fxrstor, fxsave, idt::IntFrame, set_task_switched},
+	event,
+	event::CallbackResult,
+	process::{
+		pid::Pid,
+		scheduler::{Scheduler, SCHEDULER},
+		Process,
+	},
+};
+use core::{mem::ManuallyDrop, ptr};
+use utils::errno::AllocResult;
+
+/// The ID of the *Device Not Available* exception.
+const DEVICE_NOT_AVAILABLE: u32 = 0x07;
+
+/// Tells whether `proc` owns the FPU on the core of `sched`.
+fn is_owner(sched: &Scheduler, proc: &Process) -> bool {
+	sched
+		.fpu_owner
+		.as_ref()
+		.is_some_and(|owner| ptr::eq(owner.as_ref(), proc))
+}
+
+/// Handles the *Device Not Available* exception, giving the FPU to the current task.
+fn device_not_available(_id: u32, _code: u32, _frame: &mut IntFrame, ring: u8) -> CallbackResult {
+	if ring < 3 {
+		return CallbackResult::Panic;
+	}
+	let mut sched = SCHEDULER.lock();
+	let curr = sched.curr_proc.clone();
+	set_task_switched(false);
+	if let Some(owner) = &sched.fpu_owner {
+		fxsave(&mut owner.fpu.lock());
+	}
+	fxrstor(&curr.fpu.lock());
+	sched.fpu_owner = Some(curr);
+	CallbackResult::Continue
+}
+
+/// Registers the handler of the *Device Not Available* exception.
+///
+/// Since the FPU has no owner yet, it is disabled until the first task uses it.
+pub(super) fn init() -> AllocResult<()> {
+	set_task_switched(true);
+	let _ = ManuallyDrop::new(event::register_callback(
+		DEVICE_NOT_AVAILABLE,
+		device_not_available,
+	)?);
+	Ok(())
+}
+
+/// Prepares the FPU for the execution of `next`, on context switch.
+///
+/// If `next` owns the FPU, its state is still loaded and the FPU is enabled. Otherwise, the FPU is
+/// disabled until `next` uses it.
+pub(super) fn switch(next: &Process) {
+	let owner = is_owner(&SCHEDULER.lock(), next);
+	set_task_switched(!owner);
+}
+
+/// Saves the state of the FPU to `proc`, if `proc` owns it.
+///
+/// This must be called before reading the FPU state of `proc`.
+pub fn save(proc: &Process) {
+	let sched = SCHEDULER.lock();
+	if !is_owner(&sched, proc) {
+		return;
+	}
+	set_task_switched(false);
+	fxsave(&mut proc.fpu.lock());
+	set_task_switched(!ptr::eq(sched.curr_proc.as_ref(), proc));
+}
+
+/// Releases the FPU if it is owned by the process with PID `pid`, which is being removed.
+pub(super) fn release(sched: &mut Scheduler, pid: Pid) {
+	if sched
+		.fpu_owner
+		.as_ref()
+		.is_some_and(|owner| owner.get_pid() == pid)
+	{
+		sched.fpu_owner = None;
+	}
+}
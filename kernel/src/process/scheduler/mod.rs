@@ -19,41 +19,266 @@
 //! The role of the process scheduler is to interrupt the currently running
 //! process periodically to switch to another process that is in running state.
 //!
-//! TODO
+//! Each process has a scheduling policy:
+//! - [`SCHED_OTHER`]: the default policy. Processes are scheduled in a round-robin fashion
+//! - [`SCHED_FIFO`]: real-time policy. The process runs until it blocks or yields
+//! - [`SCHED_RR`]: real-time policy. Processes with the same priority are time-sliced
+//!
+//! A runnable real-time process always preempts processes with a lower real-time priority and
+//! processes with the [`SCHED_OTHER`] policy.
+//!
+//! Among [`SCHED_OTHER`] processes, the nice value of a process determines the number of
+//! consecutive ticks it runs for before being preempted (see [`quantum_count`]).
+//!
+//! To avoid priority inversion, a process holding a [`Mutex`] that a real-time process waits for
+//! inherits the priority of the latter until it releases the mutex (see
+//! [`Scheduler::update_priority`]).
+//!
+//! Processes whose control group exhausted its CPU quota are not eligible until the next period
+//! (see [`cgroup`]).
+//!
+//! [`Mutex`]: crate::sync::mutex::Mutex
+//! [`cgroup`]: crate::process::cgroup
 
+pub mod fpu;
 pub mod switch;
+pub mod watchdog;
 
 use crate::{
-	arch::x86::{cli, idt::IntFrame, pic},
-	event,
+	arch::{
+		x86,
+		x86::{cli, idt::IntFrame, pic, sti},
+	},
+	cpu, event,
 	event::{CallbackHook, CallbackResult},
+	file::fs::debugfs,
+	format_content,
 	process::{pid::Pid, scheduler::switch::switch, Process, State},
-	sync::{atomic::AtomicU64, mutex::IntMutex, once::OnceInit},
+	sync::{atomic::AtomicU64, mutex::IntMutex, once::OnceInit, rcu},
 	time,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::Timestamp,
+	},
 };
 use core::{
-	mem,
+	fmt, mem,
+	ops::RangeFrom,
 	sync::{
 		atomic,
-		atomic::{AtomicUsize, Ordering::Release},
+		atomic::{
+			AtomicBool, AtomicU16, AtomicUsize,
+			Ordering::{Acquire, Relaxed, Release},
+		},
 	},
 };
 use utils::{
-	collections::btreemap::{BTreeMap, MapIterator},
-	errno::AllocResult,
+	collections::{
+		btreemap::{BTreeMap, MapIterator, MapRange},
+		vec::Vec,
+	},
+	errno::{AllocResult, EResult},
 	math::rational::Rational,
 	ptr::arc::Arc,
 };
 
+/// The interval between two samples of the load averages, in nanoseconds.
+const LOAD_FREQ: Timestamp = 5_000_000_000;
+/// The number of bits of precision of fixed-point load averages.
+pub const FSHIFT: u32 = 11;
+/// The value `1` in fixed-point.
+const FIXED_1: usize = 1 << FSHIFT;
+/// Decay factors, in fixed-point, of the 1, 5 and 15 minutes load averages.
+const LOAD_EXP: [usize; 3] = [1884, 2014, 2037];
+
+/// Scheduling policy: default time-sharing policy.
+pub const SCHED_OTHER: u8 = 0;
+/// Scheduling policy: real-time first-in first-out policy.
+pub const SCHED_FIFO: u8 = 1;
+/// Scheduling policy: real-time round-robin policy.
+pub const SCHED_RR: u8 = 2;
+
+/// The minimum priority of a process with a real-time policy.
+pub const RT_PRIORITY_MIN: u8 = 1;
+/// The maximum priority of a process with a real-time policy.
+pub const RT_PRIORITY_MAX: u8 = 99;
+
+/// The minimum nice value, giving the highest share of CPU time.
+pub const NICE_MIN: i8 = -20;
+/// The maximum nice value, giving the lowest share of CPU time.
+pub const NICE_MAX: i8 = 19;
+
 /// The process scheduler.
 pub static SCHEDULER: OnceInit<IntMutex<Scheduler>> = unsafe { OnceInit::new() };
 
+/// Tells whether the scheduler has been initialized.
+static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+/// Tells whether [`SCHEDULER`] has been initialized.
+pub fn is_initialized() -> bool {
+	INITIALIZED.load(Acquire)
+}
+
+/// The PID of the process currently running, readable without locking [`SCHEDULER`].
+static CURRENT_PID: AtomicU16 = AtomicU16::new(0);
+
+/// Returns the PID of the process currently running.
+///
+/// If the scheduler is not initialized or if the current process is the idle task, the function
+/// returns zero.
+#[inline]
+pub fn current_pid() -> Pid {
+	CURRENT_PID.load(Relaxed)
+}
+
+/// Called while the current process waits for a mutex held by the process whose PID is stored in
+/// `owner`.
+///
+/// If the current process is real-time, the owner inherits its priority if higher, then the
+/// current process yields so that the owner gets the chance to release the mutex. In this case,
+/// `boosted` is set so that [`restore_priority`] is called when the mutex is released, and the
+/// function returns `true`.
+///
+/// Else, the function returns `false` and the caller shall keep spinning.
+pub(crate) fn inherit_priority(owner: &AtomicU16, boosted: &AtomicBool) -> bool {
+	// If preemption is disabled, the owner cannot run before the mutex is released anyway
+	if !is_initialized() || !x86::is_interrupt_enabled() || rcu::in_read_section() {
+		return false;
+	}
+	{
+		let mut sched = SCHEDULER.lock();
+		let priority = sched.curr_proc.effective_priority();
+		if priority == 0 {
+			return false;
+		}
+		// Interrupts are disabled, so the owner cannot release the mutex concurrently
+		let Some(owner) = sched.get_by_pid(owner.load(Relaxed)) else {
+			return false;
+		};
+		if owner.effective_priority() < priority {
+			boosted.store(true, Relaxed);
+			sched.update_priority(&owner, priority);
+		}
+	}
+	Scheduler::yield_now();
+	// The scheduler returns with interrupts disabled
+	sti();
+	true
+}
+
+/// Restores the priority of the current process after it released a mutex through which it
+/// inherited a priority.
+pub(crate) fn restore_priority() {
+	let mut sched = SCHEDULER.lock();
+	let curr = sched.curr_proc.clone();
+	sched.update_priority(&curr, 0);
+}
+
+/// Scheduling attributes of a process, used to select the next process to run.
+#[derive(Clone, Copy, Debug)]
+struct Candidate {
+	/// The PID of the process.
+	pid: Pid,
+	/// Tells whether the process is runnable, allowed on the current core and not throttled by
+	/// its control group.
+	running: bool,
+	/// The effective real-time priority of the process.
+	priority: u8,
+	/// Tells whether the process has the [`SCHED_FIFO`] policy.
+	fifo: bool,
+}
+
+impl Candidate {
+	/// Returns the scheduling attributes of `proc` at the timestamp `now`.
+	fn new(proc: &Process, now: Timestamp) -> Self {
+		Self {
+			pid: proc.get_pid(),
+			running: matches!(proc.get_state(), State::Running)
+				&& proc.can_run_on(cpu::current())
+				&& !proc.cgroup().is_throttled(now),
+			priority: proc.effective_priority(),
+			fifo: proc.get_sched_policy() == SCHED_FIFO,
+		}
+	}
+}
+
+/// Returns the number of consecutive ticks a [`SCHED_OTHER`] process with the given `nice`
+/// value runs for before being preempted.
+///
+/// The count ranges from `10` for [`NICE_MIN`] to `1` for [`NICE_MAX`].
+pub fn quantum_count(nice: i8) -> u32 {
+	(20 - nice as i32).max(4) as u32 / 4
+}
+
+/// Returns the PID of the next process to run.
+///
+/// Arguments:
+/// - `procs` returns an iterator over the processes registered to the scheduler
+/// - `curr` is the current process
+/// - `yielded` tells whether the current process gave up the CPU voluntarily, in which case even a
+///   FIFO process lets other processes with the same priority run
+/// - `expired` tells whether the current process used all of its quanta (see [`quantum_count`])
+///
+/// Only the runnable processes with the highest priority are eligible.
+fn pick_next<I: Iterator<Item = Candidate>, F: Fn() -> I>(
+	procs: F,
+	curr: Candidate,
+	yielded: bool,
+	expired: bool,
+) -> Option<Pid> {
+	let priority = procs().filter(|c| c.running).map(|c| c.priority).max()?;
+	if curr.running && curr.priority == priority && !yielded {
+		// A FIFO process keeps running until it blocks or yields
+		if priority > 0 && curr.fifo {
+			return Some(curr.pid);
+		}
+		// A time-sharing process keeps running until its time slice expires
+		if priority == 0 && !expired {
+			return Some(curr.pid);
+		}
+	}
+	// Take the process following the current one, looping back to the beginning
+	let eligible = || {
+		procs()
+			.filter(move |c| c.running && c.priority == priority)
+			.map(|c| c.pid)
+	};
+	eligible()
+		.filter(|pid| *pid > curr.pid)
+		.min()
+		.or_else(|| eligible().min())
+}
+
+/// Writes the processes ready to run, for the `sched/runqueue` file of the debugfs.
+fn runqueue(off: u64, buf: &mut [u8]) -> EResult<usize> {
+	let sched = SCHEDULER.lock();
+	let procs = fmt::from_fn(|f| {
+		writeln!(f, "pid policy priority nice")?;
+		sched
+			.iter_process()
+			.filter(|(_, proc)| proc.get_state() == State::Running)
+			.try_for_each(|(pid, proc)| {
+				writeln!(
+					f,
+					"{pid} {} {} {}",
+					proc.get_sched_policy(),
+					proc.effective_priority(),
+					proc.nice()
+				)
+			})
+	});
+	format_content!(off, buf, "{procs}")
+}
+
 /// Initializes schedulers.
-pub fn init() -> AllocResult<()> {
+pub fn init() -> EResult<()> {
 	unsafe {
 		OnceInit::init(&SCHEDULER, IntMutex::new(Scheduler::new()?));
 	}
+	INITIALIZED.store(true, Release);
 	SCHEDULER.lock().setup_gs_base();
+	fpu::init()?;
+	debugfs::create_file(b"sched/runqueue", Some(runqueue), None)?;
 	Ok(())
 }
 
@@ -84,7 +309,24 @@ pub struct Scheduler {
 	curr_proc: Arc<Process>,
 	/// The current number of processes in running state.
 	running_procs: usize,
+	/// The PIDs of exited threads, to be removed once they are not running anymore.
+	reap_queue: Vec<Pid>,
+	/// The 1, 5 and 15 minutes load averages, in fixed-point (see [`FSHIFT`]).
+	load_avg: [usize; 3],
+	/// The timestamp of the next sample of the load averages, on the monotonic clock.
+	next_load_sample: Timestamp,
+	/// The timestamp, on the monotonic clock, at which the current process has been scheduled.
+	slice_start: Timestamp,
+	/// Tells whether the current process gave up the CPU voluntarily.
+	yielded: bool,
+	/// The number of ticks left in the time slice of the current process.
+	quantum: u32,
+	/// The timestamp, on the monotonic clock, up to which CPU time has been charged to the
+	/// control group of the current process.
+	charged_until: Timestamp,
 
+	/// The task whose state is currently loaded in the FPU.
+	fpu_owner: Option<Arc<Process>>,
 	/// The task used to idle.
 	idle_task: Arc<Process>,
 
@@ -97,9 +339,9 @@ impl Scheduler {
 	pub(super) fn new() -> AllocResult<Self> {
 		// Register tick callback
 		let mut clocks = time::hw::CLOCKS.lock();
-		let pit = clocks.get_mut(b"pit".as_slice()).unwrap();
+		let clock = time::hw::tick_clock(&mut clocks);
 		let tick_callback_hook = event::register_callback(
-			pit.get_interrupt_vector(),
+			clock.get_interrupt_vector(),
 			|_: u32, _: u32, _: &mut IntFrame, _: u8| {
 				Scheduler::tick();
 				CallbackResult::Continue
@@ -114,7 +356,15 @@ impl Scheduler {
 			processes: BTreeMap::new(),
 			curr_proc: idle_task.clone(),
 			running_procs: 0,
+			reap_queue: Vec::new(),
+			load_avg: [0; 3],
+			next_load_sample: current_time_ns(Clock::Monotonic) + LOAD_FREQ,
+			slice_start: 0,
+			yielded: false,
+			quantum: 0,
+			charged_until: current_time_ns(Clock::Monotonic),
 
+			fpu_owner: None,
 			idle_task,
 
 			gs: KernelGs::default(),
@@ -144,6 +394,15 @@ impl Scheduler {
 		self.processes.iter()
 	}
 
+	/// Returns an iterator on processes whose PID is greater than or equal to `start`, in
+	/// ascending order of PID.
+	pub fn iter_process_from(
+		&self,
+		start: Pid,
+	) -> MapRange<'_, Pid, Arc<Process>, RangeFrom<Pid>> {
+		self.processes.range(start..)
+	}
+
 	/// Returns the process with PID `pid`.
 	///
 	/// If the process doesn't exist, the function returns `None`.
@@ -154,8 +413,9 @@ impl Scheduler {
 	/// Returns the process with TID `tid`.
 	///
 	/// If the process doesn't exist, the function returns `None`.
-	pub fn get_by_tid(&self, _tid: Pid) -> Option<Arc<Process>> {
-		todo!()
+	pub fn get_by_tid(&self, tid: Pid) -> Option<Arc<Process>> {
+		// A thread is registered with its TID
+		self.get_by_pid(tid)
 	}
 
 	/// Returns the current running process.
@@ -168,6 +428,9 @@ impl Scheduler {
 		self.gs
 			.kernel_stack
 			.store(new.kernel_stack.top().as_ptr() as _, Release);
+		CURRENT_PID.store(new.get_pid(), Relaxed);
+		#[cfg(feature = "fault_inject")]
+		crate::fault::switch(&new);
 		mem::replace(&mut self.curr_proc, new)
 	}
 
@@ -188,53 +451,137 @@ impl Scheduler {
 		if proc.get_state() == State::Running {
 			self.decrement_running();
 		}
+		fpu::release(self, pid);
 		self.processes.remove(&pid);
 	}
 
+	/// Schedules the removal of the exited thread with TID `tid`.
+	///
+	/// Contrary to processes, threads and kernel threads are not waited for: they are removed on a
+	/// later tick, once their kernel stack is not in use anymore.
+	pub fn defer_reap(&mut self, tid: Pid) -> AllocResult<()> {
+		self.reap_queue.push(tid)
+	}
+
+	/// Removes a thread from the reap queue if it is not running, and returns it.
+	fn reap_next(&mut self) -> Option<Arc<Process>> {
+		let curr = self.curr_proc.get_pid();
+		let i = self.reap_queue.iter().position(|tid| *tid != curr)?;
+		let tid = self.reap_queue.remove(i);
+		let proc = self.get_by_pid(tid);
+		self.remove_process(tid);
+		proc
+	}
+
 	/// Returns the current ticking frequency of the scheduler.
 	pub fn get_ticking_frequency(&self) -> Rational {
 		Rational::from_integer((10 * self.running_procs) as _)
 	}
 
+	/// Returns the CPU time consumed by `proc`, in nanoseconds.
+	pub fn get_cpu_time(&self, proc: &Process) -> Timestamp {
+		let mut time = proc.cpu_time.load(Relaxed);
+		if proc.get_pid() == self.curr_proc.get_pid() {
+			time += current_time_ns(Clock::Monotonic).saturating_sub(self.slice_start);
+		}
+		time
+	}
+
+	/// Returns the number of processes registered to the scheduler.
+	pub fn processes_count(&self) -> usize {
+		self.processes.len()
+	}
+
+	/// Samples the number of running processes into the load averages, for each sampling
+	/// interval elapsed since the last sample.
+	///
+	/// Since the number of running processes can only change through
+	/// [`Self::increment_running`] and [`Self::decrement_running`], this function is called
+	/// before each change, which allows to catch up on missed samples lazily.
+	fn update_load_avg(&mut self) {
+		let now = current_time_ns(Clock::Monotonic);
+		let active = self.running_procs * FIXED_1;
+		while self.next_load_sample <= now {
+			for (load, exp) in self.load_avg.iter_mut().zip(LOAD_EXP) {
+				let mut new = *load * exp + active * (FIXED_1 - exp);
+				// Round up when growing so that the average can reach `active`
+				if active >= *load {
+					new += FIXED_1 - 1;
+				}
+				*load = new >> FSHIFT;
+			}
+			self.next_load_sample += LOAD_FREQ;
+		}
+	}
+
+	/// Returns the 1, 5 and 15 minutes load averages, in fixed-point (see [`FSHIFT`]).
+	pub fn get_load_avg(&mut self) -> [usize; 3] {
+		self.update_load_avg();
+		self.load_avg
+	}
+
 	/// Increments the number of running processes.
 	pub fn increment_running(&mut self) {
+		self.update_load_avg();
 		self.running_procs += 1;
 		let mut clocks = time::hw::CLOCKS.lock();
-		let pit = clocks.get_mut(b"pit".as_slice()).unwrap();
+		let clock = time::hw::tick_clock(&mut clocks);
 		if self.running_procs >= 1 {
-			pit.set_frequency(self.get_ticking_frequency());
-			pit.set_enabled(true);
+			clock.set_frequency(self.get_ticking_frequency());
+			clock.set_enabled(true);
 		}
 	}
 
 	/// Decrements the number of running processes.
 	pub fn decrement_running(&mut self) {
+		self.update_load_avg();
 		self.running_procs -= 1;
 		let mut clocks = time::hw::CLOCKS.lock();
-		let pit = clocks.get_mut(b"pit".as_slice()).unwrap();
+		let clock = time::hw::tick_clock(&mut clocks);
 		if self.running_procs == 0 {
-			pit.set_enabled(false);
+			clock.set_enabled(false);
 		} else {
-			pit.set_frequency(self.get_ticking_frequency());
+			clock.set_frequency(self.get_ticking_frequency());
 		}
 	}
 
-	/// Returns the next process to run with its PID.
-	fn get_next_process(&self) -> Option<Arc<Process>> {
-		// Get the current process, or take the first process in the list if no
-		// process is running
-		let curr_pid = self.curr_proc.get_pid();
-		let process_filter =
-			|(_, proc): &(&Pid, &Arc<Process>)| matches!(proc.get_state(), State::Running);
-		self.processes
-			.range((curr_pid + 1)..)
-			.find(process_filter)
-			.or_else(|| {
-				// If no suitable process is found, go back to the beginning to check processes
-				// located before the previous process (looping)
-				self.processes.range(..=curr_pid).find(process_filter)
-			})
-			.map(|(_, proc)| proc.clone())
+	/// Sets the priority inherited by `proc` from the processes waiting for a mutex it holds.
+	///
+	/// A `priority` of zero restores the process's own priority.
+	pub fn update_priority(&mut self, proc: &Process, priority: u8) {
+		proc.inherited_priority.store(priority, Relaxed);
+	}
+
+	/// Returns the next process to run at the timestamp `now`.
+	///
+	/// `yielded` tells whether the current process gave up the CPU voluntarily, and `expired`
+	/// whether its time slice expired.
+	fn get_next_process(
+		&self,
+		yielded: bool,
+		expired: bool,
+		now: Timestamp,
+	) -> Option<Arc<Process>> {
+		let curr = &self.curr_proc;
+		let procs = || {
+			self.processes
+				.iter()
+				.map(|(_, proc)| Candidate::new(proc, now))
+		};
+		let pid = pick_next(procs, Candidate::new(curr, now), yielded, expired)?;
+		if pid == curr.get_pid() {
+			return Some(curr.clone());
+		}
+		self.get_by_pid(pid)
+	}
+
+	/// Gives up the CPU, letting other runnable processes with the same priority run, then
+	/// returns once the current process is scheduled again.
+	///
+	/// Contrary to [`Self::tick`], this applies to [`SCHED_FIFO`] processes too.
+	pub fn yield_now() {
+		SCHEDULER.lock().yielded = true;
+		Self::tick();
 	}
 
 	/// Ticking the scheduler.
@@ -243,23 +590,60 @@ impl Scheduler {
 	///
 	/// If no process is ready to run, the scheduler halts the current core until a process becomes
 	/// runnable.
+	///
+	/// If the current context is inside a RCU read-side critical section, preemption is deferred
+	/// to a later tick.
 	pub fn tick() {
 		// Disable interrupts so that no interrupt can occur before switching to the next process
 		cli();
+		if !rcu::quiescent_state() {
+			// The yield is dropped so that it does not apply to a later preemption
+			SCHEDULER.lock().yielded = false;
+			return;
+		}
+		// The last reference to a reaped thread is dropped after releasing the scheduler
+		let reaped;
 		let (prev, next) = {
 			let mut sched = SCHEDULER.lock();
 			sched.total_ticks.fetch_add(1, atomic::Ordering::Relaxed);
+			reaped = sched.reap_next();
+			watchdog::pet();
+			let now = current_time_ns(Clock::Monotonic);
+			// Charge the CPU time used since the previous tick to the control group
+			if !sched.curr_proc.is_idle_task() {
+				let delta = now.saturating_sub(sched.charged_until);
+				sched.curr_proc.cgroup().charge_cpu(delta, now);
+			}
+			sched.charged_until = now;
 			// Find the next process to run
-			let next = sched.get_next_process().unwrap_or(sched.idle_task.clone());
+			let yielded = mem::take(&mut sched.yielded);
+			sched.quantum = sched.quantum.saturating_sub(1);
+			let expired = sched.quantum == 0;
+			let next = sched
+				.get_next_process(yielded, expired, now)
+				.unwrap_or(sched.idle_task.clone());
+			// Start a new time slice, unless the idle task runs since any process preempts it
+			if next.get_pid() != sched.curr_proc.get_pid() || expired {
+				sched.quantum = if next.is_idle_task() {
+					0
+				} else {
+					quantum_count(next.nice())
+				};
+			}
 			// If the process to run is the current, do nothing
 			if next.get_pid() == sched.curr_proc.get_pid() {
 				return;
 			}
+			// Account the CPU time of the previous process
+			let elapsed = now.saturating_sub(sched.slice_start);
+			sched.curr_proc.cpu_time.fetch_add(elapsed, Relaxed);
+			sched.slice_start = now;
 			// Swap current running process. We use pointers to avoid cloning the Arc
 			let next_ptr = Arc::as_ptr(&next);
 			let prev = sched.swap_current_process(next);
 			(Arc::as_ptr(&prev), next_ptr)
 		};
+		drop(reaped);
 		// Send end of interrupt, so that the next tick can be received
 		pic::end_of_interrupt(0);
 		unsafe {
@@ -267,3 +651,128 @@ impl Scheduler {
 		}
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Returns a runnable candidate.
+	fn candidate(pid: Pid, priority: u8, fifo: bool) -> Candidate {
+		Candidate {
+			pid,
+			running: true,
+			priority,
+			fifo,
+		}
+	}
+
+	#[test_case]
+	fn sched_round_robin() {
+		let procs = [
+			candidate(1, 0, false),
+			candidate(2, 0, false),
+			candidate(3, 0, false),
+		];
+		let next = |curr| pick_next(|| procs.iter().copied(), procs[curr], false, true);
+		assert_eq!(next(0), Some(2));
+		assert_eq!(next(1), Some(3));
+		assert_eq!(next(2), Some(1));
+	}
+
+	#[test_case]
+	fn sched_fifo() {
+		let procs = [
+			candidate(1, 10, true),
+			candidate(2, 10, true),
+			candidate(3, 0, false),
+		];
+		assert_eq!(
+			pick_next(|| procs.iter().copied(), procs[0], false, true),
+			Some(1)
+		);
+		assert_eq!(
+			pick_next(|| procs.iter().copied(), procs[0], true, true),
+			Some(2)
+		);
+	}
+
+	#[test_case]
+	fn sched_yield() {
+		// Two busy processes alternate on each yield
+		let procs = [candidate(1, 0, false), candidate(2, 0, false)];
+		let mut curr = procs[0];
+		for _ in 0..4 {
+			let next = pick_next(|| procs.iter().copied(), curr, true, true).unwrap();
+			assert_ne!(next, curr.pid);
+			curr = procs[next as usize - 1];
+		}
+		// A process that is the only one runnable keeps running
+		let mut procs = procs;
+		procs[1].running = false;
+		assert_eq!(
+			pick_next(|| procs.iter().copied(), procs[0], true, true),
+			Some(1)
+		);
+	}
+
+	#[test_case]
+	fn sched_priority_inversion() {
+		// The low priority process holds a mutex the high priority process waits for, while the
+		// medium priority process is busy
+		let mut low = candidate(1, 0, false);
+		let medium = candidate(2, 50, true);
+		let high = candidate(3, 90, true);
+		let procs = |low| [low, medium, high];
+		// Without inheritance, the medium priority process starves the low priority one
+		assert_eq!(
+			pick_next(|| procs(low).into_iter(), high, true, true),
+			Some(3)
+		);
+		assert_eq!(
+			pick_next(|| procs(low).into_iter(), medium, false, true),
+			Some(3)
+		);
+		// With inheritance, the low priority process runs when the high priority one yields
+		low.priority = high.priority;
+		assert_eq!(
+			pick_next(|| procs(low).into_iter(), high, true, true),
+			Some(1)
+		);
+		assert_eq!(
+			pick_next(|| procs(low).into_iter(), low, false, true),
+			Some(3)
+		);
+	}
+
+	#[test_case]
+	fn sched_nice() {
+		assert_eq!(quantum_count(NICE_MIN), 10);
+		assert_eq!(quantum_count(0), 5);
+		assert_eq!(quantum_count(NICE_MAX), 1);
+		// Simulate ticks the same way as `Scheduler::tick`, with a process having a lower nice
+		// value than the other
+		let procs = [candidate(1, 0, false), candidate(2, 0, false)];
+		let nice = [-10, 10];
+		let mut ticks = [0; 2];
+		let mut curr = 0;
+		let mut quantum = quantum_count(nice[curr]);
+		for _ in 0..1000 {
+			quantum = quantum.saturating_sub(1);
+			let expired = quantum == 0;
+			let next = pick_next(|| procs.iter().copied(), procs[curr], false, expired).unwrap();
+			let next = next as usize - 1;
+			if next != curr || expired {
+				quantum = quantum_count(nice[next]);
+			}
+			curr = next;
+			ticks[curr] += 1;
+		}
+		assert!(ticks[0] > ticks[1] * 2);
+		// A process with quanta left is preempted by a higher priority process
+		let procs = [candidate(1, 0, false), candidate(2, 10, false)];
+		assert_eq!(
+			pick_next(|| procs.iter().copied(), procs[0], false, false),
+			Some(2)
+		);
+	}
+}
@@ -19,9 +19,9 @@
 //! Context switching utilities.
 
 use crate::{
-	arch::x86::{fxrstor, fxsave, gdt, idt::IntFrame, tss},
+	arch::x86::{gdt, idt::IntFrame, tss},
 	memory::vmem::KERNEL_VMEM,
-	process::Process,
+	process::{scheduler::fpu, Process},
 };
 use core::{arch::global_asm, mem::offset_of, ptr::NonNull};
 
@@ -203,7 +203,7 @@ switch_asm:
 ///
 /// This function is jumped to from [`switch`].
 #[export_name = "switch_finish"]
-pub extern "C" fn finish(prev: &Process, next: &Process) {
+pub extern "C" fn finish(_prev: &Process, next: &Process) {
 	// Bind the memory space
 	match next.mem_space.as_ref() {
 		Some(mem_space) => mem_space.lock().bind(),
@@ -213,6 +213,7 @@ pub extern "C" fn finish(prev: &Process, next: &Process) {
 	// Update the TSS for the process
 	unsafe {
 		tss::set_kernel_stack(next.kernel_stack.top().as_ptr());
+		tss::set_io_bitmap(&next.io_bitmap.lock());
 	}
 	// Update TLS entries in the GDT
 	{
@@ -223,10 +224,10 @@ pub extern "C" fn finish(prev: &Process, next: &Process) {
 			}
 		}
 	}
-	// TODO save and restore only if necessary (enable the FPU when the first interruption occurs)
-	// Save and restore FPU state
-	fxsave(&mut prev.fpu.lock());
-	fxrstor(&next.fpu.lock());
+	// The FPU state is switched lazily, on first use
+	fpu::switch(next);
+	#[cfg(feature = "kcov")]
+	crate::device::kcov::switch(next);
 }
 
 /// The entry point of a kernel thread.
@@ -0,0 +1,210 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The watchdog detects when the system stops making progress.
+//!
+//! The scheduler pets the watchdog on each tick, and the watchdog is checked periodically from a
+//! different timer, once the scheduler has started. The following conditions are detected:
+//! - The scheduler has not ticked for [`STALL_WARN`]: a warning is printed
+//! - The scheduler has not ticked for [`STALL_PANIC`]: the kernel panics
+//! - The timer checking the watchdog has not fired for [`IRQ_LATENCY_WARN`], meaning interrupts
+//!   have been disabled for too long (for example, by a CPU spinning in the kernel): a warning is
+//!   printed
+//!
+//! Since the check itself is performed by an interrupt, a CPU spinning with interrupts disabled
+//! forever cannot be detected. Only the latency is reported, once interrupts are enabled again.
+
+use crate::{
+	println,
+	sync::atomic::AtomicU64,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::Timestamp,
+	},
+};
+use core::sync::atomic::{AtomicBool, Ordering::Relaxed};
+
+/// The duration without scheduler tick after which a warning is printed, in nanoseconds.
+pub const STALL_WARN: Timestamp = 5_000_000_000;
+/// The duration without scheduler tick after which the kernel panics, in nanoseconds.
+pub const STALL_PANIC: Timestamp = 30_000_000_000;
+/// The duration between two checks after which a warning is printed, in nanoseconds.
+pub const IRQ_LATENCY_WARN: Timestamp = 1_000_000_000;
+
+/// A problem detected by the watchdog.
+#[derive(Debug, Eq, PartialEq)]
+enum Alarm {
+	/// The scheduler has not made progress for the given duration, in nanoseconds.
+	Stall(Timestamp),
+	/// The scheduler has not made progress for too long, the system is considered hung.
+	Hang(Timestamp),
+	/// Interrupts have been disabled for the given duration, in nanoseconds.
+	IrqLatency(Timestamp),
+}
+
+/// The state of a watchdog.
+struct Watchdog {
+	/// The number of times the watchdog has been pet.
+	pets: AtomicU64,
+	/// The value of `pets` at the last check.
+	last_pets: AtomicU64,
+	/// The timestamp at which progress was last observed.
+	last_progress: AtomicU64,
+	/// The timestamp of the last check. If zero, the watchdog has never been checked.
+	last_check: AtomicU64,
+	/// Tells whether a stall warning has been printed since the last progress.
+	warned: AtomicBool,
+}
+
+impl Watchdog {
+	/// Creates a new instance.
+	const fn new() -> Self {
+		Self {
+			pets: AtomicU64::new(0),
+			last_pets: AtomicU64::new(0),
+			last_progress: AtomicU64::new(0),
+			last_check: AtomicU64::new(0),
+			warned: AtomicBool::new(false),
+		}
+	}
+
+	/// Signals progress.
+	fn pet(&self) {
+		self.pets.fetch_add(1, Relaxed);
+	}
+
+	/// Checks for progress at the timestamp `now`, returning the alarms to raise.
+	///
+	/// The function must not be called concurrently.
+	fn check(&self, now: Timestamp) -> [Option<Alarm>; 2] {
+		let last_check = self.last_check.load(Relaxed);
+		self.last_check.store(now, Relaxed);
+		// The watchdog is armed only once the scheduler has started
+		let pets = self.pets.load(Relaxed);
+		if last_check == 0 || pets == 0 {
+			self.last_progress.store(now, Relaxed);
+			return [None, None];
+		}
+		let latency = now.saturating_sub(last_check);
+		let latency = (latency >= IRQ_LATENCY_WARN).then_some(Alarm::IrqLatency(latency));
+		// Check the scheduler has made progress
+		if pets != self.last_pets.load(Relaxed) {
+			self.last_pets.store(pets, Relaxed);
+			self.last_progress.store(now, Relaxed);
+			self.warned.store(false, Relaxed);
+			return [latency, None];
+		}
+		let stall = now.saturating_sub(self.last_progress.load(Relaxed));
+		let stall = if stall >= STALL_PANIC {
+			Some(Alarm::Hang(stall))
+		} else if stall >= STALL_WARN && !self.warned.swap(true, Relaxed) {
+			Some(Alarm::Stall(stall))
+		} else {
+			None
+		};
+		[latency, stall]
+	}
+}
+
+/// The watchdog monitoring the scheduler.
+static WATCHDOG: Watchdog = Watchdog::new();
+
+/// Signals the scheduler has made progress.
+#[inline]
+pub fn pet() {
+	WATCHDOG.pet();
+}
+
+/// Checks the scheduler is making progress, reporting problems.
+///
+/// This function must be called periodically, from an interrupt handler.
+pub fn check() {
+	let now = current_time_ns(Clock::Monotonic);
+	for alarm in WATCHDOG.check(now).into_iter().flatten() {
+		match alarm {
+			Alarm::Stall(ns) => println!(
+				"watchdog: the scheduler has not ticked for {} ms",
+				ns / 1_000_000
+			),
+			Alarm::Hang(ns) => panic!(
+				"watchdog: the scheduler has not ticked for {} ms",
+				ns / 1_000_000
+			),
+			Alarm::IrqLatency(ns) => println!(
+				"watchdog: interrupts have been disabled for {} ms",
+				ns / 1_000_000
+			),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// One millisecond, in nanoseconds.
+	const MS: Timestamp = 1_000_000;
+
+	/// Checks `watchdog` every 100 milliseconds from `t`, until a stall alarm is raised.
+	fn next_stall(watchdog: &Watchdog, t: &mut Timestamp) -> Alarm {
+		loop {
+			*t += 100 * MS;
+			let [latency, stall] = watchdog.check(*t);
+			assert_eq!(latency, None);
+			if let Some(stall) = stall {
+				break stall;
+			}
+		}
+	}
+
+	#[test_case]
+	fn watchdog_stall() {
+		let watchdog = Watchdog::new();
+		watchdog.pet();
+		assert_eq!(watchdog.check(MS), [None, None]);
+		// Progress is made
+		watchdog.pet();
+		assert_eq!(watchdog.check(2 * MS), [None, None]);
+		// The scheduler stalls. The warning is printed only once before panicking
+		let mut t = 2 * MS;
+		assert_eq!(next_stall(&watchdog, &mut t), Alarm::Stall(STALL_WARN));
+		assert_eq!(next_stall(&watchdog, &mut t), Alarm::Hang(STALL_PANIC));
+		// Progress resumes
+		watchdog.pet();
+		assert_eq!(watchdog.check(t + MS), [None, None]);
+	}
+
+	#[test_case]
+	fn watchdog_irq_latency() {
+		let watchdog = Watchdog::new();
+		// The watchdog is not armed before the scheduler starts
+		assert_eq!(watchdog.check(MS), [None, None]);
+		assert_eq!(watchdog.check(MS + STALL_PANIC), [None, None]);
+		watchdog.pet();
+		assert_eq!(watchdog.check(2 * MS + STALL_PANIC), [None, None]);
+		// Interrupts are disabled, preventing the check
+		let t = 2 * MS + STALL_PANIC + IRQ_LATENCY_WARN;
+		watchdog.pet();
+		assert_eq!(
+			watchdog.check(t),
+			[Some(Alarm::IrqLatency(IRQ_LATENCY_WARN)), None]
+		);
+		watchdog.pet();
+		assert_eq!(watchdog.check(t + MS), [None, None]);
+	}
+}
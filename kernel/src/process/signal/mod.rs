@@ -21,21 +21,19 @@
 pub mod ucontext;
 
 use super::{Process, State, REDZONE_SIZE};
-use crate::{
-	arch::x86::idt::IntFrame, file::perm::Uid, memory::VirtAddr, process::pid::Pid,
-	time::unit::ClockIdT,
-};
+use crate::{arch::x86::idt::IntFrame, file::perm::Uid, memory::VirtAddr, process::pid::Pid};
 use core::{
+	array,
 	ffi::{c_int, c_void},
 	mem::{size_of, transmute},
 	ptr,
 	ptr::NonNull,
 	slice,
 };
-use ucontext::UContext32;
 #[cfg(target_pointer_width = "64")]
 use ucontext::UContext64;
-use utils::{errno, errno::Errno};
+use ucontext::{UContext32, UC_RESTART};
+use utils::{collections::vec::Vec, errno, errno::Errno};
 
 /// Signal handler value: Ignoring the signal.
 pub const SIG_IGN: usize = 0x0;
@@ -62,7 +60,7 @@ pub const SIGEV_THREAD: c_int = 2;
 
 /// The size of the signal handlers table (the number of signals + 1, since
 /// indexing begins at 1 instead of 0).
-pub const SIGNALS_COUNT: usize = 32;
+pub const SIGNALS_COUNT: usize = 65;
 
 /// Enumeration representing the action to perform for a signal.
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -80,14 +78,22 @@ pub enum SignalAction {
 }
 
 impl SignalAction {
-	/// Executes the signal action for the given process.
-	pub fn exec(self, process: &Process) {
+	/// Executes the signal action for `signal` on the given process.
+	///
+	/// Terminating the process terminates its whole thread group.
+	pub fn exec(self, signal: Signal, process: &Process) {
 		match self {
 			// TODO when `Abort`ing, dump core
-			SignalAction::Terminate | SignalAction::Abort => process.set_state(State::Zombie),
+			SignalAction::Terminate | SignalAction::Abort => process.exit_group(0, signal as _),
 			SignalAction::Ignore => {}
-			SignalAction::Stop => process.set_state(State::Stopped),
-			SignalAction::Continue => process.set_state(State::Running),
+			SignalAction::Stop => {
+				process.signal.lock().termsig = signal as _;
+				process.set_state(State::Stopped);
+			}
+			SignalAction::Continue => {
+				process.signal.lock().termsig = 0;
+				process.set_state(State::Running);
+			}
 		}
 	}
 }
@@ -95,83 +101,249 @@ impl SignalAction {
 /// A signal handler value.
 pub type SigVal = usize;
 
-// FIXME: fields are incorrect (check musl source)
+/// `si_code`: the signal was sent by `kill`.
+pub const SI_USER: i32 = 0;
+/// `si_code`: the signal was sent by the kernel.
+pub const SI_KERNEL: i32 = 0x80;
+/// `si_code`: the signal was sent by `sigqueue`.
+pub const SI_QUEUE: i32 = -1;
+/// `si_code`: the signal was sent by the expiration of a timer.
+pub const SI_TIMER: i32 = -2;
+/// `si_code`: the signal was sent by the arrival of a message on an empty message queue.
+pub const SI_MESGQ: i32 = -3;
+/// `si_code`: the signal was sent by `tkill` or `tgkill`.
+pub const SI_TKILL: i32 = -6;
+
+/// `si_code` for [`Signal::SIGILL`]: illegal opcode.
+pub const ILL_ILLOPC: i32 = 1;
+
+/// `si_code` for [`Signal::SIGFPE`]: integer divide by zero.
+pub const FPE_INTDIV: i32 = 1;
+/// `si_code` for [`Signal::SIGFPE`]: integer overflow.
+pub const FPE_INTOVF: i32 = 2;
+
+/// `si_code` for [`Signal::SIGSEGV`]: address not mapped to object.
+pub const SEGV_MAPERR: i32 = 1;
+/// `si_code` for [`Signal::SIGSEGV`]: invalid permissions for mapped object.
+pub const SEGV_ACCERR: i32 = 2;
+/// `si_code` for [`Signal::SIGBUS`]: invalid address alignment.
+pub const BUS_ADRALN: i32 = 1;
+/// `si_code` for [`Signal::SIGBUS`]: nonexistent physical address.
+pub const BUS_ADRERR: i32 = 2;
+
+/// `si_code` for [`Signal::SIGCHLD`]: the child has exited.
+pub const CLD_EXITED: i32 = 1;
+/// `si_code` for [`Signal::SIGCHLD`]: the child was killed.
+pub const CLD_KILLED: i32 = 2;
+/// `si_code` for [`Signal::SIGCHLD`]: the child has stopped.
+pub const CLD_STOPPED: i32 = 5;
+/// `si_code` for [`Signal::SIGCHLD`]: the stopped child has continued.
+pub const CLD_CONTINUED: i32 = 6;
+
+/// The size of the userspace signal information structure (`siginfo_t`), in bytes.
+const SIGINFO_SIZE: usize = 128;
+
 /// Signal information.
-#[repr(C)]
+///
+/// Only the fields relevant to the signal's origin are passed to userspace.
+#[derive(Clone, Copy, Debug, Default)]
 pub struct SigInfo {
 	/// Signal number.
-	si_signo: i32,
+	pub si_signo: i32,
 	/// An errno value.
-	si_errno: i32,
-	/// Signal code.
-	si_code: i32,
-	/// Trap number that caused hardware-generated signal.
-	si_trapno: i32,
+	pub si_errno: i32,
+	/// Signal code, telling the origin of the signal.
+	pub si_code: i32,
 	/// Sending process ID.
-	si_pid: Pid,
+	pub si_pid: Pid,
 	/// Real user ID of sending process.
-	si_uid: Uid,
+	pub si_uid: Uid,
 	/// Exit value or signal.
-	si_status: i32,
-	/// User time consumed.
-	si_utime: ClockIdT,
-	/// System time consumed.
-	si_stime: ClockIdT,
-	/// Signal value
-	si_value: SigVal,
-	/// POSIX.1b signal.
-	si_int: i32,
-	/// POSIX.1b signal.
-	si_ptr: *mut c_void,
-	/// Timer overrun count.
-	si_overrun: i32,
+	pub si_status: i32,
+	/// Signal value.
+	pub si_value: SigVal,
 	/// Timer ID.
-	si_timerid: i32,
+	pub si_timerid: i32,
+	/// Timer overrun count.
+	pub si_overrun: i32,
 	/// Memory location which caused fault.
-	si_addr: *mut c_void,
-	/// Band event.
-	si_band: i32, // FIXME long (64bits?)
-	/// File descriptor.
-	si_fd: i32,
-	/// Least significant bit of address.
-	si_addr_lsb: i16,
-	/// Lower bound when address violation.
-	si_lower: *mut c_void,
-	/// Upper bound when address violation.
-	si_upper: *mut c_void,
-	/// Protection key on PTE that caused fault.
-	si_pkey: i32,
-	/// Address of system call instruction.
-	si_call_addr: *mut c_void,
-	/// Number of attempted system call.
-	si_syscall: i32,
-	/// Architecture of attempted system call.
-	si_arch: u32,
+	pub si_addr: usize,
+}
+
+impl SigInfo {
+	/// Creates a new instance for the signal `sig`, with the code `code`.
+	pub fn new(sig: Signal, code: i32) -> Self {
+		Self {
+			si_signo: sig as _,
+			si_code: code,
+			..Default::default()
+		}
+	}
+
+	/// Returns the userspace representation of the structure.
+	///
+	/// `compat` tells whether the structure is for a 32-bit process.
+	fn to_user(self, compat: bool) -> [u8; SIGINFO_SIZE] {
+		/// Writes `val` at offset `off` in `buf`.
+		fn put(buf: &mut [u8], off: usize, val: &[u8]) {
+			buf[off..(off + val.len())].copy_from_slice(val);
+		}
+		let word = if compat { 4 } else { size_of::<usize>() };
+		let mut buf = [0; SIGINFO_SIZE];
+		put(&mut buf, 0, &self.si_signo.to_ne_bytes());
+		put(&mut buf, 4, &self.si_errno.to_ne_bytes());
+		put(&mut buf, 8, &self.si_code.to_ne_bytes());
+		// The union of fields is aligned on the size of a word
+		let off = 12usize.next_multiple_of(word);
+		let fault = matches!(
+			Signal::try_from(self.si_signo),
+			Ok(Signal::SIGSEGV
+				| Signal::SIGBUS
+				| Signal::SIGILL
+				| Signal::SIGFPE
+				| Signal::SIGTRAP)
+		);
+		let (pid, uid) = (self.si_pid as i32, self.si_uid);
+		match self.si_code {
+			// Faults generated by the kernel
+			1.. if fault => put(&mut buf, off, &self.si_addr.to_ne_bytes()[..word]),
+			1.. if self.si_signo == Signal::SIGCHLD as i32 => {
+				put(&mut buf, off, &pid.to_ne_bytes());
+				put(&mut buf, off + 4, &uid.to_ne_bytes());
+				put(&mut buf, off + 8, &self.si_status.to_ne_bytes());
+			}
+			SI_TIMER => {
+				put(&mut buf, off, &self.si_timerid.to_ne_bytes());
+				put(&mut buf, off + 4, &self.si_overrun.to_ne_bytes());
+				put(&mut buf, off + 8, &self.si_value.to_ne_bytes()[..word]);
+			}
+			_ => {
+				put(&mut buf, off, &pid.to_ne_bytes());
+				put(&mut buf, off + 4, &uid.to_ne_bytes());
+				put(&mut buf, off + 8, &self.si_value.to_ne_bytes()[..word]);
+			}
+		}
+		buf
+	}
 }
 
 /// Kernelspace signal mask.
+///
+/// Signal `n` is represented by the bit `n - 1`.
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct SigSet(pub u64);
 
 impl SigSet {
-	/// Tells whether the `n`th bit is set.
+	/// Tells whether the signal `n` is in the set.
 	pub fn is_set(&self, n: usize) -> bool {
-		self.0 & (1 << n) != 0
+		self.0 & (1 << (n - 1)) != 0
 	}
 
-	/// Sets the `n`th bit.
+	/// Adds the signal `n` to the set.
 	pub fn set(&mut self, n: usize) {
-		self.0 |= (1 << n) as u64;
+		self.0 |= 1 << (n - 1);
 	}
 
-	/// Sets the `n`th bit.
+	/// Removes the signal `n` from the set.
 	pub fn clear(&mut self, n: usize) {
-		self.0 &= !((1 << n) as u64);
+		self.0 &= !(1 << (n - 1));
 	}
 
-	/// Returns an iterator over the bitset's values
-	pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
-		(0..64).map(|n| self.is_set(n))
+	/// Returns an iterator over the signals in the set, in increasing order.
+	pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+		(1..SIGNALS_COUNT).filter(|n| self.is_set(*n))
+	}
+}
+
+/// The set of signals pending on a process.
+///
+/// Standard signals are merged: if a signal is sent several times before being delivered, it is
+/// delivered only once. Real-time signals are queued instead, so that each occurrence is
+/// delivered.
+#[derive(Debug, Default)]
+pub struct PendingSignals {
+	/// The set of pending signals.
+	set: SigSet,
+	/// Information about pending standard signals. Signal `n` is at index `n - 1`.
+	info: [SigInfo; Signal::SIGRTMIN as usize - 1],
+	/// Queued real-time signals, in the order they have been sent.
+	rt_queue: Vec<SigInfo>,
+}
+
+impl PendingSignals {
+	/// Adds an occurrence of the signal described by `info`.
+	///
+	/// If the signal cannot be queued due to a lack of memory, it is discarded.
+	pub fn push(&mut self, info: SigInfo) {
+		let Ok(sig) = Signal::try_from(info.si_signo) else {
+			return;
+		};
+		if sig.is_realtime() {
+			if self.rt_queue.push(info).is_err() {
+				return;
+			}
+		} else if !self.set.is_set(sig as _) {
+			self.info[sig as usize - 1] = info;
+		}
+		self.set.set(sig as _);
+	}
+
+	/// Returns the set of pending signals.
+	pub fn set(&self) -> SigSet {
+		self.set
+	}
+
+	/// Returns the number of pending occurrences, each queued real-time signal being counted.
+	pub fn count(&self) -> usize {
+		let std = self
+			.set
+			.iter()
+			.filter(|n| *n < Signal::SIGRTMIN as usize)
+			.count();
+		std + self.rt_queue.len()
+	}
+
+	/// Returns the pending signal with the lowest number that is not blocked by `sigmask`, without
+	/// removing it.
+	pub fn peek(&self, sigmask: &SigSet) -> Option<Signal> {
+		self.set
+			.iter()
+			.filter_map(|n| Signal::try_from(n as c_int).ok())
+			.find(|s| !s.can_catch() || !sigmask.is_set(*s as _))
+	}
+
+	/// Returns the information of a pending occurrence of a signal for which `f` returns `true`.
+	pub fn find_mut<F: FnMut(&SigInfo) -> bool>(&mut self, mut f: F) -> Option<&mut SigInfo> {
+		let set = self.set;
+		let std = self
+			.info
+			.iter_mut()
+			.enumerate()
+			.filter(|(i, _)| set.is_set(*i + 1))
+			.map(|(_, info)| info);
+		std.chain(self.rt_queue.iter_mut()).find(|info| f(info))
+	}
+
+	/// Removes an occurrence of `sig` and returns its information.
+	///
+	/// If `sig` is not pending, the function returns `None`.
+	pub fn pop(&mut self, sig: Signal) -> Option<SigInfo> {
+		if !self.set.is_set(sig as _) {
+			return None;
+		}
+		if !sig.is_realtime() {
+			self.set.clear(sig as _);
+			return Some(self.info[sig as usize - 1]);
+		}
+		let i = self
+			.rt_queue
+			.iter()
+			.position(|info| info.si_signo == sig as i32)?;
+		let info = self.rt_queue.remove(i);
+		if !self.rt_queue.iter().any(|info| info.si_signo == sig as i32) {
+			self.set.clear(sig as _);
+		}
+		Some(info)
 	}
 }
 
@@ -229,35 +401,34 @@ impl From<SigAction> for CompatSigAction {
 }
 
 /// Notification from asynchronous routines.
+///
+/// The layout matches the beginning of the userspace structure, the rest being padding.
 #[repr(C)]
 #[derive(Clone, Debug)]
 pub struct SigEvent {
-	/// Notification method.
-	pub sigev_notify: c_int,
+	/// Value passed with the notification.
+	pub sigev_value: SigVal,
 	/// Notification signal.
 	pub sigev_signo: c_int,
-	/// TODO doc
-	pub sigev_value: SigVal,
+	/// Notification method.
+	pub sigev_notify: c_int,
 	/// Function used for thread notification.
 	pub sigev_notify_function: Option<NonNull<extern "C" fn(SigVal)>>,
 	/// Data passed with notification.
 	pub sigev_notify_attributes: Option<NonNull<c_void>>,
-	/// ID of thread to signal.
-	pub sigev_notify_thread_id: Pid,
 }
 
 impl SigEvent {
 	/// Tells whether the structure is valid.
+	///
+	/// Thread notification is implemented by the libc on top of signals, so it is not valid in
+	/// the kernel.
 	pub fn is_valid(&self) -> bool {
-		if !matches!(self.sigev_notify, SIGEV_SIGNAL | SIGEV_NONE | SIGEV_THREAD) {
-			return false;
+		match self.sigev_notify {
+			SIGEV_NONE => true,
+			SIGEV_SIGNAL => Signal::try_from(self.sigev_signo).is_ok(),
+			_ => false,
 		}
-		if Signal::try_from(self.sigev_signo).is_err() {
-			return false;
-		}
-		// TODO check sigev_notify_thread_id
-
-		true
 	}
 }
 
@@ -283,6 +454,11 @@ impl From<SigAction> for SignalHandler {
 	}
 }
 
+/// Returns a table of signal handlers, all set to [`SignalHandler::Default`].
+pub fn default_handlers() -> [SignalHandler; SIGNALS_COUNT] {
+	array::from_fn(|_| SignalHandler::Default)
+}
+
 impl SignalHandler {
 	/// Creates a handler from a value given by the `signal` system call.
 	#[allow(clippy::not_unsafe_ptr_arg_deref)]
@@ -330,7 +506,10 @@ impl SignalHandler {
 	}
 
 	/// Executes the action for `signal` on the **current** process `process`.
-	pub fn exec(&self, signal: Signal, process: &Process, frame: &mut IntFrame) {
+	///
+	/// `info` is the information about the signal, passed to the handler if it has been
+	/// registered with [`SA_SIGINFO`].
+	pub fn exec(&self, signal: Signal, info: &SigInfo, process: &Process, frame: &mut IntFrame) {
 		let process_state = process.get_state();
 		if matches!(process_state, State::Zombie) {
 			return;
@@ -343,23 +522,36 @@ impl SignalHandler {
 				// Signals on the init process can be executed only if the process has set a
 				// signal handler
 				if !process.is_init() || !signal.can_catch() {
-					signal.get_default_action().exec(process);
+					signal.get_default_action().exec(signal, process);
 				}
 				return;
 			}
 		};
+		let siginfo = action.sa_flags & SA_SIGINFO != 0;
+		// If the handler interrupted a system call, the call returns `EINTR` unless the handler
+		// has been registered with `SA_RESTART`, in which case it is restarted on `sigreturn`
+		let restart = process
+			.signal
+			.lock()
+			.restart
+			.take()
+			.filter(|_| action.sa_flags & SA_RESTART != 0);
+		if let Some(id) = restart {
+			frame.rax = id as _;
+		}
 		// TODO trigger EFAULT if SA_RESTORER is not set
-		// TODO handle SA_SIGINFO
 		// TODO Handle the case where an alternate stack is specified (sigaltstack + flag
 		// SA_ONSTACK)
 		// Prepare the signal handler stack
 		let stack_addr = VirtAddr(frame.get_stack_address()) - REDZONE_SIZE;
 		// Size of the `ucontext_t` struct and arguments *on the stack*
 		let (ctx_size, ctx_align, arg_len) = if frame.is_compat() {
+			// With `SA_SIGINFO`, pointers to the signal information and context are passed too
+			let args_count = if siginfo { 4 } else { 2 };
 			(
 				size_of::<UContext32>(),
 				align_of::<UContext32>(),
-				size_of::<u32>() * 2,
+				size_of::<u32>() * args_count,
 			)
 		} else {
 			#[cfg(target_pointer_width = "32")]
@@ -371,25 +563,48 @@ impl SignalHandler {
 				size_of::<u64>(),
 			)
 		};
-		let ctx_addr = (stack_addr - ctx_size).down_align_to(ctx_align);
+		// The signal information structure is placed above the context
+		let info_addr = if siginfo {
+			(stack_addr - SIGINFO_SIZE).down_align_to(size_of::<usize>())
+		} else {
+			stack_addr
+		};
+		let ctx_addr = (info_addr - ctx_size).down_align_to(ctx_align);
 		let signal_sp = ctx_addr - arg_len;
 		// Bind virtual memory
 		process.mem_space.as_ref().unwrap().lock().bind();
 		// Write data on stack
+		if siginfo {
+			unsafe {
+				ptr::write_volatile(info_addr.as_ptr(), info.to_user(frame.is_compat()));
+			}
+		}
 		if frame.is_compat() {
 			let args = unsafe {
-				ptr::write_volatile(ctx_addr.as_ptr(), UContext32::new(process, frame));
+				let mut ctx = UContext32::new(process, frame);
+				if restart.is_some() {
+					ctx.uc_flags |= UC_RESTART;
+				}
+				ptr::write_volatile(ctx_addr.as_ptr(), ctx);
 				// Arguments slice
-				slice::from_raw_parts_mut(signal_sp.as_ptr::<u32>(), 2)
+				slice::from_raw_parts_mut(signal_sp.as_ptr::<u32>(), arg_len / size_of::<u32>())
 			};
-			// Argument
-			args[1] = signal as _;
 			// Return pointer
 			args[0] = action.sa_restorer as _;
+			// Arguments
+			args[1] = signal as _;
+			if siginfo {
+				args[2] = info_addr.0 as _;
+				args[3] = ctx_addr.0 as _;
+			}
 		} else {
 			#[cfg(target_pointer_width = "64")]
 			unsafe {
-				ptr::write_volatile(ctx_addr.as_ptr(), UContext64::new(process, frame));
+				let mut ctx = UContext64::new(process, frame);
+				if restart.is_some() {
+					ctx.uc_flags |= UC_RESTART as u64;
+				}
+				ptr::write_volatile(ctx_addr.as_ptr(), ctx);
 				// Return pointer
 				ptr::write_volatile(signal_sp.as_ptr::<u64>(), action.sa_restorer as _);
 			}
@@ -409,8 +624,12 @@ impl SignalHandler {
 		#[cfg(target_pointer_width = "64")]
 		if !frame.is_compat() {
 			frame.rcx = frame.rip;
-			// Argument
+			// Arguments
 			frame.rdi = signal as _;
+			if siginfo {
+				frame.rsi = info_addr.0 as _;
+				frame.rdx = ctx_addr.0 as _;
+			}
 		}
 	}
 }
@@ -477,6 +696,72 @@ pub enum Signal {
 	SIGPOLL = 29,
 	/// Bad system call.
 	SIGSYS = 31,
+	/// Real-time signal 0.
+	SIGRT0 = 32,
+	/// Real-time signal 1.
+	SIGRT1 = 33,
+	/// Real-time signal 2.
+	SIGRT2 = 34,
+	/// Real-time signal 3.
+	SIGRT3 = 35,
+	/// Real-time signal 4.
+	SIGRT4 = 36,
+	/// Real-time signal 5.
+	SIGRT5 = 37,
+	/// Real-time signal 6.
+	SIGRT6 = 38,
+	/// Real-time signal 7.
+	SIGRT7 = 39,
+	/// Real-time signal 8.
+	SIGRT8 = 40,
+	/// Real-time signal 9.
+	SIGRT9 = 41,
+	/// Real-time signal 10.
+	SIGRT10 = 42,
+	/// Real-time signal 11.
+	SIGRT11 = 43,
+	/// Real-time signal 12.
+	SIGRT12 = 44,
+	/// Real-time signal 13.
+	SIGRT13 = 45,
+	/// Real-time signal 14.
+	SIGRT14 = 46,
+	/// Real-time signal 15.
+	SIGRT15 = 47,
+	/// Real-time signal 16.
+	SIGRT16 = 48,
+	/// Real-time signal 17.
+	SIGRT17 = 49,
+	/// Real-time signal 18.
+	SIGRT18 = 50,
+	/// Real-time signal 19.
+	SIGRT19 = 51,
+	/// Real-time signal 20.
+	SIGRT20 = 52,
+	/// Real-time signal 21.
+	SIGRT21 = 53,
+	/// Real-time signal 22.
+	SIGRT22 = 54,
+	/// Real-time signal 23.
+	SIGRT23 = 55,
+	/// Real-time signal 24.
+	SIGRT24 = 56,
+	/// Real-time signal 25.
+	SIGRT25 = 57,
+	/// Real-time signal 26.
+	SIGRT26 = 58,
+	/// Real-time signal 27.
+	SIGRT27 = 59,
+	/// Real-time signal 28.
+	SIGRT28 = 60,
+	/// Real-time signal 29.
+	SIGRT29 = 61,
+	/// Real-time signal 30.
+	SIGRT30 = 62,
+	/// Real-time signal 31.
+	SIGRT31 = 63,
+	/// Real-time signal 32.
+	SIGRT32 = 64,
 }
 
 impl TryFrom<i32> for Signal {
@@ -484,7 +769,7 @@ impl TryFrom<i32> for Signal {
 
 	/// `id` is the signal ID.
 	fn try_from(id: i32) -> Result<Self, Self::Error> {
-		if matches!(id, (1..=15) | (17..=29) | 31) {
+		if matches!(id, (1..=15) | (17..=29) | (31..=64)) {
 			// Safe because the value is in range
 			unsafe { Ok(transmute::<i32, Self>(id)) }
 		} else {
@@ -494,8 +779,21 @@ impl TryFrom<i32> for Signal {
 }
 
 impl Signal {
+	/// The real-time signal with the highest number.
+	pub const SIGRTMAX: Self = Self::SIGRT32;
+	/// The real-time signal with the lowest number.
+	pub const SIGRTMIN: Self = Self::SIGRT0;
+
+	/// Tells whether the signal is a real-time signal.
+	pub fn is_realtime(&self) -> bool {
+		*self as i32 >= Self::SIGRTMIN as i32
+	}
+
 	/// Returns the default action for the signal.
 	pub fn get_default_action(&self) -> SignalAction {
+		if self.is_realtime() {
+			return SignalAction::Terminate;
+		}
 		match self {
 			Self::SIGHUP => SignalAction::Terminate,
 			Self::SIGINT => SignalAction::Terminate,
@@ -526,6 +824,7 @@ impl Signal {
 			Self::SIGWINCH => SignalAction::Ignore,
 			Self::SIGPOLL => SignalAction::Terminate,
 			Self::SIGSYS => SignalAction::Abort,
+			_ => SignalAction::Terminate,
 		}
 	}
 
@@ -537,3 +836,43 @@ impl Signal {
 		)
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn pending_signals() {
+		let mut pending = PendingSignals::default();
+		let mask = SigSet::default();
+		let info = |sig, pid| SigInfo {
+			si_pid: pid,
+			..SigInfo::new(sig, SI_USER)
+		};
+		// Standard signals are merged
+		pending.push(info(Signal::SIGUSR1, 1));
+		pending.push(info(Signal::SIGUSR1, 2));
+		assert_eq!(pending.peek(&mask), Some(Signal::SIGUSR1));
+		assert_eq!(pending.pop(Signal::SIGUSR1).unwrap().si_pid, 1);
+		assert_eq!(pending.peek(&mask), None);
+		// Real-time signals are queued, and delivered lowest number first
+		pending.push(info(Signal::SIGRTMAX, 1));
+		pending.push(info(Signal::SIGRTMIN, 2));
+		pending.push(info(Signal::SIGRTMIN, 3));
+		for (sig, pid) in [
+			(Signal::SIGRTMIN, 2),
+			(Signal::SIGRTMIN, 3),
+			(Signal::SIGRTMAX, 1),
+		] {
+			assert_eq!(pending.peek(&mask), Some(sig));
+			assert_eq!(pending.pop(sig).unwrap().si_pid, pid);
+		}
+		assert_eq!(pending.peek(&mask), None);
+		// Blocked signals are not delivered
+		let mut mask = SigSet::default();
+		mask.set(Signal::SIGRTMIN as _);
+		pending.push(info(Signal::SIGRTMIN, 1));
+		pending.push(info(Signal::SIGRTMAX, 1));
+		assert_eq!(pending.peek(&mask), Some(Signal::SIGRTMAX));
+	}
+}
@@ -27,6 +27,37 @@ use crate::{
 
 // TODO restore everything
 
+/// Kernel-private flag of `uc_flags`: the system call interrupted by the signal is restarted when
+/// the handler returns, since it has been registered with `SA_RESTART`.
+///
+/// The ID of the system call is saved in the context as the `rax` register.
+pub const UC_RESTART: u32 = 1 << 31;
+
+/// The flags of the `EFLAGS` register that a signal handler is allowed to change: AC, OF, DF, TF,
+/// SF, ZF, AF, PF, CF and RF.
+///
+/// Other flags (such as IF or IOPL) are kept from the current context, so that a forged context
+/// cannot grant privileges.
+const USER_FLAGS: usize = 0x50dd5;
+
+/// Returns the flags to restore, from the current flags `cur` and the flags `saved` in the
+/// context.
+fn restore_flags(cur: usize, saved: usize) -> usize {
+	(cur & !USER_FLAGS) | (saved & USER_FLAGS)
+}
+
+/// Returns the segment selector to restore, from the selector `saved` in the context.
+///
+/// A non-null selector is forced to the userspace privilege level, so that a kernel segment
+/// cannot be loaded. Code and stack segments are never restored from the context.
+fn restore_selector(saved: usize) -> usize {
+	if saved & !0b11 == 0 {
+		0
+	} else {
+		saved | 0b11
+	}
+}
+
 // ------------------------------
 //    32 bit structures
 
@@ -127,9 +158,10 @@ impl UContext32 {
 
 	/// Restores the context.
 	pub fn restore_regs(&self, proc: &Process, frame: &mut IntFrame) {
+		// Restore segment selectors
+		frame.gs = restore_selector(self.uc_mcontext.gregs[GReg32::Gs as usize] as _) as _;
+		frame.fs = restore_selector(self.uc_mcontext.gregs[GReg32::Fs as usize] as _) as _;
 		// Restore general registers
-		frame.gs = self.uc_mcontext.gregs[GReg32::Gs as usize] as _;
-		frame.fs = self.uc_mcontext.gregs[GReg32::Fs as usize] as _;
 		frame.rax = self.uc_mcontext.gregs[GReg32::Eax as usize] as _;
 		frame.rbx = self.uc_mcontext.gregs[GReg32::Ebx as usize] as _;
 		frame.rcx = self.uc_mcontext.gregs[GReg32::Ecx as usize] as _;
@@ -139,7 +171,10 @@ impl UContext32 {
 		frame.rbp = self.uc_mcontext.gregs[GReg32::Ebp as usize] as _;
 		frame.rsp = self.uc_mcontext.gregs[GReg32::Esp as usize] as _;
 		frame.rip = self.uc_mcontext.gregs[GReg32::Eip as usize] as _;
-		frame.rflags = self.uc_mcontext.gregs[GReg32::Efl as usize] as _;
+		frame.rflags = restore_flags(
+			frame.rflags as _,
+			self.uc_mcontext.gregs[GReg32::Efl as usize] as _,
+		) as _;
 		// TODO restore fpstate
 		proc.signal.lock().sigmask = self.uc_sigmask;
 	}
@@ -193,6 +228,7 @@ pub struct FpReg32 {
 #[cfg(target_arch = "x86_64")]
 /// 64 bit structures.
 mod long {
+	use super::restore_flags;
 	use crate::{
 		arch::x86::idt::IntFrame,
 		process::{mem_space::bound_check, signal::SigSet, Process},
@@ -333,7 +369,10 @@ mod long {
 			}
 			frame.rsp = rsp;
 			frame.rip = rip;
-			frame.rflags = self.uc_mcontext.gregs[GReg64::Efl as usize] as _;
+			frame.rflags = restore_flags(
+				frame.rflags as _,
+				self.uc_mcontext.gregs[GReg64::Efl as usize] as _,
+			) as _;
 			// TODO restore fpstate
 			proc.signal.lock().sigmask = self.uc_sigmask;
 			Ok(())
@@ -0,0 +1,81 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! UTS namespaces isolate the hostname and the NIS domain name of the system.
+
+use crate::{
+	file::perm::UserNamespace,
+	sync::{mutex::Mutex, once::OnceInit},
+};
+use core::sync::atomic::{AtomicU32, Ordering::Relaxed};
+use utils::{collections::vec::Vec, errno::AllocResult, ptr::arc::Arc, TryClone};
+
+/// A UTS namespace.
+#[derive(Debug)]
+pub struct UtsNamespace {
+	/// The ID of the namespace.
+	id: u32,
+	/// The user namespace owning the namespace.
+	pub user_ns: Arc<UserNamespace>,
+	/// The hostname.
+	pub hostname: Mutex<Vec<u8>>,
+	/// The NIS domain name.
+	pub domainname: Mutex<Vec<u8>>,
+}
+
+/// The ID of the next UTS namespace to be created. The initial namespace has the ID `1`.
+static NEXT_NS_ID: AtomicU32 = AtomicU32::new(2);
+
+/// The initial UTS namespace.
+static ROOT: OnceInit<Arc<UtsNamespace>> = unsafe { OnceInit::new() };
+
+impl UtsNamespace {
+	/// Returns the initial UTS namespace.
+	pub fn root() -> &'static Arc<Self> {
+		&ROOT
+	}
+
+	/// Returns the ID of the namespace.
+	pub fn id(&self) -> u32 {
+		self.id
+	}
+
+	/// Creates a new namespace with a copy of the names of `self`, owned by `user_ns`.
+	pub fn copy(&self, user_ns: Arc<UserNamespace>) -> AllocResult<Arc<Self>> {
+		Arc::new(Self {
+			id: NEXT_NS_ID.fetch_add(1, Relaxed),
+			user_ns,
+			hostname: Mutex::new(self.hostname.lock().try_clone()?),
+			domainname: Mutex::new(self.domainname.lock().try_clone()?),
+		})
+	}
+}
+
+/// Initializes the initial UTS namespace.
+pub(super) fn init() -> AllocResult<()> {
+	let ns = Arc::new(UtsNamespace {
+		id: 1,
+		user_ns: UserNamespace::root().clone(),
+		hostname: Mutex::new(Vec::new()),
+		domainname: Mutex::new(Vec::new()),
+	})?;
+	unsafe {
+		OnceInit::init(&ROOT, ns);
+	}
+	Ok(())
+}
@@ -27,10 +27,48 @@
 
 #[cfg(config_debug_qemu)]
 use crate::debug::qemu;
-use crate::power;
+use crate::{
+	arch::x86::idt::IntFrame,
+	device::{
+		queue,
+		queue::{RequestQueue, IOPRIO_CLASS_SHIFT},
+	},
+	file::{
+		fs::{debugfs, debugfs::DebugFsType, tmp::TmpFsType, FilesystemType},
+		perm::AccessProfile,
+		vfs, File, FileType, Stat, O_RDONLY, O_RDWR,
+	},
+	format_content,
+	memory::buddy,
+	net,
+	net::{buff::BuffList, Address},
+	power,
+	process::{
+		exec,
+		exec::ExecInfo,
+		scheduler::{Scheduler, SCHEDULER},
+		Process,
+	},
+	sync::{mutex::Mutex, rcu, rcu::RcuHashMap},
+	syscall,
+};
 use core::{
+	alloc::AllocError,
 	any::type_name,
-	sync::{atomic, atomic::AtomicBool},
+	fmt, mem,
+	sync::{
+		atomic,
+		atomic::{AtomicBool, AtomicUsize},
+	},
+};
+use utils::{
+	collections::{
+		path::{Path, PathBuf},
+		vec::Vec,
+	},
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
 };
 
 /// Boolean value telling whether selftesting is running.
@@ -75,3 +113,561 @@ pub fn runner(tests: &[&dyn Testable]) {
 pub fn is_running() -> bool {
 	RUNNING.load(atomic::Ordering::Relaxed)
 }
+
+/// Error returned by a failed integration test.
+#[derive(Debug)]
+pub enum TestError {
+	/// An assertion failed. The value is the location and the text of the condition.
+	Assert(&'static str),
+	/// An operation returned an error.
+	Errno(Errno),
+}
+
+impl From<Errno> for TestError {
+	fn from(e: Errno) -> Self {
+		Self::Errno(e)
+	}
+}
+
+impl From<AllocError> for TestError {
+	fn from(e: AllocError) -> Self {
+		Self::Errno(e.into())
+	}
+}
+
+impl fmt::Display for TestError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Assert(cond) => write!(f, "assertion failed: {cond}"),
+			Self::Errno(e) => write!(f, "{e}"),
+		}
+	}
+}
+
+/// The result of an integration test.
+pub type TestResult = Result<(), TestError>;
+
+/// Makes the current integration test fail if the given condition is not fulfilled.
+#[macro_export]
+macro_rules! ktest_assert {
+	($cond:expr) => {
+		if !$cond {
+			return Err($crate::selftest::TestError::Assert(concat!(
+				file!(),
+				":",
+				line!(),
+				": ",
+				stringify!($cond)
+			)));
+		}
+	};
+}
+
+/// An integration test, run on a fully booted kernel.
+///
+/// Contrary to unit tests, integration tests are compiled in every kernel and can be run at any
+/// time, either with the `selftest` boot flag or by reading `/proc/kernel_tests`.
+pub struct IntegrationTest {
+	/// The name of the test.
+	pub name: &'static str,
+	/// The function running the test.
+	pub run: fn() -> TestResult,
+}
+
+/// The list of registered integration tests.
+pub static INTEGRATION_TESTS: &[IntegrationTest] = &[
+	IntegrationTest {
+		name: "buddy_alloc",
+		run: buddy_alloc,
+	},
+	IntegrationTest {
+		name: "tmpfs_rw",
+		run: tmpfs_rw,
+	},
+	IntegrationTest {
+		name: "vfs_root",
+		run: vfs_root,
+	},
+	IntegrationTest {
+		name: "net_loopback",
+		run: net_loopback,
+	},
+	IntegrationTest {
+		name: "debugfs_file",
+		run: debugfs_file,
+	},
+	IntegrationTest {
+		name: "io_queue_priority",
+		run: io_queue_priority,
+	},
+	IntegrationTest {
+		name: "io_queue_look",
+		run: io_queue_look,
+	},
+	IntegrationTest {
+		name: "syscall_dispatch",
+		run: syscall_dispatch,
+	},
+	IntegrationTest {
+		name: "exec_shebang_loop",
+		run: exec_shebang_loop,
+	},
+	IntegrationTest {
+		name: "rcu_stress",
+		run: rcu_stress,
+	},
+];
+
+/// Runs the given integration tests, writing a report to `out`.
+///
+/// The function returns the number of tests that passed and the number of tests that failed.
+pub fn run_integration(tests: &[IntegrationTest], out: &mut dyn fmt::Write) -> (usize, usize) {
+	let mut passed = 0;
+	for test in tests {
+		let res = (test.run)();
+		// A failure to write the report must not change the results
+		let _ = match &res {
+			Ok(()) => writeln!(out, "test {} ... ok", test.name),
+			Err(e) => writeln!(out, "test {} ... FAILED: {e}", test.name),
+		};
+		passed += res.is_ok() as usize;
+	}
+	let failed = tests.len() - passed;
+	let _ = writeln!(
+		out,
+		"{} tests: {passed} passed, {failed} failed",
+		tests.len()
+	);
+	(passed, failed)
+}
+
+/// Writer printing to the kernel's console.
+struct Console;
+
+impl fmt::Write for Console {
+	fn write_str(&mut self, s: &str) -> fmt::Result {
+		crate::print!("{s}");
+		Ok(())
+	}
+}
+
+/// Runs the registered integration tests at boot, then halts the kernel or exits the emulator if
+/// possible.
+///
+/// The emulator's exit status tells whether every test passed.
+pub fn integration_runner() -> ! {
+	crate::println!("Running {} integration tests", INTEGRATION_TESTS.len());
+	let (_, failed) = run_integration(INTEGRATION_TESTS, &mut Console);
+	#[cfg(config_debug_qemu)]
+	qemu::exit(if failed == 0 {
+		qemu::SUCCESS
+	} else {
+		qemu::FAILURE
+	});
+	#[cfg(not(config_debug_qemu))]
+	let _ = failed;
+	power::halt();
+}
+
+/// Allocates frames of several orders, fills them and frees them.
+fn buddy_alloc() -> TestResult {
+	for order in 0..4 {
+		let ptr = buddy::alloc_kernel(order, buddy::ZONE_KERNEL)?;
+		let len = buddy::get_frame_size(order);
+		let ok = unsafe {
+			ptr.as_ptr().write_bytes(0xa5, len);
+			let slice = core::slice::from_raw_parts(ptr.as_ptr(), len);
+			slice.iter().all(|b| *b == 0xa5)
+		};
+		unsafe {
+			buddy::free_kernel(ptr.as_ptr(), order);
+		}
+		ktest_assert!(ok);
+	}
+	Ok(())
+}
+
+/// Creates, writes, reads back and removes a file on a tmpfs that is not mounted anywhere.
+fn tmpfs_rw() -> TestResult {
+	const CONTENT: &[u8] = b"integration test content";
+	let fs = TmpFsType.load_filesystem(None, PathBuf::root()?, false)?;
+	let root = fs.ops.root(fs.clone())?;
+	let root = Arc::new(vfs::Entry::new(Default::default(), None, Some(root)))?;
+	let ent = vfs::create_file(
+		root,
+		b"file",
+		&AccessProfile::KERNEL,
+		Stat {
+			mode: FileType::Regular.to_mode() | 0o600,
+			..Default::default()
+		},
+	)?;
+	let file = File::open_entry(ent.clone(), O_RDWR)?;
+	let len = file.ops.write(&file, 0, CONTENT)?;
+	ktest_assert!(len == CONTENT.len());
+	let mut buf = [0u8; 64];
+	let len = file.ops.read(&file, 0, &mut buf)?;
+	ktest_assert!(&buf[..len] == CONTENT);
+	// Reading past the end returns nothing
+	let len = file.ops.read(&file, CONTENT.len() as _, &mut buf)?;
+	ktest_assert!(len == 0);
+	drop(file);
+	vfs::unlink(&ent, &AccessProfile::KERNEL)?;
+	Ok(())
+}
+
+/// Resolves the root directory.
+fn vfs_root() -> TestResult {
+	let root = vfs::get_file_from_path(
+		PathBuf::root()?.as_ref(),
+		&vfs::ResolutionSettings::kernel_follow(),
+	)?;
+	ktest_assert!(root.get_type()? == FileType::Directory);
+	Ok(())
+}
+
+/// Routes local addresses to the loopback interface and sends a frame through it.
+fn net_loopback() -> TestResult {
+	for addr in [
+		Address::IPv4([127, 0, 0, 1]),
+		Address::IPv4([127, 1, 2, 3]),
+		Address::IPv6([0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+	] {
+		let iface = net::get_iface_for(addr).ok_or(TestError::Assert("no route"))?;
+		ktest_assert!(iface.lock().get_name() == net::lo::NAME);
+	}
+	let iface = net::get_iface(net::lo::NAME).ok_or(TestError::Assert("no interface"))?;
+	let mut iface = iface.lock();
+	let mut payload = BuffList::from(b"world".as_slice());
+	let frame = payload.push_front(b"hello ".as_slice().into());
+	ktest_assert!(iface.write(&frame)? == 11);
+	let mut buf = [0u8; 16];
+	let len = iface.read(&mut buf)? as usize;
+	ktest_assert!(&buf[..len] == b"hello world");
+	ktest_assert!(iface.read(&mut buf).is_err());
+	Ok(())
+}
+
+/// The counter returned by the file registered by [`debugfs_file`].
+static DEBUGFS_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the next value of [`DEBUGFS_COUNTER`].
+fn debugfs_counter(off: u64, buf: &mut [u8]) -> EResult<usize> {
+	let n = DEBUGFS_COUNTER.fetch_add(1, atomic::Ordering::Relaxed);
+	format_content!(off, buf, "{n}\n")
+}
+
+/// Registers a debugfs file returning a counter, then reads it through a debugfs that is not
+/// mounted anywhere.
+fn debugfs_file() -> TestResult {
+	const PATH: &[u8] = b"selftest/counter";
+	debugfs::create_file(PATH, Some(debugfs_counter), None)?;
+	ktest_assert!(debugfs::create_file(PATH, Some(debugfs_counter), None).is_err());
+	let fs = DebugFsType.load_filesystem(None, PathBuf::root()?, false)?;
+	let root = fs.ops.root(fs.clone())?;
+	let root = Arc::new(vfs::Entry::new(Default::default(), None, Some(root)))?;
+	let rs = vfs::ResolutionSettings {
+		cwd: Some(root),
+		..vfs::ResolutionSettings::kernel_follow()
+	};
+	let ent = vfs::get_file_from_path(Path::new(PATH)?, &rs)?;
+	let file = File::open_entry(ent, O_RDONLY)?;
+	let mut buf = [0u8; 16];
+	for expected in [b"0\n", b"1\n"] {
+		let len = file.ops.read(&file, 0, &mut buf)?;
+		ktest_assert!(&buf[..len] == expected);
+	}
+	// A removed file cannot be read anymore
+	debugfs::remove_file(PATH)?;
+	ktest_assert!(file.ops.read(&file, 0, &mut buf).is_err());
+	Ok(())
+}
+
+/// The queue used by [`io_queue_order`].
+static IO_QUEUE: RequestQueue = RequestQueue::new();
+/// The offset of the request to be submitted by the next [`io_waiter`].
+static IO_NEXT_OFF: AtomicUsize = AtomicUsize::new(0);
+/// The offsets of the requests serviced by [`IO_QUEUE`], in order.
+static IO_COMPLETED: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+/// Kernel thread submitting a request on [`IO_QUEUE`] at the offset [`IO_NEXT_OFF`], then
+/// exiting.
+fn io_waiter() -> ! {
+	let off = IO_NEXT_OFF.load(atomic::Ordering::Acquire);
+	let _ = IO_QUEUE.submit(off as _, || Ok(IO_COMPLETED.lock().push(off)?));
+	Process::current().exit(0);
+	loop {
+		Scheduler::tick();
+	}
+}
+
+/// Submits each request of `reqs`, given as an I/O priority and an offset, from a kernel thread
+/// while the queue services a request at `head`, then returns the offsets of the requests in the
+/// order they were serviced.
+fn io_queue_order(head: u64, reqs: &[(u16, usize)]) -> EResult<Vec<usize>> {
+	IO_COMPLETED.lock().clear();
+	IO_QUEUE.submit(head, || {
+		for (i, (ioprio, off)) in reqs.iter().enumerate() {
+			IO_NEXT_OFF.store(*off, atomic::Ordering::Release);
+			let proc = Process::new_kthread(None, io_waiter, false)?;
+			proc.set_ioprio(*ioprio);
+			SCHEDULER.lock().add_process(proc)?;
+			// Wait for the thread to be in the queue before submitting the next request
+			while IO_QUEUE.pending() <= i {
+				Scheduler::tick();
+			}
+		}
+		Ok(())
+	})?;
+	while IO_COMPLETED.lock().len() < reqs.len() {
+		Scheduler::tick();
+	}
+	Ok(mem::take(&mut *IO_COMPLETED.lock()))
+}
+
+/// Returns the I/O priority value for the class `class` and the level `level`.
+fn ioprio(class: u16, level: u16) -> u16 {
+	(class << IOPRIO_CLASS_SHIFT) | level
+}
+
+/// Checks requests from several processes are serviced by decreasing I/O priority class, then
+/// level.
+fn io_queue_priority() -> TestResult {
+	let order = io_queue_order(
+		0,
+		&[
+			(ioprio(queue::IOPRIO_CLASS_IDLE, 0), 6),
+			(ioprio(queue::IOPRIO_CLASS_BE, 7), 4),
+			(ioprio(queue::IOPRIO_CLASS_NONE, 0), 3),
+			(ioprio(queue::IOPRIO_CLASS_RT, 0), 1),
+			(ioprio(queue::IOPRIO_CLASS_BE, 0), 2),
+			(ioprio(queue::IOPRIO_CLASS_BE, 7), 5),
+		],
+	)?;
+	ktest_assert!(order.as_slice() == [1, 2, 3, 4, 5, 6]);
+	Ok(())
+}
+
+/// Checks requests of equal priority are serviced in elevator order from the head.
+fn io_queue_look() -> TestResult {
+	let reqs = [80, 10, 60, 30, 70].map(|off| (ioprio(queue::IOPRIO_CLASS_NONE, 0), off));
+	let order = io_queue_order(50, &reqs)?;
+	ktest_assert!(order.as_slice() == [60, 70, 80, 10, 30]);
+	Ok(())
+}
+
+/// Checks system calls are dispatched by ID to their handler.
+fn syscall_dispatch() -> TestResult {
+	let proc = Process::current();
+	let pid = proc.pid_ns().local_pid(proc.get_tgid()).unwrap_or(0) as usize;
+	let mut frame = IntFrame::default();
+	let res = syscall::do_syscall(&syscall::SYSCALLS32, 0x014, &mut frame);
+	ktest_assert!(matches!(res, Some(Ok(p)) if p == pid));
+	#[cfg(target_arch = "x86_64")]
+	{
+		let res = syscall::do_syscall(&syscall::SYSCALLS64, 0x027, &mut frame);
+		ktest_assert!(matches!(res, Some(Ok(p)) if p == pid));
+	}
+	// Unassigned IDs have no handler
+	ktest_assert!(syscall::do_syscall(&syscall::SYSCALLS32, 0x012, &mut frame).is_none());
+	ktest_assert!(syscall::do_syscall(&syscall::SYSCALLS32, usize::MAX, &mut frame).is_none());
+	Ok(())
+}
+
+/// Creates the executable file `name` with the content `content` in the directory `dir`.
+fn create_exec(dir: &Arc<vfs::Entry>, name: &[u8], content: &[u8]) -> EResult<Arc<vfs::Entry>> {
+	let ent = vfs::create_file(
+		dir.clone(),
+		name,
+		&AccessProfile::KERNEL,
+		Stat {
+			mode: FileType::Regular.to_mode() | 0o755,
+			..Default::default()
+		},
+	)?;
+	let file = File::open_entry(ent.clone(), O_RDWR)?;
+	file.ops.write(&file, 0, content)?;
+	Ok(ent)
+}
+
+/// Checks the number of nested script interpreters is limited.
+fn exec_shebang_loop() -> TestResult {
+	let fs = TmpFsType.load_filesystem(None, PathBuf::root()?, false)?;
+	let root = fs.ops.root(fs.clone())?;
+	let root = Arc::new(vfs::Entry::new(Default::default(), None, Some(root)))?;
+	let rs = vfs::ResolutionSettings {
+		root: root.clone(),
+		..vfs::ResolutionSettings::kernel_follow()
+	};
+	// Executes the file at `path` and returns the resulting errno
+	let run = |path: &[u8]| -> EResult<i32> {
+		let path = Path::new(path)?;
+		let ent = vfs::get_file_from_path(path, &rs)?;
+		let res = exec::build_image(
+			ent,
+			ExecInfo {
+				path_resolution: &rs,
+				path,
+				execfn: path,
+				argv: Vec::new(),
+				envp: Vec::new(),
+			},
+		);
+		Ok(res.err().map(|e| e.as_int()).unwrap_or(0))
+	};
+	// A script being its own interpreter
+	create_exec(&root, b"loop", b"#!/loop\n")?;
+	ktest_assert!(run(b"/loop")? == errno::ELOOP);
+	// A chain of exactly `INTERP_MAX` scripts is allowed, the last interpreter failing to load
+	create_exec(&root, b"target", b"not an executable\n")?;
+	create_exec(&root, b"s3", b"#!/target\n")?;
+	create_exec(&root, b"s2", b"#!/s3\n")?;
+	create_exec(&root, b"s1", b"#!/s2\n")?;
+	create_exec(&root, b"s0", b"#!/s1\n")?;
+	let res = run(b"/s0")?;
+	ktest_assert!(res != 0 && res != errno::ELOOP);
+	// One more script exceeds the limit
+	create_exec(&root, b"s", b"#!/s0\n")?;
+	ktest_assert!(run(b"/s")? == errno::ELOOP);
+	Ok(())
+}
+
+/// The number of keys used by [`rcu_stress`].
+const RCU_KEYS: usize = 64;
+/// The number of reader threads spawned by [`rcu_stress`].
+const RCU_READERS: usize = 4;
+/// The value stored in [`RcuValue`] until it is dropped.
+const RCU_MAGIC: usize = 0x5ca1ab1e;
+
+/// A value of [`RCU_MAP`], poisoned when dropped.
+struct RcuValue {
+	key: usize,
+	magic: AtomicUsize,
+}
+
+impl Drop for RcuValue {
+	fn drop(&mut self) {
+		self.magic.store(0, atomic::Ordering::Relaxed);
+		RCU_DROPPED.fetch_add(1, atomic::Ordering::Relaxed);
+	}
+}
+
+/// The map read by [`rcu_reader`] threads while [`rcu_stress`] updates it.
+static RCU_MAP: RcuHashMap<usize, Arc<RcuValue>> = RcuHashMap::new();
+/// Tells whether the readers must stop.
+static RCU_STOP: AtomicBool = AtomicBool::new(false);
+/// The number of readers still running.
+static RCU_RUNNING: AtomicUsize = AtomicUsize::new(0);
+/// The number of invalid values seen by the readers.
+static RCU_ERRORS: AtomicUsize = AtomicUsize::new(0);
+/// The number of dropped values.
+static RCU_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+/// Kernel thread looking up every key of [`RCU_MAP`] in a loop, until [`RCU_STOP`] is set.
+///
+/// Values are kept across preemptions, to check they remain valid as long as they are referenced.
+fn rcu_reader() -> ! {
+	let mut held = None;
+	let mut key = 0;
+	while !RCU_STOP.load(atomic::Ordering::Acquire) {
+		if let Some(val) = RCU_MAP.get(&key) {
+			held = Some(val);
+		}
+		if let Some(val) = &held {
+			if val.key >= RCU_KEYS || val.magic.load(atomic::Ordering::Relaxed) != RCU_MAGIC {
+				RCU_ERRORS.fetch_add(1, atomic::Ordering::Relaxed);
+			}
+		}
+		key = (key + 1) % RCU_KEYS;
+		if key == 0 {
+			Scheduler::tick();
+		}
+	}
+	drop(held);
+	RCU_RUNNING.fetch_sub(1, atomic::Ordering::Release);
+	Process::current().exit(0);
+	loop {
+		Scheduler::tick();
+	}
+}
+
+/// Updates a map concurrently looked up by several threads, checking no reader ever sees a value
+/// that has been released, and every removed value is eventually released.
+fn rcu_stress() -> TestResult {
+	RCU_STOP.store(false, atomic::Ordering::Relaxed);
+	RCU_ERRORS.store(0, atomic::Ordering::Relaxed);
+	RCU_DROPPED.store(0, atomic::Ordering::Relaxed);
+	for _ in 0..RCU_READERS {
+		let proc = Process::new_kthread(None, rcu_reader, false)?;
+		RCU_RUNNING.fetch_add(1, atomic::Ordering::Relaxed);
+		SCHEDULER.lock().add_process(proc)?;
+	}
+	let mut created = 0;
+	for i in 0..10000 {
+		let key = (i * 7) % RCU_KEYS;
+		if i % 3 == 0 {
+			RCU_MAP.remove(&key);
+		} else {
+			let val = Arc::new(RcuValue {
+				key,
+				magic: AtomicUsize::new(RCU_MAGIC),
+			})?;
+			created += 1;
+			RCU_MAP.insert(key, val)?;
+		}
+		// Let the readers run, then release the values whose grace period has elapsed
+		if i % 16 == 0 {
+			Scheduler::tick();
+			rcu::reclaim();
+		}
+	}
+	RCU_STOP.store(true, atomic::Ordering::Release);
+	while RCU_RUNNING.load(atomic::Ordering::Acquire) > 0 {
+		Scheduler::tick();
+	}
+	RCU_MAP.retain(|_, _| false);
+	// Two quiescent states are necessary for the last callbacks to become ready
+	for _ in 0..2 {
+		Scheduler::tick();
+		rcu::reclaim();
+	}
+	ktest_assert!(RCU_ERRORS.load(atomic::Ordering::Relaxed) == 0);
+	ktest_assert!(RCU_DROPPED.load(atomic::Ordering::Relaxed) == created);
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use utils::collections::string::{String, StringWriter};
+
+	fn pass() -> TestResult {
+		Ok(())
+	}
+
+	fn fail() -> TestResult {
+		let n = 2;
+		ktest_assert!(n == 3);
+		Ok(())
+	}
+
+	#[test_case]
+	fn integration_counts() {
+		let tests = [
+			IntegrationTest {
+				name: "pass",
+				run: pass,
+			},
+			IntegrationTest {
+				name: "fail",
+				run: fail,
+			},
+		];
+		let mut out = StringWriter(Ok(String::new()));
+		assert_eq!(run_integration(&tests, &mut out), (1, 1));
+		let report = out.0.unwrap();
+		let report = report.as_bytes();
+		assert!(report.starts_with(b"test pass ... ok\ntest fail ... FAILED: "));
+		assert!(report.ends_with(b"2 tests: 1 passed, 1 failed\n"));
+	}
+}
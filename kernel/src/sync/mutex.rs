@@ -29,18 +29,26 @@
 //!
 //! If an exception is raised while a mutex that disables interruptions is
 //! acquired, the behaviour is undefined.
+//!
+//! Since a process holding a mutex that does not disable interruptions may be preempted, a
+//! real-time process waiting for it could prevent it from ever releasing the mutex. To avoid
+//! this, the holder inherits the priority of the waiter (see
+//! [`scheduler::inherit_priority`]).
 
 use crate::{
 	arch::{
 		x86,
-		x86::{cli, sti},
+		x86::{cli, idt, sti},
 	},
+	process::scheduler,
 	sync::spinlock::Spinlock,
 };
 use core::{
 	cell::UnsafeCell,
 	fmt::{self, Formatter},
+	hint,
 	ops::{Deref, DerefMut},
+	sync::atomic::{AtomicBool, AtomicU16, Ordering::Relaxed},
 };
 
 /// Type used to declare a guard meant to unlock the associated `Mutex` at the
@@ -97,6 +105,11 @@ struct MutexIn<T: ?Sized, const INT: bool> {
 /// The `INT` generic parameter tells whether interrupts are allowed while
 /// the mutex is locked. The default value is `true`.
 pub struct Mutex<T: ?Sized, const INT: bool = true> {
+	/// The PID of the process holding the mutex, or zero if unknown. This field is relevant only
+	/// if `INT == true`.
+	owner: AtomicU16,
+	/// Tells whether the holder inherited a priority from a process waiting for the mutex.
+	boosted: AtomicBool,
 	/// An unsafe cell to the inner structure of the Mutex.
 	inner: UnsafeCell<MutexIn<T, INT>>,
 }
@@ -105,6 +118,8 @@ impl<T, const INT: bool> Mutex<T, INT> {
 	/// Creates a new Mutex with the given data to be owned.
 	pub const fn new(data: T) -> Self {
 		Self {
+			owner: AtomicU16::new(0),
+			boosted: AtomicBool::new(false),
 			inner: UnsafeCell::new(MutexIn {
 				spin: Spinlock::new(),
 				data,
@@ -135,15 +150,70 @@ impl<T: ?Sized, const INT: bool> Mutex<T, INT> {
 			// In this case, this value does not matter
 			false
 		};
-		// Safe because using the spinlock
-		let inner = unsafe { &mut *self.inner.get() };
-		inner.spin.lock();
+		if INT {
+			while !self.try_acquire() {
+				if !scheduler::inherit_priority(&self.owner, &self.boosted) {
+					hint::spin_loop();
+				}
+			}
+		} else {
+			// Safe because using the spinlock
+			let inner = unsafe { &mut *self.inner.get() };
+			inner.spin.lock();
+		}
 		MutexGuard {
 			mutex: self,
 			int_state,
 		}
 	}
 
+	/// Attempts to acquire the spinlock, recording the current process as the holder.
+	///
+	/// Interrupts are disabled so that the holder is always known while the spinlock is locked.
+	fn try_acquire(&self) -> bool {
+		idt::wrap_disable_interrupts(|| {
+			// Safe because using the spinlock
+			let inner = unsafe { &mut *self.inner.get() };
+			let acquired = inner.spin.try_lock();
+			if acquired {
+				self.owner.store(scheduler::current_pid(), Relaxed);
+			}
+			acquired
+		})
+	}
+
+	/// Attempts to lock the mutex without waiting.
+	///
+	/// If the mutex is already locked, the function returns `None`.
+	pub fn try_lock(&self) -> Option<MutexGuard<T, INT>> {
+		let int_state = if !INT {
+			let enabled = x86::is_interrupt_enabled();
+			cli();
+			enabled
+		} else {
+			// In this case, this value does not matter
+			false
+		};
+		// Safe because using the spinlock
+		let inner = unsafe { &mut *self.inner.get() };
+		let acquired = if INT {
+			self.try_acquire()
+		} else {
+			inner.spin.try_lock()
+		};
+		if acquired {
+			Some(MutexGuard {
+				mutex: self,
+				int_state,
+			})
+		} else {
+			if !INT && int_state {
+				sti();
+			}
+			None
+		}
+	}
+
 	/// Unlocks the mutex. This function should not be used directly since it is called when the
 	/// mutex guard is dropped.
 	///
@@ -156,7 +226,20 @@ impl<T: ?Sized, const INT: bool> Mutex<T, INT> {
 	/// Unlocking the mutex while the resource is being used may result in concurrent accesses.
 	pub unsafe fn unlock(&self, int_state: bool) {
 		let inner = &mut (*self.inner.get());
-		inner.spin.unlock();
+		if INT {
+			// Release atomically so that waiters never see the mutex locked without a holder
+			let boosted = idt::wrap_disable_interrupts(|| {
+				self.owner.store(0, Relaxed);
+				let boosted = self.boosted.swap(false, Relaxed);
+				inner.spin.unlock();
+				boosted
+			});
+			if boosted {
+				scheduler::restore_priority();
+			}
+		} else {
+			inner.spin.unlock();
+		}
 		if !INT && int_state {
 			sti();
 		}
@@ -18,16 +18,131 @@
 
 //! Read-Copy-Update allows several threads to read and update data structures concurrently without
 //! using locks.
+//!
+//! Readers access the protected structure inside a *read-side critical section*, delimited by
+//! [`rcu_read_lock`] and [`rcu_read_unlock`]. While inside such a section, the current context
+//! cannot be preempted.
+//!
+//! Writers publish a new version of the structure, then defer the release of the old version with
+//! [`call_rcu`]. The deferred callback is executed only after a *grace period*, that is once every
+//! reader that might still see the old version has passed through a *quiescent state* (a scheduler
+//! tick happening outside any read-side critical section).
+//!
+//! **Note**: sleeping inside a read-side critical section is forbidden.
 
+use crate::sync::mutex::{IntMutex, Mutex};
 use core::{
+	borrow::Borrow,
+	fmt,
+	fmt::Formatter,
+	hash::Hash,
 	mem,
+	mem::ManuallyDrop,
 	ptr::{null_mut, NonNull},
 	sync::atomic::{
-		AtomicPtr,
-		Ordering::{Acquire, Relaxed, SeqCst},
+		AtomicPtr, AtomicUsize,
+		Ordering::{Acquire, Relaxed, Release, SeqCst},
 	},
 };
-use utils::ptr::arc::{Arc, ArcInner};
+use utils::{
+	boxed::Box,
+	collections::{hashmap, hashmap::hash::FxHasher, vec::Vec},
+	errno::{AllocResult, EResult},
+	ptr::arc::{Arc, ArcInner},
+};
+
+/// A callback to be executed once a grace period has elapsed.
+type Callback = Box<dyn FnMut()>;
+
+/// The queues of deferred callbacks.
+struct Callbacks {
+	/// Callbacks waiting for the end of a grace period.
+	waiting: Vec<Callback>,
+	/// Callbacks whose grace period has elapsed, ready to be executed.
+	ready: Vec<Callback>,
+}
+
+/// The nesting level of read-side critical sections on the current core.
+///
+/// TODO make per-core when SMP is supported
+static READ_NESTING: AtomicUsize = AtomicUsize::new(0);
+/// Deferred callbacks.
+static CALLBACKS: IntMutex<Callbacks> = IntMutex::new(Callbacks {
+	waiting: Vec::new(),
+	ready: Vec::new(),
+});
+
+/// Enters a read-side critical section.
+///
+/// Critical sections can be nested. Each call must be matched by a call to [`rcu_read_unlock`].
+#[inline]
+pub fn rcu_read_lock() {
+	READ_NESTING.fetch_add(1, Acquire);
+}
+
+/// Exits a read-side critical section.
+#[inline]
+pub fn rcu_read_unlock() {
+	let prev = READ_NESTING.fetch_sub(1, Release);
+	debug_assert_ne!(prev, 0, "unbalanced rcu_read_unlock");
+}
+
+/// Tells whether the current context is inside a read-side critical section.
+#[inline]
+pub fn in_read_section() -> bool {
+	READ_NESTING.load(Relaxed) > 0
+}
+
+/// Defers the execution of `f` until the end of the current grace period.
+///
+/// On allocation failure, `f` is dropped without being called.
+pub fn call_rcu<F: 'static + FnOnce()>(f: F) -> AllocResult<()> {
+	let mut f = Some(f);
+	let callback: Callback = Box::new(move || {
+		if let Some(f) = f.take() {
+			f();
+		}
+	})?;
+	CALLBACKS.lock().waiting.push(callback)
+}
+
+/// Reports a quiescent state on the current core. This function is meant to be called by the
+/// scheduler on each tick.
+///
+/// If the current context is inside a read-side critical section, the function does nothing and
+/// returns `false`, in which case the caller must not preempt the current context.
+///
+/// Else, callbacks registered before this call become ready to be executed by [`reclaim`].
+pub fn quiescent_state() -> bool {
+	if in_read_section() {
+		return false;
+	}
+	let mut callbacks = CALLBACKS.lock();
+	// If ready callbacks have not been executed yet, the waiting ones will be moved on a
+	// subsequent quiescent state
+	if callbacks.ready.is_empty() {
+		let waiting = mem::take(&mut callbacks.waiting);
+		callbacks.ready = waiting;
+	}
+	true
+}
+
+/// Executes the callbacks whose grace period has elapsed.
+///
+/// This function must be called from a context in which no lock is held, such as right before
+/// returning to userspace.
+pub fn reclaim() {
+	let ready = {
+		let mut callbacks = CALLBACKS.lock();
+		if callbacks.ready.is_empty() {
+			return;
+		}
+		mem::take(&mut callbacks.ready)
+	};
+	for mut callback in ready {
+		callback();
+	}
+}
 
 /// An [`Arc`], behind a RCU.
 pub struct RcuArc<T>(RcuOptionArc<T>);
@@ -48,7 +163,10 @@ impl<T> RcuArc<T> {
 
 	/// Atomically swap the inner [`Arc`] for the given `other`.
 	#[inline]
-	pub fn swap(&self, other: Arc<T>) -> Arc<T> {
+	pub fn swap(&self, other: Arc<T>) -> Arc<T>
+	where
+		T: 'static,
+	{
 		let arc = self.0.swap(Some(other));
 		unsafe { arc.unwrap_unchecked() }
 	}
@@ -77,7 +195,7 @@ impl<T> RcuOptionArc<T> {
 
 	/// Returns a reference to the inner [`Arc`].
 	pub fn get(&self) -> Option<Arc<T>> {
-		// TODO enter RCU read critical section
+		rcu_read_lock();
 		let inner = self.inner.load(Acquire);
 		let arc = NonNull::new(inner).map(|inner| {
 			let inner_ref = unsafe { inner.as_ref() };
@@ -86,12 +204,18 @@ impl<T> RcuOptionArc<T> {
 				inner,
 			}
 		});
-		// TODO exit RCU read critical section
+		rcu_read_unlock();
 		arc
 	}
 
 	/// Atomically swap the inner [`Arc`] for the given `other`.
-	pub fn swap(&self, other: Option<Arc<T>>) -> Option<Arc<T>> {
+	///
+	/// The previous value is kept alive at least until the end of the current grace period, so
+	/// that readers which loaded it before the swap can still acquire a reference to it.
+	pub fn swap(&self, other: Option<Arc<T>>) -> Option<Arc<T>>
+	where
+		T: 'static,
+	{
 		let new = other
 			.as_ref()
 			.map(|arc| arc.inner.as_ptr())
@@ -99,12 +223,13 @@ impl<T> RcuOptionArc<T> {
 		// avoid decrementing reference counter
 		mem::forget(other);
 		let old = self.inner.swap(new, SeqCst);
-		NonNull::new(old).map(|inner| {
-			// TODO RCU sync
-			Arc {
-				inner,
-			}
-		})
+		let old = NonNull::new(old).map(|inner| Arc {
+			inner,
+		})?;
+		// On allocation failure, the reference is leaked rather than risking a use-after-free
+		let deferred = ManuallyDrop::new(old.clone());
+		let _ = call_rcu(move || drop(ManuallyDrop::into_inner(deferred)));
+		Some(old)
 	}
 }
 
@@ -123,3 +248,299 @@ impl<T> Drop for RcuOptionArc<T> {
 		}
 	}
 }
+
+/// The number of buckets of a [`RcuHashMap`].
+const BUCKETS_COUNT: usize = 256;
+
+/// An entry of a [`RcuHashMap`], linked to the next entry of the same bucket.
+struct RcuHashEntry<K, V> {
+	key: K,
+	value: V,
+	next: AtomicPtr<RcuHashEntry<K, V>>,
+}
+
+/// A hash map whose lookups do not take any lock.
+///
+/// Lookups run inside a read-side critical section, while insertions and removals are serialized
+/// by a lock. A removed entry is released once the grace period has elapsed, so that lookups that
+/// reached it before the removal can still read it.
+///
+/// Values are returned by copy, so they are usually [`Arc`]s. The number of buckets is fixed.
+pub struct RcuHashMap<K, V> {
+	/// The head of the list of entries of each bucket.
+	buckets: [AtomicPtr<RcuHashEntry<K, V>>; BUCKETS_COUNT],
+	/// Lock serializing updates.
+	lock: Mutex<()>,
+}
+
+impl<K: 'static + Eq + Hash, V: 'static + Clone> RcuHashMap<K, V> {
+	/// Creates a new empty map.
+	pub const fn new() -> Self {
+		Self {
+			buckets: [const { AtomicPtr::new(null_mut()) }; BUCKETS_COUNT],
+			lock: Mutex::new(()),
+		}
+	}
+
+	/// Returns the bucket for `key`.
+	fn bucket<Q: ?Sized + Hash>(&self, key: &Q) -> &AtomicPtr<RcuHashEntry<K, V>> {
+		let hash = hashmap::hash::<_, FxHasher>(key);
+		&self.buckets[hash as usize % BUCKETS_COUNT]
+	}
+
+	/// Returns the value for `key`, if any.
+	pub fn get<Q: ?Sized + Eq + Hash>(&self, key: &Q) -> Option<V>
+	where
+		K: Borrow<Q>,
+	{
+		rcu_read_lock();
+		let mut cur = self.bucket(key).load(Acquire);
+		let mut value = None;
+		while let Some(ent) = unsafe { cur.as_ref() } {
+			if ent.key.borrow() == key {
+				value = Some(ent.value.clone());
+				break;
+			}
+			cur = ent.next.load(Acquire);
+		}
+		rcu_read_unlock();
+		value
+	}
+
+	/// Tells whether the map is empty.
+	pub fn is_empty(&self) -> bool {
+		self.buckets.iter().all(|b| b.load(Relaxed).is_null())
+	}
+
+	/// Returns a copy of the values of the map.
+	///
+	/// Since the map is not locked, entries that are inserted or removed concurrently may or may
+	/// not be part of the result.
+	pub fn values(&self) -> AllocResult<Vec<V>> {
+		let mut values = Vec::new();
+		rcu_read_lock();
+		let res = (|| {
+			for bucket in &self.buckets {
+				let mut cur = bucket.load(Acquire);
+				while let Some(ent) = unsafe { cur.as_ref() } {
+					values.push(ent.value.clone())?;
+					cur = ent.next.load(Acquire);
+				}
+			}
+			Ok(())
+		})();
+		rcu_read_unlock();
+		res.map(|_| values)
+	}
+
+	/// Releases the unlinked entry `ent` once the grace period has elapsed.
+	fn defer_free(ent: *mut RcuHashEntry<K, V>) {
+		// On allocation failure, the entry is leaked rather than risking a use-after-free
+		let _ = call_rcu(move || unsafe { drop(Box::from_raw(ent)) });
+	}
+
+	/// Removes the entries for which `f` returns `false`, with the map's lock held.
+	fn retain_locked<F: FnMut(&K, &V) -> bool>(&self, mut f: F) {
+		for bucket in &self.buckets {
+			let mut prev = bucket;
+			loop {
+				let cur = prev.load(Relaxed);
+				let Some(ent) = (unsafe { cur.as_ref() }) else {
+					break;
+				};
+				if f(&ent.key, &ent.value) {
+					prev = &ent.next;
+					continue;
+				}
+				// Unlink the entry. Lookups going through it can still read its successor
+				prev.store(ent.next.load(Relaxed), Release);
+				Self::defer_free(cur);
+			}
+		}
+	}
+
+	/// Inserts `value` for `key` with the map's lock held, replacing the previous value.
+	fn insert_locked(&self, key: K, value: V) -> AllocResult<()> {
+		let bucket = self.bucket(&key);
+		let ent = Box::new(RcuHashEntry {
+			key,
+			value,
+			next: AtomicPtr::new(null_mut()),
+		})?;
+		let key = &ent.key;
+		// Remove the previous entry, if any
+		let mut prev = bucket;
+		loop {
+			let cur = prev.load(Relaxed);
+			let Some(other) = (unsafe { cur.as_ref() }) else {
+				break;
+			};
+			if other.key == *key {
+				prev.store(other.next.load(Relaxed), Release);
+				Self::defer_free(cur);
+				break;
+			}
+			prev = &other.next;
+		}
+		// Publish the entry, once initialized
+		ent.next.store(bucket.load(Relaxed), Relaxed);
+		bucket.store(unsafe { Box::into_raw(ent) }, Release);
+		Ok(())
+	}
+
+	/// Inserts `value` for `key`, replacing the previous value.
+	pub fn insert(&self, key: K, value: V) -> AllocResult<()> {
+		let _guard = self.lock.lock();
+		self.insert_locked(key, value)
+	}
+
+	/// Returns the value for `key` or, if not present, initializes it with `init` and inserts it.
+	///
+	/// `init` is called with the map's lock held.
+	pub fn get_or_insert<F: FnOnce() -> EResult<V>>(&self, key: K, init: F) -> EResult<V> {
+		if let Some(value) = self.get(&key) {
+			return Ok(value);
+		}
+		let _guard = self.lock.lock();
+		// The value may have been inserted while waiting for the lock
+		if let Some(value) = self.get(&key) {
+			return Ok(value);
+		}
+		let value = init()?;
+		self.insert_locked(key, value.clone())?;
+		Ok(value)
+	}
+
+	/// Removes the value for `key`.
+	pub fn remove<Q: ?Sized + Eq + Hash>(&self, key: &Q)
+	where
+		K: Borrow<Q>,
+	{
+		let _guard = self.lock.lock();
+		self.retain_locked(|k, _| k.borrow() != key);
+	}
+
+	/// Removes the entries for which `f` returns `false`.
+	pub fn retain<F: FnMut(&K, &V) -> bool>(&self, f: F) {
+		let _guard = self.lock.lock();
+		self.retain_locked(f);
+	}
+}
+
+impl<K: 'static + Eq + Hash, V: 'static + Clone> Default for RcuHashMap<K, V> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<K, V> Drop for RcuHashMap<K, V> {
+	fn drop(&mut self) {
+		// No lookup can be running since the map is borrowed mutably
+		for bucket in &mut self.buckets {
+			let mut cur = *bucket.get_mut();
+			while !cur.is_null() {
+				let ent = unsafe { Box::from_raw(cur) };
+				cur = ent.next.load(Relaxed);
+			}
+		}
+	}
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for RcuHashMap<K, V> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let mut map = f.debug_map();
+		rcu_read_lock();
+		for bucket in &self.buckets {
+			let mut cur = bucket.load(Acquire);
+			while let Some(ent) = unsafe { cur.as_ref() } {
+				map.entry(&ent.key, &ent.value);
+				cur = ent.next.load(Acquire);
+			}
+		}
+		rcu_read_unlock();
+		map.finish()
+	}
+}
+
+unsafe impl<K, V> Send for RcuHashMap<K, V> {}
+
+unsafe impl<K, V> Sync for RcuHashMap<K, V> {}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use core::sync::atomic::AtomicBool;
+
+	/// Tells whether the value has been dropped.
+	static DROPPED: AtomicBool = AtomicBool::new(false);
+
+	/// A value recording its drop.
+	struct Value(usize);
+
+	impl Drop for Value {
+		fn drop(&mut self) {
+			DROPPED.store(true, Relaxed);
+		}
+	}
+
+	/// Flushes every pending callbacks.
+	fn flush() {
+		for _ in 0..2 {
+			quiescent_state();
+			reclaim();
+		}
+	}
+
+	#[test_case]
+	fn rcu_grace_period() {
+		flush();
+		DROPPED.store(false, Relaxed);
+		let rcu = RcuArc::new(Arc::new(Value(0)).unwrap());
+		// A reader enters its critical section and loads the current value
+		rcu_read_lock();
+		let inner = rcu.0.inner.load(Acquire);
+		// A writer replaces the value and drops its own reference
+		drop(rcu.swap(Arc::new(Value(1)).unwrap()));
+		// The reader is still inside its critical section: no grace period may elapse
+		assert!(!quiescent_state());
+		reclaim();
+		assert!(!DROPPED.load(Relaxed));
+		// The reader can still acquire a reference to the old value
+		let old = unsafe { inner.as_ref() }.unwrap();
+		assert_ne!(old.ref_count.load(Relaxed), 0);
+		rcu_read_unlock();
+		assert!(quiescent_state());
+		reclaim();
+		assert!(DROPPED.load(Relaxed));
+		assert_eq!(rcu.get().0, 1);
+	}
+
+	#[test_case]
+	fn rcu_hash_map() {
+		flush();
+		DROPPED.store(false, Relaxed);
+		let map = RcuHashMap::new();
+		for i in 0..1000 {
+			map.insert(i, Arc::new(i).unwrap()).unwrap();
+		}
+		assert_eq!(map.get(&42).as_deref(), Some(&42));
+		// Replacing a value
+		map.insert(42, Arc::new(0).unwrap()).unwrap();
+		assert_eq!(map.get(&42).as_deref(), Some(&0));
+		map.remove(&42);
+		assert!(map.get(&42).is_none());
+		assert_eq!(map.values().unwrap().len(), 999);
+		// A removed value is kept until the end of the grace period
+		let map = RcuHashMap::new();
+		map.insert(0, Arc::new(Value(0)).unwrap()).unwrap();
+		rcu_read_lock();
+		map.remove(&0);
+		assert!(!quiescent_state());
+		reclaim();
+		assert!(!DROPPED.load(Relaxed));
+		rcu_read_unlock();
+		flush();
+		assert!(DROPPED.load(Relaxed));
+		assert!(map.is_empty());
+	}
+}
@@ -22,6 +22,7 @@
 use super::Args;
 use crate::process::{scheduler, scheduler::Scheduler, Process};
 use core::ffi::c_int;
+use macros::syscall;
 use utils::errno::EResult;
 
 /// Exits the current process.
@@ -33,12 +34,10 @@ use utils::errno::EResult;
 pub fn do_exit(status: u32, thread_group: bool) -> ! {
 	{
 		let proc = Process::current();
-		proc.exit(status);
-		let _pid = proc.get_pid();
-		let _tid = proc.tid;
 		if thread_group {
-			// TODO Iterate on every process of thread group `tid`, except the
-			// process with pid `pid`
+			proc.exit_group(status, 0);
+		} else {
+			proc.exit(status);
 		}
 	}
 	Scheduler::tick();
@@ -46,6 +45,7 @@ pub fn do_exit(status: u32, thread_group: bool) -> ! {
 	unreachable!();
 }
 
+#[syscall(id32 = 0x001)]
 pub fn _exit(Args(status): Args<c_int>) -> EResult<usize> {
 	do_exit(status as _, false);
 }
@@ -31,6 +31,7 @@ use core::{
 	ffi::{c_uint, c_ulong},
 	sync::atomic,
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -70,6 +71,7 @@ fn do_lseek(
 	Ok(offset as _)
 }
 
+#[syscall(id32 = 0x08c)]
 pub fn _llseek(
 	Args((fd, offset_high, offset_low, result, whence)): Args<(
 		c_uint,
@@ -85,6 +87,7 @@ pub fn _llseek(
 	Ok(0)
 }
 
+#[syscall(id64 = 0x008)]
 pub fn lseek(
 	Args((fd, offset, whence)): Args<(c_uint, u64, c_uint)>,
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
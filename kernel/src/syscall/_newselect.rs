@@ -18,18 +18,17 @@
 
 //! `_newselect` is similar to `select`.
 
-use super::select::{do_select, FDSet};
+use super::select::{do_select, get_deadline, FDSet};
 use crate::{
-	file::fd::FileDescriptorTable,
-	process::mem_space::{copy::SyscallPtr, MemSpace},
-	sync::mutex::Mutex,
-	syscall::Args,
-	time::unit::Timeval,
+	file::fd::FileDescriptorTable, process::mem_space::copy::SyscallPtr, sync::mutex::Mutex,
+	syscall::Args, time::unit::Timeval,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
 #[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x08e)]
 pub fn _newselect(
 	Args((nfds, readfds, writefds, exceptfds, timeout)): Args<(
 		c_int,
@@ -40,5 +39,6 @@ pub fn _newselect(
 	)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	do_select(fds, nfds as _, readfds, writefds, exceptfds, timeout, None)
+	let deadline = get_deadline(timeout)?;
+	do_select(&fds, nfds, readfds, writefds, exceptfds, deadline)
 }
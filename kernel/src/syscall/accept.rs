@@ -0,0 +1,38 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `accept` system call accepts a connection on a listening socket.
+
+use super::accept4::do_accept4;
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+#[syscall(id64 = 0x02b)]
+pub fn accept(
+	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, SyscallPtr<c_int>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_accept4(sockfd, addr, addrlen, 0, &fds)
+}
@@ -0,0 +1,84 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `accept4` system call accepts a connection on a listening socket.
+
+use crate::{
+	file,
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		socket::Socket,
+		File, O_NONBLOCK,
+	},
+	net::{SOCK_CLOEXEC, SOCK_NONBLOCK},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::{util::copy_sockaddr_to_user, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Accepts a connection on the socket `sockfd` and returns the file descriptor of the new
+/// connected socket.
+///
+/// The address of the peer is written to `addr`.
+pub(super) fn do_accept4(
+	sockfd: c_int,
+	addr: SyscallSlice<u8>,
+	addrlen: SyscallPtr<c_int>,
+	flags: c_int,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+		return Err(errno!(EINVAL));
+	}
+	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
+	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
+	let conn = sock.accept(file.get_flags() & O_NONBLOCK != 0)?;
+	copy_sockaddr_to_user(&addr, &addrlen, &conn.get_peername()?)?;
+	let mut file_flags = file::O_RDWR;
+	if flags & SOCK_NONBLOCK != 0 {
+		file_flags |= O_NONBLOCK;
+	}
+	let file = File::open_floating(conn, file_flags)?;
+	let fd_flags = if flags & SOCK_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd, _) = fds.lock().create_fd(fd_flags, file)?;
+	Ok(fd as _)
+}
+
+#[syscall(id32 = 0x16c, id64 = 0x120)]
+pub fn accept4(
+	Args((sockfd, addr, addrlen, flags)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		SyscallPtr<c_int>,
+		c_int,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_accept4(sockfd, addr, addrlen, flags, &fds)
+}
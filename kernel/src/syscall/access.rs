@@ -34,6 +34,7 @@ use crate::{
 	},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -103,6 +104,7 @@ pub fn do_access(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x021, id64 = 0x015)]
 pub fn access(
 	Args((pathname, mode)): Args<(SyscallString, c_int)>,
 	rs: ResolutionSettings,
@@ -0,0 +1,347 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `adjtimex` system call allows to read and adjust the real time clock, mostly for use by
+//! NTP daemons.
+//!
+//! Only a minimal subset is implemented: the clock can be slewed (gradually adjusted) or shifted
+//! by an offset. Other parameters are stored and given back without further effect.
+
+use crate::{
+	file::perm::AccessProfile,
+	process::mem_space::copy::SyscallPtr,
+	sync::mutex::Mutex,
+	syscall::Args,
+	time::{
+		clock,
+		clock::{current_time_ns, Clock},
+		unit::{Timeval, Timeval32},
+	},
+};
+use core::{
+	ffi::{c_int, c_long, c_uint},
+	fmt::Debug,
+};
+use macros::syscall;
+use utils::{errno, errno::EResult};
+
+/// Adjust the time offset.
+const ADJ_OFFSET: c_uint = 0x0001;
+/// Adjust the frequency offset.
+const ADJ_FREQUENCY: c_uint = 0x0002;
+/// Set the maximum time error.
+const ADJ_MAXERROR: c_uint = 0x0004;
+/// Set the estimated time error.
+const ADJ_ESTERROR: c_uint = 0x0008;
+/// Set the clock status.
+const ADJ_STATUS: c_uint = 0x0010;
+/// Set the PLL time constant.
+const ADJ_TIMECONST: c_uint = 0x0020;
+/// Set the TAI offset.
+const ADJ_TAI: c_uint = 0x0080;
+/// Add `time` to the current time.
+const ADJ_SETOFFSET: c_uint = 0x0100;
+/// Select microseconds resolution.
+const ADJ_MICRO: c_uint = 0x1000;
+/// Select nanoseconds resolution.
+const ADJ_NANO: c_uint = 0x2000;
+/// Old-fashioned `adjtime`: slew the clock by `offset` microseconds.
+const ADJ_OFFSET_SINGLESHOT: c_uint = 0x8001;
+/// Read the remaining adjustment of `adjtime` without changing it.
+const ADJ_OFFSET_SS_READ: c_uint = 0xa001;
+
+/// Status flag: the clock is not synchronized.
+const STA_UNSYNC: c_int = 0x0040;
+/// Status flag: the resolution is in nanoseconds. If not set, it is in microseconds.
+const STA_NANO: c_int = 0x2000;
+
+/// Return value: the clock is synchronized.
+const TIME_OK: usize = 0;
+/// Return value: the clock is not synchronized.
+const TIME_ERROR: usize = 5;
+
+/// The number of clock ticks per second, as seen by userspace.
+const USER_HZ: c_long = 100;
+/// The maximum frequency tolerance, in ppm with a 16-bit fractional part.
+const MAXFREQ: c_long = 500 << 16;
+
+/// Userspace structure to read and adjust the clock.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Timex {
+	/// The mode selector.
+	modes: c_uint,
+	/// Time offset, in microseconds or nanoseconds.
+	offset: c_long,
+	/// Frequency offset.
+	freq: c_long,
+	/// Maximum error, in microseconds.
+	maxerror: c_long,
+	/// Estimated error, in microseconds.
+	esterror: c_long,
+	/// Clock status.
+	status: c_int,
+	/// PLL time constant.
+	constant: c_long,
+	/// Clock precision, in microseconds.
+	precision: c_long,
+	/// Clock frequency tolerance.
+	tolerance: c_long,
+	/// The current time.
+	time: Timeval,
+	/// Microseconds between clock ticks.
+	tick: c_long,
+	/// PPS frequency.
+	ppsfreq: c_long,
+	/// PPS jitter.
+	jitter: c_long,
+	/// PPS interval duration.
+	shift: c_int,
+	/// PPS stability.
+	stabil: c_long,
+	/// PPS count of jitter limit exceeded events.
+	jitcnt: c_long,
+	/// PPS count of calibration intervals.
+	calcnt: c_long,
+	/// PPS count of calibration errors.
+	errcnt: c_long,
+	/// PPS count of stability limit exceeded events.
+	stbcnt: c_long,
+	/// TAI offset.
+	tai: c_int,
+	/// Padding.
+	_pad: [c_int; 11],
+}
+
+/// Compatibility version of [`Timex`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct CompatTimex {
+	modes: u32,
+	offset: i32,
+	freq: i32,
+	maxerror: i32,
+	esterror: i32,
+	status: i32,
+	constant: i32,
+	precision: i32,
+	tolerance: i32,
+	time: Timeval32,
+	tick: i32,
+	ppsfreq: i32,
+	jitter: i32,
+	shift: i32,
+	stabil: i32,
+	jitcnt: i32,
+	calcnt: i32,
+	errcnt: i32,
+	stbcnt: i32,
+	tai: i32,
+	_pad: [i32; 11],
+}
+
+impl From<Timex> for CompatTimex {
+	fn from(t: Timex) -> Self {
+		Self {
+			modes: t.modes,
+			offset: t.offset as _,
+			freq: t.freq as _,
+			maxerror: t.maxerror as _,
+			esterror: t.esterror as _,
+			status: t.status,
+			constant: t.constant as _,
+			precision: t.precision as _,
+			tolerance: t.tolerance as _,
+			time: Timeval32 {
+				tv_sec: t.time.tv_sec as _,
+				tv_usec: t.time.tv_usec as _,
+			},
+			tick: t.tick as _,
+			ppsfreq: t.ppsfreq as _,
+			jitter: t.jitter as _,
+			shift: t.shift,
+			stabil: t.stabil as _,
+			jitcnt: t.jitcnt as _,
+			calcnt: t.calcnt as _,
+			errcnt: t.errcnt as _,
+			stbcnt: t.stbcnt as _,
+			tai: t.tai,
+			_pad: [0; 11],
+		}
+	}
+}
+
+impl From<CompatTimex> for Timex {
+	fn from(t: CompatTimex) -> Self {
+		Self {
+			modes: t.modes,
+			offset: t.offset as _,
+			freq: t.freq as _,
+			maxerror: t.maxerror as _,
+			esterror: t.esterror as _,
+			status: t.status,
+			constant: t.constant as _,
+			precision: t.precision as _,
+			tolerance: t.tolerance as _,
+			// Sign-extend the seconds, which may be negative with `ADJ_SETOFFSET`
+			time: Timeval {
+				tv_sec: t.time.tv_sec as i32 as _,
+				tv_usec: t.time.tv_usec as _,
+			},
+			tick: t.tick as _,
+			ppsfreq: t.ppsfreq as _,
+			jitter: t.jitter as _,
+			shift: t.shift,
+			stabil: t.stabil as _,
+			jitcnt: t.jitcnt as _,
+			calcnt: t.calcnt as _,
+			errcnt: t.errcnt as _,
+			stbcnt: t.stbcnt as _,
+			tai: t.tai,
+			_pad: [0; 11],
+		}
+	}
+}
+
+/// Clock parameters that are not used by the kernel, but kept for NTP daemons.
+struct NtpState {
+	/// Frequency offset.
+	freq: c_long,
+	/// Maximum error, in microseconds.
+	maxerror: c_long,
+	/// Estimated error, in microseconds.
+	esterror: c_long,
+	/// Clock status.
+	status: c_int,
+	/// PLL time constant.
+	constant: c_long,
+	/// TAI offset.
+	tai: c_int,
+}
+
+/// The current NTP state.
+static STATE: Mutex<NtpState> = Mutex::new(NtpState {
+	freq: 0,
+	maxerror: 16_000_000,
+	esterror: 16_000_000,
+	status: STA_UNSYNC,
+	constant: 2,
+	tai: 0,
+});
+
+fn do_adjtimex<T: Debug + Copy + From<Timex> + Into<Timex>>(
+	buf: SyscallPtr<T>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let mut timex: Timex = buf.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?.into();
+	let modes = timex.modes;
+	if modes != 0 && modes != ADJ_OFFSET_SS_READ && !ap.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	let mut state = STATE.lock();
+	// Select resolution
+	if modes & ADJ_NANO != 0 {
+		state.status |= STA_NANO;
+	} else if modes & ADJ_MICRO != 0 {
+		state.status &= !STA_NANO;
+	}
+	let unit: i64 = if state.status & STA_NANO != 0 {
+		1
+	} else {
+		1000
+	};
+	let req_offset: i64 = timex.offset as _;
+	// Adjust the clock
+	let offset = match modes {
+		ADJ_OFFSET_SINGLESHOT => clock::adjust(req_offset * 1000) / 1000,
+		ADJ_OFFSET_SS_READ => clock::pending_adjustment() / 1000,
+		_ => {
+			if modes & ADJ_SETOFFSET != 0 {
+				let sec = timex.time.tv_sec as i64;
+				let frac = timex.time.tv_usec as i64;
+				if !(0..1_000_000_000 / unit).contains(&frac) {
+					return Err(errno!(EINVAL));
+				}
+				let delta = sec.wrapping_mul(1_000_000_000).wrapping_add(frac * unit);
+				let now = current_time_ns(Clock::Realtime);
+				clock::set_realtime(now.wrapping_add(delta as _));
+			}
+			if modes & ADJ_OFFSET != 0 {
+				clock::adjust(req_offset * unit);
+			}
+			if modes & ADJ_FREQUENCY != 0 {
+				state.freq = timex.freq.clamp(-MAXFREQ, MAXFREQ);
+			}
+			if modes & ADJ_MAXERROR != 0 {
+				state.maxerror = timex.maxerror;
+			}
+			if modes & ADJ_ESTERROR != 0 {
+				state.esterror = timex.esterror;
+			}
+			if modes & ADJ_STATUS != 0 {
+				state.status = (timex.status & !STA_NANO) | (state.status & STA_NANO);
+			}
+			if modes & ADJ_TIMECONST != 0 {
+				state.constant = timex.constant;
+			}
+			if modes & ADJ_TAI != 0 {
+				state.tai = timex.tai;
+			}
+			clock::pending_adjustment() / unit
+		}
+	};
+	// Write back the current state
+	let now = current_time_ns(Clock::Realtime);
+	let precision = clock::get_resolution(Clock::Realtime).unwrap_or(0) / 1000;
+	timex = Timex {
+		modes,
+		offset: offset as _,
+		freq: state.freq,
+		maxerror: state.maxerror,
+		esterror: state.esterror,
+		status: state.status,
+		constant: state.constant,
+		precision: precision.max(1) as _,
+		tolerance: MAXFREQ,
+		time: Timeval {
+			tv_sec: now / 1_000_000_000,
+			tv_usec: (now % 1_000_000_000) / unit as u64,
+		},
+		tick: 1_000_000 / USER_HZ,
+		tai: state.tai,
+		..Default::default()
+	};
+	let unsync = state.status & STA_UNSYNC != 0;
+	drop(state);
+	buf.copy_to_user(&timex.into())?;
+	Ok(if unsync { TIME_ERROR } else { TIME_OK })
+}
+
+#[syscall(id64 = 0x09f)]
+pub fn adjtimex(Args(buf): Args<SyscallPtr<Timex>>, ap: AccessProfile) -> EResult<usize> {
+	do_adjtimex(buf, ap)
+}
+
+#[syscall(id32 = 0x07c)]
+pub fn compat_adjtimex(
+	Args(buf): Args<SyscallPtr<CompatTimex>>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_adjtimex(buf, ap)
+}
@@ -18,13 +18,14 @@
 
 //! The `arch_prctl` system call sets architecture-specific thread state.
 
-#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-use crate::arch::x86;
 use crate::{
+	arch::x86,
 	process::mem_space::copy::SyscallPtr,
 	syscall::{Args, FromSyscallArg},
 };
 use core::ffi::c_int;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -44,6 +45,7 @@ const ARCH_GET_CPUID: c_int = 0x1011;
 const ARCH_SET_CPUID: c_int = 0x1012;
 
 #[allow(unused_variables)]
+#[syscall(id32 = 0x180, id64 = 0x09e)]
 pub fn arch_prctl(Args((code, addr)): Args<(c_int, usize)>) -> EResult<usize> {
 	// For `gs`, use kernel base because it will get swapped when returning to userspace
 	match code {
@@ -19,20 +19,24 @@
 //! The `bind` system call binds a name to a socket.
 
 use crate::{
-	file::{fd::FileDescriptorTable, socket::Socket},
+	file::{fd::FileDescriptorTable, socket::Socket, vfs::ResolutionSettings},
 	process::{mem_space::copy::SyscallSlice, Process},
 	sync::mutex::Mutex,
-	syscall::Args,
+	syscall::{Args, Umask},
 };
 use core::{any::Any, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x169, id64 = 0x031)]
 pub fn bind(
 	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, isize)>,
+	rs: ResolutionSettings,
+	umask: Umask,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Validation
@@ -45,6 +49,6 @@ pub fn bind(
 	let buf = addr
 		.copy_from_user_vec(0, addrlen as usize)?
 		.ok_or_else(|| errno!(EFAULT))?;
-	sock.bind(&buf)?;
+	sock.bind(&buf, &rs, umask.0)?;
 	Ok(0)
 }
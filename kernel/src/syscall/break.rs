@@ -18,11 +18,13 @@
 
 //! The `break` system call is not implemented.
 
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x011)]
 pub fn r#break() -> EResult<usize> {
 	Err(errno!(ENOSYS))
 }
@@ -26,11 +26,13 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_void;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x02d, id64 = 0x00c)]
 pub fn brk(
 	Args(addr): Args<VirtAddr>,
 	mem_space_mutex: Arc<IntMutex<MemSpace>>,
@@ -24,6 +24,7 @@ use crate::{
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -31,6 +32,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x00c, id64 = 0x050)]
 pub fn chdir(
 	Args(path): Args<SyscallString>,
 	proc: Arc<Process>,
@@ -26,6 +26,7 @@ use crate::{
 	syscall::{fchmodat::fchmodat, util::at::AT_FDCWD, Args},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -33,6 +34,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x00f, id64 = 0x05a)]
 pub fn chmod(
 	Args((pathname, mode)): Args<(SyscallString, file::Mode)>,
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
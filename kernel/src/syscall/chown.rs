@@ -24,6 +24,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -60,6 +61,7 @@ pub fn do_chown(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x0b6, id32 = 0x0d4, id64 = 0x05c)]
 pub fn chown(
 	Args((pathname, owner, group)): Args<(SyscallString, c_int, c_int)>,
 	rs: ResolutionSettings,
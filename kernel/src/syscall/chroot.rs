@@ -28,6 +28,7 @@ use crate::{
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -35,6 +36,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x03d, id64 = 0x0a1)]
 pub fn chroot(
 	Args(path): Args<SyscallString>,
 	proc: Arc<Process>,
@@ -47,7 +49,7 @@ pub fn chroot(
 	let path = path.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let path = PathBuf::try_from(path)?;
 	let rs = ResolutionSettings {
-		root: vfs::ROOT.clone(),
+		root: proc.fs.lock().mnt_ns.root(),
 		..rs
 	};
 	// Get file
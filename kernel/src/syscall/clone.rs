@@ -19,7 +19,10 @@
 //! The `clone` system call creates a child process.
 
 use crate::{
-	arch::x86::{cli, idt::IntFrame},
+	arch::{
+		x86,
+		x86::{cli, idt::IntFrame},
+	},
 	process::{
 		mem_space::copy::SyscallPtr,
 		pid::Pid,
@@ -32,7 +35,7 @@ use crate::{
 		user_desc::UserDesc,
 		ForkOptions, Process, State,
 	},
-	syscall::{Args, FromSyscallArg},
+	syscall::{set_thread_area::get_entry, Args, FromSyscallArg},
 };
 use core::{
 	ffi::{c_int, c_ulong, c_void},
@@ -40,13 +43,14 @@ use core::{
 	ptr::NonNull,
 	sync::atomic::Ordering::Relaxed,
 };
-use utils::{errno::EResult, ptr::arc::Arc};
+use macros::syscall;
+use utils::{errno, errno::EResult, ptr::arc::Arc};
 
 /// TODO doc
 pub const CLONE_IO: c_ulong = -0x80000000 as _;
 /// If specified, the parent and child processes share the same memory space.
 pub const CLONE_VM: c_ulong = 0x100;
-/// TODO doc
+/// If specified, the parent and child processes share the same filesystem information.
 pub const CLONE_FS: c_ulong = 0x200;
 /// If specified, the parent and child processes share the same file descriptors
 /// table.
@@ -62,33 +66,34 @@ pub const CLONE_PTRACE: c_ulong = 0x2000;
 pub const CLONE_VFORK: c_ulong = 0x4000;
 /// TODO doc
 pub const CLONE_PARENT: c_ulong = 0x8000;
-/// TODO doc
+/// If specified, the child process is a thread in the parent's thread group.
 pub const CLONE_THREAD: c_ulong = 0x10000;
-/// TODO doc
+/// If specified, the child process is created in a new mount namespace.
 pub const CLONE_NEWNS: c_ulong = 0x20000;
 /// TODO doc
 pub const CLONE_SYSVSEM: c_ulong = 0x40000;
-/// TODO doc
+/// If specified, the TLS of the child process is set from the `tls` argument.
 pub const CLONE_SETTLS: c_ulong = 0x80000;
-/// TODO doc
+/// If specified, the TID of the child process is written at `parent_tid` in the parent's memory.
 pub const CLONE_PARENT_SETTID: c_ulong = 0x100000;
-/// TODO doc
+/// If specified, zero is written at `child_tid` in the child's memory when it exits, and a
+/// thread waiting on it as a futex is woken up.
 pub const CLONE_CHILD_CLEARTID: c_ulong = 0x200000;
 /// TODO doc
 pub const CLONE_DETACHED: c_ulong = 0x400000;
 /// TODO doc
 pub const CLONE_UNTRACED: c_ulong = 0x800000;
-/// TODO doc
+/// If specified, the TID of the child process is written at `child_tid` in the child's memory.
 pub const CLONE_CHILD_SETTID: c_ulong = 0x1000000;
 /// TODO doc
 pub const CLONE_NEWCGROUP: c_ulong = 0x2000000;
-/// TODO doc
+/// If specified, the child process is created in a new UTS namespace.
 pub const CLONE_NEWUTS: c_ulong = 0x4000000;
 /// TODO doc
 pub const CLONE_NEWIPC: c_ulong = 0x8000000;
-/// TODO doc
+/// If specified, the child process is created in a new user namespace.
 pub const CLONE_NEWUSER: c_ulong = 0x10000000;
-/// TODO doc
+/// If specified, the child process is created in a new PID namespace.
 pub const CLONE_NEWPID: c_ulong = 0x20000000;
 /// TODO doc
 pub const CLONE_NEWNET: c_ulong = 0x40000000;
@@ -122,8 +127,9 @@ fn wait_vfork_done(child_pid: Pid) {
 }
 
 #[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x078)]
 pub fn compat_clone(
-	Args((flags, stack, _parent_tid, _tls, _child_tid)): Args<(
+	Args((flags, stack, parent_tidptr, tls, child_tidptr)): Args<(
 		c_ulong,
 		*mut c_void,
 		SyscallPtr<c_int>,
@@ -133,6 +139,40 @@ pub fn compat_clone(
 	proc: Arc<Process>,
 	frame: &mut IntFrame,
 ) -> EResult<usize> {
+	if unlikely(flags & (CLONE_NEWPID | CLONE_NEWUSER) != 0 && flags & CLONE_THREAD != 0) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(flags & (CLONE_NEWNS | CLONE_NEWUSER) != 0 && flags & CLONE_FS != 0) {
+		return Err(errno!(EINVAL));
+	}
+	// A thread shares the signal handlers, which in turn require a shared memory space
+	if unlikely(flags & CLONE_THREAD != 0 && flags & CLONE_SIGHAND == 0) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(flags & CLONE_SIGHAND != 0 && flags & CLONE_VM == 0) {
+		return Err(errno!(EINVAL));
+	}
+	// Creating a user namespace does not require privileges, and the child is privileged in it
+	let privileged = flags & CLONE_NEWUSER != 0 || proc.fs.lock().is_privileged();
+	if unlikely(flags & (CLONE_NEWPID | CLONE_NEWNS | CLONE_NEWUTS) != 0 && !privileged) {
+		return Err(errno!(EPERM));
+	}
+	// In compatibility mode, the TLS is a segment described by a `user_desc` structure. Read it
+	// before creating the child so that errors are reported to the parent
+	let tls_entry = if flags & CLONE_SETTLS != 0 && frame.is_compat() {
+		let info = SyscallPtr::<UserDesc>::from_ptr(tls as _)
+			.copy_from_user()?
+			.ok_or_else(|| errno!(EFAULT))?;
+		// Allocating an entry is not possible since its number cannot be returned
+		let mut entries = *proc.tls.lock();
+		let (id, _) = get_entry(&mut entries, info.get_entry_number())?;
+		if info.get_entry_number() == -1 {
+			return Err(errno!(EINVAL));
+		}
+		Some((id, info.to_descriptor()))
+	} else {
+		None
+	};
 	let (child_pid, child_tid) = {
 		// Disable interruptions so that the scheduler does not attempt to start the new process
 		cli();
@@ -142,19 +182,48 @@ pub fn compat_clone(
 				share_memory: flags & CLONE_VM != 0,
 				share_fd: flags & CLONE_FILES != 0,
 				share_sighand: flags & CLONE_SIGHAND != 0,
+				share_fs: flags & CLONE_FS != 0,
+				thread: flags & CLONE_THREAD != 0,
+				new_pid_ns: flags & CLONE_NEWPID != 0,
+				new_mnt_ns: flags & CLONE_NEWNS != 0,
+				new_uts_ns: flags & CLONE_NEWUTS != 0,
+				new_user_ns: flags & CLONE_NEWUSER != 0,
 			},
 		)?;
 		let child_pid = child.get_pid();
 		let child_tid = child.tid;
+		// The child sees its TID through its own namespace
+		let local_tid = child.pid_ns().local_pid(child_tid).unwrap_or(0) as c_int;
+		if flags & CLONE_PARENT_SETTID != 0 {
+			// The child already exists, hence the error cannot be reported
+			let _ = parent_tidptr.copy_to_user(&local_tid);
+		}
+		if flags & CLONE_CHILD_CLEARTID != 0 {
+			child
+				.clear_child_tid
+				.store(child_tidptr.as_ptr() as _, Relaxed);
+		}
+		if let Some((id, desc)) = tls_entry {
+			child.tls.lock()[id] = desc;
+		}
 		// Switch
 		switch::finish(&proc, &child);
 		SCHEDULER.lock().swap_current_process(child.clone());
+		// The child's memory space is now bound
+		if flags & CLONE_CHILD_SETTID != 0 {
+			let _ = child_tidptr.copy_to_user(&local_tid);
+		}
 		let mut child_frame = frame.clone();
 		child_frame.rax = 0; // Return value
 		if !stack.is_null() {
 			child_frame.rsp = stack as _;
 		}
 		stash_segments(|| unsafe {
+			// In 64-bit mode, the TLS is the base address of the FS segment
+			#[cfg(target_arch = "x86_64")]
+			if flags & CLONE_SETTLS != 0 && !frame.is_compat() {
+				x86::wrmsr(x86::IA32_FS_BASE, tls as _);
+			}
 			fork_asm(Arc::as_ptr(&proc), Arc::as_ptr(&child), &child_frame);
 		});
 		(child_pid, child_tid)
@@ -162,10 +231,13 @@ pub fn compat_clone(
 	if flags & CLONE_VFORK != 0 {
 		wait_vfork_done(child_pid);
 	}
+	// The parent sees the child through its own namespace
+	let child_tid = proc.pid_ns().local_pid(child_tid).unwrap_or(0);
 	Ok(child_tid as _)
 }
 
 #[allow(clippy::type_complexity)]
+#[syscall(id64 = 0x038)]
 pub fn clone(
 	Args((flags, stack, parent_tid, child_tid, tls)): Args<(
 		c_ulong,
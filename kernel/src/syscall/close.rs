@@ -21,12 +21,14 @@
 use super::Args;
 use crate::{file::fd::FileDescriptorTable, process::Process, sync::mutex::Mutex};
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x006, id64 = 0x003)]
 pub fn close(Args(fd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) -> EResult<usize> {
 	fds.lock().close_fd(fd as _)?;
 	Ok(0)
@@ -19,12 +19,13 @@
 //! The `connect` system call connects a socket to a distant host.
 
 use crate::{
-	file::{fd::FileDescriptorTable, socket::Socket},
+	file::{fd::FileDescriptorTable, socket::Socket, vfs::ResolutionSettings, O_NONBLOCK},
 	process::{mem_space::copy::SyscallSlice, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::{any::Any, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -32,8 +33,10 @@ use utils::{
 };
 
 /// The implementation of the `connect` syscall.
+#[syscall(id32 = 0x16a, id64 = 0x02a)]
 pub fn connect(
 	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, isize)>,
+	rs: ResolutionSettings,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Validation
@@ -42,10 +45,11 @@ pub fn connect(
 	}
 	// Get socket
 	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
-	let _sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
-	let _addr = addr
+	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
+	let addr = addr
 		.copy_from_user_vec(0, addrlen as usize)?
 		.ok_or_else(|| errno!(EFAULT))?;
-	// TODO connect socket
-	todo!()
+	let peer = sock.resolve(&addr, &rs)?;
+	sock.connect(peer, file.get_flags() & O_NONBLOCK != 0)?;
+	Ok(0)
 }
@@ -26,8 +26,16 @@ use crate::{
 	syscall::{openat::do_openat, util::at::AT_FDCWD},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::errno::EResult;
 
+#[syscall(id32 = 0x008, id64 = 0x055)]
 pub fn creat(Args((pathname, mode)): Args<(SyscallString, c_int)>) -> EResult<usize> {
-	do_openat(AT_FDCWD, pathname, O_CREAT | O_WRONLY | O_TRUNC, mode as _)
+	do_openat(
+		AT_FDCWD,
+		pathname,
+		O_CREAT | O_WRONLY | O_TRUNC,
+		mode as _,
+		0,
+	)
 }
@@ -25,6 +25,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_uint;
+use macros::syscall;
 use utils::{
 	collections::string::String,
 	errno,
@@ -32,6 +33,7 @@ use utils::{
 };
 // TODO handle flags
 
+#[syscall(id32 = 0x081, id64 = 0x0b0)]
 pub fn delete_module(
 	Args((name, _flags)): Args<(SyscallString, c_uint)>,
 	ap: AccessProfile,
@@ -25,12 +25,14 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x029, id64 = 0x020)]
 pub fn dup(Args(oldfd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) -> EResult<usize> {
 	let (newfd_id, _) = fds
 		.lock()
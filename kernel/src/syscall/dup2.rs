@@ -26,12 +26,14 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x03f, id64 = 0x021)]
 pub fn dup2(
 	Args((oldfd, newfd)): Args<(c_int, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -0,0 +1,43 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_create` system call creates an epoll instance.
+//!
+//! This is the legacy version of `epoll_create1`.
+
+use super::epoll_create1::do_epoll_create;
+use crate::{file::fd::FileDescriptorTable, sync::mutex::Mutex, syscall::Args};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x0fe, id64 = 0x0d5)]
+pub fn epoll_create(
+	Args(size): Args<c_int>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	// The size is not used anymore, but must still be positive
+	if size <= 0 {
+		return Err(errno!(EINVAL));
+	}
+	do_epoll_create(false, &fds)
+}
@@ -0,0 +1,57 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_create1` system call creates an epoll instance.
+
+use crate::{
+	file,
+	file::{
+		epoll::EPoll,
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		File,
+	},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Creates an epoll instance and returns its file descriptor.
+pub(super) fn do_epoll_create(cloexec: bool, fds: &Mutex<FileDescriptorTable>) -> EResult<usize> {
+	let epoll = Arc::new(EPoll::default())?;
+	let file = File::open_floating(epoll, file::O_RDWR)?;
+	let flags = if cloexec { FD_CLOEXEC } else { 0 };
+	let (fd, _) = fds.lock().create_fd(flags, file)?;
+	Ok(fd as _)
+}
+
+#[syscall(id32 = 0x149, id64 = 0x123)]
+pub fn epoll_create1(
+	Args(flags): Args<c_int>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	if flags & !file::O_CLOEXEC != 0 {
+		return Err(errno!(EINVAL));
+	}
+	do_epoll_create(flags & file::O_CLOEXEC != 0, &fds)
+}
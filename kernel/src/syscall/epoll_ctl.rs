@@ -0,0 +1,79 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_ctl` system call registers, modifies or unregisters a file on an epoll instance.
+
+use crate::{
+	file::{
+		epoll,
+		epoll::{EPoll, EPollEvent},
+		fd::FileDescriptorTable,
+		File,
+	},
+	process::mem_space::copy::SyscallPtr,
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Returns the epoll instance file open on the file descriptor `epfd`.
+///
+/// If the file is not an epoll instance, the function returns [`errno::EINVAL`].
+pub(super) fn get_epoll(fds: &FileDescriptorTable, epfd: c_int) -> EResult<Arc<File>> {
+	let file = fds.get_fd(epfd)?.get_file().clone();
+	if file.get_buffer::<EPoll>().is_none() {
+		return Err(errno!(EINVAL));
+	}
+	Ok(file)
+}
+
+#[syscall(id32 = 0x0ff, id64 = 0x0e9)]
+pub fn epoll_ctl(
+	Args((epfd, op, fd, event)): Args<(c_int, c_int, c_int, SyscallPtr<EPollEvent>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let (epoll_file, file) = {
+		let fds = fds.lock();
+		// Closed file descriptors are never registered
+		let file = fds.get_fd(fd)?.get_file().clone();
+		(get_epoll(&fds, epfd)?, file)
+	};
+	if fd == epfd {
+		return Err(errno!(EINVAL));
+	}
+	let epoll = epoll_file.get_buffer::<EPoll>().unwrap();
+	match op {
+		epoll::EPOLL_CTL_ADD | epoll::EPOLL_CTL_MOD => {
+			let event = event.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+			if op == epoll::EPOLL_CTL_ADD {
+				epoll.add(fd, &file, event)?;
+			} else {
+				epoll.modify(fd, &file, event)?;
+			}
+		}
+		epoll::EPOLL_CTL_DEL => epoll.remove(fd, &file)?,
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
@@ -0,0 +1,62 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_pwait` system call is like `epoll_wait`, but allows to change the mask of
+//! blocked signals while waiting.
+
+use super::epoll_wait::do_epoll_wait;
+use crate::{
+	file::{epoll::EPollEvent, fd::FileDescriptorTable},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		signal::SigSet,
+		Process,
+	},
+	sync::mutex::Mutex,
+	syscall::{util::with_sigmask, Args},
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x13f, id64 = 0x119)]
+pub fn epoll_pwait(
+	Args((epfd, events, maxevents, timeout, sigmask, sigsetsize)): Args<(
+		c_int,
+		SyscallSlice<EPollEvent>,
+		c_int,
+		c_int,
+		SyscallPtr<SigSet>,
+		usize,
+	)>,
+	proc: Arc<Process>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let sigmask = sigmask.copy_from_user()?;
+	if unlikely(sigmask.is_some() && sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	with_sigmask(&proc, sigmask, || {
+		do_epoll_wait(epfd, events, maxevents, timeout, &fds)
+	})
+}
@@ -0,0 +1,81 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `epoll_wait` system call waits for events on the files registered on an epoll instance.
+
+use super::epoll_ctl::get_epoll;
+use crate::{
+	file::{
+		epoll::{EPoll, EPollEvent},
+		fd::FileDescriptorTable,
+		wait_queue,
+	},
+	process::mem_space::copy::SyscallSlice,
+	sync::mutex::Mutex,
+	syscall::Args,
+	time::clock::{current_time_ns, Clock},
+};
+use core::{ffi::c_int, mem::size_of};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Waits for events on the epoll instance `epfd` and writes them to `events`.
+///
+/// `timeout` is the timeout in milliseconds. If negative, the function waits indefinitely.
+pub(super) fn do_epoll_wait(
+	epfd: c_int,
+	events: SyscallSlice<EPollEvent>,
+	maxevents: c_int,
+	timeout: c_int,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	if maxevents <= 0 || maxevents as usize > c_int::MAX as usize / size_of::<EPollEvent>() {
+		return Err(errno!(EINVAL));
+	}
+	let file = get_epoll(&fds.lock(), epfd)?;
+	let epoll = file.get_buffer::<EPoll>().unwrap();
+	// A negative timeout means waiting indefinitely
+	let deadline = (timeout >= 0)
+		.then(|| current_time_ns(Clock::Monotonic).saturating_add(timeout as u64 * 1_000_000));
+	let res = wait_queue::poll_wait(deadline, || {
+		let ready = epoll.collect(&fds.lock(), maxevents as _)?;
+		Ok((!ready.is_empty()).then_some(ready))
+	})?;
+	let Some(ready) = res else {
+		return Ok(0);
+	};
+	events.copy_to_user(0, &ready)?;
+	Ok(ready.len())
+}
+
+#[syscall(id32 = 0x100, id64 = 0x0e8)]
+pub fn epoll_wait(
+	Args((epfd, events, maxevents, timeout)): Args<(
+		c_int,
+		SyscallSlice<EPollEvent>,
+		c_int,
+		c_int,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_epoll_wait(epfd, events, maxevents, timeout, &fds)
+}
@@ -21,119 +21,25 @@
 use super::Args;
 use crate::{
 	arch::x86::idt::IntFrame,
-	file::{vfs, vfs::ResolutionSettings, File, O_RDONLY},
+	file::{vfs, vfs::ResolutionSettings},
 	process::{
 		exec,
-		exec::{exec, ExecInfo, ProgramImage},
+		exec::{exec, ExecInfo},
 		mem_space::copy::{SyscallArray, SyscallString},
-		scheduler::{switch::init_ctx, SCHEDULER},
+		scheduler::switch::init_ctx,
 		Process,
 	},
 };
-use core::intrinsics::unlikely;
+use macros::syscall;
 use utils::{
-	collections::{
-		path::{Path, PathBuf},
-		string::String,
-		vec::Vec,
-	},
+	collections::{path::PathBuf, vec::Vec},
 	errno,
-	errno::{CollectResult, EResult, Errno},
-	ptr::arc::Arc,
+	errno::{CollectResult, EResult},
 };
 
-/// The maximum length of the shebang.
-const SHEBANG_MAX: usize = 256;
-/// The maximum number of interpreters that can be used recursively for an
-/// execution.
-const INTERP_MAX: usize = 4;
-
 // TODO Use ARG_MAX
 
-/// A buffer containing a shebang.
-struct ShebangBuffer {
-	/// The before to store the shebang read from file.
-	buf: [u8; SHEBANG_MAX],
-	/// The index of the end of the shebang in the buffer.
-	end: usize,
-}
-
-impl Default for ShebangBuffer {
-	fn default() -> Self {
-		Self {
-			buf: [0; SHEBANG_MAX],
-			end: 0,
-		}
-	}
-}
-
-/// Returns the file for the given `path`.
-///
-/// The function also parses and eventual shebang string and builds the resulting **argv**.
-///
-/// Arguments:
-/// - `path` is the path of the executable file.
-/// - `rs` is the resolution settings to be used to open files.
-/// - `argv` is an iterator over the arguments passed to the system call.
-fn get_file<A: Iterator<Item = EResult<String>>>(
-	path: &Path,
-	rs: &ResolutionSettings,
-	argv: A,
-) -> EResult<(Arc<vfs::Entry>, Vec<String>)> {
-	let mut shebangs: [ShebangBuffer; INTERP_MAX] = Default::default();
-	// Read and parse shebangs
-	let mut ent = vfs::get_file_from_path(path, rs)?;
-	let mut i = 0;
-	loop {
-		// Check permission
-		let stat = ent.stat();
-		if !rs.access_profile.can_read_file(&stat) || !rs.access_profile.can_execute_file(&stat) {
-			return Err(errno!(EACCES));
-		}
-		// Read file
-		let shebang = &mut shebangs[i];
-		let len = {
-			let file = File::open_entry(ent.clone(), O_RDONLY)?;
-			file.ops.read(&file, 0, &mut shebang.buf)?
-		};
-		// Parse shebang
-		shebang.end = shebang.buf[..len]
-			.iter()
-			.position(|b| *b == b'\n')
-			.unwrap_or(len);
-		let Some(interp_path) = shebang.buf[..shebang.end].strip_prefix(b"#!") else {
-			break;
-		};
-		let interp_path = Path::new(interp_path)?;
-		i += 1;
-		// If there is still an interpreter but the limit has been reached
-		if unlikely(i >= INTERP_MAX) {
-			return Err(errno!(ELOOP));
-		}
-		// Read interpreter
-		ent = vfs::get_file_from_path(interp_path, rs)?;
-	}
-	// Build arguments
-	let final_argv = shebangs[..i]
-		.iter()
-		.rev()
-		.enumerate()
-		.flat_map(|(i, shebang)| {
-			let mut words =
-				shebang.buf[2..shebang.end].split(|b| (*b as char).is_ascii_whitespace());
-			// Skip interpreters, except the first
-			if i > 0 {
-				words.next();
-			}
-			words
-		})
-		.map(|s| Ok(String::try_from(s)?))
-		.chain(argv)
-		.collect::<EResult<CollectResult<Vec<String>>>>()?
-		.0?;
-	Ok((ent, final_argv))
-}
-
+#[syscall(id32 = 0x00b, id64 = 0x03b)]
 pub fn execve(
 	Args((pathname, argv, envp)): Args<(SyscallString, SyscallArray, SyscallArray)>,
 	rs: ResolutionSettings,
@@ -143,13 +49,16 @@ pub fn execve(
 	{
 		let path = pathname.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
 		let path = PathBuf::try_from(path)?;
-		let argv = argv.iter();
-		let (file, argv) = get_file(&path, &rs, argv)?;
+		let file = vfs::get_file_from_path(&path, &rs)?;
+		let execfn = vfs::Entry::get_path(&file)?;
+		let argv = argv.iter().collect::<EResult<CollectResult<Vec<_>>>>()?.0?;
 		let envp = envp.iter().collect::<EResult<CollectResult<Vec<_>>>>()?.0?;
 		let program_image = exec::build_image(
 			file,
 			ExecInfo {
 				path_resolution: &rs,
+				path: &path,
+				execfn: &execfn,
 				argv,
 				envp,
 			},
@@ -21,8 +21,10 @@
 
 use crate::{process::Process, syscall::Args};
 use core::ffi::c_int;
+use macros::syscall;
 use utils::errno::{EResult, Errno};
 
+#[syscall(id32 = 0x0fc, id64 = 0x0e7)]
 pub fn exit_group(Args(status): Args<c_int>) -> EResult<usize> {
 	super::_exit::do_exit(status as _, true);
 }
@@ -25,8 +25,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x133, id64 = 0x10d)]
 pub fn faccessat(
 	Args((dir_fd, pathname, mode)): Args<(c_int, SyscallString, c_int)>,
 	rs: ResolutionSettings,
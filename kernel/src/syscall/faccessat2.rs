@@ -22,15 +22,23 @@ use crate::{
 	file::{fd::FileDescriptorTable, vfs::ResolutionSettings},
 	process::mem_space::copy::SyscallString,
 	sync::mutex::Mutex,
-	syscall::Args,
+	syscall::{
+		util::at::{AT_EACCESS, AT_EMPTY_PATH, AT_SYMLINK_NOFOLLOW},
+		Args,
+	},
 };
-use core::ffi::c_int;
-use utils::{errno::EResult, ptr::arc::Arc};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{errno, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x1b7, id64 = 0x1b7)]
 pub fn faccessat2(
 	Args((dir_fd, pathname, mode, flags)): Args<(c_int, SyscallString, c_int, c_int)>,
 	rs: ResolutionSettings,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
+	if unlikely(flags & !(AT_EACCESS | AT_SYMLINK_NOFOLLOW | AT_EMPTY_PATH) != 0) {
+		return Err(errno!(EINVAL));
+	}
 	super::access::do_access(Some(dir_fd), pathname, mode, Some(flags), rs, fds)
 }
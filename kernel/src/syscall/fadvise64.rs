@@ -0,0 +1,33 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `fadvise64` syscall gives hints to the kernel about file accesses.
+
+use super::fadvise64_64::do_fadvise;
+use crate::{file::fd::FileDescriptorTable, sync::mutex::Mutex, syscall::Args};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+#[syscall(id64 = 0x0dd)]
+pub fn fadvise64(
+	Args((fd, offset, len, advice)): Args<(c_int, i64, i64, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_fadvise(fd, offset, len, advice, &fds)
+}
@@ -18,13 +18,81 @@
 
 //! The `fadvise64_64` syscall gives hints to the kernel about file accesses.
 
-use crate::syscall::Args;
-use core::ffi::c_int;
-use utils::errno::{EResult, Errno};
+use crate::{
+	file::{fd::FileDescriptorTable, FileType},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::{cmp::min, ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	limits::PAGE_SIZE,
+	ptr::arc::Arc,
+};
 
-pub fn fadvise64_64(
-	Args((_fd, _offset, _len, _advice)): Args<(c_int, u64, u64, c_int)>,
+/// No advice.
+const POSIX_FADV_NORMAL: c_int = 0;
+/// The file is accessed in random order.
+const POSIX_FADV_RANDOM: c_int = 1;
+/// The file is accessed sequentially.
+const POSIX_FADV_SEQUENTIAL: c_int = 2;
+/// The range is going to be accessed soon.
+const POSIX_FADV_WILLNEED: c_int = 3;
+/// The range is not going to be accessed soon.
+const POSIX_FADV_DONTNEED: c_int = 4;
+/// The range is going to be accessed only once.
+const POSIX_FADV_NOREUSE: c_int = 5;
+
+/// Performs the `fadvise64` and `fadvise64_64` system calls.
+///
+/// `len` is the length of the range in bytes. If zero, the range extends to the end of the file.
+pub(super) fn do_fadvise(
+	fd: c_int,
+	offset: i64,
+	len: i64,
+	advice: c_int,
+	fds: &Mutex<FileDescriptorTable>,
 ) -> EResult<usize> {
-	// TODO
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	if unlikely(offset < 0 || len < 0) {
+		return Err(errno!(EINVAL));
+	}
+	let Some(node) = file.node() else {
+		return Err(errno!(ESPIPE));
+	};
+	let stat = file.stat()?;
+	if unlikely(stat.get_type() == Some(FileType::Fifo)) {
+		return Err(errno!(ESPIPE));
+	}
+	// The range of pages
+	let end = match len {
+		0 => stat.size,
+		len => min((offset as u64).saturating_add(len as u64), stat.size),
+	};
+	let range = (offset as u64 / PAGE_SIZE as u64)..end.div_ceil(PAGE_SIZE as u64);
+	match advice {
+		// There is no readahead to tune
+		POSIX_FADV_NORMAL | POSIX_FADV_RANDOM | POSIX_FADV_SEQUENTIAL | POSIX_FADV_NOREUSE => {}
+		POSIX_FADV_WILLNEED => {
+			// This is only a hint: stop at the first error
+			for off in range {
+				if node.node_ops.read_page(node, off).is_err() {
+					break;
+				}
+			}
+		}
+		POSIX_FADV_DONTNEED => node.mapped.invalidate(range),
+		_ => return Err(errno!(EINVAL)),
+	}
 	Ok(0)
 }
+
+#[syscall(id32 = 0x110)]
+pub fn fadvise64_64(
+	Args((fd, offset, len, advice)): Args<(c_int, i64, i64, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_fadvise(fd, offset, len, advice, &fds)
+}
@@ -26,6 +26,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -33,6 +34,7 @@ use utils::{
 	TryClone,
 };
 
+#[syscall(id32 = 0x085, id64 = 0x051)]
 pub fn fchdir(
 	Args(fd): Args<c_int>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -26,12 +26,14 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x05e, id64 = 0x05b)]
 pub fn fchmod(
 	Args((fd, mode)): Args<(c_int, file::Mode)>,
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
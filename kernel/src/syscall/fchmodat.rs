@@ -32,6 +32,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -39,6 +40,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x132, id64 = 0x10c)]
 pub fn fchmodat(
 	Args((dirfd, pathname, mode, flags)): Args<(c_int, SyscallString, file::Mode, c_int)>,
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
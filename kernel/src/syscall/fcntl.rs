@@ -32,6 +32,7 @@ use core::{
 	any::Any,
 	ffi::{c_int, c_void},
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -284,6 +285,7 @@ pub fn do_fcntl(
 	}
 }
 
+#[syscall(id32 = 0x037, id64 = 0x048)]
 pub fn fcntl(
 	Args((fd, cmd, arg)): Args<(c_int, c_int, *mut c_void)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -20,11 +20,13 @@
 
 use crate::{file::fd::FileDescriptorTable, sync::mutex::Mutex, syscall::Args};
 use core::ffi::{c_int, c_void};
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x0dd)]
 pub fn fcntl64(
 	Args((fd, cmd, arg)): Args<(c_int, c_int, *mut c_void)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
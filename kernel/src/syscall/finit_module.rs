@@ -27,6 +27,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{alloc::AllocError, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -34,6 +35,7 @@ use utils::{
 	vec,
 };
 
+#[syscall(id32 = 0x15e, id64 = 0x139)]
 pub fn finit_module(
 	Args((fd, _param_values, _flags)): Args<(c_int, SyscallString, c_int)>,
 	ap: AccessProfile,
@@ -29,11 +29,13 @@ use crate::{
 	},
 };
 use core::ptr::null_mut;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x002, id64 = 0x039)]
 pub fn fork(proc: Arc<Process>, frame: &mut IntFrame) -> EResult<usize> {
 	clone(
 		Args((0, null_mut(), SyscallPtr(None), SyscallPtr(None), 0)),
@@ -18,13 +18,15 @@
 
 //! The `fstatfs` system call returns information about a mounted file system.
 
+use super::statfs::{write_statfs, CompatStatfs};
 use crate::{
 	file::{fd::FileDescriptorTable, fs::Statfs},
-	process::{mem_space::copy::SyscallPtr, Process},
+	process::mem_space::copy::SyscallPtr,
 	sync::mutex::Mutex,
 	syscall::Args,
 };
-use core::{ffi::c_int, intrinsics::size_of};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -32,13 +34,11 @@ use utils::{
 };
 
 /// Performs the `fstatfs` system call.
-pub fn do_fstatfs(
+pub(super) fn do_fstatfs<S: Debug + From<Statfs>>(
 	fd: c_int,
-	_sz: usize,
-	buf: SyscallPtr<Statfs>,
+	buf: SyscallPtr<S>,
 	fds: &FileDescriptorTable,
 ) -> EResult<usize> {
-	// TODO use `sz`
 	let stat = fds
 		.get_fd(fd)?
 		.get_file()
@@ -49,13 +49,21 @@ pub fn do_fstatfs(
 		.fs
 		.ops
 		.get_stat()?;
-	buf.copy_to_user(&stat)?;
-	Ok(0)
+	write_statfs(stat, buf)
 }
 
+#[syscall(id64 = 0x08a)]
 pub fn fstatfs(
 	Args((fd, buf)): Args<(c_int, SyscallPtr<Statfs>)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	do_fstatfs(fd, size_of::<Statfs>(), buf, &fds.lock())
+	do_fstatfs(fd, buf, &fds.lock())
+}
+
+#[syscall(id32 = 0x064)]
+pub fn compat_fstatfs(
+	Args((fd, buf)): Args<(c_int, SyscallPtr<CompatStatfs>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_fstatfs(fd, buf, &fds.lock())
 }
@@ -18,22 +18,26 @@
 
 //! The `fstatfs64` system call returns information about a mounted file system.
 
+use super::{fstatfs::do_fstatfs, statfs::CompatStatfs64};
 use crate::{
-	file::{fd::FileDescriptorTable, fs::Statfs},
-	process::{mem_space::copy::SyscallPtr, Process},
-	sync::mutex::Mutex,
+	file::fd::FileDescriptorTable, process::mem_space::copy::SyscallPtr, sync::mutex::Mutex,
 	syscall::Args,
 };
-use core::ffi::c_int;
+use core::{ffi::c_int, intrinsics::unlikely, mem::size_of};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x10d)]
 pub fn fstatfs64(
-	Args((fd, sz, buf)): Args<(c_int, usize, SyscallPtr<Statfs>)>,
+	Args((fd, sz, buf)): Args<(c_int, usize, SyscallPtr<CompatStatfs64>)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	super::fstatfs::do_fstatfs(fd, sz, buf, &fds.lock())
+	if unlikely(sz != size_of::<CompatStatfs64>()) {
+		return Err(errno!(EINVAL));
+	}
+	do_fstatfs(fd, buf, &fds.lock())
 }
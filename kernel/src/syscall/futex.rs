@@ -0,0 +1,99 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `futex` system call allows threads to wait on a value in memory, and to wake up threads
+//! waiting on it.
+
+use crate::{
+	memory::VirtAddr,
+	process::{futex, mem_space::copy::SyscallPtr, Process},
+	syscall::Args,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::{TimeUnit, Timespec, Timespec32},
+	},
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::{errno, errno::EResult, ptr::arc::Arc};
+
+/// Waits on the futex as long as it contains the given value.
+const FUTEX_WAIT: c_int = 0;
+/// Wakes up threads waiting on the futex.
+const FUTEX_WAKE: c_int = 1;
+
+/// Flag telling the futex is not shared with other processes.
+///
+/// Futexes are identified by their memory space anyway, so this flag is ignored.
+const FUTEX_PRIVATE_FLAG: c_int = 128;
+/// Flag telling the timeout is measured on the realtime clock.
+///
+/// The timeout of [`FUTEX_WAIT`] is relative, so this flag is ignored.
+const FUTEX_CLOCK_REALTIME: c_int = 256;
+
+fn do_futex<T: TimeUnit + Debug>(
+	uaddr: SyscallPtr<u32>,
+	futex_op: c_int,
+	val: u32,
+	timeout: SyscallPtr<T>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	if uaddr.as_ptr().is_null() || uaddr.as_ptr() as usize % 4 != 0 {
+		return Err(errno!(EINVAL));
+	}
+	match futex_op & !(FUTEX_PRIVATE_FLAG | FUTEX_CLOCK_REALTIME) {
+		FUTEX_WAIT => {
+			let deadline = timeout
+				.copy_from_user()?
+				.map(|ts| current_time_ns(Clock::Monotonic) + ts.to_nano());
+			futex::wait(uaddr, val, deadline)?;
+			Ok(0)
+		}
+		FUTEX_WAKE => {
+			let addr = VirtAddr(uaddr.as_ptr() as _);
+			Ok(futex::wake(&proc, addr, val as _))
+		}
+		_ => Err(errno!(ENOSYS)),
+	}
+}
+
+#[syscall(id32 = 0x1a6, id64 = 0x0ca)]
+pub fn futex(
+	Args((uaddr, futex_op, val, timeout)): Args<(
+		SyscallPtr<u32>,
+		c_int,
+		u32,
+		SyscallPtr<Timespec>,
+	)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_futex(uaddr, futex_op, val, timeout, proc)
+}
+
+#[syscall(id32 = 0x0f0)]
+pub fn compat_futex(
+	Args((uaddr, futex_op, val, timeout)): Args<(
+		SyscallPtr<u32>,
+		c_int,
+		u32,
+		SyscallPtr<Timespec32>,
+	)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_futex(uaddr, futex_op, val, timeout, proc)
+}
@@ -25,12 +25,14 @@ use crate::{
 	syscall::Args,
 };
 use core::intrinsics::unlikely;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x0b7, id64 = 0x04f)]
 pub fn getcwd(
 	Args((buf, size)): Args<(SyscallSlice<u8>, usize)>,
 	proc: Arc<Process>,
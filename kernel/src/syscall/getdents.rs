@@ -32,6 +32,7 @@ use core::{
 	ptr,
 	sync::atomic,
 };
+use macros::syscall;
 use utils::{
 	bytes::as_bytes,
 	errno,
@@ -94,6 +95,7 @@ fn do_getdents<F: FnMut(&DirEntry) -> EResult<bool>>(
 	Ok(())
 }
 
+#[syscall(id32 = 0x08d, id64 = 0x04e)]
 pub fn getdents(
 	Args((fd, dirp, count)): Args<(c_int, SyscallSlice<u8>, c_uint)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -142,6 +144,7 @@ pub fn getdents(
 	Ok(buf_off)
 }
 
+#[syscall(id32 = 0x0dc, id64 = 0x0d9)]
 pub fn getdents64(
 	Args((fd, dirp, count)): Args<(c_int, SyscallSlice<u8>, usize)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -18,9 +18,12 @@
 
 //! The `getegid` syscall returns the effective GID of the process's owner.
 
-use crate::{file::perm::AccessProfile, process::Process};
-use utils::errno::EResult;
+use crate::process::Process;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
 
-pub fn getegid(ap: AccessProfile) -> EResult<usize> {
-	Ok(ap.egid as _)
+#[syscall(id32 = 0x032, id32 = 0x0ca, id64 = 0x06c)]
+pub fn getegid(proc: Arc<Process>) -> EResult<usize> {
+	let fs = proc.fs.lock();
+	Ok(fs.local_gid(fs.access_profile.egid) as _)
 }
@@ -18,9 +18,12 @@
 
 //! The `geteuid` syscall returns the effective UID of the process's owner.
 
-use crate::{file::perm::AccessProfile, process::Process};
-use utils::errno::{EResult, Errno};
+use crate::process::Process;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
 
-pub fn geteuid(ap: AccessProfile) -> EResult<usize> {
-	Ok(ap.euid as _)
+#[syscall(id32 = 0x031, id32 = 0x0c9, id64 = 0x06b)]
+pub fn geteuid(proc: Arc<Process>) -> EResult<usize> {
+	let fs = proc.fs.lock();
+	Ok(fs.local_uid(fs.access_profile.euid) as _)
 }
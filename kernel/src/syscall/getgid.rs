@@ -18,9 +18,12 @@
 
 //! The `getgid` syscall returns the GID of the process's owner.
 
-use crate::{file::perm::AccessProfile, process::Process};
-use utils::errno::{EResult, Errno};
+use crate::process::Process;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
 
-pub fn getgid(ap: AccessProfile) -> EResult<usize> {
-	Ok(ap.gid as _)
+#[syscall(id32 = 0x02f, id32 = 0x0c8, id64 = 0x068)]
+pub fn getgid(proc: Arc<Process>) -> EResult<usize> {
+	let fs = proc.fs.lock();
+	Ok(fs.local_gid(fs.access_profile.gid) as _)
 }
@@ -0,0 +1,44 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `getpeername` system call returns the address of the peer a socket is connected to.
+
+use crate::{
+	file::{fd::FileDescriptorTable, socket::Socket},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::{util::copy_sockaddr_to_user, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x170, id64 = 0x034)]
+pub fn getpeername(
+	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, SyscallPtr<c_int>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
+	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
+	copy_sockaddr_to_user(&addr, &addrlen, &sock.get_peername()?)?;
+	Ok(0)
+}
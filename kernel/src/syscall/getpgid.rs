@@ -22,11 +22,13 @@ use crate::{
 	process::{pid::Pid, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x084, id64 = 0x079)]
 pub fn getpgid(Args(pid): Args<Pid>) -> EResult<usize> {
 	if pid == 0 {
 		let proc = Process::current();
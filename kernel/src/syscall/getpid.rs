@@ -19,11 +19,14 @@
 //! The `getpid` system call returns the PID of the current process.
 
 use crate::process::Process;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x014, id64 = 0x027)]
 pub fn getpid(proc: Arc<Process>) -> EResult<usize> {
-	Ok(proc.get_pid() as _)
+	// All threads of a thread group share the same PID
+	Ok(proc.pid_ns().local_pid(proc.get_tgid()).unwrap_or(0) as _)
 }
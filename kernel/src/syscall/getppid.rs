@@ -19,11 +19,15 @@
 //! The `getppid` system call returns the PID of the process's parent.
 
 use crate::process::Process;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x040, id64 = 0x06e)]
 pub fn getppid(proc: Arc<Process>) -> EResult<usize> {
-	Ok(proc.get_parent_pid() as _)
+	// The parent is not visible if it is outside of the process's namespace
+	let ppid = proc.pid_ns().local_pid(proc.get_parent_pid()).unwrap_or(0);
+	Ok(ppid as _)
 }
@@ -0,0 +1,32 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `getpriority` system call returns the nice value of a process.
+
+use crate::syscall::{setpriority::get_prio_target, Args};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::errno::{EResult, Errno};
+
+#[syscall(id32 = 0x060, id64 = 0x08c)]
+pub fn getpriority(Args((which, who)): Args<(c_int, c_int)>) -> EResult<usize> {
+	let target = get_prio_target(which, who)?;
+	// The nice value is returned in the range `[1, 40]` to avoid negative values, which would be
+	// mistaken for errors
+	Ok((20 - target.nice() as isize) as _)
+}
@@ -24,6 +24,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_uint;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -36,6 +37,7 @@ const GRND_RANDOM: u32 = 2;
 /// returns [`EAGAIN`].
 const GRND_NONBLOCK: u32 = 1;
 
+#[syscall(id32 = 0x163, id64 = 0x13e)]
 pub fn getrandom(
 	Args((buf, buflen, flags)): Args<(SyscallSlice<u8>, usize, c_uint)>,
 ) -> EResult<usize> {
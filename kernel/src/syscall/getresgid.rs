@@ -24,14 +24,18 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x0ab, id32 = 0x0d3, id64 = 0x078)]
 pub fn getresgid(
 	Args((rgid, egid, sgid)): Args<(SyscallPtr<Gid>, SyscallPtr<Gid>, SyscallPtr<Gid>)>,
-	ap: AccessProfile,
+	proc: Arc<Process>,
 ) -> EResult<usize> {
-	rgid.copy_to_user(&ap.gid)?;
-	egid.copy_to_user(&ap.egid)?;
-	sgid.copy_to_user(&ap.sgid)?;
+	let fs = proc.fs.lock();
+	let ap = fs.access_profile;
+	rgid.copy_to_user(&fs.local_gid(ap.gid))?;
+	egid.copy_to_user(&fs.local_gid(ap.egid))?;
+	sgid.copy_to_user(&fs.local_gid(ap.sgid))?;
 	Ok(0)
 }
@@ -24,14 +24,18 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x0a5, id32 = 0x0d1, id64 = 0x076)]
 pub fn getresuid(
 	Args((ruid, euid, suid)): Args<(SyscallPtr<Uid>, SyscallPtr<Uid>, SyscallPtr<Uid>)>,
-	ap: AccessProfile,
+	proc: Arc<Process>,
 ) -> EResult<usize> {
-	ruid.copy_to_user(&ap.uid)?;
-	euid.copy_to_user(&ap.euid)?;
-	suid.copy_to_user(&ap.suid)?;
+	let fs = proc.fs.lock();
+	let ap = fs.access_profile;
+	ruid.copy_to_user(&fs.local_uid(ap.uid))?;
+	euid.copy_to_user(&fs.local_uid(ap.euid))?;
+	suid.copy_to_user(&fs.local_uid(ap.suid))?;
 	Ok(0)
 }
@@ -24,6 +24,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{ffi::c_int, ops::Deref};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -34,6 +35,7 @@ const RUSAGE_SELF: i32 = 0;
 /// Returns the resource usage of the process's children.
 const RUSAGE_CHILDREN: i32 = -1;
 
+#[syscall(id32 = 0x04d, id64 = 0x062)]
 pub fn getrusage(Args((who, usage)): Args<(c_int, SyscallPtr<Rusage>)>) -> EResult<usize> {
 	let proc = Process::current();
 	let rusage = match who {
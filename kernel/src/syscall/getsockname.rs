@@ -25,30 +25,25 @@ use crate::{
 		Process,
 	},
 	sync::mutex::Mutex,
-	syscall::Args,
+	syscall::{util::copy_sockaddr_to_user, Args},
 };
-use core::{any::Any, cmp::min, ffi::c_int};
+use core::{any::Any, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x16f, id64 = 0x033)]
 pub fn getsockname(
-	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, SyscallPtr<isize>)>,
+	Args((sockfd, addr, addrlen)): Args<(c_int, SyscallSlice<u8>, SyscallPtr<c_int>)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Get socket
 	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
 	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
-	// Read and check buffer length
-	let addrlen_val = addrlen.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-	if addrlen_val < 0 {
-		return Err(errno!(EINVAL));
-	}
 	let name = sock.get_sockname().lock();
-	let len = min(name.len(), addrlen_val as _);
-	addr.copy_to_user(0, &name[..len])?;
-	addrlen.copy_to_user(&(len as _))?;
+	copy_sockaddr_to_user(&addr, &addrlen, &name)?;
 	Ok(0)
 }
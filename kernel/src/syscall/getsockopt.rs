@@ -20,33 +20,42 @@
 
 use crate::{
 	file::{fd::FileDescriptorTable, socket::Socket},
-	process::{mem_space::copy::SyscallSlice, Process},
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		Process,
+	},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
-use core::{any::Any, cmp::min, ffi::c_int};
+use core::{any::Any, ffi::c_int, mem::size_of};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x16d, id64 = 0x037)]
 pub fn getsockopt(
 	Args((sockfd, level, optname, optval, optlen)): Args<(
 		c_int,
 		c_int,
 		c_int,
 		SyscallSlice<u8>,
-		usize,
+		SyscallPtr<c_int>,
 	)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Get socket
 	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
 	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
-	let val = sock.get_opt(level, optname)?;
-	// Write back
-	let len = min(val.len(), optlen);
+	let len = optlen.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let len: usize = len.try_into().map_err(|_| errno!(EINVAL))?;
+	let val = sock.get_opt(level, optname)?.to_ne_bytes();
+	// Write back, truncating the value if the buffer is too small
+	let len = len.min(size_of::<c_int>());
 	optval.copy_to_user(0, &val[..len])?;
-	Ok(len as _)
+	optlen.copy_to_user(&(len as _))?;
+	Ok(0)
 }
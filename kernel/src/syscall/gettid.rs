@@ -19,11 +19,13 @@
 //! The `gettid` system call returns the thread ID of the current process.
 
 use crate::process::Process;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x0e0, id64 = 0x0ba)]
 pub fn gettid(proc: Arc<Process>) -> EResult<usize> {
-	Ok(proc.tid as _)
+	Ok(proc.pid_ns().local_pid(proc.tid).unwrap_or(0) as _)
 }
@@ -0,0 +1,69 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `gettimeofday` system call returns the time of the real time clock.
+
+use crate::{
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::{TimeUnit, Timeval, Timeval32},
+	},
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::errno::EResult;
+
+/// A timezone, as returned by `gettimeofday`.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Timezone {
+	/// Minutes west of Greenwich.
+	tz_minuteswest: c_int,
+	/// Type of daylight saving time correction.
+	tz_dsttime: c_int,
+}
+
+fn do_gettimeofday<T: TimeUnit + Debug>(
+	tv: SyscallPtr<T>,
+	tz: SyscallPtr<Timezone>,
+) -> EResult<usize> {
+	let time = current_time_ns(Clock::Realtime);
+	tv.copy_to_user(&T::from_nano(time))?;
+	// The timezone is obsolete, thus the clock is always UTC
+	tz.copy_to_user(&Timezone {
+		tz_minuteswest: 0,
+		tz_dsttime: 0,
+	})?;
+	Ok(0)
+}
+
+#[syscall(id64 = 0x060)]
+pub fn gettimeofday(
+	Args((tv, tz)): Args<(SyscallPtr<Timeval>, SyscallPtr<Timezone>)>,
+) -> EResult<usize> {
+	do_gettimeofday(tv, tz)
+}
+
+#[syscall(id32 = 0x04e)]
+pub fn compat_gettimeofday(
+	Args((tv, tz)): Args<(SyscallPtr<Timeval32>, SyscallPtr<Timezone>)>,
+) -> EResult<usize> {
+	do_gettimeofday(tv, tz)
+}
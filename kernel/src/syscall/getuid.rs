@@ -18,9 +18,12 @@
 
 //! The `getuid` syscall returns the UID of the process's owner.
 
-use crate::{file::perm::AccessProfile, process::Process};
-use utils::errno::{EResult, Errno};
+use crate::process::Process;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
 
-pub fn getuid(ap: AccessProfile) -> EResult<usize> {
-	Ok(ap.uid as _)
+#[syscall(id32 = 0x018, id32 = 0x0c7, id64 = 0x066)]
+pub fn getuid(proc: Arc<Process>) -> EResult<usize> {
+	let fs = proc.fs.lock();
+	Ok(fs.local_uid(fs.access_profile.uid) as _)
 }
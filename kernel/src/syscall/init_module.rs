@@ -29,11 +29,13 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_ulong;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x080, id64 = 0x0af)]
 pub fn init_module(
 	Args((module_image, len, _param_values)): Args<(SyscallSlice<u8>, c_ulong, SyscallString)>,
 	ap: AccessProfile,
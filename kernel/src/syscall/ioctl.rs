@@ -21,6 +21,7 @@
 
 use crate::{file::fd::FileDescriptorTable, process::Process, sync::mutex::Mutex, syscall::Args};
 use core::ffi::{c_int, c_ulong, c_void};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -53,16 +54,49 @@ pub const TCSETSW: c_ulong = 0x00005403;
 /// ioctl request: Sets the serial port settings. Making the change only when
 /// all currently written data has been transmitted.
 pub const TCSETSF: c_ulong = 0x00005404;
+/// ioctl request: Makes the terminal the controlling terminal of the current session.
+pub const TIOCSCTTY: c_ulong = 0x0000540e;
 /// ioctl request: Get the foreground process group ID on the terminal.
 pub const TIOCGPGRP: c_ulong = 0x0000540f;
 /// ioctl request: Set the foreground process group ID on the terminal.
 pub const TIOCSPGRP: c_ulong = 0x00005410;
+/// ioctl request: Inserts the given byte in the terminal's input queue.
+pub const TIOCSTI: c_ulong = 0x00005412;
 /// ioctl request: Returns the window size of the terminal.
 pub const TIOCGWINSZ: c_ulong = 0x00005413;
 /// ioctl request: Sets the window size of the terminal.
 pub const TIOCSWINSZ: c_ulong = 0x00005414;
 /// ioctl request: Returns the number of bytes available on the file descriptor.
 pub const FIONREAD: c_ulong = 0x0000541b;
+/// ioctl request: Gives up the controlling terminal of the current session.
+pub const TIOCNOTTY: c_ulong = 0x00005422;
+/// ioctl request: Returns the ID of the session of which the terminal is the controlling
+/// terminal.
+pub const TIOCGSID: c_ulong = 0x00005429;
+/// ioctl request: Returns the number of the pseudo-terminal slave associated with the master.
+pub const TIOCGPTN: c_ulong = 0x00005430;
+/// ioctl request: Locks or unlocks the pseudo-terminal slave associated with the master.
+pub const TIOCSPTLCK: c_ulong = 0x00005431;
+
+// ioctl requests: kernel coverage
+
+/// ioctl request: Allocates the trace buffer, with the given number of entries.
+pub const KCOV_INIT_TRACE: c_ulong = 0x00006301;
+/// ioctl request: Starts tracing the calling process.
+pub const KCOV_ENABLE: c_ulong = 0x00006364;
+/// ioctl request: Stops tracing the calling process.
+pub const KCOV_DISABLE: c_ulong = 0x00006365;
+
+// ioctl requests: console
+
+/// ioctl request: Returns the console's color palette.
+pub const GIO_CMAP: c_ulong = 0x00004b70;
+/// ioctl request: Sets the console's color palette.
+pub const PIO_CMAP: c_ulong = 0x00004b71;
+/// ioctl request: Returns the console's cursor settings. This request is specific to Maestro.
+pub const KDGCURSOR: c_ulong = 0x00004bf0;
+/// ioctl request: Sets the console's cursor settings. This request is specific to Maestro.
+pub const KDSCURSOR: c_ulong = 0x00004bf1;
 
 /// IO directions for ioctl requests.
 #[derive(Eq, PartialEq)]
@@ -107,7 +141,7 @@ impl From<c_ulong> for Request {
 			major: ((req >> 8) & 0xff) as u8,
 			minor: (req & 0xff) as u8,
 
-			size: ((req >> 16) & 0x3f) as usize,
+			size: ((req >> 16) & 0x3fff) as usize,
 			direction: ((req >> 30) & 0x03).try_into().unwrap(),
 		}
 	}
@@ -120,6 +154,7 @@ impl Request {
 	}
 }
 
+#[syscall(id32 = 0x036, id64 = 0x010)]
 pub(super) fn ioctl(
 	Args((fd, request, argp)): Args<(c_int, c_ulong, *const c_void)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
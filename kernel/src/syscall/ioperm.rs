@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ioperm` system call sets the I/O port permissions of the calling process.
+
+use crate::{arch::x86::tss, file::perm::AccessProfile, process::Process, syscall::Args};
+use core::{
+	ffi::{c_int, c_ulong},
+	intrinsics::unlikely,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x065, id64 = 0x0ad)]
+pub fn ioperm(
+	Args((from, num, turn_on)): Args<(c_ulong, c_ulong, c_int)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	let end = from.checked_add(num).ok_or_else(|| errno!(EINVAL))?;
+	if unlikely(end as usize > tss::IO_BITMAP_SIZE * 8) {
+		return Err(errno!(EINVAL));
+	}
+	let turn_on = turn_on != 0;
+	if turn_on && !ap.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	let mut bitmap = proc.io_bitmap.lock();
+	if bitmap.is_empty() {
+		if !turn_on {
+			return Ok(0);
+		}
+		bitmap.resize(tss::IO_BITMAP_SIZE, !0)?;
+	}
+	// A cleared bit allows access
+	for port in from as usize..end as usize {
+		let byte = &mut bitmap[port / 8];
+		if turn_on {
+			*byte &= !(1 << (port % 8));
+		} else {
+			*byte |= 1 << (port % 8);
+		}
+	}
+	// Apply to the current task
+	unsafe {
+		tss::set_io_bitmap(&bitmap);
+	}
+	Ok(0)
+}
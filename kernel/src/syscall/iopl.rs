@@ -0,0 +1,49 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `iopl` system call sets the I/O privilege level of the calling thread.
+//!
+//! A thread with an I/O privilege level of 3 can access all I/O ports.
+
+use crate::{
+	arch::x86::{idt::IntFrame, FLAG_IOPL},
+	file::perm::AccessProfile,
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x06e, id64 = 0x0ac)]
+pub fn iopl(Args(level): Args<c_int>, ap: AccessProfile, frame: &mut IntFrame) -> EResult<usize> {
+	if unlikely(!(0..=3).contains(&level)) {
+		return Err(errno!(EINVAL));
+	}
+	let flags = frame.rflags as usize;
+	let cur = (flags & FLAG_IOPL) >> FLAG_IOPL.trailing_zeros();
+	// Raising the level requires privileges
+	if level as usize > cur && !ap.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	let flags = (flags & !FLAG_IOPL) | ((level as usize) << FLAG_IOPL.trailing_zeros());
+	frame.rflags = flags as _;
+	Ok(0)
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ioprio_set` system call sets the I/O priority of a process.
+
+use crate::{
+	device::queue::{
+		ioprio_class, ioprio_level, IOPRIO_CLASS_BE, IOPRIO_CLASS_IDLE, IOPRIO_CLASS_NONE,
+		IOPRIO_CLASS_RT, IOPRIO_NR_LEVELS,
+	},
+	process::{pid::Pid, Process},
+	syscall::{sched_setscheduler::get_target, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// `which` value: the target is a process.
+const IOPRIO_WHO_PROCESS: c_int = 1;
+
+/// Returns the process targeted by the `which` and `who` arguments of `ioprio_get` and
+/// `ioprio_set`.
+///
+/// Only [`IOPRIO_WHO_PROCESS`] is supported.
+pub(super) fn get_ioprio_target(which: c_int, who: c_int) -> EResult<Arc<Process>> {
+	if which != IOPRIO_WHO_PROCESS {
+		return Err(errno!(EINVAL));
+	}
+	let pid = Pid::try_from(who).map_err(|_| errno!(ESRCH))?;
+	get_target(pid)
+}
+
+#[syscall(id32 = 0x121, id64 = 0x0fb)]
+pub fn ioprio_set(Args((which, who, ioprio)): Args<(c_int, c_int, c_int)>) -> EResult<usize> {
+	let ioprio: u16 = ioprio.try_into().map_err(|_| errno!(EINVAL))?;
+	let valid = match ioprio_class(ioprio) {
+		IOPRIO_CLASS_NONE => ioprio_level(ioprio) == 0,
+		IOPRIO_CLASS_RT | IOPRIO_CLASS_BE => ioprio_level(ioprio) < IOPRIO_NR_LEVELS,
+		IOPRIO_CLASS_IDLE => true,
+		_ => false,
+	};
+	if !valid {
+		return Err(errno!(EINVAL));
+	}
+	let target = get_ioprio_target(which, who)?;
+	let ap = Process::current().fs.lock().access_profile;
+	if !ap.is_privileged() {
+		if ioprio_class(ioprio) == IOPRIO_CLASS_RT {
+			return Err(errno!(EPERM));
+		}
+		let target_ap = target.fs.lock().access_profile;
+		if ap.euid != target_ap.uid && ap.euid != target_ap.euid {
+			return Err(errno!(EPERM));
+		}
+	}
+	target.set_ioprio(ioprio);
+	Ok(0)
+}
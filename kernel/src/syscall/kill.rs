@@ -21,15 +21,20 @@
 use super::{util, Args};
 use crate::{
 	process,
-	process::{pid::Pid, scheduler::SCHEDULER, signal::Signal, Process, State},
+	process::{
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_USER},
+		Process, State,
+	},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
-/// Tries to kill the process with PID `pid` with the signal `sig`.
+/// Tries to kill the process with the global PID `pid` with the signal `sig`.
 ///
 /// If `sig` is `None`, the function doesn't send a signal, but still checks if
 /// there is a process that could be killed.
@@ -45,7 +50,11 @@ fn try_kill(pid: Pid, sig: Option<Signal>) -> EResult<()> {
 			return Err(errno!(EPERM));
 		}
 		if let Some(sig) = sig {
-			target.kill(sig);
+			target.kill_info(SigInfo {
+				si_pid: target.pid_ns().local_pid(proc.get_pid()).unwrap_or(0),
+				si_uid: ap.uid,
+				..SigInfo::new(sig, SI_USER)
+			});
 		}
 		Ok(())
 	};
@@ -67,10 +76,11 @@ fn try_kill(pid: Pid, sig: Option<Signal>) -> EResult<()> {
 /// If `sig` is `None`, the function doesn't send a signal, but still checks if
 /// there is a process that could be killed.
 fn try_kill_group(pid: i32, sig: Option<Signal>) -> EResult<()> {
+	let proc = Process::current();
 	let pgid = match pid {
-		0 => Process::current().get_pgid(),
-		i if i < 0 => -pid as Pid,
-		_ => pid as Pid,
+		0 => proc.get_pgid(),
+		i if i < 0 => global_pid(&proc, -pid as Pid)?,
+		_ => global_pid(&proc, pid as Pid)?,
 	};
 	// Kill process group
 	Process::get_by_pid(pgid)
@@ -82,22 +92,32 @@ fn try_kill_group(pid: i32, sig: Option<Signal>) -> EResult<()> {
 		.try_for_each(|pid| try_kill(*pid as _, sig))
 }
 
+/// Returns the global PID of the process with the PID `pid`, local to the namespace of `proc`.
+///
+/// If no such process exists, the function returns [`errno::ESRCH`].
+fn global_pid(proc: &Process, pid: Pid) -> EResult<Pid> {
+	proc.pid_ns().global_pid(pid).ok_or_else(|| errno!(ESRCH))
+}
+
+#[syscall(id32 = 0x025, id64 = 0x03e)]
 pub fn kill(Args((pid, sig)): Args<(c_int, c_int)>) -> EResult<usize> {
 	let sig = (sig != 0).then(|| Signal::try_from(sig)).transpose()?;
 	match pid {
 		// Kill the process with the given PID
-		1.. => try_kill(pid as _, sig)?,
+		1.. => try_kill(global_pid(&Process::current(), pid as _)?, sig)?,
 		// Kill all processes in the current process group
 		0 => try_kill_group(0, sig)?,
 		// Kill all processes for which the current process has the permission
 		-1 => {
-			let sched = SCHEDULER.lock();
-			for (pid, _) in sched.iter_process() {
-				if *pid == process::pid::INIT_PID {
+			// Collect PIDs first since killing requires locking the scheduler
+			let proc = Process::current();
+			let ns = proc.pid_ns();
+			for pid in ns.processes()? {
+				if ns.local_pid(pid) == Some(process::pid::INIT_PID) {
 					continue;
 				}
 				// TODO Check permission
-				try_kill(*pid, sig)?;
+				try_kill(pid, sig)?;
 			}
 		}
 		// Kill the given process group
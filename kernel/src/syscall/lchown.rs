@@ -22,8 +22,10 @@ use crate::{
 	file::vfs::ResolutionSettings, process::mem_space::copy::SyscallString, syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::errno::EResult;
 
+#[syscall(id32 = 0x010, id64 = 0x05e)]
 pub fn lchown(
 	Args((pathname, owner, group)): Args<(SyscallString, c_int, c_int)>,
 	rs: ResolutionSettings,
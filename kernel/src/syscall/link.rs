@@ -25,6 +25,7 @@ use crate::{
 	sync::mutex::Mutex,
 	syscall::{linkat::linkat, util::at::AT_FDCWD},
 };
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -32,6 +33,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x009, id64 = 0x056)]
 pub fn link(
 	Args((oldpath, newpath)): Args<(SyscallString, SyscallString)>,
 	fds_mutex: Arc<Mutex<FileDescriptorTable>>,
@@ -31,6 +31,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -38,6 +39,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x12f, id64 = 0x109)]
 pub fn linkat(
 	Args((olddirfd, oldpath, newdirfd, newpath, flags)): Args<(
 		c_int,
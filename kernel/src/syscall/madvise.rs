@@ -21,8 +21,10 @@
 
 use crate::syscall::Args;
 use core::ffi::{c_int, c_void};
+use macros::syscall;
 use utils::errno::{EResult, Errno};
 
+#[syscall(id32 = 0x0db, id64 = 0x01c)]
 pub fn madvise(
 	Args((_addr, _length, _advice)): Args<(*mut c_void, usize, c_int)>,
 ) -> EResult<usize> {
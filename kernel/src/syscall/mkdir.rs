@@ -25,12 +25,14 @@ use crate::{
 	syscall::{Args, Umask},
 	time::clock::{current_time_ns, current_time_sec, Clock},
 };
+use macros::syscall;
 use utils::{
 	collections::path::{Path, PathBuf},
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x027, id64 = 0x053)]
 pub fn mkdir(
 	Args((pathname, mode)): Args<(SyscallString, file::Mode)>,
 	rs: ResolutionSettings,
@@ -26,12 +26,14 @@ use crate::{
 	syscall::{Args, Umask},
 	time::clock::{current_time_ns, current_time_sec, Clock},
 };
+use macros::syscall;
 use utils::{
 	collections::path::{Path, PathBuf},
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x00e, id64 = 0x085)]
 pub fn mknod(
 	Args((pathname, mode, dev)): Args<(SyscallString, file::Mode, u64)>,
 	umask: Umask,
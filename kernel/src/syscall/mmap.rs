@@ -35,6 +35,7 @@ use core::{
 	intrinsics::unlikely,
 	num::NonZeroUsize,
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -69,7 +70,6 @@ pub fn do_mmap(
 		return Err(errno!(EINVAL));
 	}
 	let prot = prot as u8;
-	let flags = flags as u8;
 	let constraint = {
 		if !addr.is_null() {
 			if flags & MAP_FIXED != 0 {
@@ -93,8 +93,13 @@ pub fn do_mmap(
 		let file = fds.lock().get_fd(fd)?.get_file().clone();
 		// Check permissions
 		let stat = file.stat()?;
-		if stat.get_type() != Some(FileType::Regular) {
-			return Err(errno!(EACCES));
+		match stat.get_type() {
+			Some(FileType::Regular) => {}
+			// Devices exposing physical memory
+			Some(FileType::CharDevice) => {
+				file.ops.phys_page(&file, offset / PAGE_SIZE as u64)?;
+			}
+			_ => return Err(errno!(EACCES)),
 		}
 		if prot & PROT_READ != 0 && !ap.can_read_file(&stat) {
 			return Err(errno!(EPERM));
@@ -125,6 +130,7 @@ pub fn do_mmap(
 	}
 }
 
+#[syscall(id32 = 0x05a, id64 = 0x009)]
 pub fn mmap(
 	Args((addr, length, prot, flags, fd, offset)): Args<(
 		VirtAddr,
@@ -151,6 +157,7 @@ pub fn mmap(
 	)
 }
 
+#[syscall(id32 = 0x0c0)]
 pub fn mmap2(
 	Args((addr, length, prot, flags, fd, offset)): Args<(
 		VirtAddr,
@@ -21,7 +21,11 @@
 use crate::{
 	file::{
 		fs, vfs,
-		vfs::{mountpoint, mountpoint::MountSource, ResolutionSettings},
+		vfs::{
+			mountpoint,
+			mountpoint::{MountSource, Propagation},
+			ResolutionSettings,
+		},
 		FileType,
 	},
 	process::{
@@ -31,12 +35,23 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::{c_ulong, c_void};
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
+/// Applies the propagation type change recursively to the mountpoints under the target.
+const MS_REC: c_ulong = 16384;
+/// Makes the target mountpoint private.
+const MS_PRIVATE: c_ulong = 1 << 18;
+/// Makes the target mountpoint a slave.
+const MS_SLAVE: c_ulong = 1 << 19;
+/// Makes the target mountpoint shared.
+const MS_SHARED: c_ulong = 1 << 20;
+
+#[syscall(id32 = 0x015, id64 = 0x0a5)]
 pub fn mount(
 	Args((source, target, filesystemtype, mountflags, _data)): Args<(
 		SyscallString,
@@ -50,6 +65,20 @@ pub fn mount(
 	if !rs.access_profile.is_privileged() {
 		return Err(errno!(EPERM));
 	}
+	// Change of propagation type. `source`, `filesystemtype` and `data` are ignored
+	let propagation = match mountflags & (MS_PRIVATE | MS_SLAVE | MS_SHARED) {
+		0 => None,
+		MS_PRIVATE => Some(Propagation::Private),
+		MS_SLAVE => Some(Propagation::Slave),
+		MS_SHARED => Some(Propagation::Shared),
+		_ => return Err(errno!(EINVAL)),
+	};
+	if let Some(propagation) = propagation {
+		let target = target.copy_from_user()?.ok_or(errno!(EFAULT))?;
+		let target = vfs::get_file_from_path(&PathBuf::try_from(target)?, &rs)?;
+		mountpoint::set_propagation(&target, propagation, mountflags & MS_REC != 0)?;
+		return Ok(0);
+	}
 	// Read arguments
 	let source_slice = source.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let mount_source = MountSource::new(&source_slice)?;
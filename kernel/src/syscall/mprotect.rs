@@ -27,6 +27,7 @@ use crate::{
 	sync::mutex::IntMutex,
 };
 use core::ffi::{c_int, c_void};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -34,6 +35,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x07d, id64 = 0x00a)]
 pub fn mprotect(
 	Args((addr, len, prot)): Args<(*mut c_void, usize, c_int)>,
 	mem_space: Arc<IntMutex<MemSpace>>,
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_getsetattr` system call retrieves and modifies the attributes of a POSIX message
+//! queue.
+
+use super::mq_open::get_queue;
+use crate::{
+	file::{fd::FileDescriptorTable, O_NONBLOCK},
+	ipc::{
+		mqueue,
+		mqueue::{CompatMqAttr, MqAttr},
+	},
+	process::mem_space::copy::SyscallPtr,
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::{
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+fn do_mq_getsetattr<A: Debug + From<MqAttr> + Into<MqAttr>>(
+	mqdes: c_int,
+	newattr: SyscallPtr<A>,
+	oldattr: SyscallPtr<A>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = get_queue(&fds, mqdes)?;
+	let queue = file.get_buffer::<mqueue::MsgQueue>().unwrap();
+	let flags = file.get_flags();
+	oldattr.copy_to_user(&queue.attr(flags & O_NONBLOCK).into())?;
+	// Only `O_NONBLOCK` can be changed
+	if let Some(attr) = newattr.copy_from_user()? {
+		let attr: MqAttr = attr.into();
+		let flags = (flags & !O_NONBLOCK) | (attr.mq_flags as c_int & O_NONBLOCK);
+		file.set_flags(flags, true);
+	}
+	Ok(0)
+}
+
+#[syscall(id64 = 0x0f5)]
+pub fn mq_getsetattr(
+	Args((mqdes, newattr, oldattr)): Args<(c_int, SyscallPtr<MqAttr>, SyscallPtr<MqAttr>)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_getsetattr(mqdes, newattr, oldattr, fds)
+}
+
+#[syscall(id32 = 0x11a)]
+pub fn compat_mq_getsetattr(
+	Args((mqdes, newattr, oldattr)): Args<(
+		c_int,
+		SyscallPtr<CompatMqAttr>,
+		SyscallPtr<CompatMqAttr>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_getsetattr(mqdes, newattr, oldattr, fds)
+}
@@ -0,0 +1,68 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_notify` system call registers the calling process to be notified when a message
+//! arrives in an empty POSIX message queue.
+
+use super::mq_open::get_queue;
+use crate::{
+	file::fd::FileDescriptorTable,
+	ipc::{mqueue, mqueue::Notification},
+	process::{
+		mem_space::copy::SyscallPtr,
+		signal::{SigEvent, Signal, SIGEV_NONE, SIGEV_SIGNAL},
+		Process,
+	},
+	sync::mutex::Mutex,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x119, id64 = 0x0f4)]
+pub fn mq_notify(
+	Args((mqdes, sevp)): Args<(c_int, SyscallPtr<SigEvent>)>,
+	proc: Arc<Process>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = get_queue(&fds, mqdes)?;
+	let notification = sevp
+		.copy_from_user()?
+		.map(|sevp| {
+			let signal = match sevp.sigev_notify {
+				SIGEV_NONE => None,
+				SIGEV_SIGNAL => Some(Signal::try_from(sevp.sigev_signo)?),
+				// TODO SIGEV_THREAD (requires netlink sockets)
+				_ => return Err(errno!(EINVAL)),
+			};
+			Ok(Notification {
+				pid: proc.get_pid(),
+				notify: sevp.sigev_notify,
+				signal,
+			})
+		})
+		.transpose()?;
+	let queue = file.get_buffer::<mqueue::MsgQueue>().unwrap();
+	queue.notify(proc.get_pid(), notification)?;
+	Ok(0)
+}
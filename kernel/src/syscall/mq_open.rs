@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_open` system call opens a POSIX message queue, creating it if necessary.
+
+use crate::{
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		perm::AccessProfile,
+		File, Mode, O_CREAT, O_EXCL, O_NONBLOCK, O_RDONLY, O_RDWR, O_WRONLY,
+	},
+	ipc::{
+		mqueue,
+		mqueue::{CompatMqAttr, MqAttr},
+	},
+	process::mem_space::copy::{SyscallPtr, SyscallString},
+	sync::mutex::Mutex,
+	syscall::{Args, Umask},
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Returns the open file description of the message queue `mqdes`.
+///
+/// If `mqdes` is not a valid file descriptor of a message queue, the function returns
+/// [`errno::EBADF`].
+pub(super) fn get_queue(fds: &Mutex<FileDescriptorTable>, mqdes: c_int) -> EResult<Arc<File>> {
+	let file = fds.lock().get_fd(mqdes)?.get_file().clone();
+	if file.get_buffer::<mqueue::MsgQueue>().is_none() {
+		return Err(errno!(EBADF));
+	}
+	Ok(file)
+}
+
+fn do_mq_open<A: Debug + Into<MqAttr>>(
+	name: SyscallString,
+	oflag: c_int,
+	mode: c_int,
+	attr: SyscallPtr<A>,
+	ap: AccessProfile,
+	umask: Umask,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let access = match oflag & 0b11 {
+		O_RDONLY => 0o4,
+		O_WRONLY => 0o2,
+		O_RDWR => 0o6,
+		_ => return Err(errno!(EINVAL)),
+	};
+	let name = name.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let attr = attr.copy_from_user()?.map(Into::into);
+	let mode = mode as Mode & !umask.0;
+	let queue = mqueue::open(
+		name,
+		oflag & O_CREAT != 0,
+		oflag & O_EXCL != 0,
+		access,
+		mode,
+		attr,
+		&ap,
+	)?;
+	let file = File::open_floating(queue, oflag & (0b11 | O_NONBLOCK))?;
+	let (id, _) = fds.lock().create_fd(FD_CLOEXEC, file)?;
+	Ok(id as _)
+}
+
+#[syscall(id64 = 0x0f0)]
+pub fn mq_open(
+	Args((name, oflag, mode, attr)): Args<(SyscallString, c_int, c_int, SyscallPtr<MqAttr>)>,
+	ap: AccessProfile,
+	umask: Umask,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_open(name, oflag, mode, attr, ap, umask, fds)
+}
+
+#[syscall(id32 = 0x115)]
+pub fn compat_mq_open(
+	Args((name, oflag, mode, attr)): Args<(SyscallString, c_int, c_int, SyscallPtr<CompatMqAttr>)>,
+	ap: AccessProfile,
+	umask: Umask,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_open(name, oflag, mode, attr, ap, umask, fds)
+}
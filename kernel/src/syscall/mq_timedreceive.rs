@@ -0,0 +1,96 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_timedreceive` system call receives the message with the highest priority from a POSIX
+//! message queue.
+
+use super::mq_open::get_queue;
+use crate::{
+	file::{fd::FileDescriptorTable, O_NONBLOCK},
+	ipc::mqueue,
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::Args,
+	time::unit::{TimeUnit, Timespec, Timespec32},
+};
+use core::{
+	ffi::{c_int, c_uint},
+	fmt::Debug,
+	intrinsics::unlikely,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+fn do_mq_timedreceive<T: Debug + TimeUnit>(
+	mqdes: c_int,
+	msg_ptr: SyscallSlice<u8>,
+	msg_len: usize,
+	msg_prio: SyscallPtr<c_uint>,
+	abs_timeout: SyscallPtr<T>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = get_queue(&fds, mqdes)?;
+	if unlikely(!file.can_read()) {
+		return Err(errno!(EBADF));
+	}
+	if unlikely(msg_ptr.0.is_none()) {
+		return Err(errno!(EFAULT));
+	}
+	let deadline = abs_timeout.copy_from_user()?.map(|ts| ts.to_nano());
+	let nonblock = file.get_flags() & O_NONBLOCK != 0;
+	let queue = file.get_buffer::<mqueue::MsgQueue>().unwrap();
+	let msg = queue.receive(msg_len, nonblock, deadline)?;
+	// TODO put the message back in the queue on failure
+	msg_ptr.copy_to_user(0, &msg.data)?;
+	msg_prio.copy_to_user(&msg.prio)?;
+	Ok(msg.data.len())
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x1a3, id64 = 0x0f3)]
+pub fn mq_timedreceive(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		SyscallPtr<c_uint>,
+		SyscallPtr<Timespec>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_timedreceive(mqdes, msg_ptr, msg_len, msg_prio, abs_timeout, fds)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x118)]
+pub fn compat_mq_timedreceive(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		SyscallPtr<c_uint>,
+		SyscallPtr<Timespec32>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_timedreceive(mqdes, msg_ptr, msg_len, msg_prio, abs_timeout, fds)
+}
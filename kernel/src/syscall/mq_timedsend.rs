@@ -0,0 +1,105 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_timedsend` system call sends a message to a POSIX message queue.
+
+use super::mq_open::get_queue;
+use crate::{
+	file::{fd::FileDescriptorTable, O_NONBLOCK},
+	ipc::{
+		mqueue,
+		mqueue::{Message, MQ_PRIO_MAX},
+	},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::Args,
+	time::unit::{TimeUnit, Timespec, Timespec32},
+};
+use core::{
+	ffi::{c_int, c_uint},
+	fmt::Debug,
+	intrinsics::unlikely,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+fn do_mq_timedsend<T: Debug + TimeUnit>(
+	mqdes: c_int,
+	msg_ptr: SyscallSlice<u8>,
+	msg_len: usize,
+	msg_prio: c_uint,
+	abs_timeout: SyscallPtr<T>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let file = get_queue(&fds, mqdes)?;
+	if unlikely(!file.can_write()) {
+		return Err(errno!(EBADF));
+	}
+	if unlikely(msg_prio >= MQ_PRIO_MAX) {
+		return Err(errno!(EINVAL));
+	}
+	let data = msg_ptr
+		.copy_from_user_vec(0, msg_len)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	let deadline = abs_timeout.copy_from_user()?.map(|ts| ts.to_nano());
+	let nonblock = file.get_flags() & O_NONBLOCK != 0;
+	let queue = file.get_buffer::<mqueue::MsgQueue>().unwrap();
+	queue.send(
+		Message {
+			prio: msg_prio,
+			data,
+		},
+		nonblock,
+		deadline,
+	)?;
+	Ok(0)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x1a2, id64 = 0x0f2)]
+pub fn mq_timedsend(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_uint,
+		SyscallPtr<Timespec>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_timedsend(mqdes, msg_ptr, msg_len, msg_prio, abs_timeout, fds)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x117)]
+pub fn compat_mq_timedsend(
+	Args((mqdes, msg_ptr, msg_len, msg_prio, abs_timeout)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_uint,
+		SyscallPtr<Timespec32>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_mq_timedsend(mqdes, msg_ptr, msg_len, msg_prio, abs_timeout, fds)
+}
@@ -0,0 +1,35 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mq_unlink` system call removes a POSIX message queue from the namespace.
+
+use crate::{
+	file::perm::AccessProfile, ipc::mqueue, process::mem_space::copy::SyscallString, syscall::Args,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x116, id64 = 0x0f1)]
+pub fn mq_unlink(Args(name): Args<SyscallString>, ap: AccessProfile) -> EResult<usize> {
+	let name = name.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	mqueue::unlink(&name, &ap)?;
+	Ok(0)
+}
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `mremap` system call allows the process to resize or move a mapping of memory.
+
+use crate::{
+	memory, memory::VirtAddr, process::mem_space::MemSpace, sync::mutex::IntMutex, syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely, num::NonZeroUsize};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	limits::PAGE_SIZE,
+	ptr::arc::Arc,
+};
+
+/// The mapping may be moved if it cannot be resized in place.
+const MREMAP_MAYMOVE: c_int = 1;
+/// The mapping is moved to the given address.
+const MREMAP_FIXED: c_int = 2;
+
+#[syscall(id32 = 0x0a3, id64 = 0x019)]
+pub fn mremap(
+	Args((old_address, old_size, new_size, flags, new_address)): Args<(
+		VirtAddr,
+		usize,
+		usize,
+		c_int,
+		VirtAddr,
+	)>,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	// Validation
+	if unlikely(flags & !(MREMAP_MAYMOVE | MREMAP_FIXED) != 0) {
+		return Err(errno!(EINVAL));
+	}
+	let may_move = flags & MREMAP_MAYMOVE != 0;
+	let fixed = flags & MREMAP_FIXED != 0;
+	if unlikely(fixed && !may_move) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(!old_address.is_aligned_to(PAGE_SIZE)) {
+		return Err(errno!(EINVAL));
+	}
+	let (Some(old_pages), Some(new_pages)) = (
+		NonZeroUsize::new(old_size.div_ceil(PAGE_SIZE)),
+		NonZeroUsize::new(new_size.div_ceil(PAGE_SIZE)),
+	) else {
+		return Err(errno!(EINVAL));
+	};
+	// Prevent from remapping kernel memory
+	let end = old_address.0.checked_add(old_pages.get() * PAGE_SIZE);
+	if unlikely(end.is_none_or(|end| end > memory::PROCESS_END.0)) {
+		return Err(errno!(EINVAL));
+	}
+	let new_address = fixed.then_some(new_address);
+	let addr = mem_space
+		.lock()
+		.remap(old_address, old_pages, new_pages, may_move, new_address)?;
+	Ok(addr as _)
+}
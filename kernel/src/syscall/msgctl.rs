@@ -0,0 +1,95 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgctl` system call performs control operations on a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		msg,
+		msg::{CompatMsqidDs, MsqidDs, MSGMNB},
+		IpcObject, IPC_64, IPC_RMID, IPC_SET, IPC_STAT,
+	},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+fn do_msgctl<S: Debug + From<MsqidDs> + Into<MsqidDs>>(
+	msqid: c_int,
+	cmd: c_int,
+	buf: SyscallPtr<S>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	match cmd & !IPC_64 {
+		IPC_STAT => {
+			let queue = msg::QUEUES.lock().get(msqid)?;
+			if !ap.can_access_ipc(&queue.perm().lock(), 0o4) {
+				return Err(errno!(EACCES));
+			}
+			if buf.0.is_none() {
+				return Err(errno!(EFAULT));
+			}
+			buf.copy_to_user(&queue.stat().into())?;
+		}
+		IPC_SET => {
+			let ds: MsqidDs = buf.copy_from_user()?.ok_or(errno!(EFAULT))?.into();
+			let queue = msg::QUEUES.lock().get(msqid)?;
+			if !ap.is_ipc_owner(&queue.perm().lock()) {
+				return Err(errno!(EPERM));
+			}
+			// Only a privileged user can raise the limit above the default
+			if ds.msg_qbytes as usize > MSGMNB && !ap.is_privileged() {
+				return Err(errno!(EPERM));
+			}
+			queue.set(&ds);
+		}
+		IPC_RMID => {
+			let mut queues = msg::QUEUES.lock();
+			let queue = queues.get(msqid)?;
+			if !ap.is_ipc_owner(&queue.perm().lock()) {
+				return Err(errno!(EPERM));
+			}
+			queues.remove(msqid);
+			queue.remove();
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
+
+#[syscall(id64 = 0x047)]
+pub fn msgctl(
+	Args((msqid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<MsqidDs>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_msgctl(msqid, cmd, buf, ap)
+}
+
+#[syscall(id32 = 0x192)]
+pub fn compat_msgctl(
+	Args((msqid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<CompatMsqidDs>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_msgctl(msqid, cmd, buf, ap)
+}
@@ -0,0 +1,40 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgget` system call returns the ID of a System V message queue, creating it if required.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{msg, msg::MsgQueue, Key},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::errno::EResult;
+
+#[syscall(id32 = 0x18f, id64 = 0x044)]
+pub fn msgget(Args((key, msgflg)): Args<(Key, c_int)>, ap: AccessProfile) -> EResult<usize> {
+	let id = msg::QUEUES.lock().get_or_create(
+		key,
+		msgflg,
+		&ap,
+		|_| Ok(()),
+		|perm| Ok(MsgQueue::new(perm)),
+	)?;
+	Ok(id as _)
+}
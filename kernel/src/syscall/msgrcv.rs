@@ -0,0 +1,102 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgrcv` system call receives a message from a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{msg, IpcObject},
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::{
+	cmp::min,
+	ffi::{c_int, c_long},
+	intrinsics::unlikely,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[allow(clippy::too_many_arguments)]
+fn do_msgrcv(
+	msqid: c_int,
+	msgp: SyscallSlice<u8>,
+	msgsz: usize,
+	msgtyp: c_long,
+	msgflg: c_int,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+	compat: bool,
+) -> EResult<usize> {
+	if unlikely(msgsz > isize::MAX as usize) {
+		return Err(errno!(EINVAL));
+	}
+	let queue = msg::QUEUES.lock().get(msqid)?;
+	if unlikely(!ap.can_access_ipc(&queue.perm().lock(), 0o4)) {
+		return Err(errno!(EACCES));
+	}
+	let msg = queue.receive(proc.get_pid(), msgtyp, msgsz, msgflg)?;
+	// Write the type, with the size of `long` on the userspace side
+	let off = if compat {
+		// The type is truncated if sent by a 64 bit process. Being little-endian, its low bits
+		// come first
+		msgp.copy_to_user(0, &msg.mtype.to_ne_bytes()[..size_of::<i32>()])?;
+		size_of::<i32>()
+	} else {
+		msgp.copy_to_user(0, &msg.mtype.to_ne_bytes())?;
+		size_of::<c_long>()
+	};
+	// If the message is too long, it is truncated
+	let len = min(msg.data.len(), msgsz);
+	msgp.copy_to_user(off, &msg.data[..len])?;
+	Ok(len)
+}
+
+#[syscall(id64 = 0x046)]
+pub fn msgrcv(
+	Args((msqid, msgp, msgsz, msgtyp, msgflg)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_long,
+		c_int,
+	)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_msgrcv(msqid, msgp, msgsz, msgtyp, msgflg, ap, proc, false)
+}
+
+#[syscall(id32 = 0x191)]
+pub fn compat_msgrcv(
+	Args((msqid, msgp, msgsz, msgtyp, msgflg)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_int,
+		c_int,
+	)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_msgrcv(msqid, msgp, msgsz, msgtyp as _, msgflg, ap, proc, true)
+}
@@ -0,0 +1,111 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `msgsnd` system call sends a message to a System V message queue.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		msg,
+		msg::{Message, MSGMAX},
+		IpcObject,
+	},
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::{
+	ffi::{c_int, c_long},
+	intrinsics::unlikely,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Reads the type of the message at `msgp`.
+///
+/// `compat` tells whether the type has the size of a 32 bit `long`.
+///
+/// On success, the function returns the type of the message along with its size in bytes.
+pub(super) fn read_type(msgp: &SyscallSlice<u8>, compat: bool) -> EResult<(c_long, usize)> {
+	let mut buf = [0; size_of::<c_long>()];
+	let len = if compat { 4 } else { size_of::<c_long>() };
+	if !msgp.copy_from_user(0, &mut buf[..len])? {
+		return Err(errno!(EFAULT));
+	}
+	let mtype = if compat {
+		i32::from_ne_bytes(buf[..4].try_into().unwrap()) as c_long
+	} else {
+		c_long::from_ne_bytes(buf)
+	};
+	Ok((mtype, len))
+}
+
+fn do_msgsnd(
+	msqid: c_int,
+	msgp: SyscallSlice<u8>,
+	msgsz: usize,
+	msgflg: c_int,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+	compat: bool,
+) -> EResult<usize> {
+	if unlikely(msgsz > MSGMAX) {
+		return Err(errno!(EINVAL));
+	}
+	let queue = msg::QUEUES.lock().get(msqid)?;
+	if unlikely(!ap.can_access_ipc(&queue.perm().lock(), 0o2)) {
+		return Err(errno!(EACCES));
+	}
+	let (mtype, off) = read_type(&msgp, compat)?;
+	if unlikely(mtype < 1) {
+		return Err(errno!(EINVAL));
+	}
+	let data = msgp
+		.copy_from_user_vec(off, msgsz)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	queue.send(
+		proc.get_pid(),
+		Message {
+			mtype,
+			data,
+		},
+		msgflg,
+	)?;
+	Ok(0)
+}
+
+#[syscall(id64 = 0x045)]
+pub fn msgsnd(
+	Args((msqid, msgp, msgsz, msgflg)): Args<(c_int, SyscallSlice<u8>, usize, c_int)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_msgsnd(msqid, msgp, msgsz, msgflg, ap, proc, false)
+}
+
+#[syscall(id32 = 0x190)]
+pub fn compat_msgsnd(
+	Args((msqid, msgp, msgsz, msgflg)): Args<(c_int, SyscallSlice<u8>, usize, c_int)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_msgsnd(msqid, msgp, msgsz, msgflg, ap, proc, true)
+}
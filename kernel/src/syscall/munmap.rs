@@ -27,6 +27,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{ffi::c_void, intrinsics::unlikely, num::NonZeroUsize};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -34,6 +35,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x05b, id64 = 0x00b)]
 pub fn munmap(
 	Args((addr, length)): Args<(VirtAddr, usize)>,
 	mem_space: Arc<IntMutex<MemSpace>>,
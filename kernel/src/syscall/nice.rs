@@ -0,0 +1,37 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `nice` system call adds an increment to the nice value of the current process.
+
+use crate::{
+	process::Process,
+	syscall::{setpriority::set_nice, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x022)]
+pub fn nice(Args(inc): Args<c_int>, proc: Arc<Process>) -> EResult<usize> {
+	let nice = (proc.nice() as c_int).saturating_add(inc);
+	set_nice(&proc, nice)?;
+	Ok(0)
+}
@@ -34,14 +34,16 @@ use crate::{
 	time::clock::current_time_ns,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x005, id64 = 0x002)]
 pub fn open(
 	Args((pathname, flags, mode)): Args<(SyscallString, c_int, file::Mode)>,
 ) -> EResult<usize> {
-	do_openat(AT_FDCWD, pathname, flags, mode)
+	do_openat(AT_FDCWD, pathname, flags, mode, 0)
 }
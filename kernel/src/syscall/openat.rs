@@ -25,14 +25,22 @@ use crate::{
 		perm::AccessProfile,
 		vfs,
 		vfs::{ResolutionSettings, Resolved},
-		File, FileType, Stat, O_CLOEXEC, O_CREAT, O_DIRECTORY, O_EXCL, O_NOCTTY, O_NOFOLLOW,
-		O_RDONLY, O_RDWR, O_TRUNC, O_WRONLY,
+		File, FileType, Stat, O_CLOEXEC, O_CREAT, O_DIRECTORY, O_EXCL, O_NOFOLLOW, O_RDONLY,
+		O_RDWR, O_TRUNC, O_WRONLY,
 	},
 	process::{mem_space::copy::SyscallString, Process},
-	syscall::{util::at, Args},
+	syscall::{
+		openat2::{
+			RESOLVE_BENEATH, RESOLVE_IN_ROOT, RESOLVE_NO_MAGICLINKS, RESOLVE_NO_SYMLINKS,
+			RESOLVE_NO_XDEV,
+		},
+		util::{at, at::AT_FDCWD},
+		Args,
+	},
 	time::clock::{current_time_ns, current_time_sec, Clock},
 };
 use core::{ffi::c_int, ops::Deref};
+use macros::syscall;
 use utils::{
 	collections::path::{Path, PathBuf},
 	errno,
@@ -49,6 +57,7 @@ use utils::{
 /// - `pathname` the path relative to the directory.
 /// - `flags` is a set of open file flags.
 /// - `mode` is the set of permissions to use if the file needs to be created.
+/// - `resolve` is the set of `RESOLVE_*` flags restricting the resolution.
 ///
 /// If the file doesn't exist and the `O_CREAT` flag is set, the file is created,
 /// then the function returns it.
@@ -61,9 +70,25 @@ fn get_file(
 	dirfd: c_int,
 	path: Option<&Path>,
 	flags: c_int,
-	rs: ResolutionSettings,
+	mut rs: ResolutionSettings,
 	mode: file::Mode,
+	resolve: u64,
 ) -> EResult<Arc<vfs::Entry>> {
+	// The resolution cannot go above the starting directory
+	if resolve & (RESOLVE_BENEATH | RESOLVE_IN_ROOT) != 0 {
+		if dirfd != AT_FDCWD {
+			let dir = fds
+				.get_fd(dirfd)?
+				.get_file()
+				.vfs_entry
+				.clone()
+				.ok_or_else(|| errno!(ENOTDIR))?;
+			rs.cwd = Some(dir);
+		}
+		if let Some(cwd) = &rs.cwd {
+			rs.root = cwd.clone();
+		}
+	}
 	let resolved = at::get_file(fds, rs.clone(), dirfd, path, flags)?;
 	match resolved {
 		Resolved::Found(file) => Ok(file),
@@ -89,17 +114,26 @@ fn get_file(
 }
 
 /// Perform the `openat` system call.
+///
+/// `resolve` is the set of `RESOLVE_*` flags passed to `openat2`.
 pub fn do_openat(
 	dirfd: c_int,
 	pathname: SyscallString,
 	flags: c_int,
 	mode: file::Mode,
+	resolve: u64,
 ) -> EResult<usize> {
 	let (rs, pathname, fds_mutex, mode) = {
 		let proc = Process::current();
 		let follow_link = flags & O_NOFOLLOW == 0;
+		// Magic links could point outside of the starting directory
+		let no_magiclinks = RESOLVE_NO_MAGICLINKS | RESOLVE_BENEATH | RESOLVE_IN_ROOT;
 		let rs = ResolutionSettings {
 			create: flags & O_CREAT != 0,
+			beneath: resolve & RESOLVE_BENEATH != 0,
+			no_symlinks: resolve & RESOLVE_NO_SYMLINKS != 0,
+			no_magiclinks: resolve & no_magiclinks != 0,
+			no_xdev: resolve & RESOLVE_NO_XDEV != 0,
 			..ResolutionSettings::for_process(&proc, follow_link)
 		};
 		let pathname = pathname
@@ -114,7 +148,15 @@ pub fn do_openat(
 	let mut fds = fds_mutex.lock();
 
 	// Get file
-	let file = get_file(&fds, dirfd, Some(&pathname), flags, rs.clone(), mode)?;
+	let file = get_file(
+		&fds,
+		dirfd,
+		Some(&pathname),
+		flags,
+		rs.clone(),
+		mode,
+		resolve,
+	)?;
 	// Check permissions
 	let (read, write) = match flags & 0b11 {
 		O_RDONLY => (true, false),
@@ -135,8 +177,8 @@ pub fn do_openat(
 		return Err(errno!(ENOTDIR));
 	}
 	// Open file
-	const FLAGS_MASK: i32 =
-		!(O_CLOEXEC | O_CREAT | O_DIRECTORY | O_EXCL | O_NOCTTY | O_NOFOLLOW | O_TRUNC);
+	// `O_NOCTTY` is kept for TTYs to know whether they shall become the controlling terminal
+	const FLAGS_MASK: i32 = !(O_CLOEXEC | O_CREAT | O_DIRECTORY | O_EXCL | O_NOFOLLOW | O_TRUNC);
 	let file = File::open_entry(file, flags & FLAGS_MASK)?;
 	// Truncate if necessary
 	if flags & O_TRUNC != 0 && file_type == Some(FileType::Regular) {
@@ -151,8 +193,9 @@ pub fn do_openat(
 	Ok(fd_id as _)
 }
 
+#[syscall(id32 = 0x127, id64 = 0x101)]
 pub fn openat(
 	Args((dirfd, pathname, flags, mode)): Args<(c_int, SyscallString, c_int, file::Mode)>,
 ) -> EResult<usize> {
-	do_openat(dirfd, pathname, flags, mode)
+	do_openat(dirfd, pathname, flags, mode, 0)
 }
@@ -0,0 +1,128 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `openat2` system call is an extension of `openat`, allowing to restrict the resolution of
+//! the path.
+
+use crate::{
+	file::{
+		O_APPEND, O_ASYNC, O_CLOEXEC, O_CREAT, O_DIRECT, O_DIRECTORY, O_EXCL, O_LARGEFILE,
+		O_NOATIME, O_NOCTTY, O_NOFOLLOW, O_NONBLOCK, O_RDWR, O_SYNC, O_TRUNC, O_WRONLY,
+	},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice, SyscallString},
+	syscall::{openat::do_openat, Args},
+};
+use core::{ffi::c_int, intrinsics::unlikely, mem::size_of};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	limits::PAGE_SIZE,
+};
+
+/// Resolution flag: fail if the resolution crosses a mountpoint.
+pub const RESOLVE_NO_XDEV: u64 = 0x01;
+/// Resolution flag: fail on magic links, such as the ones in `/proc/<pid>/ns/`.
+pub const RESOLVE_NO_MAGICLINKS: u64 = 0x02;
+/// Resolution flag: fail on any symbolic link.
+pub const RESOLVE_NO_SYMLINKS: u64 = 0x04;
+/// Resolution flag: fail if the resolution would leave the directory it starts from.
+pub const RESOLVE_BENEATH: u64 = 0x08;
+/// Resolution flag: resolve the path as if the directory it starts from was the root directory.
+pub const RESOLVE_IN_ROOT: u64 = 0x10;
+/// Resolution flag: only use cached entries for the resolution.
+pub const RESOLVE_CACHED: u64 = 0x20;
+
+/// The open flags `openat2` accepts.
+const VALID_FLAGS: c_int = O_WRONLY
+	| O_RDWR
+	| O_APPEND
+	| O_ASYNC
+	| O_CLOEXEC
+	| O_CREAT
+	| O_DIRECT
+	| O_DIRECTORY
+	| O_EXCL
+	| O_LARGEFILE
+	| O_NOATIME
+	| O_NOCTTY
+	| O_NOFOLLOW
+	| O_NONBLOCK
+	| O_SYNC
+	| O_TRUNC;
+
+/// How to open a file with `openat2`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct OpenHow {
+	/// The open flags.
+	flags: u64,
+	/// The permissions of the file, if created.
+	mode: u64,
+	/// The set of `RESOLVE_*` flags.
+	resolve: u64,
+}
+
+#[syscall(id32 = 0x1b5, id64 = 0x1b5)]
+pub fn openat2(
+	Args((dirfd, pathname, how, size)): Args<(c_int, SyscallString, SyscallPtr<OpenHow>, usize)>,
+) -> EResult<usize> {
+	// Check the size of the structure, which may be extended in the future
+	if unlikely(size < size_of::<OpenHow>()) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(size > PAGE_SIZE) {
+		return Err(errno!(E2BIG));
+	}
+	if size > size_of::<OpenHow>() {
+		// Fields unknown to the kernel must be zero
+		let ext = SyscallSlice::<u8>(how.0.map(|p| p.cast()))
+			.copy_from_user_vec(size_of::<OpenHow>(), size - size_of::<OpenHow>())?
+			.ok_or_else(|| errno!(EFAULT))?;
+		if unlikely(ext.iter().any(|b| *b != 0)) {
+			return Err(errno!(E2BIG));
+		}
+	}
+	let how = how.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// Contrary to `openat`, invalid flags are rejected
+	let flags = c_int::try_from(how.flags).map_err(|_| errno!(EINVAL))?;
+	if unlikely(flags & !VALID_FLAGS != 0) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(how.mode & !0o7777 != 0 || (how.mode != 0 && flags & O_CREAT == 0)) {
+		return Err(errno!(EINVAL));
+	}
+	let valid_resolve = RESOLVE_NO_XDEV
+		| RESOLVE_NO_MAGICLINKS
+		| RESOLVE_NO_SYMLINKS
+		| RESOLVE_BENEATH
+		| RESOLVE_IN_ROOT
+		| RESOLVE_CACHED;
+	if unlikely(how.resolve & !valid_resolve != 0) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(how.resolve & RESOLVE_BENEATH != 0 && how.resolve & RESOLVE_IN_ROOT != 0) {
+		return Err(errno!(EINVAL));
+	}
+	// Resolution is not guaranteed to be served from the cache. The caller is expected to retry
+	// without the flag
+	if unlikely(how.resolve & RESOLVE_CACHED != 0) {
+		return Err(errno!(EAGAIN));
+	}
+	do_openat(dirfd, pathname, flags, how.mode as _, how.resolve)
+}
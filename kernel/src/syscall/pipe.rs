@@ -26,8 +26,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{boxed::Box, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x02a, id64 = 0x016)]
 pub fn pipe(
 	Args(pipefd): Args<SyscallPtr<[c_int; 2]>>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -35,7 +37,7 @@ pub fn pipe(
 	let ops = Arc::new(PipeBuffer::new()?)?;
 	let file0 = File::open_floating(ops.clone(), file::O_RDONLY)?;
 	let file1 = File::open_floating(ops, file::O_WRONLY)?;
-	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(file0, file1)?;
+	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(0, file0, file1)?;
 	pipefd.copy_to_user(&[fd0_id as _, fd1_id as _])?;
 	Ok(0)
 }
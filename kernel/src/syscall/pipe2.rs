@@ -20,12 +20,17 @@
 
 use crate::{
 	file,
-	file::{fd::FileDescriptorTable, pipe::PipeBuffer, vfs, File},
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		pipe::PipeBuffer,
+		vfs, File,
+	},
 	process::{mem_space::copy::SyscallPtr, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	boxed::Box,
 	errno,
@@ -33,6 +38,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x14b, id64 = 0x125)]
 pub fn pipe2(
 	Args((pipefd, flags)): Args<(SyscallPtr<[c_int; 2]>, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -45,7 +51,12 @@ pub fn pipe2(
 	let ops = Arc::new(PipeBuffer::new()?)?;
 	let file0 = File::open_floating(ops.clone(), flags | file::O_RDONLY)?;
 	let file1 = File::open_floating(ops, flags | file::O_WRONLY)?;
-	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(file0, file1)?;
+	let fd_flags = if flags & file::O_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(fd_flags, file0, file1)?;
 	pipefd.copy_to_user(&[fd0_id as _, fd1_id as _])?;
 	Ok(0)
 }
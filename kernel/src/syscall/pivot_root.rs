@@ -0,0 +1,111 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pivot_root` system call allows to change the root filesystem.
+
+use crate::{
+	file::{
+		vfs,
+		vfs::{mountpoint, mountpoint::MountNamespace, ResolutionSettings},
+		FileType,
+	},
+	process::{mem_space::copy::SyscallString, scheduler::SCHEDULER, Process},
+	syscall::Args,
+};
+use core::ptr;
+use macros::syscall;
+use utils::{
+	collections::path::PathBuf,
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+	TryClone,
+};
+
+/// Tells whether `a` and `b` are the same entry.
+fn is_same(a: &Arc<vfs::Entry>, b: &Arc<vfs::Entry>) -> bool {
+	ptr::eq(Arc::as_ptr(a), Arc::as_ptr(b))
+}
+
+#[syscall(id32 = 0x0d9, id64 = 0x09b)]
+pub fn pivot_root(
+	Args((new_root, put_old)): Args<(SyscallString, SyscallString)>,
+	proc: Arc<Process>,
+	rs: ResolutionSettings,
+) -> EResult<usize> {
+	if !rs.access_profile.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	let new_root = new_root.copy_from_user()?.ok_or(errno!(EFAULT))?;
+	let new_root = vfs::get_file_from_path(&PathBuf::try_from(new_root)?, &rs)?;
+	let put_old = put_old.copy_from_user()?.ok_or(errno!(EFAULT))?;
+	let put_old = vfs::get_file_from_path(&PathBuf::try_from(put_old)?, &rs)?;
+	if new_root.get_type()? != FileType::Directory || put_old.get_type()? != FileType::Directory {
+		return Err(errno!(ENOTDIR));
+	}
+	let old_root = rs.root;
+	if is_same(&new_root, &old_root) {
+		return Err(errno!(EBUSY));
+	}
+	// Both roots must be mountpoints
+	let old_mp = mountpoint::from_entry(&old_root).ok_or_else(|| errno!(EINVAL))?;
+	if mountpoint::from_entry(&new_root).is_none() {
+		return Err(errno!(EINVAL));
+	}
+	// `put_old` must be at or underneath `new_root`
+	let mut cur = Some(&put_old);
+	while let Some(ent) = cur.filter(|ent| !is_same(ent, &new_root)) {
+		cur = ent.parent.as_ref();
+	}
+	if cur.is_none() {
+		return Err(errno!(EINVAL));
+	}
+	// Make the old root filesystem accessible at `put_old`. Mountpoints underneath the old root
+	// are not carried over
+	mountpoint::attach(
+		old_mp.source.try_clone()?,
+		old_mp.fs.clone(),
+		old_mp.flags,
+		Some(put_old),
+	)?;
+	let mnt_ns = proc.fs.lock().mnt_ns.clone();
+	// Switch the root and working directory of processes of the namespace that were using the old
+	// root
+	{
+		let sched = SCHEDULER.lock();
+		for (_, proc) in sched.iter_process() {
+			let mut fs = proc.fs.lock();
+			if !ptr::eq(Arc::as_ptr(&fs.mnt_ns), Arc::as_ptr(&mnt_ns)) {
+				continue;
+			}
+			if is_same(&fs.chroot, &old_root) {
+				fs.chroot = new_root.clone();
+			}
+			if is_same(&fs.cwd, &old_root) {
+				fs.cwd = new_root.clone();
+			}
+		}
+	}
+	if is_same(&mnt_ns.root(), &old_root) {
+		mnt_ns.set_root(new_root.clone());
+		if ptr::eq(Arc::as_ptr(&mnt_ns), Arc::as_ptr(MountNamespace::init_ns())) {
+			vfs::set_root(new_root);
+		}
+	}
+	Ok(0)
+}
@@ -20,18 +20,22 @@
 //! descriptors.
 
 use crate::{
-	process::{mem_space::copy::SyscallSlice, scheduler, scheduler::Scheduler, Process},
+	file::{fd::FileDescriptorTable, wait_queue, File},
+	process::mem_space::copy::SyscallSlice,
+	sync::mutex::Mutex,
 	syscall::Args,
 	time::{
-		clock,
-		clock::{current_time_ms, Clock},
+		clock::{current_time_ns, Clock},
 		unit::Timestamp,
 	},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
+	limits::OPEN_MAX,
+	ptr::arc::Arc,
 };
 
 /// Poll event: There is data to read.
@@ -60,7 +64,7 @@ pub const POLLRDHUP: u32 = 0x2000;
 
 /// A file descriptor passed to the `poll` system call.
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct PollFD {
 	/// The file descriptor.
 	fd: i32,
@@ -70,66 +74,73 @@ pub struct PollFD {
 	revents: i16,
 }
 
-pub(super) fn poll(
-	Args((fds, nfds, timeout)): Args<(SyscallSlice<PollFD>, usize, c_int)>,
+/// The events reported for files that do not support polling, which are always ready.
+const DEFAULT_MASK: u32 = POLLIN | POLLOUT | POLLRDNORM | POLLWRNORM;
+
+/// Returns the events that occurred on `file` among those in `mask`.
+///
+/// Files that do not support polling are considered always ready for reading and writing.
+pub(super) fn poll_file(file: &File, mask: u32) -> EResult<u32> {
+	match file.ops.poll(file, mask) {
+		Ok(events) => Ok(events),
+		Err(e) if e.as_int() == errno::EINVAL => Ok(DEFAULT_MASK & mask),
+		Err(e) => Err(e),
+	}
+}
+
+/// Waits for events on the file descriptors in `fds`.
+///
+/// Arguments:
+/// - `fds` is the list of file descriptors along with the requested events
+/// - `nfds` is the number of elements in `fds`
+/// - `deadline` is the timestamp in nanoseconds on the monotonic clock at which the function
+///   returns if no event occurred. If `None`, the function waits indefinitely
+/// - `fds_table` is the file descriptors table of the process
+pub(super) fn do_poll(
+	fds: SyscallSlice<PollFD>,
+	nfds: usize,
+	deadline: Option<Timestamp>,
+	fds_table: &Mutex<FileDescriptorTable>,
 ) -> EResult<usize> {
-	// The timeout. `None` means no timeout
-	let to = (timeout >= 0).then_some(timeout as Timestamp);
-	let start_ts = current_time_ms(Clock::Monotonic);
-	loop {
-		// Check whether the system call timed out
-		if let Some(timeout) = to {
-			let now = current_time_ms(Clock::Monotonic);
-			if now >= start_ts + timeout {
-				return Ok(0);
+	if nfds > OPEN_MAX as usize {
+		return Err(errno!(EINVAL));
+	}
+	let mut fds_arr = fds
+		.copy_from_user_vec(0, nfds)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	let count = wait_queue::poll_wait(deadline, || {
+		let fds_table = fds_table.lock();
+		let mut count = 0;
+		for fd in fds_arr.iter_mut() {
+			fd.revents = 0;
+			// Negative file descriptors are ignored
+			if fd.fd < 0 {
+				continue;
 			}
-		}
-		{
-			let fds_arr = fds
-				.copy_from_user_vec(0, nfds)?
-				.ok_or_else(|| errno!(EFAULT))?;
-			// Check the file descriptors list
-			for fd in &fds_arr {
-				if fd.events as u32 & POLLIN != 0 {
-					// TODO
-					todo!();
-				}
-				if fd.events as u32 & POLLPRI != 0 {
-					// TODO
-					todo!();
-				}
-				if fd.events as u32 & POLLOUT != 0 {
-					// TODO
-					todo!();
-				}
-				if fd.events as u32 & POLLRDNORM != 0 {
-					// TODO
-					todo!();
+			let revents = match fds_table.get_fd(fd.fd) {
+				Ok(fd_entry) => {
+					// Errors are reported even if not requested
+					let mask = fd.events as u16 as u32 | POLLERR | POLLHUP;
+					poll_file(fd_entry.get_file(), mask)? & mask
 				}
-				if fd.events as u32 & POLLRDBAND != 0 {
-					// TODO
-					todo!();
-				}
-				if fd.events as u32 & POLLWRNORM != 0 {
-					// TODO
-					todo!();
-				}
-				if fd.events as u32 & POLLWRBAND != 0 {
-					// TODO
-					todo!();
-				}
-			}
-			// The number of file descriptor with at least one event
-			let fd_event_count = fds_arr.iter().filter(|fd| fd.revents != 0).count();
-			// If at least on event happened, return the number of file descriptors
-			// concerned
-			if fd_event_count > 0 {
-				fds.copy_to_user(0, &fds_arr)?;
-				return Ok(fd_event_count as _);
-			}
+				Err(_) => POLLNVAL,
+			};
+			fd.revents = revents as _;
+			count += (revents != 0) as usize;
 		}
-		// TODO Make process sleep until an event occurs on a file descriptor in
-		// `fds`
-		Scheduler::tick();
-	}
+		Ok((count > 0).then_some(count))
+	})?;
+	fds.copy_to_user(0, &fds_arr)?;
+	Ok(count.unwrap_or(0))
+}
+
+#[syscall(id32 = 0x0a8, id64 = 0x007)]
+pub(super) fn poll(
+	Args((fds, nfds, timeout)): Args<(SyscallSlice<PollFD>, usize, c_int)>,
+	fds_table: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	// A negative timeout means waiting indefinitely
+	let deadline = (timeout >= 0)
+		.then(|| current_time_ns(Clock::Monotonic).saturating_add(timeout as u64 * 1_000_000));
+	do_poll(fds, nfds, deadline, &fds_table)
 }
@@ -0,0 +1,95 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `ppoll` system call is like `poll`, but takes a timeout with a nanosecond precision and
+//! allows to change the mask of blocked signals while waiting.
+
+use super::poll::{do_poll, PollFD};
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::{
+		mem_space::copy::{SyscallPtr, SyscallSlice},
+		signal::SigSet,
+		Process,
+	},
+	sync::mutex::Mutex,
+	syscall::{util::with_sigmask, Args},
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::{TimeUnit, Timespec, Timespec32},
+	},
+};
+use core::{fmt::Debug, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+fn do_ppoll<T: Debug + TimeUnit>(
+	fds: SyscallSlice<PollFD>,
+	nfds: usize,
+	timeout: SyscallPtr<T>,
+	sigmask: SyscallPtr<SigSet>,
+	sigsetsize: usize,
+	proc: Arc<Process>,
+	fds_table: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	// No timeout means waiting indefinitely
+	let deadline = timeout
+		.copy_from_user()?
+		.map(|t| current_time_ns(Clock::Monotonic).saturating_add(t.to_nano()));
+	let sigmask = sigmask.copy_from_user()?;
+	if unlikely(sigmask.is_some() && sigsetsize != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	with_sigmask(&proc, sigmask, || do_poll(fds, nfds, deadline, &fds_table))
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x135)]
+pub fn compat_ppoll(
+	Args((fds, nfds, timeout, sigmask, sigsetsize)): Args<(
+		SyscallSlice<PollFD>,
+		usize,
+		SyscallPtr<Timespec32>,
+		SyscallPtr<SigSet>,
+		usize,
+	)>,
+	proc: Arc<Process>,
+	fds_table: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_ppoll(fds, nfds, timeout, sigmask, sigsetsize, proc, fds_table)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id64 = 0x10f)]
+pub fn ppoll(
+	Args((fds, nfds, timeout, sigmask, sigsetsize)): Args<(
+		SyscallSlice<PollFD>,
+		usize,
+		SyscallPtr<Timespec>,
+		SyscallPtr<SigSet>,
+		usize,
+	)>,
+	proc: Arc<Process>,
+	fds_table: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_ppoll(fds, nfds, timeout, sigmask, sigsetsize, proc, fds_table)
+}
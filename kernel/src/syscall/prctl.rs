@@ -0,0 +1,86 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `prctl` system call manipulates various aspects of the behaviour of the calling thread.
+
+use crate::{
+	arch::x86::{idt::IntFrame, FLAG_AC},
+	process::{mem_space::copy::SyscallPtr, Process},
+	syscall::{Args, FromSyscallArg},
+};
+use core::{
+	ffi::{c_int, c_uint, c_ulong},
+	sync::atomic::Ordering::Relaxed,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Returns whether the process is dumpable.
+const PR_GET_DUMPABLE: c_int = 3;
+/// Sets whether the process is dumpable.
+const PR_SET_DUMPABLE: c_int = 4;
+
+/// Returns the handling of unaligned memory accesses.
+const PR_GET_UNALIGN: c_int = 5;
+/// Sets the handling of unaligned memory accesses.
+const PR_SET_UNALIGN: c_int = 6;
+
+/// Unaligned memory accesses are silently allowed.
+const PR_UNALIGN_NOPRINT: c_uint = 1;
+/// Unaligned memory accesses raise [`crate::process::signal::Signal::SIGBUS`].
+const PR_UNALIGN_SIGBUS: c_uint = 2;
+
+#[syscall(id32 = 0x0ac, id64 = 0x09d)]
+pub fn prctl(
+	Args((option, arg2, _arg3, _arg4, _arg5)): Args<(c_int, c_ulong, c_ulong, c_ulong, c_ulong)>,
+	proc: Arc<Process>,
+	frame: &mut IntFrame,
+) -> EResult<usize> {
+	match option {
+		PR_GET_DUMPABLE => return Ok(proc.dumpable.load(Relaxed) as _),
+		PR_SET_DUMPABLE => match arg2 {
+			0 | 1 => proc.dumpable.store(arg2 != 0, Relaxed),
+			_ => return Err(errno!(EINVAL)),
+		},
+		// Alignment checks are enabled by the AC flag, which is saved along with the registers of
+		// the thread
+		PR_GET_UNALIGN => {
+			let val = if frame.rflags as usize & FLAG_AC != 0 {
+				PR_UNALIGN_SIGBUS
+			} else {
+				PR_UNALIGN_NOPRINT
+			};
+			SyscallPtr::<c_uint>::from_ptr(arg2 as _).copy_to_user(&val)?;
+		}
+		PR_SET_UNALIGN => {
+			let flags = frame.rflags as usize;
+			let flags = match arg2 as c_uint {
+				PR_UNALIGN_NOPRINT => flags & !FLAG_AC,
+				PR_UNALIGN_SIGBUS => flags | FLAG_AC,
+				_ => return Err(errno!(EINVAL)),
+			};
+			frame.rflags = flags as _;
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
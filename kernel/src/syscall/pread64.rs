@@ -0,0 +1,102 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pread64` system call allows to read the content of an open file at a given offset,
+//! without changing the file's current offset.
+
+use super::Args;
+use crate::{
+	file::{fd::FileDescriptorTable, File, FileType},
+	process::mem_space::copy::SyscallSlice,
+	sync::mutex::Mutex,
+};
+use core::{cmp::min, ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+	vec,
+};
+
+/// Returns the open file of `fd`, checking it can be accessed at an arbitrary offset.
+///
+/// If the file is not seekable, the function returns [`errno::ESPIPE`].
+pub(super) fn get_seekable_file(
+	fds: &Mutex<FileDescriptorTable>,
+	fd: c_int,
+	offset: i64,
+) -> EResult<Arc<File>> {
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	// Pipes and sockets have no node
+	if unlikely(file.node().is_none()) {
+		return Err(errno!(ESPIPE));
+	}
+	match file.get_type()? {
+		FileType::Fifo => return Err(errno!(ESPIPE)),
+		FileType::Link => return Err(errno!(EINVAL)),
+		_ => {}
+	}
+	if unlikely(offset < 0) {
+		return Err(errno!(EINVAL));
+	}
+	Ok(file)
+}
+
+/// Performs the `pread64` system call.
+fn do_pread(
+	fd: c_int,
+	buf: SyscallSlice<u8>,
+	count: usize,
+	offset: i64,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	let file = get_seekable_file(fds, fd, offset)?;
+	let count = min(count, i32::MAX as usize);
+	if count == 0 {
+		return Ok(0);
+	}
+	// TODO perf: a buffer is not necessarily required
+	let mut buffer = vec![0u8; count]?;
+	let len = file.ops.read(&file, offset as u64, &mut buffer)?;
+	buf.copy_to_user(0, &buffer[..len])?;
+	Ok(len)
+}
+
+#[syscall(id64 = 0x011)]
+pub fn pread64(
+	Args((fd, buf, count, offset)): Args<(c_int, SyscallSlice<u8>, usize, i64)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_pread(fd, buf, count, offset, &fds)
+}
+
+#[syscall(id32 = 0x0b4)]
+pub fn compat_pread64(
+	Args((fd, buf, count, offset_low, offset_high)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		u32,
+		u32,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let offset = (((offset_high as u64) << 32) | offset_low as u64) as i64;
+	do_pread(fd, buf, count, offset, &fds)
+}
@@ -28,8 +28,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x14d, id64 = 0x127)]
 pub fn preadv(
 	Args((fd, iov, iovcnt, offset_low, offset_high)): Args<(
 		c_int,
@@ -28,8 +28,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x17a, id64 = 0x147)]
 pub fn preadv2(
 	Args((fd, iov, iovcnt, offset, flags)): Args<(c_int, SyscallIOVec, c_int, isize, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -28,6 +28,7 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -89,6 +90,7 @@ pub struct RLimit {
 	rlim_max: RLim,
 }
 
+#[syscall(id32 = 0x154, id64 = 0x12e)]
 pub fn prlimit64(
 	Args((pid, resource, _new_limit, _old_limit)): Args<(
 		Pid,
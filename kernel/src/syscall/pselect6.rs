@@ -18,21 +18,79 @@
 
 //! `pselect6` is similar to `select`.
 
-use super::select::{do_select, FDSet};
+use super::select::{do_select, get_deadline, FDSet};
 use crate::{
 	file::fd::FileDescriptorTable,
-	process::mem_space::{
-		copy::{SyscallPtr, SyscallSlice},
-		MemSpace,
-	},
+	process::{mem_space::copy::SyscallPtr, signal::SigSet, Process},
 	sync::mutex::Mutex,
-	syscall::Args,
-	time::unit::Timespec,
+	syscall::{util::with_sigmask, Args, FromSyscallArg},
+	time::unit::{TimeUnit, Timespec, Timespec32},
 };
-use core::ffi::c_int;
-use utils::{errno::EResult, ptr::arc::Arc};
+use core::{ffi::c_int, fmt::Debug, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Reads the signal mask from the structure passed as the last argument of `pselect6`.
+///
+/// The structure contains a pointer to the mask and the size of the mask. `sigmask_arg` is the
+/// structure, read as an array of two words of the size of userspace pointers.
+fn get_sigmask(sigmask_arg: Option<[usize; 2]>) -> EResult<Option<SigSet>> {
+	let Some([ptr, size]) = sigmask_arg else {
+		return Ok(None);
+	};
+	let sigmask = SyscallPtr::<SigSet>::from_ptr(ptr).copy_from_user()?;
+	if unlikely(sigmask.is_some() && size != size_of::<SigSet>()) {
+		return Err(errno!(EINVAL));
+	}
+	Ok(sigmask)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn do_pselect6<T: Debug + TimeUnit>(
+	nfds: c_int,
+	readfds: SyscallPtr<FDSet>,
+	writefds: SyscallPtr<FDSet>,
+	exceptfds: SyscallPtr<FDSet>,
+	timeout: SyscallPtr<T>,
+	sigmask: Option<[usize; 2]>,
+	proc: Arc<Process>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let deadline = get_deadline(timeout)?;
+	let sigmask = get_sigmask(sigmask)?;
+	with_sigmask(&proc, sigmask, || {
+		do_select(&fds, nfds, readfds, writefds, exceptfds, deadline)
+	})
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x134)]
+pub fn compat_pselect6(
+	Args((nfds, readfds, writefds, exceptfds, timeout, sigmask)): Args<(
+		c_int,
+		SyscallPtr<FDSet>,
+		SyscallPtr<FDSet>,
+		SyscallPtr<FDSet>,
+		SyscallPtr<Timespec32>,
+		SyscallPtr<[u32; 2]>,
+	)>,
+	proc: Arc<Process>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let sigmask = sigmask
+		.copy_from_user()?
+		.map(|[ptr, size]| [ptr as usize, size as usize]);
+	do_pselect6(
+		nfds, readfds, writefds, exceptfds, timeout, sigmask, proc, fds,
+	)
+}
 
 #[allow(clippy::type_complexity)]
+#[syscall(id64 = 0x10e)]
 pub fn pselect6(
 	Args((nfds, readfds, writefds, exceptfds, timeout, sigmask)): Args<(
 		c_int,
@@ -40,17 +98,13 @@ pub fn pselect6(
 		SyscallPtr<FDSet>,
 		SyscallPtr<FDSet>,
 		SyscallPtr<Timespec>,
-		SyscallSlice<u8>,
+		SyscallPtr<[usize; 2]>,
 	)>,
+	proc: Arc<Process>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	do_select(
-		fds,
-		nfds as _,
-		readfds,
-		writefds,
-		exceptfds,
-		timeout,
-		Some(sigmask),
+	let sigmask = sigmask.copy_from_user()?;
+	do_pselect6(
+		nfds, readfds, writefds, exceptfds, timeout, sigmask, proc, fds,
 	)
 }
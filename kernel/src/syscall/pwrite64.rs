@@ -0,0 +1,73 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `pwrite64` system call allows to write data to a file at a given offset, without changing
+//! the file's current offset.
+
+use super::{pread64::get_seekable_file, Args};
+use crate::{
+	file::fd::FileDescriptorTable, process::mem_space::copy::SyscallSlice, sync::mutex::Mutex,
+};
+use core::{cmp::min, ffi::c_int};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Performs the `pwrite64` system call.
+fn do_pwrite(
+	fd: c_int,
+	buf: SyscallSlice<u8>,
+	count: usize,
+	offset: i64,
+	fds: &Mutex<FileDescriptorTable>,
+) -> EResult<usize> {
+	let file = get_seekable_file(fds, fd, offset)?;
+	let count = min(count, i32::MAX as usize);
+	if count == 0 {
+		return Ok(0);
+	}
+	// TODO find a way to avoid allocating here
+	let buf_slice = buf.copy_from_user_vec(0, count)?.ok_or(errno!(EFAULT))?;
+	file.ops.write(&file, offset as u64, &buf_slice)
+}
+
+#[syscall(id64 = 0x012)]
+pub fn pwrite64(
+	Args((fd, buf, count, offset)): Args<(c_int, SyscallSlice<u8>, usize, i64)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	do_pwrite(fd, buf, count, offset, &fds)
+}
+
+#[syscall(id32 = 0x0b5)]
+pub fn compat_pwrite64(
+	Args((fd, buf, count, offset_low, offset_high)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		u32,
+		u32,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	let offset = (((offset_high as u64) << 32) | offset_low as u64) as i64;
+	do_pwrite(fd, buf, count, offset, &fds)
+}
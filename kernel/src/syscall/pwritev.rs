@@ -28,8 +28,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x14e, id64 = 0x128)]
 pub fn pwritev(
 	Args((fd, iov, iovcnt, offset_low, offset_high)): Args<(
 		c_int,
@@ -28,8 +28,10 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x17b, id64 = 0x148)]
 pub fn pwritev2(
 	Args((fd, iov, iovcnt, offset, flags)): Args<(c_int, SyscallIOVec, c_int, isize, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -25,6 +25,7 @@ use crate::{
 	sync::mutex::Mutex,
 };
 use core::{cmp::min, ffi::c_int, sync::atomic};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -32,13 +33,14 @@ use utils::{
 	vec,
 };
 
+#[syscall(id32 = 0x003, id64 = 0x000)]
 pub fn read(
-	Args((fd, buf, count)): Args<(c_int, SyscallSlice<u8>, usize)>,
+	Args((fd, buf, mut count)): Args<(c_int, SyscallSlice<u8>, usize)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Validation
-	let len = min(count, i32::MAX as usize);
-	if len == 0 {
+	count = min(count, i32::MAX as usize);
+	if count == 0 {
 		return Ok(0);
 	}
 	let file = fds.lock().get_fd(fd)?.get_file().clone();
@@ -26,6 +26,7 @@ use crate::{
 	},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	collections::{path::PathBuf, vec::Vec},
 	errno,
@@ -33,6 +34,7 @@ use utils::{
 	vec,
 };
 
+#[syscall(id32 = 0x055, id64 = 0x059)]
 pub fn readlink(
 	Args((pathname, buf, bufsiz)): Args<(SyscallString, SyscallSlice<u8>, usize)>,
 ) -> EResult<usize> {
@@ -22,7 +22,7 @@ use crate::{
 	file::{fd::FileDescriptorTable, File, FileType},
 	process::{
 		mem_space::{
-			copy::{SyscallIOVec, SyscallSlice},
+			copy::{IOVec, SyscallIOVec, SyscallSlice},
 			MemSpace,
 		},
 		scheduler, Process,
@@ -31,6 +31,7 @@ use crate::{
 	syscall::{Args, FromSyscallArg},
 };
 use core::{cmp::min, ffi::c_int, intrinsics::unlikely, sync::atomic};
+use macros::syscall;
 use utils::{
 	collections::vec::Vec,
 	errno,
@@ -42,38 +43,59 @@ use utils::{
 
 // FIXME: the operation has to be atomic
 
+/// Reads a chunk of `max_len` bytes from the file into `i`.
+///
+/// `off` is the number of bytes read from previous chunks.
+///
+/// On success, the function returns the number of bytes read.
+fn read_chunk(
+	i: &IOVec,
+	max_len: usize,
+	offset: Option<u64>,
+	off: usize,
+	file: &File,
+) -> EResult<usize> {
+	let ptr = SyscallSlice::<u8>::from_ptr(i.iov_base as usize);
+	// TODO perf: do not use a buffer
+	let mut buf = vec![0u8; max_len]?;
+	let len = if let Some(offset) = offset {
+		let file_off = offset + off as u64;
+		file.ops.read(file, file_off, &mut buf)?
+	} else {
+		let off = file.off.load(atomic::Ordering::Acquire);
+		let len = file.ops.read(file, off, &mut buf)?;
+		// Update offset
+		let new_off = off.saturating_add(len as u64);
+		file.off.store(new_off, atomic::Ordering::Release);
+		len
+	};
+	ptr.copy_to_user(0, &buf[..len])?;
+	Ok(len)
+}
+
 /// Reads the given chunks from the file.
 ///
 /// Arguments:
 /// - `iov` is the set of chunks
-/// - `iovcnt` is the number of chunks in `iov`
 /// - `offset` is the offset at which the read operation in the file begins
 /// - `open_file` is the file to read from
-fn read(iov: SyscallIOVec, iovcnt: usize, offset: Option<u64>, file: &File) -> EResult<usize> {
+///
+/// The operation stops at the first chunk that is not filled entirely. If an error occurs after
+/// some data has been read, the function returns the number of bytes read so far.
+fn read(iov: &[IOVec], offset: Option<u64>, file: &File) -> EResult<usize> {
 	let mut off = 0;
-	for i in iov.iter(iovcnt) {
-		let i = i?;
+	for i in iov {
 		// The size to read. This is limited to avoid an overflow on the total length
 		let max_len = min(i.iov_len, i32::MAX as usize - off);
-		let ptr = SyscallSlice::<u8>::from_ptr(i.iov_base as usize);
-		// Read
-		// TODO perf: do not use a buffer
-		let mut buf = vec![0u8; max_len]?;
-		let len = if let Some(offset) = offset {
-			let file_off = offset + off as u64;
-			file.ops.read(file, file_off, &mut buf)?
-		} else {
-			let off = file.off.load(atomic::Ordering::Acquire);
-			let len = file.ops.read(file, off, &mut buf)?;
-			// Update offset
-			let new_off = off.saturating_add(len as u64);
-			file.off.store(new_off, atomic::Ordering::Release);
-			len
-		};
-		ptr.copy_to_user(0, &buf[..len])?;
-		off += len;
-		if unlikely(len < max_len) {
-			break;
+		match read_chunk(i, max_len, offset, off, file) {
+			Ok(len) => {
+				off += len;
+				if unlikely(len < max_len) {
+					break;
+				}
+			}
+			Err(e) if off == 0 => return Err(e),
+			Err(_) => break,
 		}
 	}
 	Ok(off)
@@ -109,10 +131,14 @@ pub fn do_readv(
 	if file.get_type()? == FileType::Link {
 		return Err(errno!(EINVAL));
 	}
-	let len = read(iov, iovcnt as _, offset, &file)?;
+	// Copy the iovec before transferring any data, so that the operation fails early if it is
+	// not accessible
+	let iov = iov.copy_from_user(iovcnt as _)?;
+	let len = read(&iov, offset, &file)?;
 	Ok(len as _)
 }
 
+#[syscall(id32 = 0x091, id64 = 0x013)]
 pub fn readv(
 	Args((fd, iov, iovcnt)): Args<(c_int, SyscallIOVec, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
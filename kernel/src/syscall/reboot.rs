@@ -21,6 +21,7 @@
 
 use crate::{file::perm::AccessProfile, power, process::Process, syscall::Args};
 use core::ffi::{c_int, c_void};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -40,17 +41,51 @@ const CMD_HALT: c_int = 2;
 /// Command to suspend the system.
 const CMD_SUSPEND: c_int = 3;
 
+/// First magic number of the Linux interface.
+const LINUX_MAGIC: c_int = 0xfee1deadu32 as _;
+/// Accepted second magic numbers of the Linux interface.
+const LINUX_MAGIC2: [c_int; 4] = [0x28121969, 0x05121996, 0x16041998, 0x20112000];
+
+/// Linux command to disable the Ctrl-Alt-Del keystroke.
+const LINUX_REBOOT_CMD_CAD_OFF: c_int = 0x00000000;
+/// Linux command to enable the Ctrl-Alt-Del keystroke.
+const LINUX_REBOOT_CMD_CAD_ON: c_int = 0x89abcdefu32 as _;
+/// Linux command to power off the system.
+const LINUX_REBOOT_CMD_POWER_OFF: c_int = 0x4321fedc;
+/// Linux command to reboot the system.
+const LINUX_REBOOT_CMD_RESTART: c_int = 0x01234567;
+/// Linux command to halt the system.
+const LINUX_REBOOT_CMD_HALT: c_int = 0xcdef0123u32 as _;
+/// Linux command to suspend the system.
+const LINUX_REBOOT_CMD_SW_SUSPEND: c_int = 0xd000fce2u32 as _;
+
+#[syscall(id32 = 0x058, id64 = 0x0a9)]
 pub fn reboot(
 	Args((magic, magic2, cmd, _arg)): Args<(c_int, c_int, c_int, *const c_void)>,
 	ap: AccessProfile,
 ) -> EResult<usize> {
 	// Validation
-	if magic != MAGIC || magic2 != MAGIC2 {
+	let linux = magic == LINUX_MAGIC && LINUX_MAGIC2.contains(&magic2);
+	if !linux && (magic != MAGIC || magic2 != MAGIC2) {
 		return Err(errno!(EINVAL));
 	}
 	if !ap.is_privileged() {
 		return Err(errno!(EPERM));
 	}
+	// Translate commands of the Linux interface
+	let cmd = if linux {
+		match cmd {
+			// Ctrl-Alt-Del is not handled
+			LINUX_REBOOT_CMD_CAD_OFF | LINUX_REBOOT_CMD_CAD_ON => return Ok(0),
+			LINUX_REBOOT_CMD_POWER_OFF => CMD_POWEROFF,
+			LINUX_REBOOT_CMD_RESTART => CMD_REBOOT,
+			LINUX_REBOOT_CMD_HALT => CMD_HALT,
+			LINUX_REBOOT_CMD_SW_SUSPEND => CMD_SUSPEND,
+			_ => return Err(errno!(EINVAL)),
+		}
+	} else {
+		cmd
+	};
 	// Debug commands: shutdown with QEMU
 	#[cfg(config_debug_qemu)]
 	{
@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `recvfrom` system call receives a message from a socket.
+
+use crate::{
+	file::{fd::FileDescriptorTable, socket::Socket, O_NONBLOCK},
+	process::mem_space::copy::{SyscallPtr, SyscallSlice},
+	sync::mutex::Mutex,
+	syscall::{util::copy_sockaddr_to_user, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+	vec,
+};
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x173, id64 = 0x02d)]
+pub fn recvfrom(
+	Args((sockfd, buf, len, flags, src_addr, addrlen)): Args<(
+		c_int,
+		SyscallSlice<u8>,
+		usize,
+		c_int,
+		SyscallSlice<u8>,
+		SyscallPtr<c_int>,
+	)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+) -> EResult<usize> {
+	// Get socket
+	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
+	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
+	// Receive
+	let mut buf_slice = vec![0u8; len]?;
+	let (len, name) = sock.recv(&mut buf_slice, flags, file.get_flags() & O_NONBLOCK != 0)?;
+	buf.copy_to_user(0, &buf_slice[..len])?;
+	copy_sockaddr_to_user(&src_addr, &addrlen, &name)?;
+	Ok(len)
+}
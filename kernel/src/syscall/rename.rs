@@ -24,12 +24,14 @@ use crate::{
 	sync::mutex::Mutex,
 	syscall::{renameat2::do_renameat2, util::at::AT_FDCWD, Args},
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x026, id64 = 0x052)]
 pub fn rename(
 	Args((oldpath, newpath)): Args<(SyscallString, SyscallString)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
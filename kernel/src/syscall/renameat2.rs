@@ -31,6 +31,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{ffi::c_int, ptr};
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -91,6 +92,7 @@ pub(super) fn do_renameat2(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x161, id64 = 0x13c)]
 pub fn renameat2(
 	Args((olddirfd, oldpath, newdirfd, newpath, flags)): Args<(
 		c_int,
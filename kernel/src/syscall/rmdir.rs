@@ -29,12 +29,14 @@ use crate::{
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x028, id64 = 0x054)]
 pub fn rmdir(Args(pathname): Args<SyscallString>, rs: ResolutionSettings) -> EResult<usize> {
 	let path = pathname.copy_from_user()?.ok_or(errno!(EFAULT))?;
 	let path = PathBuf::try_from(path)?;
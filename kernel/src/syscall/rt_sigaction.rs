@@ -27,6 +27,7 @@ use crate::{
 	syscall::{Args, Signal},
 };
 use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
 fn do_rt_sigaction<S: Debug + From<SigAction> + Into<SigAction>>(
@@ -48,6 +49,7 @@ fn do_rt_sigaction<S: Debug + From<SigAction> + Into<SigAction>>(
 	Ok(0)
 }
 
+#[syscall(id64 = 0x00d)]
 pub fn rt_sigaction(
 	Args((signum, act, oldact)): Args<(c_int, SyscallPtr<SigAction>, SyscallPtr<SigAction>)>,
 	proc: Arc<Process>,
@@ -55,6 +57,7 @@ pub fn rt_sigaction(
 	do_rt_sigaction(signum, act, oldact, proc)
 }
 
+#[syscall(id32 = 0x0ae)]
 pub fn compat_rt_sigaction(
 	Args((signum, act, oldact)): Args<(
 		c_int,
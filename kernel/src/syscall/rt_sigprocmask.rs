@@ -27,6 +27,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{cmp::min, ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno, EINVAL},
@@ -40,6 +41,7 @@ const SIG_UNBLOCK: i32 = 1;
 /// Sets the mask with the given one.
 const SIG_SETMASK: i32 = 2;
 
+#[syscall(id32 = 0x0af, id64 = 0x00e)]
 pub fn rt_sigprocmask(
 	Args((how, set, oldset, sigsetsize)): Args<(
 		c_int,
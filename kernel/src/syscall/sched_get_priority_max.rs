@@ -0,0 +1,40 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_get_priority_max` system call returns the maximum priority of a scheduling
+//! policy.
+
+use crate::{
+	process::scheduler::{RT_PRIORITY_MAX, SCHED_FIFO, SCHED_OTHER, SCHED_RR},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x09f, id64 = 0x092)]
+pub fn sched_get_priority_max(Args(policy): Args<c_int>) -> EResult<usize> {
+	match u8::try_from(policy) {
+		Ok(SCHED_OTHER) => Ok(0),
+		Ok(SCHED_FIFO | SCHED_RR) => Ok(RT_PRIORITY_MAX as _),
+		_ => Err(errno!(EINVAL)),
+	}
+}
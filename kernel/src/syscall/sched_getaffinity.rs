@@ -0,0 +1,52 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_getaffinity` system call returns the set of CPU cores a process is allowed to run
+//! on.
+
+use super::sched_setscheduler::get_target;
+use crate::{
+	cpu,
+	cpu::CpuSet,
+	process::{mem_space::copy::SyscallSlice, pid::Pid},
+	syscall::Args,
+};
+use core::mem::size_of;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x0f2, id64 = 0x0cc)]
+pub fn sched_getaffinity(
+	Args((pid, cpusetsize, mask)): Args<(Pid, usize, SyscallSlice<u8>)>,
+) -> EResult<usize> {
+	// The buffer must be able to hold the whole set
+	if cpusetsize < size_of::<CpuSet>() || cpusetsize % size_of::<CpuSet>() != 0 {
+		return Err(errno!(EINVAL));
+	}
+	if mask.0.is_none() {
+		return Err(errno!(EFAULT));
+	}
+	let target = get_target(pid)?;
+	let set = target.get_affinity() & cpu::online();
+	mask.copy_to_user(0, &set.to_le_bytes())?;
+	// Return the number of bytes written
+	Ok(size_of::<CpuSet>())
+}
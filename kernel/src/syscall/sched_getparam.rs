@@ -0,0 +1,42 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_getparam` system call returns the scheduling parameters of a process.
+
+use super::sched_setscheduler::{get_target, SchedParam};
+use crate::{
+	process::{mem_space::copy::SyscallPtr, pid::Pid},
+	syscall::Args,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x09b, id64 = 0x08f)]
+pub fn sched_getparam(Args((pid, param)): Args<(Pid, SyscallPtr<SchedParam>)>) -> EResult<usize> {
+	if param.0.is_none() {
+		return Err(errno!(EINVAL));
+	}
+	let target = get_target(pid)?;
+	param.copy_to_user(&SchedParam {
+		sched_priority: target.rt_priority() as _,
+	})?;
+	Ok(0)
+}
@@ -0,0 +1,29 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_getscheduler` system call returns the scheduling policy of a process.
+
+use super::sched_setscheduler::get_target;
+use crate::{process::pid::Pid, syscall::Args};
+use macros::syscall;
+use utils::errno::{EResult, Errno};
+
+#[syscall(id32 = 0x09d, id64 = 0x091)]
+pub fn sched_getscheduler(Args(pid): Args<Pid>) -> EResult<usize> {
+	Ok(get_target(pid)?.get_sched_policy() as _)
+}
@@ -0,0 +1,60 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_setaffinity` system call sets the set of CPU cores a process is allowed to run on.
+
+use super::sched_setscheduler::get_target;
+use crate::{
+	cpu,
+	cpu::CpuSet,
+	process::{mem_space::copy::SyscallSlice, pid::Pid, Process},
+	syscall::Args,
+};
+use core::mem::size_of;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x0f1, id64 = 0x0cb)]
+pub fn sched_setaffinity(
+	Args((pid, cpusetsize, mask)): Args<(Pid, usize, SyscallSlice<u8>)>,
+) -> EResult<usize> {
+	// Cores that do not fit in `CpuSet` are ignored, and missing bytes are considered zero
+	let mut buf = [0u8; size_of::<CpuSet>()];
+	let len = cpusetsize.min(buf.len());
+	if !mask.copy_from_user(0, &mut buf[..len])? {
+		return Err(errno!(EFAULT));
+	}
+	let set = CpuSet::from_le_bytes(buf);
+	if set & cpu::online() == 0 {
+		return Err(errno!(EINVAL));
+	}
+	let target = get_target(pid)?;
+	// Check permissions
+	let ap = Process::current().fs.lock().access_profile;
+	if !ap.is_privileged() {
+		let target_ap = target.fs.lock().access_profile;
+		if ap.euid != target_ap.uid && ap.euid != target_ap.euid {
+			return Err(errno!(EPERM));
+		}
+	}
+	target.set_affinity(set);
+	Ok(0)
+}
@@ -0,0 +1,33 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_setparam` system call sets the scheduling parameters of a process, keeping its
+//! policy.
+
+use super::sched_setscheduler::{do_set_sched, SchedParam};
+use crate::{
+	process::{mem_space::copy::SyscallPtr, pid::Pid},
+	syscall::Args,
+};
+use macros::syscall;
+use utils::errno::{EResult, Errno};
+
+#[syscall(id32 = 0x09a, id64 = 0x08e)]
+pub fn sched_setparam(Args((pid, param)): Args<(Pid, SyscallPtr<SchedParam>)>) -> EResult<usize> {
+	do_set_sched(pid, None, param)
+}
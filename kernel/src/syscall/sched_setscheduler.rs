@@ -0,0 +1,103 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sched_setscheduler` system call sets the scheduling policy and priority of a process.
+
+use crate::{
+	process::{
+		mem_space::copy::SyscallPtr,
+		pid::Pid,
+		scheduler::{RT_PRIORITY_MAX, RT_PRIORITY_MIN, SCHED_FIFO, SCHED_OTHER, SCHED_RR},
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// Scheduling parameters of a process.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct SchedParam {
+	/// The real-time priority.
+	pub sched_priority: c_int,
+}
+
+/// Returns the process with PID `pid`, or the current process if `pid` is zero.
+pub(super) fn get_target(pid: Pid) -> EResult<Arc<Process>> {
+	if (pid as c_int) < 0 {
+		return Err(errno!(EINVAL));
+	}
+	if pid == 0 {
+		Ok(Process::current())
+	} else {
+		Process::get_by_pid(pid).ok_or_else(|| errno!(ESRCH))
+	}
+}
+
+/// Sets the scheduling `policy` and the parameters `param` of the process with PID `pid`.
+///
+/// If `policy` is `None`, the policy is left unchanged.
+pub(super) fn do_set_sched(
+	pid: Pid,
+	policy: Option<c_int>,
+	param: SyscallPtr<SchedParam>,
+) -> EResult<usize> {
+	let param = param.copy_from_user()?.ok_or_else(|| errno!(EINVAL))?;
+	let target = get_target(pid)?;
+	let policy = match policy {
+		Some(policy) => u8::try_from(policy).map_err(|_| errno!(EINVAL))?,
+		None => target.get_sched_policy(),
+	};
+	let rt = match policy {
+		SCHED_OTHER => false,
+		SCHED_FIFO | SCHED_RR => true,
+		_ => return Err(errno!(EINVAL)),
+	};
+	let priority = u8::try_from(param.sched_priority).map_err(|_| errno!(EINVAL))?;
+	let valid = if rt {
+		(RT_PRIORITY_MIN..=RT_PRIORITY_MAX).contains(&priority)
+	} else {
+		priority == 0
+	};
+	if !valid {
+		return Err(errno!(EINVAL));
+	}
+	// Check permissions
+	let ap = Process::current().fs.lock().access_profile;
+	if !ap.is_privileged() {
+		let target_uid = target.fs.lock().access_profile.uid;
+		if rt || (target.get_pid() != Process::current().get_pid() && ap.euid != target_uid) {
+			return Err(errno!(EPERM));
+		}
+	}
+	target.set_sched(policy, priority);
+	Ok(0)
+}
+
+#[syscall(id32 = 0x09c, id64 = 0x090)]
+pub fn sched_setscheduler(
+	Args((pid, policy, param)): Args<(Pid, c_int, SyscallPtr<SchedParam>)>,
+) -> EResult<usize> {
+	do_set_sched(pid, Some(policy), param)
+}
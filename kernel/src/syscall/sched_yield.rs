@@ -19,10 +19,13 @@
 //! The `sched_yield` system call ends the current tick of the current process and returns the
 //! control back to the scheduler.
 
-use crate::process::{scheduler, scheduler::Scheduler};
-use utils::errno::{EResult, Errno};
+use crate::process::scheduler::Scheduler;
+use macros::syscall;
+use utils::errno::EResult;
 
+#[syscall(id32 = 0x09e, id64 = 0x018)]
 pub fn sched_yield() -> EResult<usize> {
-	Scheduler::tick();
+	// If the current process is the only one runnable, this returns immediately
+	Scheduler::yield_now();
 	Ok(0)
 }
@@ -20,28 +20,20 @@
 //! writable or for an exception to occur.
 
 use crate::{
-	file::fd::FileDescriptorTable,
-	process::{
-		mem_space::{
-			copy::{SyscallPtr, SyscallSlice},
-			MemSpace,
-		},
-		scheduler,
-		scheduler::Scheduler,
-		Process,
-	},
+	file::{fd::FileDescriptorTable, wait_queue},
+	process::mem_space::copy::SyscallPtr,
 	sync::mutex::Mutex,
-	syscall::{poll, Args},
+	syscall::{poll, poll::poll_file, Args},
 	time::{
-		clock,
 		clock::{current_time_ns, Clock},
-		unit::{TimeUnit, Timeval},
+		unit::{TimeUnit, Timestamp, Timeval},
 	},
 };
 use core::{
 	cmp::min,
 	ffi::{c_int, c_long},
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -51,155 +43,120 @@ use utils::{
 /// The number of file descriptors in FDSet.
 pub const FD_SETSIZE: usize = 1024;
 
+/// Events making a file descriptor ready for reading.
+const READ_MASK: u32 =
+	poll::POLLIN | poll::POLLRDNORM | poll::POLLRDBAND | poll::POLLHUP | poll::POLLERR;
+/// Events making a file descriptor ready for writing.
+const WRITE_MASK: u32 = poll::POLLOUT | poll::POLLWRNORM | poll::POLLWRBAND | poll::POLLERR;
+/// Events making a file descriptor have an exceptional condition.
+const EXCEPT_MASK: u32 = poll::POLLPRI;
+
 /// Structure representing `fd_set`.
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct FDSet {
 	/// The set's bitfield.
 	fds_bits: [c_long; FD_SETSIZE / c_long::BITS as usize],
 }
 
+impl Default for FDSet {
+	fn default() -> Self {
+		Self {
+			fds_bits: [0; FD_SETSIZE / c_long::BITS as usize],
+		}
+	}
+}
+
 impl FDSet {
 	/// Tells whether the given file descriptor `fd` is set in the list.
 	fn is_set(&self, fd: u32) -> bool {
 		if fd as usize >= FD_SETSIZE {
 			return false;
 		}
-		// TODO Check correctness
 		let i = (fd as usize) / c_long::BITS as usize;
-		self.fds_bits[i] >> (fd % c_long::BITS) != 0
+		(self.fds_bits[i] >> (fd % c_long::BITS)) & 1 != 0
 	}
 
-	/// Sets or clears the bit for file descriptor `fd`.
-	fn set(&mut self, fd: u32, val: bool) {
-		// TODO Check correctness
+	/// Sets the bit for file descriptor `fd`.
+	fn set(&mut self, fd: u32) {
 		let i = (fd as usize) / c_long::BITS as usize;
-		if val {
-			self.fds_bits[i] |= 1 << (fd % c_long::BITS);
-		} else {
-			self.fds_bits[i] &= !(1 << (fd % c_long::BITS));
-		}
+		self.fds_bits[i] |= 1 << (fd % c_long::BITS);
 	}
 }
 
+/// Returns the deadline corresponding to the timeout pointed to by `timeout`.
+///
+/// If no timeout is given, the function returns `None`, meaning waiting indefinitely.
+pub fn get_deadline<T: TimeUnit>(timeout: SyscallPtr<T>) -> EResult<Option<Timestamp>> {
+	Ok(timeout
+		.copy_from_user()?
+		.map(|t| current_time_ns(Clock::Monotonic).saturating_add(t.to_nano())))
+}
+
 /// Performs the select operation.
 ///
 /// Arguments:
-/// - `mem_space` is the process's memory space.
 /// - `fds` is the process's file descriptors table.
 /// - `nfds` is the number of the highest checked fd + 1.
 /// - `readfds` is the bitfield of fds to check for read operations.
 /// - `writefds` is the bitfield of fds to check for write operations.
 /// - `exceptfds` is the bitfield of fds to check for exceptional conditions.
-/// - `timeout` is the timeout after which the syscall returns.
-/// - `sigmask` TODO
-pub fn do_select<T: TimeUnit>(
-	fds: Arc<Mutex<FileDescriptorTable>>,
-	nfds: u32,
+/// - `deadline` is the timestamp in nanoseconds on the monotonic clock at which the function
+///   returns if no event occurred. If `None`, the function waits indefinitely.
+///
+/// On return, the sets only contain the file descriptors on which an event occurred.
+pub fn do_select(
+	fds: &Mutex<FileDescriptorTable>,
+	nfds: c_int,
 	readfds: SyscallPtr<FDSet>,
 	writefds: SyscallPtr<FDSet>,
 	exceptfds: SyscallPtr<FDSet>,
-	timeout: SyscallPtr<T>,
-	_sigmask: Option<SyscallSlice<u8>>,
+	deadline: Option<Timestamp>,
 ) -> EResult<usize> {
-	let start = current_time_ns(Clock::Monotonic);
-	// Get timeout
-	let timeout = timeout
-		.copy_from_user()?
-		.map(|t| t.to_nano())
-		.unwrap_or_default();
-	// Tells whether the syscall immediately returns
-	let polling = timeout == 0;
-	// The end timestamp
-	let end = start + timeout;
-	// Read
-	let mut readfds_set = readfds.copy_from_user()?;
-	let mut writefds_set = writefds.copy_from_user()?;
-	let mut exceptfds_set = exceptfds.copy_from_user()?;
-	let res = loop {
+	if nfds < 0 {
+		return Err(errno!(EINVAL));
+	}
+	let nfds = min(nfds as u32, FD_SETSIZE as u32);
+	let sets = [
+		(readfds.copy_from_user()?, READ_MASK),
+		(writefds.copy_from_user()?, WRITE_MASK),
+		(exceptfds.copy_from_user()?, EXCEPT_MASK),
+	];
+	let mut results: [FDSet; 3] = Default::default();
+	let res = wait_queue::poll_wait(deadline, || {
+		let fds = fds.lock();
+		results = Default::default();
 		let mut events_count = 0;
-		// Set if every bitfields are set to zero
-		let mut all_zeros = true;
-		for fd_id in 0..min(nfds, FD_SETSIZE as u32) {
-			let read = readfds_set
-				.as_ref()
-				.map(|fds| fds.is_set(fd_id))
-				.unwrap_or(false);
-			let write = writefds_set
-				.as_ref()
-				.map(|fds| fds.is_set(fd_id))
-				.unwrap_or(false);
-			let except = exceptfds_set
-				.as_ref()
-				.map(|fds| fds.is_set(fd_id))
-				.unwrap_or(false);
+		for fd_id in 0..nfds {
 			// Build event mask
-			let mut mask = 0;
-			if read {
-				mask |= poll::POLLIN;
-			}
-			if write {
-				mask |= poll::POLLOUT;
-			}
-			if except {
-				mask |= poll::POLLPRI;
-			}
-			if mask != 0 {
-				all_zeros = false;
+			let mask = sets
+				.iter()
+				.filter(|(set, _)| set.as_ref().is_some_and(|s| s.is_set(fd_id)))
+				.fold(0, |mask, (_, m)| mask | m);
+			if mask == 0 {
+				continue;
 			}
-			// Poll file
-			let result = {
-				let fds = fds.lock();
-				let Ok(fd) = fds.get_fd(fd_id as _) else {
-					if mask != 0 {
-						return Err(errno!(EBADF));
-					}
-					continue;
-				};
-				let file = fd.get_file();
-				file.ops.poll(file, mask)?
-			};
+			let file = fds.get_fd(fd_id as _)?.get_file();
+			let events = poll_file(file, mask)?;
 			// Set results
-			let read = read && result & poll::POLLIN != 0;
-			let write = write && result & poll::POLLOUT != 0;
-			let except = except && result & poll::POLLPRI != 0;
-			if let Some(fds) = &mut readfds_set {
-				fds.set(fd_id, read);
+			for ((set, m), res) in sets.iter().zip(results.iter_mut()) {
+				if set.as_ref().is_some_and(|s| s.is_set(fd_id)) && events & m != 0 {
+					res.set(fd_id);
+					events_count += 1;
+				}
 			}
-			if let Some(fds) = &mut writefds_set {
-				fds.set(fd_id, write);
-			}
-			if let Some(fds) = &mut exceptfds_set {
-				fds.set(fd_id, except);
-			}
-			events_count += read as usize + write as usize + except as usize;
-		}
-		// If one or more events occurred, return
-		if all_zeros || polling || events_count > 0 {
-			break events_count;
 		}
-		let ts = current_time_ns(Clock::Monotonic);
-		// On timeout, return 0
-		if ts >= end {
-			break 0;
-		}
-		// TODO Make the process sleep?
-		Scheduler::tick();
-	};
+		Ok((events_count > 0).then_some(events_count))
+	})?;
 	// Write back
-	if let Some(val) = readfds_set {
-		readfds.copy_to_user(&val)?;
-	}
-	if let Some(val) = writefds_set {
-		writefds.copy_to_user(&val)?;
-	}
-	if let Some(val) = exceptfds_set {
-		exceptfds.copy_to_user(&val)?;
+	for (ptr, res) in [readfds, writefds, exceptfds].iter().zip(&results) {
+		ptr.copy_to_user(res)?;
 	}
-	Ok(res)
+	Ok(res.unwrap_or(0))
 }
 
 #[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x052, id64 = 0x017)]
 pub fn select(
 	Args((nfds, readfds, writefds, exceptfds, timeout)): Args<(
 		c_int,
@@ -210,5 +167,6 @@ pub fn select(
 	)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
-	do_select(fds, nfds as _, readfds, writefds, exceptfds, timeout, None)
+	let deadline = get_deadline(timeout)?;
+	do_select(&fds, nfds, readfds, writefds, exceptfds, deadline)
 }
@@ -0,0 +1,72 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `semctl` system call performs control operations on a System V semaphore set.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		sem,
+		sem::{GETVAL, SETVAL},
+		IpcObject, IPC_64, IPC_RMID,
+	},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x18a, id64 = 0x042)]
+pub fn semctl(
+	Args((semid, semnum, cmd, arg)): Args<(c_int, c_int, c_int, usize)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let semnum = semnum as usize;
+	match cmd & !IPC_64 {
+		GETVAL => {
+			let set = sem::SETS.lock().get(semid)?;
+			if unlikely(!ap.can_access_ipc(&set.perm().lock(), 0o4)) {
+				return Err(errno!(EACCES));
+			}
+			Ok(set.get(semnum)? as _)
+		}
+		SETVAL => {
+			let set = sem::SETS.lock().get(semid)?;
+			if unlikely(!ap.can_access_ipc(&set.perm().lock(), 0o2)) {
+				return Err(errno!(EACCES));
+			}
+			// The argument is a union passed by value
+			set.set(semnum, arg as c_int)?;
+			Ok(0)
+		}
+		IPC_RMID => {
+			let mut sets = sem::SETS.lock();
+			let set = sets.get(semid)?;
+			if unlikely(!ap.is_ipc_owner(&set.perm().lock())) {
+				return Err(errno!(EPERM));
+			}
+			sets.remove(semid);
+			set.remove();
+			Ok(0)
+		}
+		_ => Err(errno!(EINVAL)),
+	}
+}
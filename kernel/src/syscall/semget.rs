@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `semget` system call returns the ID of a System V semaphore set, creating it if required.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		sem,
+		sem::{SemSet, SEMMSL},
+		Key,
+	},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x189, id64 = 0x040)]
+pub fn semget(
+	Args((key, nsems, semflg)): Args<(Key, c_int, c_int)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	if unlikely(nsems < 0) {
+		return Err(errno!(EINVAL));
+	}
+	let nsems = nsems as usize;
+	let id = sem::SETS.lock().get_or_create(
+		key,
+		semflg,
+		&ap,
+		|set| {
+			if unlikely(nsems > set.count()) {
+				return Err(errno!(EINVAL));
+			}
+			Ok(())
+		},
+		|perm| {
+			if unlikely(!(1..=SEMMSL).contains(&nsems)) {
+				return Err(errno!(EINVAL));
+			}
+			Ok(SemSet::new(perm, nsems)?)
+		},
+	)?;
+	Ok(id as _)
+}
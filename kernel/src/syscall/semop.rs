@@ -0,0 +1,66 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `semop` system call performs operations on the semaphores of a System V semaphore set.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		sem,
+		sem::{Sembuf, SEMOPM},
+		IpcObject,
+	},
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id64 = 0x041)]
+pub fn semop(
+	Args((semid, sops, nsops)): Args<(c_int, SyscallSlice<Sembuf>, usize)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	if unlikely(nsops == 0) {
+		return Err(errno!(EINVAL));
+	}
+	if unlikely(nsops > SEMOPM) {
+		return Err(errno!(E2BIG));
+	}
+	let ops = sops
+		.copy_from_user_vec(0, nsops)?
+		.ok_or_else(|| errno!(EFAULT))?;
+	let set = sem::SETS.lock().get(semid)?;
+	// Waiting for zero only requires reading
+	let mode = if ops.iter().all(|op| op.sem_op == 0) {
+		0o4
+	} else {
+		0o2
+	};
+	if unlikely(!ap.can_access_ipc(&set.perm().lock(), mode)) {
+		return Err(errno!(EACCES));
+	}
+	set.apply(proc.get_pid(), &ops)?;
+	Ok(0)
+}
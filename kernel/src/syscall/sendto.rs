@@ -19,22 +19,23 @@
 //! The `sendto` system call sends a message on a socket.
 
 use crate::{
-	file::{fd::FileDescriptorTable, socket::Socket},
+	file::{fd::FileDescriptorTable, socket::Socket, vfs::ResolutionSettings, O_NONBLOCK},
 	process::{mem_space::copy::SyscallSlice, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::{any::Any, ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
-// TODO implement flags
 
 #[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x171, id64 = 0x02c)]
 pub fn sendto(
-	Args((sockfd, buf, len, _flags, dest_addr, addrlen)): Args<(
+	Args((sockfd, buf, len, flags, dest_addr, addrlen)): Args<(
 		c_int,
 		SyscallSlice<u8>,
 		usize,
@@ -42,6 +43,7 @@ pub fn sendto(
 		SyscallSlice<u8>,
 		isize,
 	)>,
+	rs: ResolutionSettings,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Validation
@@ -50,11 +52,16 @@ pub fn sendto(
 	}
 	// Get socket
 	let file = fds.lock().get_fd(sockfd)?.get_file().clone();
-	let _sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
+	let sock: &Socket = file.get_buffer().ok_or_else(|| errno!(ENOTSOCK))?;
 	// Get slices
-	let _buf_slice = buf.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
-	let _dest_addr_slice = dest_addr
-		.copy_from_user_vec(0, addrlen as usize)?
-		.ok_or(errno!(EFAULT))?;
-	todo!()
+	let buf_slice = buf.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
+	let dest = if !dest_addr.as_ptr().is_null() {
+		let dest_addr_slice = dest_addr
+			.copy_from_user_vec(0, addrlen as usize)?
+			.ok_or(errno!(EFAULT))?;
+		Some(sock.resolve(&dest_addr_slice, &rs)?)
+	} else {
+		None
+	};
+	sock.send(&buf_slice, dest, flags, file.get_flags() & O_NONBLOCK != 0)
 }
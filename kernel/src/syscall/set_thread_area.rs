@@ -25,6 +25,7 @@ use crate::{
 	syscall::Args,
 };
 use core::mem::size_of;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -37,7 +38,7 @@ const TLS_BEGIN_INDEX: usize = gdt::TLS_OFFSET / size_of::<gdt::Entry>();
 /// Returns an entry ID for the given process and entry number.
 ///
 /// If the id is `-1`, the function shall find a free entry.
-fn get_entry(
+pub(super) fn get_entry(
 	entries: &mut [gdt::Entry; process::TLS_ENTRIES_COUNT],
 	entry_number: i32,
 ) -> EResult<(usize, &mut gdt::Entry)> {
@@ -59,6 +60,7 @@ fn get_entry(
 	Ok((id, &mut entries[id]))
 }
 
+#[syscall(id32 = 0x0f3)]
 pub fn set_thread_area(
 	Args(u_info): Args<SyscallPtr<UserDesc>>,
 	proc: Arc<Process>,
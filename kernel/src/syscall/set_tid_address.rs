@@ -23,13 +23,15 @@ use crate::{
 	process::{mem_space::copy::SyscallPtr, Process},
 	syscall::Args,
 };
-use core::ffi::c_int;
+use core::{ffi::c_int, sync::atomic::Ordering::Relaxed};
+use macros::syscall;
 use utils::{errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x102, id64 = 0x0da)]
 pub fn set_tid_address(
-	Args(_tidptr): Args<SyscallPtr<c_int>>,
+	Args(tidptr): Args<SyscallPtr<c_int>>,
 	proc: Arc<Process>,
 ) -> EResult<usize> {
-	// TODO set process's clear_child_tid
-	Ok(proc.tid as _)
+	proc.clear_child_tid.store(tidptr.as_ptr() as _, Relaxed);
+	Ok(proc.pid_ns().local_pid(proc.tid).unwrap_or(0) as _)
 }
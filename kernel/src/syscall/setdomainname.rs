@@ -0,0 +1,36 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setdomainname` syscall sets the NIS domain name of the system, in the UTS namespace of
+//! the process.
+
+use super::sethostname::set_name;
+use crate::{
+	process::{mem_space::copy::SyscallSlice, Process},
+	syscall::Args,
+};
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+#[syscall(id32 = 0x079, id64 = 0x0ab)]
+pub fn setdomainname(
+	Args((name, len)): Args<(SyscallSlice<u8>, usize)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	set_name(name, len, &proc, |ns| &ns.domainname)
+}
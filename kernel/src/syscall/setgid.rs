@@ -19,12 +19,17 @@
 //! The `setgid` syscall sets the GID of the process's owner.
 
 use crate::{file::perm::Gid, process::Process, syscall::Args};
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x02e, id32 = 0x0d6, id64 = 0x06a)]
 pub fn setgid(Args(gid): Args<Gid>, proc: Arc<Process>) -> EResult<usize> {
-	proc.fs.lock().access_profile.set_gid(gid)?;
+	let mut fs = proc.fs.lock();
+	let gid = fs.global_gid(gid)?;
+	let privileged = fs.is_privileged();
+	fs.access_profile.set_gid(gid, privileged)?;
 	Ok(0)
 }
@@ -16,34 +16,53 @@
  * Maestro. If not, see <https://www.gnu.org/licenses/>.
  */
 
-//! The `sethostname` syscall sets the hostname of the system.
+//! The `sethostname` syscall sets the hostname of the system, in the UTS namespace of the
+//! process.
 
 use crate::{
-	file::perm::AccessProfile,
-	process::{mem_space::copy::SyscallSlice, Process},
+	process::{mem_space::copy::SyscallSlice, uts::UtsNamespace, Process},
+	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::intrinsics::unlikely;
+use macros::syscall;
 use utils::{
 	collections::vec::Vec,
 	errno,
 	errno::{EResult, Errno},
 	limits::HOST_NAME_MAX,
+	ptr::arc::Arc,
 };
 
-pub fn sethostname(
-	Args((name, len)): Args<(SyscallSlice<u8>, usize)>,
-	ap: AccessProfile,
+/// Sets the name selected by `select` in the UTS namespace of `proc` to the `len` bytes at
+/// `name`.
+pub(super) fn set_name(
+	name: SyscallSlice<u8>,
+	len: usize,
+	proc: &Process,
+	select: fn(&UtsNamespace) -> &Mutex<Vec<u8>>,
 ) -> EResult<usize> {
-	// Check the size of the hostname is in bounds
+	// Check the size of the name is in bounds
 	if unlikely(len > HOST_NAME_MAX) {
 		return Err(errno!(EINVAL));
 	}
 	// Check permission
-	if !ap.is_privileged() {
-		return Err(errno!(EPERM));
+	let ns = proc.uts_ns();
+	{
+		let fs = proc.fs.lock();
+		if !fs.user_ns.is_privileged_in(&fs.access_profile, &ns.user_ns) {
+			return Err(errno!(EPERM));
+		}
 	}
-	let mut hostname = crate::HOSTNAME.lock();
-	*hostname = name.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
+	let name = name.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
+	*select(&ns).lock() = name;
 	Ok(0)
 }
+
+#[syscall(id32 = 0x04a, id64 = 0x0aa)]
+pub fn sethostname(
+	Args((name, len)): Args<(SyscallSlice<u8>, usize)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	set_name(name, len, &proc, |ns| &ns.hostname)
+}
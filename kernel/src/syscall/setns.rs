@@ -0,0 +1,101 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setns` system call allows a process to join an existing namespace, referenced by a file
+//! descriptor to one of the `/proc/<pid>/ns/` links.
+
+use crate::{
+	file::fd::FileDescriptorTable,
+	process::{
+		ns::{Namespace, NsFile, NsKind},
+		Process,
+	},
+	sync::mutex::Mutex,
+	syscall::{
+		clone::{CLONE_NEWNS, CLONE_NEWPID, CLONE_NEWUSER, CLONE_NEWUTS},
+		Args,
+	},
+};
+use core::{
+	ffi::{c_int, c_ulong},
+	intrinsics::unlikely,
+	ptr,
+};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x15a, id64 = 0x134)]
+pub fn setns(
+	Args((fd, nstype)): Args<(c_int, c_int)>,
+	fds: Arc<Mutex<FileDescriptorTable>>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	let file = fds.lock().get_fd(fd)?.get_file().clone();
+	let NsFile(ns) = file.get_buffer().ok_or_else(|| errno!(EINVAL))?;
+	// If specified, the type of the namespace must match
+	let flag = match ns.kind() {
+		NsKind::Mount => CLONE_NEWNS,
+		NsKind::Pid => CLONE_NEWPID,
+		NsKind::User => CLONE_NEWUSER,
+		NsKind::Uts => CLONE_NEWUTS,
+	};
+	if unlikely(nstype != 0 && nstype as c_ulong != flag) {
+		return Err(errno!(EINVAL));
+	}
+	let mut fs = proc.fs.lock();
+	match ns {
+		Namespace::Mount(ns) => {
+			let privileged =
+				fs.is_privileged() && fs.user_ns.is_privileged_in(&fs.access_profile, &ns.user_ns);
+			if unlikely(!privileged) {
+				return Err(errno!(EPERM));
+			}
+			// Like on `chroot`, the working directory is moved to the new root
+			let root = ns.root();
+			fs.cwd = root.clone();
+			fs.chroot = root;
+			fs.mnt_ns = ns.clone();
+		}
+		// The PID namespace of a process is fixed at its creation
+		Namespace::Pid(_) => return Err(errno!(EINVAL)),
+		Namespace::User(ns) => {
+			// A process cannot join its own user namespace
+			if unlikely(ptr::eq(Arc::as_ptr(ns), Arc::as_ptr(&fs.user_ns))) {
+				return Err(errno!(EINVAL));
+			}
+			// Being privileged in the new namespace would allow a chrooted process to escape
+			if unlikely(fs.is_chrooted() || !fs.user_ns.is_privileged_in(&fs.access_profile, ns)) {
+				return Err(errno!(EPERM));
+			}
+			fs.user_ns = ns.clone();
+		}
+		Namespace::Uts(ns) => {
+			let privileged =
+				fs.is_privileged() && fs.user_ns.is_privileged_in(&fs.access_profile, &ns.user_ns);
+			if unlikely(!privileged) {
+				return Err(errno!(EPERM));
+			}
+			proc.set_uts_ns(ns.clone());
+		}
+	}
+	Ok(0)
+}
@@ -22,12 +22,14 @@ use crate::{
 	process::{pid::Pid, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x039, id64 = 0x06d)]
 pub fn setpgid(Args((mut pid, mut pgid)): Args<(Pid, Pid)>, proc: Arc<Process>) -> EResult<usize> {
 	// TODO Check processes SID
 	if pid == 0 {
@@ -0,0 +1,77 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setpriority` system call sets the nice value of a process.
+
+use crate::{
+	process::{
+		pid::Pid,
+		scheduler::{NICE_MAX, NICE_MIN},
+		Process,
+	},
+	syscall::{sched_setscheduler::get_target, Args},
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+/// `which` value: the target is a process.
+pub(super) const PRIO_PROCESS: c_int = 0;
+
+/// Returns the process targeted by the `which` and `who` arguments of `getpriority` and
+/// `setpriority`.
+///
+/// Only [`PRIO_PROCESS`] is supported.
+pub(super) fn get_prio_target(which: c_int, who: c_int) -> EResult<Arc<Process>> {
+	if which != PRIO_PROCESS {
+		return Err(errno!(EINVAL));
+	}
+	let pid = Pid::try_from(who).map_err(|_| errno!(ESRCH))?;
+	get_target(pid)
+}
+
+/// Sets the nice value of `target` to `nice`, on behalf of the current process.
+///
+/// The value is clamped to the range from [`NICE_MIN`] to [`NICE_MAX`]. Only a privileged
+/// process can lower the nice value of a process.
+pub(super) fn set_nice(target: &Process, nice: c_int) -> EResult<()> {
+	let nice = nice.clamp(NICE_MIN as _, NICE_MAX as _);
+	let ap = Process::current().fs.lock().access_profile;
+	if !ap.is_privileged() {
+		let target_ap = target.fs.lock().access_profile;
+		if ap.euid != target_ap.uid && ap.euid != target_ap.euid {
+			return Err(errno!(EPERM));
+		}
+		if nice < target.nice() as c_int {
+			return Err(errno!(EACCES));
+		}
+	}
+	target.set_nice(nice);
+	Ok(())
+}
+
+#[syscall(id32 = 0x061, id64 = 0x08d)]
+pub fn setpriority(Args((which, who, prio)): Args<(c_int, c_int, c_int)>) -> EResult<usize> {
+	let target = get_prio_target(which, who)?;
+	set_nice(&target, prio)?;
+	Ok(0)
+}
@@ -24,18 +24,24 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
-pub fn setregid(
-	Args((rgid, egid)): Args<(c_int, c_int)>,
-	ap: AccessProfile,
-	proc: Arc<Process>,
-) -> EResult<usize> {
+#[syscall(id32 = 0x047, id32 = 0x0cc, id64 = 0x072)]
+pub fn setregid(Args((rgid, egid)): Args<(c_int, c_int)>, proc: Arc<Process>) -> EResult<usize> {
 	// Validation
 	if rgid < -1 || egid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged()
+	let mut fs = proc.fs.lock();
+	// Translate to IDs of the initial user namespace
+	let global = |id: c_int| match id {
+		-1 => Ok(-1),
+		id => fs.global_gid(id as _).map(|id| id as c_int),
+	};
+	let (rgid, egid) = (global(rgid)?, global(egid)?);
+	let ap = fs.access_profile;
+	if !fs.is_privileged()
 		&& (![-1, ap.gid as _, ap.egid as _].contains(&rgid)
 			|| ![-1, ap.gid as _, ap.egid as _, ap.sgid as _].contains(&egid))
 	{
@@ -50,7 +56,6 @@ pub fn setregid(
 		-1 => ap.egid,
 		i => i as _,
 	};
-	let mut fs = proc.fs.lock();
 	fs.access_profile.gid = new_rgid;
 	fs.access_profile.egid = new_egid;
 	if new_rgid != ap.gid || new_egid != ap.gid {
@@ -24,25 +24,33 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x0aa, id32 = 0x0d2, id64 = 0x077)]
 pub fn setresgid(
 	Args((rgid, egid, sgid)): Args<(c_int, c_int, c_int)>,
-	ap: AccessProfile,
 	proc: Arc<Process>,
 ) -> EResult<usize> {
 	// Validation
 	if rgid < -1 || egid < -1 || sgid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() {
+	let mut fs = proc.fs.lock();
+	// Translate to IDs of the initial user namespace
+	let global = |id: c_int| match id {
+		-1 => Ok(-1),
+		id => fs.global_gid(id as _).map(|id| id as c_int),
+	};
+	let (rgid, egid, sgid) = (global(rgid)?, global(egid)?, global(sgid)?);
+	let ap = fs.access_profile;
+	if !fs.is_privileged() {
 		let allowed = [-1, ap.gid as _, ap.egid as _, ap.sgid as _];
 		if !allowed.contains(&rgid) || !allowed.contains(&egid) || !allowed.contains(&sgid) {
 			return Err(errno!(EPERM));
 		}
 	}
 	// Update
-	let mut fs = proc.fs.lock();
 	fs.access_profile.gid = match rgid {
 		-1 => ap.gid,
 		i => i as _,
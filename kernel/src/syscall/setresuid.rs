@@ -24,25 +24,33 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
+#[syscall(id32 = 0x0a4, id32 = 0x0d0, id64 = 0x075)]
 pub fn setresuid(
 	Args((ruid, euid, suid)): Args<(c_int, c_int, c_int)>,
-	ap: AccessProfile,
 	proc: Arc<Process>,
 ) -> EResult<usize> {
 	// Validation
 	if ruid < -1 || euid < -1 || suid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() {
+	let mut fs = proc.fs.lock();
+	// Translate to IDs of the initial user namespace
+	let global = |id: c_int| match id {
+		-1 => Ok(-1),
+		id => fs.global_uid(id as _).map(|id| id as c_int),
+	};
+	let (ruid, euid, suid) = (global(ruid)?, global(euid)?, global(suid)?);
+	let ap = fs.access_profile;
+	if !fs.is_privileged() {
 		let allowed = [-1, ap.uid as _, ap.euid as _, ap.suid as _];
 		if !allowed.contains(&ruid) || !allowed.contains(&euid) || !allowed.contains(&suid) {
 			return Err(errno!(EPERM));
 		}
 	}
 	// Update
-	let mut fs = proc.fs.lock();
 	fs.access_profile.uid = match ruid {
 		-1 => ap.uid,
 		i => i as _,
@@ -24,18 +24,24 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
-pub fn setreuid(
-	Args((ruid, euid)): Args<(c_int, c_int)>,
-	ap: AccessProfile,
-	proc: Arc<Process>,
-) -> EResult<usize> {
+#[syscall(id32 = 0x046, id32 = 0x0cb, id64 = 0x071)]
+pub fn setreuid(Args((ruid, euid)): Args<(c_int, c_int)>, proc: Arc<Process>) -> EResult<usize> {
 	// Validation
 	if ruid < -1 || euid < -1 {
 		return Err(errno!(EINVAL));
 	}
-	if !ap.is_privileged() && ![-1, ap.uid as _, ap.euid as _].contains(&ruid)
+	let mut fs = proc.fs.lock();
+	// Translate to IDs of the initial user namespace
+	let global = |id: c_int| match id {
+		-1 => Ok(-1),
+		id => fs.global_uid(id as _).map(|id| id as c_int),
+	};
+	let (ruid, euid) = (global(ruid)?, global(euid)?);
+	let ap = fs.access_profile;
+	if !fs.is_privileged() && ![-1, ap.uid as _, ap.euid as _].contains(&ruid)
 		|| ![-1, ap.uid as _, ap.euid as _, ap.suid as _].contains(&euid)
 	{
 		return Err(errno!(EPERM));
@@ -49,7 +55,6 @@ pub fn setreuid(
 		-1 => ap.euid,
 		i => i as _,
 	};
-	let mut fs = proc.fs.lock();
 	fs.access_profile.uid = new_ruid;
 	fs.access_profile.euid = new_euid;
 	if new_ruid != ap.uid || new_euid != ap.uid {
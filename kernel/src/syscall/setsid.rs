@@ -0,0 +1,29 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `setsid` system call creates a new session with the current process as its leader.
+
+use crate::process::Process;
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+#[syscall(id32 = 0x042, id64 = 0x070)]
+pub fn setsid(proc: Arc<Process>) -> EResult<usize> {
+	proc.set_sid()?;
+	Ok(proc.get_pid() as _)
+}
@@ -25,12 +25,14 @@ use crate::{
 	syscall::Args,
 };
 use core::{any::Any, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x16e, id64 = 0x036)]
 pub fn setsockopt(
 	Args((sockfd, level, optname, optval, optlen)): Args<(
 		c_int,
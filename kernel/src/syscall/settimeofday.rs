@@ -0,0 +1,59 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `settimeofday` system call sets the time of the real time clock.
+
+use crate::{
+	file::perm::AccessProfile,
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+	time::{
+		clock,
+		unit::{TimeUnit, Timeval, Timeval32},
+	},
+};
+use core::{ffi::c_void, fmt::Debug};
+use macros::syscall;
+use utils::{errno, errno::EResult};
+
+fn do_settimeofday<T: TimeUnit + Debug>(tv: SyscallPtr<T>, ap: AccessProfile) -> EResult<usize> {
+	if !ap.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	// The timezone is obsolete, thus it is ignored
+	if let Some(tv) = tv.copy_from_user()? {
+		clock::set_realtime(tv.to_nano());
+	}
+	Ok(0)
+}
+
+#[syscall(id64 = 0x0a4)]
+pub fn settimeofday(
+	Args((tv, _tz)): Args<(SyscallPtr<Timeval>, *const c_void)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_settimeofday(tv, ap)
+}
+
+#[syscall(id32 = 0x04f)]
+pub fn compat_settimeofday(
+	Args((tv, _tz)): Args<(SyscallPtr<Timeval32>, *const c_void)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_settimeofday(tv, ap)
+}
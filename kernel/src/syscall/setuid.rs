@@ -19,12 +19,17 @@
 //! The `setuid` syscall sets the UID of the process's owner.
 
 use crate::{file::perm::Uid, process::Process, syscall::Args};
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x017, id32 = 0x0d5, id64 = 0x069)]
 pub fn setuid(Args(uid): Args<Uid>, proc: Arc<Process>) -> EResult<usize> {
-	proc.fs.lock().access_profile.set_uid(uid)?;
+	let mut fs = proc.fs.lock();
+	let uid = fs.global_uid(uid)?;
+	let privileged = fs.is_privileged();
+	fs.access_profile.set_uid(uid, privileged)?;
 	Ok(0)
 }
@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmat` system call attaches a System V shared memory segment to the memory space of the
+//! current process.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		shm,
+		shm::{ShmAttach, SHMLBA, SHM_EXEC, SHM_RDONLY, SHM_RND},
+		IpcObject,
+	},
+	memory::VirtAddr,
+	process::{
+		mem_space::{MapConstraint, MemSpace, PROT_EXEC, PROT_READ, PROT_WRITE},
+		Process,
+	},
+	sync::mutex::IntMutex,
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x18d, id64 = 0x01e)]
+pub fn shmat(
+	Args((shmid, shmaddr, shmflg)): Args<(c_int, VirtAddr, c_int)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	let seg = shm::SEGMENTS.lock().get(shmid)?;
+	let (mut prot, mode) = if shmflg & SHM_RDONLY != 0 {
+		(PROT_READ, 0o4)
+	} else {
+		(PROT_READ | PROT_WRITE, 0o6)
+	};
+	if shmflg & SHM_EXEC != 0 {
+		prot |= PROT_EXEC;
+	}
+	if !ap.can_access_ipc(&seg.perm().lock(), mode) {
+		return Err(errno!(EACCES));
+	}
+	let constraint = if shmaddr.is_null() {
+		MapConstraint::None
+	} else if shmflg & SHM_RND != 0 {
+		MapConstraint::Fixed(shmaddr.down_align_to(SHMLBA))
+	} else if shmaddr.is_aligned_to(SHMLBA) {
+		MapConstraint::Fixed(shmaddr)
+	} else {
+		return Err(errno!(EINVAL));
+	};
+	let attach = ShmAttach::new(seg, proc.get_pid());
+	let addr = mem_space.lock().map_shm(constraint, prot, attach)?;
+	Ok(addr as _)
+}
@@ -0,0 +1,95 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmctl` system call performs control operations on a System V shared memory segment.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		shm,
+		shm::{CompatShmidDs, ShmidDs, SHM_DEST},
+		IpcObject, IPC_64, IPC_RMID, IPC_SET, IPC_STAT,
+	},
+	process::mem_space::copy::SyscallPtr,
+	syscall::Args,
+};
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
+
+fn do_shmctl<S: Debug + From<ShmidDs> + Into<ShmidDs>>(
+	shmid: c_int,
+	cmd: c_int,
+	buf: SyscallPtr<S>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	match cmd & !IPC_64 {
+		IPC_STAT => {
+			let seg = shm::SEGMENTS.lock().get(shmid)?;
+			if !ap.can_access_ipc(&seg.perm().lock(), 0o4) {
+				return Err(errno!(EACCES));
+			}
+			if buf.0.is_none() {
+				return Err(errno!(EFAULT));
+			}
+			buf.copy_to_user(&seg.stat().into())?;
+		}
+		IPC_SET => {
+			let ds: ShmidDs = buf.copy_from_user()?.ok_or(errno!(EFAULT))?.into();
+			let seg = shm::SEGMENTS.lock().get(shmid)?;
+			let mut perm = seg.perm().lock();
+			if !ap.is_ipc_owner(&perm) {
+				return Err(errno!(EPERM));
+			}
+			perm.set(&ds.shm_perm);
+			seg.changed();
+		}
+		IPC_RMID => {
+			let mut segs = shm::SEGMENTS.lock();
+			let seg = segs.get(shmid)?;
+			if !ap.is_ipc_owner(&seg.perm().lock()) {
+				return Err(errno!(EPERM));
+			}
+			segs.remove(shmid);
+			// The segment is freed when the last attachment is dropped
+			seg.perm().lock().mode |= SHM_DEST;
+			seg.changed();
+		}
+		_ => return Err(errno!(EINVAL)),
+	}
+	Ok(0)
+}
+
+#[syscall(id64 = 0x01f)]
+pub fn shmctl(
+	Args((shmid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<ShmidDs>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_shmctl(shmid, cmd, buf, ap)
+}
+
+#[syscall(id32 = 0x18c)]
+pub fn compat_shmctl(
+	Args((shmid, cmd, buf)): Args<(c_int, c_int, SyscallPtr<CompatShmidDs>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_shmctl(shmid, cmd, buf, ap)
+}
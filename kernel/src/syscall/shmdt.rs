@@ -0,0 +1,40 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmdt` system call detaches a System V shared memory segment from the memory space of the
+//! current process.
+
+use crate::{
+	memory::VirtAddr,
+	process::{mem_space::MemSpace, Process},
+	sync::mutex::IntMutex,
+	syscall::Args,
+};
+use macros::syscall;
+use utils::{errno::EResult, ptr::arc::Arc};
+
+#[syscall(id32 = 0x18e, id64 = 0x043)]
+pub fn shmdt(
+	Args(shmaddr): Args<VirtAddr>,
+	proc: Arc<Process>,
+	mem_space: Arc<IntMutex<MemSpace>>,
+) -> EResult<usize> {
+	let seg = mem_space.lock().unmap_shm(shmaddr)?;
+	seg.detached(proc.get_pid());
+	Ok(0)
+}
@@ -0,0 +1,64 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `shmget` system call returns the ID of a System V shared memory segment, creating it if
+//! required.
+
+use crate::{
+	file::perm::AccessProfile,
+	ipc::{
+		shm,
+		shm::{ShmSegment, SHMMAX, SHMMIN},
+		Key,
+	},
+	process::Process,
+	syscall::Args,
+};
+use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x18b, id64 = 0x01d)]
+pub fn shmget(
+	Args((key, size, shmflg)): Args<(Key, usize, c_int)>,
+	ap: AccessProfile,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	let id = shm::SEGMENTS.lock().get_or_create(
+		key,
+		shmflg,
+		&ap,
+		|seg| {
+			if unlikely(size > seg.size()) {
+				return Err(errno!(EINVAL));
+			}
+			Ok(())
+		},
+		|perm| {
+			if unlikely(!(SHMMIN..=SHMMAX).contains(&size)) {
+				return Err(errno!(EINVAL));
+			}
+			ShmSegment::new(perm, size, proc.get_pid())
+		},
+	)?;
+	Ok(id as _)
+}
@@ -25,6 +25,7 @@ use crate::{
 	syscall::Args,
 };
 use core::{any::Any, ffi::c_int};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -38,6 +39,7 @@ const SHUT_WR: c_int = 1;
 /// Both sides are shutdown.
 const SHUT_RDWR: c_int = 2;
 
+#[syscall(id32 = 0x175, id64 = 0x030)]
 pub fn shutdown(
 	Args((sockfd, how)): Args<(c_int, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -33,11 +33,13 @@ use core::{
 	mem::transmute,
 	ptr::null,
 };
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x030)]
 pub fn signal(
 	Args((signum, handler)): Args<(c_int, *const c_void)>,
 	proc: Arc<Process>,
@@ -30,24 +30,31 @@ use crate::{
 		signal::{ucontext, Signal},
 		Process,
 	},
-	syscall::FromSyscallArg,
+	syscall::{FromSyscallArg, SIGRETURN_ID},
 };
 use core::{intrinsics::unlikely, mem::size_of, ptr};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 };
 
-pub fn sigreturn(frame: &mut IntFrame) -> EResult<usize> {
+/// Restores the context saved on the stack by the signal handler's trampoline.
+///
+/// `compat_off` is the offset of the context from the stack pointer, for 32-bit processes.
+fn do_sigreturn(frame: &mut IntFrame, compat_off: usize) -> EResult<usize> {
 	let proc = Process::current();
 	// Retrieve and restore previous state
 	let stack_ptr = frame.get_stack_address();
-	if frame.is_compat() {
-		let ctx = SyscallPtr::<ucontext::UContext32>::from_ptr(stack_ptr)
+	let restart = if frame.is_compat() {
+		let ctx = SyscallPtr::<ucontext::UContext32>::from_ptr(stack_ptr + compat_off)
 			.copy_from_user()?
 			.ok_or_else(|| errno!(EFAULT))?;
 		ctx.restore_regs(&proc, frame);
+		ctx.uc_flags & ucontext::UC_RESTART != 0
 	} else {
+		#[cfg(target_arch = "x86")]
+		unreachable!();
 		#[cfg(target_arch = "x86_64")]
 		{
 			let ctx = SyscallPtr::<ucontext::UContext64>::from_ptr(stack_ptr)
@@ -57,12 +64,26 @@ pub fn sigreturn(frame: &mut IntFrame) -> EResult<usize> {
 			if unlikely(res.is_err()) {
 				proc.kill(Signal::SIGSEGV);
 			}
+			ctx.uc_flags & ucontext::UC_RESTART as u64 != 0
 		}
+	};
+	// The system call handler restarts the interrupted system call from the restored registers
+	if restart {
+		return Err(errno!(ERESTARTSYS));
 	}
 	// Left register untouched
 	Ok(frame.get_syscall_id())
 }
 
+#[syscall(id32 = SIGRETURN_ID)]
+pub fn sigreturn(frame: &mut IntFrame) -> EResult<usize> {
+	// The trampoline pops the signal number, so that the context is at the top of the stack
+	do_sigreturn(frame, 0)
+}
+
+#[syscall(id32 = 0x0ad, id64 = 0x00f)]
 pub fn rt_sigreturn(frame: &mut IntFrame) -> EResult<usize> {
-	sigreturn(frame)
+	// On 32-bit, the signal number and the pointers to the signal information and context are
+	// still on the stack
+	do_sigreturn(frame, size_of::<u32>() * 3)
 }
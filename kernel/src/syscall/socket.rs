@@ -20,13 +20,19 @@
 
 use crate::{
 	file,
-	file::{fd::FileDescriptorTable, perm::AccessProfile, socket::Socket, vfs, File},
-	net::{SocketDesc, SocketDomain, SocketType},
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		perm::AccessProfile,
+		socket::Socket,
+		vfs, File,
+	},
+	net::{SocketDesc, SocketDomain, SocketType, SOCK_CLOEXEC, SOCK_NONBLOCK},
 	process::Process,
 	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	boxed::Box,
 	errno,
@@ -34,13 +40,15 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x167, id64 = 0x029)]
 pub fn socket(
 	Args((domain, r#type, protocol)): Args<(c_int, c_int, c_int)>,
 	ap: AccessProfile,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	let sock_domain = SocketDomain::try_from(domain as u32)?;
-	let sock_type = SocketType::try_from(r#type as u32)?;
+	let flags = r#type & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+	let sock_type = SocketType::try_from((r#type & !flags) as u32)?;
 	// Check permissions
 	if !ap.can_use_sock_domain(&sock_domain) || !ap.can_use_sock_type(&sock_type) {
 		return Err(errno!(EACCES));
@@ -52,7 +60,16 @@ pub fn socket(
 	};
 	// Create socket
 	let sock = Arc::new(Socket::new(desc)?)?;
-	let file = File::open_floating(sock, file::O_RDWR)?;
-	let (sock_fd_id, _) = fds.lock().create_fd(0, file)?;
+	let mut file_flags = file::O_RDWR;
+	if flags & SOCK_NONBLOCK != 0 {
+		file_flags |= file::O_NONBLOCK;
+	}
+	let file = File::open_floating(sock, file_flags)?;
+	let fd_flags = if flags & SOCK_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (sock_fd_id, _) = fds.lock().create_fd(fd_flags, file)?;
 	Ok(sock_fd_id as _)
 }
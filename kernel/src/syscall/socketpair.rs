@@ -21,13 +21,19 @@
 
 use crate::{
 	file,
-	file::{fd::FileDescriptorTable, perm::AccessProfile, socket::Socket, vfs, File},
-	net::{SocketDesc, SocketDomain, SocketType},
-	process::{mem_space::copy::SyscallPtr, Process},
+	file::{
+		fd::{FileDescriptorTable, FD_CLOEXEC},
+		perm::AccessProfile,
+		socket::Socket,
+		vfs, File,
+	},
+	net::{SocketDesc, SocketDomain, SocketType, SOCK_CLOEXEC, SOCK_NONBLOCK},
+	process::{mem_space::copy::SyscallSlice, Process},
 	sync::mutex::Mutex,
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	boxed::Box,
 	errno,
@@ -35,13 +41,15 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x168, id64 = 0x035)]
 pub fn socketpair(
-	Args((domain, r#type, protocol, sv)): Args<(c_int, c_int, c_int, SyscallPtr<[c_int; 2]>)>,
+	Args((domain, r#type, protocol, sv)): Args<(c_int, c_int, c_int, SyscallSlice<c_int>)>,
 	ap: AccessProfile,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	let sock_domain = SocketDomain::try_from(domain as u32)?;
-	let sock_type = SocketType::try_from(r#type as u32)?;
+	let flags = r#type & (SOCK_NONBLOCK | SOCK_CLOEXEC);
+	let sock_type = SocketType::try_from((r#type & !flags) as u32)?;
 	// Check permissions
 	if !ap.can_use_sock_domain(&sock_domain) || !ap.can_use_sock_type(&sock_type) {
 		return Err(errno!(EACCES));
@@ -51,12 +59,21 @@ pub fn socketpair(
 		type_: sock_type,
 		protocol,
 	};
-	// Create socket
-	let sock = Arc::new(Socket::new(desc)?)?;
-	let file0 = File::open_floating(sock.clone(), file::O_RDWR)?;
-	let file1 = File::open_floating(sock, file::O_RDWR)?;
+	// Create sockets
+	let (sock0, sock1) = Socket::pair(desc)?;
+	let mut file_flags = file::O_RDWR;
+	if flags & SOCK_NONBLOCK != 0 {
+		file_flags |= file::O_NONBLOCK;
+	}
+	let file0 = File::open_floating(Arc::new(sock0)?, file_flags)?;
+	let file1 = File::open_floating(Arc::new(sock1)?, file_flags)?;
 	// Create file descriptors
-	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(file0, file1)?;
-	sv.copy_to_user(&[fd0_id as _, fd1_id as _])?;
+	let fd_flags = if flags & SOCK_CLOEXEC != 0 {
+		FD_CLOEXEC
+	} else {
+		0
+	};
+	let (fd0_id, fd1_id) = fds.lock().create_fd_pair(fd_flags, file0, file1)?;
+	sv.copy_to_user(0, &[fd0_id as _, fd1_id as _])?;
 	Ok(0)
 }
@@ -43,6 +43,7 @@ use core::{
 	ffi::{c_int, c_long, c_uint, c_ushort},
 	intrinsics::unlikely,
 };
+use macros::syscall;
 use utils::{collections::path::PathBuf, errno, errno::EResult, ptr::arc::Arc};
 
 /// Status of a file, 32 bit version.
@@ -177,6 +178,7 @@ fn do_stat64(stat: Stat, entry: Option<&vfs::Entry>, statbuf: SyscallPtr<Stat64>
 	})
 }
 
+#[syscall(id32 = 0x06a)]
 pub fn stat(
 	Args((pathname, statbuf)): Args<(SyscallString, SyscallPtr<Stat32>)>,
 	rs: ResolutionSettings,
@@ -189,6 +191,7 @@ pub fn stat(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x0c3, id64 = 0x004)]
 pub fn stat64(
 	Args((pathname, statbuf)): Args<(SyscallString, SyscallPtr<Stat64>)>,
 	rs: ResolutionSettings,
@@ -201,6 +204,7 @@ pub fn stat64(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x06c)]
 pub fn fstat(
 	Args((fd, statbuf)): Args<(c_int, SyscallPtr<Stat32>)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -212,6 +216,7 @@ pub fn fstat(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x0c5, id64 = 0x005)]
 pub fn fstat64(
 	Args((fd, statbuf)): Args<(c_int, SyscallPtr<Stat64>)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -223,6 +228,7 @@ pub fn fstat64(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x06b)]
 pub fn lstat(
 	Args((pathname, statbuf)): Args<(SyscallString, SyscallPtr<Stat32>)>,
 	rs: ResolutionSettings,
@@ -239,6 +245,7 @@ pub fn lstat(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x0c4, id64 = 0x006)]
 pub fn lstat64(
 	Args((pathname, statbuf)): Args<(SyscallString, SyscallPtr<Stat64>)>,
 	rs: ResolutionSettings,
@@ -329,6 +336,7 @@ pub struct Statx {
 	__padding1: [u32; 19],
 }
 
+#[syscall(id32 = 0x17f, id64 = 0x14c)]
 pub fn statx(
 	Args((dirfd, pathname, flags, _mask, statxbuff)): Args<(
 		c_int,
@@ -19,28 +19,115 @@
 //! The `statfs` system call returns information about a mounted file system.
 
 use crate::{
-	file::{fs::Statfs, vfs, vfs::ResolutionSettings},
-	process::{
-		mem_space::copy::{SyscallPtr, SyscallString},
-		Process,
+	file::{
+		fs::{Fsid, Statfs},
+		vfs,
+		vfs::ResolutionSettings,
 	},
+	process::mem_space::copy::{SyscallPtr, SyscallString},
 	syscall::Args,
 };
+use core::{fmt::Debug, intrinsics::unlikely};
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
-pub(super) fn do_statfs(
+/// Compatibility version of [`Statfs`], used by the `statfs` and `fstatfs` system calls.
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct CompatStatfs {
+	f_type: u32,
+	f_bsize: u32,
+	f_blocks: u32,
+	f_bfree: u32,
+	f_bavail: u32,
+	f_files: u32,
+	f_ffree: u32,
+	f_fsid: Fsid,
+	f_namelen: u32,
+	f_frsize: u32,
+	f_flags: u32,
+	f_spare: [u32; 4],
+}
+
+impl From<Statfs> for CompatStatfs {
+	fn from(stat: Statfs) -> Self {
+		Self {
+			f_type: stat.f_type as _,
+			f_bsize: stat.f_bsize as _,
+			f_blocks: stat.f_blocks.min(u32::MAX as _) as _,
+			f_bfree: stat.f_bfree.min(u32::MAX as _) as _,
+			f_bavail: stat.f_bavail.min(u32::MAX as _) as _,
+			f_files: stat.f_files.min(u32::MAX as _) as _,
+			f_ffree: stat.f_ffree.min(u32::MAX as _) as _,
+			f_fsid: stat.f_fsid,
+			f_namelen: stat.f_namelen as _,
+			f_frsize: stat.f_frsize as _,
+			f_flags: stat.f_flags as _,
+			f_spare: [0; 4],
+		}
+	}
+}
+
+/// Compatibility version of [`Statfs`], used by the `statfs64` and `fstatfs64` system calls.
+#[allow(missing_docs)]
+#[repr(C, packed(4))]
+#[derive(Debug)]
+pub struct CompatStatfs64 {
+	f_type: u32,
+	f_bsize: u32,
+	f_blocks: u64,
+	f_bfree: u64,
+	f_bavail: u64,
+	f_files: u64,
+	f_ffree: u64,
+	f_fsid: Fsid,
+	f_namelen: u32,
+	f_frsize: u32,
+	f_flags: u32,
+	f_spare: [u32; 4],
+}
+
+impl From<Statfs> for CompatStatfs64 {
+	fn from(stat: Statfs) -> Self {
+		Self {
+			f_type: stat.f_type as _,
+			f_bsize: stat.f_bsize as _,
+			f_blocks: stat.f_blocks,
+			f_bfree: stat.f_bfree,
+			f_bavail: stat.f_bavail,
+			f_files: stat.f_files,
+			f_ffree: stat.f_ffree,
+			f_fsid: stat.f_fsid,
+			f_namelen: stat.f_namelen as _,
+			f_frsize: stat.f_frsize as _,
+			f_flags: stat.f_flags as _,
+			f_spare: [0; 4],
+		}
+	}
+}
+
+/// Writes `stat` to the userspace pointer `buf`.
+pub(super) fn write_statfs<S: Debug + From<Statfs>>(
+	stat: Statfs,
+	buf: SyscallPtr<S>,
+) -> EResult<usize> {
+	if unlikely(buf.0.is_none()) {
+		return Err(errno!(EFAULT));
+	}
+	buf.copy_to_user(&stat.into())?;
+	Ok(0)
+}
+
+pub(super) fn do_statfs<S: Debug + From<Statfs>>(
 	path: SyscallString,
-	buf: SyscallPtr<Statfs>,
+	buf: SyscallPtr<S>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
-	let rs = ResolutionSettings {
-		follow_link: false,
-		..rs
-	};
 	let path = path.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
 	let path = PathBuf::try_from(path)?;
 	let stat = vfs::get_file_from_path(&path, &rs)?
@@ -48,14 +135,21 @@ pub(super) fn do_statfs(
 		.fs
 		.ops
 		.get_stat()?;
-	// Write structure to userspace
-	buf.copy_to_user(&stat)?;
-	Ok(0)
+	write_statfs(stat, buf)
 }
 
+#[syscall(id64 = 0x089)]
 pub fn statfs(
 	Args((path, buf)): Args<(SyscallString, SyscallPtr<Statfs>)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
 	do_statfs(path, buf, rs)
 }
+
+#[syscall(id32 = 0x063)]
+pub fn compat_statfs(
+	Args((path, buf)): Args<(SyscallString, SyscallPtr<CompatStatfs>)>,
+	rs: ResolutionSettings,
+) -> EResult<usize> {
+	do_statfs(path, buf, rs)
+}
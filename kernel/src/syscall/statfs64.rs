@@ -18,19 +18,26 @@
 
 //! The `statfs64` system call returns information about a mounted file system.
 
-use super::statfs::do_statfs;
+use super::statfs::{do_statfs, CompatStatfs64};
 use crate::{
-	file::{fs::Statfs, vfs::ResolutionSettings},
+	file::vfs::ResolutionSettings,
 	process::mem_space::copy::{SyscallPtr, SyscallString},
 	syscall::Args,
 };
-use utils::errno::EResult;
+use core::{intrinsics::unlikely, mem::size_of};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+};
 
-// TODO Check args types
+#[syscall(id32 = 0x10c)]
 pub fn statfs64(
-	Args((path, _sz, buf)): Args<(SyscallString, usize, SyscallPtr<Statfs>)>,
+	Args((path, sz, buf)): Args<(SyscallString, usize, SyscallPtr<CompatStatfs64>)>,
 	rs: ResolutionSettings,
 ) -> EResult<usize> {
-	// TODO Use `sz`
+	if unlikely(sz != size_of::<CompatStatfs64>()) {
+		return Err(errno!(EINVAL));
+	}
 	do_statfs(path, buf, rs)
 }
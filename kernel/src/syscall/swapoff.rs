@@ -0,0 +1,45 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `swapoff` system call deactivates a swap area.
+
+use crate::{
+	file::{vfs, vfs::ResolutionSettings, File, O_RDWR},
+	memory::swap,
+	process::mem_space::copy::SyscallString,
+	syscall::Args,
+};
+use macros::syscall;
+use utils::{
+	collections::path::PathBuf,
+	errno,
+	errno::{EResult, Errno},
+};
+
+#[syscall(id32 = 0x073, id64 = 0x0a8)]
+pub fn swapoff(Args(path): Args<SyscallString>, rs: ResolutionSettings) -> EResult<usize> {
+	if !rs.access_profile.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	let path = path.copy_from_user()?.ok_or(errno!(EFAULT))?;
+	let path = PathBuf::try_from(path)?;
+	let ent = vfs::get_file_from_path(&path, &rs)?;
+	let file = File::open_entry(ent, O_RDWR)?;
+	swap::swapoff(&file)?;
+	Ok(0)
+}
@@ -25,6 +25,7 @@ use crate::{
 	syscall::{symlinkat::symlinkat, util::at::AT_FDCWD, Args},
 	time::clock::current_time_ns,
 };
+use macros::syscall;
 use utils::{
 	collections::path::{Path, PathBuf},
 	errno,
@@ -33,6 +34,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x053, id64 = 0x058)]
 pub fn symlink(
 	Args((target, linkpath)): Args<(SyscallString, SyscallString)>,
 	rs: ResolutionSettings,
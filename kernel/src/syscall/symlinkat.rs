@@ -32,6 +32,7 @@ use crate::{
 	time::clock::{current_time_ns, current_time_sec, Clock},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -40,6 +41,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x130, id64 = 0x10a)]
 pub fn symlinkat(
 	Args((target, newdirfd, linkpath)): Args<(SyscallString, c_int, SyscallString)>,
 	rs: ResolutionSettings,
@@ -19,13 +19,14 @@
 //! Filesystem synchronization system calls.
 
 use crate::{
-	file::{fd::FileDescriptorTable, vfs},
+	file::{fd::FileDescriptorTable, vfs, vfs::mountpoint},
 	memory::VirtAddr,
 	process::mem_space::MemSpace,
 	sync::mutex::{IntMutex, Mutex},
 	syscall::Args,
 };
 use core::{ffi::c_int, intrinsics::unlikely};
+use macros::syscall;
 use utils::{errno, errno::EResult, limits::PAGE_SIZE, ptr::arc::Arc};
 
 /// Schedules a synchronization and returns directly
@@ -35,11 +36,16 @@ const MS_SYNC: i32 = 0b010;
 /// Invalidates other mappings of the same file, so they can be updated
 const MS_INVALIDATE: i32 = 0b100;
 
+#[syscall(id32 = 0x024, id64 = 0x0a2)]
 pub fn sync() -> EResult<usize> {
-	// TODO sync all files on the VFS
+	for mp in mountpoint::MOUNT_POINTS.values()? {
+		// `sync` cannot report errors
+		let _ = mp.fs.sync();
+	}
 	Ok(0)
 }
 
+#[syscall(id32 = 0x158, id64 = 0x132)]
 pub fn syncfs(Args(fd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) -> EResult<usize> {
 	let fds = fds.lock();
 	if fd < 0 {
@@ -49,8 +55,7 @@ pub fn syncfs(Args(fd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) -> ER
 	let Some(ent) = &file.vfs_entry else {
 		return Ok(0);
 	};
-	let _fs = &ent.node().fs;
-	// TODO sync all files on the filesystem
+	ent.node().fs.sync()?;
 	Ok(0)
 }
 
@@ -66,6 +71,7 @@ fn do_fsync(fd: c_int, fds: Arc<Mutex<FileDescriptorTable>>, metadata: bool) ->
 	Ok(0)
 }
 
+#[syscall(id32 = 0x076, id64 = 0x04a)]
 pub fn fsync(Args(fd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) -> EResult<usize> {
 	do_fsync(fd, fds, true)
 }
@@ -74,6 +80,7 @@ pub fn fsyncdata(Args(fd): Args<c_int>, fds: Arc<Mutex<FileDescriptorTable>>) ->
 	do_fsync(fd, fds, false)
 }
 
+#[syscall(id32 = 0x090, id64 = 0x01a)]
 pub fn msync(
 	Args((addr, length, flags)): Args<(VirtAddr, usize, c_int)>,
 	mem_space: Arc<IntMutex<MemSpace>>,
@@ -0,0 +1,158 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `sysinfo` system call returns statistics about the system.
+
+use crate::{
+	memory::{stats::MEM_INFO, swap},
+	process::{
+		mem_space::copy::SyscallPtr,
+		scheduler::{FSHIFT, SCHEDULER},
+	},
+	syscall::Args,
+	time::clock::{current_time_ns, Clock},
+};
+use core::{
+	ffi::{c_long, c_ulong},
+	fmt::Debug,
+	mem::size_of,
+};
+use macros::syscall;
+use utils::{errno::EResult, limits::PAGE_SIZE};
+
+/// The number of bits of precision of load averages returned to userspace.
+const SI_LOAD_SHIFT: u32 = 16;
+
+/// Userspace structure storing statistics about the system.
+#[repr(C)]
+#[derive(Debug)]
+pub struct Sysinfo {
+	/// Seconds since boot.
+	uptime: c_long,
+	/// The 1, 5 and 15 minutes load averages, in fixed-point (see [`SI_LOAD_SHIFT`]).
+	loads: [c_ulong; 3],
+	/// Total usable main memory size.
+	totalram: c_ulong,
+	/// Available memory size.
+	freeram: c_ulong,
+	/// Amount of shared memory.
+	sharedram: c_ulong,
+	/// Memory used by buffers.
+	bufferram: c_ulong,
+	/// Total swap space size.
+	totalswap: c_ulong,
+	/// Swap space still available.
+	freeswap: c_ulong,
+	/// Number of current processes.
+	procs: u16,
+	/// Padding.
+	pad: u16,
+	/// Total high memory size.
+	totalhigh: c_ulong,
+	/// Available high memory size.
+	freehigh: c_ulong,
+	/// Memory unit size in bytes.
+	mem_unit: u32,
+	/// Padding.
+	_f: [u8; 20 - 2 * size_of::<c_ulong>() - size_of::<u32>()],
+}
+
+/// Compatibility version of [`Sysinfo`].
+#[allow(missing_docs)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct CompatSysinfo {
+	uptime: i32,
+	loads: [u32; 3],
+	totalram: u32,
+	freeram: u32,
+	sharedram: u32,
+	bufferram: u32,
+	totalswap: u32,
+	freeswap: u32,
+	procs: u16,
+	pad: u16,
+	totalhigh: u32,
+	freehigh: u32,
+	mem_unit: u32,
+	_f: [u8; 8],
+}
+
+impl From<Sysinfo> for CompatSysinfo {
+	fn from(info: Sysinfo) -> Self {
+		Self {
+			uptime: info.uptime as _,
+			loads: info.loads.map(|l| l as _),
+			totalram: info.totalram as _,
+			freeram: info.freeram as _,
+			sharedram: info.sharedram as _,
+			bufferram: info.bufferram as _,
+			totalswap: info.totalswap as _,
+			freeswap: info.freeswap as _,
+			procs: info.procs,
+			pad: 0,
+			totalhigh: info.totalhigh as _,
+			freehigh: info.freehigh as _,
+			mem_unit: info.mem_unit,
+			_f: [0; 8],
+		}
+	}
+}
+
+fn do_sysinfo<S: Debug + From<Sysinfo>>(info: SyscallPtr<S>) -> EResult<usize> {
+	let uptime = current_time_ns(Clock::Boottime) / 1_000_000_000;
+	let (loads, procs) = {
+		let mut sched = SCHEDULER.lock();
+		(sched.get_load_avg(), sched.processes_count())
+	};
+	let (totalram, freeram, bufferram) = {
+		let mem_info = MEM_INFO.lock();
+		(mem_info.mem_total, mem_info.mem_free, mem_info.inactive)
+	};
+	let (totalswap, freeswap) = swap::stats();
+	let sysinfo = Sysinfo {
+		uptime: uptime as _,
+		loads: loads.map(|l| (l << (SI_LOAD_SHIFT - FSHIFT)) as _),
+		// Memory amounts are in KiB
+		totalram: totalram as _,
+		freeram: freeram as _,
+		// TODO
+		sharedram: 0,
+		bufferram: bufferram as _,
+		totalswap: (totalswap * PAGE_SIZE / 1024) as _,
+		freeswap: (freeswap * PAGE_SIZE / 1024) as _,
+		procs: procs.min(u16::MAX as usize) as _,
+		pad: 0,
+		totalhigh: 0,
+		freehigh: 0,
+		mem_unit: 1024,
+		_f: Default::default(),
+	};
+	info.copy_to_user(&sysinfo.into())?;
+	Ok(0)
+}
+
+#[syscall(id64 = 0x063)]
+pub fn sysinfo(Args(info): Args<SyscallPtr<Sysinfo>>) -> EResult<usize> {
+	do_sysinfo(info)
+}
+
+#[syscall(id32 = 0x074)]
+pub fn compat_sysinfo(Args(info): Args<SyscallPtr<CompatSysinfo>>) -> EResult<usize> {
+	do_sysinfo(info)
+}
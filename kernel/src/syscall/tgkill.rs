@@ -0,0 +1,60 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `tgkill` system call allows to send a signal to a specific thread of a thread group.
+
+use crate::{
+	file::perm::AccessProfile,
+	process::{
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_TKILL},
+		Process,
+	},
+	syscall::Args,
+};
+use core::ffi::c_int;
+use macros::syscall;
+use utils::{errno, errno::EResult, ptr::arc::Arc};
+
+#[syscall(id32 = 0x10e, id64 = 0x0ea)]
+pub fn tgkill(
+	Args((tgid, tid, sig)): Args<(Pid, Pid, c_int)>,
+	proc: Arc<Process>,
+	access_profile: AccessProfile,
+) -> EResult<usize> {
+	if tgid == 0 || tid == 0 {
+		return Err(errno!(EINVAL));
+	}
+	let signal = Signal::try_from(sig)?;
+	// IDs are given as seen from the namespace of the current process
+	let ns = proc.pid_ns();
+	let tgid = ns.global_pid(tgid).ok_or_else(|| errno!(ESRCH))?;
+	let tid = ns.global_pid(tid).ok_or_else(|| errno!(ESRCH))?;
+	let thread = Process::get_by_tid(tid)
+		.filter(|thread| thread.get_tgid() == tgid)
+		.ok_or_else(|| errno!(ESRCH))?;
+	if !access_profile.can_kill(&thread) {
+		return Err(errno!(EPERM));
+	}
+	thread.kill_info(SigInfo {
+		si_pid: thread.pid_ns().local_pid(proc.get_pid()).unwrap_or(0),
+		si_uid: access_profile.uid,
+		..SigInfo::new(signal, SI_TKILL)
+	});
+	Ok(0)
+}
@@ -20,8 +20,10 @@
 //! the UNIX Epoch.
 
 use crate::{
+	file::perm::AccessProfile,
 	process::{
 		mem_space::copy::SyscallPtr,
+		scheduler::SCHEDULER,
 		signal::{SigEvent, Signal, SIGEV_SIGNAL},
 		Process,
 	},
@@ -29,84 +31,284 @@ use crate::{
 	time::{
 		clock,
 		clock::{current_time_ns, current_time_sec, Clock},
-		sleep_for,
-		unit::{ClockIdT, ITimerspec32, TimeUnit, TimerT, Timespec, Timespec32},
+		sleep_for, sleep_until,
+		unit::{
+			ClockIdT, ITimerspec, ITimerspec32, TimeUnit, TimerT, Timespec, Timespec32, Timestamp,
+		},
 	},
 };
-use core::ffi::c_int;
+use core::{ffi::c_int, fmt::Debug};
+use macros::syscall;
 use utils::{errno, errno::EResult, ptr::arc::Arc};
 
 /// If set, the specified time is *not* relative to the timer's current counter.
 const TIMER_ABSTIME: c_int = 1;
 
+#[syscall(id64 = 0x0c9)]
+pub fn time(Args(tloc): Args<SyscallPtr<u64>>) -> EResult<usize> {
+	let time = current_time_sec(Clock::Realtime);
+	tloc.copy_to_user(&time)?;
+	Ok(time as _)
+}
+
 // TODO Watch for timestamp overflow
-pub fn time(Args(tloc): Args<SyscallPtr<u32>>) -> EResult<usize> {
-	let time = current_time_sec(Clock::Monotonic);
+#[syscall(id32 = 0x00d)]
+pub fn compat_time(Args(tloc): Args<SyscallPtr<u32>>) -> EResult<usize> {
+	let time = current_time_sec(Clock::Realtime);
 	tloc.copy_to_user(&(time as _))?;
 	Ok(time as _)
 }
 
+/// Returns the current value of the clock `clk` for the process `proc`, in nanoseconds.
+///
+/// If the clock is not implemented, the function returns `EINVAL`.
+fn clock_value(clk: Clock, proc: &Process) -> EResult<Timestamp> {
+	if clock::get_resolution(clk).is_none() {
+		return Err(errno!(EINVAL));
+	}
+	let ts = match clk {
+		// TODO distinguish threads once they are implemented
+		Clock::ProcessCputimeId | Clock::ThreadCputimeId => SCHEDULER.lock().get_cpu_time(proc),
+		_ => current_time_ns(clk),
+	};
+	Ok(ts)
+}
+
+#[syscall(id32 = 0x109, id64 = 0x0e4)]
 pub fn clock_gettime(
 	Args((clockid, tp)): Args<(ClockIdT, SyscallPtr<Timespec>)>,
+	proc: Arc<Process>,
 ) -> EResult<usize> {
 	let clk = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
-	let ts = current_time_ns(clk);
+	let ts = clock_value(clk, &proc)?;
 	tp.copy_to_user(&Timespec::from_nano(ts))?;
 	Ok(0)
 }
 
+#[syscall(id32 = 0x193)]
 pub fn clock_gettime64(
 	Args((clockid, tp)): Args<(ClockIdT, SyscallPtr<Timespec>)>,
+	proc: Arc<Process>,
 ) -> EResult<usize> {
 	let clock = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
-	let ts = current_time_ns(clock);
+	let ts = clock_value(clock, &proc)?;
 	tp.copy_to_user(&Timespec::from_nano(ts))?;
 	Ok(0)
 }
 
-pub fn nanosleep(
-	Args((req, rem)): Args<(SyscallPtr<Timespec32>, SyscallPtr<Timespec32>)>,
+fn do_clock_settime<T: TimeUnit + Debug>(
+	clockid: ClockIdT,
+	tp: SyscallPtr<T>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	let clk = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
+	let ts = tp.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// Only the real time clock can be set
+	if !matches!(clk, Clock::Realtime) {
+		return Err(errno!(EINVAL));
+	}
+	if !ap.is_privileged() {
+		return Err(errno!(EPERM));
+	}
+	clock::set_realtime(ts.to_nano());
+	Ok(0)
+}
+
+#[syscall(id32 = 0x194, id64 = 0x0e3)]
+pub fn clock_settime(
+	Args((clockid, tp)): Args<(ClockIdT, SyscallPtr<Timespec>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_clock_settime(clockid, tp, ap)
+}
+
+#[syscall(id32 = 0x108)]
+pub fn compat_clock_settime(
+	Args((clockid, tp)): Args<(ClockIdT, SyscallPtr<Timespec32>)>,
+	ap: AccessProfile,
+) -> EResult<usize> {
+	do_clock_settime(clockid, tp, ap)
+}
+
+fn do_clock_getres<T: TimeUnit + Debug>(clockid: ClockIdT, res: SyscallPtr<T>) -> EResult<usize> {
+	let res_val = Clock::from_id(clockid)
+		.and_then(clock::get_resolution)
+		.ok_or_else(|| errno!(EINVAL))?;
+	res.copy_to_user(&T::from_nano(res_val))?;
+	Ok(0)
+}
+
+#[syscall(id32 = 0x196, id64 = 0x0e5)]
+pub fn clock_getres(
+	Args((clockid, res)): Args<(ClockIdT, SyscallPtr<Timespec>)>,
+) -> EResult<usize> {
+	do_clock_getres(clockid, res)
+}
+
+#[syscall(id32 = 0x10a)]
+pub fn compat_clock_getres(
+	Args((clockid, res)): Args<(ClockIdT, SyscallPtr<Timespec32>)>,
+) -> EResult<usize> {
+	do_clock_getres(clockid, res)
+}
+
+/// Sleeps until the timestamp `ts`, in nanoseconds, is reached on `clock`.
+///
+/// If `absolute` is not set, `ts` is relative to the current time. In that case, the remaining
+/// time is written to `rem` if the sleep is interrupted by a signal.
+fn do_sleep<T: TimeUnit + Debug>(
+	clock: Clock,
+	absolute: bool,
+	ts: Timestamp,
+	rem: SyscallPtr<T>,
 ) -> EResult<usize> {
-	let delay = req
-		.copy_from_user()?
-		.ok_or_else(|| errno!(EFAULT))?
-		.to_nano();
 	let mut remain = 0;
-	let res = sleep_for(Clock::Monotonic, delay, &mut remain);
+	let res = if absolute {
+		sleep_until(clock, ts, &mut remain)
+	} else {
+		sleep_for(clock, ts, &mut remain)
+	};
 	match res {
 		Ok(_) => Ok(0),
 		Err(e) => {
-			rem.copy_to_user(&Timespec32::from_nano(remain))?;
+			if !absolute && e.as_int() == errno::EINTR {
+				rem.copy_to_user(&T::from_nano(remain))?;
+			}
 			Err(e)
 		}
 	}
 }
 
+fn do_nanosleep<T: TimeUnit + Debug>(req: SyscallPtr<T>, rem: SyscallPtr<T>) -> EResult<usize> {
+	let delay = req
+		.copy_from_user()?
+		.ok_or_else(|| errno!(EFAULT))?
+		.to_nano();
+	do_sleep(Clock::Monotonic, false, delay, rem)
+}
+
+#[syscall(id64 = 0x023)]
+pub fn nanosleep(
+	Args((req, rem)): Args<(SyscallPtr<Timespec>, SyscallPtr<Timespec>)>,
+) -> EResult<usize> {
+	do_nanosleep(req, rem)
+}
+
+#[syscall(id32 = 0x0a2)]
+pub fn compat_nanosleep(
+	Args((req, rem)): Args<(SyscallPtr<Timespec32>, SyscallPtr<Timespec32>)>,
+) -> EResult<usize> {
+	do_nanosleep(req, rem)
+}
+
+fn do_clock_nanosleep<T: TimeUnit + Debug>(
+	clockid: ClockIdT,
+	flags: c_int,
+	req: SyscallPtr<T>,
+	rem: SyscallPtr<T>,
+) -> EResult<usize> {
+	let clock = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
+	// Sleeping on a CPU-time clock is not supported
+	if matches!(clock, Clock::ProcessCputimeId | Clock::ThreadCputimeId)
+		|| clock::get_resolution(clock).is_none()
+	{
+		return Err(errno!(EINVAL));
+	}
+	let ts = req
+		.copy_from_user()?
+		.ok_or_else(|| errno!(EFAULT))?
+		.to_nano();
+	do_sleep(clock, flags & TIMER_ABSTIME != 0, ts, rem)
+}
+
+#[syscall(id32 = 0x197, id64 = 0x0e6)]
+pub fn clock_nanosleep(
+	Args((clockid, flags, req, rem)): Args<(
+		ClockIdT,
+		c_int,
+		SyscallPtr<Timespec>,
+		SyscallPtr<Timespec>,
+	)>,
+) -> EResult<usize> {
+	do_clock_nanosleep(clockid, flags, req, rem)
+}
+
+#[syscall(id32 = 0x10b)]
+pub fn compat_clock_nanosleep(
+	Args((clockid, flags, req, rem)): Args<(
+		ClockIdT,
+		c_int,
+		SyscallPtr<Timespec32>,
+		SyscallPtr<Timespec32>,
+	)>,
+) -> EResult<usize> {
+	do_clock_nanosleep(clockid, flags, req, rem)
+}
+
+#[syscall(id32 = 0x103, id64 = 0x0de)]
 pub fn timer_create(
-	Args((clockid, sevp, timerid)): Args<(ClockIdT, SyscallPtr<SigEvent>, SyscallPtr<TimerT>)>,
+	Args((clockid, sevp, timerid)): Args<(ClockIdT, SyscallPtr<SigEvent>, SyscallPtr<c_int>)>,
 	proc: Arc<Process>,
 ) -> EResult<usize> {
 	let clock = Clock::from_id(clockid).ok_or_else(|| errno!(EINVAL))?;
-	let timerid_val = timerid.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-	let sevp_val = sevp.copy_from_user()?.unwrap_or_else(|| SigEvent {
-		sigev_notify: SIGEV_SIGNAL,
-		sigev_signo: Signal::SIGALRM as _,
-		sigev_value: timerid_val,
-		sigev_notify_function: None,
-		sigev_notify_attributes: None,
-		sigev_notify_thread_id: proc.tid,
-	});
-	let id = proc.timer_manager.lock().create_timer(clock, sevp_val)?;
-	timerid.copy_to_user(&(id as _))?;
+	let sevp_val = sevp.copy_from_user()?;
+	let mut manager = proc.timer_manager.lock();
+	let id = manager.create_timer(clock, sevp_val)?;
+	if let Err(e) = timerid.copy_to_user(&(id as _)) {
+		// Do not leak the timer since userspace cannot know its ID
+		manager.delete_timer(id as _)?;
+		return Err(e);
+	}
 	Ok(0)
 }
 
+#[syscall(id32 = 0x107, id64 = 0x0e2)]
 pub fn timer_delete(Args(timerid): Args<TimerT>, proc: Arc<Process>) -> EResult<usize> {
 	proc.timer_manager.lock().delete_timer(timerid)?;
 	Ok(0)
 }
 
+fn do_timer_settime<T: TimeUnit>(
+	timerid: TimerT,
+	flags: c_int,
+	new_value: SyscallPtr<ITimerspec<T>>,
+	old_value: SyscallPtr<ITimerspec<T>>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	let new_value = new_value.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	// Get timer
+	let mut manager = proc.timer_manager.lock();
+	let timer = manager
+		.get_timer_mut(timerid)
+		.ok_or_else(|| errno!(EINVAL))?;
+	// Write old value
+	old_value.copy_to_user(&timer.get_time())?;
+	// Set new value
+	timer.set_time(
+		new_value.it_interval.to_nano(),
+		new_value.it_value.to_nano(),
+		flags & TIMER_ABSTIME != 0,
+	)?;
+	Ok(0)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x199, id64 = 0x0df)]
 pub fn timer_settime(
+	Args((timerid, flags, new_value, old_value)): Args<(
+		TimerT,
+		c_int,
+		SyscallPtr<ITimerspec>,
+		SyscallPtr<ITimerspec>,
+	)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_timer_settime(timerid, flags, new_value, old_value, proc)
+}
+
+#[allow(clippy::type_complexity)]
+#[syscall(id32 = 0x104)]
+pub fn compat_timer_settime(
 	Args((timerid, flags, new_value, old_value)): Args<(
 		TimerT,
 		c_int,
@@ -115,22 +317,43 @@ pub fn timer_settime(
 	)>,
 	proc: Arc<Process>,
 ) -> EResult<usize> {
-	// Get timer
+	do_timer_settime(timerid, flags, new_value, old_value, proc)
+}
+
+fn do_timer_gettime<T: TimeUnit>(
+	timerid: TimerT,
+	curr_value: SyscallPtr<ITimerspec<T>>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
 	let mut manager = proc.timer_manager.lock();
 	let timer = manager
 		.get_timer_mut(timerid)
 		.ok_or_else(|| errno!(EINVAL))?;
-	// Write old value
-	let old = timer.get_time();
-	old_value.copy_to_user(&old)?;
-	// Set new value
-	let mut new_value_val = new_value.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
-	if (flags & TIMER_ABSTIME) == 0 {
-		new_value_val.it_value = new_value_val.it_value + old.it_value;
-	}
-	timer.set_time(
-		new_value_val.it_interval.to_nano(),
-		new_value_val.it_value.to_nano(),
-	)?;
+	curr_value.copy_to_user(&timer.get_time())?;
 	Ok(0)
 }
+
+#[syscall(id32 = 0x198, id64 = 0x0e0)]
+pub fn timer_gettime(
+	Args((timerid, curr_value)): Args<(TimerT, SyscallPtr<ITimerspec>)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_timer_gettime(timerid, curr_value, proc)
+}
+
+#[syscall(id32 = 0x105)]
+pub fn compat_timer_gettime(
+	Args((timerid, curr_value)): Args<(TimerT, SyscallPtr<ITimerspec32>)>,
+	proc: Arc<Process>,
+) -> EResult<usize> {
+	do_timer_gettime(timerid, curr_value, proc)
+}
+
+#[syscall(id32 = 0x106, id64 = 0x0e1)]
+pub fn timer_getoverrun(Args(timerid): Args<TimerT>, proc: Arc<Process>) -> EResult<usize> {
+	let mut manager = proc.timer_manager.lock();
+	let timer = manager
+		.get_timer_mut(timerid)
+		.ok_or_else(|| errno!(EINVAL))?;
+	Ok(timer.get_overrun() as _)
+}
@@ -20,25 +20,38 @@
 
 use crate::{
 	file::perm::AccessProfile,
-	process::{pid::Pid, signal::Signal, Process},
+	process::{
+		pid::Pid,
+		signal::{SigInfo, Signal, SI_TKILL},
+		Process,
+	},
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x0ee, id64 = 0x0c8)]
 pub fn tkill(
 	Args((tid, sig)): Args<(Pid, c_int)>,
+	proc: Arc<Process>,
 	access_profile: AccessProfile,
 ) -> EResult<usize> {
 	let signal = Signal::try_from(sig)?;
+	// The TID is given as seen from the namespace of the current process
+	let tid = proc.pid_ns().global_pid(tid).ok_or(errno!(ESRCH))?;
 	let thread = Process::get_by_tid(tid).ok_or(errno!(ESRCH))?;
 	if !access_profile.can_kill(&thread) {
 		return Err(errno!(EPERM));
 	}
-	thread.kill(signal);
+	thread.kill_info(SigInfo {
+		si_pid: thread.pid_ns().local_pid(proc.get_pid()).unwrap_or(0),
+		si_uid: access_profile.uid,
+		..SigInfo::new(signal, SI_TKILL)
+	});
 	Ok(0)
 }
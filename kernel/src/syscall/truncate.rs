@@ -23,12 +23,14 @@ use crate::{
 	process::{mem_space::copy::SyscallString, Process},
 	syscall::Args,
 };
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x05c, id64 = 0x04c)]
 pub fn truncate(Args((path, length)): Args<(SyscallString, usize)>) -> EResult<usize> {
 	let proc = Process::current();
 	let rs = ResolutionSettings::for_process(&proc, true);
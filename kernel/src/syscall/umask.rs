@@ -20,11 +20,13 @@
 
 use crate::{file, process::Process, syscall::Args};
 use core::{mem, sync::atomic};
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x03c, id64 = 0x05f)]
 pub fn umask(Args(mask): Args<file::Mode>, proc: Arc<Process>) -> EResult<usize> {
 	let prev = proc
 		.fs
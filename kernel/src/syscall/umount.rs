@@ -28,16 +28,19 @@ use crate::{
 	syscall::Args,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
 	errno::{EResult, Errno},
 };
 
+#[syscall(id32 = 0x016)]
 pub fn umount(Args(target): Args<SyscallString>, rs: ResolutionSettings) -> EResult<usize> {
 	umount2(Args((target, 0)), rs)
 }
 
+#[syscall(id32 = 0x034, id64 = 0x0a6)]
 pub fn umount2(
 	Args((target, _flags)): Args<(SyscallString, c_int)>,
 	rs: ResolutionSettings,
@@ -22,11 +22,13 @@ use crate::{
 	arch::ARCH,
 	process::{mem_space::copy::SyscallPtr, Process},
 	syscall::Args,
-	HOSTNAME, NAME, VERSION,
+	NAME, VERSION,
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
+	ptr::arc::Arc,
 };
 
 /// The length of a field of the utsname structure.
@@ -46,21 +48,27 @@ pub struct Utsname {
 	version: [u8; UTSNAME_LENGTH],
 	/// Hardware identifier.
 	machine: [u8; UTSNAME_LENGTH],
+	/// NIS domain name.
+	domainname: [u8; UTSNAME_LENGTH],
 }
 
-pub fn uname(Args(buf): Args<SyscallPtr<Utsname>>) -> EResult<usize> {
+#[syscall(id32 = 0x07a, id64 = 0x03f)]
+pub fn uname(Args(buf): Args<SyscallPtr<Utsname>>, proc: Arc<Process>) -> EResult<usize> {
 	let mut utsname = Utsname {
 		sysname: [0; UTSNAME_LENGTH],
 		nodename: [0; UTSNAME_LENGTH],
 		release: [0; UTSNAME_LENGTH],
 		version: [0; UTSNAME_LENGTH],
 		machine: [0; UTSNAME_LENGTH],
+		domainname: [0; UTSNAME_LENGTH],
 	};
+	let ns = proc.uts_ns();
 	utils::slice_copy(NAME.as_bytes(), &mut utsname.sysname);
-	utils::slice_copy(&HOSTNAME.lock(), &mut utsname.nodename);
+	utils::slice_copy(&ns.hostname.lock(), &mut utsname.nodename);
 	utils::slice_copy(VERSION.as_bytes(), &mut utsname.release);
 	utils::slice_copy(&[], &mut utsname.version);
 	utils::slice_copy(ARCH.as_bytes(), &mut utsname.machine);
+	utils::slice_copy(&ns.domainname.lock(), &mut utsname.domainname);
 	buf.copy_to_user(&utsname)?;
 	Ok(0)
 }
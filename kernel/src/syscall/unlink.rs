@@ -27,12 +27,14 @@ use crate::{
 	sync::mutex::Mutex,
 	syscall::{unlinkat::do_unlinkat, util::at::AT_FDCWD},
 };
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x00a, id64 = 0x057)]
 pub fn unlink(
 	Args(pathname): Args<SyscallString>,
 	rs: ResolutionSettings,
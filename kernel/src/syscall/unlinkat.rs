@@ -32,6 +32,7 @@ use crate::{
 	syscall::{util::at::AT_EMPTY_PATH, Args},
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -70,6 +71,7 @@ pub fn do_unlinkat(
 	Ok(0)
 }
 
+#[syscall(id32 = 0x12d, id64 = 0x107)]
 pub fn unlinkat(
 	Args((dirfd, pathname, flags)): Args<(c_int, SyscallString, c_int)>,
 	rs: ResolutionSettings,
@@ -0,0 +1,58 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The `unshare` system call allows a process to disassociate parts of its execution context
+//! that are currently shared with other processes.
+
+use crate::{
+	process::Process,
+	syscall::{
+		clone::{CLONE_FS, CLONE_NEWNS, CLONE_NEWUSER, CLONE_NEWUTS},
+		Args,
+	},
+};
+use core::{ffi::c_ulong, intrinsics::unlikely};
+use macros::syscall;
+use utils::{
+	errno,
+	errno::{EResult, Errno},
+	ptr::arc::Arc,
+};
+
+#[syscall(id32 = 0x136, id64 = 0x110)]
+pub fn unshare(Args(flags): Args<c_ulong>, proc: Arc<Process>) -> EResult<usize> {
+	// The filesystem information is never shared between processes, so `CLONE_FS` is a no-op
+	if unlikely(flags & !(CLONE_FS | CLONE_NEWNS | CLONE_NEWUTS | CLONE_NEWUSER) != 0) {
+		return Err(errno!(EINVAL));
+	}
+	let mut fs = proc.fs.lock();
+	// The user namespace is created first, so that the other namespaces are owned by it
+	if flags & CLONE_NEWUSER != 0 {
+		fs.unshare_user_ns()?;
+	}
+	if unlikely(flags & (CLONE_NEWNS | CLONE_NEWUTS) != 0 && !fs.is_privileged()) {
+		return Err(errno!(EPERM));
+	}
+	if flags & CLONE_NEWNS != 0 {
+		fs.unshare_mount_ns()?;
+	}
+	if flags & CLONE_NEWUTS != 0 {
+		proc.set_uts_ns(proc.uts_ns().copy(fs.user_ns.clone())?);
+	}
+	Ok(0)
+}
@@ -19,3 +19,49 @@
 //! Utility functions for system calls.
 
 pub mod at;
+
+use crate::process::{
+	mem_space::copy::{SyscallPtr, SyscallSlice},
+	signal::SigSet,
+	Process,
+};
+use core::{ffi::c_int, mem};
+use utils::{errno, errno::EResult};
+
+/// Runs `f` with the mask of blocked signals of `proc` replaced by `sigmask`, then restores the
+/// previous mask.
+///
+/// If `sigmask` is `None`, the mask is left unchanged.
+///
+/// This is used by system calls that wait with a different signal mask, such as `pselect6`.
+pub fn with_sigmask<T, F: FnOnce() -> T>(proc: &Process, sigmask: Option<SigSet>, f: F) -> T {
+	let Some(sigmask) = sigmask else {
+		return f();
+	};
+	let prev = mem::replace(&mut proc.signal.lock().sigmask, sigmask);
+	let res = f();
+	// TODO on interruption, restore the mask only after the signal handler has run
+	proc.signal.lock().sigmask = prev;
+	res
+}
+
+/// Writes the socket address `name` to `addr`, whose size is read from `addrlen`.
+///
+/// The address is truncated to fit, and the actual size of the address is written back to
+/// `addrlen`.
+///
+/// If `addr` is NULL, the function does nothing.
+pub fn copy_sockaddr_to_user(
+	addr: &SyscallSlice<u8>,
+	addrlen: &SyscallPtr<c_int>,
+	name: &[u8],
+) -> EResult<()> {
+	if addr.as_ptr().is_null() {
+		return Ok(());
+	}
+	let len = addrlen.copy_from_user()?.ok_or_else(|| errno!(EFAULT))?;
+	let len: usize = len.try_into().map_err(|_| errno!(EINVAL))?;
+	addr.copy_to_user(0, &name[..len.min(name.len())])?;
+	addrlen.copy_to_user(&(name.len() as _))?;
+	Ok(())
+}
@@ -41,6 +41,7 @@ use crate::{
 	tty::vga::DEFAULT_COLOR,
 };
 use core::ffi::c_int;
+use macros::syscall;
 use utils::{
 	collections::path::PathBuf,
 	errno,
@@ -48,6 +49,7 @@ use utils::{
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x140, id64 = 0x118)]
 pub fn utimensat(
 	Args((dirfd, pathname, times, flags)): Args<(
 		c_int,
@@ -66,7 +68,7 @@ pub fn utimensat(
 		.copy_from_user()?
 		.map(|[atime, mtime]| (atime.to_nano(), mtime.to_nano()))
 		.unwrap_or_else(|| {
-			let ts = current_time_ns(Clock::Monotonic);
+			let ts = current_time_ns(Clock::Realtime);
 			(ts, ts)
 		});
 	// Get file
@@ -32,11 +32,13 @@ use crate::{
 	},
 };
 use core::ptr::null_mut;
+use macros::syscall;
 use utils::{
 	errno::{EResult, Errno},
 	ptr::arc::Arc,
 };
 
+#[syscall(id32 = 0x0be, id64 = 0x03a)]
 pub fn vfork(proc: Arc<Process>, frame: &mut IntFrame) -> EResult<usize> {
 	clone(
 		Args((
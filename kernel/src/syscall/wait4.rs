@@ -21,8 +21,10 @@
 use super::{waitpid, Args};
 use crate::process::{mem_space::copy::SyscallPtr, rusage::Rusage, Process};
 use core::ffi::c_int;
+use macros::syscall;
 use utils::errno::EResult;
 
+#[syscall(id32 = 0x072, id64 = 0x03d)]
 pub fn wait4(
 	Args((pid, wstatus, options, rusage)): Args<(
 		c_int,
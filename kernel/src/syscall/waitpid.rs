@@ -27,6 +27,7 @@ use crate::{
 	syscall::{waitpid::scheduler::SCHEDULER, Args},
 };
 use core::{ffi::c_int, iter};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -148,13 +149,23 @@ pub fn do_waitpid(
 	options: i32,
 	rusage: SyscallPtr<Rusage>,
 ) -> EResult<usize> {
+	// PIDs are given in the namespace of the current process
+	let pid = {
+		let ns = Process::current().pid_ns().clone();
+		let global = |pid: i32| ns.global_pid(pid as _).ok_or_else(|| errno!(ECHILD));
+		match pid {
+			..-1 => -(global(-pid)? as i32),
+			1.. => global(pid)? as i32,
+			_ => pid,
+		}
+	};
 	loop {
 		{
 			let proc = Process::current();
 			let result = get_waitable(&proc, pid, &wstatus, options, &rusage)?;
 			// On success, return
 			if let Some(p) = result {
-				return Ok(p as _);
+				return Ok(proc.pid_ns().local_pid(p).unwrap_or(0) as _);
 			}
 			// If the flag is set, do not wait
 			if options & WNOHANG != 0 {
@@ -168,6 +179,7 @@ pub fn do_waitpid(
 	}
 }
 
+#[syscall(id32 = 0x007)]
 pub fn waitpid(
 	Args((pid, wstatus, options)): Args<(c_int, SyscallPtr<c_int>, c_int)>,
 ) -> EResult<usize> {
@@ -26,6 +26,7 @@ use crate::{
 	syscall::Signal,
 };
 use core::{cmp::min, ffi::c_int, sync::atomic};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -34,13 +35,14 @@ use utils::{
 
 // TODO O_ASYNC
 
+#[syscall(id32 = 0x004, id64 = 0x001)]
 pub fn write(
-	Args((fd, buf, count)): Args<(c_int, SyscallSlice<u8>, usize)>,
+	Args((fd, buf, mut count)): Args<(c_int, SyscallSlice<u8>, usize)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
 ) -> EResult<usize> {
 	// Validation
-	let len = min(count, i32::MAX as usize);
-	if len == 0 {
+	count = min(count, i32::MAX as usize);
+	if count == 0 {
 		return Ok(0);
 	}
 	let file = fds.lock().get_fd(fd)?.get_file().clone();
@@ -49,7 +51,7 @@ pub fn write(
 		return Err(errno!(EINVAL));
 	}
 	// TODO find a way to avoid allocating here
-	let buf_slice = buf.copy_from_user_vec(0, len)?.ok_or(errno!(EFAULT))?;
+	let buf_slice = buf.copy_from_user_vec(0, count)?.ok_or(errno!(EFAULT))?;
 	// Write file
 	let off = file.off.load(atomic::Ordering::Acquire);
 	let len = file.ops.write(&file, off, &buf_slice)?;
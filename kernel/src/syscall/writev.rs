@@ -22,7 +22,7 @@ use crate::{
 	file::{fd::FileDescriptorTable, File, FileType, O_NONBLOCK},
 	process::{
 		mem_space::{
-			copy::{SyscallIOVec, SyscallSlice},
+			copy::{IOVec, SyscallIOVec, SyscallSlice},
 			MemSpace,
 		},
 		scheduler,
@@ -33,6 +33,7 @@ use crate::{
 	syscall::{Args, FromSyscallArg},
 };
 use core::{cmp::min, ffi::c_int, sync::atomic};
+use macros::syscall;
 use utils::{
 	errno,
 	errno::{EResult, Errno},
@@ -42,33 +43,59 @@ use utils::{
 
 // FIXME: the operation has to be atomic
 
+/// Writes a chunk of `len` bytes from `i` to the file.
+///
+/// `off` is the number of bytes written from previous chunks.
+///
+/// On success, the function returns the number of bytes written.
+fn write_chunk(
+	i: &IOVec,
+	len: usize,
+	offset: Option<u64>,
+	off: usize,
+	file: &File,
+) -> EResult<usize> {
+	let ptr = SyscallSlice::<u8>::from_ptr(i.iov_base as usize);
+	let Some(buf) = ptr.copy_from_user_vec(0, len)? else {
+		return if len > 0 { Err(errno!(EFAULT)) } else { Ok(0) };
+	};
+	let len = if let Some(offset) = offset {
+		let file_off = offset + off as u64;
+		file.ops.write(file, file_off, &buf)?
+	} else {
+		let off = file.off.load(atomic::Ordering::Acquire);
+		let len = file.ops.write(file, off, &buf)?;
+		// Update offset
+		let new_off = off.saturating_add(len as u64);
+		file.off.store(new_off, atomic::Ordering::Release);
+		len
+	};
+	Ok(len)
+}
+
 /// Writes the given chunks to the file.
 ///
 /// Arguments:
 /// - `iov` is the set of chunks
-/// - `iovcnt` is the number of chunks in `iov`
 /// - `offset` is the offset at which the write operation in the file begins
 /// - `file` is the file to write to
-fn write(iov: SyscallIOVec, iovcnt: usize, offset: Option<u64>, file: &File) -> EResult<usize> {
+///
+/// The operation stops at the first chunk that is not written entirely. If an error occurs after
+/// some data has been written, the function returns the number of bytes written so far.
+fn write(iov: &[IOVec], offset: Option<u64>, file: &File) -> EResult<usize> {
 	let mut off = 0;
-	for i in iov.iter(iovcnt) {
-		let i = i?;
+	for i in iov {
 		// The size to write. This is limited to avoid an overflow on the total length
 		let l = min(i.iov_len, i32::MAX as usize - off);
-		let ptr = SyscallSlice::<u8>::from_ptr(i.iov_base as usize);
-		if let Some(buf) = ptr.copy_from_user_vec(0, l)? {
-			let len = if let Some(offset) = offset {
-				let file_off = offset + off as u64;
-				file.ops.write(file, file_off, &buf)?
-			} else {
-				let off = file.off.load(atomic::Ordering::Acquire);
-				let len = file.ops.write(file, off, &buf)?;
-				// Update offset
-				let new_off = off.saturating_add(len as u64);
-				file.off.store(new_off, atomic::Ordering::Release);
-				len
-			};
-			off += len;
+		match write_chunk(i, l, offset, off, file) {
+			Ok(len) => {
+				off += len;
+				if len < l {
+					break;
+				}
+			}
+			Err(e) if off == 0 => return Err(e),
+			Err(_) => break,
 		}
 	}
 	Ok(off)
@@ -104,9 +131,13 @@ pub fn do_writev(
 	if file.get_type()? == FileType::Link {
 		return Err(errno!(EINVAL));
 	}
-	write(iov, iovcnt as _, offset, &file)
+	// Copy the iovec before transferring any data, so that the operation fails early if it is
+	// not accessible
+	let iov = iov.copy_from_user(iovcnt as _)?;
+	write(&iov, offset, &file)
 }
 
+#[syscall(id32 = 0x092, id64 = 0x014)]
 pub fn writev(
 	Args((fd, iov, iovcnt)): Args<(c_int, SyscallIOVec, c_int)>,
 	fds: Arc<Mutex<FileDescriptorTable>>,
@@ -19,12 +19,18 @@
 //! System clocks.
 
 use crate::{
+	arch::x86::idt,
+	process::exec::vdso,
 	sync::atomic::AtomicU64,
-	time::{unit::ClockIdT, Timestamp},
+	time::{
+		hw::{hpet, tsc},
+		unit::ClockIdT,
+		Timestamp,
+	},
 };
-use core::{
-	cmp::max,
-	sync::atomic::Ordering::{Acquire, Release},
+use core::sync::atomic::{
+	AtomicU8,
+	Ordering::{Acquire, Release},
 };
 
 /// Available clocks
@@ -68,21 +74,170 @@ impl Clock {
 	}
 }
 
-// TODO allow accessing clocks through an address shared with userspace (vDSO)
+/// The ratio between the time elapsed and the maximum adjustment applied to the real time clock
+/// while slewing. This corresponds to 500 microseconds per second.
+const SLEW_RATIO: u64 = 2000;
 
-/// The current timestamp of the real time clock, in nanoseconds.
-static REALTIME: AtomicU64 = AtomicU64::new(0);
-/// On time adjustment, this value is updated with the previous value of the real time clock so
-/// that it can be used if the clock went backwards in time.
+/// The offset to add to the monotonic clock to get the real time clock, in nanoseconds.
+///
+/// The value wraps around so that the real time clock can be set before the boot time.
+static REALTIME_OFFSET: AtomicU64 = AtomicU64::new(0);
+/// The remaining adjustment to gradually apply to the real time clock, in nanoseconds. The value
+/// is to be interpreted as signed.
+static SLEW: AtomicU64 = AtomicU64::new(0);
+/// The monotonic clock, which cannot be set and does not jump when the real time clock is
+/// modified.
 static MONOTONIC: AtomicU64 = AtomicU64::new(0);
+/// The last value returned for the monotonic clock, in nanoseconds.
+///
+/// Rounding errors when converting counts of the clock source may otherwise make the clock go
+/// backwards by a few nanoseconds across an update.
+static LAST_MONOTONIC: AtomicU64 = AtomicU64::new(0);
 /// The time elapsed since boot time, in nanoseconds.
 static BOOTTIME: AtomicU64 = AtomicU64::new(0);
+/// The resolution of clocks, in nanoseconds. This is the interval at which the hardware clock
+/// source updates them.
+static RESOLUTION: AtomicU64 = AtomicU64::new(0);
+
+/// Clock source: clocks only advance at each tick.
+const SOURCE_TICK: u8 = 0;
+/// Clock source: the TSC.
+const SOURCE_TSC: u8 = 1;
+/// Clock source: the main counter of the HPET.
+const SOURCE_HPET: u8 = 2;
+
+/// The clock source, used to measure the time elapsed between ticks.
+static SOURCE: AtomicU8 = AtomicU8::new(SOURCE_TICK);
+/// The value of the clock source's counter corresponding to the last update of clocks.
+static LAST_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Uses the TSC as the clock source.
+///
+/// The TSC must have been calibrated beforehand.
+pub fn set_tsc_source() {
+	LAST_COUNT.store(tsc::read(), Release);
+	SOURCE.store(SOURCE_TSC, Release);
+	publish_vvar();
+}
+
+/// Uses the main counter of the HPET as the clock source.
+///
+/// The HPET must have been initialized beforehand.
+pub fn set_hpet_source() {
+	LAST_COUNT.store(hpet::read(), Release);
+	SOURCE.store(SOURCE_HPET, Release);
+	publish_vvar();
+}
+
+/// Publishes the state of clocks to the vvar page, so that userspace can read them without a
+/// system call.
+pub(crate) fn publish_vvar() {
+	vdso::update_vvar(|vvar| {
+		let tsc = (SOURCE.load(Acquire) == SOURCE_TSC)
+			.then(tsc::mult_shift)
+			.flatten();
+		vvar.clock_mode = tsc.is_some() as _;
+		vvar.monotonic = MONOTONIC.load(Acquire);
+		vvar.realtime_offset = REALTIME_OFFSET.load(Acquire);
+		vvar.boottime = BOOTTIME.load(Acquire);
+		vvar.tsc_base = LAST_COUNT.load(Acquire);
+		(vvar.tsc_mult, vvar.tsc_shift) = tsc.unwrap_or_default();
+	});
+}
+
+/// Returns the time elapsed since the last update of clocks, in nanoseconds.
+///
+/// If clocks only advance at each tick, the function returns zero.
+fn elapsed_since_update() -> Timestamp {
+	let last = LAST_COUNT.load(Acquire);
+	match SOURCE.load(Acquire) {
+		SOURCE_TSC => tsc::cycles_to_ns(tsc::read().wrapping_sub(last)),
+		SOURCE_HPET => hpet::ticks_to_ns(hpet::ticks_since(last)),
+		_ => 0,
+	}
+}
 
 /// Updates clocks with the given delta value in nanoseconds.
+///
+/// If clocks do not only advance at each tick, `delta` is ignored and the time actually elapsed
+/// since the last update, measured by the clock source, is used instead.
 pub fn update(delta: Timestamp) {
-	REALTIME.fetch_add(delta as _, Release);
+	// Only consume the counts corresponding to `delta` so that rounding errors do not accumulate
+	let delta = match SOURCE.load(Acquire) {
+		SOURCE_TSC => {
+			let delta = elapsed_since_update();
+			LAST_COUNT.fetch_add(tsc::ns_to_cycles(delta), Release);
+			delta
+		}
+		SOURCE_HPET => {
+			let delta = elapsed_since_update();
+			LAST_COUNT.fetch_add(hpet::ns_to_ticks(delta), Release);
+			delta
+		}
+		_ => delta,
+	};
 	MONOTONIC.fetch_add(delta as _, Release);
 	BOOTTIME.fetch_add(delta as _, Release);
+	// Gradually apply the pending adjustment
+	let slew = SLEW.load(Acquire) as i64;
+	if slew != 0 {
+		let max = (delta / SLEW_RATIO) as i64;
+		let step = slew.clamp(-max, max);
+		SLEW.fetch_add(step.wrapping_neg() as _, Release);
+		REALTIME_OFFSET.fetch_add(step as _, Release);
+	}
+	publish_vvar();
+}
+
+/// Sets the real time clock to the timestamp `ts`, in nanoseconds.
+///
+/// The monotonic clock is not affected. Any pending adjustment is cancelled.
+pub fn set_realtime(ts: Timestamp) {
+	let monotonic = current_time_ns(Clock::Monotonic);
+	SLEW.store(0, Release);
+	REALTIME_OFFSET.store(ts.wrapping_sub(monotonic), Release);
+	publish_vvar();
+}
+
+/// Schedules a gradual adjustment of the real time clock by `delta` nanoseconds, replacing the
+/// pending one.
+///
+/// The function returns the adjustment that was still pending, in nanoseconds.
+pub fn adjust(delta: i64) -> i64 {
+	let prev = SLEW.load(Acquire) as i64;
+	SLEW.store(delta as _, Release);
+	prev
+}
+
+/// Returns the adjustment of the real time clock that is still pending, in nanoseconds.
+pub fn pending_adjustment() -> i64 {
+	SLEW.load(Acquire) as _
+}
+
+/// Sets the resolution of clocks to `res`, in nanoseconds.
+pub fn set_resolution(res: Timestamp) {
+	RESOLUTION.store(res, Release);
+}
+
+/// Returns the resolution of the clock `clk`, in nanoseconds.
+///
+/// If the clock is not implemented, the function returns `None`.
+pub fn get_resolution(clk: Clock) -> Option<Timestamp> {
+	match clk {
+		Clock::Realtime
+		| Clock::RealtimeAlarm
+		| Clock::RealtimeCoarse
+		| Clock::Monotonic
+		| Clock::MonotonicRaw
+		| Clock::MonotonicCoarse
+		| Clock::Boottime
+		| Clock::BoottimeAlarm
+		// CPU time is measured with the monotonic clock
+		| Clock::ProcessCputimeId
+		| Clock::ThreadCputimeId
+		| Clock::Tai => Some(RESOLUTION.load(Acquire)),
+		_ => None,
+	}
 }
 
 /// Returns the current timestamp in nanoseconds.
@@ -91,19 +246,32 @@ pub fn update(delta: Timestamp) {
 ///
 /// The returned timestamp is in nanoseconds.
 ///
-/// If the clock is invalid, the function returns an error.
+/// Coarse clocks do not account for the time elapsed since the last update, which makes them
+/// cheaper but only precise to the resolution of the clock.
+///
+/// If the clock is not implemented (see [`get_resolution`]), the function returns zero.
 pub fn current_time_ns(clk: Clock) -> Timestamp {
-	match clk {
-		Clock::Realtime | Clock::RealtimeAlarm => REALTIME.load(Acquire),
-		Clock::Monotonic => {
-			let realtime = REALTIME.load(Acquire);
-			let monotonic = MONOTONIC.load(Acquire);
-			max(realtime, monotonic)
+	// Prevent clocks from being updated while reading them
+	idt::wrap_disable_interrupts(|| match clk {
+		// TAI is not maintained separately since the kernel does not know about leap seconds
+		Clock::Realtime | Clock::RealtimeAlarm | Clock::Tai => MONOTONIC
+			.load(Acquire)
+			.wrapping_add(REALTIME_OFFSET.load(Acquire))
+			.wrapping_add(elapsed_since_update()),
+		Clock::RealtimeCoarse => MONOTONIC
+			.load(Acquire)
+			.wrapping_add(REALTIME_OFFSET.load(Acquire)),
+		// Adjustments are only applied to the real time clock, so both are the same
+		Clock::Monotonic | Clock::MonotonicRaw => {
+			let ts = MONOTONIC.load(Acquire) + elapsed_since_update();
+			let ts = ts.max(LAST_MONOTONIC.load(Acquire));
+			LAST_MONOTONIC.store(ts, Release);
+			ts
 		}
-		Clock::Boottime | Clock::BoottimeAlarm => BOOTTIME.load(Acquire),
-		// TODO implement all clocks
+		Clock::MonotonicCoarse => MONOTONIC.load(Acquire),
+		Clock::Boottime | Clock::BoottimeAlarm => BOOTTIME.load(Acquire) + elapsed_since_update(),
 		_ => 0,
-	}
+	})
 }
 
 /// Returns the current timestamp in milliseconds.
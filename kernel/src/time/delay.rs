@@ -0,0 +1,76 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Short busy-waiting delays, for use by device drivers that need precise timings.
+//!
+//! Delays do not yield to other processes, so they are meant to be short, typically shorter than
+//! a clock tick.
+//!
+//! If the Time Stamp Counter (TSC) is usable, delays are measured with it. Otherwise, or before
+//! its calibration, the PIT is used directly.
+
+use crate::time::hw::{pit, tsc};
+use core::hint;
+
+/// Busy-waits for at least `ns` nanoseconds.
+pub fn ndelay(ns: u64) {
+	if tsc::cycles_per_ms().is_none() {
+		pit::busy_wait(ns);
+		return;
+	}
+	let cycles = tsc::ns_to_cycles(ns);
+	let start = tsc::read();
+	while tsc::read().wrapping_sub(start) < cycles {
+		hint::spin_loop();
+	}
+}
+
+/// Busy-waits for at least `us` microseconds.
+#[inline]
+pub fn udelay(us: u64) {
+	ndelay(us * 1000);
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::arch::x86::idt;
+
+	/// Measures the number of TSC cycles elapsed while running `f`.
+	fn measure<F: FnOnce()>(f: F) -> u64 {
+		idt::wrap_disable_interrupts(|| {
+			let start = tsc::read();
+			f();
+			tsc::read().wrapping_sub(start)
+		})
+	}
+
+	#[test_case]
+	fn udelay_calibration() {
+		tsc::calibrate();
+		let Some(cycles_per_ms) = tsc::cycles_per_ms() else {
+			// The TSC cannot be used as a reference
+			return;
+		};
+		// Compare the TSC-based delay with the PIT
+		let tsc = measure(|| udelay(1000));
+		let pit = measure(|| pit::busy_wait(1_000_000));
+		assert!(tsc >= cycles_per_ms);
+		assert!(tsc.abs_diff(pit) < pit / 10);
+	}
+}
@@ -0,0 +1,357 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The High Precision Event Timer (HPET) is made of a main counter incremented at a constant
+//! frequency (at least 10 MHz) and several comparators, each able to trigger interrupts.
+//!
+//! The HPET is located through the ACPI `HPET` table.
+//!
+//! Comparators `0` and `1` are used through the legacy replacement route, in which they
+//! respectively replace the PIT (IRQ `0`) and the RTC (IRQ `8`).
+
+use super::HwClock;
+use crate::{
+	acpi,
+	arch::x86::{idt, pic},
+	memory::mmio::MMIO,
+	sync::once::OnceInit,
+	time::unit::Timestamp,
+};
+use core::{
+	ptr,
+	sync::atomic::{
+		AtomicBool,
+		Ordering::{Acquire, Release},
+	},
+};
+use utils::math::rational::Rational;
+
+/// Register: General Capabilities and ID.
+const REG_CAPABILITIES: usize = 0x000;
+/// Register: General Configuration.
+const REG_CONFIG: usize = 0x010;
+/// Register: Main Counter Value.
+const REG_MAIN_COUNTER: usize = 0x0f0;
+
+/// Returns the offset of the Configuration and Capability register of the comparator `n`.
+const fn reg_timer_config(n: usize) -> usize {
+	0x100 + 0x20 * n
+}
+
+/// Returns the offset of the Comparator Value register of the comparator `n`.
+const fn reg_timer_comparator(n: usize) -> usize {
+	0x108 + 0x20 * n
+}
+
+/// Capability: the main counter is 64 bits wide.
+const CAP_COUNT_SIZE: u64 = 1 << 13;
+/// Capability: the legacy replacement route is supported.
+const CAP_LEG_RT: u64 = 1 << 15;
+
+/// Configuration: enables the main counter and interrupts.
+const CONFIG_ENABLE: u64 = 1 << 0;
+/// Configuration: enables the legacy replacement route.
+const CONFIG_LEG_RT: u64 = 1 << 1;
+
+/// Comparator configuration: enables interrupts.
+const TIMER_INT_ENB: u64 = 1 << 2;
+/// Comparator configuration: enables periodic mode.
+const TIMER_TYPE_PERIODIC: u64 = 1 << 3;
+/// Comparator capability: periodic mode is supported.
+const TIMER_PER_INT_CAP: u64 = 1 << 4;
+/// Comparator configuration: allows setting the accumulator in periodic mode.
+const TIMER_VAL_SET: u64 = 1 << 6;
+/// Comparator configuration: forces the comparator to operate on 32 bits.
+const TIMER_32MODE: u64 = 1 << 8;
+
+/// The number of femtoseconds in a nanosecond.
+const FS_PER_NS: u64 = 1_000_000;
+
+/// Information on the HPET.
+struct Hpet {
+	/// The mapping of the registers.
+	mmio: MMIO,
+	/// The mask of the valid bits of the main counter.
+	counter_mask: u64,
+	/// The period of the main counter, in femtoseconds.
+	period: u64,
+}
+
+impl Hpet {
+	/// Reads the register at offset `off`.
+	fn read_reg(&self, off: usize) -> u64 {
+		unsafe {
+			let ptr = self.mmio.as_ptr().as_ptr().add(off) as *const u32;
+			let lo = ptr::read_volatile(ptr) as u64;
+			let hi = ptr::read_volatile(ptr.add(1)) as u64;
+			(hi << 32) | lo
+		}
+	}
+
+	/// Writes `val` to the lower half of the register at offset `off`, leaving the upper half
+	/// untouched.
+	fn write_reg32(&self, off: usize, val: u32) {
+		unsafe {
+			let ptr = self.mmio.as_ptr().as_ptr().add(off) as *mut u32;
+			ptr::write_volatile(ptr, val);
+		}
+	}
+
+	/// Writes `val` to the register at offset `off`.
+	fn write_reg(&self, off: usize, val: u64) {
+		unsafe {
+			let ptr = self.mmio.as_ptr().as_ptr().add(off) as *mut u32;
+			ptr::write_volatile(ptr, val as u32);
+			ptr::write_volatile(ptr.add(1), (val >> 32) as u32);
+		}
+	}
+}
+
+/// The HPET. Initialized only if [`PRESENT`] is `true`.
+static HPET: OnceInit<Hpet> = unsafe { OnceInit::new() };
+/// Tells whether the HPET is present.
+static PRESENT: AtomicBool = AtomicBool::new(false);
+
+/// Initializes the HPET and starts its main counter.
+///
+/// If the HPET is not present, the function returns `false`.
+pub(crate) fn init() -> bool {
+	let Some(addr) = acpi::hpet_address() else {
+		return false;
+	};
+	let Ok(mmio) = MMIO::new(addr, 1, false) else {
+		return false;
+	};
+	let mut hpet = Hpet {
+		mmio,
+		counter_mask: 0,
+		period: 0,
+	};
+	let cap = hpet.read_reg(REG_CAPABILITIES);
+	hpet.counter_mask = if cap & CAP_COUNT_SIZE != 0 {
+		u64::MAX
+	} else {
+		u32::MAX as _
+	};
+	hpet.period = cap >> 32;
+	// The specification requires a period of at most 100 nanoseconds
+	if hpet.period == 0 || hpet.period > 100 * FS_PER_NS {
+		return false;
+	}
+	// Start the main counter, with interrupts disabled on all comparators
+	let config = hpet.read_reg(REG_CONFIG);
+	hpet.write_reg(REG_CONFIG, config & !CONFIG_LEG_RT);
+	let count = ((cap >> 8) & 0x1f) as usize + 1;
+	for n in 0..count {
+		let timer_config = hpet.read_reg(reg_timer_config(n));
+		hpet.write_reg(reg_timer_config(n), timer_config & !TIMER_INT_ENB);
+	}
+	hpet.write_reg(REG_CONFIG, config | CONFIG_ENABLE);
+	unsafe {
+		OnceInit::init(&HPET, hpet);
+	}
+	PRESENT.store(true, Release);
+	true
+}
+
+/// Tells whether the HPET is present and initialized.
+pub fn is_present() -> bool {
+	PRESENT.load(Acquire)
+}
+
+/// Tells whether the comparators `0` and `1` can be used in place of the PIT and the RTC.
+///
+/// This requires the legacy replacement route and periodic mode on both comparators.
+pub fn has_legacy_timers() -> bool {
+	if !is_present() {
+		return false;
+	}
+	let periodic = |n| HPET.read_reg(reg_timer_config(n)) & TIMER_PER_INT_CAP != 0;
+	HPET.read_reg(REG_CAPABILITIES) & CAP_LEG_RT != 0 && periodic(0) && periodic(1)
+}
+
+/// Returns the current value of the main counter.
+///
+/// The HPET must be present.
+pub fn read() -> u64 {
+	let hpet = &*HPET;
+	#[cfg(target_arch = "x86_64")]
+	{
+		unsafe {
+			let ptr = hpet.mmio.as_ptr().as_ptr().add(REG_MAIN_COUNTER) as *const u64;
+			ptr::read_volatile(ptr) & hpet.counter_mask
+		}
+	}
+	#[cfg(not(target_arch = "x86_64"))]
+	{
+		if hpet.counter_mask == u32::MAX as u64 {
+			return hpet.read_reg(REG_MAIN_COUNTER) & hpet.counter_mask;
+		}
+		// The counter may be incremented between the reads of its two halves. Retry if the
+		// upper half changed
+		unsafe {
+			let ptr = hpet.mmio.as_ptr().as_ptr().add(REG_MAIN_COUNTER) as *const u32;
+			loop {
+				let hi = ptr::read_volatile(ptr.add(1));
+				let lo = ptr::read_volatile(ptr);
+				if ptr::read_volatile(ptr.add(1)) == hi {
+					break ((hi as u64) << 32) | lo as u64;
+				}
+			}
+		}
+	}
+}
+
+/// Returns the number of ticks of the main counter elapsed since the value `since`.
+///
+/// The function handles the rollover of the counter, if it happened at most once.
+///
+/// The HPET must be present.
+pub fn ticks_since(since: u64) -> u64 {
+	read().wrapping_sub(since) & HPET.counter_mask
+}
+
+/// Returns the resolution of the main counter, in nanoseconds, rounded up.
+///
+/// The HPET must be present.
+pub fn resolution() -> Timestamp {
+	HPET.period.div_ceil(FS_PER_NS)
+}
+
+/// Converts the given number of `ticks` of the main counter to nanoseconds, rounding down.
+///
+/// The HPET must be present.
+pub fn ticks_to_ns(ticks: u64) -> Timestamp {
+	(ticks as u128 * HPET.period as u128 / FS_PER_NS as u128) as _
+}
+
+/// Converts the given duration in nanoseconds to a number of ticks of the main counter, rounding
+/// up.
+///
+/// The HPET must be present.
+pub fn ns_to_ticks(ns: Timestamp) -> u64 {
+	(ns as u128 * FS_PER_NS as u128).div_ceil(HPET.period as u128) as _
+}
+
+/// A comparator of the HPET, used through the legacy replacement route.
+///
+/// Creating an instance enables the legacy replacement route, which disconnects the PIT and the
+/// RTC from their IRQs.
+pub struct HpetTimer {
+	/// The index of the comparator. Either `0` or `1`.
+	n: usize,
+}
+
+impl HpetTimer {
+	/// Creates a new instance for the comparator `n`.
+	///
+	/// By default, the timer is disabled and its frequency is undefined.
+	///
+	/// [`has_legacy_timers`] must return `true`.
+	pub fn new(n: usize) -> Self {
+		let config = HPET.read_reg(REG_CONFIG);
+		HPET.write_reg(REG_CONFIG, config | CONFIG_LEG_RT);
+		let mut s = Self {
+			n,
+		};
+		s.set_enabled(false);
+		s
+	}
+
+	/// Returns the IRQ of the timer.
+	fn irq(&self) -> u8 {
+		if self.n == 0 {
+			0
+		} else {
+			8
+		}
+	}
+}
+
+impl HwClock for HpetTimer {
+	fn set_enabled(&mut self, enable: bool) {
+		idt::wrap_disable_interrupts(|| {
+			let off = reg_timer_config(self.n);
+			let config = HPET.read_reg(off);
+			if enable {
+				HPET.write_reg(off, config | TIMER_INT_ENB);
+				pic::enable_irq(self.irq());
+			} else {
+				HPET.write_reg(off, config & !TIMER_INT_ENB);
+				pic::disable_irq(self.irq());
+			}
+		});
+	}
+
+	fn set_frequency(&mut self, freq: Rational) {
+		if freq == Rational::from(0) {
+			self.set_enabled(false);
+			return;
+		}
+		let interval = i64::from(Rational::from(1_000_000_000) / freq) as Timestamp;
+		let ticks = ns_to_ticks(interval).clamp(1, u32::MAX as _) as u32;
+		idt::wrap_disable_interrupts(|| {
+			let off = reg_timer_config(self.n);
+			let config = HPET.read_reg(off);
+			// Operate on 32 bits so that the comparator can be written atomically
+			HPET.write_reg(
+				off,
+				config | TIMER_TYPE_PERIODIC | TIMER_VAL_SET | TIMER_32MODE,
+			);
+			// The first write sets the time of the first interrupt, the second sets the period
+			let comparator = reg_timer_comparator(self.n);
+			HPET.write_reg32(comparator, (read() as u32).wrapping_add(ticks));
+			HPET.write_reg32(comparator, ticks);
+		});
+	}
+
+	fn get_value(&self) -> Option<Timestamp> {
+		Some(ticks_to_ns(read()))
+	}
+
+	fn get_interrupt_vector(&self) -> u32 {
+		0x20 + self.irq() as u32
+	}
+}
+
+impl Drop for HpetTimer {
+	fn drop(&mut self) {
+		self.set_enabled(false);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::time::hw::pit;
+
+	#[test_case]
+	fn hpet_accuracy() {
+		if !is_present() {
+			return;
+		}
+		// Compare against a PIT delay of 1 millisecond
+		let ns = idt::wrap_disable_interrupts(|| {
+			let start = read();
+			pit::busy_wait(1_000_000);
+			ticks_to_ns(ticks_since(start))
+		});
+		assert!(ns.abs_diff(1_000_000) < 100_000);
+		assert!(ticks_to_ns(ns_to_ticks(1_000_000)) >= 1_000_000);
+	}
+}
@@ -18,10 +18,14 @@
 
 //! This module implements hardware clocks.
 
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod hpet;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod pit;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 pub mod rtc;
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+pub mod tsc;
 
 use crate::{sync::mutex::Mutex, time::unit::Timestamp};
 use utils::{
@@ -53,3 +57,15 @@ pub trait HwClock {
 ///
 /// The key is the name of the clock.
 pub static CLOCKS: Mutex<HashMap<String, Box<dyn HwClock>>> = Mutex::new(HashMap::new());
+
+/// Returns the hardware clock generating scheduler ticks, from the list of `clocks`.
+///
+/// The HPET is preferred over the PIT if available.
+pub fn tick_clock(clocks: &mut HashMap<String, Box<dyn HwClock>>) -> &mut dyn HwClock {
+	let name = if clocks.contains_key(b"hpet0".as_slice()) {
+		b"hpet0".as_slice()
+	} else {
+		b"pit".as_slice()
+	};
+	clocks.get_mut(name).unwrap().as_mut()
+}
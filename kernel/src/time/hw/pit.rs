@@ -20,7 +20,19 @@
 //! trigger interruptions at a fixed interval.
 
 use super::HwClock;
-use crate::arch::x86::{idt, io::outb, pic};
+use crate::{
+	arch::x86::{
+		idt,
+		io::{inb, outb},
+		pic,
+	},
+	sync::atomic::AtomicU64,
+	time::{
+		clock::{current_time_ms, Clock},
+		unit::Timestamp,
+	},
+};
+use core::sync::atomic::Ordering::Relaxed;
 use utils::math::rational::Rational;
 
 /// PIT channel number 0.
@@ -34,6 +46,14 @@ const PIT_COMMAND: u16 = 0x43;
 
 /// The command to enable the PC speaker.
 const BEEPER_ENABLE_COMMAND: u8 = 0x61;
+/// The system control port, controlling the gate of channel 2 and giving its output.
+const CONTROL_PORT: u16 = 0x61;
+/// Bit of [`CONTROL_PORT`] enabling the gate of channel 2.
+const CONTROL_GATE_2: u8 = 0b01;
+/// Bit of [`CONTROL_PORT`] connecting channel 2 to the PC speaker.
+const CONTROL_SPEAKER: u8 = 0b10;
+/// Bit of [`CONTROL_PORT`] giving the output of channel 2.
+const CONTROL_OUT_2: u8 = 0b100000;
 
 /// Select PIT channel 0.
 const SELECT_CHANNEL_0: u8 = 0b00 << 6;
@@ -72,7 +92,77 @@ const MODE_5: u8 = 0b101 << 1;
 const BCD_MODE: u8 = 0b1;
 
 /// The base frequency of the PIT.
-const BASE_FREQUENCY: Rational = Rational::from_integer(1193182);
+const BASE_FREQUENCY: Rational = Rational::from_integer(BASE_FREQUENCY_HZ as _);
+/// The base frequency of the PIT, in Hertz.
+const BASE_FREQUENCY_HZ: u64 = 1193182;
+
+/// Busy-waits for `ns` nanoseconds using channel 2 of the PIT, without relying on interruptions.
+///
+/// The duration is rounded up to the resolution of the PIT, which is about 838 nanoseconds.
+pub fn busy_wait(ns: u64) {
+	let mut count = (ns * BASE_FREQUENCY_HZ).div_ceil(1_000_000_000);
+	while count > 0 {
+		let chunk = count.min(u16::MAX as _);
+		unsafe {
+			// Enable the gate and disconnect the speaker
+			let ctrl = inb(CONTROL_PORT);
+			outb(CONTROL_PORT, (ctrl & !CONTROL_SPEAKER) | CONTROL_GATE_2);
+			// The output is set when the count reaches zero
+			outb(
+				PIT_COMMAND,
+				SELECT_CHANNEL_2 | ACCESS_LOBYTE_HIBYTE | MODE_0,
+			);
+			outb(CHANNEL_2, (chunk & 0xff) as u8);
+			outb(CHANNEL_2, ((chunk >> 8) & 0xff) as u8);
+			while inb(CONTROL_PORT) & CONTROL_OUT_2 == 0 {
+				core::hint::spin_loop();
+			}
+		}
+		count -= chunk;
+	}
+}
+
+/// The timestamp, in milliseconds, at which the PC speaker must be stopped. If zero, the speaker
+/// is not playing.
+static BEEP_END: AtomicU64 = AtomicU64::new(0);
+
+/// Makes the PC speaker play a sound at `frequency` Hz for `duration` milliseconds.
+///
+/// The function does not block: the speaker is stopped later by [`beep_tick`].
+pub fn beep(frequency: u32, duration: u32) {
+	if frequency == 0 {
+		return;
+	}
+	let count = (BASE_FREQUENCY_HZ / frequency as u64).min(u16::MAX as _);
+	idt::wrap_disable_interrupts(|| unsafe {
+		outb(
+			PIT_COMMAND,
+			SELECT_CHANNEL_2 | ACCESS_LOBYTE_HIBYTE | MODE_3,
+		);
+		outb(CHANNEL_2, (count & 0xff) as u8);
+		outb(CHANNEL_2, ((count >> 8) & 0xff) as u8);
+		// Connect channel 2 to the speaker
+		let ctrl = inb(CONTROL_PORT);
+		outb(CONTROL_PORT, ctrl | CONTROL_GATE_2 | CONTROL_SPEAKER);
+	});
+	let end = current_time_ms(Clock::Monotonic) + duration as Timestamp;
+	BEEP_END.store(end, Relaxed);
+}
+
+/// Stops the PC speaker if the current sound has finished playing.
+///
+/// This function is meant to be called periodically.
+pub(crate) fn beep_tick() {
+	let end = BEEP_END.load(Relaxed);
+	if end == 0 || current_time_ms(Clock::Monotonic) < end {
+		return;
+	}
+	BEEP_END.store(0, Relaxed);
+	unsafe {
+		let ctrl = inb(CONTROL_PORT);
+		outb(CONTROL_PORT, ctrl & !(CONTROL_GATE_2 | CONTROL_SPEAKER));
+	}
+}
 
 // FIXME prevent having several instances at the same time
 
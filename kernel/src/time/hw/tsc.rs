@@ -0,0 +1,140 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The Time Stamp Counter (TSC) is a per-CPU counter incremented at each clock cycle. Reading it
+//! is the cheapest way to get a timestamp.
+//!
+//! The TSC can only be used as a clock source if it is invariant, that is if it runs at a
+//! constant rate regardless of frequency scaling and power states. Its frequency is calibrated at
+//! boot against the PIT.
+//!
+//! On SMP systems, TSCs of different CPUs are assumed to be synchronized, which is the case on
+//! processors advertising an invariant TSC.
+//!
+//! If the TSC is not invariant, the HPET or the ticks of the RTC are used as the clock source
+//! instead.
+//!
+//! Conversions between cycles and nanoseconds use 128 bits integers rather than a [`Rational`],
+//! whose 64 bits numerator would overflow for durations above about an hour at 3 GHz.
+//!
+//! [`Rational`]: utils::math::rational::Rational
+
+use crate::{
+	arch::x86::{features, idt, rdtsc},
+	sync::atomic::AtomicU64,
+	time::{hw::pit, unit::Timestamp},
+};
+use core::sync::atomic::Ordering::{Acquire, Release};
+
+/// The duration of the calibration, in milliseconds.
+const CALIBRATION_MS: u64 = 10;
+
+/// The shift of the multiplier returned by [`mult_shift`].
+const MULT_SHIFT: u32 = 24;
+
+/// The number of cycles per millisecond. If zero, the TSC is not usable as a clock source.
+static CYCLES_PER_MS: AtomicU64 = AtomicU64::new(0);
+
+/// Calibrates the frequency of the TSC against the PIT.
+///
+/// If the TSC is not invariant, it is not usable and the function does nothing.
+pub fn calibrate() {
+	let features = features::get();
+	if !features.tsc || !features.invariant_tsc {
+		return;
+	}
+	let cycles = idt::wrap_disable_interrupts(|| {
+		let start = rdtsc();
+		pit::busy_wait(CALIBRATION_MS * 1_000_000);
+		rdtsc().wrapping_sub(start)
+	});
+	CYCLES_PER_MS.store(cycles / CALIBRATION_MS, Release);
+}
+
+/// Returns the calibrated number of cycles per millisecond.
+///
+/// If the TSC is not usable as a clock source, the function returns `None`.
+pub fn cycles_per_ms() -> Option<u64> {
+	Some(CYCLES_PER_MS.load(Acquire)).filter(|c| *c != 0)
+}
+
+/// Returns a multiplier `mult` and a shift `shift` such that a number of cycles `c` corresponds to
+/// `(c * mult) >> shift` nanoseconds.
+///
+/// This allows converting cycles without a division, at the cost of precision.
+///
+/// If the TSC is not usable as a clock source, the function returns `None`.
+pub fn mult_shift() -> Option<(u32, u32)> {
+	let cycles_per_ms = cycles_per_ms()?;
+	let mult = (1_000_000u64 << MULT_SHIFT) / cycles_per_ms;
+	Some((mult.try_into().ok()?, MULT_SHIFT))
+}
+
+/// Returns the current value of the TSC.
+#[inline]
+pub fn read() -> u64 {
+	rdtsc()
+}
+
+/// Converts the given number of `cycles` to nanoseconds, rounding down.
+///
+/// If the TSC is not usable as a clock source, the function returns zero.
+pub fn cycles_to_ns(cycles: u64) -> Timestamp {
+	let Some(cycles_per_ms) = cycles_per_ms() else {
+		return 0;
+	};
+	// Intermediate computation on 128 bits to avoid overflows
+	(cycles as u128 * 1_000_000 / cycles_per_ms as u128) as _
+}
+
+/// Converts the given duration in nanoseconds to a number of cycles, rounding up.
+///
+/// If the TSC is not usable as a clock source, the function returns zero.
+pub fn ns_to_cycles(ns: Timestamp) -> u64 {
+	let Some(cycles_per_ms) = cycles_per_ms() else {
+		return 0;
+	};
+	(ns as u128 * cycles_per_ms as u128).div_ceil(1_000_000) as _
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn tsc_accuracy() {
+		calibrate();
+		if cycles_per_ms().is_none() {
+			// The TSC is not usable
+			return;
+		}
+		// Compare against a PIT delay of 1 millisecond
+		let ns = idt::wrap_disable_interrupts(|| {
+			let start = read();
+			pit::busy_wait(1_000_000);
+			cycles_to_ns(read().wrapping_sub(start))
+		});
+		assert!(ns.abs_diff(1_000_000) < 100_000);
+		assert!(cycles_to_ns(ns_to_cycles(1_000_000)) >= 1_000_000);
+		// The approximation must be close to the exact conversion
+		let (mult, shift) = mult_shift().unwrap();
+		let cycles = ns_to_cycles(1_000_000);
+		let approx = (cycles * mult as u64) >> shift;
+		assert!(approx.abs_diff(cycles_to_ns(cycles)) < 1_000);
+	}
+}
@@ -25,6 +25,7 @@
 //! - Software Clocks, which maintain a timestamp based on hardware clocks.
 
 pub mod clock;
+mod delay;
 pub mod hw;
 pub mod timer;
 pub mod unit;
@@ -32,18 +33,14 @@ pub mod unit;
 use crate::{
 	event,
 	event::CallbackResult,
-	process::{
-		scheduler::Scheduler,
-		signal::{SigEvent, Signal, SIGEV_SIGNAL},
-		Process, State,
-	},
+	process::{scheduler, scheduler::Scheduler, Process, State},
 	time::{
 		clock::{current_time_ns, Clock},
 		timer::Timer,
-		unit::TimeUnit,
 	},
 };
 use core::{intrinsics::unlikely, mem::ManuallyDrop};
+pub use delay::{ndelay, udelay};
 use unit::Timestamp;
 use utils::{boxed::Box, errno, errno::EResult, math::rational::Rational};
 
@@ -57,35 +54,26 @@ const FREQUENCY: Rational = Rational::from_frac(1, 1024);
 /// If the current process is interrupted by a signal, the function returns [`errno::EINTR`] and
 /// sets the remaining time in `remain`.
 pub fn sleep_until(clock: Clock, ts: Timestamp, remain: &mut Timestamp) -> EResult<()> {
-	// Setup timer
-	let pid = Process::current().get_pid();
-	let mut timer = Timer::new(
-		clock,
-		pid,
-		SigEvent {
-			sigev_notify: SIGEV_SIGNAL,
-			sigev_signo: Signal::SIGALRM as _,
-			sigev_value: 0,
-			sigev_notify_function: None,
-			sigev_notify_attributes: None,
-			sigev_notify_thread_id: 0,
-		},
-	)?;
-	timer.set_time(0, ts)?;
-	// Loop until the timer expires
+	// The timer wakes the process up on the tick at which `ts` is reached
+	let _timer = Timer::wake_at(clock, Process::current().get_pid(), ts)?;
 	loop {
 		let cur_ts = current_time_ns(clock);
-		if unlikely(timer.has_expired(cur_ts)) {
+		if unlikely(cur_ts >= ts) {
 			break;
 		}
 		// The timer has not expired, we need to sleep
 		{
 			let proc = Process::current();
 			if proc.has_pending_signal() {
-				*remain = timer.get_time().it_value.to_nano();
+				*remain = ts - cur_ts;
 				return Err(errno!(EINTR));
 			}
 			proc.set_state(State::Sleeping);
+			// The timer might have fired before the state was changed
+			if unlikely(current_time_ns(clock) >= ts) {
+				proc.set_state(State::Running);
+				break;
+			}
 		}
 		Scheduler::tick();
 	}
@@ -108,19 +96,45 @@ pub(crate) fn init() -> EResult<()> {
 	let mut hw_clocks = hw::CLOCKS.lock();
 	hw_clocks.insert(b"pit".try_into()?, Box::new(hw::pit::PIT::new())?)?;
 	hw_clocks.insert(b"rtc".try_into()?, Box::new(hw::rtc::RTC::new())?)?;
-	// TODO implement HPET
+	let hpet = hw::hpet::init();
+	// If available, the HPET's comparators replace the PIT and the RTC
+	let hpet_timers = hpet && hw::hpet::has_legacy_timers();
+	if hpet_timers {
+		hw_clocks.insert(b"hpet0".try_into()?, Box::new(hw::hpet::HpetTimer::new(0))?)?;
+		hw_clocks.insert(b"hpet1".try_into()?, Box::new(hw::hpet::HpetTimer::new(1))?)?;
+	}
 	// TODO implement APIC timer
+	// Prefer the TSC as the clock source if it is usable, then the HPET
+	hw::tsc::calibrate();
+	if hw::tsc::cycles_per_ms().is_some() {
+		clock::set_tsc_source();
+		clock::set_resolution(1);
+	} else if hpet {
+		clock::set_hpet_source();
+		clock::set_resolution(hw::hpet::resolution());
+	} else {
+		clock::set_resolution(i64::from(FREQUENCY * 1_000_000_000) as _);
+	}
 	// Link hardware clock to software clock
-	let rtc = hw_clocks.get_mut(b"rtc".as_slice()).unwrap();
-	rtc.set_frequency(FREQUENCY);
-	let hook = event::register_callback(rtc.get_interrupt_vector(), move |_, _, _, _| {
+	let name = if hpet_timers {
+		b"hpet1".as_slice()
+	} else {
+		b"rtc".as_slice()
+	};
+	let hw_clock = hw_clocks.get_mut(name).unwrap();
+	// `FREQUENCY` is the interval between ticks
+	hw_clock.set_frequency(Rational::from(1) / FREQUENCY);
+	let hook = event::register_callback(hw_clock.get_interrupt_vector(), move |_, _, _, _| {
+		// Harmless if the HPET is used instead
 		hw::rtc::RTC::reset();
 		// FIXME: the value is probably not right
 		clock::update(i64::from(FREQUENCY * 1_000_000_000) as _);
 		timer::tick();
+		hw::pit::beep_tick();
+		scheduler::watchdog::check();
 		CallbackResult::Continue
 	})?;
 	let _ = ManuallyDrop::new(hook);
-	rtc.set_enabled(true);
+	hw_clock.set_enabled(true);
 	Ok(())
 }
@@ -18,28 +18,33 @@
 
 //! Timers implementation.
 
-use super::unit::{ITimerspec32, TimerT};
+use super::unit::{ITimerspec, TimerT};
 use crate::{
 	memory::oom,
 	process::{
 		pid::Pid,
-		signal::{SigEvent, Signal, SIGEV_NONE, SIGEV_SIGNAL, SIGEV_THREAD},
+		signal::{SigEvent, SigInfo, Signal, SIGEV_NONE, SIGEV_SIGNAL, SI_TIMER},
 		Process,
 	},
 	sync::mutex::{IntMutex, Mutex},
 	time::{
 		clock::{current_time_ns, Clock},
-		unit::{TimeUnit, Timespec32, Timestamp},
+		unit::{TimeUnit, Timestamp},
 	},
 };
-use core::intrinsics::unlikely;
+use core::{ffi::c_int, intrinsics::unlikely};
 use utils::{
 	boxed::Box,
 	collections::{btreemap::BTreeMap, hashmap::HashMap, id_allocator::IDAllocator},
 	errno,
 	errno::{AllocResult, EResult},
-	limits::TIMER_MAX,
+	limits::{DELAYTIMER_MAX, TIMER_MAX},
 };
+/// Kernel-internal notification method: wakes the process up, without sending any signal.
+///
+/// This is used to implement timeouts on blocking operations.
+const SIGEV_WAKE: c_int = -1;
+
 // TODO make sure a timer doesn't send a signal to a thread that do not belong to the manager's
 // process
 
@@ -51,9 +56,13 @@ struct TimerSpec {
 	///
 	/// If zero, the timer is unarmed.
 	next: Option<Timestamp>,
+	/// The number of expirations that were not notified, for the last notification.
+	overrun: u32,
 }
 
 struct TimerInner {
+	/// The ID of the timer in its manager.
+	id: TimerT,
 	/// The clock to user.
 	clock: Clock,
 	/// PID of the process to notify.
@@ -85,7 +94,9 @@ impl TimerInner {
 	}
 
 	/// Fires the timer.
-	fn fire(&self) {
+	///
+	/// `ts` is the current timestamp in nanoseconds.
+	fn fire(&self, ts: Timestamp) {
 		match self.sevp.sigev_notify {
 			SIGEV_NONE => {}
 			SIGEV_SIGNAL => {
@@ -95,10 +106,39 @@ impl TimerInner {
 				let Some(proc) = Process::get_by_pid(self.pid) else {
 					return;
 				};
-				// TODO on sigint_t, set si_code to SI_TIMER
-				proc.kill(signal);
+				let mut spec = self.spec.lock();
+				// Periodic expirations that elapsed before the timer could be fired
+				let missed = match (spec.next, spec.interval) {
+					(Some(next), interval @ 1..) => ts.saturating_sub(next) / interval,
+					_ => 0,
+				};
+				let missed = missed.try_into().unwrap_or(u32::MAX);
+				let mut signal_manager = proc.signal.lock();
+				// If the previous notification has not been delivered yet, count an overrun
+				// instead of sending the signal again
+				let pending = signal_manager.find_pending(|info| {
+					info.si_code == SI_TIMER && info.si_timerid == self.id as i32
+				});
+				if let Some(info) = pending {
+					let overrun = (info.si_overrun as u32).saturating_add(missed + 1);
+					info.si_overrun = overrun.min(DELAYTIMER_MAX as _) as _;
+					spec.overrun = info.si_overrun as _;
+					return;
+				}
+				drop(signal_manager);
+				spec.overrun = missed.min(DELAYTIMER_MAX as _);
+				proc.kill_info(SigInfo {
+					si_value: self.sevp.sigev_value,
+					si_timerid: self.id as _,
+					si_overrun: spec.overrun as _,
+					..SigInfo::new(signal, SI_TIMER)
+				});
+			}
+			SIGEV_WAKE => {
+				if let Some(proc) = Process::get_by_pid(self.pid) {
+					proc.wake();
+				}
 			}
-			SIGEV_THREAD => todo!(),
 			_ => {}
 		}
 	}
@@ -123,7 +163,10 @@ impl TimerInner {
 		if spec.interval == 0 {
 			spec.next = None;
 		} else {
-			let next = ts + spec.interval;
+			// Keep expirations aligned on the interval, skipping the ones that were missed
+			let next = spec.next.unwrap_or(ts);
+			let missed = ts.saturating_sub(next) / spec.interval;
+			let next = next + (missed + 1) * spec.interval;
 			spec.next = Some(next);
 			// Insert back in queue
 			queue.insert((next, self), ())?;
@@ -139,15 +182,17 @@ impl Timer {
 	/// Creates a timer.
 	///
 	/// Arguments:
+	/// - `id` is the ID of the timer, reported with its notifications.
 	/// - `clock` is the clock to use.
 	/// - `pid` is the PID of the process to notify.
 	/// - `sevp` describes the event to be triggered by the clock.
-	pub fn new(clock: Clock, pid: Pid, sevp: SigEvent) -> EResult<Self> {
+	pub fn new(id: TimerT, clock: Clock, pid: Pid, sevp: SigEvent) -> EResult<Self> {
 		// Validation
 		if unlikely(!sevp.is_valid()) {
 			return Err(errno!(EINVAL));
 		}
 		Ok(Self(Box::new(TimerInner {
+			id,
 			clock,
 			pid,
 			sevp,
@@ -156,28 +201,70 @@ impl Timer {
 		})?))
 	}
 
+	/// Creates a oneshot timer waking up the process `pid` when the timestamp `ts`, in
+	/// nanoseconds, is reached on `clock`.
+	///
+	/// The timer is disarmed when dropped.
+	pub fn wake_at(clock: Clock, pid: Pid, ts: Timestamp) -> AllocResult<Self> {
+		let timer = Self(Box::new(TimerInner {
+			id: 0,
+			clock,
+			pid,
+			sevp: SigEvent {
+				sigev_value: 0,
+				sigev_signo: 0,
+				sigev_notify: SIGEV_WAKE,
+				sigev_notify_function: None,
+				sigev_notify_attributes: None,
+			},
+			spec: Mutex::new(TimerSpec {
+				interval: 0,
+				next: Some(ts),
+				overrun: 0,
+			}),
+		})?);
+		TIMERS_QUEUE.lock().insert((ts, timer.0.as_ptr()), ())?;
+		Ok(timer)
+	}
+
 	/// Returns the current state of the timer.
+	///
+	/// The value is the time remaining until the next expiration. If the timer is disarmed, it
+	/// is zero.
 	#[inline]
-	pub fn get_time(&self) -> ITimerspec32 {
+	pub fn get_time<T: TimeUnit>(&self) -> ITimerspec<T> {
 		let spec = self.0.spec.lock();
 		let value = spec
 			.next
-			.map(|next| next.saturating_sub(current_time_ns(self.0.clock)))
+			// An expired timer that has not been fired yet is about to be
+			.map(|next| next.saturating_sub(current_time_ns(self.0.clock)).max(1))
 			.unwrap_or(0);
-		ITimerspec32 {
-			it_interval: Timespec32::from_nano(spec.interval),
-			it_value: Timespec32::from_nano(value),
+		ITimerspec {
+			it_interval: T::from_nano(spec.interval),
+			it_value: T::from_nano(value),
 		}
 	}
 
+	/// Returns the number of expirations that were not notified, for the last notification of
+	/// the timer.
+	pub fn get_overrun(&self) -> u32 {
+		self.0.spec.lock().overrun
+	}
+
 	/// Sets the timer's state.
 	///
 	/// Arguments:
 	/// - `interval` is the interval between two timer tick
-	/// - `value` is the initial value of the timer
+	/// - `value` is the initial value of the timer. If zero, the timer is disarmed
+	/// - `absolute` tells whether `value` is a timestamp on the timer's clock instead of a delay
 	///
 	/// On allocation error, the function returns an error.
-	pub fn set_time(&mut self, interval: Timestamp, value: Timestamp) -> AllocResult<()> {
+	pub fn set_time(
+		&mut self,
+		interval: Timestamp,
+		value: Timestamp,
+		absolute: bool,
+	) -> AllocResult<()> {
 		let mut queue = TIMERS_QUEUE.lock();
 		let mut spec = self.0.spec.lock();
 		// Remove from queue
@@ -186,11 +273,16 @@ impl Timer {
 		}
 		// Update timer
 		spec.interval = interval;
+		spec.overrun = 0;
 		// Arm or disarm
 		if value == 0 {
 			spec.next = None;
 		} else {
-			let next = current_time_ns(self.0.clock) + interval;
+			let next = if absolute {
+				value
+			} else {
+				current_time_ns(self.0.clock) + value
+			};
 			spec.next = Some(next);
 			// Insert back in queue
 			queue.insert((next, self.0.as_ptr()), ())?;
@@ -244,12 +336,26 @@ impl TimerManager {
 	///
 	/// Arguments:
 	/// - `clock` is the clock to use.
-	/// - `sevp` describes the event to be triggered by the clock.
+	/// - `sevp` describes the event to be triggered by the clock. If `None`, the timer sends
+	///   [`Signal::SIGALRM`] with its ID as value.
 	///
 	/// On success, the function returns the ID of the newly created timer.
-	pub fn create_timer(&mut self, clock: Clock, sevp: SigEvent) -> EResult<u32> {
-		let timer = Timer::new(clock, self.pid, sevp)?;
+	pub fn create_timer(&mut self, clock: Clock, sevp: Option<SigEvent>) -> EResult<u32> {
 		let id = self.id_allocator.alloc(None)?;
+		let sevp = sevp.unwrap_or(SigEvent {
+			sigev_value: id as _,
+			sigev_signo: Signal::SIGALRM as _,
+			sigev_notify: SIGEV_SIGNAL,
+			sigev_notify_function: None,
+			sigev_notify_attributes: None,
+		});
+		let timer = match Timer::new(id as _, clock, self.pid, sevp) {
+			Ok(timer) => timer,
+			Err(e) => {
+				self.id_allocator.free(id);
+				return Err(e);
+			}
+		};
 		if let Err(e) = self.timers.insert(id, timer) {
 			// Allocation error: rollback
 			self.id_allocator.free(id);
@@ -272,6 +378,7 @@ impl TimerManager {
 		self.timers
 			.remove(&(id as _))
 			.ok_or_else(|| errno!(EINVAL))?;
+		self.id_allocator.free(id as _);
 		Ok(())
 	}
 }
@@ -300,7 +407,7 @@ pub(super) fn tick() {
 			// If this timer has not expired, all the following timers won't be expired either
 			break;
 		}
-		timer.fire();
+		timer.fire(ts);
 		if timer.is_oneshot() {
 			queue.pop_first();
 		} else {
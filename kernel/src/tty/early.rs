@@ -0,0 +1,34 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! Early boot console.
+//!
+//! Before the TTY is initialized, kernel logs are written directly to the first serial port. The
+//! early console is also used as a fallback when the TTY cannot be used, such as when a panic
+//! occurs while it is locked.
+
+use crate::device::serial;
+
+/// Writes `buf` to the early console.
+pub fn write(buf: &[u8]) {
+	match serial::PORTS[0].try_lock() {
+		Some(mut port) => port.write(buf),
+		// The port is held by a context interrupted by a panic, which will never resume
+		None => serial::Serial::from_port(serial::COM1).write(buf),
+	}
+}
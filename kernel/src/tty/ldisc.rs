@@ -0,0 +1,337 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! The line discipline processes the input of a terminal according to its [`Termios`]: it
+//! translates characters, edits lines in canonical mode, echoes and triggers signals.
+//!
+//! It is shared by the TTY and pseudo-terminals, which differ only in where echoes are written.
+
+use crate::{
+	file::{wait_queue, wait_queue::WaitQueue},
+	process::signal::Signal,
+	sync::mutex::Mutex,
+	time::{
+		clock::{current_time_ns, Clock},
+		unit::Timestamp,
+	},
+	tty::termios::{consts::*, Termios},
+};
+use core::{
+	cmp::min,
+	sync::atomic::{AtomicBool, Ordering::Relaxed},
+};
+use utils::{errno, errno::EResult};
+
+/// The maximum number of characters in the input buffer of a terminal.
+const INPUT_MAX: usize = 4096;
+
+/// The destination of a terminal's echoes.
+pub trait Echo {
+	/// Writes `buf` as an echo of the input.
+	fn echo(&mut self, buf: &[u8]);
+	/// Erases the last `count` echoed characters.
+	fn echo_erase(&mut self, count: usize);
+	/// Rings the bell, when the input buffer is full.
+	fn bell(&mut self);
+}
+
+/// Terminal input buffer.
+struct TTYInput {
+	/// The buffer containing characters from terminal input.
+	buf: [u8; INPUT_MAX],
+	/// The current size of the input buffer.
+	input_size: usize,
+	/// The size of the data available to be read from the terminal.
+	available_size: usize,
+}
+
+impl TTYInput {
+	/// Moves available data to `buf`, removing it from the input buffer.
+	///
+	/// The function returns the number of bytes moved.
+	fn consume(&mut self, buf: &mut [u8]) -> usize {
+		let len = min(buf.len(), self.available_size);
+		buf[..len].copy_from_slice(&self.buf[..len]);
+		// Shift data
+		self.buf.rotate_left(len);
+		self.input_size -= len;
+		self.available_size -= len;
+		len
+	}
+
+	/// Reads a line in canonical mode.
+	///
+	/// If no full line is available, the function returns `None`.
+	fn read_canon(&mut self, termios: &Termios, buf: &mut [u8]) -> Option<usize> {
+		if self.available_size == 0 {
+			return None;
+		}
+		let mut len = min(buf.len(), self.available_size);
+		let eof = termios.c_cc[VEOF];
+		let eof_off = self.buf[..len].iter().position(|v| *v == eof);
+		if eof_off == Some(0) {
+			// Shift data
+			self.buf.rotate_left(1);
+			self.input_size -= 1;
+			self.available_size -= 1;
+			return Some(0);
+		}
+		if let Some(eof_off) = eof_off {
+			// Making the next call EOF
+			len = eof_off;
+		}
+		Some(self.consume(&mut buf[..len]))
+	}
+}
+
+/// A terminal's line discipline.
+pub struct LineDiscipline {
+	/// The input buffer.
+	input: Mutex<TTYInput>,
+	/// The queue of processes waiting for incoming data to read.
+	rd_queue: WaitQueue,
+	/// If set, the terminal has been hung up: reading it returns end-of-file once the input is
+	/// drained.
+	hung_up: AtomicBool,
+}
+
+impl LineDiscipline {
+	/// Creates a new instance.
+	#[allow(clippy::new_without_default)]
+	pub const fn new() -> Self {
+		Self {
+			input: Mutex::new(TTYInput {
+				buf: [0; INPUT_MAX],
+				input_size: 0,
+				available_size: 0,
+			}),
+			rd_queue: WaitQueue::new(),
+			hung_up: AtomicBool::new(false),
+		}
+	}
+
+	/// Returns the number of bytes available for reading, or `None` if the terminal has been hung
+	/// up and nothing is left.
+	fn available(&self) -> Option<usize> {
+		let available = self.input.lock().available_size;
+		if available == 0 && self.hung_up.load(Relaxed) {
+			return None;
+		}
+		Some(available)
+	}
+
+	/// Waits for data in non-canonical mode, according to `VMIN` and `VTIME`.
+	fn wait_raw(&self, termios: &Termios, buf_len: usize) -> EResult<()> {
+		let min_chars = min(termios.c_cc[VMIN] as usize, buf_len);
+		// `VTIME` is in tenths of a second
+		let time = termios.c_cc[VTIME] as Timestamp * 100_000_000;
+		// Once hung up, stop waiting
+		let available = || self.available().unwrap_or(usize::MAX);
+		if time == 0 {
+			return self
+				.rd_queue
+				.wait_until(|| (available() >= min_chars).then_some(()));
+		}
+		// If `VMIN` is set, `VTIME` is an inter-byte timeout starting after the first byte
+		if min_chars > 0 {
+			self.rd_queue
+				.wait_until(|| (available() > 0).then_some(()))?;
+		}
+		let mut last = available();
+		while last < min_chars.max(1) {
+			let deadline = current_time_ns(Clock::Monotonic) + time;
+			let res = self
+				.rd_queue
+				.wait_until_deadline(Clock::Monotonic, deadline, || {
+					let cur = available();
+					(cur > last).then_some(cur)
+				});
+			match res {
+				Ok(_) if min_chars == 0 => break,
+				Ok(cur) => last = cur,
+				Err(e) if e.as_int() == errno::ETIMEDOUT => break,
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(())
+	}
+
+	// TODO Implement IUTF8
+	/// Reads input and places it into the buffer `buf`.
+	///
+	/// In canonical mode, the function waits for a full line. Else, it waits according to the
+	/// `VMIN` and `VTIME` special characters.
+	///
+	/// The function returns the number of bytes read.
+	pub fn read(&self, termios: &Termios, buf: &mut [u8]) -> EResult<usize> {
+		if termios.c_lflag & ICANON != 0 {
+			return self.rd_queue.wait_until(|| {
+				let res = self.input.lock().read_canon(termios, buf);
+				res.or_else(|| self.available().is_none().then_some(0))
+			});
+		}
+		self.wait_raw(termios, buf.len())?;
+		Ok(self.input.lock().consume(buf))
+	}
+
+	/// Tells whether any data is available to be read.
+	pub fn has_input_available(&self, termios: &Termios) -> bool {
+		// Canonical mode
+		let canon = termios.c_lflag & ICANON != 0;
+		let min = if canon {
+			1
+		} else {
+			termios.c_cc[VMIN] as usize
+		};
+		self.input.lock().available_size >= min
+	}
+
+	/// Processes the input byte `b` according to `termios`, echoing on `out`.
+	///
+	/// If the byte triggers a signal, the function returns it.
+	pub fn input_byte(&self, termios: &Termios, out: &mut dyn Echo, mut b: u8) -> Option<Signal> {
+		let mut input = self.input.lock();
+		// TODO Implement IGNBRK and BRKINT
+		// TODO Implement parity checking
+		if termios.c_iflag & ISTRIP != 0 {
+			// Stripping eighth bit
+			b &= 0x7f;
+		}
+		if b == b'\r' && termios.c_iflag & IGNCR != 0 {
+			return None;
+		}
+		if termios.c_iflag & INLCR != 0 && b == b'\n' {
+			// Translating NL to CR
+			b = b'\r';
+		} else if termios.c_iflag & ICRNL != 0 && b == b'\r' {
+			// Translating CR to NL
+			b = b'\n';
+		}
+		if termios.c_iflag & IUCLC != 0 {
+			// Translating uppercase characters to lowercase
+			b = b.to_ascii_lowercase();
+		}
+		// TODO IXON
+		// TODO IXANY
+		// TODO IXOFF
+
+		let echo = termios.c_lflag & ECHO != 0;
+		let echoctl = termios.c_lflag & ECHOCTL != 0;
+		// Sending signals if enabled
+		if termios.c_lflag & ISIG != 0 {
+			let sig = match b {
+				_ if b == termios.c_cc[VINTR] => Some(Signal::SIGINT),
+				_ if b == termios.c_cc[VQUIT] => Some(Signal::SIGQUIT),
+				_ if b == termios.c_cc[VSUSP] => Some(Signal::SIGTSTP),
+				_ => None,
+			};
+			if let Some(sig) = sig {
+				if echo && echoctl {
+					out.echo(&[b'^', b + b'@']);
+				}
+				if termios.c_lflag & NOFLSH == 0 {
+					input.input_size = 0;
+					input.available_size = 0;
+				}
+				return Some(sig);
+			}
+		}
+
+		let canon = termios.c_lflag & ICANON != 0;
+		if canon {
+			if b == termios.c_cc[VERASE] {
+				if input.input_size > input.available_size {
+					input.input_size -= 1;
+					if echo && termios.c_lflag & ECHOE != 0 {
+						let c = input.buf[input.input_size];
+						out.echo_erase(if echoctl && c < 32 { 2 } else { 1 });
+					}
+				}
+				return None;
+			}
+			if b == termios.c_cc[VKILL] {
+				let line = &input.buf[input.available_size..input.input_size];
+				let count = line
+					.iter()
+					.map(|c| if echoctl && *c < 32 { 2 } else { 1 })
+					.sum();
+				input.input_size = input.available_size;
+				if echo && termios.c_lflag & ECHOK != 0 {
+					out.echo_erase(count);
+				}
+				return None;
+			}
+		}
+
+		if input.input_size >= input.buf.len() {
+			// Ring the bell if the buffer is full
+			if termios.c_iflag & IMAXBEL != 0 {
+				out.bell();
+			}
+			return None;
+		}
+		let input_size = input.input_size;
+		input.buf[input_size] = b;
+		input.input_size += 1;
+
+		let eol = b == b'\n' || (termios.c_cc[VEOL] != 0 && b == termios.c_cc[VEOL]);
+		let eof = b == termios.c_cc[VEOF];
+		if echo {
+			if canon && eof {
+				// EOF is not echoed
+			} else if echoctl && b < 32 && b != b'\n' && b != b'\t' {
+				out.echo(&[b'^', b + b'@']);
+			} else {
+				out.echo(&[b]);
+			}
+		} else if canon && b == b'\n' && termios.c_lflag & ECHONL != 0 {
+			out.echo(b"\n");
+		}
+		// Making the input available for reading
+		if !canon || eol || eof {
+			input.available_size = input.input_size;
+		}
+		None
+	}
+
+	/// Erases `count` characters of the line being edited, in canonical mode.
+	pub fn erase(&self, termios: &Termios, out: &mut dyn Echo, count: usize) {
+		{
+			let mut input = self.input.lock();
+			let count = min(count, input.input_size - input.available_size);
+			input.input_size -= count;
+			if termios.c_lflag & ECHOE != 0 {
+				out.echo_erase(count);
+			}
+		}
+		self.wake();
+	}
+
+	/// Wakes up a process waiting for input.
+	pub fn wake(&self) {
+		self.rd_queue.wake_next();
+		wait_queue::poll_notify();
+	}
+
+	/// Hangs up the terminal, making readers return end-of-file once the input is drained.
+	pub fn hangup(&self) {
+		self.hung_up.store(true, Relaxed);
+		self.rd_queue.wake_all();
+		wait_queue::poll_notify();
+	}
+}
@@ -19,23 +19,28 @@
 //! The TeleTypeWriter (TTY) is an electromechanical device that was used in the
 //! past to send and receive typed messages through a communication channel.
 //!
-//! This module implements line discipline for TTYs.
+//! This module implements the kernel's TTY. The line discipline, shared with pseudo-terminals,
+//! is implemented in [`ldisc`].
 //!
 //! At startup, the kernel has one TTY: the init TTY, which is stored separately
 //! because at the time of creation, memory management isn't initialized yet.
 
 mod ansi;
+pub mod early;
+pub mod ldisc;
+pub mod pty;
 pub mod termios;
 pub mod vga;
 
 use crate::{
 	device::serial,
-	file::wait_queue::WaitQueue,
 	memory::vmem,
 	process::{pid::Pid, signal::Signal, Process},
 	sync::mutex::Mutex,
+	time::hw::pit,
 	tty::{
 		ansi::ANSIBuffer,
+		ldisc::{Echo, LineDiscipline},
 		termios::{consts::*, Termios},
 	},
 };
@@ -53,9 +58,6 @@ const EMPTY_CHAR: vga::Char = (vga::DEFAULT_COLOR as vga::Char) << 8;
 /// The size of a tabulation in space-equivalent.
 const TAB_SIZE: usize = 4;
 
-/// The maximum number of characters in the input buffer of a TTY.
-const INPUT_MAX: usize = 4096;
-
 /// The frequency of the bell in Hz.
 const BELL_FREQUENCY: u32 = 2000;
 /// The duraction of the bell in ms.
@@ -93,7 +95,7 @@ fn get_tab_size(cursor_x: vga::Pos) -> usize {
 
 /// Rings the TTY's bell.
 fn ring_bell() {
-	// TODO
+	pit::beep(BELL_FREQUENCY, BELL_DURATION);
 }
 
 /// Sends a signal `sig` to the given process group `pgid`.
@@ -127,11 +129,17 @@ pub struct TTYDisplay {
 	/// The ANSI escape codes buffer.
 	ansi_buffer: ANSIBuffer,
 
+	/// The ID of the session of which the TTY is the controlling terminal.
+	///
+	/// If zero, the TTY is not the controlling terminal of any session.
+	sid: Pid,
 	/// The current foreground Program Group ID.
 	pgrp: Pid,
 
 	/// Tells whether the cursor is currently visible on screen.
 	cursor_visible: bool,
+	/// The first and last scanlines of the cursor.
+	cursor_shape: (u8, u8),
 	/// The current color for the text to be written
 	current_color: vga::Color,
 }
@@ -164,12 +172,29 @@ impl TTYDisplay {
 	pub fn set_cursor_visible(&mut self, visible: bool) {
 		self.cursor_visible = visible;
 		if visible {
-			vga::enable_cursor();
+			vga::enable_cursor(self.cursor_shape.0, self.cursor_shape.1);
 		} else {
 			vga::disable_cursor();
 		}
 	}
 
+	/// Returns the cursor's visibility and shape, as its first and last scanlines.
+	pub fn get_cursor(&self) -> (bool, u8, u8) {
+		(
+			self.cursor_visible,
+			self.cursor_shape.0,
+			self.cursor_shape.1,
+		)
+	}
+
+	/// Sets the shape of the cursor, from scanline `start` to `end`.
+	///
+	/// Scanlines are clamped to the height of a character.
+	pub fn set_cursor_shape(&mut self, start: u8, end: u8) {
+		self.cursor_shape = (min(start, vga::CURSOR_END), min(end, vga::CURSOR_END));
+		self.set_cursor_visible(self.cursor_visible);
+	}
+
 	/// Reinitializes TTY's current attributes.
 	pub fn reset_attrs(&mut self) {
 		self.current_color = vga::DEFAULT_COLOR;
@@ -341,7 +366,11 @@ impl TTYDisplay {
 	pub fn write(&mut self, buffer: &[u8]) {
 		// TODO Add a compilation and/or runtime option for this
 		serial::PORTS[0].lock().write(buffer);
+		self.write_screen(buffer);
+	}
 
+	/// Writes string `buffer` to the screen only.
+	pub fn write_screen(&mut self, buffer: &[u8]) {
 		let mut i = 0;
 		while i < buffer.len() {
 			let c = buffer[i];
@@ -358,37 +387,82 @@ impl TTYDisplay {
 		}
 		self.update();
 	}
+}
 
+/// The control settings of a terminal, shared by the TTY and pseudo-terminals.
+pub trait Terminal {
 	/// Returns the terminal IO settings.
-	pub fn get_termios(&self) -> &Termios {
+	fn get_termios(&self) -> &Termios;
+	/// Sets the terminal IO settings.
+	fn set_termios(&mut self, termios: Termios);
+
+	/// Returns the current foreground Program Group ID.
+	fn get_pgrp(&self) -> Pid;
+	/// Sets the current foreground Program Group ID.
+	fn set_pgrp(&mut self, pgrp: Pid);
+
+	/// Returns the ID of the session of which the terminal is the controlling terminal.
+	///
+	/// If the terminal is not a controlling terminal, the function returns zero.
+	fn get_sid(&self) -> Pid;
+	/// Makes the terminal the controlling terminal of the session `sid`, with `pgrp` as the
+	/// foreground process group.
+	fn set_session(&mut self, sid: Pid, pgrp: Pid);
+
+	/// If the terminal is the controlling terminal of the session `sid`, disassociates it from
+	/// the session.
+	///
+	/// The foreground process group receives `SIGHUP` and `SIGCONT`.
+	fn hangup(&mut self, sid: Pid) {
+		if sid == 0 || self.get_sid() != sid {
+			return;
+		}
+		let pgrp = self.get_pgrp();
+		send_signal(Signal::SIGHUP, pgrp);
+		send_signal(Signal::SIGCONT, pgrp);
+		self.set_session(0, 0);
+	}
+
+	/// Returns the window size of the terminal.
+	fn get_winsize(&self) -> &WinSize;
+	/// Sets the window size of the terminal.
+	///
+	/// If a foreground process group is set on the terminal, the function shall send it a
+	/// `SIGWINCH` signal.
+	fn set_winsize(&mut self, winsize: WinSize);
+}
+
+impl Terminal for TTYDisplay {
+	fn get_termios(&self) -> &Termios {
 		&self.termios
 	}
 
-	/// Sets the terminal IO settings.
-	pub fn set_termios(&mut self, termios: Termios) {
+	fn set_termios(&mut self, termios: Termios) {
 		self.termios = termios;
 	}
 
-	/// Returns the current foreground Program Group ID.
-	pub fn get_pgrp(&self) -> Pid {
+	fn get_pgrp(&self) -> Pid {
 		self.pgrp
 	}
 
-	/// Sets the current foreground Program Group ID.
-	pub fn set_pgrp(&mut self, pgrp: Pid) {
+	fn set_pgrp(&mut self, pgrp: Pid) {
 		self.pgrp = pgrp;
 	}
 
-	/// Returns the window size of the TTY.
-	pub fn get_winsize(&self) -> &WinSize {
+	fn get_sid(&self) -> Pid {
+		self.sid
+	}
+
+	fn set_session(&mut self, sid: Pid, pgrp: Pid) {
+		self.sid = sid;
+		self.pgrp = pgrp;
+	}
+
+	fn get_winsize(&self) -> &WinSize {
 		&self.winsize
 	}
 
-	/// Sets the window size of the TTY.
-	///
-	/// If a foreground process group is set on the TTY, the function shall send
-	/// it a `SIGWINCH` signal.
-	pub fn set_winsize(&mut self, mut winsize: WinSize) {
+	fn set_winsize(&mut self, mut winsize: WinSize) {
 		// Clamping values
 		if winsize.ws_col > vga::WIDTH as _ {
 			winsize.ws_col = vga::WIDTH as _;
@@ -407,14 +481,22 @@ impl TTYDisplay {
 	}
 }
 
-/// TTY input manager.
-struct TTYInput {
-	/// The buffer containing characters from TTY input.
-	buf: [u8; INPUT_MAX],
-	/// The current size of the input buffer.
-	input_size: usize,
-	/// The size of the data available to be read from the TTY.
-	available_size: usize,
+impl Echo for TTYDisplay {
+	fn echo(&mut self, buf: &[u8]) {
+		self.write(buf);
+	}
+
+	fn echo_erase(&mut self, count: usize) {
+		// TODO Handle tab characters
+		self.cursor_backward(count, 0);
+		let begin = get_history_offset(self.cursor_x, self.cursor_y);
+		self.history[begin..(begin + count)].fill(EMPTY_CHAR);
+		self.update();
+	}
+
+	fn bell(&mut self) {
+		ring_bell();
+	}
 }
 
 // TODO Use the values in winsize
@@ -422,10 +504,8 @@ struct TTYInput {
 pub struct TTY {
 	/// Display manager.
 	pub display: Mutex<TTYDisplay>,
-	/// Input manager.
-	input: Mutex<TTYInput>,
-	/// The queue of processes waiting for incoming data to read.
-	rd_queue: WaitQueue,
+	/// The line discipline, handling input.
+	ldisc: LineDiscipline,
 }
 
 /// The TTY.
@@ -447,225 +527,128 @@ pub static TTY: TTY = TTY {
 		},
 		ansi_buffer: ANSIBuffer::new(),
 
+		sid: 0,
 		pgrp: 0,
 
 		cursor_visible: true,
+		cursor_shape: (vga::CURSOR_START, vga::CURSOR_END),
 		current_color: vga::DEFAULT_COLOR,
 	}),
-	input: Mutex::new(TTYInput {
-		buf: [0; INPUT_MAX],
-		input_size: 0,
-		available_size: 0,
-	}),
-	rd_queue: WaitQueue::new(),
+	ldisc: LineDiscipline::new(),
 };
 
 impl TTY {
-	// TODO Implement IUTF8
 	/// Reads inputs from the TTY and places it into the buffer `buf`.
 	///
+	/// In canonical mode, the function waits for a full line. Else, it waits according to the
+	/// `VMIN` and `VTIME` special characters.
+	///
 	/// The function returns the number of bytes read.
 	pub fn read(&self, buf: &mut [u8]) -> EResult<usize> {
-		self.rd_queue.wait_until(|| {
-			let termios = self.display.lock().get_termios().clone();
-			let mut input = self.input.lock();
-			// Canonical mode
-			let canon = termios.c_lflag & ICANON != 0;
-			let min_chars = if canon {
-				1
-			} else {
-				termios.c_cc[VMIN] as usize
-			};
-			// If not enough data is available, wait
-			if input.available_size < min_chars {
-				return None;
-			}
-			let mut len = min(buf.len(), input.available_size);
-			if canon {
-				let eof = termios.c_cc[VEOF];
-				let eof_off = input.buf[..len].iter().position(|v| *v == eof);
-				if eof_off == Some(0) {
-					// Shift data
-					input.buf.rotate_left(1);
-					input.input_size -= 1;
-					input.available_size -= 1;
-					return Some(0);
-				}
-				if let Some(eof_off) = eof_off {
-					// Making the next call EOF
-					len = eof_off;
-				}
-			} else {
-				// Update available length
-				len = min(buf.len(), input.available_size);
-			}
-			// Copy data
-			buf[..len].copy_from_slice(&input.buf[..len]);
-			// Shift data
-			input.buf.rotate_left(len);
-			input.input_size -= len;
-			input.available_size -= len;
-			// Ring the bell if the buffer is full
-			if termios.c_iflag & IMAXBEL != 0 && input.input_size >= buf.len() {
-				ring_bell();
-			}
-			Some(len)
-		})
+		let termios = self.display.lock().get_termios().clone();
+		self.ldisc.read(&termios, buf)
 	}
 
 	/// Tells whether the TTY has any data available to be read.
 	pub fn has_input_available(&self) -> bool {
-		let display = self.display.lock();
-		let input = self.input.lock();
-		// Canonical mode
-		let canon = display.termios.c_lflag & ICANON != 0;
-		let min = if canon {
-			1
-		} else {
-			display.get_termios().c_cc[VMIN] as usize
-		};
-		input.available_size >= min
+		let termios = self.display.lock().get_termios().clone();
+		self.ldisc.has_input_available(&termios)
 	}
 
-	// TODO Implement IUTF8
 	/// Takes the given string `buffer` as input, making it available from the
 	/// terminal input.
 	pub fn input(&self, buffer: &[u8]) {
-		let termios = self.display.lock().get_termios().clone();
-		let mut input = self.input.lock();
-		// The length to write to the input buffer
-		let len = min(buffer.len(), input.buf.len() - input.input_size);
-		// The slice containing the input
-		let buffer = &buffer[..len];
-
-		if termios.c_lflag & ECHO != 0 {
-			// Write onto the TTY
-			self.display.lock().write(buffer);
-		}
-		// TODO If ECHO is disabled but ICANON and ECHONL are set, print newlines
-
-		// TODO Implement IGNBRK and BRKINT
-		// TODO Implement parity checking
-
-		// Writing to the input buffer
-		// TODO Put in a different function
-		{
-			let input_size = input.input_size;
-			utils::slice_copy(buffer, &mut input.buf[input_size..]);
-			let new_bytes = &mut input.buf[input_size..(input_size + len)];
-
-			for b in new_bytes {
-				if termios.c_iflag & ISTRIP != 0 {
-					// Stripping eighth bit
-					*b &= 1 << 7;
-				}
-
-				// TODO Implement IGNCR (ignore carriage return)
-
-				if termios.c_iflag & INLCR != 0 {
-					// Translating NL to CR
-					if *b == b'\n' {
-						*b = b'\r';
-					}
-				}
-
-				if termios.c_iflag & ICRNL != 0 {
-					// Translating CR to NL
-					if *b == b'\r' {
-						*b = b'\n';
-					}
-				}
-
-				if termios.c_iflag & IUCLC != 0 {
-					// Translating uppercase characters to lowercase
-					if (*b as char).is_ascii_uppercase() {
-						*b = (*b as char).to_ascii_uppercase() as u8;
-					}
-				}
-			}
-			input.input_size += len;
-		}
-
-		// TODO IXON
-		// TODO IXANY
-		// TODO IXOFF
-
-		if termios.c_lflag & ICANON != 0 {
-			// Processing input
-			let mut i = input.input_size - len;
-			while i < input.input_size {
-				let b = input.buf[i];
-
-				if b == termios.c_cc[VEOF] || b == b'\n' {
-					// Making the input available for reading
-					input.available_size = i + 1;
-
-					i += 1;
-				} else if b == 0xf7 {
-					// TODO Check
-					self.erase(1);
-				} else {
-					i += 1;
-				}
-			}
-		} else {
-			// Making the input available for reading
-			input.available_size = input.input_size;
-		}
-
-		// Sending signals if enabled
-		if termios.c_lflag & ISIG != 0 {
-			for b in buffer {
-				// Printing special control characters if enabled
-				if termios.c_lflag & ECHO != 0
-					&& termios.c_lflag & ECHOCTL != 0
-					&& *b >= 1 && *b < 32
-				{
-					self.display.lock().write(&[b'^', b + b'A']);
-				}
-
-				// TODO Handle every special characters
-				let pgrp = self.display.lock().pgrp;
-				if *b == termios.c_cc[VINTR] {
-					send_signal(Signal::SIGINT, pgrp);
-				} else if *b == termios.c_cc[VQUIT] {
-					send_signal(Signal::SIGQUIT, pgrp);
-				} else if *b == termios.c_cc[VSUSP] {
-					send_signal(Signal::SIGTSTP, pgrp);
-				}
+		for b in buffer {
+			let (sig, pgrp) = {
+				let mut disp = self.display.lock();
+				let termios = disp.get_termios().clone();
+				let sig = self.ldisc.input_byte(&termios, &mut *disp, *b);
+				(sig, disp.pgrp)
+			};
+			if let Some(sig) = sig {
+				send_signal(sig, pgrp);
 			}
 		}
-
-		self.rd_queue.wake_next();
+		self.ldisc.wake();
 	}
 
 	/// Erases `count` characters in TTY.
 	pub fn erase(&self, count: usize) {
 		let termios = self.display.lock().termios.clone();
-		let mut input = self.input.lock();
 		if termios.c_lflag & ICANON != 0 {
-			let count = min(count, input.buf.len());
-			if count > input.input_size {
-				return;
-			}
-
-			if termios.c_lflag & ECHOE != 0 {
-				let mut disp = self.display.lock();
-				// TODO Handle tab characters
-				disp.cursor_backward(count, 0);
-				let begin = get_history_offset(disp.cursor_x, disp.cursor_y);
-				disp.history[begin..(begin + count)].fill(EMPTY_CHAR);
-				disp.update();
-			}
-
-			input.input_size -= count;
+			let mut disp = self.display.lock();
+			self.ldisc.erase(&termios, &mut *disp, count);
 		} else {
 			// Printing DEL characters
 			for _ in 0..count {
 				self.input(&[0x7f]);
 			}
 		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn tty_backspace() {
+		let mut disp = TTY.display.lock();
+		disp.write_screen(b"\nab\x08c");
+		let begin = get_history_offset(0, disp.cursor_y);
+		assert_eq!(disp.history[begin] & 0xff, b'a' as vga::Char);
+		assert_eq!(disp.history[begin + 1] & 0xff, b'c' as vga::Char);
+		assert_eq!(disp.cursor_x, 2);
+		// Backspace does not erase
+		disp.write_screen(b"\x08");
+		assert_eq!(disp.cursor_x, 1);
+		assert_eq!(disp.history[begin + 1] & 0xff, b'c' as vga::Char);
+		// Backspace at the beginning of a line wraps to the previous one
+		disp.write_screen(b"\n");
+		let y = disp.cursor_y;
+		disp.write_screen(b"\x08");
+		assert_eq!(disp.cursor_x, vga::WIDTH - 1);
+		assert_eq!(disp.cursor_y, y - 1);
+		disp.write_screen(b"\n");
+	}
+
+	#[test_case]
+	fn tty_canonical() {
+		TTY.display.lock().set_termios(Termios::new());
+		let mut buf = [0; 16];
+		TTY.input(b"ab\x7fc");
+		// The line is not complete yet
+		assert!(!TTY.has_input_available());
+		TTY.input(b"\r");
+		let len = TTY.read(&mut buf).unwrap();
+		assert_eq!(&buf[..len], b"ac\n");
+		// Kill the line
+		TTY.input(b"abc\x15d\n");
+		let len = TTY.read(&mut buf).unwrap();
+		assert_eq!(&buf[..len], b"d\n");
+		// Erasing cannot go past the end of a line
+		TTY.input(b"e\n\x7f\x04");
+		let len = TTY.read(&mut buf).unwrap();
+		assert_eq!(&buf[..len], b"e\n");
+		let len = TTY.read(&mut buf).unwrap();
+		assert_eq!(len, 0);
+	}
 
-		self.rd_queue.wake_next();
+	#[test_case]
+	fn tty_raw() {
+		let mut termios = Termios::new();
+		termios.c_lflag &= !(ICANON | ECHO);
+		termios.c_cc[VMIN] = 0;
+		TTY.display.lock().set_termios(termios.clone());
+		let mut buf = [0; 16];
+		assert_eq!(TTY.read(&mut buf).unwrap(), 0);
+		TTY.input(b"a\x7f");
+		let len = TTY.read(&mut buf).unwrap();
+		assert_eq!(&buf[..len], b"a\x7f");
+		// Timeout
+		termios.c_cc[VTIME] = 1;
+		TTY.display.lock().set_termios(termios);
+		assert_eq!(TTY.read(&mut buf).unwrap(), 0);
+		TTY.display.lock().set_termios(Termios::new());
 	}
 }
@@ -0,0 +1,431 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! A pseudo-terminal (pty) is a pair of virtual devices:
+//! - The master, held by a program such as a terminal emulator
+//! - The slave, which behaves like a TTY for the programs running in the terminal
+//!
+//! Data written on the master goes through the slave's line discipline, while data written on
+//! the slave can be read from the master.
+
+use crate::{
+	file::{wait_queue, wait_queue::WaitQueue},
+	process::{pid::Pid, signal::Signal},
+	sync::mutex::Mutex,
+	tty::{
+		ldisc::{Echo, LineDiscipline},
+		send_signal,
+		termios::{consts::*, Termios},
+		Terminal, WinSize,
+	},
+};
+use core::{
+	fmt,
+	sync::atomic::{
+		AtomicBool, AtomicUsize,
+		Ordering::{Acquire, Relaxed, Release},
+	},
+};
+use utils::{
+	collections::{hashmap::HashMap, ring_buffer::RingBuffer},
+	errno,
+	errno::EResult,
+	ptr::arc::Arc,
+};
+
+/// The maximum number of pseudo-terminals.
+pub const PTY_MAX: u32 = 256;
+
+/// The size of the buffer of data written by the slave, in bytes.
+const OUTPUT_SIZE: usize = 4096;
+
+/// The control settings of a pseudo-terminal.
+pub struct PtyCtl {
+	/// Terminal I/O settings.
+	termios: Termios,
+	/// The size of the window.
+	winsize: WinSize,
+	/// The ID of the session of which the pseudo-terminal is the controlling terminal.
+	sid: Pid,
+	/// The current foreground program group.
+	pgrp: Pid,
+}
+
+impl Terminal for PtyCtl {
+	fn get_termios(&self) -> &Termios {
+		&self.termios
+	}
+
+	fn set_termios(&mut self, termios: Termios) {
+		self.termios = termios;
+	}
+
+	fn get_pgrp(&self) -> Pid {
+		self.pgrp
+	}
+
+	fn set_pgrp(&mut self, pgrp: Pid) {
+		self.pgrp = pgrp;
+	}
+
+	fn get_sid(&self) -> Pid {
+		self.sid
+	}
+
+	fn set_session(&mut self, sid: Pid, pgrp: Pid) {
+		self.sid = sid;
+		self.pgrp = pgrp;
+	}
+
+	fn get_winsize(&self) -> &WinSize {
+		&self.winsize
+	}
+
+	fn set_winsize(&mut self, winsize: WinSize) {
+		self.winsize = winsize;
+		send_signal(Signal::SIGWINCH, self.pgrp);
+	}
+}
+
+/// Buffer of data written by the slave, to be read from the master.
+struct Output(RingBuffer<u8, [u8; OUTPUT_SIZE]>);
+
+impl Output {
+	/// Writes `buf` to the buffer, processing it according to the output modes of `termios`.
+	///
+	/// The function returns the number of bytes of `buf` that have been written.
+	fn write(&mut self, termios: &Termios, buf: &[u8]) -> usize {
+		let onlcr = termios.c_oflag & (OPOST | ONLCR) == OPOST | ONLCR;
+		for (i, b) in buf.iter().enumerate() {
+			let b: &[u8] = if onlcr && *b == b'\n' {
+				b"\r\n"
+			} else {
+				core::slice::from_ref(b)
+			};
+			if self.0.get_available_len() < b.len() {
+				return i;
+			}
+			self.0.write(b);
+		}
+		buf.len()
+	}
+}
+
+/// Echoes the input of a pseudo-terminal on its output.
+struct PtyEcho<'a> {
+	/// The output buffer.
+	out: &'a mut Output,
+	/// Terminal I/O settings.
+	termios: &'a Termios,
+}
+
+impl Echo for PtyEcho<'_> {
+	fn echo(&mut self, buf: &[u8]) {
+		// If the output is full, the echo is lost
+		self.out.write(self.termios, buf);
+	}
+
+	fn echo_erase(&mut self, count: usize) {
+		for _ in 0..count {
+			self.out.write(self.termios, b"\x08 \x08");
+		}
+	}
+
+	fn bell(&mut self) {
+		self.out.write(self.termios, b"\x07");
+	}
+}
+
+/// A pseudo-terminal.
+pub struct Pty {
+	/// The number of the pseudo-terminal, which is also the minor number of the slave.
+	index: u32,
+	/// If set, the slave cannot be opened.
+	locked: AtomicBool,
+
+	/// Control settings.
+	pub ctl: Mutex<PtyCtl>,
+	/// The line discipline, handling data written on the master.
+	ldisc: LineDiscipline,
+	/// Data written on the slave.
+	output: Mutex<Output>,
+	/// The queue of processes waiting for data to read on the master.
+	master_queue: WaitQueue,
+	/// The queue of processes waiting for space to write on the slave.
+	slave_queue: WaitQueue,
+
+	/// The number of open files on the slave.
+	slaves: AtomicUsize,
+	/// Tells whether the slave has been closed after being opened.
+	slave_closed: AtomicBool,
+	/// Tells whether the master has been closed.
+	master_closed: AtomicBool,
+}
+
+impl Pty {
+	/// Returns the number of the pseudo-terminal.
+	pub fn get_index(&self) -> u32 {
+		self.index
+	}
+
+	/// Locks or unlocks the slave.
+	pub fn set_locked(&self, locked: bool) {
+		self.locked.store(locked, Release);
+	}
+
+	/// Reads data written on the slave.
+	///
+	/// If `nonblock` is set and no data is available, the function returns
+	/// [`errno::EAGAIN`]. If the slave has been closed, it returns [`errno::EIO`].
+	pub fn master_read(&self, buf: &mut [u8], nonblock: bool) -> EResult<usize> {
+		if buf.is_empty() {
+			return Ok(0);
+		}
+		let len = self.master_queue.wait_until(|| {
+			let len = self.output.lock().0.read(buf);
+			match len {
+				0 if self.slave_closed.load(Acquire) => Some(Err(errno!(EIO))),
+				0 if nonblock => Some(Err(errno!(EAGAIN))),
+				0 => None,
+				len => Some(Ok(len)),
+			}
+		})??;
+		self.slave_queue.wake_all();
+		wait_queue::poll_notify();
+		Ok(len)
+	}
+
+	/// Passes `buf` through the slave's line discipline, as if it were typed on the terminal.
+	pub fn master_write(&self, buf: &[u8]) {
+		let (termios, pgrp) = {
+			let ctl = self.ctl.lock();
+			(ctl.termios.clone(), ctl.pgrp)
+		};
+		for b in buf {
+			let sig = {
+				let mut out = self.output.lock();
+				let mut echo = PtyEcho {
+					out: &mut out,
+					termios: &termios,
+				};
+				self.ldisc.input_byte(&termios, &mut echo, *b)
+			};
+			if let Some(sig) = sig {
+				send_signal(sig, pgrp);
+			}
+		}
+		self.ldisc.wake();
+		self.master_queue.wake_all();
+		wait_queue::poll_notify();
+	}
+
+	/// Tells whether data is available to be read on the master.
+	pub fn master_readable(&self) -> bool {
+		!self.output.lock().0.is_empty()
+	}
+
+	/// Tells whether the slave has been closed.
+	pub fn is_slave_closed(&self) -> bool {
+		self.slave_closed.load(Acquire)
+	}
+
+	/// Closes the master, hanging up the slave.
+	pub fn close_master(&self) {
+		self.master_closed.store(true, Release);
+		{
+			let mut ctl = self.ctl.lock();
+			let sid = ctl.sid;
+			ctl.hangup(sid);
+		}
+		self.ldisc.hangup();
+		self.slave_queue.wake_all();
+		wait_queue::poll_notify();
+	}
+
+	/// Checks whether the slave can be opened.
+	///
+	/// If the slave is locked or if the master has been closed, the function returns
+	/// [`errno::EIO`].
+	pub fn check_slave_open(&self) -> EResult<()> {
+		if self.locked.load(Acquire) || self.master_closed.load(Acquire) {
+			return Err(errno!(EIO));
+		}
+		Ok(())
+	}
+
+	/// Opens a file of the slave.
+	pub fn open_slave(&self) {
+		self.slaves.fetch_add(1, Relaxed);
+		self.slave_closed.store(false, Release);
+	}
+
+	/// Closes a file of the slave.
+	pub fn close_slave(&self) {
+		if self.slaves.fetch_sub(1, Relaxed) == 1 {
+			self.slave_closed.store(true, Release);
+			self.master_queue.wake_all();
+			wait_queue::poll_notify();
+		}
+	}
+
+	/// Reads input from the slave's line discipline.
+	pub fn slave_read(&self, buf: &mut [u8]) -> EResult<usize> {
+		let termios = self.ctl.lock().termios.clone();
+		self.ldisc.read(&termios, buf)
+	}
+
+	/// Writes `buf` on the slave, making it available to the master.
+	///
+	/// If `nonblock` is set and the output buffer is full, the function returns
+	/// [`errno::EAGAIN`]. If the master has been closed, it returns [`errno::EIO`].
+	pub fn slave_write(&self, buf: &[u8], nonblock: bool) -> EResult<usize> {
+		let termios = self.ctl.lock().termios.clone();
+		let mut off = 0;
+		while off < buf.len() {
+			let res = self.slave_queue.wait_until(|| {
+				if self.master_closed.load(Acquire) {
+					return Some(Err(errno!(EIO)));
+				}
+				let len = self.output.lock().write(&termios, &buf[off..]);
+				match len {
+					0 if nonblock => Some(Err(errno!(EAGAIN))),
+					0 => None,
+					len => Some(Ok(len)),
+				}
+			});
+			match res.and_then(|r| r) {
+				Ok(len) => {
+					off += len;
+					self.master_queue.wake_all();
+					wait_queue::poll_notify();
+				}
+				// Report the partial write
+				Err(_) if off > 0 => break,
+				Err(e) => return Err(e),
+			}
+		}
+		Ok(off)
+	}
+
+	/// Tells whether the slave has any data available to be read.
+	pub fn slave_readable(&self) -> bool {
+		let termios = self.ctl.lock().termios.clone();
+		self.ldisc.has_input_available(&termios)
+	}
+
+	/// Tells whether the slave can be written without blocking.
+	pub fn slave_writable(&self) -> bool {
+		self.output.lock().0.get_available_len() > 0
+	}
+
+	/// Tells whether the master has been closed.
+	pub fn is_master_closed(&self) -> bool {
+		self.master_closed.load(Acquire)
+	}
+}
+
+impl fmt::Debug for Pty {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("Pty").field("index", &self.index).finish()
+	}
+}
+
+/// The list of pseudo-terminals whose master is open, by number.
+static PTYS: Mutex<HashMap<u32, Arc<Pty>>> = Mutex::new(HashMap::new());
+
+/// Allocates a new pseudo-terminal, with its slave locked.
+///
+/// If no number is available, the function returns [`errno::ENOSPC`].
+pub fn alloc() -> EResult<Arc<Pty>> {
+	let mut ptys = PTYS.lock();
+	let index = (0..PTY_MAX)
+		.find(|i| !ptys.contains_key(i))
+		.ok_or_else(|| errno!(ENOSPC))?;
+	let pty = Arc::new(Pty {
+		index,
+		locked: AtomicBool::new(true),
+
+		ctl: Mutex::new(PtyCtl {
+			termios: Termios::new(),
+			winsize: WinSize {
+				ws_row: 0,
+				ws_col: 0,
+				ws_xpixel: 0,
+				ws_ypixel: 0,
+			},
+			sid: 0,
+			pgrp: 0,
+		}),
+		ldisc: LineDiscipline::new(),
+		output: Mutex::new(Output(RingBuffer::new([0; OUTPUT_SIZE]))),
+		master_queue: WaitQueue::new(),
+		slave_queue: WaitQueue::new(),
+
+		slaves: AtomicUsize::new(0),
+		slave_closed: AtomicBool::new(false),
+		master_closed: AtomicBool::new(false),
+	})?;
+	ptys.insert(index, pty.clone())?;
+	Ok(pty)
+}
+
+/// Frees the number of the pseudo-terminal `pty`, making it available for reuse.
+pub fn free(pty: &Pty) {
+	PTYS.lock().remove(&pty.index);
+}
+
+/// Disassociates the session `sid` from the pseudo-terminal of which it is the controlling
+/// terminal, if any.
+pub fn hangup(sid: Pid) {
+	let ptys = PTYS.lock();
+	for (_, pty) in ptys.iter() {
+		pty.ctl.lock().hangup(sid);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test_case]
+	fn pty_echo() {
+		let pty = alloc().unwrap();
+		let mut buf = [0; 16];
+		pty.master_write(b"ab\x7fc\r");
+		// Echo, with NL translated to CR-NL
+		let len = pty.master_read(&mut buf, true).unwrap();
+		assert_eq!(&buf[..len], b"ab\x08 \x08c\r\n");
+		let len = pty.slave_read(&mut buf).unwrap();
+		assert_eq!(&buf[..len], b"ac\n");
+		pty.slave_write(b"x\n", true).unwrap();
+		let len = pty.master_read(&mut buf, true).unwrap();
+		assert_eq!(&buf[..len], b"x\r\n");
+		assert_eq!(
+			pty.master_read(&mut buf, true).unwrap_err().as_int(),
+			errno::EAGAIN
+		);
+		pty.close_master();
+		free(&pty);
+		// The slave is hung up
+		assert_eq!(pty.slave_read(&mut buf).unwrap(), 0);
+		assert_eq!(
+			pty.slave_write(b"x", true).unwrap_err().as_int(),
+			errno::EIO
+		);
+	}
+}
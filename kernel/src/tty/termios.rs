@@ -212,7 +212,7 @@ impl Termios {
 	pub const fn new() -> Self {
 		use consts::*;
 		let mut t = Self {
-			c_iflag: ICRNL | IUCLC | IXANY | IMAXBEL,
+			c_iflag: ICRNL | IXANY | IMAXBEL,
 			c_oflag: OPOST | ONLCR,
 			c_cflag: CS8,
 			c_lflag: ISIG | ICANON | ECHO | ECHOE | ECHOK,
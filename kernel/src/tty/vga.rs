@@ -115,13 +115,74 @@ pub fn clear() {
 	}
 }
 
-/// Enables the VGA text mode cursor.
-pub fn enable_cursor() {
+/// The number of entries in the text mode palette.
+pub const PALETTE_SIZE: usize = 16;
+
+/// Register of the attribute controller: index and data write.
+const ATTR_ADDR: u16 = 0x3c0;
+/// Register of the attribute controller: data read.
+const ATTR_DATA_READ: u16 = 0x3c1;
+/// Input status register, reading it resets the attribute controller's flip-flop.
+const INPUT_STATUS: u16 = 0x3da;
+/// Bit of the attribute controller's index enabling the display.
+const ATTR_PALETTE_ADDRESS_SOURCE: u8 = 0x20;
+/// DAC register: index for reading.
+const DAC_READ_INDEX: u16 = 0x3c7;
+/// DAC register: index for writing.
+const DAC_WRITE_INDEX: u16 = 0x3c8;
+/// DAC register: color data.
+const DAC_DATA: u16 = 0x3c9;
+
+/// Returns the index in the DAC corresponding to the text mode color `color`.
+///
+/// The text mode color is an index in the attribute controller's palette, which itself selects
+/// an entry of the DAC.
+fn dac_index(color: Color) -> u8 {
+	unsafe {
+		inb(INPUT_STATUS);
+		outb(ATTR_ADDR, color & 0xf);
+		let index = inb(ATTR_DATA_READ);
+		// Re-enable the display, disabled while accessing the palette
+		inb(INPUT_STATUS);
+		outb(ATTR_ADDR, ATTR_PALETTE_ADDRESS_SOURCE);
+		index & 0x3f
+	}
+}
+
+/// Returns the RGB value of the text mode color `color`, with 8 bits per component.
+pub fn get_palette_color(color: Color) -> [u8; 3] {
+	let index = dac_index(color);
+	let mut rgb = [0; 3];
+	unsafe {
+		outb(DAC_READ_INDEX, index);
+		for c in &mut rgb {
+			// The DAC has 6 bits per component
+			*c = (inb(DAC_DATA) & 0x3f) << 2;
+		}
+	}
+	rgb
+}
+
+/// Sets the RGB value of the text mode color `color`, with 8 bits per component.
+///
+/// The change applies immediately to the characters on screen.
+pub fn set_palette_color(color: Color, rgb: [u8; 3]) {
+	let index = dac_index(color);
+	unsafe {
+		outb(DAC_WRITE_INDEX, index);
+		for c in rgb {
+			outb(DAC_DATA, c >> 2);
+		}
+	}
+}
+
+/// Enables the VGA text mode cursor, spanning from scanline `start` to `end`.
+pub fn enable_cursor(start: u8, end: u8) {
 	unsafe {
 		outb(0x3d4, 0x0a);
-		outb(0x3d5, (inb(0x3d5) & 0xc0) | CURSOR_START);
+		outb(0x3d5, (inb(0x3d5) & 0xc0) | (start & 0x1f));
 		outb(0x3d4, 0x0b);
-		outb(0x3d5, (inb(0x3d5) & 0xe0) | CURSOR_END);
+		outb(0x3d5, (inb(0x3d5) & 0xe0) | (end & 0x1f));
 	}
 }
 
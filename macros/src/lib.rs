@@ -16,7 +16,7 @@
  * Maestro. If not, see <https://www.gnu.org/licenses/>.
  */
 
-//! This crate implements derive macros for the Maestro kernel.
+//! This crate implements procedural macros for the Maestro kernel.
 
 #![feature(iter_intersperse)]
 #![deny(warnings)]
@@ -24,6 +24,7 @@
 extern crate proc_macro;
 
 mod aml;
+mod syscall;
 mod util;
 
 use crate::util::has_repr_c;
@@ -53,3 +54,22 @@ pub fn any_repr(input: TokenStream) -> TokenStream {
 pub fn aml_parseable(input: TokenStream) -> TokenStream {
 	aml::derive_parseable(input)
 }
+
+/// Registers a function as a system call handler.
+///
+/// The arguments are the IDs of the system call, in the table for 32 bit userspace (`id32`) and
+/// in the table for 64 bit userspace (`id64`). Each may be given several times if the handler
+/// has several IDs, or omitted if the system call does not exist in the corresponding table.
+///
+/// Example:
+///
+/// ```ignore
+/// #[syscall(id32 = 0x014, id64 = 0x027)]
+/// pub fn getpid(proc: Arc<Process>) -> EResult<usize> {
+///     // ...
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn syscall(attr: TokenStream, item: TokenStream) -> TokenStream {
+	syscall::syscall(attr, item)
+}
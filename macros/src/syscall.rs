@@ -0,0 +1,63 @@
+/*
+ * Copyright 2024 Luc Lenôtre
+ *
+ * This file is part of Maestro.
+ *
+ * Maestro is free software: you can redistribute it and/or modify it under the
+ * terms of the GNU General Public License as published by the Free Software
+ * Foundation, either version 3 of the License, or (at your option) any later
+ * version.
+ *
+ * Maestro is distributed in the hope that it will be useful, but WITHOUT ANY
+ * WARRANTY; without even the implied warranty of MERCHANTABILITY or FITNESS FOR
+ * A PARTICULAR PURPOSE. See the GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License along with
+ * Maestro. If not, see <https://www.gnu.org/licenses/>.
+ */
+
+//! This module implements the attribute macro used to register system call handlers.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{
+	ext::IdentExt, parse_macro_input, punctuated::Punctuated, ItemFn, MetaNameValue, Token,
+};
+
+/// Registers the annotated function as the handler of the system calls whose IDs are given in
+/// arguments.
+pub fn syscall(attr: TokenStream, item: TokenStream) -> TokenStream {
+	let ids =
+		parse_macro_input!(attr with Punctuated::<MetaNameValue, Token![,]>::parse_terminated);
+	let func = parse_macro_input!(item as ItemFn);
+	let ident = &func.sig.ident;
+	let name = ident.unraw().to_string();
+	let entries = ids.iter().map(|id| {
+		// The 64 bit table exists only on 64 bit kernels
+		let (section, cfg) = if id.path.is_ident("id32") {
+			(".syscalls32", quote! {})
+		} else if id.path.is_ident("id64") {
+			(".syscalls64", quote! { #[cfg(target_arch = "x86_64")] })
+		} else {
+			panic!("{name}: expected `id32` or `id64`");
+		};
+		let id = &id.value;
+		quote! {
+			#cfg
+			const _: () = {
+				#[used]
+				#[link_section = #section]
+				static ENTRY: crate::syscall::SyscallEntry = crate::syscall::SyscallEntry {
+					id: #id,
+					name: #name,
+					handler: |frame| crate::syscall::SyscallHandler::call(#ident, #name, frame),
+				};
+			};
+		}
+	});
+	let toks = quote! {
+		#func
+		#(#entries)*
+	};
+	TokenStream::from(toks)
+}
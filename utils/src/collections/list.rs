@@ -196,11 +196,35 @@ impl<T, const OFF: usize> List<T, OFF> {
 		}
 	}
 
+	/// Returns a pointer to the first element of the list, if any.
+	pub fn front_raw(&self) -> Option<NonNull<T>> {
+		self.head
+			.map(|head| unsafe { head.byte_sub(OFF).cast::<T>() })
+	}
+
 	/// Inserts `val` at the first position of the list.
 	pub fn insert_front(&mut self, val: Arc<T>) {
-		let node = Self::get_node(&val);
+		let ptr = NonNull::from(&*val);
 		// Keep reference
 		mem::forget(val);
+		unsafe {
+			self.insert_front_raw(ptr);
+		}
+	}
+
+	/// Inserts the value pointed to by `val` at the first position of the list, without taking
+	/// ownership over it.
+	///
+	/// This is useful when the element does not reside in an [`Arc`].
+	///
+	/// # Safety
+	///
+	/// The caller must ensure `val` remains valid and does not move while it is in the list.
+	///
+	/// An element inserted with this function must be removed with [`Self::remove_raw`] before
+	/// the list is dropped or cleared, since those would attempt to release an [`Arc`].
+	pub unsafe fn insert_front_raw(&mut self, val: NonNull<T>) {
+		let node = Self::get_node(val.as_ref());
 		if let Some(head) = self.head {
 			// There is already an element in the list
 			unsafe {
@@ -240,6 +264,19 @@ impl<T, const OFF: usize> List<T, OFF> {
 		cursor.remove();
 	}
 
+	/// Unlinks the value pointed to by `val` from the list, without releasing it.
+	///
+	/// # Safety
+	///
+	/// The caller must ensure `val` actually is inserted in `self`.
+	pub unsafe fn remove_raw(&mut self, val: NonNull<T>) {
+		let cursor = Cursor {
+			list: NonNull::from(&mut *self),
+			node: Self::get_node(val.as_ref()).as_ref(),
+		};
+		cursor.unlink();
+	}
+
 	/// Moves the node to the beginning of the list.
 	///
 	/// # Safety
@@ -296,8 +333,8 @@ impl<'l, T: 'l, const OFF: usize> Cursor<'l, T, OFF> {
 		arc
 	}
 
-	/// Removes the element from the list, returning the value as an [`Arc`].
-	pub fn remove(mut self) -> Arc<T> {
+	/// Unlinks the element from the list, without touching the value itself.
+	fn unlink(mut self) {
 		unsafe {
 			let list = self.list.as_mut();
 			// Cannot fail since `self` is in the list
@@ -311,10 +348,16 @@ impl<'l, T: 'l, const OFF: usize> Cursor<'l, T, OFF> {
 					.map(NonNull::from);
 			}
 			self.node.unlink();
-			Arc::from_raw(self.value())
 		}
 	}
 
+	/// Removes the element from the list, returning the value as an [`Arc`].
+	pub fn remove(self) -> Arc<T> {
+		let val = NonNull::from(self.value());
+		self.unlink();
+		unsafe { Arc::from_raw(val.as_ptr()) }
+	}
+
 	/// Moves the node to the beginning of the list.
 	///
 	/// This is useful when the list is used as an LRU.
@@ -462,4 +505,30 @@ mod test {
 		assert_eq!(iter.next().map(|n| n.value().foo), Some(1));
 		assert!(iter.next().is_none());
 	}
+
+	#[test]
+	fn list_raw() {
+		let mut list = pin!(list!(Foo, node));
+		let a = Foo {
+			foo: 0,
+			node: ListNode::default(),
+		};
+		let b = Foo {
+			foo: 1,
+			node: ListNode::default(),
+		};
+		unsafe {
+			list.insert_front_raw(NonNull::from(&a));
+			list.insert_front_raw(NonNull::from(&b));
+		}
+		assert_eq!(list.front_raw(), Some(NonNull::from(&b)));
+		unsafe {
+			list.remove_raw(NonNull::from(&b));
+		}
+		assert_eq!(list.front_raw(), Some(NonNull::from(&a)));
+		unsafe {
+			list.remove_raw(NonNull::from(&a));
+		}
+		assert!(list.front_raw().is_none());
+	}
 }
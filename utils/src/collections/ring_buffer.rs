@@ -51,7 +51,7 @@ impl<T: Default + Copy, B: AsRef<[T]> + AsMut<[T]>> RingBuffer<T, B> {
 	/// Creates a new instance.
 	///
 	/// `buffer` is the buffer to be used.
-	pub fn new(buffer: B) -> Self {
+	pub const fn new(buffer: B) -> Self {
 		Self {
 			buffer,
 
@@ -112,6 +112,149 @@ impl Errno {
 		self.errno
 	}
 
+	/// Returns the name of the errno (example: `ENOENT`).
+	///
+	/// If the errno is unknown, the function returns `None`.
+	pub fn name(&self) -> Option<&'static str> {
+		let name = match self.errno {
+			EPERM => "EPERM",
+			ENOENT => "ENOENT",
+			ESRCH => "ESRCH",
+			EINTR => "EINTR",
+			EIO => "EIO",
+			ENXIO => "ENXIO",
+			E2BIG => "E2BIG",
+			ENOEXEC => "ENOEXEC",
+			EBADF => "EBADF",
+			ECHILD => "ECHILD",
+			EAGAIN => "EAGAIN",
+			ENOMEM => "ENOMEM",
+			EACCES => "EACCES",
+			EFAULT => "EFAULT",
+			ENOTBLK => "ENOTBLK",
+			EBUSY => "EBUSY",
+			EEXIST => "EEXIST",
+			EXDEV => "EXDEV",
+			ENODEV => "ENODEV",
+			ENOTDIR => "ENOTDIR",
+			EISDIR => "EISDIR",
+			EINVAL => "EINVAL",
+			ENFILE => "ENFILE",
+			EMFILE => "EMFILE",
+			ENOTTY => "ENOTTY",
+			ETXTBSY => "ETXTBSY",
+			EFBIG => "EFBIG",
+			ENOSPC => "ENOSPC",
+			ESPIPE => "ESPIPE",
+			EROFS => "EROFS",
+			EMLINK => "EMLINK",
+			EPIPE => "EPIPE",
+			EDOM => "EDOM",
+			ERANGE => "ERANGE",
+			EDEADLK => "EDEADLK",
+			ENAMETOOLONG => "ENAMETOOLONG",
+			ENOLCK => "ENOLCK",
+			ENOSYS => "ENOSYS",
+			ENOTEMPTY => "ENOTEMPTY",
+			ELOOP => "ELOOP",
+			ENOMSG => "ENOMSG",
+			EIDRM => "EIDRM",
+			ECHRNG => "ECHRNG",
+			EL2NSYNC => "EL2NSYNC",
+			EL3HLT => "EL3HLT",
+			EL3RST => "EL3RST",
+			ELNRNG => "ELNRNG",
+			EUNATCH => "EUNATCH",
+			ENOCSI => "ENOCSI",
+			EL2HLT => "EL2HLT",
+			EBADE => "EBADE",
+			EBADR => "EBADR",
+			EXFULL => "EXFULL",
+			ENOANO => "ENOANO",
+			EBADRQC => "EBADRQC",
+			EBADSLT => "EBADSLT",
+			EBFONT => "EBFONT",
+			ENOSTR => "ENOSTR",
+			ENODATA => "ENODATA",
+			ETIME => "ETIME",
+			ENOSR => "ENOSR",
+			ENONET => "ENONET",
+			ENOPKG => "ENOPKG",
+			EREMOTE => "EREMOTE",
+			ENOLINK => "ENOLINK",
+			EADV => "EADV",
+			ESRMNT => "ESRMNT",
+			ECOMM => "ECOMM",
+			EPROTO => "EPROTO",
+			EMULTIHOP => "EMULTIHOP",
+			EDOTDOT => "EDOTDOT",
+			EBADMSG => "EBADMSG",
+			EOVERFLOW => "EOVERFLOW",
+			ENOTUNIQ => "ENOTUNIQ",
+			EBADFD => "EBADFD",
+			EREMCHG => "EREMCHG",
+			ELIBACC => "ELIBACC",
+			ELIBBAD => "ELIBBAD",
+			ELIBSCN => "ELIBSCN",
+			ELIBMAX => "ELIBMAX",
+			ELIBEXEC => "ELIBEXEC",
+			EILSEQ => "EILSEQ",
+			ERESTART => "ERESTART",
+			ESTRPIPE => "ESTRPIPE",
+			EUSERS => "EUSERS",
+			ENOTSOCK => "ENOTSOCK",
+			EDESTADDRREQ => "EDESTADDRREQ",
+			EMSGSIZE => "EMSGSIZE",
+			EPROTOTYPE => "EPROTOTYPE",
+			ENOPROTOOPT => "ENOPROTOOPT",
+			EPROTONOSUPPORT => "EPROTONOSUPPORT",
+			ESOCKTNOSUPPORT => "ESOCKTNOSUPPORT",
+			EOPNOTSUPP => "EOPNOTSUPP",
+			EPFNOSUPPORT => "EPFNOSUPPORT",
+			EAFNOSUPPORT => "EAFNOSUPPORT",
+			EADDRINUSE => "EADDRINUSE",
+			EADDRNOTAVAIL => "EADDRNOTAVAIL",
+			ENETDOWN => "ENETDOWN",
+			ENETUNREACH => "ENETUNREACH",
+			ENETRESET => "ENETRESET",
+			ECONNABORTED => "ECONNABORTED",
+			ECONNRESET => "ECONNRESET",
+			ENOBUFS => "ENOBUFS",
+			EISCONN => "EISCONN",
+			ENOTCONN => "ENOTCONN",
+			ESHUTDOWN => "ESHUTDOWN",
+			ETOOMANYREFS => "ETOOMANYREFS",
+			ETIMEDOUT => "ETIMEDOUT",
+			ECONNREFUSED => "ECONNREFUSED",
+			EHOSTDOWN => "EHOSTDOWN",
+			EHOSTUNREACH => "EHOSTUNREACH",
+			EALREADY => "EALREADY",
+			EINPROGRESS => "EINPROGRESS",
+			ESTALE => "ESTALE",
+			EUCLEAN => "EUCLEAN",
+			ENOTNAM => "ENOTNAM",
+			ENAVAIL => "ENAVAIL",
+			EISNAM => "EISNAM",
+			EREMOTEIO => "EREMOTEIO",
+			EDQUOT => "EDQUOT",
+			ENOMEDIUM => "ENOMEDIUM",
+			EMEDIUMTYPE => "EMEDIUMTYPE",
+			ECANCELED => "ECANCELED",
+			ENOKEY => "ENOKEY",
+			EKEYEXPIRED => "EKEYEXPIRED",
+			EKEYREVOKED => "EKEYREVOKED",
+			EKEYREJECTED => "EKEYREJECTED",
+			EOWNERDEAD => "EOWNERDEAD",
+			ENOTRECOVERABLE => "ENOTRECOVERABLE",
+			ERFKILL => "ERFKILL",
+			EHWPOISON => "EHWPOISON",
+			ERESTARTSYS => "ERESTARTSYS",
+
+			_ => return None,
+		};
+		Some(name)
+	}
+
 	/// Returns the error message for the given errno.
 	pub fn strerror(&self) -> &'static str {
 		match self.errno {
@@ -246,6 +389,7 @@ impl Errno {
 			ENOTRECOVERABLE => "State not recoverable",
 			ERFKILL => "Operation not possible due to RF-kill",
 			EHWPOISON => "Memory page has hardware error",
+			ERESTARTSYS => "Interrupted system call should be restarted",
 
 			_ => "Unknown error",
 		}
@@ -261,20 +405,22 @@ impl PartialEq for Errno {
 #[cfg(not(debug_assertions))]
 impl fmt::Display for Errno {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		write!(f, "errno: {}: {}", self.errno, self.strerror())
+		match self.name() {
+			Some(name) => write!(f, "{name} ({})", self.errno)?,
+			None => write!(f, "errno {}", self.errno)?,
+		}
+		write!(f, ": {}", self.strerror())
 	}
 }
 
 #[cfg(debug_assertions)]
 impl fmt::Display for Errno {
 	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-		write!(
-			f,
-			"errno: {}: {} (at: {})",
-			self.errno,
-			self.strerror(),
-			self.location
-		)
+		match self.name() {
+			Some(name) => write!(f, "{name} ({})", self.errno)?,
+			None => write!(f, "errno {}", self.errno)?,
+		}
+		write!(f, ": {} (at: {})", self.strerror(), self.location)
 	}
 }
 
@@ -574,5 +720,25 @@ pub const ERFKILL: i32 = 132;
 /// Memory page has hardware error.
 pub const EHWPOISON: i32 = 133;
 
+/// Kernel-internal: the system call has been interrupted by a signal and shall be restarted if the
+/// signal handler has been registered with `SA_RESTART`.
+///
+/// This errno is never returned to userspace, which receives [`EINTR`] instead when the system
+/// call is not restarted.
+pub const ERESTARTSYS: i32 = 512;
+
 /// An alias to [`Result`] with [`Errno`] as error type.
 pub type EResult<T> = Result<T, Errno>;
+
+#[cfg(test)]
+mod test {
+	use rust_alloc::format;
+
+	#[test]
+	fn errno_display() {
+		let s = format!("{}", crate::errno!(ENOENT));
+		assert!(s.starts_with("ENOENT (2): No such file or directory"));
+		let s = format!("{}", crate::errno!(EWOULDBLOCK));
+		assert!(s.starts_with("EAGAIN (11)"));
+	}
+}
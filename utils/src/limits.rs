@@ -40,7 +40,7 @@ pub const DELAYTIMER_MAX: usize = 32;
 pub const HOST_NAME_MAX: usize = 255;
 /// Maximum number of iovec structures that one process has available for use
 /// with readv() or writev().
-pub const IOV_MAX: usize = 16;
+pub const IOV_MAX: usize = 1024;
 /// Maximum length of a login name.
 pub const LOGIN_NAME_MAX: usize = 255;
 /// The maximum number of open message queue descriptors a process may hold.